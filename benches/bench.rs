@@ -0,0 +1,455 @@
+//!
+//! Simple timing benchmarks comparing the arena types.
+//! Run with `cargo bench`.
+//!
+
+use std::{alloc::{GlobalAlloc, Layout, System}, hint::black_box, sync::atomic::{AtomicUsize, Ordering}, time::Instant};
+
+use gen_arena::*;
+
+const N: usize = 100_000;
+
+// Counts allocations, to compare allocation heavy approaches.
+struct CountingAlloc;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAlloc{
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8{
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        unsafe{System.alloc(layout)}
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout){
+        unsafe{System.dealloc(ptr, layout)}
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAlloc = CountingAlloc;
+
+fn count_allocations<R>(f: impl FnOnce() -> R) -> (usize, R){
+    let before = ALLOCATIONS.load(Ordering::Relaxed);
+    let r = f();
+    (ALLOCATIONS.load(Ordering::Relaxed) - before, r)
+}
+
+fn bench(name: &str, mut f: impl FnMut()){
+    // Warm up once before measuring.
+    f();
+    let runs = 10;
+    let start = Instant::now();
+    for _ in 0..runs{
+        f();
+    }
+    let elapsed = start.elapsed() / runs;
+    println!("{:<40} {:>12.3?}", name, elapsed);
+}
+
+fn bench_chunk_arena(){
+    bench("Arena insert", ||{
+        let mut arena = Arena::new();
+        for i in 0..N{
+            black_box(arena.insert(i));
+        }
+    });
+    bench("ChunkArena insert", ||{
+        let arena = ChunkArena::new();
+        for i in 0..N{
+            black_box(arena.insert(i));
+        }
+    });
+
+    let mut arena = Arena::new();
+    let keys: Vec<_> = (0..N).map(|i| arena.insert(i)).collect();
+    bench("Arena get", ||{
+        for key in keys.iter(){
+            black_box(arena.get(*key));
+        }
+    });
+    let chunk_arena = ChunkArena::new();
+    let keys: Vec<_> = (0..N).map(|i| chunk_arena.insert(i)).collect();
+    bench("ChunkArena get", ||{
+        for key in keys.iter(){
+            black_box(chunk_arena.get(*key));
+        }
+    });
+    bench("Arena iter", ||{
+        black_box(arena.values().sum::<usize>());
+    });
+    bench("ChunkArena iter", ||{
+        black_box(chunk_arena.values().sum::<usize>());
+    });
+}
+
+fn bench_dense_arena(){
+    // Only every tenth element stays in the arena.
+    let mut arena = Arena::new();
+    let mut dense_arena = DenseArena::new();
+    for i in 0..N{
+        let key = arena.insert(i);
+        let dense_key = dense_arena.insert(i);
+        if i % 10 != 0{
+            arena.remove(key);
+            dense_arena.remove(dense_key);
+        }
+    }
+    bench("Arena iter 10% occupancy", ||{
+        black_box(arena.values().sum::<usize>());
+    });
+    bench("DenseArena iter 10% occupancy", ||{
+        black_box(dense_arena.values().sum::<usize>());
+    });
+}
+
+fn bench_small_arena(){
+    // Many tiny arenas, like per node child lists.
+    bench("Arena 4 elements x 10000", ||{
+        for _ in 0..N / 10{
+            let mut arena = Arena::new();
+            for i in 0..4{
+                black_box(arena.insert(i));
+            }
+            black_box(&arena);
+        }
+    });
+    bench("SmallArena<_, 8> 4 elements x 10000", ||{
+        for _ in 0..N / 10{
+            let mut arena = SmallArena::<_, 8>::new();
+            for i in 0..4{
+                black_box(arena.insert(i));
+            }
+            black_box(&arena);
+        }
+    });
+}
+
+fn bench_slab(){
+    bench("Slab insert", ||{
+        let mut slab = Slab::new();
+        for i in 0..N{
+            black_box(slab.insert(i));
+        }
+    });
+
+    let mut arena = Arena::new();
+    let keys: Vec<_> = (0..N).map(|i| arena.insert(i)).collect();
+    bench("Arena get (generation checked)", ||{
+        for key in keys.iter(){
+            black_box(arena.get(*key));
+        }
+    });
+    let mut slab = Slab::new();
+    let keys: Vec<_> = (0..N).map(|i| slab.insert(i)).collect();
+    bench("Slab get", ||{
+        for key in keys.iter(){
+            black_box(slab.get(*key));
+        }
+    });
+}
+
+fn bench_sarena(){
+    // Keys validated up front, as by an audio callback looking up its voices.
+    const CAPACITY: usize = 4096;
+    let arena = SArena::<usize, CAPACITY>::new();
+    let keys: Vec<_> = (0..CAPACITY).map(|i| arena.insert(i)).collect();
+    bench("SArena get x 100", ||{
+        for _ in 0..100{
+            for key in keys.iter(){
+                black_box(arena.get(*key));
+            }
+        }
+    });
+    bench("SArena get_unchecked x 100", ||{
+        for _ in 0..100{
+            for key in keys.iter(){
+                // SAFETY: Nothing is removed, so all keys stay valid.
+                black_box(unsafe{arena.get_unchecked(*key)});
+            }
+        }
+    });
+
+    // Startup of a large arena, the cells are only initialized when they are first used.
+    bench("SArena<u64, 65536> new_boxed x 100", ||{
+        for _ in 0..100{
+            black_box(SArena::<u64, 65536>::new_boxed());
+        }
+    });
+    bench("SArena<u64, 65536> new_boxed + 1 insert x 100", ||{
+        for _ in 0..100{
+            let arena = SArena::<u64, 65536>::new_boxed();
+            black_box(arena.insert(0));
+            black_box(arena);
+        }
+    });
+}
+
+fn bench_bump(){
+    // A balanced binary tree over N leaves, as built by a parser.
+    enum Node<'a>{
+        Leaf(usize),
+        Pair(&'a Node<'a>, &'a Node<'a>),
+    }
+    enum BoxNode{
+        Leaf(usize),
+        Pair(Box<BoxNode>, Box<BoxNode>),
+    }
+    fn sum(node: &Node) -> usize{
+        match node{
+            Node::Leaf(i) => *i,
+            Node::Pair(a, b) => sum(a) + sum(b),
+        }
+    }
+    fn box_sum(node: &BoxNode) -> usize{
+        match node{
+            BoxNode::Leaf(i) => *i,
+            BoxNode::Pair(a, b) => box_sum(a) + box_sum(b),
+        }
+    }
+
+    bench("Box per node", ||{
+        let mut nodes: Vec<_> = (0..N).map(|i| Box::new(BoxNode::Leaf(i))).collect();
+        while nodes.len() > 1{
+            let mut level = nodes.into_iter();
+            nodes = Vec::new();
+            while let Some(a) = level.next(){
+                match level.next(){
+                    Some(b) => nodes.push(Box::new(BoxNode::Pair(a, b))),
+                    None => nodes.push(a),
+                }
+            }
+        }
+        black_box(box_sum(&nodes[0]));
+    });
+    bench("Bump", ||{
+        let bump = Bump::new();
+        let mut nodes: Vec<&Node> = (0..N).map(|i| &*bump.alloc(Node::Leaf(i))).collect();
+        while nodes.len() > 1{
+            nodes = nodes.chunks(2).map(|pair| match pair{
+                [a, b] => &*bump.alloc(Node::Pair(a, b)),
+                _ => pair[0],
+            }).collect();
+        }
+        black_box(sum(nodes[0]));
+    });
+}
+
+fn bench_str_arena(){
+    let words: Vec<String> = (0..N).map(|i| format!("identifier_{}", i)).collect();
+
+    bench("Vec<String> alloc", ||{
+        let strings: Vec<String> = words.iter().map(|w| w.to_string()).collect();
+        black_box(strings);
+    });
+    bench("StrArena alloc", ||{
+        let mut strings = StrArena::new();
+        for w in words.iter(){
+            black_box(strings.alloc(w));
+        }
+    });
+
+    // Requested bytes only, the per allocation overhead of the allocator is not included.
+    let (allocations, strings) = count_allocations(|| words.iter().map(|w| w.to_string()).collect::<Vec<String>>());
+    let bytes = strings.capacity() * std::mem::size_of::<String>() + strings.iter().map(|s| s.capacity()).sum::<usize>();
+    println!("{:<40} {:>12} allocations {:>10} bytes", "Vec<String>", allocations, bytes);
+    let (allocations, arena) = count_allocations(||{
+        let mut arena = StrArena::new();
+        let keys: Vec<_> = words.iter().map(|w| arena.alloc(w)).collect();
+        (arena, keys)
+    });
+    let bytes = arena.0.capacity_bytes() + arena.1.capacity() * std::mem::size_of::<StrIdx>() * 2;
+    println!("{:<40} {:>12} allocations {:>10} bytes", "StrArena", allocations, bytes);
+}
+
+fn bench_arena_set(){
+    let mut arena = Arena::new();
+    let keys: Vec<_> = (0..N).map(|i| arena.insert(i)).collect();
+    let selected: Vec<_> = keys.iter().copied().filter(|key| key.index() % 3 == 0).collect();
+
+    // ArenaIdx is not Hash, so the HashSet stores (index, generation) pairs.
+    let hash_set: std::collections::HashSet<_> = selected.iter().map(|key| (key.index(), key.gen())).collect();
+    let arena_set: ArenaSet<_> = selected.iter().copied().collect();
+
+    bench("HashSet contains", ||{
+        for key in keys.iter(){
+            black_box(hash_set.contains(&(key.index(), key.gen())));
+        }
+    });
+    bench("ArenaSet contains", ||{
+        for key in keys.iter(){
+            black_box(arena_set.contains(*key));
+        }
+    });
+    bench("HashSet iter", ||{
+        black_box(hash_set.iter().map(|(index, _)| arena.get_any(*index).unwrap()).sum::<usize>());
+    });
+    bench("ArenaSet iter", ||{
+        black_box(arena_set.iter().map(|key| arena[key]).sum::<usize>());
+    });
+}
+
+fn bench_boxed_arena(){
+    const PAGES: usize = 10_000;
+    let page = |i: usize| [i as u8; 4096];
+
+    bench("Arena<[u8; 4096]> insert", ||{
+        let mut arena = Arena::new();
+        for i in 0..PAGES{
+            black_box(arena.insert(page(i)));
+        }
+    });
+    bench("BoxedArena<[u8; 4096]> insert", ||{
+        let mut arena = BoxedArena::new();
+        for i in 0..PAGES{
+            black_box(arena.insert(page(i)));
+        }
+    });
+
+    let mut arena = Arena::new();
+    let mut boxed = BoxedArena::new();
+    for i in 0..PAGES{
+        let _ = arena.insert(page(i));
+        let _ = boxed.insert(page(i));
+    }
+    bench("Arena<[u8; 4096]> iter", ||{
+        black_box(arena.values().map(|page| page[0] as usize).sum::<usize>());
+    });
+    bench("BoxedArena<[u8; 4096]> iter", ||{
+        black_box(boxed.values().map(|page| page[0] as usize).sum::<usize>());
+    });
+}
+
+fn bench_cache_aligned(){
+    const INCREMENTS: usize = 10_000_000;
+
+    // Two threads keep incrementing neighbouring elements.
+    fn increment_pair<T: Send>(arena: &mut Arena<T>, counter: fn(&mut T) -> &mut u64){
+        let keys: Vec<_> = arena.keys().collect();
+        if let (Some(a), Some(b)) = arena.get2_mut((keys[0], keys[1])){
+            std::thread::scope(|s|{
+                for val in [a, b]{
+                    s.spawn(move ||{
+                        for _ in 0..INCREMENTS{
+                            *counter(black_box(&mut *val)) += 1;
+                        }
+                    });
+                }
+            });
+        }
+    }
+
+    let mut packed = Arena::new();
+    let mut aligned = Arena::new();
+    for _ in 0..2{
+        let _ = packed.insert(0u64);
+        let _ = aligned.insert(CacheAligned(0u64));
+    }
+
+    bench("Arena<u64> two threads", || increment_pair(&mut packed, |val| val));
+    bench("Arena<CacheAligned<u64>> two threads", || increment_pair(&mut aligned, |val| &mut val.0));
+}
+
+#[cfg(feature = "derive")]
+fn bench_soa(){
+    #[derive(ArenaSoA)]
+    struct Particle{
+        pos: [f32; 3],
+        vel: [f32; 3],
+        life: f32,
+    }
+    const PARTICLES: usize = 1_000_000;
+    let particle = |i: usize| Particle{
+        pos: [i as f32; 3],
+        vel: [1.; 3],
+        life: i as f32,
+    };
+
+    let mut aos = Arena::new();
+    let mut soa = ParticleArenas::new();
+    for i in 0..PARTICLES{
+        let _ = aos.insert(particle(i));
+        let _ = soa.insert(particle(i));
+    }
+
+    bench("Arena<Particle> sum life", ||{
+        black_box(aos.values().map(|p| p.life).sum::<f32>());
+    });
+    bench("ParticleArenas sum life", ||{
+        black_box(soa.life_slice().iter().sum::<f32>());
+    });
+    bench("Arena<Particle> integrate", ||{
+        for p in aos.values_mut(){
+            for k in 0..3{
+                p.pos[k] += p.vel[k];
+            }
+        }
+    });
+    bench("ParticleArenas integrate", ||{
+        let ParticleSlicesMut{pos, vel, ..} = soa.slices_mut();
+        for (p, v) in pos.iter_mut().zip(vel.iter()){
+            for k in 0..3{
+                p[k] += v[k];
+            }
+        }
+    });
+}
+
+#[cfg(feature = "arc-swap")]
+fn bench_rcu_arena(){
+    use std::sync::{atomic::AtomicBool, RwLock};
+
+    const READERS: usize = 4;
+    const READS: usize = 1_000_000;
+
+    // Runs READERS threads doing READS lookups each while another thread keeps writing.
+    fn under_writes(read: impl Fn(usize) -> usize + Sync, write: impl Fn() + Sync){
+        let done = AtomicBool::new(false);
+        std::thread::scope(|s|{
+            s.spawn(||{
+                while !done.load(Ordering::Relaxed){
+                    write();
+                }
+            });
+            let readers: Vec<_> = (0..READERS).map(|_| s.spawn(|| (0..READS).map(&read).sum::<usize>())).collect();
+            for reader in readers{
+                black_box(reader.join().unwrap());
+            }
+            done.store(true, Ordering::Relaxed);
+        });
+    }
+
+    let mut arena = Arena::new();
+    let keys: Vec<_> = (0..1000).map(|i| arena.insert(i)).collect();
+    let rcu = RcuArena::from_arena(arena.clone());
+    let locked = RwLock::new(arena);
+
+    bench("RcuArena reads under writes", ||{
+        under_writes(
+            |i| rcu.with(keys[i % keys.len()], |val| *val).unwrap_or(0),
+            || rcu.update(|arena| arena[keys[0]] += 1),
+        );
+    });
+    bench("RwLock<Arena> reads under writes", ||{
+        under_writes(
+            |i| locked.read().unwrap().get(keys[i % keys.len()]).copied().unwrap_or(0),
+            || locked.write().unwrap()[keys[0]] += 1,
+        );
+    });
+}
+
+fn main(){
+    bench_chunk_arena();
+    bench_dense_arena();
+    bench_small_arena();
+    bench_slab();
+    bench_sarena();
+    bench_bump();
+    bench_str_arena();
+    bench_arena_set();
+    bench_boxed_arena();
+    bench_cache_aligned();
+    #[cfg(feature = "derive")]
+    bench_soa();
+    #[cfg(feature = "arc-swap")]
+    bench_rcu_arena();
+}
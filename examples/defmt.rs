@@ -0,0 +1,55 @@
+//!
+//! Logs keys and errors of the arenas through defmt, so the defmt::Format impls keep compiling.
+//! On a device the global logger comes from a transport like defmt-rtt and the log is decoded on
+//! the host. The crate needs std, so the example runs on the host and its logger only counts
+//! the encoded bytes.
+//! Run with `cargo run --example defmt --features defmt`.
+//!
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use gen_arena::*;
+
+static BYTES: AtomicUsize = AtomicUsize::new(0);
+
+#[defmt::global_logger]
+struct CountingLogger;
+
+unsafe impl defmt::Logger for CountingLogger{
+    fn acquire(){}
+
+    unsafe fn flush(){}
+
+    unsafe fn release(){}
+
+    unsafe fn write(bytes: &[u8]){
+        BYTES.fetch_add(bytes.len(), Ordering::Relaxed);
+    }
+}
+
+defmt::timestamp!("{=u32}", 0);
+
+fn main(){
+    let mut sensors = SArena::<u16, 4, u8>::new();
+    let key = sensors.insert(512);
+    defmt::println!("inserted {} = {}", key, sensors.get(key));
+    sensors.remove(key);
+    defmt::println!("stats {}", sensors.stats());
+    if let Err(err) = sensors.validate(){
+        defmt::println!("corrupted: {}", err);
+    }
+
+    if let Err(err) = SArena::<u16, 2>::try_from_iter([1, 2, 3]){
+        defmt::println!("{}", err);
+    }
+    let mut arena = Arena::new();
+    let _ = arena.insert(0u16);
+    if let Err(err) = SArena::<u16, 0>::try_from_arena(arena){
+        defmt::println!("{}", err);
+    }
+    if let Err(err) = "7".parse::<ArenaIdx<u16>>(){
+        defmt::println!("{}", err);
+    }
+
+    println!("{} bytes logged", BYTES.load(Ordering::Relaxed));
+}
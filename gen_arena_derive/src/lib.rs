@@ -0,0 +1,198 @@
+//!
+//! Derive macros for gen_arena. Use them through the derive feature of gen_arena.
+//!
+
+use proc_macro::TokenStream;
+use proc_macro2::Span;
+use quote::{format_ident, quote};
+use syn::{parse_macro_input, Data, DeriveInput, Fields, GenericParam, Lifetime, LifetimeParam};
+
+///
+/// Generates `{Name}Arenas`, a struct of arrays storing every field of Name in its own dense Vec,
+/// sharing one generational slot table.
+///
+/// Besides insert, remove, contains, keys, len, is_empty and clear, it has for every field f
+/// the accessors f(index), f_mut(index), f_slice() and f_slice_mut().
+/// slices() and slices_mut() return `{Name}Slices` and `{Name}SlicesMut`, holding the keys
+/// and one slice per field, so any combination of fields can be iterated together.
+///
+/// Only structs with named fields are supported.
+///
+#[proc_macro_derive(ArenaSoA)]
+pub fn derive_arena_soa(input: TokenStream) -> TokenStream{
+    let input = parse_macro_input!(input as DeriveInput);
+    match arena_soa(input){
+        Ok(tokens) => tokens.into(),
+        Err(err) => err.to_compile_error().into(),
+    }
+}
+
+fn arena_soa(input: DeriveInput) -> syn::Result<proc_macro2::TokenStream>{
+    let fields = match &input.data{
+        Data::Struct(data) => match &data.fields{
+            Fields::Named(fields) => &fields.named,
+            _ => return Err(syn::Error::new_spanned(&input.ident, "ArenaSoA needs a struct with named fields.")),
+        },
+        _ => return Err(syn::Error::new_spanned(&input.ident, "ArenaSoA can only be derived for structs.")),
+    };
+
+    let vis = &input.vis;
+    let name = &input.ident;
+    let arenas = format_ident!("{}Arenas", name);
+    let slices = format_ident!("{}Slices", name);
+    let slices_mut = format_ident!("{}SlicesMut", name);
+
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let mut slice_generics = input.generics.clone();
+    let lifetime = Lifetime::new("'soa", Span::call_site());
+    slice_generics.params.insert(0, GenericParam::Lifetime(LifetimeParam::new(lifetime.clone())));
+    let (slice_impl_generics, _, _) = slice_generics.split_for_impl();
+    // The generic arguments of the slice structs returned from methods, with an elided lifetime.
+    let ty_args = input.generics.params.iter().map(|param| match param{
+        GenericParam::Lifetime(param) => {
+            let lifetime = &param.lifetime;
+            quote!(#lifetime)
+        }
+        GenericParam::Type(param) => {
+            let ident = &param.ident;
+            quote!(#ident)
+        }
+        GenericParam::Const(param) => {
+            let ident = &param.ident;
+            quote!(#ident)
+        }
+    });
+    let slice_ty_generics = quote!(<'_, #(#ty_args),*>);
+
+    let names: Vec<_> = fields.iter().map(|f| f.ident.clone().expect("Named fields have names.")).collect();
+    let types: Vec<_> = fields.iter().map(|f| &f.ty).collect();
+    let names_mut: Vec<_> = names.iter().map(|n| format_ident!("{}_mut", n)).collect();
+    let names_slice: Vec<_> = names.iter().map(|n| format_ident!("{}_slice", n)).collect();
+    let names_slice_mut: Vec<_> = names.iter().map(|n| format_ident!("{}_slice_mut", n)).collect();
+
+    let key = quote!(::gen_arena::ArenaIdx<#name #ty_generics>);
+    let arenas_doc = format!("The fields of {} in parallel dense Vecs, generated by `#[derive(ArenaSoA)]`.", name);
+    let slices_doc = format!("The keys and fields of all elements of a {}.", arenas);
+
+    Ok(quote!{
+        #[doc = #arenas_doc]
+        #[allow(dead_code)]
+        #vis struct #arenas #impl_generics #where_clause{
+            soa_index: ::gen_arena::SoaIndex<#name #ty_generics>,
+            #(#names: ::std::vec::Vec<#types>,)*
+        }
+
+        #[doc = #slices_doc]
+        #[allow(dead_code)]
+        #vis struct #slices #slice_impl_generics #where_clause{
+            pub keys: &#lifetime [#key],
+            #(pub #names: &#lifetime [#types],)*
+        }
+
+        #[doc = #slices_doc]
+        #[allow(dead_code)]
+        #vis struct #slices_mut #slice_impl_generics #where_clause{
+            pub keys: &#lifetime [#key],
+            #(pub #names: &#lifetime mut [#types],)*
+        }
+
+        #[allow(dead_code)]
+        impl #impl_generics #arenas #ty_generics #where_clause{
+            /// Creates an empty struct of arrays.
+            pub fn new() -> Self{
+                Self{
+                    soa_index: ::gen_arena::SoaIndex::new(),
+                    #(#names: ::std::vec::Vec::new(),)*
+                }
+            }
+
+            /// Inserts the fields of val and returns its key.
+            #[must_use]
+            pub fn insert(&mut self, val: #name #ty_generics) -> #key{
+                let index = self.soa_index.insert();
+                let #name{#(#names,)*} = val;
+                #(self.#names.push(#names);)*
+                index
+            }
+
+            /// Removes the element and returns it if the index is still valid.
+            /// The last element is moved into the position of the removed one.
+            pub fn remove(&mut self, index: #key) -> ::std::option::Option<#name #ty_generics>{
+                let dense = self.soa_index.remove(index)?;
+                ::std::option::Option::Some(#name{
+                    #(#names: self.#names.swap_remove(dense),)*
+                })
+            }
+
+            #[inline]
+            pub fn contains(&self, index: #key) -> bool{
+                self.soa_index.dense(index).is_some()
+            }
+
+            /// Returns the keys in the order of the field slices.
+            #[inline]
+            pub fn keys(&self) -> &[#key]{
+                self.soa_index.keys()
+            }
+
+            #[inline]
+            pub fn len(&self) -> usize{
+                self.soa_index.len()
+            }
+
+            #[inline]
+            pub fn is_empty(&self) -> bool{
+                self.soa_index.is_empty()
+            }
+
+            pub fn clear(&mut self){
+                self.soa_index.clear();
+                #(self.#names.clear();)*
+            }
+
+            #(
+                #[inline]
+                pub fn #names(&self, index: #key) -> ::std::option::Option<&#types>{
+                    self.soa_index.dense(index).map(|dense| &self.#names[dense])
+                }
+
+                #[inline]
+                pub fn #names_mut(&mut self, index: #key) -> ::std::option::Option<&mut #types>{
+                    self.soa_index.dense(index).map(|dense| &mut self.#names[dense])
+                }
+
+                #[inline]
+                pub fn #names_slice(&self) -> &[#types]{
+                    &self.#names
+                }
+
+                #[inline]
+                pub fn #names_slice_mut(&mut self) -> &mut [#types]{
+                    &mut self.#names
+                }
+            )*
+
+            /// Returns the keys and a slice per field.
+            pub fn slices(&self) -> #slices #slice_ty_generics{
+                #slices{
+                    keys: self.soa_index.keys(),
+                    #(#names: &self.#names,)*
+                }
+            }
+
+            /// Returns the keys and a mutable slice per field.
+            pub fn slices_mut(&mut self) -> #slices_mut #slice_ty_generics{
+                #slices_mut{
+                    keys: self.soa_index.keys(),
+                    #(#names: &mut self.#names,)*
+                }
+            }
+        }
+
+        impl #impl_generics ::std::default::Default for #arenas #ty_generics #where_clause{
+            fn default() -> Self{
+                Self::new()
+            }
+        }
+    })
+}
@@ -0,0 +1,126 @@
+#ifndef GEN_ARENA_H
+#define GEN_ARENA_H
+
+/* Generated with cbindgen from src/ffi.rs, do not edit by hand. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+/**
+ * The operation succeeded.
+ */
+#define GEN_ARENA_OK 0
+
+/**
+ * A required pointer argument was null.
+ */
+#define GEN_ARENA_ERR_NULL -1
+
+/**
+ * The handle does not refer to an element of the arena.
+ */
+#define GEN_ARENA_ERR_INVALID_HANDLE -2
+
+/**
+ * The slot index of a new element does not fit into a handle.
+ */
+#define GEN_ARENA_ERR_OVERFLOW -3
+
+/**
+ *
+ * An Arena of byte blobs of the size given to gen_arena_new.
+ * Only ever handled through a pointer from C.
+ *
+ */
+typedef struct GenArenaOpaque GenArenaOpaque;
+
+/**
+ *
+ * A handle to an element of a GenArenaOpaque.
+ *
+ */
+typedef struct GenArenaHandle {
+  uint32_t index;
+  uint32_t generation;
+} GenArenaHandle;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+/**
+ *
+ * Creates an arena for elements of elem_size bytes.
+ * The arena has to be freed with gen_arena_free.
+ *
+ */
+struct GenArenaOpaque *gen_arena_new(size_t elem_size);
+
+/**
+ *
+ * Frees the arena and all its elements. Does nothing if arena is null.
+ *
+ * # Safety
+ *
+ * arena has to be null or a pointer returned by gen_arena_new that has not been freed yet.
+ *
+ */
+void gen_arena_free(struct GenArenaOpaque *arena);
+
+/**
+ *
+ * Copies elem_size bytes from value into a new element and writes its handle to out.
+ *
+ * # Safety
+ *
+ * arena has to be null or a live arena, value null or readable for elem_size bytes
+ * and out null or writable.
+ *
+ */
+int32_t gen_arena_insert(struct GenArenaOpaque *arena,
+                         const uint8_t *value,
+                         struct GenArenaHandle *out);
+
+/**
+ *
+ * Copies the element of the handle to out.
+ *
+ * # Safety
+ *
+ * arena has to be null or a live arena and out null or writable for elem_size bytes.
+ *
+ */
+int32_t gen_arena_get(const struct GenArenaOpaque *arena,
+                      struct GenArenaHandle handle,
+                      uint8_t *out);
+
+/**
+ *
+ * Removes the element of the handle and copies it to out, unless out is null.
+ *
+ * # Safety
+ *
+ * arena has to be null or a live arena and out null or writable for elem_size bytes.
+ *
+ */
+int32_t gen_arena_remove(struct GenArenaOpaque *arena, struct GenArenaHandle handle, uint8_t *out);
+
+/**
+ *
+ * Returns the number of elements in the arena, or 0 if arena is null.
+ *
+ * # Safety
+ *
+ * arena has to be null or a live arena.
+ *
+ */
+size_t gen_arena_len(const struct GenArenaOpaque *arena);
+
+#ifdef __cplusplus
+} // extern "C"
+#endif // __cplusplus
+
+#endif /* GEN_ARENA_H */
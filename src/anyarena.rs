@@ -0,0 +1,238 @@
+use std::{any::{Any, TypeId}, collections::HashMap, ops::{Index, IndexMut}};
+
+use crate::*;
+
+///
+/// Object safe view of an Arena<T> with its element type erased.
+///
+trait ErasedArena{
+    fn arena_any(&self) -> &dyn Any;
+    fn arena_any_mut(&mut self) -> &mut dyn Any;
+    fn num(&self) -> usize;
+    fn clear(&mut self);
+}
+
+impl<T: 'static> ErasedArena for Arena<T>{
+    fn arena_any(&self) -> &dyn Any{
+        self
+    }
+
+    fn arena_any_mut(&mut self) -> &mut dyn Any{
+        self
+    }
+
+    fn num(&self) -> usize{
+        Arena::num(self)
+    }
+
+    fn clear(&mut self){
+        Arena::clear(self)
+    }
+}
+
+///
+/// A collection of Arenas for values of different types.
+///
+/// Holds one Arena<T> per type, created on the first insertion of a T.
+/// Since keys are typed, a lookup always goes to the arena of the right type.
+///
+/// # Example
+///
+///```rust
+/// use gen_arena::*;
+///
+/// let mut arena = AnyArena::new();
+///
+/// let i0 = arena.insert(1u32);
+/// let i1 = arena.insert("one");
+///
+/// assert_eq!(arena.get(i0), Some(&1));
+/// assert_eq!(arena.get(i1), Some(&"one"));
+///
+/// assert_eq!(arena.arena_of::<u32>().unwrap().num(), 1);
+/// assert!(arena.arena_of::<f32>().is_none());
+///```
+///
+pub struct AnyArena{
+    arenas: HashMap<TypeId, Box<dyn ErasedArena>>,
+}
+
+impl AnyArena{
+    ///
+    /// Creates an empty AnyArena.
+    ///
+    pub fn new() -> Self{
+        Self{
+            arenas: HashMap::new(),
+        }
+    }
+
+    ///
+    /// Returns the arena holding values of type T, if any value of that type was ever inserted.
+    ///
+    pub fn arena_of<T: 'static>(&self) -> Option<&Arena<T>>{
+        self.arenas.get(&TypeId::of::<T>()).map(|arena|{
+            arena.arena_any().downcast_ref().expect("The arena is always stored under the TypeId of its element type.")
+        })
+    }
+
+    ///
+    /// Returns the mutable arena holding values of type T, if any value of that type was ever inserted.
+    ///
+    pub fn arena_of_mut<T: 'static>(&mut self) -> Option<&mut Arena<T>>{
+        self.arenas.get_mut(&TypeId::of::<T>()).map(|arena|{
+            arena.arena_any_mut().downcast_mut().expect("The arena is always stored under the TypeId of its element type.")
+        })
+    }
+
+    ///
+    /// Returns the arena holding values of type T, creating it if necessary.
+    ///
+    pub fn arena_of_or_default<T: 'static>(&mut self) -> &mut Arena<T>{
+        self.arenas.entry(TypeId::of::<T>())
+            .or_insert_with(|| Box::new(Arena::<T>::new()))
+            .arena_any_mut()
+            .downcast_mut()
+            .expect("The arena is always stored under the TypeId of its element type.")
+    }
+
+    ///
+    /// Tries to insert into the arena of type T.
+    /// Returns val as Err if failed.
+    ///
+    pub fn try_insert<T: 'static>(&mut self, val: T) -> Result<ArenaIdx<T>, T>{
+        self.arena_of_or_default().try_insert(val).map_err(InsertError::into_value)
+    }
+
+    ///
+    /// Inserts a new element into the arena of type T.
+    ///
+    #[must_use]
+    pub fn insert<T: 'static>(&mut self, val: T) -> ArenaIdx<T>{
+        self.arena_of_or_default().insert(val)
+    }
+
+    ///
+    /// Removes the element from the arena of its type.
+    /// Returns the value if the index is still valid.
+    ///
+    pub fn remove<T: 'static>(&mut self, index: ArenaIdx<T>) -> Option<T>{
+        self.arena_of_mut()?.remove(index)
+    }
+
+    ///
+    /// Returns an optional reference to the value at the index.
+    ///
+    pub fn get<T: 'static>(&self, index: ArenaIdx<T>) -> Option<&T>{
+        self.arena_of()?.get(index)
+    }
+
+    ///
+    /// Returns a mutable optional reference to the value at the index.
+    ///
+    pub fn get_mut<T: 'static>(&mut self, index: ArenaIdx<T>) -> Option<&mut T>{
+        self.arena_of_mut()?.get_mut(index)
+    }
+
+    ///
+    /// Returns true if an arena for values of type T exists.
+    ///
+    #[inline]
+    pub fn contains_type<T: 'static>(&self) -> bool{
+        self.arenas.contains_key(&TypeId::of::<T>())
+    }
+
+    ///
+    /// Clears all arenas. The arenas themselves are kept, so keys stay typed to them.
+    ///
+    pub fn clear(&mut self){
+        for arena in self.arenas.values_mut(){
+            arena.clear();
+        }
+    }
+
+    ///
+    /// Returns the number of elements of all types.
+    ///
+    pub fn num(&self) -> usize{
+        self.arenas.values().map(|arena| arena.num()).sum()
+    }
+}
+
+impl Default for AnyArena{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl std::fmt::Debug for AnyArena{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AnyArena")
+            .field("types", &self.arenas.len())
+            .field("num", &self.num())
+            .finish()
+    }
+}
+
+impl<T: 'static> Index<ArenaIdx<T>> for AnyArena{
+    type Output = T;
+
+    fn index(&self, index: ArenaIdx<T>) -> &Self::Output {
+        self.get(index).expect("There is no element at this index with that generation.")
+    }
+}
+
+impl<T: 'static> IndexMut<ArenaIdx<T>> for AnyArena{
+    fn index_mut(&mut self, index: ArenaIdx<T>) -> &mut Self::Output {
+        self.get_mut(index).expect("There is no element at this index with that generation.")
+    }
+}
+
+#[cfg(test)]
+mod test{
+    use super::*;
+
+    #[derive(Debug, PartialEq)]
+    struct Position(f32, f32);
+
+    #[derive(Debug, PartialEq)]
+    struct Name(&'static str);
+
+    #[test]
+    fn test_multiple_types(){
+        let mut arena = AnyArena::new();
+
+        let p0 = arena.insert(Position(0., 0.));
+        let p1 = arena.insert(Position(1., 1.));
+        let n0 = arena.insert(Name("zero"));
+
+        // Same slot index in different arenas.
+        assert_eq!(p0.index(), n0.index());
+        assert_eq!(arena.get(p0), Some(&Position(0., 0.)));
+        assert_eq!(arena.get(n0), Some(&Name("zero")));
+
+        arena.get_mut(p1).unwrap().0 = 2.;
+        assert_eq!(arena[p1], Position(2., 1.));
+        assert_eq!(arena.num(), 3);
+
+        // Missing types.
+        assert!(!arena.contains_type::<u8>());
+        assert!(arena.arena_of::<u8>().is_none());
+        assert_eq!(arena.get(ArenaIdx::<u8>::from_raw_parts(0, 0)), None);
+        assert_eq!(arena.remove(ArenaIdx::<u8>::from_raw_parts(0, 0)), None);
+
+        // Removing the last element of a type keeps the other types' keys valid.
+        assert_eq!(arena.remove(n0), Some(Name("zero")));
+        assert!(arena.contains_type::<Name>());
+        assert_eq!(arena.arena_of::<Name>().unwrap().num(), 0);
+        assert_eq!(arena.get(p0), Some(&Position(0., 0.)));
+        assert_eq!(arena.get(p1), Some(&Position(2., 1.)));
+
+        let xs: Vec<_> = arena.arena_of::<Position>().unwrap().values().map(|p| p.0).collect();
+        assert_eq!(xs, vec![0., 2.]);
+
+        arena.clear();
+        assert_eq!(arena.num(), 0);
+        assert_eq!(arena.get(p0), None);
+    }
+}
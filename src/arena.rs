@@ -1,58 +1,226 @@
 
-use std::{marker::PhantomData, ops::{Index, IndexMut}};
+use std::{marker::PhantomData, ops::{Index, IndexMut, Range}};
+
+use crate::generation::GenCounter;
+use crate::inserterror::*;
+use crate::raw::*;
+use crate::recycler::RecycleQueue;
+#[cfg(feature = "leak-detect")]
+use crate::leakdetect::LeakTracker;
+#[cfg(feature = "labels")]
+use crate::labels::Labels;
+#[cfg(feature = "metrics")]
+use crate::metrics::ArenaMetrics;
+#[cfg(feature = "removal-watch")]
+use crate::removalwatch::RemovalWatchers;
 
 ///
 /// Cell of an Arena.
 ///
-#[derive(Debug)]
-pub enum ArenaCell<T>{
-    Allocated{val: T, generation: usize},
-    Freed{next: Option<usize>, generation: usize},
+/// The layout of the cells is private, Arena::slot_states, Arena::generation_at and
+/// Arena::free_indices expose the state of the slots instead.
+///
+#[deprecated(note = "The cells of an Arena are private, use Arena::slot_states instead.")]
+#[derive(Debug, Clone)]
+pub enum ArenaCell<T, G = usize>{
+    Allocated{val: T, generation: G},
+    Freed{next: Option<usize>, generation: G},
+}
+
+///
+/// The state of a slot of an Arena, see Arena::slot_states.
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SlotState<'a, T, G = usize>{
+    /// The slot holds an element.
+    Live{val: &'a T, generation: G},
+    /// The slot is on the free list and links to the next slot on it.
+    Free{next: Option<usize>, generation: G},
+    /// The generation of the slot ran out, it is freed but never reused.
+    Retired{generation: G},
+    /// The slot is reserved by Arena::reserve_keys and gets an element with the generation when it is filled.
+    Reserved{generation: G},
+}
+
+impl<'a, T, G: GenCounter> SlotState<'a, T, G>{
+    #[inline]
+    pub fn generation(&self) -> G{
+        match *self{
+            Self::Live{generation, ..} | Self::Free{generation, ..} | Self::Retired{generation} | Self::Reserved{generation} => generation,
+        }
+    }
 }
 
 ///
 /// An index referring to an index and epoch in an Arena.
 ///
-#[derive(Debug, PartialEq, Eq)]
-pub struct ArenaIdx<T>{
+/// The key with index and generation usize::MAX is reserved as dangling key, see ArenaIdx::dangling.
+///
+/// Tag is the tag of the arena that issued the key, see Arena.
+///
+pub struct ArenaIdx<T: ?Sized, G = usize, Tag = ()>{
     index: usize,
-    generation: usize,
+    generation: G,
     _ty: PhantomData<T>,
+    _tag: PhantomData<fn() -> Tag>,
 }
 
-impl<T> ArenaIdx<T>{
-    pub fn new(index: usize, generation: usize) -> Self{
+impl<T: ?Sized, G: GenCounter, Tag> ArenaIdx<T, G, Tag>{
+    // The constructor used by the arenas themselves, keeping from_raw_parts for fabricated keys.
+    #[inline]
+    pub(crate) const fn from_parts(index: usize, generation: G) -> Self{
         Self{
             index,
             generation,
             _ty: PhantomData,
+            _tag: PhantomData,
         }
     }
 
+    ///
+    /// Fabricates a key from an index and a generation, as returned by into_raw_parts.
+    ///
+    /// This is meant for round trips through serialization or FFI only. A fabricated key is
+    /// not tied to any arena and may refer to a live element of an arena it never came from.
+    /// To audit every fabrication site, deny it with clippy's disallowed_methods lint:
+    ///
+    /// ```toml
+    /// # clippy.toml
+    /// disallowed-methods = ["gen_arena::ArenaIdx::from_raw_parts"]
+    /// ```
+    ///
+    /// ```rust
+    /// use gen_arena::*;
+    ///
+    /// let mut arena = Arena::new();
+    /// let i0 = arena.insert(0);
+    ///
+    /// let (index, generation) = i0.into_raw_parts();
+    /// assert_eq!(ArenaIdx::from_raw_parts(index, generation), i0);
+    /// ```
+    ///
+    #[inline]
+    #[must_use]
+    pub const fn from_raw_parts(index: usize, generation: G) -> Self{
+        Self::from_parts(index, generation)
+    }
+
+    ///
+    /// Fabricates a key from an index and a generation.
+    ///
+    #[deprecated(note = "Use ArenaIdx::from_raw_parts, which makes fabricating keys explicit.")]
+    #[inline]
+    #[must_use]
+    pub fn new(index: usize, generation: G) -> Self{
+        Self::from_raw_parts(index, generation)
+    }
+
+    ///
+    /// Returns the index and the generation of the key, see from_raw_parts.
+    ///
+    #[inline]
+    #[must_use]
+    pub const fn into_raw_parts(self) -> (usize, G){
+        (self.index, self.generation)
+    }
+
     #[inline]
     pub fn index(&self) -> usize{
         self.index
     }
 
     #[inline]
-    pub fn gen(&self) -> usize{
+    pub fn gen(&self) -> G{
         self.generation
     }
 }
 
-// Have to implement copy and clone myselfe because of generic.
-impl<T> Clone for ArenaIdx<T>{
+impl<T: ?Sized, Tag> ArenaIdx<T, usize, Tag>{
+    ///
+    /// Returns a placeholder key that no arena ever issues.
+    ///
+    /// Its index and generation are both usize::MAX, a pattern serialization formats can rely on.
+    /// Arenas treat it like any stale key: lookups miss and removals do nothing.
+    ///
+    /// ```rust
+    /// use gen_arena::*;
+    ///
+    /// let mut arena = Arena::new();
+    /// let _ = arena.insert(0);
+    ///
+    /// let placeholder = ArenaIdx::<i32>::dangling();
+    /// assert!(placeholder.is_dangling());
+    /// assert_eq!(arena.get(placeholder), None);
+    /// assert_eq!(arena.remove(placeholder), None);
+    /// ```
+    ///
+    #[inline]
+    pub const fn dangling() -> Self{
+        Self{
+            index: usize::MAX,
+            generation: usize::MAX,
+            _ty: PhantomData,
+            _tag: PhantomData,
+        }
+    }
+
+    ///
+    /// Returns true if this is the key returned by ArenaIdx::dangling.
+    ///
+    #[inline]
+    pub const fn is_dangling(&self) -> bool{
+        self.index == usize::MAX && self.generation == usize::MAX
+    }
+}
+
+// Have to implement copy, clone and eq myselfe because of generic.
+impl<T: ?Sized, G: GenCounter, Tag> Clone for ArenaIdx<T, G, Tag>{
     #[inline]
     fn clone(&self) -> Self {
         *self
     }
 }
 
-impl<T> Copy for ArenaIdx<T>{}
+impl<T: ?Sized, G: GenCounter, Tag> Copy for ArenaIdx<T, G, Tag>{}
+
+impl<T: ?Sized, G: GenCounter, Tag> PartialEq for ArenaIdx<T, G, Tag>{
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        self.index == other.index && self.generation == other.generation
+    }
+}
+
+impl<T: ?Sized, G: GenCounter, Tag> Eq for ArenaIdx<T, G, Tag>{}
+
+///
+/// Logged as index@generation, like the textual form of keys.
+///
+#[cfg(feature = "defmt")]
+impl<T: ?Sized, G: GenCounter + defmt::Format, Tag> defmt::Format for ArenaIdx<T, G, Tag>{
+    fn format(&self, f: defmt::Formatter<'_>) {
+        defmt::write!(f, "{=usize}@{}", self.index, self.generation)
+    }
+}
+
+impl<T: ?Sized, G: GenCounter, Tag> std::fmt::Debug for ArenaIdx<T, G, Tag>{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ArenaIdx")
+            .field("index", &self.index)
+            .field("generation", &self.generation)
+            .finish()
+    }
+}
 
 ///
 /// An Generational Arena that keeps track of freed cells in a Vec.
 ///
+/// The generation type G defaults to usize, see GenCounter for the other choices and what happens
+/// when a generation runs out. Arenas with another generation type are created with default.
+///
+/// The Tag defaults to () and only exists for the compiler. Keys carry the tag of their arena,
+/// so keys of an arena with one tag do not type-check against an arena with another tag.
+/// Tags are declared with arena_tag.
+///
 /// # Example
 ///
 ///```rust
@@ -78,11 +246,19 @@ impl<T> Copy for ArenaIdx<T>{}
 ///
 ///```
 ///
-#[derive(Debug)]
-pub struct Arena<T>{
-    cells: Vec<ArenaCell<T>>,
-    freed: Option<usize>,
-    num: usize,
+#[derive(Debug, Clone)]
+pub struct Arena<T, G = usize, Tag = ()>{
+    raw: RawArena<T, G>,
+    pub(crate) recycler: RecycleQueue,
+    #[cfg(feature = "leak-detect")]
+    pub(crate) leaks: LeakTracker,
+    #[cfg(feature = "metrics")]
+    pub(crate) metrics: ArenaMetrics,
+    #[cfg(feature = "labels")]
+    pub(crate) labels: Labels<G>,
+    #[cfg(feature = "removal-watch")]
+    pub(crate) watchers: RemovalWatchers<T, G, Tag>,
+    _tag: PhantomData<fn() -> Tag>,
 }
 
 impl<T> Arena<T>{
@@ -97,11 +273,7 @@ impl<T> Arena<T>{
     ///```
     ///
     pub fn new() -> Self{
-        Self{
-            cells: Vec::new(),
-            freed: None,
-            num: 0,
-        }
+        Self::default()
     }
 
     ///
@@ -119,11 +291,22 @@ impl<T> Arena<T>{
     ///
     pub fn with_capacity(cap: usize) -> Self{
         Self{
-            cells: Vec::with_capacity(cap),
-            freed: None,
-            num: 0,
+            raw: RawArena::with_capacity(cap),
+            recycler: RecycleQueue::default(),
+            #[cfg(feature = "leak-detect")]
+            leaks: LeakTracker::default(),
+            #[cfg(feature = "metrics")]
+            metrics: ArenaMetrics::default(),
+            #[cfg(feature = "labels")]
+            labels: Labels::new(),
+            #[cfg(feature = "removal-watch")]
+            watchers: RemovalWatchers::new(),
+            _tag: PhantomData,
         }
     }
+}
+
+impl<T, G: GenCounter, Tag> Arena<T, G, Tag>{
 
     ///
     /// Clears the arena and resets the list of Freed cells.
@@ -145,63 +328,32 @@ impl<T> Arena<T>{
     /// ```
     ///
     pub fn clear(&mut self){
-        let len = self.cells.len();
-        for (i, cell) in self.cells.iter_mut().enumerate(){
-            match cell{
-                ArenaCell::Allocated{val: _, generation} => {
-                    *cell = ArenaCell::Freed{
-                        generation: *generation + 1,
-                        next: if i < len-1 {Some(i+1)} else{None},
-                    }
-                },
-                ArenaCell::Freed{next: _, generation} => {
-                    *cell = ArenaCell::Freed{
-                        generation: *generation,
-                        next: if i < len-1 {Some(i+1)} else{None},
-                    }
-                }
-            }
-        }
-        self.num = 0;
+        #[cfg(feature = "leak-detect")]
+        self.leaks.reset();
+        #[cfg(feature = "metrics")]
+        self.metrics.removed(self.raw.num);
+        #[cfg(feature = "labels")]
+        self.labels.clear();
+        self.raw.clear();
+        #[cfg(feature = "removal-watch")]
+        self.watchers.fire_all();
     }
 
     ///
     /// Tries to insert into Arena.
-    /// Returns val as Err if failed.
-    ///
-    pub fn try_insert(&mut self, val: T) -> Result<ArenaIdx<T>, T>{
-        match self.freed{
-            Some(i) => {
-                if let ArenaCell::Freed{next, generation} = self.cells[i]{
-                    self.freed = next;
-                    self.cells[i] = ArenaCell::Allocated{
-                        val,
-                        generation,
-                    };
-                    self.num += 1;
-                    Ok(ArenaIdx{
-                        index: i,
-                        generation,
-                        _ty: PhantomData,
-                    })
-                }
-                else{
-                    Err(val)
-                }
-            }
-            None => {
-                self.cells.push(ArenaCell::Allocated{
-                    generation: 0,
-                    val,
-                });
-                self.num += 1;
-                Ok(ArenaIdx{
-                    index: self.cells.len() -1,
-                    generation: 0,
-                    _ty: PhantomData,
-                })
-            }
-        }
+    /// Returns val with the reason as Err if failed.
+    ///
+    /// The arena grows as needed, so it is never full. If its list of freed slots is corrupt,
+    /// it is rebuilt from the slots before inserting, so the insertion only fails if that does
+    /// not help either.
+    ///
+    pub fn try_insert(&mut self, val: T) -> Result<ArenaIdx<T, G, Tag>, InsertError<T>>{
+        let (index, generation) = self.raw.try_insert(val)?;
+        #[cfg(feature = "leak-detect")]
+        self.leaks.record(index);
+        #[cfg(feature = "metrics")]
+        self.metrics.inserted(1, self.raw.num, self.raw.cells.len());
+        Ok(ArenaIdx::from_parts(index, generation))
     }
 
     ///
@@ -223,7 +375,7 @@ impl<T> Arena<T>{
     /// ```
     ///
     #[must_use]
-    pub fn insert(&mut self, val: T) -> ArenaIdx<T>{
+    pub fn insert(&mut self, val: T) -> ArenaIdx<T, G, Tag>{
         match self.try_insert(val){
             Ok(index) => index,
             Err(_val) => panic!("Insertion not successfull."),
@@ -232,30 +384,233 @@ impl<T> Arena<T>{
 
     ///
     /// Removes the cell from the arena and increaces its generation.
+    /// Returns the value if the index is still valid, stale indices leave the cell untouched.
     ///
-    pub fn remove(&mut self, index: ArenaIdx<T>){
-        if let ArenaCell::Allocated{val: _, generation} = &self.cells[index.index]{
-            self.cells[index.index] = ArenaCell::Freed{
-                next: self.freed,
-                generation: generation + 1,
-            };
-            self.num -= 1;
-            self.freed = Some(index.index);
-        }
+    /// ```rust
+    /// use gen_arena::*;
+    ///
+    /// let mut arena = Arena::new();
+    ///
+    /// let i1 = arena.insert(1);
+    ///
+    /// assert_eq!(arena.remove(i1), Some(1));
+    /// assert_eq!(arena.remove(i1), None);
+    ///
+    /// let i2 = arena.insert(2);
+    ///
+    /// // The stale index does not remove the new value.
+    /// assert_eq!(arena.remove(i1), None);
+    /// assert_eq!(*arena.get(i2).unwrap(), 2);
+    /// ```
+    ///
+    pub fn remove(&mut self, index: ArenaIdx<T, G, Tag>) -> Option<T>{
+        let val = self.raw.remove(index.index, index.generation)?;
+        #[cfg(feature = "leak-detect")]
+        self.leaks.forget(index.index);
+        #[cfg(feature = "metrics")]
+        self.metrics.removed(1);
+        #[cfg(feature = "labels")]
+        self.labels.remove(&index.index);
+        #[cfg(feature = "removal-watch")]
+        self.watchers.fire(index.index);
+        Some(val)
+    }
+
+    ///
+    /// Invalidates every key to the element without moving or dropping it and returns its new key.
+    /// Returns None if the index is not valid, or if the generation of a narrow GenCounter is exhausted,
+    /// in which case the key stays valid.
+    ///
+    /// ```rust
+    /// use gen_arena::*;
+    ///
+    /// let mut arena = Arena::new();
+    /// let old = arena.insert("asset");
+    ///
+    /// let new = arena.bump_generation(old).unwrap();
+    /// assert_eq!(arena.get(old), None);
+    /// assert_eq!(arena[new], "asset");
+    /// assert_eq!(arena.bump_generation(old), None);
+    /// ```
+    ///
+    pub fn bump_generation(&mut self, index: ArenaIdx<T, G, Tag>) -> Option<ArenaIdx<T, G, Tag>>{
+        let generation = self.raw.bump(index.index, index.generation)?;
+        #[cfg(feature = "removal-watch")]
+        self.watchers.fire(index.index);
+        Some(ArenaIdx::from_parts(index.index, generation))
     }
 
     ///
     /// Gets the Generation for a given index.
+    /// Panics if the index is out of range, see generation_at.
+    ///
+    pub fn gen(&self, index: usize) -> G{
+        self.raw.gen(index)
+    }
+
+    ///
+    /// Returns the generation of the slot, whether it holds an element or not,
+    /// or None if the index is out of range.
+    ///
+    /// ```rust
+    /// use gen_arena::*;
+    ///
+    /// let mut arena = Arena::new();
+    /// let i0 = arena.insert(0);
+    /// arena.remove(i0);
+    ///
+    /// assert_eq!(arena.generation_at(0), Some(1));
+    /// assert_eq!(arena.generation_at(1), None);
+    /// ```
+    ///
+    #[inline]
+    pub fn generation_at(&self, index: usize) -> Option<G>{
+        match self.raw.cells.get(index)?{
+            RawCell::Allocated{generation, ..} | RawCell::Freed{generation, ..} => Some(*generation),
+        }
+    }
+
+    ///
+    /// Returns the key of the element currently stored in the slot at index, if there is one.
+    ///
+    /// ```rust
+    /// use gen_arena::*;
+    ///
+    /// let mut arena = Arena::new();
+    /// let i0 = arena.insert(0);
+    ///
+    /// assert_eq!(arena.idx_at(0), Some(i0));
+    /// assert_eq!(arena.idx_at(1), None);
+    /// ```
+    ///
+    pub fn idx_at(&self, index: usize) -> Option<ArenaIdx<T, G, Tag>>{
+        match self.raw.cells.get(index)?{
+            RawCell::Allocated{generation, ..} => Some(ArenaIdx::from_parts(index, *generation)),
+            RawCell::Freed{..} => None,
+        }
+    }
+
+    ///
+    /// Returns the key of the element in the lowest occupied slot.
+    ///
+    /// ```rust
+    /// use gen_arena::*;
+    ///
+    /// let mut arena = Arena::new();
+    /// let keys: Vec<_> = (0..5).map(|i| arena.insert(i)).collect();
+    /// arena.remove(keys[0]);
+    /// arena.remove(keys[4]);
+    ///
+    /// assert_eq!(arena.first_key(), Some(keys[1]));
+    /// assert_eq!(arena.last_key(), Some(keys[3]));
+    /// assert_eq!(arena.occupied_range(), Some(1..4));
+    ///
+    /// arena.clear();
+    /// assert_eq!((arena.first_key(), arena.last_key(), arena.occupied_range()), (None, None, None));
+    /// ```
+    ///
+    #[inline]
+    pub fn first_key(&self) -> Option<ArenaIdx<T, G, Tag>>{
+        self.keys().next()
+    }
+
+    ///
+    /// Returns the key of the element in the highest occupied slot.
+    ///
+    #[inline]
+    pub fn last_key(&self) -> Option<ArenaIdx<T, G, Tag>>{
+        (0..self.slots()).rev().find_map(|i| self.idx_at(i))
+    }
+
+    ///
+    /// Returns the smallest range of slots that contains all elements, or None if the arena is empty.
+    /// Slots at and after its end are free or retired.
+    ///
+    pub fn occupied_range(&self) -> Option<Range<usize>>{
+        Some(self.first_key()?.index()..self.last_key()?.index() + 1)
+    }
+
+    ///
+    /// Returns the key of the element the reference points to, or None if it does not point to
+    /// an element of this arena.
+    ///
+    /// ```rust
+    /// use gen_arena::*;
+    ///
+    /// let mut arena = Arena::new();
+    /// let _ = arena.insert(0);
+    /// let i1 = arena.insert(1);
+    ///
+    /// let one = arena.values().find(|val| **val == 1).unwrap();
+    /// assert_eq!(arena.key_of(one), Some(i1));
+    /// assert_eq!(arena.key_of(&1), None);
+    /// ```
     ///
-    pub fn gen(&self, index: usize) -> usize{
-        match self.cells[index]{
-            ArenaCell::Freed{generation, ..} => generation,
-            ArenaCell::Allocated{generation, ..} => generation,
+    pub fn key_of(&self, value: &T) -> Option<ArenaIdx<T, G, Tag>>{
+        // Only addresses are compared, so pointers into other allocations are never dereferenced.
+        let base = self.raw.cells.as_ptr() as usize;
+        let offset = (value as *const T as usize).checked_sub(base)?;
+        let index = offset / std::mem::size_of::<RawCell<T, G>>();
+        match self.raw.cells.get(index)?{
+            RawCell::Allocated{val, generation} if std::ptr::eq(val, value) => Some(ArenaIdx::from_parts(index, *generation)),
+            _ => None,
         }
     }
 
+    ///
+    /// Returns the slot index the next insertion will use.
+    ///
+    #[inline]
+    pub(crate) fn vacant_index(&self) -> usize{
+        self.raw.freed.unwrap_or(self.raw.cells.len())
+    }
+
+    ///
+    /// Frees the element in the slot and gives its cell the generation, see RawArena::take_at.
+    ///
+    #[inline]
+    pub(crate) fn take_at(&mut self, index: usize, generation: G, pop: bool) -> T{
+        #[cfg(feature = "leak-detect")]
+        self.leaks.forget(index);
+        #[cfg(feature = "labels")]
+        self.labels.remove(&index);
+        let val = self.raw.take_at(index, generation, pop);
+        #[cfg(feature = "removal-watch")]
+        self.watchers.fire(index);
+        val
+    }
+
+    ///
+    /// Allocates the slot with the value and generation, see RawArena::put_at.
+    ///
+    #[inline]
+    pub(crate) fn put_at(&mut self, index: usize, val: T, generation: G, push: bool){
+        #[cfg(feature = "leak-detect")]
+        self.leaks.record(index);
+        self.raw.put_at(index, val, generation, push)
+    }
+
+    #[inline]
+    pub(crate) fn raw(&self) -> &RawArena<T, G>{
+        &self.raw
+    }
+
+    #[inline]
+    pub(crate) fn raw_mut(&mut self) -> &mut RawArena<T, G>{
+        &mut self.raw
+    }
+
+    ///
+    /// Returns the number of cells, including freed ones.
+    ///
+    #[inline]
+    pub(crate) fn slots(&self) -> usize{
+        self.raw.cells.len()
+    }
+
     ///
     /// Returns an optional reference to the value at the index.
+    /// Besides keys it accepts anything that converts into one, like a TaggedIdx.
     ///
     /// ```rust
     /// use gen_arena::*;
@@ -272,30 +627,45 @@ impl<T> Arena<T>{
     ///
     /// ```
     ///
-    pub fn get(&self, index: ArenaIdx<T>) -> Option<&T>{
-        if let ArenaCell::Allocated{val, generation} = &self.cells[index.index]{
-            if *generation == index.generation{
-                Some(val)
-            }
-            else{
-                None
-            }
-        }
-        else{
-            None
-        }
+    pub fn get(&self, index: impl Into<ArenaIdx<T, G, Tag>>) -> Option<&T>{
+        let index = index.into();
+        self.raw.get(index.index, index.generation)
     }
 
     ///
     /// Returns an optional reference to a cell with any generation.
     ///
     pub fn get_any(&self, index: usize) -> Option<&T>{
-        if let ArenaCell::Allocated{val, generation: _} = &self.cells[index]{
-            Some(val)
-        }
-        else{
-            None
-        }
+        self.raw.get_any(index)
+    }
+
+    ///
+    /// Returns the element that is in the slot of the key now, with a valid key to it,
+    /// or None if the slot is vacant or out of range.
+    ///
+    /// # Warning
+    ///
+    /// This ignores the generation of the key and with it the protection against ABA, the element
+    /// returned is usually not the one the key was issued for. It is meant for stale keys whose
+    /// element is known to have been replaced in the same slot, like an asset reinserted by a hot
+    /// reload. Use get everywhere else. Unlike get_any it returns the key of the element,
+    /// so later accesses are checked again.
+    ///
+    /// ```rust
+    /// use gen_arena::*;
+    ///
+    /// let mut assets = Arena::new();
+    /// let stale = assets.insert("shader v1");
+    /// assets.remove(stale);
+    /// let fresh = assets.insert("shader v2");
+    ///
+    /// assert_eq!(assets.get(stale), None);
+    /// assert_eq!(assets.get_latest(stale), Some((fresh, &"shader v2")));
+    /// ```
+    ///
+    pub fn get_latest(&self, stale: ArenaIdx<T, G, Tag>) -> Option<(ArenaIdx<T, G, Tag>, &T)>{
+        let index = self.idx_at(stale.index())?;
+        self.get(index).map(|val| (index, val))
     }
 
     ///
@@ -316,7 +686,7 @@ impl<T> Arena<T>{
     ///
     /// ```
     ///
-    pub fn getn<const N: usize>(&self, indices: [ArenaIdx<T>; N]) -> [Option<&T>; N]{
+    pub fn getn<const N: usize>(&self, indices: [ArenaIdx<T, G, Tag>; N]) -> [Option<&T>; N]{
         let mut ret = [None; N];
 
         for (i, index) in indices.iter().enumerate(){
@@ -327,6 +697,7 @@ impl<T> Arena<T>{
 
     ///
     /// Returns a mutable optional reference to the value at the index.
+    /// Besides keys it accepts anything that converts into one, like a TaggedIdx.
     ///
     /// ```rust
     /// use gen_arena::*;
@@ -347,35 +718,21 @@ impl<T> Arena<T>{
     ///
     /// ```
     ///
-    pub fn get_mut(&mut self, index: ArenaIdx<T>) -> Option<&mut T>{
-        if let ArenaCell::Allocated{val, generation} = &mut self.cells[index.index]{
-            if *generation == index.generation{
-                Some(val)
-            }
-            else{
-                None
-            }
-        }
-        else{
-            None
-        }
+    pub fn get_mut(&mut self, index: impl Into<ArenaIdx<T, G, Tag>>) -> Option<&mut T>{
+        let index = index.into();
+        self.raw.get_mut(index.index, index.generation)
     }
 
     ///
     /// Returns an optional mutable reference to the value of a cell at a index with any generation.
     ///
     pub fn get_any_mut(&mut self, index: usize) -> Option<&mut T>{
-        if let ArenaCell::Allocated{val, generation: _} = &mut self.cells[index]{
-            Some(val)
-        }
-        else{
-            None
-        }
+        self.raw.get_any_mut(index)
     }
 
     ///
     /// Returns mutable optional references to two distinct values.
-    /// Indices have to be different.
+    /// Panics if both indices refer to the same element, stale indices to the same slot yield None.
     ///
     ///```rust
     /// use gen_arena::*;
@@ -395,75 +752,67 @@ impl<T> Arena<T>{
     ///
     ///```
     ///
-    pub fn get2_mut(&mut self, indices: (ArenaIdx<T>, ArenaIdx<T>)) -> (Option<&mut T>, Option<&mut T>){
-        if indices.0.index == indices.1.index{
-            if indices.0.generation == indices.1.generation{
-                panic!("Cannot take 2 mutable references to a value at the same index.")
-            }
+    pub fn get2_mut(&mut self, indices: (ArenaIdx<T, G, Tag>, ArenaIdx<T, G, Tag>)) -> (Option<&mut T>, Option<&mut T>){
+        self.raw.get2_mut((indices.0.index, indices.0.generation), (indices.1.index, indices.1.generation))
+    }
 
-            if indices.0.generation > indices.1.generation{
-                return (self.get_mut(indices.0), None);
-            }
-            else{
-                return (None, self.get_mut(indices.1));
+    ///
+    /// Returns mutable references to N distinct values, or None if any index is stale or out of range.
+    /// Panics if the same live index is passed twice, stale indices passed twice yield None,
+    /// like in SArena::getn_mut.
+    ///
+    ///```rust
+    /// use gen_arena::*;
+    ///
+    /// let mut arena = Arena::new();
+    ///
+    /// let i1 = arena.insert(1);
+    /// let i2 = arena.insert(2);
+    /// let i3 = arena.insert(3);
+    ///
+    /// let [c3, c1, c2] = arena.getn_mut([i3, i1, i2]).unwrap();
+    ///
+    /// *c1 += 10;
+    /// *c2 += 10;
+    /// *c3 += 10;
+    ///
+    /// assert_eq!(arena.values().collect::<Vec<_>>(), [&11, &12, &13]);
+    ///
+    /// arena.remove(i2);
+    /// assert!(arena.getn_mut([i1, i2]).is_none());
+    ///```
+    ///
+    pub fn getn_mut<const N: usize>(&mut self, indices: [ArenaIdx<T, G, Tag>; N]) -> Option<[&mut T; N]>{
+        for (i, index) in indices.iter().enumerate(){
+            if indices[..i].contains(index) && self.get(*index).is_some(){
+                panic!("Cannot take 2 mutable references to a value at the same index.")
             }
         }
-
-        if indices.0.index >= self.cells.len(){
-            return (None, self.get_mut(indices.1));
-        }
-        if indices.1.index >= self.cells.len(){
-            return (self.get_mut(indices.0), None);
+        if indices.iter().any(|index| self.get(*index).is_none()){
+            return None;
         }
 
-        let (cell0, cell1) = {
-            let split = self.cells.split_at_mut(indices.0.index.max(indices.1.index));
-            if indices.0.index < indices.1.index{
-                (&mut split.0[indices.0.index], &mut split.1[0])
-            }
-            else{
-                (&mut split.1[0], &mut split.0[indices.1.index])
+        // The indices are live and distinct, so their slots are too. Visit them in ascending
+        // order, so each cell can be split off the front of the remaining cells.
+        let mut order = [0; N];
+        for (i, j) in order.iter_mut().enumerate(){
+            *j = i;
+        }
+        order.sort_unstable_by_key(|&j| indices[j].index);
+
+        let mut ret = [(); N].map(|()| None);
+        let mut rest = &mut self.raw.cells[..];
+        let mut start = 0;
+        for j in order{
+            let index = indices[j].index;
+            let (head, tail) = std::mem::take(&mut rest).split_at_mut(index - start + 1);
+            rest = tail;
+            start = index + 1;
+            if let Some(RawCell::Allocated{val, ..}) = head.last_mut(){
+                ret[j] = Some(val);
             }
-        };
-
-        let cell0 = match cell0{
-            ArenaCell::Allocated{val, generation} => {
-                if indices.0.generation == *generation{
-                    Some(val)
-                }
-                else{
-                    None
-                }
-            },
-            _ => None
-        };
-        let cell1 = match cell1{
-            ArenaCell::Allocated{val, generation} => {
-                if indices.1.generation == *generation{
-                    Some(val)
-                }
-                else{
-                    None
-                }
-            },
-            _ => None
-        };
-
-        (cell0, cell1)
-    }
-
-    // TODO: implement
-    pub fn getn_mut<const N: usize>(&mut self, indices: [ArenaIdx<T>; N]) -> Option<[ArenaIdx<T>; N]>{
-        let mut i = 0;
-        for index in indices{
-
         }
-        let mut i = 0;
-        let indices = indices.map(|index|{
-            i += 1;
-            (i - 1, index)
-        });
-        todo!()
+        Some(ret.map(|val| val.expect("Every index refers to an element.")))
     }
 
     ///
@@ -483,7 +832,7 @@ impl<T> Arena<T>{
     /// ```
     ///
     #[inline]
-    pub fn values(&self) -> Values<T>{
+    pub fn values(&self) -> Values<'_, T, G, Tag>{
         Values{
             iter: self.iter()
         }
@@ -509,7 +858,7 @@ impl<T> Arena<T>{
     /// ```
     ///
     #[inline]
-    pub fn values_mut(&mut self) -> ValuesMut<T>{
+    pub fn values_mut(&mut self) -> ValuesMut<'_, T, G, Tag>{
         ValuesMut{
             iter: self.iter_mut()
         }
@@ -527,16 +876,16 @@ impl<T> Arena<T>{
     ///
     /// for (i, key) in arena.keys().enumerate(){
     ///     if i == 0{
-    ///         assert_eq!(key, ArenaIdx::new(0, 0));
+    ///         assert_eq!(key, ArenaIdx::from_raw_parts(0, 0));
     ///     }
     ///     if i == 1{
-    ///         assert_eq!(key, ArenaIdx::new(1, 0));
+    ///         assert_eq!(key, ArenaIdx::from_raw_parts(1, 0));
     ///     }
     /// }
     /// ```
     ///
     #[inline]
-    pub fn keys(&self) -> Keys<T>{
+    pub fn keys(&self) -> Keys<'_, T, G, Tag>{
         Keys{
             iter: self.iter(),
         }
@@ -564,9 +913,10 @@ impl<T> Arena<T>{
     /// ```
     ///
     #[inline]
-    pub fn iter(&self) -> Iter<T>{
+    pub fn iter(&self) -> Iter<'_, T, G, Tag>{
         Iter{
-            iter: self.cells.iter().enumerate(),
+            iter: self.raw.cells.iter().enumerate(),
+            _tag: PhantomData,
         }
     }
 
@@ -590,55 +940,399 @@ impl<T> Arena<T>{
     /// ```
     ///
     #[inline]
-    pub fn iter_mut(&mut self) -> IterMut<T>{
+    pub fn iter_mut(&mut self) -> IterMut<'_, T, G, Tag>{
         IterMut{
-            iter: self.cells.iter_mut().enumerate(),
+            iter: self.raw.cells.iter_mut().enumerate(),
+            _tag: PhantomData,
+        }
+    }
+
+    ///
+    /// Returns an iterator over the states of all slots, including freed ones.
+    ///
+    /// ```rust
+    /// use gen_arena::*;
+    /// let mut arena = Arena::new();
+    ///
+    /// let i0 = arena.insert(0);
+    /// let _ = arena.insert(1);
+    /// arena.remove(i0);
+    ///
+    /// let states: Vec<_> = arena.slot_states().collect();
+    /// assert_eq!(states, [SlotState::Free{next: None, generation: 1}, SlotState::Live{val: &1, generation: 0}]);
+    /// ```
+    ///
+    pub fn slot_states(&self) -> impl DoubleEndedIterator<Item = SlotState<'_, T, G>> + ExactSizeIterator{
+        self.raw.cells.iter().enumerate().map(|(i, cell)| match *cell{
+            RawCell::Allocated{ref val, generation} => SlotState::Live{val, generation},
+            RawCell::Freed{generation, ..} if generation == G::RETIRED => SlotState::Retired{generation},
+            RawCell::Freed{next, generation} if next == Some(i) => SlotState::Reserved{generation},
+            RawCell::Freed{next, generation} => SlotState::Free{next, generation},
+        })
+    }
+
+    ///
+    /// Returns an iterator over the indices of the freed slots in the order they are reused,
+    /// the first one is taken by the next insert. Retired slots are not on the list.
+    ///
+    /// ```rust
+    /// use gen_arena::*;
+    /// let mut arena = Arena::new();
+    ///
+    /// let keys: Vec<_> = (0..3).map(|i| arena.insert(i)).collect();
+    /// arena.remove(keys[0]);
+    /// arena.remove(keys[2]);
+    ///
+    /// assert_eq!(arena.free_indices().collect::<Vec<_>>(), [2, 0]);
+    /// ```
+    ///
+    pub fn free_indices(&self) -> impl Iterator<Item = usize> + '_{
+        std::iter::successors(self.raw.freed, move |&i|{
+            match self.raw.cells.get(i){
+                Some(RawCell::Freed{next, ..}) => *next,
+                _ => None,
+            }
+        })
+    }
+
+    ///
+    /// Links the freed slots in ascending order, so the next insertions use the lowest free slots first.
+    ///
+    /// # Determinism
+    ///
+    /// Keys only depend on the operations on an arena: two arenas that start out equal and go through
+    /// the same insertions, removals and clears hand out the same keys. Clones, serde round trips and
+    /// apply_diff keep the order of the free list and with it the keys of future insertions.
+    /// Arenas rebuilt in other ways, for example from an SArena, link their freed slots in ascending
+    /// order instead. Canonicalizing the free list on every peer after loading a snapshot makes them
+    /// agree again, regardless of how the snapshot was loaded. The order of iteration is always the
+    /// order of the slots.
+    ///
+    /// ```rust
+    /// use gen_arena::*;
+    /// let mut arena = Arena::new();
+    ///
+    /// let keys: Vec<_> = (0..3).map(|i| arena.insert(i)).collect();
+    /// arena.remove(keys[0]);
+    /// arena.remove(keys[2]);
+    /// assert_eq!(arena.free_indices().collect::<Vec<_>>(), [2, 0]);
+    ///
+    /// arena.canonicalize_free_list();
+    /// assert_eq!(arena.free_indices().collect::<Vec<_>>(), [0, 2]);
+    /// ```
+    ///
+    pub fn canonicalize_free_list(&mut self){
+        self.raw.relink()
+    }
+
+    ///
+    /// Creates an arena with the values at their positions in the Vec and the first generation,
+    /// so the positions used as ids before become keys with ArenaIdx::from_raw_parts(i, G::FIRST).
+    /// The None positions are freed slots, linked in ascending order.
+    ///
+    /// ```rust
+    /// use gen_arena::*;
+    ///
+    /// let arena = Arena::<_>::from_sparse_vec(vec![None, Some("a"), None]);
+    /// assert_eq!(arena[ArenaIdx::from_raw_parts(1, 0)], "a");
+    /// assert_eq!(arena.free_indices().collect::<Vec<_>>(), [0, 2]);
+    ///
+    /// assert_eq!(arena.into_sparse_vec(), [None, Some("a"), None]);
+    /// ```
+    ///
+    pub fn from_sparse_vec(vec: Vec<Option<T>>) -> Self{
+        let cells = vec.into_iter().map(|val| match val{
+            Some(val) => RawCell::Allocated{val, generation: G::FIRST},
+            None => RawCell::Freed{next: None, generation: G::FIRST},
+        });
+        let mut arena = Self::default();
+        *arena.raw_mut() = RawArena::from_cells(cells.collect());
+        arena
+    }
+
+    ///
+    /// Returns the values at the positions of their slots and None for freed slots, dropping the generations.
+    ///
+    pub fn into_sparse_vec(mut self) -> Vec<Option<T>>{
+        #[cfg(feature = "leak-detect")]
+        self.leaks.reset();
+        std::mem::take(&mut self.raw.cells).into_iter().map(|cell| match cell{
+            RawCell::Allocated{val, ..} => Some(val),
+            RawCell::Freed{..} => None,
+        }).collect()
+    }
+
+    ///
+    /// Returns a new arena with clones of the elements of the keys at the same slots and generations,
+    /// so the keys resolve to the same values in both, and the keys that are not valid.
+    /// All other slots are freed, and inserting into the new arena never hands out a key of this one.
+    ///
+    /// ```rust
+    /// use gen_arena::*;
+    ///
+    /// let mut arena = Arena::new();
+    /// let a = arena.insert("a");
+    /// let b = arena.insert("b");
+    /// let c = arena.insert("c");
+    /// arena.remove(c);
+    ///
+    /// let (preview, skipped) = arena.clone_subset([b, c]);
+    /// assert_eq!(preview.get(b), Some(&"b"));
+    /// assert_eq!(preview.get(a), None);
+    /// assert_eq!(skipped, [c]);
+    /// ```
+    ///
+    pub fn clone_subset(&self, keys: impl IntoIterator<Item = ArenaIdx<T, G, Tag>>) -> (Self, Vec<ArenaIdx<T, G, Tag>>)
+    where
+        T: Clone,
+    {
+        let mut selected = vec![false; self.slots()];
+        let mut skipped = Vec::new();
+        for key in keys{
+            match self.get(key){
+                Some(_) => selected[key.index()] = true,
+                None => skipped.push(key),
+            }
         }
+        let cells = self.raw.cells.iter().zip(selected).map(|(cell, selected)| match cell{
+            RawCell::Allocated{val, generation} if selected => RawCell::Allocated{val: val.clone(), generation: *generation},
+            // Like a removal, so the keys of the elements left out stay stale.
+            RawCell::Allocated{generation, ..} => RawCell::Freed{next: None, generation: generation.next()},
+            RawCell::Freed{generation, ..} => RawCell::Freed{next: None, generation: *generation},
+        });
+        let mut arena = Self::default();
+        *arena.raw_mut() = RawArena::from_cells(cells.collect());
+        (arena, skipped)
     }
 
     #[inline]
     pub fn reserve(&mut self, additional: usize){
-        self.cells.reserve(additional)
+        self.raw.cells.reserve(additional)
     }
 
     #[inline]
     pub fn capacity(&self) -> usize{
-        self.cells.capacity()
+        self.raw.cells.capacity()
     }
 
     #[inline]
     pub fn num(&self) -> usize{
-        self.num
+        self.raw.num
+    }
+}
+
+impl<T, G: GenCounter, Tag> Default for Arena<T, G, Tag>{
+    ///
+    /// Creates an empty Arena with any generation type.
+    ///
+    fn default() -> Self{
+        Self{
+            raw: RawArena::new(),
+            recycler: RecycleQueue::default(),
+            #[cfg(feature = "leak-detect")]
+            leaks: LeakTracker::default(),
+            #[cfg(feature = "metrics")]
+            metrics: ArenaMetrics::default(),
+            #[cfg(feature = "labels")]
+            labels: Labels::new(),
+            #[cfg(feature = "removal-watch")]
+            watchers: RemovalWatchers::new(),
+            _tag: PhantomData,
+        }
     }
 }
 
-impl<T> Index<ArenaIdx<T>> for Arena<T>{
+///
+/// Creates an arena keeping the positions of the values, see Arena::from_sparse_vec.
+///
+impl<T, G: GenCounter, Tag> From<Vec<Option<T>>> for Arena<T, G, Tag>{
+    fn from(vec: Vec<Option<T>>) -> Self {
+        Self::from_sparse_vec(vec)
+    }
+}
+
+///
+/// Returns the values at the positions of their slots, see Arena::into_sparse_vec.
+///
+impl<T, G: GenCounter, Tag> From<Arena<T, G, Tag>> for Vec<Option<T>>{
+    fn from(arena: Arena<T, G, Tag>) -> Self {
+        arena.into_sparse_vec()
+    }
+}
+
+// Reports the elements that were not removed, see LeakReport.
+#[cfg(feature = "leak-detect")]
+impl<T, G, Tag> Drop for Arena<T, G, Tag>{
+    fn drop(&mut self){
+        let live = self.raw.cells.iter().enumerate()
+            .filter(|(_, cell)| matches!(cell, RawCell::Allocated{..}))
+            .map(|(i, _)| i);
+        self.leaks.report_dropped(live);
+    }
+}
+
+///
+/// Serialized as a struct of the elements with their slots and generations, and the freed slots
+/// with their generations in the order of the free list, followed by the retired and reserved slots.
+/// Reservations are not kept, reserved slots are deserialized as freed ones.
+///
+#[cfg(feature = "serde")]
+impl<T: serde::Serialize, G: GenCounter + serde::Serialize, Tag> serde::Serialize for Arena<T, G, Tag>{
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::{SerializeSeq, SerializeStruct};
+
+        // Sequences are written with their length, which formats like postcard require.
+        struct Values<'a, T, G, Tag>(&'a Arena<T, G, Tag>);
+        impl<T: serde::Serialize, G: GenCounter + serde::Serialize, Tag> serde::Serialize for Values<'_, T, G, Tag>{
+            fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                let mut seq = serializer.serialize_seq(Some(self.0.num()))?;
+                for (key, val) in self.0.iter(){
+                    seq.serialize_element(&(key.index(), key.gen(), val))?;
+                }
+                seq.end()
+            }
+        }
+
+        struct Freed<'a, T, G, Tag>(&'a Arena<T, G, Tag>);
+        impl<T, G: GenCounter + serde::Serialize, Tag> serde::Serialize for Freed<'_, T, G, Tag>{
+            fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                let retired = self.0.slot_states().enumerate()
+                    .filter(|(_, state)| matches!(state, SlotState::Retired{..} | SlotState::Reserved{..}))
+                    .map(|(i, _)| i);
+                let mut seq = serializer.serialize_seq(Some(self.0.slots() - self.0.num()))?;
+                for i in self.0.free_indices().chain(retired){
+                    seq.serialize_element(&(i, self.0.gen(i)))?;
+                }
+                seq.end()
+            }
+        }
+
+        let mut state = serializer.serialize_struct("Arena", 2)?;
+        state.serialize_field("values", &Values(self))?;
+        state.serialize_field("freed", &Freed(self))?;
+        state.end()
+    }
+}
+
+///
+/// Rejects slots that are given twice and indices that leave more slots missing than are given.
+/// Slots that are not given are freed with the first generation and put at the end of the free list,
+/// in ascending order.
+///
+#[cfg(feature = "serde")]
+impl<'de, T: serde::Deserialize<'de>, G: GenCounter + serde::Deserialize<'de>, Tag> serde::Deserialize<'de> for Arena<T, G, Tag>{
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        use serde::de::Error;
+
+        #[derive(serde::Deserialize)]
+        #[serde(rename = "Arena")]
+        struct Data<T, G>{
+            values: Vec<(usize, G, T)>,
+            freed: Vec<(usize, G)>,
+        }
+
+        let data = Data::<T, G>::deserialize(deserializer)?;
+        // Every slot below the largest index is allocated, so the missing ones are bounded by the given ones.
+        let limit = (data.values.len() + data.freed.len()).saturating_mul(2);
+        let indices = data.values.iter().map(|(index, _, _)| *index)
+            .chain(data.freed.iter().map(|(index, _)| *index));
+        let mut slots = 0;
+        for index in indices{
+            if index >= limit{
+                return Err(D::Error::custom(format_args!("the index {} leaves more slots missing than are given", index)));
+            }
+            slots = slots.max(index + 1);
+        }
+        let mut cells: Vec<Option<RawCell<T, G>>> = (0..slots).map(|_| None).collect();
+        let mut assign = |index: usize, cell|{
+            match cells[index].replace(cell){
+                Some(_) => Err(D::Error::custom(format!("the slot {} is given twice", index))),
+                None => Ok(()),
+            }
+        };
+        let mut order = Vec::new();
+        for (index, generation, val) in data.values{
+            assign(index, RawCell::Allocated{val, generation})?;
+        }
+        for (index, generation) in data.freed{
+            assign(index, RawCell::Freed{next: None, generation})?;
+            order.push(index);
+        }
+        for (index, cell) in cells.iter().enumerate(){
+            if cell.is_none(){
+                order.push(index);
+            }
+        }
+
+        let mut arena = Self::default();
+        arena.raw.cells = cells.into_iter()
+            .map(|cell| cell.unwrap_or(RawCell::Freed{next: None, generation: G::FIRST}))
+            .collect();
+        arena.raw.relink();
+        // Link the free list in the given order instead of the ascending one.
+        let order: Vec<_> = order.into_iter().filter(|&i| arena.gen(i) != G::RETIRED).collect();
+        for link in order.windows(2){
+            if let RawCell::Freed{next, ..} = &mut arena.raw.cells[link[0]]{
+                *next = Some(link[1]);
+            }
+        }
+        if let Some(&last) = order.last(){
+            if let RawCell::Freed{next, ..} = &mut arena.raw.cells[last]{
+                *next = None;
+            }
+        }
+        arena.raw.freed = order.first().copied();
+        Ok(arena)
+    }
+}
+
+impl<T, G: GenCounter, Tag> Index<ArenaIdx<T, G, Tag>> for Arena<T, G, Tag>{
     type Output = T;
 
-    fn index(&self, index: ArenaIdx<T>) -> &Self::Output {
-        self.get(index).expect("There is no element at this index with that generation.")
+    fn index(&self, index: ArenaIdx<T, G, Tag>) -> &Self::Output {
+        match self.get(index){
+            Some(val) => val,
+            None => self.missing(index),
+        }
     }
 }
 
-impl<T> IndexMut<ArenaIdx<T>> for Arena<T>{
-    fn index_mut(&mut self, index: ArenaIdx<T>) -> &mut Self::Output {
-        self.get_mut(index).expect("There is no element at this index with that generation.")
+impl<T, G: GenCounter, Tag> IndexMut<ArenaIdx<T, G, Tag>> for Arena<T, G, Tag>{
+    fn index_mut(&mut self, index: ArenaIdx<T, G, Tag>) -> &mut Self::Output {
+        if self.get(index).is_none(){
+            self.missing(index);
+        }
+        self.get_mut(index).expect("The index was just checked.")
     }
 }
 
-pub struct Iter<'i, T: 'i>{
-    pub(crate) iter: std::iter::Enumerate<std::slice::Iter<'i, ArenaCell<T>>>,
+impl<T, G: GenCounter, Tag> Arena<T, G, Tag>{
+    // The panic of Index, naming the element that replaced the missing one if it is labelled.
+    #[cold]
+    #[track_caller]
+    fn missing(&self, _index: ArenaIdx<T, G, Tag>) -> !{
+        #[cfg(feature = "labels")]
+        if let Some(label) = self.slot_label(_index.index()){
+            panic!("There is no element at this index with that generation, the slot holds \"{}\".", label);
+        }
+        panic!("There is no element at this index with that generation.")
+    }
 }
 
-impl<'i, T> Iterator for Iter<'i, T>{
-    type Item = (ArenaIdx<T>, &'i T);
+pub struct Iter<'i, T: 'i, G = usize, Tag = ()>{
+    pub(crate) iter: std::iter::Enumerate<std::slice::Iter<'i, RawCell<T, G>>>,
+    pub(crate) _tag: PhantomData<fn() -> Tag>,
+}
+
+impl<'i, T, G: GenCounter, Tag> Iterator for Iter<'i, T, G, Tag>{
+    type Item = (ArenaIdx<T, G, Tag>, &'i T);
 
     fn next(&mut self) -> Option<Self::Item> {
         loop{
             match self.iter.next(){
-                Some((_, ArenaCell::Freed{..})) => continue,
-                Some((i, ArenaCell::Allocated{val, generation})) => {
-                    return Some((ArenaIdx::new(i, *generation), val));
+                Some((_, RawCell::Freed{..})) => continue,
+                Some((i, RawCell::Allocated{val, generation})) => {
+                    return Some((ArenaIdx::from_parts(i, *generation), val));
                 }
                 None => {return None;},
             }
@@ -646,11 +1340,11 @@ impl<'i, T> Iterator for Iter<'i, T>{
     }
 }
 
-pub struct Values<'i, T: 'i>{
-    pub (crate) iter: Iter<'i, T>,
+pub struct Values<'i, T: 'i, G = usize, Tag = ()>{
+    pub (crate) iter: Iter<'i, T, G, Tag>,
 }
 
-impl<'i, T> Iterator for Values<'i, T>{
+impl<'i, T, G: GenCounter, Tag> Iterator for Values<'i, T, G, Tag>{
     type Item = &'i T;
 
     fn next(&mut self) -> Option<Self::Item> {
@@ -658,19 +1352,20 @@ impl<'i, T> Iterator for Values<'i, T>{
     }
 }
 
-pub struct IterMut<'i, T: 'i>{
-    pub(crate) iter: std::iter::Enumerate<std::slice::IterMut<'i, ArenaCell<T>>>,
+pub struct IterMut<'i, T: 'i, G = usize, Tag = ()>{
+    pub(crate) iter: std::iter::Enumerate<std::slice::IterMut<'i, RawCell<T, G>>>,
+    pub(crate) _tag: PhantomData<fn() -> Tag>,
 }
 
-impl<'i, T> Iterator for IterMut<'i, T>{
-    type Item = (ArenaIdx<T>, &'i mut T);
+impl<'i, T, G: GenCounter, Tag> Iterator for IterMut<'i, T, G, Tag>{
+    type Item = (ArenaIdx<T, G, Tag>, &'i mut T);
 
     fn next(&mut self) -> Option<Self::Item> {
         loop{
             match self.iter.next(){
-                Some((_, ArenaCell::Freed{..})) => continue,
-                Some((i, ArenaCell::Allocated{val, generation})) => {
-                    return Some((ArenaIdx::new(i, *generation), val));
+                Some((_, RawCell::Freed{..})) => continue,
+                Some((i, RawCell::Allocated{val, generation})) => {
+                    return Some((ArenaIdx::from_parts(i, *generation), val));
                 }
                 None => {return None;},
             }
@@ -678,11 +1373,11 @@ impl<'i, T> Iterator for IterMut<'i, T>{
     }
 }
 
-pub struct ValuesMut<'i, T: 'i>{
-    pub(crate) iter: IterMut<'i, T>,
+pub struct ValuesMut<'i, T: 'i, G = usize, Tag = ()>{
+    pub(crate) iter: IterMut<'i, T, G, Tag>,
 }
 
-impl<'i, T> Iterator for ValuesMut<'i, T>{
+impl<'i, T, G: GenCounter, Tag> Iterator for ValuesMut<'i, T, G, Tag>{
     type Item = &'i mut T;
 
     fn next(&mut self) -> Option<Self::Item> {
@@ -690,12 +1385,12 @@ impl<'i, T> Iterator for ValuesMut<'i, T>{
     }
 }
 
-pub struct Keys<'i, T: 'i>{
-    pub(crate) iter: Iter<'i, T>,
+pub struct Keys<'i, T: 'i, G = usize, Tag = ()>{
+    pub(crate) iter: Iter<'i, T, G, Tag>,
 }
 
-impl<'i, T> Iterator for Keys<'i, T>{
-    type Item = ArenaIdx<T>;
+impl<'i, T, G: GenCounter, Tag> Iterator for Keys<'i, T, G, Tag>{
+    type Item = ArenaIdx<T, G, Tag>;
 
     fn next(&mut self) -> Option<Self::Item> {
         self.iter.next().map(|(i, _)|{i})
@@ -728,5 +1423,406 @@ mod test{
             println!("{}, {}", index.index(), val);
         });
     }
+
+    #[test]
+    fn test_dangling(){
+        use crate::*;
+
+        let d = ArenaIdx::dangling();
+        assert!(d.is_dangling());
+        assert!(!ArenaIdx::<u8>::from_raw_parts(usize::MAX, 0).is_dangling());
+
+        let mut arena = Arena::new();
+        let i0 = arena.insert(0u8);
+        assert!(!i0.is_dangling());
+        assert_eq!(arena.get(d), None);
+        assert_eq!(arena.get_mut(d), None);
+        assert_eq!(arena.get_any(d.index()), None);
+        assert_eq!(arena.get_any_mut(d.index()), None);
+        assert_eq!(arena.idx_at(d.index()), None);
+        assert_eq!(arena.getn([d, i0]), [None, Some(&0)]);
+        assert_eq!(arena.get2_mut((d, d)), (None, None));
+        assert_eq!(arena.get2_mut((d, i0)), (None, Some(&mut 0)));
+        assert_eq!(arena.remove(d), None);
+        assert_eq!(arena.num(), 1);
+
+        let mut dense = DenseArena::new();
+        let _ = dense.insert(0u8);
+        assert_eq!(dense.get(d), None);
+        assert_eq!(dense.get_mut(d), None);
+        assert_eq!(dense.remove(d), None);
+
+        let mut chunk = ChunkArena::new();
+        let _ = chunk.insert(0u8);
+        assert_eq!(chunk.get(d), None);
+        assert_eq!(chunk.get_mut(d), None);
+        assert_eq!(chunk.remove(d), None);
+
+        let mut atomic = AtomicArena::new();
+        let _ = atomic.insert(0u8);
+        assert_eq!(atomic.get(d), None);
+        assert_eq!(atomic.get_mut(d), None);
+        assert_eq!(atomic.remove(d), None);
+
+        let mut small = SmallArena::<u8, 2>::new();
+        let _ = small.insert(0);
+        assert_eq!(small.get(d), None);
+        assert_eq!(small.get_mut(d), None);
+        assert_eq!(small.remove(d), None);
+
+        let (persistent, _) = PersistentArena::new().insert(0u8);
+        assert_eq!(persistent.get(d), None);
+        assert!(persistent.remove(d).is_none());
+
+        let mut sparse = SparseSecondaryMap::new();
+        sparse.insert(i0, 0u8);
+        assert_eq!(sparse.get(d), None);
+        assert_eq!(sparse.get_mut(d), None);
+        assert_eq!(sparse.remove(d), None);
+
+        let mut set = ArenaSet::new();
+        set.insert(i0);
+        assert!(!set.contains(d));
+        assert!(!set.remove(d));
+
+        assert_eq!(WeakIdx::new(d).upgrade(&arena), None);
+    }
+
+    #[test]
+    fn test_generation_width(){
+        use std::mem::size_of;
+        use std::num::Wrapping;
+
+        // The free list link dominates the cell, so narrow generations never grow it.
+        assert!(size_of::<RawCell<u32, u8>>() <= size_of::<RawCell<u32>>());
+        assert!(size_of::<ArenaIdx<u32, u16>>() <= size_of::<ArenaIdx<u32>>());
+        assert_eq!(size_of::<ArenaIdx<u32, u64>>(), size_of::<usize>() + 8);
+
+        // A u8 slot is retired after its last generation and skipped by clear.
+        let mut arena = Arena::<u32, u8>::default();
+        let mut i0 = arena.insert(0);
+        let i1 = arena.insert(1);
+        for _ in 0..u8::MAX - 1{
+            assert_eq!(arena.remove(i0), Some(0));
+            i0 = arena.insert(0);
+            assert_eq!(i0.index(), 0);
+        }
+        assert_eq!(i0.gen(), 254);
+        assert_eq!(arena.remove(i0), Some(0));
+        assert_eq!(arena.gen(0), u8::MAX);
+        let i2 = arena.insert(2);
+        assert_eq!(i2.index(), 2);
+        arena.clear();
+        assert_eq!(arena.get(i1), None);
+        let keys: Vec<_> = (0..3).map(|i| arena.insert(i)).collect();
+        assert!(keys.iter().all(|k| k.index() != 0));
+        assert_eq!(arena.get(i0), None);
+
+        // A Wrapping<u8> slot wraps around and the oldest key refers to the new element.
+        let mut arena = Arena::<u32, Wrapping<u8>>::default();
+        let first = arena.insert(0);
+        let mut i0 = first;
+        for i in 1..=u8::MAX as u32{
+            arena.remove(i0);
+            i0 = arena.insert(i);
+            assert_eq!(i0.index(), 0);
+        }
+        assert_eq!(i0.gen(), Wrapping(0));
+        assert_eq!(i0, first);
+        assert_eq!(arena[first], u8::MAX as u32);
+    }
+
+    // These tests compare pointers into and out of the arena and are meant to be run under Miri as well:
+    // cargo +nightly miri test key_of
+    #[test]
+    fn test_key_of(){
+        let mut arena = Arena::new();
+        let keys: Vec<_> = (0..8).map(|i| arena.insert([i as u16; 3])).collect();
+        arena.remove(keys[2]);
+
+        for (key, val) in arena.iter(){
+            assert_eq!(arena.key_of(val), Some(key));
+        }
+        let key = keys[5];
+        let val: *const _ = arena.get_mut(key).unwrap();
+        // SAFETY: The arena is not mutated while the pointer is used.
+        assert_eq!(arena.key_of(unsafe{&*val}), Some(key));
+
+        // Pointers outside of the elements, into other allocations or at moved out values.
+        let outside = [5u16; 3];
+        assert_eq!(arena.key_of(&outside), None);
+        assert_eq!(arena.key_of(&arena.clone()[keys[0]]), None);
+        let empty = Arena::<[u16; 3]>::with_capacity(4);
+        assert_eq!(empty.key_of(&arena[keys[0]]), None);
+
+        let stale = arena.remove(keys[7]).unwrap();
+        assert_eq!(arena.key_of(&stale), None);
+        let reused = arena.insert([9; 3]);
+        assert_eq!(arena.key_of(&arena[reused]), Some(reused));
+
+        // Zero sized values live in distinct cells as well.
+        let mut units = Arena::new();
+        let a = units.insert(());
+        let b = units.insert(());
+        assert_eq!(units.key_of(&units[b]), Some(b));
+        assert_eq!(units.key_of(&units[a]), Some(a));
+        assert_eq!(units.key_of(&()), None);
+    }
+
+    #[test]
+    fn test_bump_generation(){
+        let mut arena = Arena::new();
+        let keys: Vec<_> = (0..4).map(|i| arena.insert(Box::new(i))).collect();
+        let addr: *const i32 = &*arena[keys[2]];
+
+        let bumped = arena.bump_generation(keys[2]).unwrap();
+        assert_eq!(bumped.index(), keys[2].index());
+        assert_eq!(bumped.gen(), keys[2].gen() + 1);
+        assert_eq!(arena.get(keys[2]), None);
+        assert_eq!(arena.get_mut(keys[2]), None);
+        assert_eq!(arena.remove(keys[2]), None);
+        assert_eq!(arena.num(), 4);
+        // The value was neither moved nor reallocated.
+        assert!(std::ptr::eq(&*arena[bumped], addr));
+        assert_eq!(arena.bump_generation(keys[2]), None);
+
+        // Removing with the new key frees the slot with the next generation.
+        let bumped_again = arena.bump_generation(bumped).unwrap();
+        assert_eq!(arena.remove(bumped_again).as_deref(), Some(&2));
+        let reused = arena.insert(Box::new(5));
+        assert_eq!(reused.index(), keys[2].index());
+        assert_eq!(reused.gen(), 3);
+        assert_eq!(arena.get(bumped), None);
+
+        // A narrow generation that would be exhausted is not bumped.
+        let mut narrow = Arena::<i32, u8>::default();
+        let mut key = narrow.insert(0);
+        for _ in 0..253{
+            key = narrow.bump_generation(key).unwrap();
+        }
+        assert_eq!(key.gen(), 253);
+        key = narrow.bump_generation(key).unwrap();
+        assert_eq!(narrow.bump_generation(key), None);
+        assert_eq!(narrow[key], 0);
+    }
+
+    #[test]
+    fn test_get2_mut_same_slot(){
+        let mut arena = Arena::new();
+        let stale = arena.insert(0);
+        arena.remove(stale);
+        let live = arena.insert(1);
+        assert_eq!(stale.index(), live.index());
+
+        assert_eq!(arena.get2_mut((stale, stale)), (None, None));
+        assert_eq!(arena.get2_mut((live, stale)), (Some(&mut 1), None));
+        assert_eq!(arena.get2_mut((stale, live)), (None, Some(&mut 1)));
+
+        // Two stale copies of a freed slot.
+        arena.remove(live);
+        assert_eq!(arena.get2_mut((live, live)), (None, None));
+        assert_eq!(arena.get2_mut((stale, live)), (None, None));
+
+        let foreign = ArenaIdx::from_raw_parts(4, 0);
+        assert_eq!(arena.get2_mut((foreign, foreign)), (None, None));
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_get2_mut_same_live(){
+        let mut arena = Arena::new();
+        let i0 = arena.insert(0);
+        let _ = arena.get2_mut((i0, i0));
+    }
+
+    #[test]
+    fn test_getn_mut_stale_duplicate(){
+        let mut arena = Arena::new();
+        let stale = arena.insert(0);
+        arena.remove(stale);
+        let live = arena.insert(1);
+        let other = arena.insert(2);
+        let foreign = ArenaIdx::from_raw_parts(4, 0);
+
+        assert_eq!(arena.getn_mut([other, live]), Some([&mut 2, &mut 1]));
+        assert_eq!(arena.getn_mut([stale, stale]), None);
+        assert_eq!(arena.getn_mut([live, stale, other]), None);
+        assert_eq!(arena.getn_mut([foreign, live]), None);
+        assert_eq!(arena.getn_mut::<0>([]), Some([]));
+
+        arena.remove(live);
+        assert_eq!(arena.getn_mut([live, other, live]), None);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_getn_mut_same_live(){
+        let mut arena = Arena::new();
+        let i0 = arena.insert(0);
+        let i1 = arena.insert(1);
+        let _ = arena.getn_mut([i0, i1, i0]);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde(){
+        let mut arena = Arena::<String, u8>::default();
+        let keys: Vec<_> = (0..6).map(|i| arena.insert(i.to_string())).collect();
+        for i in [4, 1, 5]{
+            arena.remove(keys[i]);
+        }
+        // Retire slot 0.
+        let mut retired = keys[0];
+        for _ in 0..254{
+            arena.remove(retired);
+            retired = arena.insert(String::from("retired"));
+        }
+        arena.remove(retired);
+
+        // The free list keeps its order through a round trip.
+        let json = serde_json::to_string(&arena).unwrap();
+        assert_eq!(json, r#"{"values":[[2,0,"2"],[3,0,"3"]],"freed":[[5,1],[1,1],[4,1],[0,255]]}"#);
+        let loaded: Arena<String, u8> = serde_json::from_str(&json).unwrap();
+        assert_eq!(loaded.debug_dump(), arena.debug_dump());
+        let loaded: Arena<String, u8> = postcard::from_bytes(&postcard::to_allocvec(&arena).unwrap()).unwrap();
+        assert_eq!(loaded.debug_dump(), arena.debug_dump());
+
+        // Missing slots are appended to the free list, duplicates are rejected.
+        let sparse: Arena<u32> = serde_json::from_str(r#"{"values":[[3,2,7]],"freed":[[2,1]]}"#).unwrap();
+        assert_eq!(sparse.free_indices().collect::<Vec<_>>(), [2, 0, 1]);
+        assert_eq!(sparse[ArenaIdx::from_raw_parts(3, 2)], 7);
+        let err = serde_json::from_str::<Arena<u32>>(r#"{"values":[[1,0,7]],"freed":[[1,1]]}"#).unwrap_err();
+        assert!(err.to_string().contains("twice"));
+
+        // Huge indices are rejected before any slots are allocated.
+        let json = format!(r#"{{"values":[[{},0,7]],"freed":[]}}"#, usize::MAX);
+        let err = serde_json::from_str::<Arena<u32>>(&json).unwrap_err();
+        assert!(err.to_string().contains("missing"));
+        let err = serde_json::from_str::<Arena<u32>>(r#"{"values":[[0,0,7]],"freed":[[1000000000,1]]}"#).unwrap_err();
+        assert!(err.to_string().contains("missing"));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_canonical_replay(){
+        // A live session with a scrambled free list.
+        let mut session = Arena::new();
+        let keys: Vec<_> = (0..8).map(|i| session.insert(i)).collect();
+        for i in [6, 2, 7, 0, 4]{
+            session.remove(keys[i]);
+        }
+        let snapshot = serde_json::to_string(&session).unwrap();
+
+        // One peer loads the snapshot as it is, the other through an SArena.
+        let mut exact: Arena<i32> = serde_json::from_str(&snapshot).unwrap();
+        let fixed: crate::SArena<i32, 8> = serde_json::from_str(&snapshot).unwrap();
+        let mut rebuilt = Arena::from(fixed);
+        assert_eq!(exact.free_indices().collect::<Vec<_>>(), session.free_indices().collect::<Vec<_>>());
+        assert_ne!(exact.free_indices().collect::<Vec<_>>(), rebuilt.free_indices().collect::<Vec<_>>());
+
+        // Canonicalized, all of them mint the same keys.
+        for arena in [&mut session, &mut exact, &mut rebuilt]{
+            arena.canonicalize_free_list();
+        }
+        let replay = |arena: &mut Arena<i32>|{
+            let a = arena.insert(10);
+            let b = arena.insert(11);
+            arena.remove(a);
+            [a, b, arena.insert(12), arena.insert(13), arena.insert(14)]
+        };
+        let expected = replay(&mut session);
+        assert_eq!(expected.map(|key| key.index()), [0, 2, 0, 4, 6]);
+        assert_eq!(replay(&mut exact), expected);
+        assert_eq!(replay(&mut rebuilt), expected);
+    }
+
+    #[test]
+    fn test_sparse_vec(){
+        let sparse = vec![None, Some(1), None, None, Some(4), Some(5), None];
+        let mut arena = Arena::<_>::from_sparse_vec(sparse.clone());
+        assert_eq!(arena.num(), 3);
+        for (i, val) in sparse.iter().enumerate(){
+            assert_eq!(arena.get(ArenaIdx::from_raw_parts(i, 0)), val.as_ref());
+        }
+        assert_eq!(arena.free_indices().collect::<Vec<_>>(), [0, 2, 3, 6]);
+        assert_eq!(Vec::from(arena.clone()), sparse);
+
+        // The holes are filled first, the generations are dropped.
+        let key = arena.insert(0);
+        assert_eq!(key, ArenaIdx::from_raw_parts(0, 0));
+        arena.remove(ArenaIdx::from_raw_parts(4, 0));
+        assert_eq!(arena.into_sparse_vec(), [Some(0), Some(1), None, None, None, Some(5), None]);
+
+        for sparse in [vec![], vec![None, None], vec![Some(0)]]{
+            assert_eq!(Arena::<i32, u8>::from(sparse.clone()).into_sparse_vec(), sparse);
+        }
+    }
+
+    #[test]
+    fn test_clone_subset(){
+        let mut arena = Arena::<String, u8>::default();
+        let keys: Vec<_> = (0..6).map(|i| arena.insert(i.to_string())).collect();
+        arena.remove(keys[4]);
+        let reused = arena.insert(String::from("reused"));
+
+        let stale = keys[4];
+        let out_of_range = ArenaIdx::from_raw_parts(10, 0);
+        let (subset, skipped) = arena.clone_subset([keys[1], keys[3], keys[1], stale, reused, out_of_range]);
+        assert_eq!(skipped, [stale, out_of_range]);
+        assert_eq!(subset.num(), 3);
+        assert_eq!(subset.slots(), arena.slots());
+        for key in [keys[1], keys[3], reused]{
+            assert_eq!(subset.get(key), arena.get(key));
+        }
+
+        // The other slots are vacant and new elements get fresh keys there.
+        for i in [0, 2, 5]{
+            assert_eq!(subset.get(keys[i]), None);
+            assert_eq!(subset.idx_at(i), None);
+        }
+        let mut subset = subset;
+        let fresh: Vec<_> = (0..3).map(|_| subset.insert(String::new())).collect();
+        assert_eq!(fresh.iter().map(|key| key.index()).collect::<Vec<_>>(), [0, 2, 5]);
+        assert!(fresh.iter().all(|key| arena.get(*key).is_none()));
+        assert_eq!(subset.insert(String::new()).index(), 6);
+    }
+
+    #[test]
+    fn test_get_latest(){
+        let mut arena = Arena::new();
+        let a = arena.insert("a");
+        let b = arena.insert("b");
+        assert_eq!(arena.get_latest(a), Some((a, &"a")));
+
+        arena.remove(a);
+        assert_eq!(arena.get_latest(a), None);
+        let c = arena.insert("c");
+        assert_eq!(arena.get_latest(a), Some((c, &"c")));
+        assert_eq!(arena.get_latest(b), Some((b, &"b")));
+
+        // The key of the occupant is fresh and checked again.
+        arena.remove(c);
+        assert_eq!(arena.get_latest(a), None);
+        assert_eq!(arena.get_latest(ArenaIdx::from_raw_parts(7, 0)), None);
+    }
+
+    #[test]
+    fn test_corrupt_free_list(){
+        let mut arena = Arena::new();
+        let keys: Vec<_> = (0..4).map(|i| arena.insert(i)).collect();
+        arena.remove(keys[1]);
+        arena.remove(keys[3]);
+
+        // The list links to an element, it is rebuilt from the slots before inserting.
+        arena.raw_mut().freed = Some(0);
+        let a = arena.try_insert(4).unwrap();
+        let b = arena.try_insert(5).unwrap();
+        assert_eq!((a.index(), b.index()), (1, 3));
+        assert_eq!(arena.values().copied().collect::<Vec<_>>(), [0, 4, 2, 5]);
+
+        arena.raw_mut().freed = Some(10);
+        assert_eq!(arena.try_insert(6).map(|key| key.index()), Ok(4));
+        assert_eq!(arena.num(), 5);
+    }
 }
 
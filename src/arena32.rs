@@ -0,0 +1,829 @@
+use std::{marker::PhantomData, ops::{Index, IndexMut}};
+
+///
+/// Cell of an Arena32.
+///
+#[derive(Debug)]
+pub(crate) enum Arena32Cell<T>{
+    Allocated{val: T, generation: u32},
+    Freed{next: Option<u32>, generation: u32},
+}
+
+///
+/// A compact index referring to an index and epoch in an Arena32.
+/// Both the index and the generation are stored as u32 so the handle is 8 bytes on every target.
+///
+#[derive(Debug, PartialEq, Eq)]
+pub struct Idx32<T>{
+    index: u32,
+    generation: u32,
+    _ty: PhantomData<T>,
+}
+
+impl<T> Idx32<T>{
+    #[inline]
+    pub(crate) const fn from_parts(index: u32, generation: u32) -> Self{
+        Self{
+            index,
+            generation,
+            _ty: PhantomData,
+        }
+    }
+
+    ///
+    /// Fabricates a key from an index and a generation, as returned by into_raw_parts.
+    ///
+    /// Like ArenaIdx::from_raw_parts, this is meant for serialization and FFI round trips only.
+    ///
+    #[inline]
+    #[must_use]
+    pub const fn from_raw_parts(index: u32, generation: u32) -> Self{
+        Self::from_parts(index, generation)
+    }
+
+    ///
+    /// Fabricates a key from an index and a generation.
+    ///
+    #[deprecated(note = "Use Idx32::from_raw_parts, which makes fabricating keys explicit.")]
+    #[inline]
+    #[must_use]
+    pub fn new(index: u32, generation: u32) -> Self{
+        Self::from_raw_parts(index, generation)
+    }
+
+    ///
+    /// Returns the index and the generation of the key.
+    ///
+    #[inline]
+    #[must_use]
+    pub const fn into_raw_parts(self) -> (u32, u32){
+        (self.index, self.generation)
+    }
+
+    #[inline]
+    pub fn index(&self) -> u32{
+        self.index
+    }
+
+    #[inline]
+    pub fn gen(&self) -> u32{
+        self.generation
+    }
+
+    ///
+    /// Packs the index into a single u64.
+    /// The generation is stored in the upper and the index in the lower 32 bits.
+    ///
+    /// ```rust
+    /// use gen_arena::*;
+    ///
+    /// let idx = Idx32::<i32>::from_raw_parts(17, 3);
+    ///
+    /// assert_eq!(idx.to_bits(), (3 << 32) | 17);
+    /// assert_eq!(Idx32::<i32>::from_bits(idx.to_bits()), idx);
+    /// ```
+    ///
+    #[inline]
+    pub fn to_bits(self) -> u64{
+        ((self.generation as u64) << 32) | self.index as u64
+    }
+
+    ///
+    /// Unpacks an index previously packed with to_bits.
+    ///
+    #[inline]
+    pub fn from_bits(bits: u64) -> Self{
+        Self::from_parts(bits as u32, (bits >> 32) as u32)
+    }
+}
+
+impl<T> Clone for Idx32<T>{
+    #[inline]
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for Idx32<T>{}
+
+///
+/// A Generational Arena with u32 indices and generations.
+/// It mirrors Arena but halves the size of its handles on 64 bit targets.
+/// Generations wrap around on overflow.
+///
+/// # Example
+///
+///```rust
+///
+/// use gen_arena::*;
+///
+/// let mut arena = Arena32::new();
+///
+/// let i0 = arena.insert(0);
+/// let i1 = arena.insert(1);
+///
+/// assert_eq!(*arena.get(i0).unwrap(), 0);
+/// assert_eq!(*arena.get(i1).unwrap(), 1);
+///
+/// arena.remove(i1);
+///
+/// assert_eq!(arena.get(i1), None);
+///
+/// let i2 = arena.insert(2);
+///
+/// assert_eq!(*arena.get(i2).unwrap(), 2);
+/// assert_eq!(arena.get(i1), None);
+///
+/// assert_eq!(std::mem::size_of::<Idx32<i32>>(), 8);
+///
+///```
+///
+#[derive(Debug)]
+pub struct Arena32<T>{
+    cells: Vec<Arena32Cell<T>>,
+    freed: Option<u32>,
+    num: usize,
+}
+
+impl<T> Arena32<T>{
+
+    ///
+    /// Creates an empty Arena32.
+    ///
+    ///```rust
+    /// use gen_arena::*;
+    ///
+    /// let arena = Arena32::<i32>::new();
+    ///```
+    ///
+    pub fn new() -> Self{
+        Self{
+            cells: Vec::new(),
+            freed: None,
+            num: 0,
+        }
+    }
+
+    ///
+    /// Creates an emty Arena32 with capacity.
+    ///
+    /// ```rust
+    ///
+    /// use gen_arena::*;
+    ///
+    /// let arena = Arena32::<i32>::with_capacity(10);
+    ///
+    /// assert_eq!(arena.capacity(), 10);
+    ///
+    /// ```
+    ///
+    pub fn with_capacity(cap: usize) -> Self{
+        Self{
+            cells: Vec::with_capacity(cap),
+            freed: None,
+            num: 0,
+        }
+    }
+
+    ///
+    /// Clears the arena and resets the list of Freed cells.
+    ///
+    /// ```rust
+    ///
+    /// use gen_arena::*;
+    ///
+    /// let mut arena = Arena32::new();
+    ///
+    /// let i1 = arena.insert(1);
+    /// let i2 = arena.insert(2);
+    ///
+    /// arena.clear();
+    ///
+    /// assert_eq!(arena.get(i1), None);
+    /// assert_eq!(arena.get(i2), None);
+    ///
+    /// ```
+    ///
+    pub fn clear(&mut self){
+        let len = self.cells.len();
+        for (i, cell) in self.cells.iter_mut().enumerate(){
+            match cell{
+                Arena32Cell::Allocated{val: _, generation} => {
+                    *cell = Arena32Cell::Freed{
+                        generation: generation.wrapping_add(1),
+                        next: if i < len-1 {Some(i as u32 + 1)} else{None},
+                    }
+                },
+                Arena32Cell::Freed{next: _, generation} => {
+                    *cell = Arena32Cell::Freed{
+                        generation: *generation,
+                        next: if i < len-1 {Some(i as u32 + 1)} else{None},
+                    }
+                }
+            }
+        }
+        self.freed = if len > 0 {Some(0)} else{None};
+        self.num = 0;
+    }
+
+    ///
+    /// Tries to insert into Arena32.
+    /// Returns val as Err if failed, in particular when all u32::MAX slots are in use.
+    ///
+    pub fn try_insert(&mut self, val: T) -> Result<Idx32<T>, T>{
+        match self.freed{
+            Some(i) => {
+                if let Arena32Cell::Freed{next, generation} = self.cells[i as usize]{
+                    self.freed = next;
+                    self.cells[i as usize] = Arena32Cell::Allocated{
+                        val,
+                        generation,
+                    };
+                    self.num += 1;
+                    Ok(Idx32::from_parts(i, generation))
+                }
+                else{
+                    Err(val)
+                }
+            }
+            None => {
+                // Index u32::MAX is never handed out so that the number of slots fits into a u32.
+                if self.cells.len() >= u32::MAX as usize{
+                    return Err(val);
+                }
+                self.cells.push(Arena32Cell::Allocated{
+                    generation: 0,
+                    val,
+                });
+                self.num += 1;
+                Ok(Idx32::from_parts(self.cells.len() as u32 - 1, 0))
+            }
+        }
+    }
+
+    ///
+    /// Inserts a new element into the Arena32.
+    /// Panics if the arena has run out of u32 indices.
+    ///
+    /// # Example:
+    ///
+    /// ```rust
+    /// use gen_arena::*;
+    ///
+    /// let mut arena = Arena32::new();
+    ///
+    /// let i1 = arena.insert(1);
+    /// let i2 = arena.insert(2);
+    ///
+    /// assert_eq!(*arena.get(i1).unwrap(), 1);
+    /// assert_eq!(*arena.get(i2).unwrap(), 2)
+    ///
+    /// ```
+    ///
+    #[must_use]
+    pub fn insert(&mut self, val: T) -> Idx32<T>{
+        match self.try_insert(val){
+            Ok(index) => index,
+            Err(_val) => panic!("Insertion not successfull."),
+        }
+    }
+
+    ///
+    /// Removes the cell from the arena and increaces its generation.
+    /// Returns the value if the index is still valid, stale indices leave the cell untouched.
+    ///
+    pub fn remove(&mut self, index: Idx32<T>) -> Option<T>{
+        let i = index.index as usize;
+        match &self.cells[i]{
+            Arena32Cell::Allocated{val: _, generation} if *generation == index.generation => {
+                let cell = std::mem::replace(&mut self.cells[i], Arena32Cell::Freed{
+                    next: self.freed,
+                    generation: index.generation.wrapping_add(1),
+                });
+                self.num -= 1;
+                self.freed = Some(index.index);
+                match cell{
+                    Arena32Cell::Allocated{val, ..} => Some(val),
+                    Arena32Cell::Freed{..} => unreachable!(),
+                }
+            }
+            _ => None,
+        }
+    }
+
+    ///
+    /// Gets the Generation for a given index.
+    ///
+    pub fn gen(&self, index: u32) -> u32{
+        match self.cells[index as usize]{
+            Arena32Cell::Freed{generation, ..} => generation,
+            Arena32Cell::Allocated{generation, ..} => generation,
+        }
+    }
+
+    ///
+    /// Returns an optional reference to the value at the index.
+    ///
+    /// ```rust
+    /// use gen_arena::*;
+    ///
+    /// let mut arena = Arena32::new();
+    ///
+    /// let i1 = arena.insert(1);
+    ///
+    /// assert_eq!(*arena.get(i1).unwrap(), 1);
+    ///
+    /// arena.remove(i1);
+    ///
+    /// assert_eq!(arena.get(i1), None);
+    ///
+    /// ```
+    ///
+    pub fn get(&self, index: Idx32<T>) -> Option<&T>{
+        if let Arena32Cell::Allocated{val, generation} = &self.cells[index.index as usize]{
+            if *generation == index.generation{
+                Some(val)
+            }
+            else{
+                None
+            }
+        }
+        else{
+            None
+        }
+    }
+
+    ///
+    /// Returns an optional reference to a cell with any generation.
+    ///
+    pub fn get_any(&self, index: u32) -> Option<&T>{
+        if let Arena32Cell::Allocated{val, generation: _} = &self.cells[index as usize]{
+            Some(val)
+        }
+        else{
+            None
+        }
+    }
+
+    ///
+    /// Get N optional references to N indices in the arena.
+    ///
+    /// ```rust
+    /// use gen_arena::*;
+    ///
+    /// let mut arena = Arena32::new();
+    ///
+    /// let i1 = arena.insert(1);
+    /// let i2 = arena.insert(2);
+    ///
+    /// let res = arena.getn([i1, i2]);
+    ///
+    /// assert_eq!(*res[0].unwrap(), 1);
+    /// assert_eq!(*res[1].unwrap(), 2);
+    ///
+    /// ```
+    ///
+    pub fn getn<const N: usize>(&self, indices: [Idx32<T>; N]) -> [Option<&T>; N]{
+        let mut ret = [None; N];
+
+        for (i, index) in indices.iter().enumerate(){
+            ret[i] = self.get(*index);
+        }
+        ret
+    }
+
+    ///
+    /// Returns a mutable optional reference to the value at the index.
+    ///
+    /// ```rust
+    /// use gen_arena::*;
+    ///
+    /// let mut arena = Arena32::new();
+    ///
+    /// let i1 = arena.insert(1);
+    ///
+    /// *arena.get_mut(i1).unwrap() = 2;
+    ///
+    /// assert_eq!(*arena.get(i1).unwrap(), 2);
+    ///
+    /// ```
+    ///
+    pub fn get_mut(&mut self, index: Idx32<T>) -> Option<&mut T>{
+        if let Arena32Cell::Allocated{val, generation} = &mut self.cells[index.index as usize]{
+            if *generation == index.generation{
+                Some(val)
+            }
+            else{
+                None
+            }
+        }
+        else{
+            None
+        }
+    }
+
+    ///
+    /// Returns an optional mutable reference to the value of a cell at a index with any generation.
+    ///
+    pub fn get_any_mut(&mut self, index: u32) -> Option<&mut T>{
+        if let Arena32Cell::Allocated{val, generation: _} = &mut self.cells[index as usize]{
+            Some(val)
+        }
+        else{
+            None
+        }
+    }
+
+    ///
+    /// Returns mutable optional references to two distinct values.
+    /// Panics if both indices refer to the same element, stale indices to the same slot yield None.
+    ///
+    ///```rust
+    /// use gen_arena::*;
+    ///
+    /// let mut arena = Arena32::new();
+    ///
+    /// let i1 = arena.insert(1);
+    /// let i2 = arena.insert(2);
+    ///
+    /// let (c1, c2) = arena.get2_mut((i1, i2));
+    ///
+    /// *c1.unwrap() = 3;
+    /// *c2.unwrap() = 4;
+    ///
+    /// assert_eq!(*arena.get(i1).unwrap(), 3);
+    /// assert_eq!(*arena.get(i2).unwrap(), 4);
+    ///
+    ///```
+    ///
+    pub fn get2_mut(&mut self, indices: (Idx32<T>, Idx32<T>)) -> (Option<&mut T>, Option<&mut T>){
+        if indices.0.index == indices.1.index{
+            // Only a generation matching the allocated one refers to a value,
+            // so the same slot is an error only if both indices are live.
+            if self.get(indices.0).is_none(){
+                return (None, self.get_mut(indices.1));
+            }
+            if indices.0.generation == indices.1.generation{
+                panic!("Cannot take 2 mutable references to a value at the same index.")
+            }
+            return (self.get_mut(indices.0), None);
+        }
+
+        let (i0, i1) = (indices.0.index as usize, indices.1.index as usize);
+
+        if i0 >= self.cells.len(){
+            return (None, self.get_mut(indices.1));
+        }
+        if i1 >= self.cells.len(){
+            return (self.get_mut(indices.0), None);
+        }
+
+        let (cell0, cell1) = {
+            let split = self.cells.split_at_mut(i0.max(i1));
+            if i0 < i1{
+                (&mut split.0[i0], &mut split.1[0])
+            }
+            else{
+                (&mut split.1[0], &mut split.0[i1])
+            }
+        };
+
+        let cell0 = match cell0{
+            Arena32Cell::Allocated{val, generation} => {
+                if indices.0.generation == *generation{
+                    Some(val)
+                }
+                else{
+                    None
+                }
+            },
+            _ => None
+        };
+        let cell1 = match cell1{
+            Arena32Cell::Allocated{val, generation} => {
+                if indices.1.generation == *generation{
+                    Some(val)
+                }
+                else{
+                    None
+                }
+            },
+            _ => None
+        };
+
+        (cell0, cell1)
+    }
+
+    ///
+    /// Returns iterator over all Allocated cells.
+    ///
+    /// ```rust
+    /// use gen_arena::*;
+    /// let mut arena = Arena32::new();
+    ///
+    /// let i1 = arena.insert(1);
+    /// let i2 = arena.insert(1);
+    ///
+    /// for val in arena.values(){
+    ///     assert_eq!(*val, 1);
+    /// }
+    ///
+    /// ```
+    ///
+    #[inline]
+    pub fn values(&self) -> Values32<'_, T>{
+        Values32{
+            iter: self.iter()
+        }
+    }
+
+    ///
+    /// Returns mutable iterator over all Allocated cells.
+    ///
+    /// ```rust
+    /// use gen_arena::*;
+    /// let mut arena = Arena32::new();
+    ///
+    /// let i1 = arena.insert(1);
+    /// let i2 = arena.insert(2);
+    ///
+    /// for val in arena.values_mut(){
+    ///     *val = 0;
+    /// }
+    ///
+    /// assert_eq!(*arena.get(i1).unwrap(), 0);
+    /// assert_eq!(*arena.get(i2).unwrap(), 0);
+    ///
+    /// ```
+    ///
+    #[inline]
+    pub fn values_mut(&mut self) -> ValuesMut32<'_, T>{
+        ValuesMut32{
+            iter: self.iter_mut()
+        }
+    }
+
+    ///
+    /// Iterator over all keys in the Arena32.
+    ///
+    /// ```rust
+    /// use gen_arena::*;
+    /// let mut arena = Arena32::new();
+    ///
+    /// let i1 = arena.insert(1);
+    /// let i2 = arena.insert(2);
+    ///
+    /// let keys: Vec<_> = arena.keys().collect();
+    ///
+    /// assert_eq!(keys, vec![Idx32::from_raw_parts(0, 0), Idx32::from_raw_parts(1, 0)]);
+    /// ```
+    ///
+    #[inline]
+    pub fn keys(&self) -> Keys32<'_, T>{
+        Keys32{
+            iter: self.iter(),
+        }
+    }
+
+    ///
+    /// Returns an iterator over the Allocated cells with index.
+    ///
+    /// ```rust
+    /// use gen_arena::*;
+    /// let mut arena = Arena32::new();
+    ///
+    /// let i1 = arena.insert(1);
+    /// let i2 = arena.insert(2);
+    ///
+    /// for (index, val) in arena.iter(){
+    ///     if index == i1{
+    ///         assert_eq!(*val, 1);
+    ///     }
+    ///     if index == i2{
+    ///         assert_eq!(*val, 2);
+    ///     }
+    /// }
+    ///
+    /// ```
+    ///
+    #[inline]
+    pub fn iter(&self) -> Iter32<'_, T>{
+        Iter32{
+            iter: self.cells.iter().enumerate(),
+        }
+    }
+
+    ///
+    /// Returns an mutable iterator over the Allocated cells with indices.
+    ///
+    /// ```rust
+    /// use gen_arena::*;
+    /// let mut arena = Arena32::new();
+    ///
+    /// let i1 = arena.insert(1);
+    /// let i2 = arena.insert(2);
+    ///
+    /// for (index, val) in arena.iter_mut(){
+    ///     *val = index.index();
+    /// }
+    ///
+    /// assert_eq!(*arena.get(i1).unwrap(), 0);
+    /// assert_eq!(*arena.get(i2).unwrap(), 1);
+    ///
+    /// ```
+    ///
+    #[inline]
+    pub fn iter_mut(&mut self) -> IterMut32<'_, T>{
+        IterMut32{
+            iter: self.cells.iter_mut().enumerate(),
+        }
+    }
+
+    #[inline]
+    pub fn reserve(&mut self, additional: usize){
+        self.cells.reserve(additional)
+    }
+
+    #[inline]
+    pub fn capacity(&self) -> usize{
+        self.cells.capacity()
+    }
+
+    #[inline]
+    pub fn num(&self) -> usize{
+        self.num
+    }
+}
+
+impl<T> Default for Arena32<T>{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Index<Idx32<T>> for Arena32<T>{
+    type Output = T;
+
+    fn index(&self, index: Idx32<T>) -> &Self::Output {
+        self.get(index).expect("There is no element at this index with that generation.")
+    }
+}
+
+impl<T> IndexMut<Idx32<T>> for Arena32<T>{
+    fn index_mut(&mut self, index: Idx32<T>) -> &mut Self::Output {
+        self.get_mut(index).expect("There is no element at this index with that generation.")
+    }
+}
+
+pub struct Iter32<'i, T: 'i>{
+    pub(crate) iter: std::iter::Enumerate<std::slice::Iter<'i, Arena32Cell<T>>>,
+}
+
+impl<'i, T> Iterator for Iter32<'i, T>{
+    type Item = (Idx32<T>, &'i T);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop{
+            match self.iter.next(){
+                Some((_, Arena32Cell::Freed{..})) => continue,
+                Some((i, Arena32Cell::Allocated{val, generation})) => {
+                    return Some((Idx32::from_parts(i as u32, *generation), val));
+                }
+                None => {return None;},
+            }
+        }
+    }
+}
+
+pub struct Values32<'i, T: 'i>{
+    pub (crate) iter: Iter32<'i, T>,
+}
+
+impl<'i, T> Iterator for Values32<'i, T>{
+    type Item = &'i T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.iter.next().map(|(_, val)|{val})
+    }
+}
+
+pub struct IterMut32<'i, T: 'i>{
+    pub(crate) iter: std::iter::Enumerate<std::slice::IterMut<'i, Arena32Cell<T>>>,
+}
+
+impl<'i, T> Iterator for IterMut32<'i, T>{
+    type Item = (Idx32<T>, &'i mut T);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop{
+            match self.iter.next(){
+                Some((_, Arena32Cell::Freed{..})) => continue,
+                Some((i, Arena32Cell::Allocated{val, generation})) => {
+                    return Some((Idx32::from_parts(i as u32, *generation), val));
+                }
+                None => {return None;},
+            }
+        }
+    }
+}
+
+pub struct ValuesMut32<'i, T: 'i>{
+    pub(crate) iter: IterMut32<'i, T>,
+}
+
+impl<'i, T> Iterator for ValuesMut32<'i, T>{
+    type Item = &'i mut T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.iter.next().map(|(_, val)|{val})
+    }
+}
+
+pub struct Keys32<'i, T: 'i>{
+    pub(crate) iter: Iter32<'i, T>,
+}
+
+impl<'i, T> Iterator for Keys32<'i, T>{
+    type Item = Idx32<T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.iter.next().map(|(i, _)|{i})
+    }
+}
+
+#[cfg(test)]
+mod test{
+    use super::*;
+    #[test]
+    fn test_to_bits_round_trip(){
+        let mut arena = Arena32::new();
+
+        let i0 = arena.insert(0);
+        let i1 = arena.insert(1);
+        arena.remove(i0);
+        let i2 = arena.insert(2);
+
+        for index in [i0, i1, i2]{
+            assert_eq!(Idx32::from_bits(index.to_bits()), index);
+        }
+        assert_eq!(arena.get(Idx32::from_bits(i2.to_bits())), Some(&2));
+        assert_eq!(arena.get(Idx32::from_bits(i0.to_bits())), None);
+
+        for (index, generation) in [(0, 0), (u32::MAX, 0), (0, u32::MAX), (u32::MAX, u32::MAX)]{
+            let idx = Idx32::<()>::from_raw_parts(index, generation);
+            let idx = Idx32::<()>::from_bits(idx.to_bits());
+            assert_eq!(idx.index(), index);
+            assert_eq!(idx.gen(), generation);
+        }
+    }
+
+    #[test]
+    fn test_clear_reuses_cells(){
+        let mut arena = Arena32::new();
+
+        let i0 = arena.insert(0);
+        let i1 = arena.insert(1);
+
+        arena.clear();
+
+        assert_eq!(arena.num(), 0);
+        assert_eq!(arena.get(i0), None);
+        assert_eq!(arena.get(i1), None);
+
+        let i2 = arena.insert(2);
+        let i3 = arena.insert(3);
+
+        assert_eq!(i2, Idx32::from_raw_parts(0, 1));
+        assert_eq!(i3, Idx32::from_raw_parts(1, 1));
+    }
+
+    #[test]
+    fn test_get2_mut_same_slot(){
+        let mut arena = Arena32::new();
+        let stale = arena.insert(0);
+        arena.remove(stale);
+        let live = arena.insert(1);
+        assert_eq!(stale.index(), live.index());
+
+        assert_eq!(arena.get2_mut((stale, stale)), (None, None));
+        assert_eq!(arena.get2_mut((live, stale)), (Some(&mut 1), None));
+        assert_eq!(arena.get2_mut((stale, live)), (None, Some(&mut 1)));
+
+        // A stale key of a later generation does not hide the live one.
+        let ahead = Idx32::from_raw_parts(live.index(), live.gen() + 5);
+        assert_eq!(arena.get2_mut((live, ahead)), (Some(&mut 1), None));
+        assert_eq!(arena.get2_mut((ahead, live)), (None, Some(&mut 1)));
+
+        // Two stale copies of a freed slot.
+        arena.remove(live);
+        assert_eq!(arena.get2_mut((live, live)), (None, None));
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_get2_mut_same_live(){
+        let mut arena = Arena32::new();
+        let i0 = arena.insert(0);
+        let _ = arena.get2_mut((i0, i0));
+    }
+}
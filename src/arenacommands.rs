@@ -0,0 +1,176 @@
+use std::collections::HashMap;
+
+use crate::*;
+
+///
+/// Refers to an insertion queued in an ArenaCommands buffer.
+/// Arena::apply maps it to the key of the inserted element.
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct PendingIdx(usize);
+
+impl PendingIdx{
+    ///
+    /// Returns the position of the insertion among all insertions of its buffer.
+    ///
+    #[inline]
+    pub fn index(&self) -> usize{
+        self.0
+    }
+}
+
+#[derive(Debug)]
+enum Command<T>{
+    Insert(T),
+    Remove(ArenaIdx<T>),
+}
+
+///
+/// A buffer of insertions and removals, to be applied to an Arena later.
+///
+/// Code that only holds a shared reference to an Arena, for example while many threads read from it,
+/// can queue structural changes into its own ArenaCommands and hand them over to the owner of the
+/// Arena, which applies them with Arena::apply.
+///
+/// # Example
+///
+///```rust
+/// use gen_arena::*;
+///
+/// let mut arena = Arena::new();
+/// for i in 0..8{
+///     let _ = arena.insert(i);
+/// }
+///
+/// // Every thread replaces the odd numbers of its half by their doubles.
+/// let buffers: Vec<_> = std::thread::scope(|s|{
+///     let handles: Vec<_> = (0..2).map(|half|{
+///         let arena = &arena;
+///         s.spawn(move ||{
+///             let mut cmds = ArenaCommands::new();
+///             for (key, val) in arena.iter().filter(|(_, val)| *val / 4 == half && *val % 2 == 1){
+///                 cmds.queue_remove(key);
+///                 let _ = cmds.queue_insert(val * 2);
+///             }
+///             cmds
+///         })
+///     }).collect();
+///     handles.into_iter().map(|h| h.join().unwrap()).collect()
+/// });
+///
+/// for cmds in buffers{
+///     let inserted = arena.apply(cmds);
+///     assert_eq!(inserted.len(), 2);
+/// }
+///
+/// let mut values: Vec<_> = arena.values().copied().collect();
+/// values.sort();
+/// assert_eq!(values, [0, 2, 2, 4, 6, 6, 10, 14]);
+///```
+///
+#[derive(Debug)]
+pub struct ArenaCommands<T>{
+    commands: Vec<Command<T>>,
+    inserts: usize,
+}
+
+impl<T> ArenaCommands<T>{
+    ///
+    /// Creates an empty ArenaCommands buffer.
+    ///
+    pub fn new() -> Self{
+        Self{
+            commands: Vec::new(),
+            inserts: 0,
+        }
+    }
+
+    ///
+    /// Queues the insertion of the value.
+    ///
+    #[must_use]
+    pub fn queue_insert(&mut self, val: T) -> PendingIdx{
+        self.commands.push(Command::Insert(val));
+        self.inserts += 1;
+        PendingIdx(self.inserts - 1)
+    }
+
+    ///
+    /// Queues the removal of the element.
+    /// The removal is skipped if the index is not valid anymore once the commands are applied.
+    ///
+    pub fn queue_remove(&mut self, index: ArenaIdx<T>){
+        self.commands.push(Command::Remove(index));
+    }
+
+    ///
+    /// Returns the number of queued commands.
+    ///
+    #[inline]
+    pub fn len(&self) -> usize{
+        self.commands.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool{
+        self.commands.is_empty()
+    }
+}
+
+impl<T> Default for ArenaCommands<T>{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Arena<T>{
+    ///
+    /// Applies the queued commands in the order they were queued.
+    /// Returns the keys the pending insertions were inserted at.
+    ///
+    pub fn apply(&mut self, cmds: ArenaCommands<T>) -> HashMap<PendingIdx, ArenaIdx<T>>{
+        let mut inserted = HashMap::with_capacity(cmds.inserts);
+        for command in cmds.commands{
+            match command{
+                Command::Insert(val) => {
+                    let pending = PendingIdx(inserted.len());
+                    inserted.insert(pending, self.insert(val));
+                }
+                Command::Remove(index) => {
+                    self.remove(index);
+                }
+            }
+        }
+        inserted
+    }
+}
+
+#[cfg(test)]
+mod test{
+    use super::*;
+
+    #[test]
+    fn test_apply(){
+        let mut arena = Arena::new();
+        let a = arena.insert("a");
+        let b = arena.insert("b");
+
+        let mut cmds = ArenaCommands::new();
+        let c = cmds.queue_insert("c");
+        cmds.queue_remove(a);
+        let d = cmds.queue_insert("d");
+        // Removing twice and removing stale keys is ignored.
+        cmds.queue_remove(a);
+        assert_eq!(arena.remove(b), Some("b"));
+        cmds.queue_remove(b);
+        assert_eq!(cmds.len(), 5);
+
+        let inserted = arena.apply(cmds);
+        assert_eq!(inserted.len(), 2);
+        assert_eq!(arena[inserted[&c]], "c");
+        assert_eq!(arena[inserted[&d]], "d");
+        assert_eq!(arena.get(a), None);
+        assert_eq!(arena.num(), 2);
+        assert_eq!((c.index(), d.index()), (0, 1));
+    }
+}
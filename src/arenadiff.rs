@@ -0,0 +1,400 @@
+use std::fmt;
+
+use crate::*;
+
+///
+/// The changes turning one Arena into another, computed by Arena::diff.
+///
+/// Elements are keyed by slot and generation, so applying the diff with Arena::apply_diff
+/// reproduces the keys, the generations of freed slots and the free list of the target.
+/// With the serde feature diffs can be serialized.
+///
+/// # Example
+///
+///```rust
+/// use gen_arena::*;
+///
+/// let mut server = Arena::new();
+/// let a = server.insert("a");
+/// let mut client = server.clone();
+///
+/// let b = server.insert("b");
+/// server.remove(a);
+///
+/// let diff = client.diff(&server);
+/// assert_eq!(diff.added().len(), 1);
+/// client.apply_diff(&diff).unwrap();
+///
+/// assert_eq!(client.get(a), None);
+/// assert_eq!(client[b], "b");
+///```
+///
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ArenaDiff<T, G = usize>{
+    added: Vec<(usize, G, T)>,
+    removed: Vec<(usize, G)>,
+    changed: Vec<(usize, G, T)>,
+    // The generations of the freed slots of the target that differ from the base.
+    vacant: Vec<(usize, G)>,
+    // The number of slots and the free list of the target.
+    slots: usize,
+    free: Vec<usize>,
+}
+
+impl<T, G: GenCounter> ArenaDiff<T, G>{
+    ///
+    /// Returns the slot, generation and value of the elements only in the target.
+    ///
+    #[inline]
+    pub fn added(&self) -> &[(usize, G, T)]{
+        &self.added
+    }
+
+    ///
+    /// Returns the slot and generation of the elements only in the base.
+    ///
+    #[inline]
+    pub fn removed(&self) -> &[(usize, G)]{
+        &self.removed
+    }
+
+    ///
+    /// Returns the slot, generation and new value of the elements in both with different values.
+    ///
+    #[inline]
+    pub fn changed(&self) -> &[(usize, G, T)]{
+        &self.changed
+    }
+
+    ///
+    /// Returns true if no element is added, removed or changed.
+    /// Applying the diff may still change freed slots.
+    ///
+    #[inline]
+    pub fn is_empty(&self) -> bool{
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+///
+/// The reason Arena::apply_diff failed because the arena is not the base of the diff
+/// or the diff is malformed. Nothing is changed when it fails.
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum DiffError{
+    /// A removed or changed element is not in the slot with its generation.
+    Missing{
+        slot: usize,
+    },
+    /// The slot holds an element that the diff neither keeps nor removes.
+    Occupied{
+        slot: usize,
+    },
+    /// The diff names a slot outside of the target or lists it twice, which a diff
+    /// computed by Arena::diff never does.
+    Malformed{
+        slot: usize,
+    },
+}
+
+impl fmt::Display for DiffError{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self{
+            Self::Missing{slot} => write!(f, "the element in slot {} is missing or has another generation", slot),
+            Self::Occupied{slot} => write!(f, "slot {} holds an element the diff does not know", slot),
+            Self::Malformed{slot} => write!(f, "the diff names slot {} out of range or twice", slot),
+        }
+    }
+}
+
+impl std::error::Error for DiffError{}
+
+impl<T, G: GenCounter, Tag> Arena<T, G, Tag>{
+    ///
+    /// Returns the changes turning this arena into other, see ArenaDiff.
+    ///
+    pub fn diff(&self, other: &Arena<T, G, Tag>) -> ArenaDiff<T, G>
+    where
+        T: PartialEq + Clone,
+    {
+        let mut diff = ArenaDiff{
+            added: Vec::new(),
+            removed: Vec::new(),
+            changed: Vec::new(),
+            vacant: Vec::new(),
+            slots: other.slots(),
+            free: other.free_indices().collect(),
+        };
+        let base = self.raw();
+        let target = other.raw();
+        for slot in 0..base.cells.len().max(target.cells.len()){
+            let (old, new) = (base.cells.get(slot), target.cells.get(slot));
+            if let Some(RawCell::Allocated{val, generation}) = old{
+                match new{
+                    Some(RawCell::Allocated{val: new_val, generation: new_generation}) if new_generation == generation => {
+                        if new_val != val{
+                            diff.changed.push((slot, *generation, new_val.clone()));
+                        }
+                        continue;
+                    }
+                    _ => diff.removed.push((slot, *generation)),
+                }
+            }
+            match new{
+                Some(RawCell::Allocated{val, generation}) => diff.added.push((slot, *generation, val.clone())),
+                Some(RawCell::Freed{generation, ..}) if !matches!(old, Some(RawCell::Freed{generation: old_generation, ..}) if old_generation == generation) => {
+                    diff.vacant.push((slot, *generation));
+                }
+                _ => (),
+            }
+        }
+        diff
+    }
+
+    ///
+    /// Turns the arena into the target of the diff, if it is the base the diff was computed from.
+    /// Returns why it is not and changes nothing otherwise.
+    ///
+    pub fn apply_diff(&mut self, diff: &ArenaDiff<T, G>) -> Result<(), DiffError>
+    where
+        T: Clone,
+    {
+        let live = |arena: &Self, slot: usize, generation: G| arena.idx_at(slot).is_some_and(|index| index.gen() == generation);
+        for &(slot, generation) in diff.removed.iter(){
+            if !live(self, slot, generation){
+                return Err(DiffError::Missing{slot});
+            }
+        }
+        for &(slot, generation, _) in diff.changed.iter(){
+            if !live(self, slot, generation){
+                return Err(DiffError::Missing{slot});
+            }
+        }
+        // Every other element is kept, so it may neither be overwritten nor cut off.
+        let mut removed = vec![false; self.slots()];
+        for &(slot, _) in diff.removed.iter(){
+            removed[slot] = true;
+        }
+        let kept = |slot: usize| self.idx_at(slot).is_some() && !removed[slot];
+        if let Some(slot) = (diff.slots..self.slots()).find(|&slot| kept(slot)){
+            return Err(DiffError::Occupied{slot});
+        }
+        // The slots written below have to be in the target and must not hold a kept element.
+        let mut added = vec![false; diff.slots];
+        for &(slot, _, _) in diff.added.iter(){
+            if slot >= diff.slots || std::mem::replace(&mut added[slot], true){
+                return Err(DiffError::Malformed{slot});
+            }
+            if kept(slot){
+                return Err(DiffError::Occupied{slot});
+            }
+        }
+        for &(slot, _) in diff.vacant.iter(){
+            if slot >= diff.slots || added[slot]{
+                return Err(DiffError::Malformed{slot});
+            }
+            if kept(slot){
+                return Err(DiffError::Occupied{slot});
+            }
+        }
+        let mut listed = vec![false; diff.slots];
+        for &slot in diff.free.iter(){
+            if slot >= diff.slots || added[slot] || std::mem::replace(&mut listed[slot], true){
+                return Err(DiffError::Malformed{slot});
+            }
+            if kept(slot){
+                return Err(DiffError::Occupied{slot});
+            }
+        }
+
+        for &(_slot, _) in diff.removed.iter(){
+            #[cfg(feature = "leak-detect")]
+            self.leaks.forget(_slot);
+            #[cfg(feature = "labels")]
+            self.labels.remove(&_slot);
+        }
+        #[cfg(feature = "metrics")]
+        self.metrics.removed(diff.removed.len());
+        let raw = self.raw_mut();
+        for &(slot, generation) in diff.removed.iter(){
+            // The generation is replaced by the one of the target below.
+            raw.cells[slot] = RawCell::Freed{next: None, generation};
+            raw.num -= 1;
+        }
+        raw.cells.resize_with(diff.slots, || RawCell::Freed{next: None, generation: G::FIRST});
+        for &(slot, generation) in diff.vacant.iter(){
+            raw.cells[slot] = RawCell::Freed{next: None, generation};
+        }
+        for (slot, generation, val) in diff.added.iter(){
+            raw.cells[*slot] = RawCell::Allocated{val: val.clone(), generation: *generation};
+            raw.num += 1;
+        }
+        for (slot, _, val) in diff.changed.iter(){
+            if let RawCell::Allocated{val: old, ..} = &mut raw.cells[*slot]{
+                *old = val.clone();
+            }
+        }
+        for cell in raw.cells.iter_mut(){
+            if let RawCell::Freed{next, ..} = cell{
+                *next = None;
+            }
+        }
+        for link in diff.free.windows(2){
+            if let RawCell::Freed{next, ..} = &mut raw.cells[link[0]]{
+                *next = Some(link[1]);
+            }
+        }
+        raw.freed = diff.free.first().copied();
+        #[cfg(feature = "removal-watch")]
+        for &(slot, _) in diff.removed.iter(){
+            self.watchers.fire(slot);
+        }
+        #[cfg(feature = "metrics")]
+        self.metrics.inserted(diff.added.len(), self.num(), self.slots());
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test{
+    use super::*;
+    use proptest::prelude::*;
+
+    #[derive(Debug, Clone)]
+    enum Op{
+        Insert(u32),
+        Remove(usize),
+        Set(usize, u32),
+        Clear,
+    }
+
+    fn op() -> impl Strategy<Value = Op>{
+        prop_oneof![
+            4 => any::<u32>().prop_map(Op::Insert),
+            3 => any::<usize>().prop_map(Op::Remove),
+            2 => (any::<usize>(), 0..4u32).prop_map(|(i, v)| Op::Set(i, v)),
+            1 => Just(Op::Clear),
+        ]
+    }
+
+    fn run(arena: &mut Arena<u32, u8>, ops: Vec<Op>){
+        let mut keys = arena.keys().collect::<Vec<_>>();
+        for op in ops{
+            match op{
+                Op::Insert(val) => keys.push(arena.insert(val)),
+                Op::Remove(i) if !keys.is_empty() => {
+                    arena.remove(keys[i % keys.len()]);
+                }
+                Op::Set(i, val) if !keys.is_empty() => {
+                    if let Some(old) = arena.get_mut(keys[i % keys.len()]){
+                        *old = val;
+                    }
+                }
+                Op::Clear => arena.clear(),
+                _ => (),
+            }
+        }
+    }
+
+    // The slots with their generations, the free list and the values.
+    fn state<T: std::fmt::Debug, G: GenCounter + std::fmt::Display>(arena: &Arena<T, G>) -> String{
+        format!("{}{:?}", arena.debug_dump(), arena.iter().collect::<Vec<_>>())
+    }
+
+    proptest!{
+        #[test]
+        fn test_apply_diff(base in proptest::collection::vec(op(), 0..100), ops in proptest::collection::vec(op(), 0..100), fresh: bool){
+            let mut a = Arena::default();
+            run(&mut a, base);
+            let mut b = if fresh {Arena::default()} else {a.clone()};
+            run(&mut b, ops);
+
+            let diff = a.diff(&b);
+            let mut patched = a.clone();
+            prop_assert_eq!(patched.apply_diff(&diff), Ok(()));
+            prop_assert_eq!(state(&patched), state(&b));
+
+            // The keys and the free list match, so both arenas keep agreeing.
+            let (x, y) = (patched.insert(7), b.insert(7));
+            prop_assert_eq!(x, y);
+            prop_assert!(patched.diff(&b).is_empty());
+        }
+    }
+
+    #[test]
+    fn test_wrong_base(){
+        let mut a = Arena::new();
+        let keys: Vec<_> = (0..3).map(|i| a.insert(i)).collect();
+        let mut b = a.clone();
+        b.remove(keys[0]);
+        b[keys[1]] = 10;
+        let diff = a.diff(&b);
+        assert_eq!((diff.removed(), diff.changed()), (&[(0, 0)][..], &[(1, 0, 10)][..]));
+
+        // The element to remove was already removed and replaced.
+        let mut c = a.clone();
+        c.remove(keys[0]);
+        let _ = c.insert(0);
+        assert_eq!(c.apply_diff(&diff), Err(DiffError::Missing{slot: 0}));
+
+        // The diff does not know about an added element.
+        let mut d = a.clone();
+        let extra = d.insert(3);
+        let before = state(&d);
+        assert_eq!(d.apply_diff(&diff), Err(DiffError::Occupied{slot: extra.index()}));
+        assert_eq!(state(&d), before);
+        assert_eq!(d.apply_diff(&diff).unwrap_err().to_string(), "slot 3 holds an element the diff does not know");
+
+        assert_eq!(a.apply_diff(&diff), Ok(()));
+        assert_eq!(a.values().copied().collect::<Vec<_>>(), [10, 2]);
+    }
+
+    #[test]
+    fn test_foreign_base(){
+        // The diff frees slot 0 with a new generation.
+        let base = Arena::new();
+        let mut target = Arena::new();
+        let key = target.insert(0);
+        target.remove(key);
+        let diff = base.diff(&target);
+
+        // Another arena holds an element in slot 0 the diff does not know.
+        let mut foreign = Arena::new();
+        let live = foreign.insert(1);
+        let before = state(&foreign);
+        assert_eq!(foreign.apply_diff(&diff), Err(DiffError::Occupied{slot: 0}));
+        assert_eq!(state(&foreign), before);
+        assert_eq!(foreign[live], 1);
+
+        // Slots outside of the target or listed twice are rejected instead of panicking.
+        let mut empty = Arena::<i32>::new();
+        let out_of_range = ArenaDiff{added: vec![(5, 0, 1)], ..base.diff(&base)};
+        assert_eq!(empty.apply_diff(&out_of_range), Err(DiffError::Malformed{slot: 5}));
+        let repeated = ArenaDiff{free: vec![0, 0], ..diff.clone()};
+        assert_eq!(empty.apply_diff(&repeated), Err(DiffError::Malformed{slot: 0}));
+        let vacant = ArenaDiff{vacant: vec![(3, 1)], ..diff.clone()};
+        assert_eq!(empty.apply_diff(&vacant), Err(DiffError::Malformed{slot: 3}));
+        assert_eq!(empty.slots(), 0);
+
+        assert_eq!(empty.apply_diff(&diff), Ok(()));
+        assert_eq!(state(&empty), state(&target));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde(){
+        let mut a = Arena::new();
+        let key = a.insert(String::from("a"));
+        let mut b = a.clone();
+        b.remove(key);
+        let _ = b.insert(String::from("b"));
+
+        let diff = a.diff(&b);
+        let json = serde_json::to_string(&diff).unwrap();
+        let diff: ArenaDiff<String> = serde_json::from_str(&json).unwrap();
+        a.apply_diff(&diff).unwrap();
+        assert_eq!(a.values().collect::<Vec<_>>(), ["b"]);
+    }
+}
@@ -0,0 +1,808 @@
+use std::ops::{Index, IndexMut};
+
+use crate::*;
+
+///
+/// A node of an ArenaList.
+///
+#[derive(Debug, Clone)]
+pub struct ListNode<T>{
+    val: T,
+    prev: Option<ListIdx<T>>,
+    next: Option<ListIdx<T>>,
+}
+
+///
+/// A handle to a node of an ArenaList.
+///
+pub type ListIdx<T> = ArenaIdx<ListNode<T>>;
+
+///
+/// A doubly linked list whose nodes live in an Arena.
+///
+/// Every node is addressed by a generational ListIdx which stays valid until the node itself is removed,
+/// no matter what else is inserted, removed or spliced.
+/// Unlike std::collections::LinkedList this allows O(1) insertion and removal at any handle
+/// and O(1) splicing of ranges within the list.
+///
+/// # Example
+///
+///```rust
+/// use gen_arena::*;
+///
+/// let mut list = ArenaList::new();
+///
+/// let a = list.push_back('a');
+/// let c = list.push_back('c');
+/// let b = list.insert_after(a, 'b').unwrap();
+/// list.push_front('_');
+///
+/// assert_eq!(list.iter().collect::<String>(), "_abc");
+///
+/// assert_eq!(list.remove(b), Some('b'));
+/// assert_eq!(list.remove(b), None);
+/// assert_eq!(list[c], 'c');
+/// assert_eq!(list.iter().rev().collect::<String>(), "ca_");
+///```
+///
+#[derive(Debug, Clone)]
+pub struct ArenaList<T>{
+    nodes: Arena<ListNode<T>>,
+    head: Option<ListIdx<T>>,
+    tail: Option<ListIdx<T>>,
+}
+
+impl<T> ArenaList<T>{
+    ///
+    /// Creates an empty ArenaList.
+    ///
+    pub fn new() -> Self{
+        Self{
+            nodes: Arena::new(),
+            head: None,
+            tail: None,
+        }
+    }
+
+    ///
+    /// Creates an empty ArenaList with capacity.
+    ///
+    pub fn with_capacity(cap: usize) -> Self{
+        Self{
+            nodes: Arena::with_capacity(cap),
+            head: None,
+            tail: None,
+        }
+    }
+
+    #[inline]
+    fn node_mut(&mut self, index: ListIdx<T>) -> &mut ListNode<T>{
+        self.nodes.get_mut(index).expect("Linked nodes are always valid.")
+    }
+
+    #[inline]
+    fn set_next(&mut self, index: Option<ListIdx<T>>, next: Option<ListIdx<T>>){
+        match index{
+            Some(index) => self.node_mut(index).next = next,
+            None => self.head = next,
+        }
+    }
+
+    #[inline]
+    fn set_prev(&mut self, index: Option<ListIdx<T>>, prev: Option<ListIdx<T>>){
+        match index{
+            Some(index) => self.node_mut(index).prev = prev,
+            None => self.tail = prev,
+        }
+    }
+
+    ///
+    /// Inserts a new node between prev and next which have to be adjacent.
+    ///
+    fn link(&mut self, prev: Option<ListIdx<T>>, next: Option<ListIdx<T>>, val: T) -> ListIdx<T>{
+        let index = self.nodes.insert(ListNode{
+            val,
+            prev,
+            next,
+        });
+        self.set_next(prev, Some(index));
+        self.set_prev(next, Some(index));
+        index
+    }
+
+    ///
+    /// Cuts the range first..=last out of the list, leaving the links of its ends dangling.
+    ///
+    fn unlink_range(&mut self, first: ListIdx<T>, last: ListIdx<T>){
+        let prev = self.nodes[first].prev;
+        let next = self.nodes[last].next;
+        self.set_next(prev, next);
+        self.set_prev(next, prev);
+    }
+
+    ///
+    /// Links the range first..=last between prev and next which have to be adjacent.
+    ///
+    fn link_range(&mut self, prev: Option<ListIdx<T>>, next: Option<ListIdx<T>>, first: ListIdx<T>, last: ListIdx<T>){
+        self.node_mut(first).prev = prev;
+        self.node_mut(last).next = next;
+        self.set_next(prev, Some(first));
+        self.set_prev(next, Some(last));
+    }
+
+    ///
+    /// Inserts a value at the front of the list.
+    ///
+    #[must_use]
+    pub fn push_front(&mut self, val: T) -> ListIdx<T>{
+        self.link(None, self.head, val)
+    }
+
+    ///
+    /// Inserts a value at the back of the list.
+    ///
+    #[must_use]
+    pub fn push_back(&mut self, val: T) -> ListIdx<T>{
+        self.link(self.tail, None, val)
+    }
+
+    ///
+    /// Inserts a value after the node at the index.
+    /// Returns val as Err if the index is not valid.
+    ///
+    pub fn insert_after(&mut self, index: ListIdx<T>, val: T) -> Result<ListIdx<T>, T>{
+        match self.nodes.get(index){
+            Some(node) => {
+                let next = node.next;
+                Ok(self.link(Some(index), next, val))
+            }
+            None => Err(val),
+        }
+    }
+
+    ///
+    /// Inserts a value before the node at the index.
+    /// Returns val as Err if the index is not valid.
+    ///
+    pub fn insert_before(&mut self, index: ListIdx<T>, val: T) -> Result<ListIdx<T>, T>{
+        match self.nodes.get(index){
+            Some(node) => {
+                let prev = node.prev;
+                Ok(self.link(prev, Some(index), val))
+            }
+            None => Err(val),
+        }
+    }
+
+    ///
+    /// Removes the node at the index from the list.
+    /// Returns the value if the index is still valid.
+    ///
+    pub fn remove(&mut self, index: ListIdx<T>) -> Option<T>{
+        self.nodes.get(index)?;
+        self.unlink_range(index, index);
+        self.nodes.remove(index).map(|node| node.val)
+    }
+
+    ///
+    /// Removes the first node of the list and returns its value.
+    ///
+    pub fn pop_front(&mut self) -> Option<T>{
+        self.remove(self.head?)
+    }
+
+    ///
+    /// Removes the last node of the list and returns its value.
+    ///
+    pub fn pop_back(&mut self) -> Option<T>{
+        self.remove(self.tail?)
+    }
+
+    ///
+    /// Moves the nodes first..=last to after the node at.
+    ///
+    /// Runs in O(1). first..=last has to be a range of the list in forward order that does not contain at.
+    /// This is only checked in debug builds.
+    /// Returns false and leaves the list untouched if any index is not valid.
+    ///
+    /// ```rust
+    /// use gen_arena::*;
+    ///
+    /// let mut list = ArenaList::new();
+    /// let keys: Vec<_> = (0..6).map(|i| list.push_back(i)).collect();
+    ///
+    /// assert!(list.splice_after(keys[4], keys[1], keys[2]));
+    /// assert_eq!(list.iter().copied().collect::<Vec<_>>(), vec![0, 3, 4, 1, 2, 5]);
+    /// ```
+    ///
+    pub fn splice_after(&mut self, at: ListIdx<T>, first: ListIdx<T>, last: ListIdx<T>) -> bool{
+        if !self.contains(at) || !self.contains(first) || !self.contains(last){
+            return false;
+        }
+        debug_assert!(self.is_range_without(first, last, at), "Invalid range for splicing.");
+        self.unlink_range(first, last);
+        let next = self.nodes[at].next;
+        self.link_range(Some(at), next, first, last);
+        true
+    }
+
+    ///
+    /// Moves the nodes first..=last to before the node at.
+    /// See splice_after for the requirements.
+    ///
+    pub fn splice_before(&mut self, at: ListIdx<T>, first: ListIdx<T>, last: ListIdx<T>) -> bool{
+        if !self.contains(at) || !self.contains(first) || !self.contains(last){
+            return false;
+        }
+        debug_assert!(self.is_range_without(first, last, at), "Invalid range for splicing.");
+        self.unlink_range(first, last);
+        let prev = self.nodes[at].prev;
+        self.link_range(prev, Some(at), first, last);
+        true
+    }
+
+    ///
+    /// Returns true if last can be reached from first without passing at.
+    ///
+    fn is_range_without(&self, first: ListIdx<T>, last: ListIdx<T>, at: ListIdx<T>) -> bool{
+        let mut current = Some(first);
+        while let Some(index) = current{
+            if index == at{
+                return false;
+            }
+            if index == last{
+                return true;
+            }
+            current = self.nodes[index].next;
+        }
+        false
+    }
+
+    ///
+    /// Returns true if the node at the index is in the list.
+    ///
+    #[inline]
+    pub fn contains(&self, index: ListIdx<T>) -> bool{
+        self.nodes.get(index).is_some()
+    }
+
+    ///
+    /// Returns an optional reference to the value of the node at the index.
+    ///
+    #[inline]
+    pub fn get(&self, index: ListIdx<T>) -> Option<&T>{
+        self.nodes.get(index).map(|node| &node.val)
+    }
+
+    ///
+    /// Returns a mutable optional reference to the value of the node at the index.
+    ///
+    #[inline]
+    pub fn get_mut(&mut self, index: ListIdx<T>) -> Option<&mut T>{
+        self.nodes.get_mut(index).map(|node| &mut node.val)
+    }
+
+    ///
+    /// Returns the index of the first node.
+    ///
+    #[inline]
+    pub fn front(&self) -> Option<ListIdx<T>>{
+        self.head
+    }
+
+    ///
+    /// Returns the index of the last node.
+    ///
+    #[inline]
+    pub fn back(&self) -> Option<ListIdx<T>>{
+        self.tail
+    }
+
+    ///
+    /// Returns the index of the node following the node at the index.
+    ///
+    #[inline]
+    pub fn next(&self, index: ListIdx<T>) -> Option<ListIdx<T>>{
+        self.nodes.get(index)?.next
+    }
+
+    ///
+    /// Returns the index of the node preceding the node at the index.
+    ///
+    #[inline]
+    pub fn prev(&self, index: ListIdx<T>) -> Option<ListIdx<T>>{
+        self.nodes.get(index)?.prev
+    }
+
+    ///
+    /// Returns a cursor pointing at the first node.
+    ///
+    #[inline]
+    pub fn cursor_front(&self) -> Cursor<'_, T>{
+        Cursor{
+            list: self,
+            current: self.head,
+        }
+    }
+
+    ///
+    /// Returns a cursor pointing at the node at the index.
+    ///
+    #[inline]
+    pub fn cursor_at(&self, index: ListIdx<T>) -> Cursor<'_, T>{
+        Cursor{
+            list: self,
+            current: self.nodes.get(index).map(|_| index),
+        }
+    }
+
+    ///
+    /// Returns a mutable cursor pointing at the first node.
+    ///
+    #[inline]
+    pub fn cursor_front_mut(&mut self) -> CursorMut<'_, T>{
+        CursorMut{
+            current: self.head,
+            list: self,
+        }
+    }
+
+    ///
+    /// Returns a mutable cursor pointing at the node at the index.
+    ///
+    #[inline]
+    pub fn cursor_at_mut(&mut self, index: ListIdx<T>) -> CursorMut<'_, T>{
+        CursorMut{
+            current: self.nodes.get(index).map(|_| index),
+            list: self,
+        }
+    }
+
+    ///
+    /// Returns an iterator over the values in list order.
+    ///
+    #[inline]
+    pub fn iter(&self) -> ListIter<'_, T>{
+        ListIter{
+            list: self,
+            front: self.head,
+            back: self.tail,
+        }
+    }
+
+    ///
+    /// Returns an iterator over the node indices and values in list order.
+    ///
+    #[inline]
+    pub fn iter_with_keys(&self) -> impl DoubleEndedIterator<Item = (ListIdx<T>, &T)> + '_{
+        self.keys().map(|index| (index, &self.nodes[index].val))
+    }
+
+    ///
+    /// Returns an iterator over the node indices in list order.
+    ///
+    #[inline]
+    pub fn keys(&self) -> ListKeys<'_, T>{
+        ListKeys{
+            list: self,
+            front: self.head,
+            back: self.tail,
+        }
+    }
+
+    ///
+    /// Returns a mutable iterator over the values in list order.
+    /// The references are collected up front, which allocates once.
+    ///
+    pub fn iter_mut(&mut self) -> ListIterMut<'_, T>{
+        let order: Vec<_> = self.keys().map(|index| index.index()).collect();
+        let mut slots: Vec<Option<&mut ListNode<T>>> = Vec::new();
+        for (index, node) in self.nodes.iter_mut(){
+            if slots.len() <= index.index(){
+                slots.resize_with(index.index() + 1, || None);
+            }
+            slots[index.index()] = Some(node);
+        }
+        let nodes: Vec<_> = order.into_iter()
+            .map(|i| slots[i].take().expect("Every linked node is in the arena exactly once."))
+            .collect();
+        ListIterMut{
+            iter: nodes.into_iter(),
+        }
+    }
+
+    ///
+    /// Removes all nodes.
+    ///
+    pub fn clear(&mut self){
+        self.nodes.clear();
+        self.head = None;
+        self.tail = None;
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize{
+        self.nodes.num()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool{
+        self.head.is_none()
+    }
+}
+
+impl<T> Default for ArenaList<T>{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Index<ListIdx<T>> for ArenaList<T>{
+    type Output = T;
+
+    fn index(&self, index: ListIdx<T>) -> &Self::Output {
+        self.get(index).expect("There is no node at this index with that generation.")
+    }
+}
+
+impl<T> IndexMut<ListIdx<T>> for ArenaList<T>{
+    fn index_mut(&mut self, index: ListIdx<T>) -> &mut Self::Output {
+        self.get_mut(index).expect("There is no node at this index with that generation.")
+    }
+}
+
+impl<'i, T> IntoIterator for &'i ArenaList<T>{
+    type Item = &'i T;
+    type IntoIter = ListIter<'i, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<T> FromIterator<T> for ArenaList<T>{
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut list = Self::new();
+        for val in iter{
+            let _ = list.push_back(val);
+        }
+        list
+    }
+}
+
+pub struct ListKeys<'i, T: 'i>{
+    list: &'i ArenaList<T>,
+    front: Option<ListIdx<T>>,
+    back: Option<ListIdx<T>>,
+}
+
+impl<'i, T> Iterator for ListKeys<'i, T>{
+    type Item = ListIdx<T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let index = self.front?;
+        if Some(index) == self.back{
+            self.front = None;
+            self.back = None;
+        }
+        else{
+            self.front = self.list.nodes[index].next;
+        }
+        Some(index)
+    }
+}
+
+impl<'i, T> DoubleEndedIterator for ListKeys<'i, T>{
+    fn next_back(&mut self) -> Option<Self::Item> {
+        let index = self.back?;
+        if Some(index) == self.front{
+            self.front = None;
+            self.back = None;
+        }
+        else{
+            self.back = self.list.nodes[index].prev;
+        }
+        Some(index)
+    }
+}
+
+pub struct ListIter<'i, T: 'i>{
+    list: &'i ArenaList<T>,
+    front: Option<ListIdx<T>>,
+    back: Option<ListIdx<T>>,
+}
+
+impl<'i, T> ListIter<'i, T>{
+    #[inline]
+    fn keys(&mut self) -> ListKeys<'i, T>{
+        ListKeys{
+            list: self.list,
+            front: self.front,
+            back: self.back,
+        }
+    }
+}
+
+impl<'i, T> Iterator for ListIter<'i, T>{
+    type Item = &'i T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut keys = self.keys();
+        let index = keys.next()?;
+        (self.front, self.back) = (keys.front, keys.back);
+        Some(&self.list.nodes[index].val)
+    }
+}
+
+impl<'i, T> DoubleEndedIterator for ListIter<'i, T>{
+    fn next_back(&mut self) -> Option<Self::Item> {
+        let mut keys = self.keys();
+        let index = keys.next_back()?;
+        (self.front, self.back) = (keys.front, keys.back);
+        Some(&self.list.nodes[index].val)
+    }
+}
+
+pub struct ListIterMut<'i, T: 'i>{
+    iter: std::vec::IntoIter<&'i mut ListNode<T>>,
+}
+
+impl<'i, T> Iterator for ListIterMut<'i, T>{
+    type Item = &'i mut T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.iter.next().map(|node| &mut node.val)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+impl<'i, T> DoubleEndedIterator for ListIterMut<'i, T>{
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.iter.next_back().map(|node| &mut node.val)
+    }
+}
+
+///
+/// A cursor over an ArenaList.
+///
+/// ```rust
+/// use gen_arena::*;
+///
+/// let list: ArenaList<_> = (0..4).collect();
+///
+/// let mut cursor = list.cursor_front();
+/// cursor.move_next();
+/// cursor.move_next();
+/// assert_eq!(cursor.current(), Some(&2));
+/// cursor.move_prev();
+/// assert_eq!(cursor.current(), Some(&1));
+/// assert_eq!(cursor.peek_next(), Some(&2));
+/// ```
+///
+pub struct Cursor<'i, T: 'i>{
+    list: &'i ArenaList<T>,
+    current: Option<ListIdx<T>>,
+}
+
+impl<'i, T> Cursor<'i, T>{
+    ///
+    /// Returns the index of the node the cursor points at.
+    /// None if the cursor moved past either end of the list.
+    ///
+    #[inline]
+    pub fn index(&self) -> Option<ListIdx<T>>{
+        self.current
+    }
+
+    #[inline]
+    pub fn current(&self) -> Option<&'i T>{
+        self.list.get(self.current?)
+    }
+
+    #[inline]
+    pub fn peek_next(&self) -> Option<&'i T>{
+        self.list.get(self.list.next(self.current?)?)
+    }
+
+    #[inline]
+    pub fn peek_prev(&self) -> Option<&'i T>{
+        self.list.get(self.list.prev(self.current?)?)
+    }
+
+    ///
+    /// Moves to the next node. Returns false if there is none.
+    ///
+    pub fn move_next(&mut self) -> bool{
+        self.current = self.current.and_then(|index| self.list.next(index));
+        self.current.is_some()
+    }
+
+    ///
+    /// Moves to the previous node. Returns false if there is none.
+    ///
+    pub fn move_prev(&mut self) -> bool{
+        self.current = self.current.and_then(|index| self.list.prev(index));
+        self.current.is_some()
+    }
+}
+
+///
+/// A cursor over an ArenaList that can modify the list.
+///
+/// ```rust
+/// use gen_arena::*;
+///
+/// let mut list: ArenaList<_> = (0..5).collect();
+///
+/// // Remove all odd values and duplicate the even ones.
+/// let mut cursor = list.cursor_front_mut();
+/// while let Some(val) = cursor.current().copied(){
+///     if val % 2 == 1{
+///         cursor.remove_current();
+///     }
+///     else{
+///         cursor.insert_after(val);
+///         cursor.move_next();
+///         cursor.move_next();
+///     }
+/// }
+///
+/// assert_eq!(list.iter().copied().collect::<Vec<_>>(), vec![0, 0, 2, 2, 4, 4]);
+/// ```
+///
+pub struct CursorMut<'i, T: 'i>{
+    list: &'i mut ArenaList<T>,
+    current: Option<ListIdx<T>>,
+}
+
+impl<'i, T> CursorMut<'i, T>{
+    #[inline]
+    pub fn index(&self) -> Option<ListIdx<T>>{
+        self.current
+    }
+
+    #[inline]
+    pub fn current(&mut self) -> Option<&mut T>{
+        self.list.get_mut(self.current?)
+    }
+
+    pub fn move_next(&mut self) -> bool{
+        self.current = self.current.and_then(|index| self.list.next(index));
+        self.current.is_some()
+    }
+
+    pub fn move_prev(&mut self) -> bool{
+        self.current = self.current.and_then(|index| self.list.prev(index));
+        self.current.is_some()
+    }
+
+    ///
+    /// Inserts a value after the current node, or at the front if the cursor points at no node.
+    ///
+    pub fn insert_after(&mut self, val: T) -> ListIdx<T>{
+        match self.current{
+            Some(index) => self.list.insert_after(index, val).ok().expect("The cursor always points at a valid node."),
+            None => self.list.push_front(val),
+        }
+    }
+
+    ///
+    /// Inserts a value before the current node, or at the back if the cursor points at no node.
+    ///
+    pub fn insert_before(&mut self, val: T) -> ListIdx<T>{
+        match self.current{
+            Some(index) => self.list.insert_before(index, val).ok().expect("The cursor always points at a valid node."),
+            None => self.list.push_back(val),
+        }
+    }
+
+    ///
+    /// Removes the current node and moves the cursor to the next node.
+    ///
+    pub fn remove_current(&mut self) -> Option<T>{
+        let index = self.current?;
+        self.current = self.list.next(index);
+        self.list.remove(index)
+    }
+}
+
+#[cfg(test)]
+mod test{
+    use super::*;
+
+    struct Rng(u64);
+
+    impl Rng{
+        fn next(&mut self, n: usize) -> usize{
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            (self.0 % n as u64) as usize
+        }
+    }
+
+    fn check(list: &ArenaList<usize>, model: &[(ListIdx<usize>, usize)]){
+        let keys: Vec<_> = list.keys().collect();
+        let expected: Vec<_> = model.iter().map(|(key, _)| *key).collect();
+        assert_eq!(keys, expected);
+        let vals: Vec<_> = list.iter().copied().collect();
+        let expected: Vec<_> = model.iter().map(|(_, val)| *val).collect();
+        assert_eq!(vals, expected);
+        let mut rev: Vec<_> = list.iter().rev().copied().collect();
+        rev.reverse();
+        assert_eq!(rev, expected);
+        assert_eq!(list.len(), model.len());
+        assert_eq!(list.front(), model.first().map(|(key, _)| *key));
+        assert_eq!(list.back(), model.last().map(|(key, _)| *key));
+    }
+
+    #[test]
+    fn test_churn_and_splice(){
+        let mut rng = Rng(0x2545F4914F6CDD1D);
+        let mut list = ArenaList::new();
+        let mut model: Vec<(ListIdx<usize>, usize)> = Vec::new();
+        let mut removed = Vec::new();
+
+        for val in 0..5000{
+            match rng.next(6){
+                0 => model.push((list.push_back(val), val)),
+                1 => model.insert(0, (list.push_front(val), val)),
+                2 if !model.is_empty() => {
+                    let i = rng.next(model.len());
+                    let key = list.insert_after(model[i].0, val).unwrap();
+                    model.insert(i + 1, (key, val));
+                }
+                3 if !model.is_empty() => {
+                    let (key, val) = model.remove(rng.next(model.len()));
+                    assert_eq!(list.remove(key), Some(val));
+                    removed.push(key);
+                }
+                4 | 5 if model.len() > 2 => {
+                    // Splice a random range to after or before a node outside of it.
+                    let first = rng.next(model.len());
+                    let last = first + rng.next(model.len() - first);
+                    if last - first + 1 == model.len(){
+                        continue;
+                    }
+                    let range: Vec<_> = model.drain(first..=last).collect();
+                    let at = rng.next(model.len());
+                    let (first, last) = (range[0].0, range[range.len() - 1].0);
+                    if val % 2 == 0{
+                        assert!(list.splice_after(model[at].0, first, last));
+                        model.splice(at + 1..at + 1, range);
+                    }
+                    else{
+                        assert!(list.splice_before(model[at].0, first, last));
+                        model.splice(at..at, range);
+                    }
+                }
+                _ => {}
+            }
+            if val % 97 == 0{
+                check(&list, &model);
+            }
+        }
+        check(&list, &model);
+
+        // Handles survived all unrelated operations, removed ones are stale.
+        for (key, val) in model.iter(){
+            assert_eq!(list[*key], *val);
+        }
+        for key in removed{
+            assert_eq!(list.get(key), None);
+            assert!(!list.splice_after(key, key, key));
+        }
+
+        for val in list.iter_mut(){
+            *val += 1;
+        }
+        assert!(list.iter().zip(model.iter()).all(|(a, (_, b))| *a == b + 1));
+
+        while list.pop_front().is_some(){}
+        assert!(list.is_empty());
+        check(&list, &[]);
+    }
+}
@@ -0,0 +1,278 @@
+use std::{ops::{Deref, DerefMut, Index, IndexMut}, rc::{Rc, Weak}};
+
+use crate::*;
+
+///
+/// An owning, reference counted handle to an element of an ArenaRc.
+///
+/// Cloning a StrongIdx increases the reference count of its slot.
+/// Once the last StrongIdx of a slot is dropped, the slot is freed by the next call to ArenaRc::collect.
+///
+pub struct StrongIdx<T>{
+    index: ArenaIdx<T>,
+    count: Rc<()>,
+}
+
+impl<T> StrongIdx<T>{
+    ///
+    /// Returns the plain, non owning key of the element.
+    ///
+    #[inline]
+    pub fn idx(&self) -> ArenaIdx<T>{
+        self.index
+    }
+
+    ///
+    /// Returns the number of StrongIdx handles referring to the element.
+    ///
+    #[inline]
+    pub fn strong_count(&self) -> usize{
+        Rc::strong_count(&self.count)
+    }
+}
+
+impl<T> Clone for StrongIdx<T>{
+    #[inline]
+    fn clone(&self) -> Self {
+        Self{
+            index: self.index,
+            count: self.count.clone(),
+        }
+    }
+}
+
+impl<T> PartialEq for StrongIdx<T>{
+    fn eq(&self, other: &Self) -> bool {
+        self.index == other.index
+    }
+}
+
+impl<T> Eq for StrongIdx<T>{}
+
+impl<T> std::fmt::Debug for StrongIdx<T>{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("StrongIdx")
+            .field("index", &self.index.index())
+            .field("generation", &self.index.gen())
+            .field("strong_count", &self.strong_count())
+            .finish()
+    }
+}
+
+impl<T> From<&StrongIdx<T>> for ArenaIdx<T>{
+    #[inline]
+    fn from(index: &StrongIdx<T>) -> Self {
+        index.index
+    }
+}
+
+///
+/// An Arena whose elements can be owned by reference counted StrongIdx handles.
+///
+/// Elements inserted with insert_strong live as long as at least one StrongIdx refers to them.
+/// Dropping the last handle only marks the slot, it is freed by ArenaRc::collect,
+/// which needs mutable access to the arena.
+/// Plain ArenaIdx keys keep working for non owning access through Deref to Arena.
+///
+/// Reference cycles between elements holding StrongIdx handles to each other are never collected,
+/// just like cycles of Rc. Use plain ArenaIdx keys or WeakIdx for back references.
+///
+/// # Example
+///
+///```rust
+/// use gen_arena::*;
+///
+/// let mut arena = ArenaRc::new();
+///
+/// let a = arena.insert_strong(1);
+/// let b = a.clone();
+/// let key = a.idx();
+///
+/// drop(a);
+/// assert_eq!(ArenaRc::collect(&mut arena), 0);
+/// assert_eq!(arena[&b], 1);
+///
+/// drop(b);
+/// assert_eq!(ArenaRc::collect(&mut arena), 1);
+/// assert_eq!(arena.get(key), None);
+///```
+///
+#[derive(Debug)]
+pub struct ArenaRc<T>{
+    arena: Arena<T>,
+    strong: Vec<(ArenaIdx<T>, Weak<()>)>,
+}
+
+impl<T> ArenaRc<T>{
+    ///
+    /// Creates an empty ArenaRc.
+    ///
+    pub fn new() -> Self{
+        Self{
+            arena: Arena::new(),
+            strong: Vec::new(),
+        }
+    }
+
+    ///
+    /// Inserts a new element owned by the returned StrongIdx.
+    ///
+    #[must_use]
+    pub fn insert_strong(&mut self, val: T) -> StrongIdx<T>{
+        let index = self.arena.insert(val);
+        let count = Rc::new(());
+        self.strong.push((index, Rc::downgrade(&count)));
+        StrongIdx{
+            index,
+            count,
+        }
+    }
+
+    ///
+    /// Frees all slots that are no longer referenced by a StrongIdx.
+    /// Elements freed this way drop the handles they own, so this repeats until nothing else can be freed.
+    /// Returns the number of freed elements.
+    ///
+    pub fn collect(&mut self) -> usize{
+        let mut freed = 0;
+        loop{
+            let arena = &mut self.arena;
+            let mut removed = Vec::new();
+            self.strong.retain(|(index, count)|{
+                if count.strong_count() > 0{
+                    true
+                }
+                else{
+                    removed.extend(arena.remove(*index));
+                    false
+                }
+            });
+            if removed.is_empty(){
+                return freed;
+            }
+            freed += removed.len();
+            // Dropping the values may release further handles.
+            drop(removed);
+        }
+    }
+
+    ///
+    /// Returns an optional reference to the element owned by the handle.
+    /// Only returns None if the element was removed through a plain key.
+    ///
+    #[inline]
+    pub fn get_strong(&self, index: &StrongIdx<T>) -> Option<&T>{
+        self.arena.get(index.index)
+    }
+
+    ///
+    /// Returns an optional mutable reference to the element owned by the handle.
+    ///
+    #[inline]
+    pub fn get_strong_mut(&mut self, index: &StrongIdx<T>) -> Option<&mut T>{
+        self.arena.get_mut(index.index)
+    }
+}
+
+impl<T> Default for ArenaRc<T>{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Deref for ArenaRc<T>{
+    type Target = Arena<T>;
+
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        &self.arena
+    }
+}
+
+impl<T> DerefMut for ArenaRc<T>{
+    #[inline]
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.arena
+    }
+}
+
+impl<T> Index<ArenaIdx<T>> for ArenaRc<T>{
+    type Output = T;
+
+    fn index(&self, index: ArenaIdx<T>) -> &Self::Output {
+        &self.arena[index]
+    }
+}
+
+impl<T> IndexMut<ArenaIdx<T>> for ArenaRc<T>{
+    fn index_mut(&mut self, index: ArenaIdx<T>) -> &mut Self::Output {
+        &mut self.arena[index]
+    }
+}
+
+impl<T> Index<&StrongIdx<T>> for ArenaRc<T>{
+    type Output = T;
+
+    fn index(&self, index: &StrongIdx<T>) -> &Self::Output {
+        self.get_strong(index).expect("There is no element at this index with that generation.")
+    }
+}
+
+impl<T> IndexMut<&StrongIdx<T>> for ArenaRc<T>{
+    fn index_mut(&mut self, index: &StrongIdx<T>) -> &mut Self::Output {
+        self.get_strong_mut(index).expect("There is no element at this index with that generation.")
+    }
+}
+
+#[cfg(test)]
+mod test{
+    use super::*;
+
+    struct Node{
+        children: Vec<StrongIdx<Node>>,
+    }
+
+    #[test]
+    fn test_tree_is_collected(){
+        let mut arena = ArenaRc::new();
+
+        let leaf = arena.insert_strong(Node{children: Vec::new()});
+        let leaf_key = leaf.idx();
+        let inner = arena.insert_strong(Node{children: vec![leaf.clone(), leaf]});
+        let root = arena.insert_strong(Node{children: vec![inner]});
+
+        // A plain value next to the reference counted ones is never collected.
+        let plain = arena.insert(Node{children: Vec::new()});
+
+        assert_eq!(arena.collect(), 0);
+        assert_eq!(arena[&root].children.len(), 1);
+        assert_eq!(arena[&root].children[0].strong_count(), 1);
+        assert_eq!(arena[&arena[&root].children[0]].children[0].strong_count(), 2);
+
+        // Freeing the root releases the handles it owns, so the whole tree is collected.
+        drop(root);
+        assert!(arena.get(leaf_key).is_some());
+        assert_eq!(arena.collect(), 3);
+        assert!(arena.get(leaf_key).is_none());
+        assert_eq!(arena.collect(), 0);
+
+        assert_eq!(arena.num(), 1);
+        assert!(arena.get(plain).is_some());
+    }
+
+    #[test]
+    fn test_removed_through_plain_key(){
+        let mut arena = ArenaRc::new();
+
+        let a = arena.insert_strong(0);
+        assert_eq!(arena.remove(a.idx()), Some(0));
+        let b = arena.insert(1);
+        assert_eq!(b.index(), a.idx().index());
+
+        // The stale handle does not free the new occupant of its slot.
+        assert_eq!(arena.get_strong(&a), None);
+        drop(a);
+        assert_eq!(arena.collect(), 0);
+        assert_eq!(arena[b], 1);
+    }
+}
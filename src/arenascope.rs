@@ -0,0 +1,164 @@
+use std::ops::Deref;
+
+use crate::*;
+
+///
+/// Access to an Arena during Arena::scope, removing the elements inserted through it when the scope ends.
+///
+/// Derefs to the arena for reading. Elements inserted with insert are removed when the closure returns
+/// or panics, unless they were removed before or kept with keep.
+///
+pub struct ArenaScope<'a, T, G: GenCounter = usize, Tag = ()>{
+    arena: &'a mut Arena<T, G, Tag>,
+    inserted: Vec<ArenaIdx<T, G, Tag>>,
+}
+
+impl<T, G: GenCounter, Tag> ArenaScope<'_, T, G, Tag>{
+    ///
+    /// Inserts a new element that is removed at the end of the scope.
+    ///
+    #[must_use]
+    pub fn insert(&mut self, val: T) -> ArenaIdx<T, G, Tag>{
+        let index = self.arena.insert(val);
+        self.inserted.push(index);
+        index
+    }
+
+    ///
+    /// Keeps the element inserted through the scope in the arena when the scope ends.
+    /// Returns false if the key was not inserted through the scope or is already kept.
+    ///
+    pub fn keep(&mut self, index: ArenaIdx<T, G, Tag>) -> bool{
+        match self.inserted.iter().position(|&inserted| inserted == index){
+            Some(i) => {
+                self.inserted.swap_remove(i);
+                true
+            }
+            None => false,
+        }
+    }
+
+    #[inline]
+    pub fn get_mut(&mut self, index: ArenaIdx<T, G, Tag>) -> Option<&mut T>{
+        self.arena.get_mut(index)
+    }
+
+    ///
+    /// Removes any element of the arena, not only the ones inserted through the scope.
+    ///
+    #[inline]
+    pub fn remove(&mut self, index: ArenaIdx<T, G, Tag>) -> Option<T>{
+        self.arena.remove(index)
+    }
+}
+
+impl<T, G: GenCounter, Tag> Deref for ArenaScope<'_, T, G, Tag>{
+    type Target = Arena<T, G, Tag>;
+
+    fn deref(&self) -> &Self::Target {
+        self.arena
+    }
+}
+
+// Runs when the closure returns and while unwinding from a panic in it.
+impl<T, G: GenCounter, Tag> Drop for ArenaScope<'_, T, G, Tag>{
+    fn drop(&mut self){
+        for index in self.inserted.drain(..){
+            self.arena.remove(index);
+        }
+    }
+}
+
+impl<T, G: GenCounter, Tag> Arena<T, G, Tag>{
+    ///
+    /// Runs f with an ArenaScope and removes every element inserted through it when f returns or panics,
+    /// except the ones kept with ArenaScope::keep.
+    ///
+    /// ```rust
+    /// use gen_arena::*;
+    ///
+    /// let mut arena = Arena::new();
+    /// let mesh = arena.insert("mesh");
+    ///
+    /// let outline = arena.scope(|frame|{
+    ///     let _ = frame.insert("debug line");
+    ///     let outline = frame.insert("outline");
+    ///     frame.keep(outline);
+    ///     assert_eq!(frame.num(), 3);
+    ///     outline
+    /// });
+    ///
+    /// assert_eq!(arena.values().collect::<Vec<_>>(), [&"mesh", &"outline"]);
+    /// assert_eq!(arena[mesh], "mesh");
+    /// assert_eq!(arena[outline], "outline");
+    /// ```
+    ///
+    pub fn scope<R>(&mut self, f: impl FnOnce(&mut ArenaScope<'_, T, G, Tag>) -> R) -> R{
+        let mut scope = ArenaScope{
+            arena: self,
+            inserted: Vec::new(),
+        };
+        f(&mut scope)
+    }
+}
+
+#[cfg(test)]
+mod test{
+    use std::{cell::Cell, panic::{self, AssertUnwindSafe}, rc::Rc};
+
+    use super::*;
+
+    struct Counted(Rc<Cell<usize>>);
+
+    impl Drop for Counted{
+        fn drop(&mut self){
+            self.0.set(self.0.get() + 1);
+        }
+    }
+
+    #[test]
+    fn test_scope(){
+        let dropped = Rc::new(Cell::new(0));
+        let mut arena = Arena::new();
+        let outside = arena.insert(Counted(dropped.clone()));
+
+        let (kept, removed) = arena.scope(|scope|{
+            let keys: Vec<_> = (0..4).map(|_| scope.insert(Counted(dropped.clone()))).collect();
+            assert!(scope.keep(keys[1]));
+            assert!(!scope.keep(keys[1]));
+            assert!(!scope.keep(outside));
+
+            // Removed elements are not removed again, even if their slot is reused.
+            scope.remove(keys[2]);
+            assert_eq!(dropped.get(), 1);
+            let _ = scope.insert(Counted(dropped.clone()));
+            assert_eq!(scope.num(), 5);
+            (keys[1], keys[2])
+        });
+        assert_eq!(dropped.get(), 4);
+        assert_eq!(arena.num(), 2);
+        assert!(arena.get(outside).is_some() && arena.get(kept).is_some());
+        assert!(arena.get(removed).is_none());
+    }
+
+    #[test]
+    fn test_scope_unwinding(){
+        let dropped = Rc::new(Cell::new(0));
+        let mut arena = Arena::new();
+        let mut kept = None;
+
+        let result = panic::catch_unwind(AssertUnwindSafe(|| arena.scope(|scope|{
+            for i in 0..8{
+                let key = scope.insert(Counted(dropped.clone()));
+                if i == 3{
+                    scope.keep(key);
+                    kept = Some(key);
+                }
+            }
+            panic!("frame failed");
+        })));
+        assert!(result.is_err());
+        assert_eq!(dropped.get(), 7);
+        assert_eq!(arena.keys().collect::<Vec<_>>(), [kept.unwrap()]);
+    }
+}
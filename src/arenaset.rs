@@ -0,0 +1,355 @@
+use std::marker::PhantomData;
+
+use crate::*;
+
+const BITS: usize = u64::BITS as usize;
+
+///
+/// A set of keys of an Arena.
+///
+/// Stores one bit and one generation per slot up to the highest slot in the set, so membership
+/// tests do not hash and iteration yields the keys in slot order. A slot holds at most one key;
+/// inserting a key of another generation replaces the old one.
+/// Keys whose slots have been reused by the arena can be purged with retain_valid.
+///
+/// # Example
+///
+///```rust
+/// use gen_arena::*;
+///
+/// let mut arena = Arena::new();
+/// let a = arena.insert("a");
+/// let b = arena.insert("b");
+/// let c = arena.insert("c");
+///
+/// let mut selected = ArenaSet::new();
+/// selected.insert(c);
+/// selected.insert(a);
+///
+/// assert!(selected.contains(a));
+/// assert!(!selected.contains(b));
+/// assert_eq!(selected.iter().collect::<Vec<_>>(), [a, c]);
+///
+/// // The slot of a is reused, so a is no longer valid.
+/// arena.remove(a);
+/// let d = arena.insert("d");
+/// selected.retain_valid(&arena);
+/// assert!(!selected.contains(d));
+/// assert_eq!(selected.len(), 1);
+///```
+///
+pub struct ArenaSet<T>{
+    bits: Vec<u64>,
+    gens: Vec<usize>,
+    len: usize,
+    _ty: PhantomData<fn() -> T>,
+}
+
+impl<T> ArenaSet<T>{
+    ///
+    /// Creates an empty ArenaSet.
+    ///
+    pub fn new() -> Self{
+        Self{
+            bits: Vec::new(),
+            gens: Vec::new(),
+            len: 0,
+            _ty: PhantomData,
+        }
+    }
+
+    #[inline]
+    fn has_slot(&self, slot: usize) -> bool{
+        self.bits.get(slot / BITS).is_some_and(|word| word & (1 << (slot % BITS)) != 0)
+    }
+
+    ///
+    /// Inserts the key. Returns false if the set already contained it.
+    ///
+    pub fn insert(&mut self, index: ArenaIdx<T>) -> bool{
+        let slot = index.index();
+        if self.has_slot(slot){
+            let old = std::mem::replace(&mut self.gens[slot], index.gen());
+            return old != index.gen();
+        }
+        if slot >= self.gens.len(){
+            self.gens.resize(slot + 1, 0);
+            self.bits.resize(slot / BITS + 1, 0);
+        }
+        self.bits[slot / BITS] |= 1 << (slot % BITS);
+        self.gens[slot] = index.gen();
+        self.len += 1;
+        true
+    }
+
+    fn remove_slot(&mut self, slot: usize){
+        self.bits[slot / BITS] &= !(1 << (slot % BITS));
+        self.len -= 1;
+    }
+
+    ///
+    /// Removes the key. Returns false if the set did not contain it.
+    ///
+    pub fn remove(&mut self, index: ArenaIdx<T>) -> bool{
+        if self.contains(index){
+            self.remove_slot(index.index());
+            true
+        }
+        else{
+            false
+        }
+    }
+
+    ///
+    /// Returns true if the set contains the key with its generation.
+    ///
+    #[inline]
+    pub fn contains(&self, index: ArenaIdx<T>) -> bool{
+        self.has_slot(index.index()) && self.gens[index.index()] == index.gen()
+    }
+
+    ///
+    /// Returns an iterator over the keys in slot order.
+    ///
+    pub fn iter(&self) -> ArenaSetIter<'_, T>{
+        ArenaSetIter{
+            set: self,
+            word: 0,
+            bits: self.bits.first().copied().unwrap_or(0),
+        }
+    }
+
+    ///
+    /// Inserts all keys of other. Where both sets contain a key for the same slot,
+    /// the one with the higher, newer, generation is kept.
+    ///
+    pub fn union_with(&mut self, other: &Self){
+        for index in other.iter(){
+            let slot = index.index();
+            if !self.has_slot(slot) || self.gens[slot] < index.gen(){
+                self.insert(index);
+            }
+        }
+    }
+
+    ///
+    /// Removes all keys not contained in other.
+    ///
+    pub fn intersect_with(&mut self, other: &Self){
+        self.retain(|index| other.contains(index));
+    }
+
+    ///
+    /// Removes all keys contained in other.
+    ///
+    pub fn difference_with(&mut self, other: &Self){
+        for index in other.iter(){
+            self.remove(index);
+        }
+    }
+
+    ///
+    /// Removes all keys for which f returns false.
+    ///
+    pub fn retain(&mut self, mut f: impl FnMut(ArenaIdx<T>) -> bool){
+        let remove: Vec<_> = self.iter().filter(|index| !f(*index)).collect();
+        for index in remove{
+            self.remove_slot(index.index());
+        }
+    }
+
+    ///
+    /// Removes all keys that no longer refer to an element of the arena.
+    ///
+    pub fn retain_valid(&mut self, arena: &Arena<T>){
+        self.retain(|index| arena.idx_at(index.index()) == Some(index));
+    }
+
+    ///
+    /// Removes all keys and frees the memory of the set.
+    ///
+    pub fn clear(&mut self){
+        self.bits.clear();
+        self.gens.clear();
+        self.len = 0;
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize{
+        self.len
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool{
+        self.len == 0
+    }
+}
+
+impl<T> Default for ArenaSet<T>{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Clone for ArenaSet<T>{
+    fn clone(&self) -> Self {
+        Self{
+            bits: self.bits.clone(),
+            gens: self.gens.clone(),
+            len: self.len,
+            _ty: PhantomData,
+        }
+    }
+}
+
+impl<T> PartialEq for ArenaSet<T>{
+    fn eq(&self, other: &Self) -> bool {
+        self.len == other.len && self.iter().all(|index| other.contains(index))
+    }
+}
+
+impl<T> Eq for ArenaSet<T>{}
+
+impl<T> std::fmt::Debug for ArenaSet<T>{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_set().entries(self.iter()).finish()
+    }
+}
+
+impl<T> FromIterator<ArenaIdx<T>> for ArenaSet<T>{
+    fn from_iter<I: IntoIterator<Item = ArenaIdx<T>>>(iter: I) -> Self {
+        let mut set = Self::new();
+        set.extend(iter);
+        set
+    }
+}
+
+impl<T> Extend<ArenaIdx<T>> for ArenaSet<T>{
+    fn extend<I: IntoIterator<Item = ArenaIdx<T>>>(&mut self, iter: I) {
+        for index in iter{
+            self.insert(index);
+        }
+    }
+}
+
+impl<'s, T> IntoIterator for &'s ArenaSet<T>{
+    type Item = ArenaIdx<T>;
+    type IntoIter = ArenaSetIter<'s, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+pub struct ArenaSetIter<'s, T>{
+    set: &'s ArenaSet<T>,
+    word: usize,
+    // The bits of the current word not yet yielded.
+    bits: u64,
+}
+
+impl<'s, T> Iterator for ArenaSetIter<'s, T>{
+    type Item = ArenaIdx<T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.bits == 0{
+            self.word += 1;
+            self.bits = *self.set.bits.get(self.word)?;
+        }
+        let slot = self.word * BITS + self.bits.trailing_zeros() as usize;
+        // Clear the lowest set bit.
+        self.bits &= self.bits - 1;
+        Some(ArenaIdx::from_parts(slot, self.set.gens[slot]))
+    }
+}
+
+#[cfg(test)]
+mod test{
+    use super::*;
+    use std::collections::BTreeSet;
+
+    struct Rng(u64);
+
+    impl Rng{
+        fn next(&mut self) -> u64{
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            self.0
+        }
+    }
+
+    fn model<T>(set: &ArenaSet<T>) -> BTreeSet<(usize, usize)>{
+        set.iter().map(|i| (i.index(), i.gen())).collect()
+    }
+
+    #[test]
+    fn test_against_btreeset(){
+        let mut rng = Rng(0x9e3779b97f4a7c15);
+        let mut a = ArenaSet::<()>::new();
+        let mut b = ArenaSet::<()>::new();
+        let mut ma = BTreeSet::new();
+        let mut mb = BTreeSet::new();
+
+        for _ in 0..2000{
+            let slot = rng.next() as usize % 300;
+            let gen = rng.next() as usize % 3;
+            let key = ArenaIdx::from_raw_parts(slot, gen);
+            let (set, m) = if rng.next().is_multiple_of(2) {(&mut a, &mut ma)} else{(&mut b, &mut mb)};
+            if rng.next().is_multiple_of(3){
+                assert_eq!(set.remove(key), m.remove(&(slot, gen)));
+            }
+            else{
+                // A slot holds one key, so the model drops other generations.
+                let old = m.iter().find(|(s, _)| *s == slot).copied();
+                m.retain(|(s, _)| *s != slot);
+                m.insert((slot, gen));
+                assert_eq!(set.insert(key), old != Some((slot, gen)));
+            }
+            assert_eq!(model(set), *m);
+            assert_eq!(set.len(), m.len());
+            assert!(set.iter().zip(set.iter().skip(1)).all(|(x, y)| x.index() < y.index()));
+        }
+
+        let mut inter = a.clone();
+        inter.intersect_with(&b);
+        assert_eq!(model(&inter), ma.intersection(&mb).copied().collect());
+
+        let mut diff = a.clone();
+        diff.difference_with(&b);
+        assert_eq!(model(&diff), ma.difference(&mb).copied().collect());
+
+        let mut union = a.clone();
+        union.union_with(&b);
+        let slots: BTreeSet<_> = ma.iter().chain(mb.iter()).map(|(s, _)| *s).collect();
+        assert_eq!(union.len(), slots.len());
+        for (slot, gen) in ma.iter().chain(mb.iter()){
+            let kept = model(&union).into_iter().find(|(s, _)| s == slot).unwrap();
+            assert!(kept.1 >= *gen);
+        }
+        assert_eq!(union, union.iter().collect());
+    }
+
+    #[test]
+    fn test_retain_valid(){
+        let mut arena = Arena::new();
+        let keys: Vec<_> = (0..200).map(|i| arena.insert(i)).collect();
+        let mut set: ArenaSet<_> = keys.iter().copied().filter(|k| k.index() % 3 == 0).collect();
+        assert_eq!(set.len(), 67);
+
+        for key in keys.iter().filter(|k| k.index() % 2 == 0){
+            arena.remove(*key);
+        }
+        // Reuse some of the freed slots.
+        for i in 0..50{
+            let _ = arena.insert(i);
+        }
+        set.retain_valid(&arena);
+        assert!(set.iter().all(|key| arena.get(key).is_some()));
+        assert_eq!(set.len(), keys.iter().filter(|k| k.index() % 6 == 3).count());
+
+        set.clear();
+        assert!(set.is_empty());
+        assert_eq!(set.iter().next(), None);
+    }
+}
@@ -0,0 +1,49 @@
+///
+/// Declares marker types to use as the Tag of an Arena.
+///
+/// Keys of arenas with different tags have different types, so mixing them up is a compile error
+/// instead of a lookup that misses or returns an unrelated element.
+///
+/// ```rust
+/// use gen_arena::*;
+///
+/// arena_tag!{
+///     /// Meshes uploaded to the GPU.
+///     pub struct GpuTag;
+///     pub struct CpuTag;
+/// }
+///
+/// let mut gpu: Arena<&str, usize, GpuTag> = Arena::default();
+/// let mut cpu: Arena<&str, usize, CpuTag> = Arena::default();
+///
+/// let mesh = gpu.insert("cube");
+/// let _ = cpu.insert("sphere");
+/// assert_eq!(gpu[mesh], "cube");
+///
+/// // Attached values only accept keys with the same tag.
+/// let mut names = SparseSecondaryMap::new();
+/// names.insert(mesh, "Cube");
+/// assert_eq!(names[mesh], "Cube");
+/// ```
+///
+/// Using `mesh` with `cpu` does not compile:
+///
+/// ```compile_fail
+/// # use gen_arena::*;
+/// # arena_tag!{ struct GpuTag; struct CpuTag; }
+/// # let mut gpu: Arena<&str, usize, GpuTag> = Arena::default();
+/// # let cpu: Arena<&str, usize, CpuTag> = Arena::default();
+/// let mesh = gpu.insert("cube");
+/// cpu.get(mesh);
+/// ```
+///
+#[macro_export]
+macro_rules! arena_tag{
+    ($($(#[$meta:meta])* $vis:vis struct $name:ident;)*) => {
+        $(
+            $(#[$meta])*
+            #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+            $vis struct $name;
+        )*
+    };
+}
@@ -0,0 +1,441 @@
+use std::{cell::UnsafeCell, mem::MaybeUninit, ptr, sync::atomic::{AtomicPtr, AtomicUsize, Ordering}};
+
+use crate::*;
+
+const FIRST_CHUNK_BITS: u32 = 5;
+const FIRST_CHUNK: usize = 1 << FIRST_CHUNK_BITS;
+const NUM_CHUNKS: usize = (usize::BITS - FIRST_CHUNK_BITS) as usize;
+const NONE: usize = usize::MAX;
+
+///
+/// A slot of an AtomicArena.
+/// The tag holds the generation in the upper bits and whether the slot is occupied in the lowest bit.
+///
+struct Slot<T>{
+    tag: AtomicUsize,
+    next: AtomicUsize,
+    val: UnsafeCell<MaybeUninit<T>>,
+}
+
+impl<T> Slot<T>{
+    fn new() -> Self{
+        Self{
+            tag: AtomicUsize::new(0),
+            next: AtomicUsize::new(NONE),
+            val: UnsafeCell::new(MaybeUninit::uninit()),
+        }
+    }
+}
+
+///
+/// Returns the chunk and the offset in that chunk of a slot index.
+/// Chunk k holds FIRST_CHUNK << k slots.
+///
+#[inline]
+fn locate(index: usize) -> Option<(usize, usize)>{
+    let i = index.checked_add(FIRST_CHUNK)?;
+    let chunk = (usize::BITS - 1 - i.leading_zeros() - FIRST_CHUNK_BITS) as usize;
+    Some((chunk, i - (FIRST_CHUNK << chunk)))
+}
+
+///
+/// A Generational Arena which allows lock free insertion from many threads.
+///
+/// Insertion through a shared reference first pops a slot from the list of freed slots
+/// and otherwise bumps an atomic cursor. Storage is split into chunks of doubling size
+/// which are allocated on demand and never moved, so values are never relocated.
+/// Removal requires a mutable reference, which is what keeps the free list free of ABA problems:
+/// slots are only ever pushed onto it while no other thread can access the arena.
+///
+/// # Consistency model
+///
+/// - insert is lock free, get is wait free.
+/// - A value is published with release semantics when insert returns its key. Any thread that received the key
+///   through a synchronizing operation (a channel, a join, a mutex, ...) is guaranteed to observe the value.
+///   A thread that merely guesses the key may observe None until the insertion is published.
+/// - remove, clear and get_mut take a mutable reference and are therefore ordered with every other operation
+///   by whatever gave the caller exclusive access.
+/// - Generations wrap around after usize::MAX / 2 removals of the same slot.
+///
+/// # Example
+///
+///```rust
+/// use gen_arena::*;
+///
+/// let mut arena = AtomicArena::new();
+///
+/// let keys: Vec<_> = std::thread::scope(|s|{
+///     let handles: Vec<_> = (0..4).map(|i|{
+///         let arena = &arena;
+///         s.spawn(move || arena.insert(i))
+///     }).collect();
+///     handles.into_iter().map(|h| h.join().unwrap()).collect()
+/// });
+///
+/// for (i, key) in keys.iter().enumerate(){
+///     assert_eq!(*arena.get(*key).unwrap(), i);
+/// }
+///
+/// assert_eq!(arena.remove(keys[0]), Some(0));
+/// assert_eq!(arena.get(keys[0]), None);
+///```
+///
+pub struct AtomicArena<T>{
+    chunks: [AtomicPtr<Slot<T>>; NUM_CHUNKS],
+    cursor: AtomicUsize,
+    freed: AtomicUsize,
+    num: AtomicUsize,
+}
+
+// SAFETY: Values are moved in from and referenced by different threads, which requires T: Send + Sync.
+unsafe impl<T: Send + Sync> Sync for AtomicArena<T>{}
+// SAFETY: The arena owns its values.
+unsafe impl<T: Send> Send for AtomicArena<T>{}
+
+impl<T> AtomicArena<T>{
+    ///
+    /// Creates an empty AtomicArena.
+    /// No storage is allocated until the first insertion.
+    ///
+    pub fn new() -> Self{
+        Self{
+            chunks: std::array::from_fn(|_| AtomicPtr::new(ptr::null_mut())),
+            cursor: AtomicUsize::new(0),
+            freed: AtomicUsize::new(NONE),
+            num: AtomicUsize::new(0),
+        }
+    }
+
+    #[inline]
+    fn slot(&self, index: usize) -> Option<&Slot<T>>{
+        let (chunk, offset) = locate(index)?;
+        let ptr = self.chunks[chunk].load(Ordering::Acquire);
+        if ptr.is_null(){
+            None
+        }
+        else{
+            // SAFETY: Chunk k holds FIRST_CHUNK << k initialized slots and lives as long as self.
+            Some(unsafe{&*ptr.add(offset)})
+        }
+    }
+
+    fn slot_or_alloc(&self, index: usize) -> Option<&Slot<T>>{
+        let (chunk, offset) = locate(index)?;
+        let mut ptr = self.chunks[chunk].load(Ordering::Acquire);
+        if ptr.is_null(){
+            let new: Box<[Slot<T>]> = (0..FIRST_CHUNK << chunk).map(|_| Slot::new()).collect();
+            let new = Box::into_raw(new) as *mut Slot<T>;
+            match self.chunks[chunk].compare_exchange(ptr::null_mut(), new, Ordering::AcqRel, Ordering::Acquire){
+                Ok(_) => ptr = new,
+                Err(other) => {
+                    // Another thread allocated the chunk first.
+                    // SAFETY: new was never shared.
+                    unsafe{drop(Box::from_raw(ptr::slice_from_raw_parts_mut(new, FIRST_CHUNK << chunk)))};
+                    ptr = other;
+                }
+            }
+        }
+        // SAFETY: Chunk k holds FIRST_CHUNK << k initialized slots and lives as long as self.
+        Some(unsafe{&*ptr.add(offset)})
+    }
+
+    ///
+    /// Pops a slot from the list of freed slots.
+    /// This is free of ABA problems since slots are only pushed through a mutable reference.
+    ///
+    fn pop_freed(&self) -> Option<usize>{
+        let mut head = self.freed.load(Ordering::Acquire);
+        loop{
+            if head == NONE{
+                return None;
+            }
+            let next = self.slot(head)?.next.load(Ordering::Relaxed);
+            match self.freed.compare_exchange_weak(head, next, Ordering::AcqRel, Ordering::Acquire){
+                Ok(_) => return Some(head),
+                Err(current) => head = current,
+            }
+        }
+    }
+
+    ///
+    /// Tries to insert into the arena.
+    /// Returns val as Err if the arena ran out of indices.
+    ///
+    pub fn try_insert(&self, val: T) -> Result<ArenaIdx<T>, T>{
+        let index = match self.pop_freed(){
+            Some(index) => index,
+            None => self.cursor.fetch_add(1, Ordering::Relaxed),
+        };
+        let slot = match self.slot_or_alloc(index){
+            Some(slot) => slot,
+            None => return Err(val),
+        };
+
+        // The slot is owned by this thread until the tag is published.
+        let tag = slot.tag.load(Ordering::Relaxed);
+        // SAFETY: The slot is unoccupied and no other thread can access its value.
+        unsafe{(*slot.val.get()).write(val)};
+        slot.tag.store(tag | 1, Ordering::Release);
+        self.num.fetch_add(1, Ordering::Relaxed);
+
+        Ok(ArenaIdx::from_parts(index, tag >> 1))
+    }
+
+    ///
+    /// Inserts a new element into the arena.
+    ///
+    #[must_use]
+    pub fn insert(&self, val: T) -> ArenaIdx<T>{
+        match self.try_insert(val){
+            Ok(index) => index,
+            Err(_val) => panic!("Insertion not successfull."),
+        }
+    }
+
+    ///
+    /// Returns an optional reference to the value at the index.
+    ///
+    pub fn get(&self, index: ArenaIdx<T>) -> Option<&T>{
+        let slot = self.slot(index.index())?;
+        let tag = slot.tag.load(Ordering::Acquire);
+        if tag & 1 == 1 && tag >> 1 == index.gen(){
+            // SAFETY: The value was published by the release store of the tag
+            // and can only be removed through a mutable reference.
+            Some(unsafe{(*slot.val.get()).assume_init_ref()})
+        }
+        else{
+            None
+        }
+    }
+
+    ///
+    /// Returns a mutable optional reference to the value at the index.
+    ///
+    pub fn get_mut(&mut self, index: ArenaIdx<T>) -> Option<&mut T>{
+        let slot = self.slot(index.index())?;
+        let tag = slot.tag.load(Ordering::Relaxed);
+        if tag & 1 == 1 && tag >> 1 == index.gen(){
+            // SAFETY: The slot is occupied and self is borrowed mutably.
+            Some(unsafe{(*slot.val.get()).assume_init_mut()})
+        }
+        else{
+            None
+        }
+    }
+
+    ///
+    /// Removes the element from the arena and increaces the generation of its slot.
+    /// Returns the value if the index is still valid.
+    ///
+    pub fn remove(&mut self, index: ArenaIdx<T>) -> Option<T>{
+        let freed = *self.freed.get_mut();
+        let slot = self.slot(index.index())?;
+        let tag = slot.tag.load(Ordering::Relaxed);
+        if tag & 1 == 1 && tag >> 1 == index.gen(){
+            // SAFETY: The slot is occupied and self is borrowed mutably.
+            let val = unsafe{(*slot.val.get()).assume_init_read()};
+            slot.tag.store((tag >> 1).wrapping_add(1) << 1, Ordering::Relaxed);
+            slot.next.store(freed, Ordering::Relaxed);
+            *self.freed.get_mut() = index.index();
+            *self.num.get_mut() -= 1;
+            Some(val)
+        }
+        else{
+            None
+        }
+    }
+
+    ///
+    /// Removes all elements from the arena.
+    /// All slots are kept and linked into the list of freed slots in ascending order.
+    ///
+    pub fn clear(&mut self){
+        let len = *self.cursor.get_mut();
+        for i in (0..len).rev(){
+            let next = *self.freed.get_mut();
+            let slot = match self.slot(i){
+                Some(slot) => slot,
+                None => continue,
+            };
+            let tag = slot.tag.load(Ordering::Relaxed);
+            if tag & 1 == 1{
+                // SAFETY: The slot is occupied and self is borrowed mutably.
+                unsafe{(*slot.val.get()).assume_init_drop()};
+                slot.tag.store((tag >> 1).wrapping_add(1) << 1, Ordering::Relaxed);
+            }
+            slot.next.store(if i == len - 1 {NONE} else{next}, Ordering::Relaxed);
+            *self.freed.get_mut() = i;
+        }
+        *self.num.get_mut() = 0;
+    }
+
+    ///
+    /// Returns an iterator over the elements with their keys.
+    /// Elements inserted concurrently may or may not be yielded.
+    ///
+    #[inline]
+    pub fn iter(&self) -> AtomicIter<'_, T>{
+        AtomicIter{
+            arena: self,
+            index: 0,
+            end: self.cursor.load(Ordering::Acquire),
+        }
+    }
+
+    ///
+    /// Returns the number of elements in the arena.
+    ///
+    #[inline]
+    pub fn num(&self) -> usize{
+        self.num.load(Ordering::Relaxed)
+    }
+}
+
+impl<T> Default for AtomicArena<T>{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Drop for AtomicArena<T>{
+    fn drop(&mut self) {
+        for (k, chunk) in self.chunks.iter_mut().enumerate(){
+            let ptr = *chunk.get_mut();
+            if ptr.is_null(){
+                continue;
+            }
+            // SAFETY: Chunk k was created from a boxed slice of FIRST_CHUNK << k slots.
+            let slots = unsafe{Box::from_raw(ptr::slice_from_raw_parts_mut(ptr, FIRST_CHUNK << k))};
+            for slot in slots.iter(){
+                if slot.tag.load(Ordering::Relaxed) & 1 == 1{
+                    // SAFETY: The slot is occupied.
+                    unsafe{(*slot.val.get()).assume_init_drop()};
+                }
+            }
+        }
+    }
+}
+
+impl<T: std::fmt::Debug> std::fmt::Debug for AtomicArena<T>{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_map().entries(self.iter().map(|(i, val)| (i.index(), val))).finish()
+    }
+}
+
+pub struct AtomicIter<'i, T: 'i>{
+    arena: &'i AtomicArena<T>,
+    index: usize,
+    end: usize,
+}
+
+impl<'i, T> Iterator for AtomicIter<'i, T>{
+    type Item = (ArenaIdx<T>, &'i T);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.index < self.end{
+            let i = self.index;
+            self.index += 1;
+            if let Some(slot) = self.arena.slot(i){
+                let tag = slot.tag.load(Ordering::Acquire);
+                if tag & 1 == 1{
+                    // SAFETY: The value was published by the release store of the tag.
+                    return Some((ArenaIdx::from_parts(i, tag >> 1), unsafe{(*slot.val.get()).assume_init_ref()}));
+                }
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod test{
+    use super::*;
+    use std::collections::HashSet;
+
+    #[test]
+    fn test_locate(){
+        assert_eq!(locate(0), Some((0, 0)));
+        assert_eq!(locate(FIRST_CHUNK - 1), Some((0, FIRST_CHUNK - 1)));
+        assert_eq!(locate(FIRST_CHUNK), Some((1, 0)));
+        assert_eq!(locate(3 * FIRST_CHUNK - 1), Some((1, 2 * FIRST_CHUNK - 1)));
+        assert_eq!(locate(3 * FIRST_CHUNK), Some((2, 0)));
+        assert_eq!(locate(usize::MAX - FIRST_CHUNK).unwrap().0, NUM_CHUNKS - 1);
+        assert_eq!(locate(usize::MAX), None);
+    }
+
+    type Entry = (ArenaIdx<(usize, usize)>, (usize, usize));
+
+    fn insert_concurrently(arena: &AtomicArena<(usize, usize)>, threads: usize, ops: usize) -> Vec<Entry>{
+        std::thread::scope(|s|{
+            let handles: Vec<_> = (0..threads).map(|t|{
+                s.spawn(move ||{
+                    (0..ops).map(|i| (arena.insert((t, i)), (t, i))).collect::<Vec<_>>()
+                })
+            }).collect();
+            handles.into_iter().flat_map(|h| h.join().unwrap()).collect()
+        })
+    }
+
+    #[test]
+    fn test_stress(){
+        const THREADS: usize = 8;
+        const OPS: usize = 2000;
+
+        let mut arena = AtomicArena::new();
+
+        let mut live = insert_concurrently(&arena, THREADS, OPS);
+        let unique: HashSet<_> = live.iter().map(|(key, _)| key.index()).collect();
+        assert_eq!(unique.len(), THREADS * OPS);
+
+        // Free every other slot at a sync point and refill them concurrently.
+        let mut removed = Vec::new();
+        live.retain(|(key, val)|{
+            if key.index() % 2 == 0{
+                assert_eq!(arena.remove(*key), Some(*val));
+                removed.push(*key);
+                false
+            }
+            else{
+                true
+            }
+        });
+
+        live.extend(insert_concurrently(&arena, THREADS, OPS));
+
+        let unique: HashSet<_> = live.iter().map(|(key, _)| key.index()).collect();
+        assert_eq!(unique.len(), live.len());
+        assert_eq!(arena.num(), live.len());
+        for (key, val) in live.iter(){
+            assert_eq!(arena.get(*key), Some(val));
+        }
+        for key in removed.iter(){
+            assert_eq!(arena.get(*key), None);
+        }
+        assert_eq!(arena.iter().count(), live.len());
+    }
+
+    #[test]
+    fn test_clear_and_drop(){
+        let rc = std::sync::Arc::new(());
+        let mut arena = AtomicArena::new();
+
+        let keys: Vec<_> = (0..100).map(|_| arena.insert(rc.clone())).collect();
+        arena.remove(keys[50]);
+        assert_eq!(std::sync::Arc::strong_count(&rc), 100);
+
+        arena.clear();
+        assert_eq!(std::sync::Arc::strong_count(&rc), 1);
+        assert_eq!(arena.num(), 0);
+        assert_eq!(arena.get(keys[0]), None);
+
+        let i0 = arena.insert(rc.clone());
+        let i1 = arena.insert(rc.clone());
+        assert_eq!(i0, ArenaIdx::from_raw_parts(0, 1));
+        assert_eq!(i1, ArenaIdx::from_raw_parts(1, 1));
+        *arena.get_mut(i1).unwrap() = std::sync::Arc::new(());
+        assert_eq!(std::sync::Arc::strong_count(&rc), 2);
+
+        drop(arena);
+        assert_eq!(std::sync::Arc::strong_count(&rc), 1);
+    }
+}
@@ -0,0 +1,248 @@
+use std::ops::{Index, IndexMut};
+
+use crate::*;
+
+///
+/// A Generational Arena that keeps every value in a box of its own.
+///
+/// The slots only hold a pointer and the generation, so freed slots cost little and growing
+/// the arena moves pointers instead of values, which pays off for values of a few kilobytes.
+/// Iterating follows a pointer per element, so small values are better off in an Arena.
+///
+/// # Stable addresses
+///
+/// The value of an element stays at the same address until it is removed. Inserting and
+/// removing other elements, growing the arena and clearing it never move it, so a pointer
+/// taken from get stays valid for as long as the element is in the arena, unlike for Arena.
+///
+/// # Example
+///
+///```rust
+/// use gen_arena::*;
+///
+/// let mut pages = BoxedArena::new();
+/// let page = pages.insert([0u8; 4096]);
+/// let address: *const [u8; 4096] = &pages[page];
+///
+/// for _ in 0..100{
+///     let _ = pages.insert([1u8; 4096]);
+/// }
+/// assert!(std::ptr::eq(&pages[page], address));
+/// assert_eq!(pages.remove_boxed(page).map(|page| page[0]), Some(0));
+///```
+///
+#[derive(Debug, Clone)]
+pub struct BoxedArena<T, G = usize>{
+    arena: Arena<Box<T>, G>,
+}
+
+impl<T> BoxedArena<T>{
+    ///
+    /// Creates an empty BoxedArena.
+    ///
+    pub fn new() -> Self{
+        Self{
+            arena: Arena::new(),
+        }
+    }
+
+    ///
+    /// Creates an empty BoxedArena with room for cap slots, which does not allocate the boxes.
+    ///
+    pub fn with_capacity(cap: usize) -> Self{
+        Self{
+            arena: Arena::with_capacity(cap),
+        }
+    }
+}
+
+impl<T, G: GenCounter> BoxedArena<T, G>{
+    #[inline]
+    fn cast(index: ArenaIdx<T, G>) -> ArenaIdx<Box<T>, G>{
+        ArenaIdx::from_parts(index.index(), index.gen())
+    }
+
+    #[inline]
+    fn uncast(index: ArenaIdx<Box<T>, G>) -> ArenaIdx<T, G>{
+        ArenaIdx::from_parts(index.index(), index.gen())
+    }
+
+    ///
+    /// Tries to insert into the arena.
+    /// Returns val as Err if failed.
+    ///
+    pub fn try_insert(&mut self, val: T) -> Result<ArenaIdx<T, G>, T>{
+        self.try_insert_boxed(Box::new(val)).map_err(|val| *val)
+    }
+
+    ///
+    /// Inserts a new element into the arena.
+    ///
+    #[must_use]
+    pub fn insert(&mut self, val: T) -> ArenaIdx<T, G>{
+        Self::uncast(self.arena.insert(Box::new(val)))
+    }
+
+    ///
+    /// Tries to insert the boxed value without moving it.
+    /// Returns val as Err if failed.
+    ///
+    pub fn try_insert_boxed(&mut self, val: Box<T>) -> Result<ArenaIdx<T, G>, Box<T>>{
+        self.arena.try_insert(val).map(Self::uncast).map_err(InsertError::into_value)
+    }
+
+    ///
+    /// Inserts the boxed value without moving it.
+    ///
+    #[must_use]
+    pub fn insert_boxed(&mut self, val: Box<T>) -> ArenaIdx<T, G>{
+        Self::uncast(self.arena.insert(val))
+    }
+
+    ///
+    /// Removes the element from the arena.
+    /// Returns the value if the index is still valid.
+    ///
+    pub fn remove(&mut self, index: ArenaIdx<T, G>) -> Option<T>{
+        self.remove_boxed(index).map(|val| *val)
+    }
+
+    ///
+    /// Removes the element and returns its box without moving the value.
+    ///
+    pub fn remove_boxed(&mut self, index: ArenaIdx<T, G>) -> Option<Box<T>>{
+        self.arena.remove(Self::cast(index))
+    }
+
+    ///
+    /// Returns an optional reference to the value at the index, see Stable addresses.
+    ///
+    #[inline]
+    pub fn get(&self, index: ArenaIdx<T, G>) -> Option<&T>{
+        self.arena.get(Self::cast(index)).map(|val| &**val)
+    }
+
+    ///
+    /// Returns a mutable optional reference to the value at the index.
+    ///
+    #[inline]
+    pub fn get_mut(&mut self, index: ArenaIdx<T, G>) -> Option<&mut T>{
+        self.arena.get_mut(Self::cast(index)).map(|val| &mut **val)
+    }
+
+    #[inline]
+    pub fn contains(&self, index: ArenaIdx<T, G>) -> bool{
+        self.get(index).is_some()
+    }
+
+    ///
+    /// Returns an iterator over the elements with their keys.
+    ///
+    pub fn iter(&self) -> impl Iterator<Item = (ArenaIdx<T, G>, &T)>{
+        self.arena.iter().map(|(index, val)| (Self::uncast(index), &**val))
+    }
+
+    ///
+    /// Returns a mutable iterator over the elements with their keys.
+    ///
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (ArenaIdx<T, G>, &mut T)>{
+        self.arena.iter_mut().map(|(index, val)| (Self::uncast(index), &mut **val))
+    }
+
+    ///
+    /// Returns an iterator over the keys of the elements.
+    ///
+    pub fn keys(&self) -> impl Iterator<Item = ArenaIdx<T, G>> + '_{
+        self.arena.keys().map(Self::uncast)
+    }
+
+    ///
+    /// Returns an iterator over the elements.
+    ///
+    pub fn values(&self) -> impl Iterator<Item = &T>{
+        self.arena.values().map(|val| &**val)
+    }
+
+    ///
+    /// Returns a mutable iterator over the elements.
+    ///
+    pub fn values_mut(&mut self) -> impl Iterator<Item = &mut T>{
+        self.arena.values_mut().map(|val| &mut **val)
+    }
+
+    #[inline]
+    pub fn clear(&mut self){
+        self.arena.clear()
+    }
+
+    #[inline]
+    pub fn reserve(&mut self, additional: usize){
+        self.arena.reserve(additional)
+    }
+
+    #[inline]
+    pub fn capacity(&self) -> usize{
+        self.arena.capacity()
+    }
+
+    #[inline]
+    pub fn num(&self) -> usize{
+        self.arena.num()
+    }
+}
+
+impl<T, G: GenCounter> Default for BoxedArena<T, G>{
+    fn default() -> Self {
+        Self{
+            arena: Arena::default(),
+        }
+    }
+}
+
+impl<T, G: GenCounter> Index<ArenaIdx<T, G>> for BoxedArena<T, G>{
+    type Output = T;
+
+    fn index(&self, index: ArenaIdx<T, G>) -> &Self::Output {
+        self.get(index).expect("There is no element at this index with that generation.")
+    }
+}
+
+impl<T, G: GenCounter> IndexMut<ArenaIdx<T, G>> for BoxedArena<T, G>{
+    fn index_mut(&mut self, index: ArenaIdx<T, G>) -> &mut Self::Output {
+        self.get_mut(index).expect("There is no element at this index with that generation.")
+    }
+}
+
+#[cfg(test)]
+mod test{
+    use super::*;
+
+    #[test]
+    fn test_stable_addresses(){
+        let mut arena = BoxedArena::<_, u8>::default();
+        let keys: Vec<_> = (0..4).map(|i| arena.insert([i; 256])).collect();
+        let addresses: Vec<*const [u32; 256]> = keys.iter().map(|&key| &arena[key] as *const _).collect();
+
+        // Growing, removing and reusing other slots keeps the values in place.
+        arena.remove(keys[1]);
+        let others: Vec<_> = (0..1000).map(|i| arena.insert([i; 256])).collect();
+        for i in [0, 2, 3]{
+            assert!(std::ptr::eq(&arena[keys[i]], addresses[i]));
+            assert_eq!(arena[keys[i]][0], i as u32);
+        }
+        assert_eq!(arena.get(keys[1]), None);
+        assert_eq!(arena.num(), 1003);
+
+        arena[keys[0]][0] = 7;
+        let boxed = arena.remove_boxed(keys[0]).unwrap();
+        assert!(std::ptr::eq(&*boxed, addresses[0]));
+        assert_eq!(boxed[0], 7);
+        let key = arena.insert_boxed(boxed);
+        assert!(std::ptr::eq(&arena[key], addresses[0]));
+
+        for key in others{
+            arena.remove(key);
+        }
+        assert_eq!(arena.values().map(|val| val[0]).collect::<Vec<_>>(), [7, 2, 3]);
+    }
+}
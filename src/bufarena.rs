@@ -0,0 +1,345 @@
+use std::{mem::MaybeUninit, ops::{Index, IndexMut}};
+
+use crate::*;
+
+///
+/// A slot of a BufArena. Buffers for a BufArena are slices of uninitialized slots.
+///
+pub struct Slot<T>(RawCell<T>);
+
+///
+/// A Generational Arena in a buffer provided by the caller, for example a static or a DMA capable region.
+///
+/// It never allocates and holds at most as many elements as the buffer has slots.
+/// Slots are initialized as they are first used, so creating a BufArena is O(1).
+/// The arena borrows the buffer mutably for its whole lifetime and drops its elements when dropped.
+/// Leaking a BufArena leaks its elements, the buffer can be used again afterwards.
+///
+/// # Example
+///
+///```rust
+/// use gen_arena::*;
+/// use std::mem::MaybeUninit;
+///
+/// let mut buf = [const{ MaybeUninit::<Slot<&str>>::uninit() }; 2];
+/// let mut arena = BufArena::new(&mut buf);
+///
+/// let a = arena.insert("a");
+/// let b = arena.insert("b");
+/// assert_eq!(arena.try_insert("c"), Err("c"));
+///
+/// arena.remove(a);
+/// let c = arena.insert("c");
+/// assert_eq!(arena.get(a), None);
+/// assert_eq!(arena[b], "b");
+/// assert_eq!(arena[c], "c");
+///```
+///
+pub struct BufArena<'a, T>{
+    slots: &'a mut [MaybeUninit<Slot<T>>],
+    // Slots below init are initialized.
+    init: usize,
+    freed: Option<usize>,
+    num: usize,
+}
+
+impl<'a, T> BufArena<'a, T>{
+    ///
+    /// Creates an empty BufArena over the buffer. Previous contents of the buffer are ignored.
+    ///
+    pub fn new(buf: &'a mut [MaybeUninit<Slot<T>>]) -> Self{
+        Self{
+            slots: buf,
+            init: 0,
+            freed: None,
+            num: 0,
+        }
+    }
+
+    #[inline]
+    fn cell(&self, index: usize) -> Option<&RawCell<T>>{
+        if index < self.init{
+            // SAFETY: Slots below init are initialized.
+            Some(unsafe{&self.slots[index].assume_init_ref().0})
+        }
+        else{
+            None
+        }
+    }
+
+    #[inline]
+    fn cell_mut(&mut self, index: usize) -> Option<&mut RawCell<T>>{
+        if index < self.init{
+            // SAFETY: Slots below init are initialized.
+            Some(unsafe{&mut self.slots[index].assume_init_mut().0})
+        }
+        else{
+            None
+        }
+    }
+
+    ///
+    /// Tries to insert into the BufArena.
+    /// Returns val as Err if it is full.
+    ///
+    pub fn try_insert(&mut self, val: T) -> Result<ArenaIdx<T>, T>{
+        match self.freed{
+            Some(i) => {
+                let cell = self.cell_mut(i).expect("Freed cells are initialized.");
+                let (next, generation) = match *cell{
+                    RawCell::Freed{next, generation} => (next, generation),
+                    RawCell::Allocated{..} => unreachable!("The free list only holds freed cells."),
+                };
+                *cell = RawCell::Allocated{val, generation};
+                self.freed = next;
+                self.num += 1;
+                Ok(ArenaIdx::from_parts(i, generation))
+            }
+            None if self.init < self.slots.len() => {
+                self.slots[self.init].write(Slot(RawCell::Allocated{val, generation: 0}));
+                self.init += 1;
+                self.num += 1;
+                Ok(ArenaIdx::from_parts(self.init - 1, 0))
+            }
+            None => Err(val),
+        }
+    }
+
+    ///
+    /// Inserts a new element into the BufArena.
+    /// Panics if it is full.
+    ///
+    #[must_use]
+    pub fn insert(&mut self, val: T) -> ArenaIdx<T>{
+        match self.try_insert(val){
+            Ok(index) => index,
+            Err(_val) => panic!("Insertion not successfull."),
+        }
+    }
+
+    ///
+    /// Removes the element and returns its value if the index is still valid.
+    ///
+    pub fn remove(&mut self, index: ArenaIdx<T>) -> Option<T>{
+        self.get(index)?;
+        let freed = self.freed;
+        let cell = self.cell_mut(index.index()).expect("The cell was just checked.");
+        let cell = std::mem::replace(cell, RawCell::Freed{
+            next: freed,
+            generation: index.gen() + 1,
+        });
+        self.freed = Some(index.index());
+        self.num -= 1;
+        match cell{
+            RawCell::Allocated{val, ..} => Some(val),
+            RawCell::Freed{..} => unreachable!(),
+        }
+    }
+
+    ///
+    /// Returns an optional reference to the value at the index.
+    ///
+    #[inline]
+    pub fn get(&self, index: ArenaIdx<T>) -> Option<&T>{
+        match self.cell(index.index())?{
+            RawCell::Allocated{val, generation} if *generation == index.gen() => Some(val),
+            _ => None,
+        }
+    }
+
+    ///
+    /// Returns a mutable optional reference to the value at the index.
+    ///
+    #[inline]
+    pub fn get_mut(&mut self, index: ArenaIdx<T>) -> Option<&mut T>{
+        match self.cell_mut(index.index())?{
+            RawCell::Allocated{val, generation} if *generation == index.gen() => Some(val),
+            _ => None,
+        }
+    }
+
+    #[inline]
+    pub fn contains(&self, index: ArenaIdx<T>) -> bool{
+        self.get(index).is_some()
+    }
+
+    ///
+    /// Returns an iterator over the elements with their keys.
+    ///
+    pub fn iter(&self) -> impl Iterator<Item = (ArenaIdx<T>, &T)>{
+        (0..self.init).filter_map(|i| match self.cell(i)?{
+            RawCell::Allocated{val, generation} => Some((ArenaIdx::from_parts(i, *generation), val)),
+            RawCell::Freed{..} => None,
+        })
+    }
+
+    ///
+    /// Returns a mutable iterator over the elements with their keys.
+    ///
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (ArenaIdx<T>, &mut T)>{
+        self.slots[..self.init].iter_mut().enumerate().filter_map(|(i, slot)|{
+            // SAFETY: Slots below init are initialized.
+            match unsafe{&mut slot.assume_init_mut().0}{
+                RawCell::Allocated{val, generation} => Some((ArenaIdx::from_parts(i, *generation), val)),
+                RawCell::Freed{..} => None,
+            }
+        })
+    }
+
+    ///
+    /// Removes all elements, invalidating their keys.
+    ///
+    pub fn clear(&mut self){
+        for i in 0..self.init{
+            let freed = self.freed;
+            let cell = self.cell_mut(i).expect("Slots below init are initialized.");
+            let generation = match cell{
+                RawCell::Allocated{generation, ..} => *generation + 1,
+                RawCell::Freed{generation, ..} => *generation,
+            };
+            *cell = RawCell::Freed{
+                next: freed,
+                generation,
+            };
+            self.freed = Some(i);
+        }
+        self.num = 0;
+    }
+
+    ///
+    /// Returns the number of slots of the buffer.
+    ///
+    #[inline]
+    pub fn capacity(&self) -> usize{
+        self.slots.len()
+    }
+
+    #[inline]
+    pub fn num(&self) -> usize{
+        self.num
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool{
+        self.num == 0
+    }
+}
+
+impl<'a, T> Drop for BufArena<'a, T>{
+    fn drop(&mut self){
+        for slot in self.slots[..self.init].iter_mut(){
+            // SAFETY: Slots below init are initialized and not used after this.
+            unsafe{slot.assume_init_drop()};
+        }
+    }
+}
+
+impl<'a, T: std::fmt::Debug> std::fmt::Debug for BufArena<'a, T>{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_map().entries(self.iter().map(|(i, val)| (i.index(), val))).finish()
+    }
+}
+
+impl<'a, T> Index<ArenaIdx<T>> for BufArena<'a, T>{
+    type Output = T;
+
+    fn index(&self, index: ArenaIdx<T>) -> &Self::Output {
+        self.get(index).expect("There is no element at this index with that generation.")
+    }
+}
+
+impl<'a, T> IndexMut<ArenaIdx<T>> for BufArena<'a, T>{
+    fn index_mut(&mut self, index: ArenaIdx<T>) -> &mut Self::Output {
+        self.get_mut(index).expect("There is no element at this index with that generation.")
+    }
+}
+
+// These tests exercise the MaybeUninit handling and are meant to be run under Miri as well:
+// cargo +nightly miri test bufarena
+#[cfg(test)]
+mod test{
+    use super::*;
+    use std::rc::Rc;
+
+    fn buffer<T, const N: usize>() -> [MaybeUninit<Slot<T>>; N]{
+        [const{ MaybeUninit::uninit() }; N]
+    }
+
+    #[test]
+    fn test_drops(){
+        let counter = Rc::new(());
+        let mut buf = buffer::<Rc<()>, 8>();
+        {
+            let mut arena = BufArena::new(&mut buf);
+            let keys: Vec<_> = (0..8).map(|_| arena.insert(counter.clone())).collect();
+            assert!(arena.try_insert(counter.clone()).is_err());
+            assert_eq!(Rc::strong_count(&counter), 9);
+
+            drop(arena.remove(keys[3]));
+            drop(arena.remove(keys[5]));
+            assert_eq!(arena.remove(keys[5]), None);
+            assert_eq!(Rc::strong_count(&counter), 7);
+
+            let k = arena.insert(counter.clone());
+            assert_eq!(k.index(), 5);
+            assert_eq!(k.gen(), 1);
+            assert_eq!(arena.get(keys[5]), None);
+            assert_eq!(arena.num(), 7);
+        }
+        // Dropping the arena drops the remaining elements, but not the freed slots.
+        assert_eq!(Rc::strong_count(&counter), 1);
+
+        // A leaked arena only leaks its elements and the buffer can be reused.
+        {
+            let mut arena = BufArena::new(&mut buf);
+            for _ in 0..3{
+                let _ = arena.insert(counter.clone());
+            }
+            std::mem::forget(arena);
+        }
+        assert_eq!(Rc::strong_count(&counter), 4);
+        let mut arena = BufArena::new(&mut buf);
+        assert!(arena.is_empty());
+        let k = arena.insert(counter.clone());
+        assert_eq!(k.gen(), 0);
+        drop(arena);
+        assert_eq!(Rc::strong_count(&counter), 4);
+    }
+
+    #[test]
+    fn test_partial_init_and_clear(){
+        let mut buf = buffer::<String, 16>();
+        let mut arena = BufArena::new(&mut buf);
+        assert_eq!(arena.capacity(), 16);
+
+        // Only the first few slots are ever initialized.
+        let a = arena.insert(String::from("a"));
+        let b = arena.insert(String::from("b"));
+        arena[b].push('b');
+        assert_eq!(arena.iter().map(|(_, v)| v.as_str()).collect::<Vec<_>>(), ["a", "bb"]);
+        for (_, val) in arena.iter_mut(){
+            val.push('!');
+        }
+        assert_eq!(arena.get(ArenaIdx::from_raw_parts(10, 0)), None);
+        assert_eq!(arena.remove(ArenaIdx::from_raw_parts(15, 0)), None);
+
+        arena.clear();
+        assert_eq!(arena.get(a), None);
+        assert_eq!(arena.get(b), None);
+        assert!(arena.is_empty());
+
+        // Cleared slots are reused before new ones, all 16 slots are usable.
+        let keys: Vec<_> = (0..16).map(|i| arena.insert(i.to_string())).collect();
+        assert_eq!(keys.iter().filter(|k| k.gen() == 1).count(), 2);
+        assert!(arena.try_insert(String::new()).is_err());
+        assert_eq!(arena.iter().count(), 16);
+    }
+
+    #[test]
+    fn test_zero_capacity(){
+        let mut buf = buffer::<u8, 0>();
+        let mut arena = BufArena::new(&mut buf);
+        assert_eq!(arena.try_insert(1), Err(1));
+        assert_eq!(arena.iter().count(), 0);
+    }
+}
@@ -0,0 +1,297 @@
+use std::{alloc::{self, Layout}, cell::{Cell, RefCell}, ptr::{self, NonNull}};
+
+const FIRST_CHUNK: usize = 4096;
+const CHUNK_ALIGN: usize = 16;
+
+///
+/// A bump allocator handing out references instead of keys.
+///
+/// Values are placed one after another into chunks of memory, which are never moved,
+/// so references returned by alloc stay valid for as long as the Bump is borrowed.
+/// Individual values can not be freed; their memory is freed together when the Bump is dropped
+/// or reset.
+///
+/// Like bumpalo, the Bump never runs the destructors of its values. It has no type parameter,
+/// so the compiler could not check that values borrowing each other outlive each other's destructors.
+/// Values owning resources, such as a Vec, leak them.
+///
+/// This suits workloads like building an AST, where nodes are only freed all at once.
+///
+/// # Example
+///
+///```rust
+/// use gen_arena::*;
+///
+/// enum Expr<'a>{
+///     Num(i64),
+///     Add(&'a Expr<'a>, &'a Expr<'a>),
+///     Var(&'a str),
+/// }
+///
+/// fn eval(expr: &Expr, x: i64) -> i64{
+///     match expr{
+///         Expr::Num(n) => *n,
+///         Expr::Add(a, b) => eval(a, x) + eval(b, x),
+///         Expr::Var(_) => x,
+///     }
+/// }
+///
+/// let bump = Bump::new();
+/// let x = bump.alloc(Expr::Var(bump.alloc_str("x")));
+/// let one = bump.alloc(Expr::Num(1));
+/// let expr = bump.alloc(Expr::Add(x, one));
+///
+/// assert_eq!(eval(expr, 41), 42);
+///```
+///
+pub struct Bump{
+    chunks: RefCell<Vec<(NonNull<u8>, Layout)>>,
+    // Bytes used in the last chunk.
+    used: Cell<usize>,
+}
+
+impl Bump{
+    ///
+    /// Creates an empty Bump. No memory is allocated until the first value is.
+    ///
+    pub fn new() -> Self{
+        Self{
+            chunks: RefCell::new(Vec::new()),
+            used: Cell::new(0),
+        }
+    }
+
+    ///
+    /// Creates an empty Bump with a first chunk of at least the given number of bytes.
+    ///
+    pub fn with_capacity(bytes: usize) -> Self{
+        let bump = Self::new();
+        if bytes > 0{
+            bump.new_chunk(Layout::from_size_align(bytes, 1).expect("Capacity overflow."));
+            bump.used.set(0);
+        }
+        bump
+    }
+
+    ///
+    /// Allocates a chunk fitting the layout and returns memory for the layout at its start.
+    ///
+    fn new_chunk(&self, layout: Layout) -> NonNull<u8>{
+        let mut chunks = self.chunks.borrow_mut();
+        let next = chunks.last().map(|(_, chunk)| chunk.size().saturating_mul(2)).unwrap_or(FIRST_CHUNK);
+        let chunk = Layout::from_size_align(
+            next.max(layout.size()),
+            layout.align().max(CHUNK_ALIGN),
+        ).expect("Capacity overflow.");
+        // SAFETY: The chunk has a non zero size.
+        let ptr = unsafe{alloc::alloc(chunk)};
+        let Some(ptr) = NonNull::new(ptr) else{
+            alloc::handle_alloc_error(chunk)
+        };
+        chunks.push((ptr, chunk));
+        self.used.set(layout.size());
+        ptr
+    }
+
+    ///
+    /// Returns uninitialized memory for the layout which stays valid until the Bump is reset or dropped.
+    ///
+    fn alloc_layout(&self, layout: Layout) -> NonNull<u8>{
+        if let Some((base, chunk)) = self.chunks.borrow().last(){
+            let used = self.used.get();
+            let addr = base.as_ptr() as usize + used;
+            let offset = used + (addr.wrapping_neg() & (layout.align() - 1));
+            if offset <= chunk.size() && layout.size() <= chunk.size() - offset{
+                self.used.set(offset + layout.size());
+                // SAFETY: offset + layout.size() is within the chunk.
+                return unsafe{NonNull::new_unchecked(base.as_ptr().add(offset))};
+            }
+        }
+        self.new_chunk(layout)
+    }
+
+    ///
+    /// Moves the value into the Bump and returns a mutable reference to it.
+    /// The value is never dropped, see Bump.
+    ///
+    #[allow(clippy::mut_from_ref)]
+    pub fn alloc<T>(&self, val: T) -> &mut T{
+        let ptr = self.alloc_layout(Layout::new::<T>()).cast::<T>();
+        // SAFETY: The memory is valid for a T, aligned and not handed out to anyone else.
+        unsafe{ptr.as_ptr().write(val)};
+        // SAFETY: The value was just initialized and lives until the Bump is reset or dropped,
+        // both of which need a mutable borrow, which ends all references handed out.
+        unsafe{&mut *ptr.as_ptr()}
+    }
+
+    ///
+    /// Copies the slice into the Bump and returns a mutable reference to the copy.
+    ///
+    #[allow(clippy::mut_from_ref)]
+    pub fn alloc_slice_copy<T: Copy>(&self, src: &[T]) -> &mut [T]{
+        let ptr = self.alloc_layout(Layout::for_value(src)).cast::<T>();
+        // SAFETY: The memory is valid for src.len() values of T and does not overlap src.
+        unsafe{
+            ptr::copy_nonoverlapping(src.as_ptr(), ptr.as_ptr(), src.len());
+            std::slice::from_raw_parts_mut(ptr.as_ptr(), src.len())
+        }
+    }
+
+    ///
+    /// Copies the string into the Bump and returns a mutable reference to the copy.
+    ///
+    #[allow(clippy::mut_from_ref)]
+    pub fn alloc_str(&self, src: &str) -> &mut str{
+        let bytes = self.alloc_slice_copy(src.as_bytes());
+        // SAFETY: The bytes were copied from a str.
+        unsafe{std::str::from_utf8_unchecked_mut(bytes)}
+    }
+
+    ///
+    /// Frees all values without dropping them, and all memory except for the largest chunk, which is reused.
+    ///
+    pub fn reset(&mut self){
+        let chunks = self.chunks.get_mut();
+        let last = chunks.pop();
+        for (ptr, layout) in chunks.drain(..){
+            // SAFETY: The chunk was allocated with this layout.
+            unsafe{alloc::dealloc(ptr.as_ptr(), layout)};
+        }
+        chunks.extend(last);
+        self.used.set(0);
+    }
+
+    ///
+    /// Returns the number of bytes allocated for chunks.
+    ///
+    pub fn allocated_bytes(&self) -> usize{
+        self.chunks.borrow().iter().map(|(_, layout)| layout.size()).sum()
+    }
+}
+
+impl Default for Bump{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Drop for Bump{
+    fn drop(&mut self){
+        for (ptr, layout) in self.chunks.get_mut().drain(..){
+            // SAFETY: The chunk was allocated with this layout.
+            unsafe{alloc::dealloc(ptr.as_ptr(), layout)};
+        }
+    }
+}
+
+impl std::fmt::Debug for Bump{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Bump")
+            .field("chunks", &self.chunks.borrow().len())
+            .field("allocated_bytes", &self.allocated_bytes())
+            .finish()
+    }
+}
+
+// These tests exercise the unsafe chunk management and are meant to be run under Miri as well:
+// cargo +nightly miri test bump
+#[cfg(test)]
+mod test{
+    use super::*;
+
+    #[test]
+    fn test_alignment_and_stable_addresses(){
+        let bump = Bump::new();
+
+        let mut refs = Vec::new();
+        for i in 0..2000u64{
+            let _ = bump.alloc(i as u8);
+            let val = bump.alloc(i);
+            assert_eq!(val as *const u64 as usize % std::mem::align_of::<u64>(), 0);
+            refs.push(val);
+        }
+        #[repr(align(64))]
+        struct Aligned(u8);
+        let aligned = bump.alloc(Aligned(1));
+        assert_eq!(aligned as *const Aligned as usize % 64, 0);
+        assert_eq!(aligned.0, 1);
+
+        // Growing into new chunks did not move earlier values.
+        assert!(bump.chunks.borrow().len() > 1);
+        for (i, val) in refs.iter_mut().enumerate(){
+            assert_eq!(**val, i as u64);
+            **val += 1;
+        }
+        assert_eq!(*refs[1999], 2000);
+
+        let _ = bump.alloc(());
+        let large = bump.alloc_slice_copy(&[7u32; 10_000]);
+        assert!(large.iter().all(|x| *x == 7));
+        let s = bump.alloc_str("hello");
+        s.make_ascii_uppercase();
+        assert_eq!(s, "HELLO");
+        assert!(bump.alloc_slice_copy::<u8>(&[]).is_empty());
+    }
+
+    // Counts its drops without owning memory, so Miri does not report the values as leaked.
+    struct Counted<'a>(&'a Cell<usize>);
+
+    impl Drop for Counted<'_>{
+        fn drop(&mut self){
+            self.0.set(self.0.get() + 1);
+        }
+    }
+
+    #[test]
+    fn test_reset_without_drop(){
+        let dropped = Cell::new(0);
+        let mut bump = Bump::new();
+        for _ in 0..1000{
+            let _ = bump.alloc(Counted(&dropped));
+        }
+
+        // The values are freed but not dropped.
+        bump.reset();
+        assert_eq!(dropped.get(), 0);
+        assert_eq!(bump.chunks.borrow().len(), 1);
+        let kept = bump.allocated_bytes();
+
+        let _ = bump.alloc(Counted(&dropped));
+        assert_eq!(bump.allocated_bytes(), kept);
+
+        drop(bump);
+        assert_eq!(dropped.get(), 0);
+    }
+
+    #[test]
+    fn test_cyclic_borrows(){
+        // Dropping these nodes used to read the freed String of the other one, which Miri reported.
+        struct Node<'a>{
+            name: RefCell<String>,
+            other: Cell<Option<&'a Node<'a>>>,
+            dropped: &'a Cell<usize>,
+        }
+
+        impl Drop for Node<'_>{
+            fn drop(&mut self){
+                if let Some(other) = self.other.get(){
+                    assert!(other.name.borrow().chars().all(char::is_alphabetic));
+                }
+                self.dropped.set(self.dropped.get() + 1);
+            }
+        }
+
+        let dropped = Cell::new(0);
+        let bump = Bump::new();
+        let a = bump.alloc(Node{name: RefCell::new(String::from("a")), other: Cell::new(None), dropped: &dropped});
+        let b = bump.alloc(Node{name: RefCell::new(String::from("b")), other: Cell::new(None), dropped: &dropped});
+        a.other.set(Some(b));
+        b.other.set(Some(a));
+        assert_eq!(*a.other.get().unwrap().name.borrow(), "b");
+        // Free the Strings, which the Bump leaks, to keep Miri's leak check quiet.
+        a.name.take();
+        b.name.take();
+        drop(bump);
+        assert_eq!(dropped.get(), 0);
+    }
+}
@@ -0,0 +1,92 @@
+use std::ops::{Deref, DerefMut};
+
+///
+/// Aligns a value to a cache line of 64 bytes.
+///
+/// Storing values as CacheAligned<T> in an Arena or SArena starts every value on a cache line
+/// of its own, as the slots take the alignment of their values. Threads mutating neighbouring
+/// elements then no longer contend for the same cache line, which is called false sharing.
+/// Every slot takes at least 64 bytes, so this only pays off for elements written by different threads.
+///
+/// # Example
+///
+///```rust
+/// use gen_arena::*;
+///
+/// let mut counters = Arena::new();
+/// let a = counters.insert(CacheAligned(0u64));
+/// let b = counters.insert(CacheAligned(0u64));
+///
+/// if let (Some(a), Some(b)) = counters.get2_mut((a, b)){
+///     std::thread::scope(|s|{
+///         s.spawn(|| **a += 1);
+///         s.spawn(|| **b += 2);
+///     });
+/// }
+/// assert_eq!(*counters[b], 2);
+///```
+///
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[repr(align(64))]
+pub struct CacheAligned<T>(pub T);
+
+impl<T> CacheAligned<T>{
+    #[inline]
+    pub fn into_inner(self) -> T{
+        self.0
+    }
+}
+
+impl<T> Deref for CacheAligned<T>{
+    type Target = T;
+
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<T> DerefMut for CacheAligned<T>{
+    #[inline]
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+impl<T> From<T> for CacheAligned<T>{
+    #[inline]
+    fn from(val: T) -> Self {
+        Self(val)
+    }
+}
+
+#[cfg(test)]
+mod test{
+    use std::mem::{align_of, size_of};
+
+    use super::*;
+    use crate::*;
+
+    #[test]
+    fn test_layout(){
+        assert_eq!(align_of::<CacheAligned<u8>>(), 64);
+        assert_eq!(size_of::<CacheAligned<u8>>(), 64);
+        assert_eq!(size_of::<CacheAligned<[u8; 65]>>(), 128);
+        assert_eq!(align_of::<RawCell<CacheAligned<u8>, u8>>(), 64);
+        assert_eq!(size_of::<RawCell<CacheAligned<u8>, u8>>() % 64, 0);
+
+        // No two values share a cache line, in both kinds of arenas.
+        let mut arena = Arena::new();
+        let sarena = SArena::<_, 8>::new();
+        for i in 0..8u8{
+            let _ = arena.insert(CacheAligned(i));
+            let _ = sarena.insert(CacheAligned(i));
+        }
+        for lines in [arena.values().map(|val| val as *const _ as usize).collect::<Vec<_>>(), sarena.values().map(|val| val as *const _ as usize).collect()]{
+            assert!(lines.iter().all(|address| address % 64 == 0));
+            let mut lines: Vec<_> = lines.iter().map(|address| address / 64).collect();
+            lines.dedup();
+            assert_eq!(lines.len(), 8);
+        }
+    }
+}
@@ -0,0 +1,738 @@
+use std::{cell::{Cell, UnsafeCell}, marker::PhantomData, ops::{Index, IndexMut}, ptr::NonNull};
+
+use crate::*;
+
+///
+/// Number of cells in one chunk of a ChunkArena.
+///
+pub const CHUNK_SIZE: usize = 256;
+
+///
+/// A Generational Arena that stores its cells in fixed size chunks.
+///
+/// Chunks are allocated on demand and never moved or freed before the arena is dropped,
+/// so the address of a value is stable for as long as it stays in the arena.
+/// This allows insertion through a shared reference while references returned by get are held.
+/// Removal still requires a mutable reference so no reference to a removed value can exist.
+///
+/// The interior mutability is single threaded, a ChunkArena is Send but not Sync.
+///
+/// # Example
+///
+///```rust
+/// use gen_arena::*;
+///
+/// let arena = ChunkArena::new();
+///
+/// let i0 = arena.insert(0);
+/// let v0 = arena.get_pinned(i0).unwrap();
+///
+/// // Inserting does not move existing values.
+/// let indices: Vec<_> = (1..1000).map(|i| arena.insert(i)).collect();
+///
+/// assert_eq!(*v0, 0);
+/// assert_eq!(*arena.get(indices[998]).unwrap(), 999);
+///```
+///
+pub struct ChunkArena<T>{
+    chunks: UnsafeCell<Vec<NonNull<UnsafeCell<RawCell<T>>>>>,
+    freed: Cell<Option<usize>>,
+    num: Cell<usize>,
+    _ty: PhantomData<T>,
+}
+
+// SAFETY: The arena owns its chunks and values exclusively, just like a Vec<T>.
+unsafe impl<T: Send> Send for ChunkArena<T>{}
+
+impl<T> ChunkArena<T>{
+    ///
+    /// Creates an empty ChunkArena.
+    /// No chunk is allocated until the first insertion.
+    ///
+    pub fn new() -> Self{
+        Self{
+            chunks: UnsafeCell::new(Vec::new()),
+            freed: Cell::new(None),
+            num: Cell::new(0),
+            _ty: PhantomData,
+        }
+    }
+
+    ///
+    /// Creates a ChunkArena with enough chunks allocated for cap elements.
+    ///
+    /// ```rust
+    /// use gen_arena::*;
+    ///
+    /// let arena = ChunkArena::<i32>::with_capacity(300);
+    ///
+    /// assert_eq!(arena.capacity(), 2 * CHUNK_SIZE);
+    /// ```
+    ///
+    pub fn with_capacity(cap: usize) -> Self{
+        let arena = Self::new();
+        while arena.capacity() < cap{
+            arena.alloc_chunk();
+        }
+        arena
+    }
+
+    #[inline]
+    fn num_chunks(&self) -> usize{
+        // SAFETY: No reference to the chunk Vec outlives a method call.
+        unsafe{&*self.chunks.get()}.len()
+    }
+
+    ///
+    /// Allocates a new chunk and prepends its cells to the list of Freed cells.
+    ///
+    fn alloc_chunk(&self){
+        let base = self.num_chunks() * CHUNK_SIZE;
+        let freed = self.freed.get();
+        let chunk: Box<[UnsafeCell<RawCell<T>>]> = (0..CHUNK_SIZE).map(|i|{
+            UnsafeCell::new(RawCell::Freed{
+                next: if i < CHUNK_SIZE - 1 {Some(base + i + 1)} else{freed},
+                generation: 0,
+            })
+        }).collect();
+        let chunk = NonNull::new(Box::into_raw(chunk) as *mut UnsafeCell<RawCell<T>>).unwrap();
+
+        // SAFETY: No reference to the chunk Vec outlives a method call,
+        // references handed out point into the chunks which are not moved by the push.
+        unsafe{&mut *self.chunks.get()}.push(chunk);
+        self.freed.set(Some(base));
+    }
+
+    #[inline]
+    fn cell(&self, index: usize) -> Option<&UnsafeCell<RawCell<T>>>{
+        // SAFETY: No reference to the chunk Vec outlives a method call.
+        let chunks = unsafe{&*self.chunks.get()};
+        chunks.get(index / CHUNK_SIZE).map(|chunk|{
+            // SAFETY: Every chunk holds CHUNK_SIZE initialized cells and lives as long as self.
+            unsafe{&*chunk.as_ptr().add(index % CHUNK_SIZE)}
+        })
+    }
+
+    #[inline]
+    fn cell_mut(&mut self, index: usize) -> Option<&mut RawCell<T>>{
+        // SAFETY: The mutable reference to self guarantees exclusive access to all cells.
+        self.cell(index).map(|cell| unsafe{&mut *cell.get()})
+    }
+
+    ///
+    /// Clears the arena and resets the list of Freed cells.
+    /// The chunks are kept for reuse.
+    ///
+    /// ```rust
+    /// use gen_arena::*;
+    ///
+    /// let mut arena = ChunkArena::new();
+    ///
+    /// let i1 = arena.insert(1);
+    /// let i2 = arena.insert(2);
+    ///
+    /// arena.clear();
+    ///
+    /// assert_eq!(arena.get(i1), None);
+    /// assert_eq!(arena.get(i2), None);
+    /// assert_eq!(arena.num(), 0);
+    /// ```
+    ///
+    pub fn clear(&mut self){
+        let len = self.num_chunks() * CHUNK_SIZE;
+        for i in 0..len{
+            let cell = self.cell_mut(i).unwrap();
+            let next = if i < len - 1 {Some(i + 1)} else{None};
+            match cell{
+                RawCell::Allocated{val: _, generation} => {
+                    *cell = RawCell::Freed{
+                        generation: *generation + 1,
+                        next,
+                    }
+                },
+                RawCell::Freed{next: _, generation} => {
+                    *cell = RawCell::Freed{
+                        generation: *generation,
+                        next,
+                    }
+                }
+            }
+        }
+        self.freed.set(if len > 0 {Some(0)} else{None});
+        self.num.set(0);
+    }
+
+    ///
+    /// Tries to insert into the arena.
+    /// Returns val as Err if failed.
+    ///
+    /// Unlike Arena::try_insert this only needs a shared reference,
+    /// since values are never moved by an insertion.
+    ///
+    pub fn try_insert(&self, val: T) -> Result<ArenaIdx<T>, T>{
+        if self.freed.get().is_none(){
+            self.alloc_chunk();
+        }
+        let i = self.freed.get().unwrap();
+        let cell = self.cell(i).unwrap();
+
+        // SAFETY: References are only handed out to Allocated cells,
+        // so nothing can observe the Freed cell while it is overwritten.
+        let (next, generation) = match unsafe{&*cell.get()}{
+            RawCell::Freed{next, generation} => (*next, *generation),
+            RawCell::Allocated{..} => return Err(val),
+        };
+        unsafe{cell.get().write(RawCell::Allocated{val, generation})};
+
+        self.freed.set(next);
+        self.num.set(self.num.get() + 1);
+        Ok(ArenaIdx::from_parts(i, generation))
+    }
+
+    ///
+    /// Inserts a new element into the arena.
+    ///
+    /// ```rust
+    /// use gen_arena::*;
+    ///
+    /// let arena = ChunkArena::new();
+    ///
+    /// let i1 = arena.insert(1);
+    /// let i2 = arena.insert(2);
+    ///
+    /// assert_eq!(*arena.get(i1).unwrap(), 1);
+    /// assert_eq!(*arena.get(i2).unwrap(), 2)
+    /// ```
+    ///
+    #[must_use]
+    pub fn insert(&self, val: T) -> ArenaIdx<T>{
+        match self.try_insert(val){
+            Ok(index) => index,
+            Err(_val) => panic!("Insertion not successfull."),
+        }
+    }
+
+    ///
+    /// Removes the cell from the arena and increaces its generation.
+    /// Returns the value if the index is still valid, stale indices leave the cell untouched.
+    ///
+    pub fn remove(&mut self, index: ArenaIdx<T>) -> Option<T>{
+        let freed = self.freed.get();
+        let cell = self.cell_mut(index.index())?;
+        match cell{
+            RawCell::Allocated{val: _, generation} if *generation == index.gen() => {
+                let cell = std::mem::replace(cell, RawCell::Freed{
+                    next: freed,
+                    generation: index.gen() + 1,
+                });
+                self.num.set(self.num.get() - 1);
+                self.freed.set(Some(index.index()));
+                match cell{
+                    RawCell::Allocated{val, ..} => Some(val),
+                    RawCell::Freed{..} => unreachable!(),
+                }
+            }
+            _ => None,
+        }
+    }
+
+    ///
+    /// Gets the Generation for a given index.
+    ///
+    pub fn gen(&self, index: usize) -> usize{
+        // SAFETY: Only the generation is read which is never written through a shared reference.
+        match unsafe{&*self.cell(index).expect("Index out of bounds.").get()}{
+            RawCell::Freed{generation, ..} => *generation,
+            RawCell::Allocated{generation, ..} => *generation,
+        }
+    }
+
+    ///
+    /// Returns an optional reference to the value at the index.
+    /// The reference stays valid across insertions, see get_pinned.
+    ///
+    pub fn get(&self, index: ArenaIdx<T>) -> Option<&T>{
+        let cell = self.cell(index.index())?;
+        // SAFETY: Allocated cells are only written through a mutable reference to self.
+        if let RawCell::Allocated{val, generation} = unsafe{&*cell.get()}{
+            if *generation == index.gen(){
+                Some(val)
+            }
+            else{
+                None
+            }
+        }
+        else{
+            None
+        }
+    }
+
+    ///
+    /// Returns an optional reference to the value at the index, which is guaranteed to stay valid
+    /// and at the same address until the value is removed from the arena.
+    /// Since removal needs a mutable reference this is enforced by the borrow checker,
+    /// while insertions through a shared reference are allowed.
+    ///
+    /// ```rust
+    /// use gen_arena::*;
+    ///
+    /// let arena = ChunkArena::new();
+    ///
+    /// let i0 = arena.insert(String::from("a"));
+    /// let a = arena.get_pinned(i0).unwrap();
+    ///
+    /// for i in 0..(4 * CHUNK_SIZE){
+    ///     let _ = arena.insert(i.to_string());
+    /// }
+    ///
+    /// assert_eq!(a, "a");
+    /// assert!(std::ptr::eq(a, arena.get(i0).unwrap()));
+    /// ```
+    ///
+    #[inline]
+    pub fn get_pinned(&self, index: ArenaIdx<T>) -> Option<&T>{
+        self.get(index)
+    }
+
+    ///
+    /// Returns an optional reference to a cell with any generation.
+    ///
+    pub fn get_any(&self, index: usize) -> Option<&T>{
+        let cell = self.cell(index)?;
+        // SAFETY: Allocated cells are only written through a mutable reference to self.
+        if let RawCell::Allocated{val, generation: _} = unsafe{&*cell.get()}{
+            Some(val)
+        }
+        else{
+            None
+        }
+    }
+
+    ///
+    /// Get N optional references to N indices in the arena.
+    ///
+    pub fn getn<const N: usize>(&self, indices: [ArenaIdx<T>; N]) -> [Option<&T>; N]{
+        let mut ret = [None; N];
+
+        for (i, index) in indices.iter().enumerate(){
+            ret[i] = self.get(*index);
+        }
+        ret
+    }
+
+    ///
+    /// Returns a mutable optional reference to the value at the index.
+    ///
+    /// ```rust
+    /// use gen_arena::*;
+    ///
+    /// let mut arena = ChunkArena::new();
+    ///
+    /// let i1 = arena.insert(1);
+    ///
+    /// *arena.get_mut(i1).unwrap() = 2;
+    ///
+    /// assert_eq!(*arena.get(i1).unwrap(), 2);
+    /// ```
+    ///
+    pub fn get_mut(&mut self, index: ArenaIdx<T>) -> Option<&mut T>{
+        if let RawCell::Allocated{val, generation} = self.cell_mut(index.index())?{
+            if *generation == index.gen(){
+                Some(val)
+            }
+            else{
+                None
+            }
+        }
+        else{
+            None
+        }
+    }
+
+    ///
+    /// Returns an optional mutable reference to the value of a cell at a index with any generation.
+    ///
+    pub fn get_any_mut(&mut self, index: usize) -> Option<&mut T>{
+        if let RawCell::Allocated{val, generation: _} = self.cell_mut(index)?{
+            Some(val)
+        }
+        else{
+            None
+        }
+    }
+
+    ///
+    /// Returns mutable optional references to two distinct values.
+    /// Panics if both indices refer to the same element, stale indices to the same slot yield None.
+    ///
+    ///```rust
+    /// use gen_arena::*;
+    ///
+    /// let mut arena = ChunkArena::new();
+    ///
+    /// let i1 = arena.insert(1);
+    /// let i2 = arena.insert(2);
+    ///
+    /// let (c1, c2) = arena.get2_mut((i1, i2));
+    ///
+    /// *c1.unwrap() = 3;
+    /// *c2.unwrap() = 4;
+    ///
+    /// assert_eq!(*arena.get(i1).unwrap(), 3);
+    /// assert_eq!(*arena.get(i2).unwrap(), 4);
+    ///```
+    ///
+    pub fn get2_mut(&mut self, indices: (ArenaIdx<T>, ArenaIdx<T>)) -> (Option<&mut T>, Option<&mut T>){
+        if indices.0.index() == indices.1.index(){
+            // Only a generation matching the allocated one refers to a value,
+            // so the same slot is an error only if both indices are live.
+            if self.get(indices.0).is_none(){
+                return (None, self.get_mut(indices.1));
+            }
+            if indices.0.gen() == indices.1.gen(){
+                panic!("Cannot take 2 mutable references to a value at the same index.")
+            }
+            return (self.get_mut(indices.0), None);
+        }
+
+        let cell = |arena: &Self, index: ArenaIdx<T>|{
+            // SAFETY: The indices are distinct so the cells are too,
+            // and the mutable reference to self guarantees exclusive access.
+            match unsafe{&mut *arena.cell(index.index())?.get()}{
+                RawCell::Allocated{val, generation} if *generation == index.gen() => Some(val),
+                _ => None,
+            }
+        };
+
+        (cell(self, indices.0), cell(self, indices.1))
+    }
+
+    ///
+    /// Returns iterator over all Allocated cells.
+    ///
+    #[inline]
+    pub fn values(&self) -> ChunkValues<'_, T>{
+        ChunkValues{
+            iter: self.iter(),
+        }
+    }
+
+    ///
+    /// Returns mutable iterator over all Allocated cells.
+    ///
+    #[inline]
+    pub fn values_mut(&mut self) -> ChunkValuesMut<'_, T>{
+        ChunkValuesMut{
+            iter: self.iter_mut(),
+        }
+    }
+
+    ///
+    /// Iterator over all keys in the arena.
+    ///
+    #[inline]
+    pub fn keys(&self) -> ChunkKeys<'_, T>{
+        ChunkKeys{
+            iter: self.iter(),
+        }
+    }
+
+    ///
+    /// Returns an iterator over the Allocated cells with index.
+    /// Values inserted while iterating may or may not be yielded.
+    ///
+    /// ```rust
+    /// use gen_arena::*;
+    ///
+    /// let arena = ChunkArena::new();
+    ///
+    /// let i1 = arena.insert(1);
+    /// let i2 = arena.insert(2);
+    ///
+    /// for (index, val) in arena.iter(){
+    ///     if index == i1{
+    ///         assert_eq!(*val, 1);
+    ///     }
+    ///     if index == i2{
+    ///         assert_eq!(*val, 2);
+    ///     }
+    /// }
+    /// ```
+    ///
+    #[inline]
+    pub fn iter(&self) -> ChunkIter<'_, T>{
+        ChunkIter{
+            arena: self,
+            index: 0,
+        }
+    }
+
+    ///
+    /// Returns an mutable iterator over the Allocated cells with indices.
+    ///
+    /// ```rust
+    /// use gen_arena::*;
+    ///
+    /// let mut arena = ChunkArena::new();
+    ///
+    /// let i1 = arena.insert(1);
+    /// let i2 = arena.insert(2);
+    ///
+    /// for (index, val) in arena.iter_mut(){
+    ///     *val = index.index();
+    /// }
+    ///
+    /// assert_eq!(*arena.get(i1).unwrap(), 0);
+    /// assert_eq!(*arena.get(i2).unwrap(), 1);
+    /// ```
+    ///
+    #[inline]
+    pub fn iter_mut(&mut self) -> ChunkIterMut<'_, T>{
+        ChunkIterMut{
+            chunks: self.chunks.get_mut().iter(),
+            cells: [].iter_mut(),
+            index: 0,
+        }
+    }
+
+    ///
+    /// Returns the number of cells in all allocated chunks.
+    ///
+    #[inline]
+    pub fn capacity(&self) -> usize{
+        self.num_chunks() * CHUNK_SIZE
+    }
+
+    #[inline]
+    pub fn num(&self) -> usize{
+        self.num.get()
+    }
+}
+
+impl<T> Default for ChunkArena<T>{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Drop for ChunkArena<T>{
+    fn drop(&mut self) {
+        for chunk in self.chunks.get_mut().drain(..){
+            // SAFETY: Every chunk was created from a boxed slice of CHUNK_SIZE cells.
+            unsafe{drop(Box::from_raw(std::ptr::slice_from_raw_parts_mut(chunk.as_ptr(), CHUNK_SIZE)))};
+        }
+    }
+}
+
+impl<T: std::fmt::Debug> std::fmt::Debug for ChunkArena<T>{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_map().entries(self.iter().map(|(i, val)| (i.index(), val))).finish()
+    }
+}
+
+impl<T> Index<ArenaIdx<T>> for ChunkArena<T>{
+    type Output = T;
+
+    fn index(&self, index: ArenaIdx<T>) -> &Self::Output {
+        self.get(index).expect("There is no element at this index with that generation.")
+    }
+}
+
+impl<T> IndexMut<ArenaIdx<T>> for ChunkArena<T>{
+    fn index_mut(&mut self, index: ArenaIdx<T>) -> &mut Self::Output {
+        self.get_mut(index).expect("There is no element at this index with that generation.")
+    }
+}
+
+pub struct ChunkIter<'i, T: 'i>{
+    arena: &'i ChunkArena<T>,
+    index: usize,
+}
+
+impl<'i, T> Iterator for ChunkIter<'i, T>{
+    type Item = (ArenaIdx<T>, &'i T);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // The chunk Vec may grow during iteration, so cells are looked up by index.
+        loop{
+            let i = self.index;
+            self.index += 1;
+            if let Some(val) = self.arena.get_any(i){
+                return Some((ArenaIdx::from_parts(i, self.arena.gen(i)), val));
+            }
+            if i >= self.arena.capacity(){
+                return None;
+            }
+        }
+    }
+}
+
+pub struct ChunkIterMut<'i, T: 'i>{
+    chunks: std::slice::Iter<'i, NonNull<UnsafeCell<RawCell<T>>>>,
+    cells: std::slice::IterMut<'i, UnsafeCell<RawCell<T>>>,
+    index: usize,
+}
+
+impl<'i, T> Iterator for ChunkIterMut<'i, T>{
+    type Item = (ArenaIdx<T>, &'i mut T);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop{
+            match self.cells.next(){
+                Some(cell) => {
+                    let i = self.index;
+                    self.index += 1;
+                    if let RawCell::Allocated{val, generation} = cell.get_mut(){
+                        return Some((ArenaIdx::from_parts(i, *generation), val));
+                    }
+                }
+                None => {
+                    let chunk = self.chunks.next()?;
+                    // SAFETY: Every chunk holds CHUNK_SIZE cells and the iterator borrows the arena mutably.
+                    self.cells = unsafe{std::slice::from_raw_parts_mut(chunk.as_ptr(), CHUNK_SIZE)}.iter_mut();
+                }
+            }
+        }
+    }
+}
+
+pub struct ChunkValues<'i, T: 'i>{
+    iter: ChunkIter<'i, T>,
+}
+
+impl<'i, T> Iterator for ChunkValues<'i, T>{
+    type Item = &'i T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.iter.next().map(|(_, val)|{val})
+    }
+}
+
+pub struct ChunkValuesMut<'i, T: 'i>{
+    iter: ChunkIterMut<'i, T>,
+}
+
+impl<'i, T> Iterator for ChunkValuesMut<'i, T>{
+    type Item = &'i mut T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.iter.next().map(|(_, val)|{val})
+    }
+}
+
+pub struct ChunkKeys<'i, T: 'i>{
+    iter: ChunkIter<'i, T>,
+}
+
+impl<'i, T> Iterator for ChunkKeys<'i, T>{
+    type Item = ArenaIdx<T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.iter.next().map(|(i, _)|{i})
+    }
+}
+
+#[cfg(test)]
+mod test{
+    use super::*;
+    use std::rc::Rc;
+
+    #[test]
+    fn test_references_survive_inserts(){
+        let arena = ChunkArena::new();
+
+        let keys: Vec<_> = (0..CHUNK_SIZE).map(|i| arena.insert(i)).collect();
+        let refs: Vec<&usize> = keys.iter().map(|k| arena.get_pinned(*k).unwrap()).collect();
+
+        let more: Vec<_> = (0..3 * CHUNK_SIZE).map(|i| arena.insert(i + CHUNK_SIZE)).collect();
+
+        for (i, r) in refs.iter().enumerate(){
+            assert_eq!(**r, i);
+            assert!(std::ptr::eq(*r, arena.get(keys[i]).unwrap()));
+        }
+        assert_eq!(arena.num(), 4 * CHUNK_SIZE);
+        assert_eq!(*arena.get(more[3 * CHUNK_SIZE - 1]).unwrap(), 4 * CHUNK_SIZE - 1);
+        assert_eq!(arena.iter().count(), 4 * CHUNK_SIZE);
+    }
+
+    #[test]
+    fn test_remove_and_reuse(){
+        let mut arena = ChunkArena::new();
+
+        let keys: Vec<_> = (0..2 * CHUNK_SIZE).map(|i| arena.insert(i)).collect();
+        arena.remove(keys[CHUNK_SIZE + 3]);
+        arena.remove(keys[1]);
+
+        assert_eq!(arena.get(keys[1]), None);
+        assert_eq!(arena.num(), 2 * CHUNK_SIZE - 2);
+
+        let i0 = arena.insert(100);
+        let i1 = arena.insert(101);
+        assert_eq!(i0, ArenaIdx::from_raw_parts(1, 1));
+        assert_eq!(i1, ArenaIdx::from_raw_parts(CHUNK_SIZE + 3, 1));
+        assert_eq!(arena.capacity(), 2 * CHUNK_SIZE);
+
+        for val in arena.values_mut(){
+            *val += 1;
+        }
+        assert_eq!(arena[i0], 101);
+        assert_eq!(arena[keys[0]], 1);
+        assert_eq!(arena.get(ArenaIdx::from_raw_parts(10 * CHUNK_SIZE, 0)), None);
+
+        let (a, b) = arena.get2_mut((keys[0], i1));
+        std::mem::swap(a.unwrap(), b.unwrap());
+        assert_eq!(arena[keys[0]], 102);
+        assert_eq!(arena.keys().count(), 2 * CHUNK_SIZE);
+    }
+
+    #[test]
+    fn test_drop_and_clear(){
+        let rc = Rc::new(());
+        let mut arena = ChunkArena::new();
+
+        let keys: Vec<_> = (0..CHUNK_SIZE + 1).map(|_| arena.insert(rc.clone())).collect();
+        assert_eq!(Rc::strong_count(&rc), CHUNK_SIZE + 2);
+
+        arena.remove(keys[0]);
+        assert_eq!(Rc::strong_count(&rc), CHUNK_SIZE + 1);
+
+        arena.clear();
+        assert_eq!(Rc::strong_count(&rc), 1);
+        assert_eq!(arena.get(keys[1]), None);
+
+        let i0 = arena.insert(rc.clone());
+        assert_eq!(i0, ArenaIdx::from_raw_parts(0, 1));
+        drop(arena);
+        assert_eq!(Rc::strong_count(&rc), 1);
+    }
+
+    #[test]
+    fn test_get2_mut_same_slot(){
+        let mut arena = ChunkArena::new();
+        let stale = arena.insert(0);
+        arena.remove(stale);
+        let live = arena.insert(1);
+        assert_eq!(stale.index(), live.index());
+
+        assert_eq!(arena.get2_mut((stale, stale)), (None, None));
+        assert_eq!(arena.get2_mut((live, stale)), (Some(&mut 1), None));
+        assert_eq!(arena.get2_mut((stale, live)), (None, Some(&mut 1)));
+
+        // A stale key with a higher generation, as after wrapping, does not win over the live one.
+        let ahead = ArenaIdx::from_raw_parts(live.index(), live.gen() + 5);
+        assert_eq!(arena.get2_mut((ahead, live)), (None, Some(&mut 1)));
+        assert_eq!(arena.get2_mut((live, ahead)), (Some(&mut 1), None));
+
+        // Two stale copies of a freed slot.
+        arena.remove(live);
+        assert_eq!(arena.get2_mut((live, live)), (None, None));
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_get2_mut_same_live(){
+        let mut arena = ChunkArena::new();
+        let i0 = arena.insert(0);
+        let _ = arena.get2_mut((i0, i0));
+    }
+}
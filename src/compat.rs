@@ -0,0 +1,308 @@
+//!
+//! Adapters mirroring the APIs of other crates, to ease migrating to this one.
+//!
+
+use std::ops::{Index, IndexMut};
+
+use crate::*;
+
+///
+/// An Arena with the API of the slab crate.
+///
+/// Keys are plain usize slot indices, which are mapped to the generation of the element currently
+/// stored in the slot. This makes it possible to replace the slab dependency first and switch
+/// code over to generational ArenaIdx keys one place at a time, using idx to convert a key and
+/// arena/arena_mut to reach the underlying Arena.
+///
+/// Like in the slab crate, a key of a removed element refers to whichever element is inserted
+/// into its slot next. Only ArenaIdx keys protect against that.
+///
+/// # Example
+///
+///```rust
+/// use gen_arena::compat::Slab;
+///
+/// let mut slab = Slab::new();
+///
+/// let hello = slab.insert("hello");
+/// let world = slab.insert("world");
+///
+/// assert_eq!(slab[hello], "hello");
+/// assert_eq!(slab.remove(world), "world");
+/// assert!(!slab.contains(world));
+///
+/// let entry = slab.vacant_entry();
+/// let key = entry.key();
+/// entry.insert("again");
+/// assert_eq!(slab[key], "again");
+///
+/// // Generational keys for new code.
+/// let idx = slab.idx(hello).unwrap();
+/// assert_eq!(slab.arena()[idx], "hello");
+///```
+///
+#[derive(Debug, Clone)]
+pub struct Slab<T>{
+    arena: Arena<T>,
+}
+
+impl<T> Slab<T>{
+    ///
+    /// Creates an empty Slab.
+    ///
+    pub fn new() -> Self{
+        Self{
+            arena: Arena::new(),
+        }
+    }
+
+    ///
+    /// Creates an empty Slab with capacity.
+    ///
+    pub fn with_capacity(cap: usize) -> Self{
+        Self{
+            arena: Arena::with_capacity(cap),
+        }
+    }
+
+    ///
+    /// Returns the generational key of the element at the key.
+    ///
+    #[inline]
+    pub fn idx(&self, key: usize) -> Option<ArenaIdx<T>>{
+        self.arena.idx_at(key)
+    }
+
+    ///
+    /// Returns the underlying Arena.
+    ///
+    #[inline]
+    pub fn arena(&self) -> &Arena<T>{
+        &self.arena
+    }
+
+    ///
+    /// Returns the underlying Arena mutably.
+    ///
+    #[inline]
+    pub fn arena_mut(&mut self) -> &mut Arena<T>{
+        &mut self.arena
+    }
+
+    ///
+    /// Returns the underlying Arena.
+    ///
+    #[inline]
+    pub fn into_arena(self) -> Arena<T>{
+        self.arena
+    }
+
+    ///
+    /// Inserts the value and returns its key.
+    ///
+    pub fn insert(&mut self, val: T) -> usize{
+        self.arena.insert(val).index()
+    }
+
+    ///
+    /// Returns the key the next inserted value will get.
+    ///
+    #[inline]
+    pub fn vacant_key(&self) -> usize{
+        self.arena.vacant_index()
+    }
+
+    ///
+    /// Returns a handle to the slot the next inserted value will be stored in.
+    ///
+    pub fn vacant_entry(&mut self) -> VacantEntry<'_, T>{
+        VacantEntry{
+            key: self.vacant_key(),
+            arena: &mut self.arena,
+        }
+    }
+
+    ///
+    /// Removes the element and returns its value.
+    /// Panics if there is no element at the key.
+    ///
+    pub fn remove(&mut self, key: usize) -> T{
+        self.try_remove(key).expect("invalid key")
+    }
+
+    ///
+    /// Removes the element and returns its value if there is one at the key.
+    ///
+    pub fn try_remove(&mut self, key: usize) -> Option<T>{
+        let idx = self.idx(key)?;
+        self.arena.remove(idx)
+    }
+
+    #[inline]
+    pub fn contains(&self, key: usize) -> bool{
+        self.idx(key).is_some()
+    }
+
+    #[inline]
+    pub fn get(&self, key: usize) -> Option<&T>{
+        self.arena.get_any(key)
+    }
+
+    #[inline]
+    pub fn get_mut(&mut self, key: usize) -> Option<&mut T>{
+        self.arena.get_any_mut(key)
+    }
+
+    ///
+    /// Returns an iterator over the elements with their keys.
+    ///
+    pub fn iter(&self) -> impl Iterator<Item = (usize, &T)>{
+        self.arena.iter().map(|(idx, val)| (idx.index(), val))
+    }
+
+    ///
+    /// Returns a mutable iterator over the elements with their keys.
+    ///
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (usize, &mut T)>{
+        self.arena.iter_mut().map(|(idx, val)| (idx.index(), val))
+    }
+
+    ///
+    /// Removes all elements for which f returns false.
+    ///
+    pub fn retain(&mut self, mut f: impl FnMut(usize, &mut T) -> bool){
+        let remove: Vec<_> = self.arena.iter_mut()
+            .filter_map(|(idx, val)| (!f(idx.index(), val)).then_some(idx))
+            .collect();
+        for idx in remove{
+            self.arena.remove(idx);
+        }
+    }
+
+    #[inline]
+    pub fn clear(&mut self){
+        self.arena.clear()
+    }
+
+    #[inline]
+    pub fn reserve(&mut self, additional: usize){
+        self.arena.reserve(additional)
+    }
+
+    #[inline]
+    pub fn capacity(&self) -> usize{
+        self.arena.capacity()
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize{
+        self.arena.num()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool{
+        self.len() == 0
+    }
+}
+
+impl<T> Default for Slab<T>{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Index<usize> for Slab<T>{
+    type Output = T;
+
+    fn index(&self, key: usize) -> &Self::Output {
+        self.get(key).expect("invalid key")
+    }
+}
+
+impl<T> IndexMut<usize> for Slab<T>{
+    fn index_mut(&mut self, key: usize) -> &mut Self::Output {
+        self.get_mut(key).expect("invalid key")
+    }
+}
+
+impl<T> From<Arena<T>> for Slab<T>{
+    fn from(arena: Arena<T>) -> Self {
+        Self{
+            arena,
+        }
+    }
+}
+
+///
+/// A handle to a vacant slot of a Slab.
+///
+#[derive(Debug)]
+pub struct VacantEntry<'a, T>{
+    arena: &'a mut Arena<T>,
+    key: usize,
+}
+
+impl<'a, T> VacantEntry<'a, T>{
+    ///
+    /// Returns the key the value will be inserted at.
+    ///
+    #[inline]
+    pub fn key(&self) -> usize{
+        self.key
+    }
+
+    ///
+    /// Inserts the value into the slot and returns a mutable reference to it.
+    ///
+    pub fn insert(self, val: T) -> &'a mut T{
+        let idx = self.arena.insert(val);
+        debug_assert_eq!(idx.index(), self.key, "The vacant slot is the next one to be used.");
+        self.arena.get_mut(idx).expect("The value was just inserted.")
+    }
+}
+
+#[cfg(test)]
+mod test{
+    use super::*;
+
+    #[test]
+    fn test_slab_api(){
+        let mut slab = Slab::with_capacity(4);
+        let keys: Vec<_> = (0..6).map(|i| slab.insert(i)).collect();
+        assert_eq!(keys, [0, 1, 2, 3, 4, 5]);
+
+        assert_eq!(slab.remove(2), 2);
+        assert_eq!(slab.try_remove(2), None);
+        assert_eq!(slab.get(2), None);
+        assert_eq!(slab.vacant_key(), 2);
+
+        // The freed slot is reused, like in the slab crate.
+        let entry = slab.vacant_entry();
+        assert_eq!(entry.key(), 2);
+        *entry.insert(20) += 1;
+        assert_eq!(slab[2], 21);
+
+        slab.retain(|key, val|{
+            *val *= 10;
+            key % 2 == 0
+        });
+        let items: Vec<_> = slab.iter().map(|(k, v)| (k, *v)).collect();
+        assert_eq!(items, [(0, 0), (2, 210), (4, 40)]);
+        assert_eq!(slab.len(), 3);
+
+        // Generational keys catch what usize keys can not.
+        let idx = slab.idx(4).unwrap();
+        slab.remove(4);
+        let key = slab.insert(4);
+        assert_eq!(key, 4);
+        assert_eq!(slab.get(4), Some(&4));
+        assert_eq!(slab.arena().get(idx), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "invalid key")]
+    fn test_remove_invalid_key(){
+        let mut slab = Slab::<u8>::new();
+        slab.remove(0);
+    }
+}
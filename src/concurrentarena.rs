@@ -0,0 +1,287 @@
+use std::sync::{atomic::{AtomicUsize, Ordering}, Mutex, MutexGuard, TryLockError};
+
+use crate::*;
+
+///
+/// A Generational Arena that can be shared between threads.
+///
+/// Slots are sharded across several Mutex protected Arenas, so threads working on different shards
+/// do not block each other. The shard is encoded in the index of the handle:
+/// a handle with index i refers to the slot i / shards of shard i % shards.
+///
+/// A closure passed to with or with_mut that panics leaves its shard poisoned, but the arena
+/// is still intact, so the shard keeps being used. Only the element may be half updated.
+///
+/// # Example
+///
+///```rust
+/// use gen_arena::*;
+///
+/// let arena = ConcurrentArena::with_shards(4);
+///
+/// let keys: Vec<_> = std::thread::scope(|s|{
+///     let handles: Vec<_> = (0..4).map(|i|{
+///         let arena = &arena;
+///         s.spawn(move || arena.insert(i))
+///     }).collect();
+///     handles.into_iter().map(|h| h.join().unwrap()).collect()
+/// });
+///
+/// for (i, key) in keys.iter().enumerate(){
+///     assert_eq!(arena.with(*key, |val| *val), Some(i));
+/// }
+///
+/// assert_eq!(arena.remove(keys[0]), Some(0));
+/// assert_eq!(arena.with(keys[0], |val| *val), None);
+/// assert_eq!(arena.num(), 3);
+///```
+///
+#[derive(Debug)]
+pub struct ConcurrentArena<T>{
+    shards: Box<[Mutex<Arena<T>>]>,
+    next: AtomicUsize,
+}
+
+impl<T> ConcurrentArena<T>{
+    ///
+    /// Creates an empty ConcurrentArena with one shard per available thread.
+    ///
+    pub fn new() -> Self{
+        Self::with_shards(std::thread::available_parallelism().map(|n| n.get()).unwrap_or(1))
+    }
+
+    ///
+    /// Creates an empty ConcurrentArena with the given number of shards.
+    /// Panics if shards is zero.
+    ///
+    pub fn with_shards(shards: usize) -> Self{
+        assert!(shards > 0, "A ConcurrentArena needs at least one shard.");
+        Self{
+            shards: (0..shards).map(|_| Mutex::new(Arena::new())).collect(),
+            next: AtomicUsize::new(0),
+        }
+    }
+
+    ///
+    /// Returns the number of shards.
+    ///
+    #[inline]
+    pub fn shards(&self) -> usize{
+        self.shards.len()
+    }
+
+    // A panic while a shard is locked comes from user code, like the closures of with and with_mut,
+    // and leaves the arena intact.
+    #[inline]
+    fn lock(&self, shard: usize) -> MutexGuard<'_, Arena<T>>{
+        self.shards[shard].lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    ///
+    /// Splits a global index into the shard and the index local to the shard.
+    ///
+    #[inline]
+    fn split(&self, index: ArenaIdx<T>) -> (usize, ArenaIdx<T>){
+        let shards = self.shards.len();
+        (index.index() % shards, ArenaIdx::from_parts(index.index() / shards, index.gen()))
+    }
+
+    ///
+    /// Tries to insert into the arena.
+    /// Returns val as Err if failed.
+    ///
+    pub fn try_insert(&self, val: T) -> Result<ArenaIdx<T>, T>{
+        let shards = self.shards.len();
+        let start = self.next.fetch_add(1, Ordering::Relaxed) % shards;
+
+        // Prefer a shard that is not locked by another thread.
+        let (shard, mut arena) = (0..shards)
+            .map(|i| (start + i) % shards)
+            .find_map(|shard| match self.shards[shard].try_lock(){
+                Ok(arena) => Some((shard, arena)),
+                Err(TryLockError::Poisoned(poisoned)) => Some((shard, poisoned.into_inner())),
+                Err(TryLockError::WouldBlock) => None,
+            })
+            .unwrap_or_else(|| (start, self.lock(start)));
+
+        let local = arena.try_insert(val).map_err(InsertError::into_value)?;
+        Ok(ArenaIdx::from_parts(local.index() * shards + shard, local.gen()))
+    }
+
+    ///
+    /// Inserts a new element into the arena.
+    ///
+    #[must_use]
+    pub fn insert(&self, val: T) -> ArenaIdx<T>{
+        match self.try_insert(val){
+            Ok(index) => index,
+            Err(_val) => panic!("Insertion not successfull."),
+        }
+    }
+
+    ///
+    /// Removes the element from the arena.
+    /// Returns the value if the index is still valid.
+    ///
+    pub fn remove(&self, index: ArenaIdx<T>) -> Option<T>{
+        let (shard, local) = self.split(index);
+        self.lock(shard).remove(local)
+    }
+
+    ///
+    /// Calls f with a reference to the element and returns its result,
+    /// or None if the index is not valid.
+    /// The shard of the element is locked while f runs.
+    ///
+    pub fn with<R>(&self, index: ArenaIdx<T>, f: impl FnOnce(&T) -> R) -> Option<R>{
+        let (shard, local) = self.split(index);
+        self.lock(shard).get(local).map(f)
+    }
+
+    ///
+    /// Calls f with a mutable reference to the element and returns its result,
+    /// or None if the index is not valid.
+    /// The shard of the element is locked while f runs.
+    ///
+    /// ```rust
+    /// use gen_arena::*;
+    ///
+    /// let arena = ConcurrentArena::with_shards(2);
+    ///
+    /// let i0 = arena.insert(1);
+    ///
+    /// arena.with_mut(i0, |val| *val += 1);
+    ///
+    /// assert_eq!(arena.with(i0, |val| *val), Some(2));
+    /// ```
+    ///
+    pub fn with_mut<R>(&self, index: ArenaIdx<T>, f: impl FnOnce(&mut T) -> R) -> Option<R>{
+        let (shard, local) = self.split(index);
+        self.lock(shard).get_mut(local).map(f)
+    }
+
+    ///
+    /// Returns a mutable optional reference to the element.
+    /// No locking is needed since the arena is borrowed mutably.
+    ///
+    pub fn get_mut(&mut self, index: ArenaIdx<T>) -> Option<&mut T>{
+        let (shard, local) = self.split(index);
+        self.shards[shard].get_mut().unwrap_or_else(|poisoned| poisoned.into_inner()).get_mut(local)
+    }
+
+    ///
+    /// Clears all shards.
+    ///
+    pub fn clear(&self){
+        for shard in 0..self.shards.len(){
+            self.lock(shard).clear();
+        }
+    }
+
+    ///
+    /// Calls f for every element in the arena, locking one shard at a time.
+    ///
+    pub fn for_each(&self, mut f: impl FnMut(ArenaIdx<T>, &T)){
+        let shards = self.shards.len();
+        for shard in 0..shards{
+            for (local, val) in self.lock(shard).iter(){
+                f(ArenaIdx::from_parts(local.index() * shards + shard, local.gen()), val);
+            }
+        }
+    }
+
+    ///
+    /// Returns the number of elements in the arena.
+    /// Other threads may modify the arena while the shards are counted.
+    ///
+    pub fn num(&self) -> usize{
+        (0..self.shards.len()).map(|shard| self.lock(shard).num()).sum()
+    }
+}
+
+impl<T> Default for ConcurrentArena<T>{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod test{
+    use super::*;
+    use std::collections::{HashMap, HashSet};
+
+    #[test]
+    fn test_send_sync(){
+        fn assert_send_sync<T: Send + Sync>(){}
+        assert_send_sync::<ConcurrentArena<std::cell::Cell<i32>>>();
+    }
+
+    #[test]
+    fn test_poisoned_shard(){
+        let mut arena = ConcurrentArena::with_shards(1);
+        let a = arena.insert(0);
+        let panicked = std::panic::catch_unwind(std::panic::AssertUnwindSafe(||{
+            arena.with_mut(a, |_| panic!("A user closure panicked."))
+        }));
+        assert!(panicked.is_err());
+
+        // The shard keeps working.
+        assert_eq!(arena.with_mut(a, |val|{*val += 1; *val}), Some(1));
+        let b = arena.insert(2);
+        assert_eq!(arena.num(), 2);
+        assert_eq!(arena.get_mut(b), Some(&mut 2));
+        assert_eq!(arena.remove(a), Some(1));
+    }
+
+    #[test]
+    fn test_stress(){
+        const THREADS: usize = 8;
+        const OPS: usize = 2000;
+
+        let arena = ConcurrentArena::<(usize, usize)>::with_shards(4);
+
+        let kept: Vec<Vec<_>> = std::thread::scope(|s|{
+            let handles: Vec<_> = (0..THREADS).map(|t|{
+                let arena = &arena;
+                s.spawn(move ||{
+                    let mut kept = Vec::new();
+                    for i in 0..OPS{
+                        let key = arena.insert((t, i));
+                        if i % 3 == 0{
+                            assert_eq!(arena.remove(key), Some((t, i)));
+                            assert_eq!(arena.remove(key), None);
+                        }
+                        else{
+                            kept.push((key, (t, i)));
+                        }
+                    }
+                    kept
+                })
+            }).collect();
+            handles.into_iter().map(|h| h.join().unwrap()).collect()
+        });
+
+        let kept: Vec<_> = kept.into_iter().flatten().collect();
+
+        // No slot was handed out twice.
+        let unique: HashSet<_> = kept.iter().map(|(key, _)| key.index()).collect();
+        assert_eq!(unique.len(), kept.len());
+
+        // No value got lost.
+        assert_eq!(arena.num(), kept.len());
+        for (key, val) in kept.iter(){
+            assert_eq!(arena.with(*key, |v| *v), Some(*val));
+        }
+
+        let expected: HashMap<_, _> = kept.iter().map(|(key, val)| (key.index(), (key.gen(), *val))).collect();
+        let mut count = 0;
+        arena.for_each(|key, val|{
+            assert_eq!(expected[&key.index()], (key.gen(), *val));
+            count += 1;
+        });
+        assert_eq!(count, kept.len());
+
+        arena.clear();
+        assert_eq!(arena.num(), 0);
+    }
+}
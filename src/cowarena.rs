@@ -0,0 +1,289 @@
+use std::{ops::{Deref, Index, IndexMut}, sync::Arc};
+
+use crate::*;
+
+///
+/// A copy on write Arena.
+/// The cells are held in an Arc so cloning a CowArena is O(1).
+/// Any mutating call copies the storage first if it is shared with another clone.
+/// Since the layout is copied bit for bit, keys stay valid across clones.
+///
+/// All non mutating methods of Arena are available through Deref.
+///
+/// # Example
+///
+///```rust
+/// use gen_arena::*;
+///
+/// let mut arena = CowArena::new();
+///
+/// let i0 = arena.insert(0);
+///
+/// let snapshot = arena.clone();
+///
+/// *arena.get_mut(i0).unwrap() = 1;
+/// let i1 = arena.insert(2);
+///
+/// assert_eq!(*arena.get(i0).unwrap(), 1);
+/// assert_eq!(*snapshot.get(i0).unwrap(), 0);
+/// assert_eq!(*arena.get(i1).unwrap(), 2);
+/// assert_eq!(snapshot.num(), 1);
+///
+///```
+///
+#[derive(Debug)]
+pub struct CowArena<T>{
+    arena: Arc<Arena<T>>,
+}
+
+impl<T> Clone for CowArena<T>{
+    #[inline]
+    fn clone(&self) -> Self {
+        Self{
+            arena: self.arena.clone(),
+        }
+    }
+}
+
+impl<T> CowArena<T>{
+    ///
+    /// Creates an empty CowArena.
+    ///
+    pub fn new() -> Self{
+        Self{
+            arena: Arc::new(Arena::new()),
+        }
+    }
+
+    ///
+    /// Creates an empty CowArena with capacity.
+    ///
+    pub fn with_capacity(cap: usize) -> Self{
+        Self{
+            arena: Arc::new(Arena::with_capacity(cap)),
+        }
+    }
+
+    ///
+    /// Returns true if the storage is shared with another clone.
+    ///
+    /// ```rust
+    /// use gen_arena::*;
+    ///
+    /// let arena = CowArena::<i32>::new();
+    /// assert!(!arena.is_shared());
+    ///
+    /// let snapshot = arena.clone();
+    /// assert!(arena.is_shared());
+    ///
+    /// drop(snapshot);
+    /// assert!(!arena.is_shared());
+    /// ```
+    ///
+    #[inline]
+    pub fn is_shared(&self) -> bool{
+        Arc::strong_count(&self.arena) > 1
+    }
+
+    ///
+    /// Returns true if both arenas share the same storage.
+    ///
+    #[inline]
+    pub fn ptr_eq(&self, other: &Self) -> bool{
+        Arc::ptr_eq(&self.arena, &other.arena)
+    }
+}
+
+impl<T> Default for CowArena<T>{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Clone> CowArena<T>{
+    ///
+    /// Returns a mutable reference to the underlying Arena,
+    /// copying the storage if it is shared.
+    ///
+    #[inline]
+    pub fn make_mut(&mut self) -> &mut Arena<T>{
+        Arc::make_mut(&mut self.arena)
+    }
+
+    ///
+    /// Tries to insert into the arena.
+    /// Returns val as Err if failed.
+    ///
+    pub fn try_insert(&mut self, val: T) -> Result<ArenaIdx<T>, T>{
+        self.make_mut().try_insert(val).map_err(InsertError::into_value)
+    }
+
+    ///
+    /// Inserts a new element into the arena.
+    ///
+    #[must_use]
+    pub fn insert(&mut self, val: T) -> ArenaIdx<T>{
+        self.make_mut().insert(val)
+    }
+
+    ///
+    /// Removes the cell from the arena and increaces its generation.
+    /// Returns the value if the index is still valid.
+    ///
+    pub fn remove(&mut self, index: ArenaIdx<T>) -> Option<T>{
+        self.make_mut().remove(index)
+    }
+
+    ///
+    /// Clears the arena.
+    ///
+    pub fn clear(&mut self){
+        self.make_mut().clear()
+    }
+
+    ///
+    /// Returns a mutable optional reference to the value at the index.
+    ///
+    pub fn get_mut(&mut self, index: ArenaIdx<T>) -> Option<&mut T>{
+        self.make_mut().get_mut(index)
+    }
+
+    ///
+    /// Returns an optional mutable reference to the value of a cell at a index with any generation.
+    ///
+    pub fn get_any_mut(&mut self, index: usize) -> Option<&mut T>{
+        self.make_mut().get_any_mut(index)
+    }
+
+    ///
+    /// Returns mutable optional references to two distinct values.
+    ///
+    pub fn get2_mut(&mut self, indices: (ArenaIdx<T>, ArenaIdx<T>)) -> (Option<&mut T>, Option<&mut T>){
+        self.make_mut().get2_mut(indices)
+    }
+
+    ///
+    /// Returns mutable iterator over all Allocated cells.
+    ///
+    #[inline]
+    pub fn values_mut(&mut self) -> ValuesMut<'_, T>{
+        self.make_mut().values_mut()
+    }
+
+    ///
+    /// Returns an mutable iterator over the Allocated cells with indices.
+    ///
+    #[inline]
+    pub fn iter_mut(&mut self) -> IterMut<'_, T>{
+        self.make_mut().iter_mut()
+    }
+
+    #[inline]
+    pub fn reserve(&mut self, additional: usize){
+        self.make_mut().reserve(additional)
+    }
+}
+
+impl<T> Deref for CowArena<T>{
+    type Target = Arena<T>;
+
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        &self.arena
+    }
+}
+
+impl<T> From<Arena<T>> for CowArena<T>{
+    fn from(arena: Arena<T>) -> Self {
+        Self{
+            arena: Arc::new(arena),
+        }
+    }
+}
+
+impl<T> Index<ArenaIdx<T>> for CowArena<T>{
+    type Output = T;
+
+    fn index(&self, index: ArenaIdx<T>) -> &Self::Output {
+        &self.arena[index]
+    }
+}
+
+impl<T: Clone> IndexMut<ArenaIdx<T>> for CowArena<T>{
+    fn index_mut(&mut self, index: ArenaIdx<T>) -> &mut Self::Output {
+        &mut self.make_mut()[index]
+    }
+}
+
+#[cfg(test)]
+mod test{
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    static CLONES: AtomicUsize = AtomicUsize::new(0);
+
+    #[derive(Debug, PartialEq)]
+    struct Counted(i32);
+
+    impl Clone for Counted{
+        fn clone(&self) -> Self {
+            CLONES.fetch_add(1, Ordering::SeqCst);
+            Counted(self.0)
+        }
+    }
+
+    #[test]
+    fn test_clones_are_independent(){
+        let mut a = CowArena::new();
+
+        let i0 = a.insert(0);
+        let i1 = a.insert(1);
+
+        let mut b = a.clone();
+        assert!(a.ptr_eq(&b));
+
+        a.remove(i0);
+        *b.get_mut(i1).unwrap() = 10;
+        let i2 = b.insert(2);
+
+        assert!(!a.ptr_eq(&b));
+        assert_eq!(a.get(i0), None);
+        assert_eq!(b.get(i0), Some(&0));
+        assert_eq!(a.get(i1), Some(&1));
+        assert_eq!(b.get(i1), Some(&10));
+        assert_eq!(b.get(i2), Some(&2));
+        assert_eq!(a.num(), 1);
+        assert_eq!(b.num(), 3);
+
+        let c = b.clone();
+        b.clear();
+        assert_eq!(b.num(), 0);
+        assert_eq!(c.num(), 3);
+        assert_eq!(c[i1], 10);
+    }
+
+    #[test]
+    fn test_no_copy_when_unique(){
+        let mut a = CowArena::new();
+
+        let i0 = a.insert(Counted(0));
+        let i1 = a.insert(Counted(1));
+        a.get_mut(i0).unwrap().0 = 2;
+        a.remove(i1);
+        a[i0].0 = 3;
+        assert_eq!(CLONES.load(Ordering::SeqCst), 0);
+
+        let b = a.clone();
+        assert_eq!(CLONES.load(Ordering::SeqCst), 0);
+        assert_eq!(b[i0], Counted(3));
+
+        a.get_mut(i0).unwrap().0 = 4;
+        assert_eq!(CLONES.load(Ordering::SeqCst), 1);
+        assert_eq!(b[i0], Counted(3));
+        assert_eq!(a[i0], Counted(4));
+
+        drop(b);
+        a.get_mut(i0).unwrap().0 = 5;
+        assert_eq!(CLONES.load(Ordering::SeqCst), 1);
+    }
+}
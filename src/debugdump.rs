@@ -0,0 +1,131 @@
+use std::fmt::{self, Write};
+
+use crate::*;
+
+impl<T, G: GenCounter + fmt::Display, Tag> Arena<T, G, Tag>{
+    ///
+    /// Returns a line for the head of the free list and one for every slot,
+    /// to look at fragmentation or a corrupted free list.
+    ///
+    /// ```rust
+    /// use gen_arena::*;
+    ///
+    /// let mut arena = Arena::new();
+    /// let keys: Vec<_> = (0..3).map(|i| arena.insert(i)).collect();
+    /// arena.remove(keys[0]);
+    /// arena.remove(keys[2]);
+    ///
+    /// assert_eq!(arena.debug_dump(), "\
+    /// free -> #2
+    /// #0 gen=1 free
+    /// #1 gen=0 LIVE
+    /// #2 gen=1 free -> #0
+    /// ");
+    /// ```
+    ///
+    pub fn debug_dump(&self) -> String{
+        let mut dump = String::new();
+        match self.raw().freed{
+            Some(head) => writeln!(dump, "free -> #{}", head),
+            None => writeln!(dump, "free list empty"),
+        }.expect("Writing to a String does not fail.");
+        for (i, state) in self.slot_states().enumerate(){
+            match state{
+                #[cfg(feature = "labels")]
+                SlotState::Live{generation, ..} if self.slot_label(i).is_some() => {
+                    writeln!(dump, "#{} gen={} LIVE {:?}", i, generation, self.slot_label(i).unwrap_or_default())
+                }
+                SlotState::Live{generation, ..} => writeln!(dump, "#{} gen={} LIVE", i, generation),
+                SlotState::Free{next: Some(next), generation} => writeln!(dump, "#{} gen={} free -> #{}", i, generation, next),
+                SlotState::Free{next: None, generation} => writeln!(dump, "#{} gen={} free", i, generation),
+                SlotState::Retired{generation} => writeln!(dump, "#{} gen={} retired", i, generation),
+                SlotState::Reserved{generation} => writeln!(dump, "#{} gen={} reserved", i, generation),
+            }.expect("Writing to a String does not fail.");
+        }
+        dump
+    }
+
+    ///
+    /// Returns a Graphviz graph of the slots and the free list.
+    ///
+    /// Live slots are filled, retired ones dashed and reserved ones dotted, the edges follow the free list from its head,
+    /// so cycles and freed slots that are not on the list stand out. Render it with `dot -Tsvg`.
+    ///
+    pub fn to_dot(&self) -> String{
+        let mut dot = String::from("digraph arena{\n    rankdir=LR;\n    head [shape=point];\n");
+        for (i, state) in self.slot_states().enumerate(){
+            let style = match state{
+                SlotState::Live{..} => ", style=filled",
+                SlotState::Free{..} => "",
+                SlotState::Retired{..} => ", style=dashed",
+                SlotState::Reserved{..} => ", style=dotted",
+            };
+            writeln!(dot, "    s{} [label=\"#{} gen={}\"{}];", i, i, state.generation(), style)
+                .expect("Writing to a String does not fail.");
+        }
+        if let Some(head) = self.raw().freed{
+            writeln!(dot, "    head -> s{};", head).expect("Writing to a String does not fail.");
+        }
+        for (i, state) in self.slot_states().enumerate(){
+            if let SlotState::Free{next: Some(next), ..} = state{
+                writeln!(dot, "    s{} -> s{};", i, next).expect("Writing to a String does not fail.");
+            }
+        }
+        dot.push_str("}\n");
+        dot
+    }
+}
+
+#[cfg(test)]
+mod test{
+    use super::*;
+
+    // Two reused slots, a retired one and a trailing freed one.
+    fn scripted() -> Arena<&'static str, u8>{
+        let mut arena = Arena::default();
+        let keys: Vec<_> = ["a", "b", "c", "d", "e"].into_iter().map(|val| arena.insert(val)).collect();
+        arena.remove(keys[1]);
+        let b = arena.insert("b2");
+        arena.remove(keys[3]);
+        arena.remove(keys[4]);
+        arena.remove(b);
+        arena.remove(keys[0]);
+        let mut retired = arena.insert("retired");
+        for _ in 0..253{
+            arena.remove(retired);
+            retired = arena.insert("retired");
+        }
+        arena.remove(retired);
+        arena
+    }
+
+    #[test]
+    fn test_debug_dump(){
+        assert_eq!(Arena::<u32>::new().debug_dump(), "free list empty\n");
+        assert_eq!(scripted().debug_dump(), "\
+free -> #1
+#0 gen=255 retired
+#1 gen=2 free -> #4
+#2 gen=0 LIVE
+#3 gen=1 free
+#4 gen=1 free -> #3
+");
+    }
+
+    #[test]
+    fn test_to_dot(){
+        assert_eq!(scripted().to_dot(), r##"digraph arena{
+    rankdir=LR;
+    head [shape=point];
+    s0 [label="#0 gen=255", style=dashed];
+    s1 [label="#1 gen=2"];
+    s2 [label="#2 gen=0", style=filled];
+    s3 [label="#3 gen=1"];
+    s4 [label="#4 gen=1"];
+    head -> s1;
+    s1 -> s4;
+    s4 -> s3;
+}
+"##);
+    }
+}
@@ -0,0 +1,527 @@
+use std::ops::{Index, IndexMut};
+
+use crate::*;
+
+///
+/// A Generational Arena that keeps its values packed in a Vec.
+///
+/// Keys refer to slots in a sparse table which map to positions in the dense value Vec.
+/// Removing a value swaps the last value into its position, so the order of values is not stable,
+/// but keys are.
+/// Lookups cost one more indirection than in Arena, while iterating over the values is plain slice iteration.
+///
+/// # Example
+///
+///```rust
+/// use gen_arena::*;
+///
+/// let mut arena = DenseArena::new();
+///
+/// let i0 = arena.insert(0);
+/// let i1 = arena.insert(1);
+/// let i2 = arena.insert(2);
+///
+/// assert_eq!(arena.remove(i0), Some(0));
+///
+/// assert_eq!(arena.get(i0), None);
+/// assert_eq!(*arena.get(i1).unwrap(), 1);
+/// assert_eq!(*arena.get(i2).unwrap(), 2);
+///
+/// assert_eq!(arena.as_slice(), &[2, 1]);
+///```
+///
+#[derive(Debug, Clone)]
+pub struct DenseArena<T>{
+    values: Vec<T>,
+    keys: Vec<ArenaIdx<T>>,
+    slots: Vec<RawCell<usize>>,
+    freed: Option<usize>,
+}
+
+impl<T> DenseArena<T>{
+    ///
+    /// Creates an empty DenseArena.
+    ///
+    pub fn new() -> Self{
+        Self{
+            values: Vec::new(),
+            keys: Vec::new(),
+            slots: Vec::new(),
+            freed: None,
+        }
+    }
+
+    ///
+    /// Creates an empty DenseArena with capacity.
+    ///
+    pub fn with_capacity(cap: usize) -> Self{
+        Self{
+            values: Vec::with_capacity(cap),
+            keys: Vec::with_capacity(cap),
+            slots: Vec::with_capacity(cap),
+            freed: None,
+        }
+    }
+
+    ///
+    /// Clears the arena and resets the list of Freed slots.
+    ///
+    /// ```rust
+    /// use gen_arena::*;
+    ///
+    /// let mut arena = DenseArena::new();
+    ///
+    /// let i1 = arena.insert(1);
+    /// let i2 = arena.insert(2);
+    ///
+    /// arena.clear();
+    ///
+    /// assert_eq!(arena.get(i1), None);
+    /// assert_eq!(arena.get(i2), None);
+    /// assert!(arena.as_slice().is_empty());
+    /// ```
+    ///
+    pub fn clear(&mut self){
+        let len = self.slots.len();
+        for (i, slot) in self.slots.iter_mut().enumerate(){
+            let next = if i < len - 1 {Some(i + 1)} else{None};
+            match slot{
+                RawCell::Allocated{val: _, generation} => {
+                    *slot = RawCell::Freed{
+                        generation: *generation + 1,
+                        next,
+                    }
+                },
+                RawCell::Freed{next: _, generation} => {
+                    *slot = RawCell::Freed{
+                        generation: *generation,
+                        next,
+                    }
+                }
+            }
+        }
+        self.freed = if len > 0 {Some(0)} else{None};
+        self.values.clear();
+        self.keys.clear();
+    }
+
+    ///
+    /// Tries to insert into the arena.
+    /// Returns val as Err if failed.
+    ///
+    pub fn try_insert(&mut self, val: T) -> Result<ArenaIdx<T>, T>{
+        let dense = self.values.len();
+        let index = match self.freed{
+            Some(i) => {
+                if let RawCell::Freed{next, generation} = self.slots[i]{
+                    self.freed = next;
+                    self.slots[i] = RawCell::Allocated{
+                        val: dense,
+                        generation,
+                    };
+                    ArenaIdx::from_parts(i, generation)
+                }
+                else{
+                    return Err(val);
+                }
+            }
+            None => {
+                self.slots.push(RawCell::Allocated{
+                    val: dense,
+                    generation: 0,
+                });
+                ArenaIdx::from_parts(self.slots.len() - 1, 0)
+            }
+        };
+        self.values.push(val);
+        self.keys.push(index);
+        Ok(index)
+    }
+
+    ///
+    /// Inserts a new element into the arena.
+    ///
+    #[must_use]
+    pub fn insert(&mut self, val: T) -> ArenaIdx<T>{
+        match self.try_insert(val){
+            Ok(index) => index,
+            Err(_val) => panic!("Insertion not successfull."),
+        }
+    }
+
+    #[inline]
+    fn dense(&self, index: ArenaIdx<T>) -> Option<usize>{
+        match self.slots.get(index.index())?{
+            RawCell::Allocated{val, generation} if *generation == index.gen() => Some(*val),
+            _ => None,
+        }
+    }
+
+    ///
+    /// Removes the value from the arena and increaces the generation of its slot.
+    /// The last value is moved into the position of the removed one.
+    ///
+    pub fn remove(&mut self, index: ArenaIdx<T>) -> Option<T>{
+        let dense = self.dense(index)?;
+
+        self.slots[index.index()] = RawCell::Freed{
+            next: self.freed,
+            generation: index.gen() + 1,
+        };
+        self.freed = Some(index.index());
+
+        let val = self.values.swap_remove(dense);
+        self.keys.swap_remove(dense);
+        if let Some(moved) = self.keys.get(dense){
+            self.slots[moved.index()] = RawCell::Allocated{
+                val: dense,
+                generation: moved.gen(),
+            };
+        }
+        Some(val)
+    }
+
+    ///
+    /// Gets the Generation for a given index.
+    ///
+    pub fn gen(&self, index: usize) -> usize{
+        match self.slots[index]{
+            RawCell::Freed{generation, ..} => generation,
+            RawCell::Allocated{generation, ..} => generation,
+        }
+    }
+
+    ///
+    /// Returns an optional reference to the value at the index.
+    ///
+    pub fn get(&self, index: ArenaIdx<T>) -> Option<&T>{
+        self.dense(index).map(|dense| &self.values[dense])
+    }
+
+    ///
+    /// Returns a mutable optional reference to the value at the index.
+    ///
+    pub fn get_mut(&mut self, index: ArenaIdx<T>) -> Option<&mut T>{
+        self.dense(index).map(|dense| &mut self.values[dense])
+    }
+
+    ///
+    /// Get N optional references to N indices in the arena.
+    ///
+    pub fn getn<const N: usize>(&self, indices: [ArenaIdx<T>; N]) -> [Option<&T>; N]{
+        let mut ret = [None; N];
+
+        for (i, index) in indices.iter().enumerate(){
+            ret[i] = self.get(*index);
+        }
+        ret
+    }
+
+    ///
+    /// Returns mutable optional references to two distinct values.
+    /// Panics if both indices refer to the same element, stale indices to the same slot yield None.
+    ///
+    ///```rust
+    /// use gen_arena::*;
+    ///
+    /// let mut arena = DenseArena::new();
+    ///
+    /// let i1 = arena.insert(1);
+    /// let i2 = arena.insert(2);
+    ///
+    /// let (c1, c2) = arena.get2_mut((i1, i2));
+    ///
+    /// *c1.unwrap() = 3;
+    /// *c2.unwrap() = 4;
+    ///
+    /// assert_eq!(*arena.get(i1).unwrap(), 3);
+    /// assert_eq!(*arena.get(i2).unwrap(), 4);
+    ///```
+    ///
+    pub fn get2_mut(&mut self, indices: (ArenaIdx<T>, ArenaIdx<T>)) -> (Option<&mut T>, Option<&mut T>){
+        if indices.0.index() == indices.1.index(){
+            // Only a generation matching the allocated one refers to a value,
+            // so the same slot is an error only if both indices are live.
+            if self.get(indices.0).is_none(){
+                return (None, self.get_mut(indices.1));
+            }
+            if indices.0.gen() == indices.1.gen(){
+                panic!("Cannot take 2 mutable references to a value at the same index.")
+            }
+            return (self.get_mut(indices.0), None);
+        }
+
+        match (self.dense(indices.0), self.dense(indices.1)){
+            (Some(d0), Some(d1)) => {
+                let split = self.values.split_at_mut(d0.max(d1));
+                if d0 < d1{
+                    (Some(&mut split.0[d0]), Some(&mut split.1[0]))
+                }
+                else{
+                    (Some(&mut split.1[0]), Some(&mut split.0[d1]))
+                }
+            }
+            (Some(d0), None) => (Some(&mut self.values[d0]), None),
+            (None, Some(d1)) => (None, Some(&mut self.values[d1])),
+            (None, None) => (None, None),
+        }
+    }
+
+    ///
+    /// Returns the values as a slice.
+    /// The order changes when values are removed.
+    ///
+    #[inline]
+    pub fn as_slice(&self) -> &[T]{
+        &self.values
+    }
+
+    ///
+    /// Returns the values as a mutable slice.
+    ///
+    #[inline]
+    pub fn as_mut_slice(&mut self) -> &mut [T]{
+        &mut self.values
+    }
+
+    ///
+    /// Returns iterator over all values.
+    ///
+    /// ```rust
+    /// use gen_arena::*;
+    /// let mut arena = DenseArena::new();
+    ///
+    /// let i1 = arena.insert(1);
+    /// let i2 = arena.insert(2);
+    ///
+    /// assert_eq!(arena.values().sum::<i32>(), 3);
+    /// ```
+    ///
+    #[inline]
+    pub fn values(&self) -> std::slice::Iter<'_, T>{
+        self.values.iter()
+    }
+
+    ///
+    /// Returns mutable iterator over all values.
+    ///
+    #[inline]
+    pub fn values_mut(&mut self) -> std::slice::IterMut<'_, T>{
+        self.values.iter_mut()
+    }
+
+    ///
+    /// Iterator over all keys in the arena, in the same order as values.
+    ///
+    #[inline]
+    pub fn keys(&self) -> std::iter::Copied<std::slice::Iter<'_, ArenaIdx<T>>>{
+        self.keys.iter().copied()
+    }
+
+    ///
+    /// Returns an iterator over the values with their keys.
+    ///
+    /// ```rust
+    /// use gen_arena::*;
+    /// let mut arena = DenseArena::new();
+    ///
+    /// let i1 = arena.insert(1);
+    /// let i2 = arena.insert(2);
+    ///
+    /// for (index, val) in arena.iter(){
+    ///     if index == i1{
+    ///         assert_eq!(*val, 1);
+    ///     }
+    ///     if index == i2{
+    ///         assert_eq!(*val, 2);
+    ///     }
+    /// }
+    /// ```
+    ///
+    #[inline]
+    pub fn iter(&self) -> DenseIter<'_, T>{
+        DenseIter{
+            iter: self.keys.iter().zip(self.values.iter()),
+        }
+    }
+
+    ///
+    /// Returns a mutable iterator over the values with their keys.
+    ///
+    #[inline]
+    pub fn iter_mut(&mut self) -> DenseIterMut<'_, T>{
+        DenseIterMut{
+            iter: self.keys.iter().zip(self.values.iter_mut()),
+        }
+    }
+
+    #[inline]
+    pub fn reserve(&mut self, additional: usize){
+        self.values.reserve(additional);
+        self.keys.reserve(additional);
+    }
+
+    #[inline]
+    pub fn capacity(&self) -> usize{
+        self.values.capacity()
+    }
+
+    #[inline]
+    pub fn num(&self) -> usize{
+        self.values.len()
+    }
+}
+
+impl<T> Default for DenseArena<T>{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Index<ArenaIdx<T>> for DenseArena<T>{
+    type Output = T;
+
+    fn index(&self, index: ArenaIdx<T>) -> &Self::Output {
+        self.get(index).expect("There is no element at this index with that generation.")
+    }
+}
+
+impl<T> IndexMut<ArenaIdx<T>> for DenseArena<T>{
+    fn index_mut(&mut self, index: ArenaIdx<T>) -> &mut Self::Output {
+        self.get_mut(index).expect("There is no element at this index with that generation.")
+    }
+}
+
+pub struct DenseIter<'i, T: 'i>{
+    iter: std::iter::Zip<std::slice::Iter<'i, ArenaIdx<T>>, std::slice::Iter<'i, T>>,
+}
+
+impl<'i, T> Iterator for DenseIter<'i, T>{
+    type Item = (ArenaIdx<T>, &'i T);
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        self.iter.next().map(|(i, val)| (*i, val))
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+pub struct DenseIterMut<'i, T: 'i>{
+    iter: std::iter::Zip<std::slice::Iter<'i, ArenaIdx<T>>, std::slice::IterMut<'i, T>>,
+}
+
+impl<'i, T> Iterator for DenseIterMut<'i, T>{
+    type Item = (ArenaIdx<T>, &'i mut T);
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        self.iter.next().map(|(i, val)| (*i, val))
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+#[cfg(test)]
+mod test{
+    use super::*;
+
+    #[test]
+    fn test_keys_survive_swaps(){
+        let mut arena = DenseArena::new();
+
+        let mut keys: Vec<_> = (0..100).map(|i| (arena.insert(i), i)).collect();
+
+        // Remove every third element, including the last one.
+        let mut i = 0;
+        keys.retain(|(key, val)|{
+            i += 1;
+            if i % 3 == 0 || *val == 99{
+                assert_eq!(arena.remove(*key), Some(*val));
+                assert_eq!(arena.remove(*key), None);
+                false
+            }
+            else{
+                true
+            }
+        });
+
+        assert_eq!(arena.num(), keys.len());
+        for (key, val) in keys.iter(){
+            assert_eq!(arena[*key], *val);
+        }
+        for (key, val) in arena.iter(){
+            assert_eq!(keys.iter().find(|(k, _)| *k == key).unwrap().1, *val);
+        }
+
+        let reused: Vec<_> = (0..5).map(|i| arena.insert(1000 + i)).collect();
+        for (i, key) in reused.iter().enumerate(){
+            assert_eq!(key.gen(), 1);
+            assert_eq!(arena[*key], 1000 + i);
+        }
+        for (key, val) in keys.iter(){
+            assert_eq!(arena[*key], *val);
+        }
+        assert_eq!(arena.keys().count(), arena.as_slice().len());
+    }
+
+    #[test]
+    fn test_get2_mut_and_clear(){
+        let mut arena = DenseArena::new();
+
+        let i0 = arena.insert(0);
+        let i1 = arena.insert(1);
+        let i2 = arena.insert(2);
+        arena.remove(i0);
+
+        let (a, b) = arena.get2_mut((i2, i1));
+        std::mem::swap(a.unwrap(), b.unwrap());
+        assert_eq!(arena[i1], 2);
+        assert_eq!(arena[i2], 1);
+        assert_eq!(arena.get2_mut((i0, i1)).0, None);
+
+        arena.clear();
+        assert_eq!(arena.num(), 0);
+        assert_eq!(arena.get(i1), None);
+
+        let i3 = arena.insert(3);
+        assert_eq!(i3, ArenaIdx::from_raw_parts(0, 1));
+        assert_eq!(arena.as_slice(), &[3]);
+    }
+
+    #[test]
+    fn test_get2_mut_same_slot(){
+        let mut arena = DenseArena::new();
+        let stale = arena.insert(0);
+        arena.remove(stale);
+        let live = arena.insert(1);
+        assert_eq!(stale.index(), live.index());
+
+        assert_eq!(arena.get2_mut((stale, stale)), (None, None));
+        assert_eq!(arena.get2_mut((live, stale)), (Some(&mut 1), None));
+        assert_eq!(arena.get2_mut((stale, live)), (None, Some(&mut 1)));
+
+        // A stale key of a later generation does not hide the live one.
+        let ahead = ArenaIdx::from_raw_parts(live.index(), live.gen() + 5);
+        assert_eq!(arena.get2_mut((live, ahead)), (Some(&mut 1), None));
+        assert_eq!(arena.get2_mut((ahead, live)), (None, Some(&mut 1)));
+
+        // Two stale copies of a freed slot.
+        arena.remove(live);
+        assert_eq!(arena.get2_mut((live, live)), (None, None));
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_get2_mut_same_live(){
+        let mut arena = DenseArena::new();
+        let i0 = arena.insert(0);
+        let _ = arena.get2_mut((i0, i0));
+    }
+}
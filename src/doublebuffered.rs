@@ -0,0 +1,204 @@
+use crate::*;
+
+///
+/// Two Arenas with the same layout, holding the current and the previous state of every element.
+///
+/// Insertion and removal are applied to both buffers, so the same ArenaIdx refers to the
+/// same element in either of them. This suits frame based simulations, which read the state of
+/// the last frame while writing the state of the current one and flip the buffers with swap
+/// at the end of every frame.
+///
+/// # Example
+///
+///```rust
+/// use gen_arena::*;
+///
+/// let mut positions = DoubleBuffered::new();
+/// let i0 = positions.insert(0.);
+/// let i1 = positions.insert(10.);
+///
+/// for _ in 0..3{
+///     // Move every element halfway towards the previous position of i1.
+///     let target = positions.previous()[i1];
+///     for (_, previous, current) in positions.iter_frames_mut(){
+///         *current = previous + (target - previous) / 2.;
+///     }
+///     positions.swap();
+/// }
+///
+/// assert_eq!(positions.previous()[i0], 8.75);
+/// assert_eq!(positions.previous()[i1], 10.);
+///```
+///
+#[derive(Debug, Clone)]
+pub struct DoubleBuffered<T>{
+    current: Arena<T>,
+    previous: Arena<T>,
+}
+
+impl<T> DoubleBuffered<T>{
+    ///
+    /// Creates two empty buffers.
+    ///
+    pub fn new() -> Self{
+        Self{
+            current: Arena::new(),
+            previous: Arena::new(),
+        }
+    }
+
+    ///
+    /// Inserts the values into the current and the previous buffer under the same key.
+    ///
+    #[must_use]
+    pub fn insert_pair(&mut self, current: T, previous: T) -> ArenaIdx<T>{
+        let index = self.current.insert(current);
+        let previous = self.previous.insert(previous);
+        debug_assert!(index == previous, "Both buffers always have the same layout.");
+        index
+    }
+
+    ///
+    /// Inserts the value into both buffers under the same key.
+    ///
+    #[must_use]
+    pub fn insert(&mut self, val: T) -> ArenaIdx<T>
+    where T: Clone
+    {
+        self.insert_pair(val.clone(), val)
+    }
+
+    ///
+    /// Removes the element from both buffers.
+    /// Returns the value of the current buffer if the index is still valid.
+    ///
+    pub fn remove(&mut self, index: ArenaIdx<T>) -> Option<T>{
+        self.previous.remove(index);
+        self.current.remove(index)
+    }
+
+    ///
+    /// Returns the buffer being written in this frame.
+    ///
+    #[inline]
+    pub fn current(&self) -> &Arena<T>{
+        &self.current
+    }
+
+    ///
+    /// Returns the buffer written in the last frame.
+    ///
+    #[inline]
+    pub fn previous(&self) -> &Arena<T>{
+        &self.previous
+    }
+
+    ///
+    /// Returns a mutable optional reference to the value in the current buffer.
+    ///
+    #[inline]
+    pub fn get_mut(&mut self, index: ArenaIdx<T>) -> Option<&mut T>{
+        self.current.get_mut(index)
+    }
+
+    ///
+    /// Returns the previous value together with a mutable reference to the current value.
+    ///
+    pub fn get_frames_mut(&mut self, index: ArenaIdx<T>) -> Option<(&T, &mut T)>{
+        Some((self.previous.get(index)?, self.current.get_mut(index)?))
+    }
+
+    ///
+    /// Returns an iterator over all elements with their previous values
+    /// and mutable references to their current values.
+    ///
+    pub fn iter_frames_mut(&mut self) -> impl Iterator<Item = (ArenaIdx<T>, &T, &mut T)>{
+        self.previous.values().zip(self.current.iter_mut()).map(|(previous, (index, current))|{
+            (index, previous, current)
+        })
+    }
+
+    ///
+    /// Flips the buffers at the end of a frame, so the current buffer becomes the previous one.
+    /// The new current buffer still holds the values of two frames ago.
+    ///
+    #[inline]
+    pub fn swap(&mut self){
+        std::mem::swap(&mut self.current, &mut self.previous);
+    }
+
+    ///
+    /// Overwrites every value of the current buffer with a clone of its previous value.
+    ///
+    pub fn copy_forward(&mut self)
+    where T: Clone
+    {
+        for (previous, current) in self.previous.values().zip(self.current.values_mut()){
+            current.clone_from(previous);
+        }
+    }
+
+    #[inline]
+    pub fn clear(&mut self){
+        self.current.clear();
+        self.previous.clear();
+    }
+
+    #[inline]
+    pub fn num(&self) -> usize{
+        self.current.num()
+    }
+}
+
+impl<T> Default for DoubleBuffered<T>{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod test{
+    use super::*;
+
+    #[test]
+    fn test_keys_valid_in_both_buffers(){
+        let mut buffers = DoubleBuffered::new();
+        let mut keys = Vec::new();
+
+        for frame in 0..50usize{
+            // Interleave insertions and removals, so freed slots are reused in both buffers.
+            keys.push(buffers.insert_pair(frame, frame + 1000));
+            if frame % 3 == 0{
+                let key = keys.remove(frame % keys.len());
+                assert!(buffers.remove(key).is_some());
+                assert_eq!(buffers.current().get(key), None);
+                assert_eq!(buffers.previous().get(key), None);
+            }
+
+            for key in keys.iter(){
+                let (previous, current) = buffers.get_frames_mut(*key).unwrap();
+                *current += *previous % 7;
+            }
+            if frame % 2 == 0{
+                buffers.swap();
+            }
+            else{
+                buffers.swap();
+                buffers.copy_forward();
+                for key in keys.iter(){
+                    assert_eq!(buffers.current()[*key], buffers.previous()[*key]);
+                }
+            }
+
+            assert_eq!(buffers.num(), keys.len());
+            assert_eq!(buffers.previous().num(), keys.len());
+            for key in keys.iter(){
+                assert!(buffers.current().get(*key).is_some());
+                assert!(buffers.previous().get(*key).is_some());
+            }
+            let current: Vec<_> = buffers.current().keys().collect();
+            let previous: Vec<_> = buffers.previous().keys().collect();
+            assert_eq!(current, previous);
+        }
+    }
+}
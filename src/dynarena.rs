@@ -0,0 +1,300 @@
+use std::{any::Any, ops::{Index, IndexMut}};
+
+use crate::*;
+
+///
+/// Access to a value as Any, for downcasting trait objects stored in a DynArena.
+///
+/// Implemented for every sized 'static type. Making it a supertrait of a trait
+/// allows downcasting trait objects of that trait.
+/// Note that calling as_any on a Box<dyn Trait> returns the Box itself as Any,
+/// so it has to be called on the dereferenced trait object.
+///
+///```rust
+/// use gen_arena::*;
+///
+/// trait Widget: AsAny{}
+///```
+///
+pub trait AsAny{
+    fn as_any(&self) -> &dyn Any;
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+}
+
+impl<T: Any> AsAny for T{
+    #[inline]
+    fn as_any(&self) -> &dyn Any{
+        self
+    }
+
+    #[inline]
+    fn as_any_mut(&mut self) -> &mut dyn Any{
+        self
+    }
+}
+
+///
+/// A Generational Arena for unsized values such as trait objects.
+///
+/// Values are stored as Box<T>, so an element can be of any type coercing to T.
+/// If T is a trait object of a trait with AsAny as supertrait, elements can be downcast to
+/// their concrete type with get_downcast.
+///
+/// # Example
+///
+///```rust
+/// use gen_arena::*;
+///
+/// trait Widget: AsAny{
+///     fn width(&self) -> u32;
+/// }
+///
+/// struct Button;
+/// struct Label(String);
+///
+/// impl Widget for Button{
+///     fn width(&self) -> u32{ 10 }
+/// }
+///
+/// impl Widget for Label{
+///     fn width(&self) -> u32{ self.0.len() as u32 }
+/// }
+///
+/// let mut widgets: DynArena<dyn Widget> = DynArena::new();
+///
+/// let button = widgets.insert_boxed(Box::new(Button));
+/// let label = widgets.insert_boxed(Box::new(Label("hello".into())));
+///
+/// assert_eq!(widgets.values().map(|w| w.width()).sum::<u32>(), 15);
+/// assert_eq!(widgets.get_downcast::<Label>(label).unwrap().0, "hello");
+/// assert!(widgets.get_downcast::<Label>(button).is_none());
+///```
+///
+pub struct DynArena<T: ?Sized>{
+    arena: Arena<Box<T>>,
+}
+
+impl<T: ?Sized> DynArena<T>{
+    ///
+    /// Creates an empty DynArena.
+    ///
+    pub fn new() -> Self{
+        Self{
+            arena: Arena::new(),
+        }
+    }
+
+    #[inline]
+    fn cast(index: ArenaIdx<T>) -> ArenaIdx<Box<T>>{
+        ArenaIdx::from_parts(index.index(), index.gen())
+    }
+
+    #[inline]
+    fn uncast(index: ArenaIdx<Box<T>>) -> ArenaIdx<T>{
+        ArenaIdx::from_parts(index.index(), index.gen())
+    }
+
+    ///
+    /// Tries to insert the boxed value.
+    /// Returns val as Err if failed.
+    ///
+    pub fn try_insert_boxed(&mut self, val: Box<T>) -> Result<ArenaIdx<T>, Box<T>>{
+        self.arena.try_insert(val).map(Self::uncast).map_err(InsertError::into_value)
+    }
+
+    ///
+    /// Inserts the boxed value.
+    ///
+    #[must_use]
+    pub fn insert_boxed(&mut self, val: Box<T>) -> ArenaIdx<T>{
+        Self::uncast(self.arena.insert(val))
+    }
+
+    ///
+    /// Removes the element and returns its box if the index is still valid.
+    ///
+    pub fn remove(&mut self, index: ArenaIdx<T>) -> Option<Box<T>>{
+        self.arena.remove(Self::cast(index))
+    }
+
+    ///
+    /// Returns an optional reference to the value at the index.
+    ///
+    #[inline]
+    pub fn get(&self, index: ArenaIdx<T>) -> Option<&T>{
+        self.arena.get(Self::cast(index)).map(|val| &**val)
+    }
+
+    ///
+    /// Returns a mutable optional reference to the value at the index.
+    ///
+    #[inline]
+    pub fn get_mut(&mut self, index: ArenaIdx<T>) -> Option<&mut T>{
+        self.arena.get_mut(Self::cast(index)).map(|val| &mut **val)
+    }
+
+    ///
+    /// Returns a reference to the value at the index if it is a U.
+    ///
+    pub fn get_downcast<U: Any>(&self, index: ArenaIdx<T>) -> Option<&U>
+    where T: AsAny
+    {
+        AsAny::as_any(self.get(index)?).downcast_ref()
+    }
+
+    ///
+    /// Returns a mutable reference to the value at the index if it is a U.
+    ///
+    pub fn get_downcast_mut<U: Any>(&mut self, index: ArenaIdx<T>) -> Option<&mut U>
+    where T: AsAny
+    {
+        AsAny::as_any_mut(self.get_mut(index)?).downcast_mut()
+    }
+
+    #[inline]
+    pub fn contains(&self, index: ArenaIdx<T>) -> bool{
+        self.get(index).is_some()
+    }
+
+    ///
+    /// Returns an iterator over the elements with their keys.
+    ///
+    pub fn iter(&self) -> impl Iterator<Item = (ArenaIdx<T>, &T)>{
+        self.arena.iter().map(|(index, val)| (Self::uncast(index), &**val))
+    }
+
+    ///
+    /// Returns a mutable iterator over the elements with their keys.
+    ///
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (ArenaIdx<T>, &mut T)>{
+        self.arena.iter_mut().map(|(index, val)| (Self::uncast(index), &mut **val))
+    }
+
+    ///
+    /// Returns an iterator over the elements.
+    ///
+    pub fn values(&self) -> impl Iterator<Item = &T>{
+        self.arena.values().map(|val| &**val)
+    }
+
+    ///
+    /// Returns a mutable iterator over the elements.
+    ///
+    pub fn values_mut(&mut self) -> impl Iterator<Item = &mut T>{
+        self.arena.values_mut().map(|val| &mut **val)
+    }
+
+    #[inline]
+    pub fn clear(&mut self){
+        self.arena.clear()
+    }
+
+    #[inline]
+    pub fn num(&self) -> usize{
+        self.arena.num()
+    }
+}
+
+impl<T: ?Sized> Default for DynArena<T>{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: ?Sized> std::fmt::Debug for DynArena<T>{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DynArena")
+            .field("num", &self.num())
+            .finish()
+    }
+}
+
+impl<T: ?Sized> Index<ArenaIdx<T>> for DynArena<T>{
+    type Output = T;
+
+    fn index(&self, index: ArenaIdx<T>) -> &Self::Output {
+        self.get(index).expect("There is no element at this index with that generation.")
+    }
+}
+
+impl<T: ?Sized> IndexMut<ArenaIdx<T>> for DynArena<T>{
+    fn index_mut(&mut self, index: ArenaIdx<T>) -> &mut Self::Output {
+        self.get_mut(index).expect("There is no element at this index with that generation.")
+    }
+}
+
+#[cfg(test)]
+mod test{
+    use super::*;
+    use std::fmt::Display;
+
+    trait Shape: AsAny{
+        fn area(&self) -> f32;
+        fn scale(&mut self, s: f32);
+    }
+
+    #[derive(Debug, PartialEq)]
+    struct Square(f32);
+    #[derive(Debug, PartialEq)]
+    struct Rect(f32, f32);
+
+    impl Shape for Square{
+        fn area(&self) -> f32{
+            self.0 * self.0
+        }
+        fn scale(&mut self, s: f32){
+            self.0 *= s;
+        }
+    }
+
+    impl Shape for Rect{
+        fn area(&self) -> f32{
+            self.0 * self.1
+        }
+        fn scale(&mut self, s: f32){
+            self.0 *= s;
+            self.1 *= s;
+        }
+    }
+
+    #[test]
+    fn test_downcast_and_remove(){
+        let mut shapes: DynArena<dyn Shape> = DynArena::new();
+        let s0 = shapes.insert_boxed(Box::new(Square(1.)));
+        let r0 = shapes.insert_boxed(Box::new(Rect(1., 2.)));
+        let s1 = shapes.insert_boxed(Box::new(Square(3.)));
+
+        for shape in shapes.values_mut(){
+            shape.scale(2.);
+        }
+        assert_eq!(shapes[s1].area(), 36.);
+
+        // Hits and misses.
+        assert_eq!(shapes.get_downcast::<Square>(s0), Some(&Square(2.)));
+        assert_eq!(shapes.get_downcast::<Rect>(r0), Some(&Rect(2., 4.)));
+        assert_eq!(shapes.get_downcast::<Rect>(s0), None);
+        assert_eq!(shapes.get_downcast::<Square>(r0), None);
+        // Downcasting to the box instead of the concrete type does not match.
+        assert!(shapes.get_downcast::<Box<dyn Shape>>(s0).is_none());
+        shapes.get_downcast_mut::<Rect>(r0).unwrap().1 = 0.;
+        assert_eq!(shapes[r0].area(), 0.);
+
+        // Removing frees the slot for a value of another concrete type.
+        let removed = shapes.remove(s0).unwrap();
+        assert_eq!((*removed).as_any().downcast_ref::<Square>(), Some(&Square(2.)));
+        assert!(shapes.get(s0).is_none());
+        let r1 = shapes.insert_boxed(Box::new(Rect(1., 1.)));
+        assert_eq!(r1.index(), s0.index());
+        assert_eq!(shapes.get_downcast::<Square>(s0), None);
+        assert_eq!(shapes.get_downcast::<Rect>(r1), Some(&Rect(1., 1.)));
+        assert_eq!(shapes.num(), 3);
+
+        // Trait objects without AsAny can still be stored.
+        let mut names: DynArena<dyn Display> = DynArena::new();
+        let n0 = names.insert_boxed(Box::new(1));
+        let n1 = names.insert_boxed(Box::new("two"));
+        assert_eq!(format!("{} {}", &names[n0], &names[n1]), "1 two");
+        let keys: Vec<_> = names.iter().map(|(key, _)| key).collect();
+        assert_eq!(keys, [n0, n1]);
+    }
+}
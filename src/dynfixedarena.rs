@@ -0,0 +1,461 @@
+use std::{cell::{Cell, UnsafeCell}, marker::PhantomData, ops::{Index, IndexMut}};
+
+use crate::*;
+
+///
+/// A Generational Arena with a fixed capacity that is chosen at runtime.
+///
+/// All cells are allocated in one boxed slice by with_capacity and the arena never reallocates,
+/// so values never move and insertion only needs a shared reference, like SArena.
+/// try_insert fails once the arena is full.
+///
+/// The interior mutability is single threaded, a DynFixedArena is Send but not Sync.
+///
+/// # Example
+///
+///```rust
+/// use gen_arena::*;
+///
+/// let arena = DynFixedArena::with_capacity(2);
+///
+/// let i0 = arena.insert(0);
+/// let v0 = arena.get(i0).unwrap();
+///
+/// let i1 = arena.insert(1);
+/// assert!(arena.is_full());
+/// assert_eq!(arena.try_insert(2), Err(2));
+///
+/// assert_eq!(*v0, 0);
+/// assert_eq!(*arena.get(i1).unwrap(), 1);
+///```
+///
+pub struct DynFixedArena<T>{
+    cells: Box<[UnsafeCell<RawCell<T>>]>,
+    freed: Cell<Option<usize>>,
+    num: Cell<usize>,
+}
+
+impl<T> DynFixedArena<T>{
+    ///
+    /// Creates an empty DynFixedArena with room for exactly cap elements.
+    /// This is the only allocation the arena ever makes.
+    ///
+    pub fn with_capacity(cap: usize) -> Self{
+        Self{
+            cells: (0..cap).map(|i| UnsafeCell::new(RawCell::Freed{
+                next: if i + 1 < cap {Some(i + 1)} else{None},
+                generation: 0,
+            })).collect(),
+            freed: Cell::new(if cap > 0 {Some(0)} else{None}),
+            num: Cell::new(0),
+        }
+    }
+
+    #[inline]
+    fn cell_mut(&mut self, index: usize) -> Option<&mut RawCell<T>>{
+        self.cells.get_mut(index).map(|cell| cell.get_mut())
+    }
+
+    ///
+    /// Clears the arena and resets the list of Freed cells.
+    ///
+    pub fn clear(&mut self){
+        let len = self.cells.len();
+        for (i, cell) in self.cells.iter_mut().enumerate(){
+            let cell = cell.get_mut();
+            let generation = match cell{
+                RawCell::Allocated{generation, ..} => *generation + 1,
+                RawCell::Freed{generation, ..} => *generation,
+            };
+            *cell = RawCell::Freed{
+                generation,
+                next: if i < len-1 {Some(i+1)} else{None},
+            };
+        }
+        self.freed.set(if len > 0 {Some(0)} else{None});
+        self.num.set(0);
+    }
+
+    ///
+    /// Tries to insert into the arena.
+    /// Returns val as Err if the arena is full.
+    ///
+    /// Unlike Arena::try_insert this only needs a shared reference,
+    /// since the cells are never moved.
+    ///
+    pub fn try_insert(&self, val: T) -> Result<ArenaIdx<T>, T>{
+        let i = match self.freed.get(){
+            Some(i) => i,
+            None => return Err(val),
+        };
+        let cell = &self.cells[i];
+
+        // SAFETY: References are only handed out to Allocated cells,
+        // so nothing can observe the Freed cell while it is overwritten.
+        let (next, generation) = match unsafe{&*cell.get()}{
+            RawCell::Freed{next, generation} => (*next, *generation),
+            RawCell::Allocated{..} => return Err(val),
+        };
+        unsafe{cell.get().write(RawCell::Allocated{val, generation})};
+
+        self.freed.set(next);
+        self.num.set(self.num.get() + 1);
+        Ok(ArenaIdx::from_parts(i, generation))
+    }
+
+    ///
+    /// Inserts a new element into the arena.
+    /// Panics if it is full.
+    ///
+    #[must_use]
+    pub fn insert(&self, val: T) -> ArenaIdx<T>{
+        match self.try_insert(val){
+            Ok(index) => index,
+            Err(_val) => panic!("Insertion not successfull."),
+        }
+    }
+
+    ///
+    /// Removes the cell from the arena and increaces its generation.
+    /// Returns the value if the index is still valid.
+    ///
+    pub fn remove(&mut self, index: ArenaIdx<T>) -> Option<T>{
+        let freed = self.freed.get();
+        let cell = self.cell_mut(index.index())?;
+        match cell{
+            RawCell::Allocated{generation, ..} if *generation == index.gen() => {
+                let cell = std::mem::replace(cell, RawCell::Freed{
+                    next: freed,
+                    generation: index.gen() + 1,
+                });
+                self.num.set(self.num.get() - 1);
+                self.freed.set(Some(index.index()));
+                match cell{
+                    RawCell::Allocated{val, ..} => Some(val),
+                    RawCell::Freed{..} => unreachable!(),
+                }
+            }
+            _ => None,
+        }
+    }
+
+    ///
+    /// Gets the Generation for a given index.
+    ///
+    pub fn gen(&self, index: usize) -> usize{
+        // SAFETY: The reference does not outlive this call and no insertion can happen during it.
+        match unsafe{&*self.cells[index].get()}{
+            RawCell::Freed{generation, ..} => *generation,
+            RawCell::Allocated{generation, ..} => *generation,
+        }
+    }
+
+    ///
+    /// Returns an optional reference to the value at the index.
+    /// The reference stays valid across insertions.
+    ///
+    pub fn get(&self, index: ArenaIdx<T>) -> Option<&T>{
+        let cell = self.cells.get(index.index())?;
+        // SAFETY: Allocated cells are only written through a mutable reference to self.
+        match unsafe{&*cell.get()}{
+            RawCell::Allocated{val, generation} if *generation == index.gen() => Some(val),
+            _ => None,
+        }
+    }
+
+    ///
+    /// Returns an optional reference to a cell with any generation.
+    ///
+    pub fn get_any(&self, index: usize) -> Option<&T>{
+        let cell = self.cells.get(index)?;
+        // SAFETY: Allocated cells are only written through a mutable reference to self.
+        match unsafe{&*cell.get()}{
+            RawCell::Allocated{val, ..} => Some(val),
+            _ => None,
+        }
+    }
+
+    ///
+    /// Get N optional references to N indices in the arena.
+    ///
+    pub fn getn<const N: usize>(&self, indices: [ArenaIdx<T>; N]) -> [Option<&T>; N]{
+        indices.map(|index| self.get(index))
+    }
+
+    ///
+    /// Returns a mutable optional reference to the value at the index.
+    ///
+    pub fn get_mut(&mut self, index: ArenaIdx<T>) -> Option<&mut T>{
+        match self.cell_mut(index.index())?{
+            RawCell::Allocated{val, generation} if *generation == index.gen() => Some(val),
+            _ => None,
+        }
+    }
+
+    ///
+    /// Returns an optional mutable reference to the value of a cell at a index with any generation.
+    ///
+    pub fn get_any_mut(&mut self, index: usize) -> Option<&mut T>{
+        match self.cell_mut(index)?{
+            RawCell::Allocated{val, ..} => Some(val),
+            _ => None,
+        }
+    }
+
+    ///
+    /// Returns mutable optional references to two distinct values.
+    /// Panics if both indices refer to the same element, stale indices to the same slot yield None.
+    ///
+    ///```rust
+    /// use gen_arena::*;
+    ///
+    /// let mut arena = DynFixedArena::with_capacity(4);
+    ///
+    /// let i1 = arena.insert(1);
+    /// let i2 = arena.insert(2);
+    ///
+    /// let (c1, c2) = arena.get2_mut((i1, i2));
+    ///
+    /// *c1.unwrap() = 3;
+    /// *c2.unwrap() = 4;
+    ///
+    /// assert_eq!(*arena.get(i1).unwrap(), 3);
+    /// assert_eq!(*arena.get(i2).unwrap(), 4);
+    ///```
+    ///
+    pub fn get2_mut(&mut self, indices: (ArenaIdx<T>, ArenaIdx<T>)) -> (Option<&mut T>, Option<&mut T>){
+        if indices.0.index() == indices.1.index(){
+            // Only a generation matching the allocated one refers to a value,
+            // so the same slot is an error only if both indices are live.
+            if self.get(indices.0).is_none(){
+                return (None, self.get_mut(indices.1));
+            }
+            if indices.0.gen() == indices.1.gen(){
+                panic!("Cannot take 2 mutable references to a value at the same index.")
+            }
+            return (self.get_mut(indices.0), None);
+        }
+
+        let cell = |arena: &Self, index: ArenaIdx<T>|{
+            // SAFETY: The indices are distinct so the cells are too,
+            // and the mutable reference to self guarantees exclusive access.
+            match unsafe{&mut *arena.cells.get(index.index())?.get()}{
+                RawCell::Allocated{val, generation} if *generation == index.gen() => Some(val),
+                _ => None,
+            }
+        };
+
+        (cell(self, indices.0), cell(self, indices.1))
+    }
+
+    ///
+    /// Returns iterator over all Allocated cells.
+    ///
+    #[inline]
+    pub fn values(&self) -> impl Iterator<Item = &T> + '_{
+        self.iter().map(|(_, val)| val)
+    }
+
+    ///
+    /// Returns mutable iterator over all Allocated cells.
+    ///
+    #[inline]
+    pub fn values_mut(&mut self) -> ValuesMut<'_, T>{
+        ValuesMut{
+            iter: self.iter_mut(),
+        }
+    }
+
+    ///
+    /// Iterator over all keys in the arena.
+    ///
+    #[inline]
+    pub fn keys(&self) -> impl Iterator<Item = ArenaIdx<T>> + '_{
+        self.iter().map(|(i, _)| i)
+    }
+
+    ///
+    /// Returns an iterator over the Allocated cells with index.
+    /// Values inserted while iterating may or may not be yielded.
+    ///
+    #[inline]
+    pub fn iter(&self) -> DynFixedIter<'_, T>{
+        DynFixedIter{
+            arena: self,
+            index: 0,
+        }
+    }
+
+    ///
+    /// Returns an mutable iterator over the Allocated cells with indices.
+    ///
+    #[inline]
+    pub fn iter_mut(&mut self) -> IterMut<'_, T>{
+        let cells: &mut [UnsafeCell<RawCell<T>>] = &mut self.cells;
+        // SAFETY: UnsafeCell<RawCell<T>> has the same layout as RawCell<T>
+        // and the mutable reference to self guarantees exclusive access.
+        let cells = unsafe{&mut *(cells as *mut [UnsafeCell<RawCell<T>>] as *mut [RawCell<T>])};
+        IterMut{
+            iter: cells.iter_mut().enumerate(),
+            _tag: PhantomData,
+        }
+    }
+
+    #[inline]
+    pub fn capacity(&self) -> usize{
+        self.cells.len()
+    }
+
+    ///
+    /// Returns true if no more elements can be inserted.
+    ///
+    #[inline]
+    pub fn is_full(&self) -> bool{
+        self.freed.get().is_none()
+    }
+
+    #[inline]
+    pub fn num(&self) -> usize{
+        self.num.get()
+    }
+}
+
+impl<T: std::fmt::Debug> std::fmt::Debug for DynFixedArena<T>{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_map().entries(self.iter().map(|(i, val)| (i.index(), val))).finish()
+    }
+}
+
+impl<T> Index<ArenaIdx<T>> for DynFixedArena<T>{
+    type Output = T;
+
+    fn index(&self, index: ArenaIdx<T>) -> &Self::Output {
+        self.get(index).expect("There is no element at this index with that generation.")
+    }
+}
+
+impl<T> IndexMut<ArenaIdx<T>> for DynFixedArena<T>{
+    fn index_mut(&mut self, index: ArenaIdx<T>) -> &mut Self::Output {
+        self.get_mut(index).expect("There is no element at this index with that generation.")
+    }
+}
+
+pub struct DynFixedIter<'i, T: 'i>{
+    arena: &'i DynFixedArena<T>,
+    index: usize,
+}
+
+impl<'i, T> Iterator for DynFixedIter<'i, T>{
+    type Item = (ArenaIdx<T>, &'i T);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some(cell) = self.arena.cells.get(self.index){
+            let i = self.index;
+            self.index += 1;
+            // SAFETY: Allocated cells are only written through a mutable reference to the arena.
+            if let RawCell::Allocated{val, generation} = unsafe{&*cell.get()}{
+                return Some((ArenaIdx::from_parts(i, *generation), val));
+            }
+        }
+        None
+    }
+}
+
+// These tests exercise the interior mutability and are meant to be run under Miri as well:
+// cargo +nightly miri test dynfixedarena
+#[cfg(test)]
+mod test{
+    use super::*;
+
+    #[test]
+    fn test_values_do_not_move(){
+        let arena = DynFixedArena::with_capacity(16);
+
+        let i0 = arena.insert(String::from("a"));
+        let a = arena.get(i0).unwrap();
+        let ptr = a.as_ptr();
+
+        let keys: Vec<_> = (1..16).map(|i| arena.insert(i.to_string())).collect();
+        assert!(arena.is_full());
+        assert_eq!(arena.try_insert(String::from("full")), Err(String::from("full")));
+
+        // The reference taken before the insertions is still valid and points to the same value.
+        assert_eq!(a, "a");
+        assert!(std::ptr::eq(a, arena.get(i0).unwrap()));
+        assert_eq!(arena.get(i0).unwrap().as_ptr(), ptr);
+
+        // Iterating while inserting through a shared reference.
+        let mut arena = arena;
+        arena.remove(keys[3]);
+        arena.remove(keys[7]);
+        let mut seen = 0;
+        for (_, val) in arena.iter(){
+            if !arena.is_full(){
+                let _ = arena.insert(val.clone());
+            }
+            seen += 1;
+        }
+        assert!(seen >= 14);
+        assert!(arena.is_full());
+        assert_eq!(arena.num(), 16);
+    }
+
+    #[test]
+    fn test_reuse_and_clear(){
+        let mut arena = DynFixedArena::with_capacity(2);
+
+        let i0 = arena.insert(0);
+        let i1 = arena.insert(1);
+        assert_eq!(arena.remove(i0), Some(0));
+        assert_eq!(arena.remove(i0), None);
+
+        let i2 = arena.insert(2);
+        assert_eq!(i2, ArenaIdx::from_raw_parts(0, 1));
+        assert_eq!(arena.get(i0), None);
+
+        for val in arena.values_mut(){
+            *val *= 10;
+        }
+        assert_eq!(arena.values().copied().collect::<Vec<_>>(), vec![20, 10]);
+
+        arena.clear();
+        assert_eq!(arena.num(), 0);
+        assert_eq!(arena.get(i1), None);
+        assert_eq!(arena.insert(3), ArenaIdx::from_raw_parts(0, 2));
+        assert_eq!(arena.insert(4), ArenaIdx::from_raw_parts(1, 1));
+
+        let empty = DynFixedArena::<i32>::with_capacity(0);
+        assert!(empty.is_full());
+        assert_eq!(empty.try_insert(0), Err(0));
+    }
+
+    #[test]
+    fn test_get2_mut_same_slot(){
+        let mut arena = DynFixedArena::with_capacity(4);
+        let stale = arena.insert(0);
+        arena.remove(stale);
+        let live = arena.insert(1);
+        assert_eq!(stale.index(), live.index());
+
+        assert_eq!(arena.get2_mut((stale, stale)), (None, None));
+        assert_eq!(arena.get2_mut((live, stale)), (Some(&mut 1), None));
+        assert_eq!(arena.get2_mut((stale, live)), (None, Some(&mut 1)));
+
+        // A stale key of a later generation does not hide the live one.
+        let ahead = ArenaIdx::from_raw_parts(live.index(), live.gen() + 5);
+        assert_eq!(arena.get2_mut((live, ahead)), (Some(&mut 1), None));
+        assert_eq!(arena.get2_mut((ahead, live)), (None, Some(&mut 1)));
+
+        // Two stale copies of a freed slot.
+        arena.remove(live);
+        assert_eq!(arena.get2_mut((live, live)), (None, None));
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_get2_mut_same_live(){
+        let mut arena = DynFixedArena::with_capacity(4);
+        let i0 = arena.insert(0);
+        let _ = arena.get2_mut((i0, i0));
+    }
+}
@@ -0,0 +1,318 @@
+//!
+//! A C interface to an Arena of fixed size byte blobs.
+//!
+//! Build the crate as a cdylib or staticlib with the ffi feature enabled, for example with
+//! `cargo rustc --release --features ffi --crate-type cdylib`, and include include/gen_arena.h,
+//! which is generated with `cbindgen --config cbindgen.toml --output include/gen_arena.h`.
+//! The manifest keeps the default crate type so Rust users do not build the C libraries.
+//!
+//! Generations are u32 like in the handles. A slot whose generation is exhausted is retired,
+//! see GenCounter, so a handle is never issued twice.
+//!
+//! Every function checks its pointers for null and its handles for validity and reports failures
+//! with one of the GEN_ARENA_* return codes. Non null pointers have to be valid though, and an arena
+//! must not be used from several threads at once.
+//!
+//! # Example
+//!
+//!```rust
+//! use gen_arena::ffi::*;
+//!
+//! unsafe{
+//!     let arena = gen_arena_new(4);
+//!
+//!     let mut handle = GenArenaHandle{ index: 0, generation: 0 };
+//!     assert_eq!(gen_arena_insert(arena, 42u32.to_ne_bytes().as_ptr(), &mut handle), GEN_ARENA_OK);
+//!
+//!     let mut out = [0u8; 4];
+//!     assert_eq!(gen_arena_get(arena, handle, out.as_mut_ptr()), GEN_ARENA_OK);
+//!     assert_eq!(u32::from_ne_bytes(out), 42);
+//!
+//!     assert_eq!(gen_arena_remove(arena, handle, std::ptr::null_mut()), GEN_ARENA_OK);
+//!     assert_eq!(gen_arena_get(arena, handle, out.as_mut_ptr()), GEN_ARENA_ERR_INVALID_HANDLE);
+//!
+//!     gen_arena_free(arena);
+//! }
+//!```
+//!
+
+use std::ptr;
+
+use crate::*;
+
+/// The operation succeeded.
+pub const GEN_ARENA_OK: i32 = 0;
+/// A required pointer argument was null.
+pub const GEN_ARENA_ERR_NULL: i32 = -1;
+/// The handle does not refer to an element of the arena.
+pub const GEN_ARENA_ERR_INVALID_HANDLE: i32 = -2;
+/// The slot index of a new element does not fit into a handle.
+pub const GEN_ARENA_ERR_OVERFLOW: i32 = -3;
+
+///
+/// A handle to an element of a GenArenaOpaque.
+///
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GenArenaHandle{
+    pub index: u32,
+    pub generation: u32,
+}
+
+impl GenArenaHandle{
+    #[inline]
+    fn idx(self) -> ArenaIdx<Box<[u8]>, u32>{
+        ArenaIdx::from_raw_parts(self.index as usize, self.generation)
+    }
+}
+
+///
+/// An Arena of byte blobs of the size given to gen_arena_new.
+/// Only ever handled through a pointer from C.
+///
+pub struct GenArenaOpaque{
+    arena: Arena<Box<[u8]>, u32>,
+    elem_size: usize,
+}
+
+///
+/// Creates an arena for elements of elem_size bytes.
+/// The arena has to be freed with gen_arena_free.
+///
+#[no_mangle]
+pub extern "C" fn gen_arena_new(elem_size: usize) -> *mut GenArenaOpaque{
+    Box::into_raw(Box::new(GenArenaOpaque{
+        arena: Arena::default(),
+        elem_size,
+    }))
+}
+
+///
+/// Frees the arena and all its elements. Does nothing if arena is null.
+///
+/// # Safety
+///
+/// arena has to be null or a pointer returned by gen_arena_new that has not been freed yet.
+///
+#[no_mangle]
+pub unsafe extern "C" fn gen_arena_free(arena: *mut GenArenaOpaque){
+    if !arena.is_null(){
+        // SAFETY: The pointer was created by Box::into_raw in gen_arena_new and is freed only once.
+        drop(unsafe{Box::from_raw(arena)});
+    }
+}
+
+///
+/// Copies elem_size bytes from value into a new element and writes its handle to out.
+///
+/// # Safety
+///
+/// arena has to be null or a live arena, value null or readable for elem_size bytes
+/// and out null or writable.
+///
+#[no_mangle]
+pub unsafe extern "C" fn gen_arena_insert(arena: *mut GenArenaOpaque, value: *const u8, out: *mut GenArenaHandle) -> i32{
+    if arena.is_null() || value.is_null() || out.is_null(){
+        return GEN_ARENA_ERR_NULL;
+    }
+    // SAFETY: Checked for null, validity is up to the caller.
+    let arena = unsafe{&mut *arena};
+    // SAFETY: The caller guarantees value to be readable for elem_size bytes.
+    let val = unsafe{std::slice::from_raw_parts(value, arena.elem_size)};
+    let idx = arena.arena.insert(val.into());
+    match u32::try_from(idx.index()){
+        Ok(index) => {
+            // SAFETY: Checked for null, validity is up to the caller.
+            unsafe{out.write(GenArenaHandle{index, generation: idx.gen()})};
+            GEN_ARENA_OK
+        }
+        Err(_) => {
+            arena.arena.remove(idx);
+            GEN_ARENA_ERR_OVERFLOW
+        }
+    }
+}
+
+///
+/// Copies the element of the handle to out.
+///
+/// # Safety
+///
+/// arena has to be null or a live arena and out null or writable for elem_size bytes.
+///
+#[no_mangle]
+pub unsafe extern "C" fn gen_arena_get(arena: *const GenArenaOpaque, handle: GenArenaHandle, out: *mut u8) -> i32{
+    if arena.is_null() || out.is_null(){
+        return GEN_ARENA_ERR_NULL;
+    }
+    // SAFETY: Checked for null, validity is up to the caller.
+    let arena = unsafe{&*arena};
+    match arena.arena.get(handle.idx()){
+        Some(val) => {
+            // SAFETY: Every element has elem_size bytes and out is writable for as many.
+            unsafe{ptr::copy_nonoverlapping(val.as_ptr(), out, val.len())};
+            GEN_ARENA_OK
+        }
+        None => GEN_ARENA_ERR_INVALID_HANDLE,
+    }
+}
+
+///
+/// Removes the element of the handle and copies it to out, unless out is null.
+///
+/// # Safety
+///
+/// arena has to be null or a live arena and out null or writable for elem_size bytes.
+///
+#[no_mangle]
+pub unsafe extern "C" fn gen_arena_remove(arena: *mut GenArenaOpaque, handle: GenArenaHandle, out: *mut u8) -> i32{
+    if arena.is_null(){
+        return GEN_ARENA_ERR_NULL;
+    }
+    // SAFETY: Checked for null, validity is up to the caller.
+    let arena = unsafe{&mut *arena};
+    match arena.arena.remove(handle.idx()){
+        Some(val) => {
+            if !out.is_null(){
+                // SAFETY: Every element has elem_size bytes and out is writable for as many.
+                unsafe{ptr::copy_nonoverlapping(val.as_ptr(), out, val.len())};
+            }
+            GEN_ARENA_OK
+        }
+        None => GEN_ARENA_ERR_INVALID_HANDLE,
+    }
+}
+
+///
+/// Returns the number of elements in the arena, or 0 if arena is null.
+///
+/// # Safety
+///
+/// arena has to be null or a live arena.
+///
+#[no_mangle]
+pub unsafe extern "C" fn gen_arena_len(arena: *const GenArenaOpaque) -> usize{
+    // SAFETY: Validity is up to the caller.
+    unsafe{arena.as_ref()}.map_or(0, |arena| arena.arena.num())
+}
+
+#[cfg(test)]
+mod test{
+    use super::*;
+    use std::ptr::{null, null_mut};
+
+    // Mirrors a C struct stored in the arena.
+    #[repr(C)]
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    struct Entity{
+        id: u64,
+        pos: [f32; 3],
+    }
+
+    const ENTITY_SIZE: usize = std::mem::size_of::<Entity>();
+
+    unsafe fn insert(arena: *mut GenArenaOpaque, e: Entity) -> GenArenaHandle{
+        let mut handle = GenArenaHandle{ index: u32::MAX, generation: u32::MAX };
+        let code = unsafe{gen_arena_insert(arena, &e as *const Entity as *const u8, &mut handle)};
+        assert_eq!(code, GEN_ARENA_OK);
+        handle
+    }
+
+    unsafe fn get(arena: *const GenArenaOpaque, handle: GenArenaHandle) -> Result<Entity, i32>{
+        let mut out = std::mem::MaybeUninit::<Entity>::uninit();
+        match unsafe{gen_arena_get(arena, handle, out.as_mut_ptr() as *mut u8)}{
+            GEN_ARENA_OK => Ok(unsafe{out.assume_init()}),
+            code => Err(code),
+        }
+    }
+
+    #[test]
+    fn test_c_usage(){
+        unsafe{
+            let arena = gen_arena_new(ENTITY_SIZE);
+            let handles: Vec<_> = (0..8).map(|i| insert(arena, Entity{ id: i, pos: [i as f32; 3] })).collect();
+            assert_eq!(gen_arena_len(arena), 8);
+            assert_eq!(handles[3], GenArenaHandle{ index: 3, generation: 0 });
+            assert_eq!(get(arena, handles[5]), Ok(Entity{ id: 5, pos: [5.; 3] }));
+
+            let mut removed = std::mem::MaybeUninit::<Entity>::uninit();
+            assert_eq!(gen_arena_remove(arena, handles[5], removed.as_mut_ptr() as *mut u8), GEN_ARENA_OK);
+            assert_eq!(removed.assume_init().id, 5);
+            assert_eq!(gen_arena_remove(arena, handles[5], null_mut()), GEN_ARENA_ERR_INVALID_HANDLE);
+            assert_eq!(get(arena, handles[5]), Err(GEN_ARENA_ERR_INVALID_HANDLE));
+
+            // The slot is reused with a new generation and the old handle stays invalid.
+            let reused = insert(arena, Entity{ id: 50, pos: [0.; 3] });
+            assert_eq!(reused.index, handles[5].index);
+            assert_ne!(reused.generation, handles[5].generation);
+            assert_eq!(get(arena, handles[5]), Err(GEN_ARENA_ERR_INVALID_HANDLE));
+            assert_eq!(get(arena, reused).map(|e| e.id), Ok(50));
+
+            // Handles made up by the caller are rejected.
+            let bogus = GenArenaHandle{ index: 1000, generation: 0 };
+            assert_eq!(get(arena, bogus), Err(GEN_ARENA_ERR_INVALID_HANDLE));
+            assert_eq!(gen_arena_remove(arena, bogus, null_mut()), GEN_ARENA_ERR_INVALID_HANDLE);
+            assert_eq!(gen_arena_len(arena), 8);
+
+            gen_arena_free(arena);
+        }
+    }
+
+    #[test]
+    fn test_exhausted_generation(){
+        unsafe{
+            let arena = gen_arena_new(4);
+            let handle = insert(arena, Entity{ id: 0, pos: [0.; 3] });
+            assert_eq!(gen_arena_remove(arena, handle, null_mut()), GEN_ARENA_OK);
+            // Skip to the last generation a handle can hold.
+            (*arena).arena.raw_mut().cells[0] = RawCell::Freed{next: None, generation: u32::MAX - 1};
+
+            let last = insert(arena, Entity{ id: 1, pos: [0.; 3] });
+            assert_eq!(last, GenArenaHandle{ index: 0, generation: u32::MAX - 1 });
+            assert_eq!(gen_arena_remove(arena, last, null_mut()), GEN_ARENA_OK);
+
+            // The slot is retired and inserting keeps working with a new one.
+            for i in 1..4{
+                let handle = insert(arena, Entity{ id: i, pos: [0.; 3] });
+                assert_eq!(handle, GenArenaHandle{ index: i as u32, generation: 0 });
+            }
+            assert_eq!(get(arena, last), Err(GEN_ARENA_ERR_INVALID_HANDLE));
+
+            gen_arena_free(arena);
+        }
+    }
+
+    #[test]
+    fn test_null_arguments(){
+        unsafe{
+            let mut handle = GenArenaHandle{ index: 0, generation: 0 };
+            let mut out = [0u8; 4];
+            let val = [1u8; 4];
+
+            assert_eq!(gen_arena_insert(null_mut(), val.as_ptr(), &mut handle), GEN_ARENA_ERR_NULL);
+            assert_eq!(gen_arena_get(null(), handle, out.as_mut_ptr()), GEN_ARENA_ERR_NULL);
+            assert_eq!(gen_arena_remove(null_mut(), handle, out.as_mut_ptr()), GEN_ARENA_ERR_NULL);
+            assert_eq!(gen_arena_len(null()), 0);
+            gen_arena_free(null_mut());
+
+            let arena = gen_arena_new(4);
+            assert_eq!(gen_arena_insert(arena, null(), &mut handle), GEN_ARENA_ERR_NULL);
+            assert_eq!(gen_arena_insert(arena, val.as_ptr(), null_mut()), GEN_ARENA_ERR_NULL);
+            assert_eq!(gen_arena_len(arena), 0);
+            assert_eq!(gen_arena_insert(arena, val.as_ptr(), &mut handle), GEN_ARENA_OK);
+            assert_eq!(gen_arena_get(arena, handle, null_mut()), GEN_ARENA_ERR_NULL);
+            assert_eq!(gen_arena_get(arena, handle, out.as_mut_ptr()), GEN_ARENA_OK);
+            assert_eq!(out, val);
+
+            // Zero sized elements never touch the pointers' memory.
+            let empty = gen_arena_new(0);
+            let dangling = std::ptr::NonNull::<u8>::dangling().as_ptr();
+            assert_eq!(gen_arena_insert(empty, dangling, &mut handle), GEN_ARENA_OK);
+            assert_eq!(gen_arena_get(empty, handle, dangling), GEN_ARENA_OK);
+            assert_eq!(gen_arena_len(empty), 1);
+
+            gen_arena_free(empty);
+            gen_arena_free(arena);
+        }
+    }
+}
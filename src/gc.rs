@@ -0,0 +1,259 @@
+use crate::*;
+
+///
+/// An untyped key, as reported by Trace.
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct RawIdx{
+    pub index: usize,
+    pub generation: usize,
+}
+
+impl<T: ?Sized> From<ArenaIdx<T>> for RawIdx{
+    #[inline]
+    fn from(index: ArenaIdx<T>) -> Self {
+        Self{
+            index: index.index(),
+            generation: index.gen(),
+        }
+    }
+}
+
+///
+/// Reports the keys a value holds, for Arena::collect_garbage.
+///
+/// Every reported key is treated as a key into the arena being collected,
+/// so keys into other arenas should not be reported.
+///
+/// # Example
+///
+///```rust
+/// use gen_arena::*;
+///
+/// struct Node{
+///     name: String,
+///     parent: Option<ArenaIdx<Node>>,
+///     children: Vec<ArenaIdx<Node>>,
+/// }
+///
+/// impl Trace for Node{
+///     fn trace(&self, visitor: &mut dyn FnMut(RawIdx)){
+///         self.parent.trace(visitor);
+///         self.children.trace(visitor);
+///     }
+/// }
+///```
+///
+pub trait Trace{
+    fn trace(&self, visitor: &mut dyn FnMut(RawIdx));
+}
+
+impl<T: ?Sized> Trace for ArenaIdx<T>{
+    #[inline]
+    fn trace(&self, visitor: &mut dyn FnMut(RawIdx)){
+        visitor((*self).into())
+    }
+}
+
+impl Trace for RawIdx{
+    #[inline]
+    fn trace(&self, visitor: &mut dyn FnMut(RawIdx)){
+        visitor(*self)
+    }
+}
+
+impl<T: Trace> Trace for Option<T>{
+    #[inline]
+    fn trace(&self, visitor: &mut dyn FnMut(RawIdx)){
+        if let Some(val) = self{
+            val.trace(visitor);
+        }
+    }
+}
+
+impl<T: Trace> Trace for [T]{
+    fn trace(&self, visitor: &mut dyn FnMut(RawIdx)){
+        for val in self{
+            val.trace(visitor);
+        }
+    }
+}
+
+impl<T: Trace, const N: usize> Trace for [T; N]{
+    #[inline]
+    fn trace(&self, visitor: &mut dyn FnMut(RawIdx)){
+        self.as_slice().trace(visitor)
+    }
+}
+
+impl<T: Trace> Trace for Vec<T>{
+    #[inline]
+    fn trace(&self, visitor: &mut dyn FnMut(RawIdx)){
+        self.as_slice().trace(visitor)
+    }
+}
+
+impl<T: Trace + ?Sized> Trace for Box<T>{
+    #[inline]
+    fn trace(&self, visitor: &mut dyn FnMut(RawIdx)){
+        (**self).trace(visitor)
+    }
+}
+
+macro_rules! impl_trace_leaf{
+    ($($ty:ty),*) => {
+        $(
+            impl Trace for $ty{
+                #[inline]
+                fn trace(&self, _visitor: &mut dyn FnMut(RawIdx)){}
+            }
+        )*
+    };
+}
+
+impl_trace_leaf!((), bool, char, u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize, f32, f64, str, String);
+
+macro_rules! impl_trace_tuple{
+    ($($name:ident),*) => {
+        impl<$($name: Trace),*> Trace for ($($name,)*){
+            #[allow(non_snake_case)]
+            fn trace(&self, visitor: &mut dyn FnMut(RawIdx)){
+                let ($($name,)*) = self;
+                $($name.trace(visitor);)*
+            }
+        }
+    };
+}
+
+impl_trace_tuple!(A);
+impl_trace_tuple!(A, B);
+impl_trace_tuple!(A, B, C);
+impl_trace_tuple!(A, B, C, D);
+impl_trace_tuple!(A, B, C, D, E);
+impl_trace_tuple!(A, B, C, D, E, F);
+
+impl<T: Trace> Arena<T>{
+    ///
+    /// Removes every element not reachable from the roots through the keys reported by Trace.
+    /// Returns the number of removed elements.
+    ///
+    /// Stale roots and keys are ignored. Reachable elements keep their keys.
+    ///
+    /// ```rust
+    /// use gen_arena::*;
+    ///
+    /// struct Node(Vec<ArenaIdx<Node>>);
+    ///
+    /// impl Trace for Node{
+    ///     fn trace(&self, visitor: &mut dyn FnMut(RawIdx)){
+    ///         self.0.trace(visitor);
+    ///     }
+    /// }
+    ///
+    /// let mut arena = Arena::new();
+    ///
+    /// let root = arena.insert(Node(Vec::new()));
+    /// let child = arena.insert(Node(Vec::new()));
+    /// arena[root].0.push(child);
+    ///
+    /// // Two elements referring to each other but not reachable from the root.
+    /// let a = arena.insert(Node(Vec::new()));
+    /// let b = arena.insert(Node(vec![a]));
+    /// arena[a].0.push(b);
+    ///
+    /// assert_eq!(arena.collect_garbage([root]), 2);
+    /// assert!(arena.get(child).is_some());
+    /// assert!(arena.get(a).is_none());
+    /// ```
+    ///
+    pub fn collect_garbage(&mut self, roots: impl IntoIterator<Item = ArenaIdx<T>>) -> usize{
+        let mut marked = vec![false; self.slots()];
+        let mut stack: Vec<RawIdx> = roots.into_iter().map(RawIdx::from).collect();
+
+        while let Some(index) = stack.pop(){
+            let index = ArenaIdx::from_parts(index.index, index.generation);
+            let Some(val) = self.get(index) else{
+                continue;
+            };
+            if std::mem::replace(&mut marked[index.index()], true){
+                continue;
+            }
+            val.trace(&mut |child| stack.push(child));
+        }
+
+        let garbage: Vec<_> = self.keys().filter(|index| !marked[index.index()]).collect();
+        for index in garbage.iter(){
+            self.remove(*index);
+        }
+        garbage.len()
+    }
+}
+
+#[cfg(test)]
+mod test{
+    use super::*;
+
+    struct Node{
+        name: &'static str,
+        next: Option<ArenaIdx<Node>>,
+        edges: Vec<(ArenaIdx<Node>, f32)>,
+    }
+
+    impl Trace for Node{
+        fn trace(&self, visitor: &mut dyn FnMut(RawIdx)){
+            self.next.trace(visitor);
+            self.edges.trace(visitor);
+        }
+    }
+
+    fn node(name: &'static str) -> Node{
+        Node{
+            name,
+            next: None,
+            edges: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_unreachable_cycle(){
+        let mut arena = Arena::new();
+        let root = arena.insert(node("root"));
+        let a = arena.insert(node("a"));
+        let b = arena.insert(node("b"));
+        let c = arena.insert(node("c"));
+        // A reachable cycle root -> a -> b -> root.
+        arena[root].next = Some(a);
+        arena[a].edges.push((b, 1.));
+        arena[b].next = Some(root);
+
+        // An unreachable cycle x -> y -> z -> x, pointing into the reachable part.
+        let x = arena.insert(node("x"));
+        let y = arena.insert(node("y"));
+        let z = arena.insert(node("z"));
+        arena[x].next = Some(y);
+        arena[y].edges.push((z, 0.5));
+        arena[z].next = Some(x);
+        arena[z].edges.push((a, 2.));
+
+        // A stale key is not followed even if its slot is reused.
+        let stale = arena.insert(node("stale"));
+        arena.remove(stale);
+        arena[c].next = Some(stale);
+        let reused = arena.insert(node("reused"));
+        assert_eq!(reused.index(), stale.index());
+        arena[b].edges.push((c, 0.));
+
+        assert_eq!(arena.collect_garbage([root]), 4);
+        let mut names: Vec<_> = arena.values().map(|n| n.name).collect();
+        names.sort();
+        assert_eq!(names, ["a", "b", "c", "root"]);
+        assert_eq!(arena[a].name, "a");
+        assert_eq!(arena[c].name, "c");
+        assert!(arena.get(x).is_none() && arena.get(reused).is_none());
+
+        // Collecting again finds nothing, without roots everything goes.
+        assert_eq!(arena.collect_garbage([root, root]), 0);
+        assert_eq!(arena.collect_garbage([x]), 4);
+        assert_eq!(arena.num(), 0);
+    }
+}
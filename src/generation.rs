@@ -0,0 +1,82 @@
+use std::num::Wrapping;
+
+///
+/// The generation type of an Arena and its keys.
+///
+/// The integers saturate: issued generations range from 0 to MAX - 1 and a slot whose
+/// generation would reach MAX is retired instead of reused, so a key is never issued twice.
+/// Retired slots stay allocated until the arena is dropped. With u64 or usize this never
+/// happens in practice, with u8 it happens after 255 reuses of the same slot.
+///
+/// The Wrapping integers wrap from MAX - 1 back to 0 and never retire a slot.
+/// A key that survives 2^bits - 1 reuses of its slot then refers to the new element.
+///
+/// # Example
+///
+///```rust
+/// use gen_arena::*;
+///
+/// let mut arena = Arena::<&str, u8>::default();
+///
+/// let mut i0 = arena.insert("a");
+/// for _ in 0..254{
+///     arena.remove(i0);
+///     i0 = arena.insert("a");
+/// }
+/// assert_eq!(i0.gen(), 254);
+///
+/// // The slot is retired and the next insertion uses a new one.
+/// arena.remove(i0);
+/// let i1 = arena.insert("b");
+/// assert_eq!(i1.index(), 1);
+///```
+///
+pub trait GenCounter: Copy + Eq + Ord + std::hash::Hash + std::fmt::Debug{
+    ///
+    /// The generation of a new slot.
+    ///
+    const FIRST: Self;
+
+    ///
+    /// Never issued, the generation of retired slots.
+    ///
+    const RETIRED: Self;
+
+    ///
+    /// Returns the generation of a slot after its element is removed, RETIRED to retire it.
+    ///
+    fn next(self) -> Self;
+}
+
+macro_rules! impl_gen_counter{
+    ($($ty:ty),*) => {
+        $(
+            impl GenCounter for $ty{
+                const FIRST: Self = 0;
+                const RETIRED: Self = <$ty>::MAX;
+
+                #[inline]
+                fn next(self) -> Self{
+                    self.saturating_add(1)
+                }
+            }
+
+            impl GenCounter for Wrapping<$ty>{
+                const FIRST: Self = Wrapping(0);
+                const RETIRED: Self = Wrapping(<$ty>::MAX);
+
+                #[inline]
+                fn next(self) -> Self{
+                    if self.0 == <$ty>::MAX - 1{
+                        Self::FIRST
+                    }
+                    else{
+                        Wrapping(self.0 + 1)
+                    }
+                }
+            }
+        )*
+    };
+}
+
+impl_gen_counter!(u8, u16, u32, u64, usize);
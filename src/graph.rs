@@ -0,0 +1,460 @@
+use std::ops::{Index, IndexMut};
+
+use crate::*;
+
+///
+/// A node of an ArenaGraph.
+///
+#[derive(Debug, Clone)]
+pub struct GraphNode<N, E>{
+    val: N,
+    first_out: Option<EdgeIdx<N, E>>,
+    first_in: Option<EdgeIdx<N, E>>,
+}
+
+///
+/// An edge of an ArenaGraph.
+/// It is linked into the list of outgoing edges of its source and the incoming edges of its target.
+///
+#[derive(Debug, Clone)]
+pub struct GraphEdge<N, E>{
+    val: E,
+    from: NodeIdx<N, E>,
+    to: NodeIdx<N, E>,
+    prev_out: Option<EdgeIdx<N, E>>,
+    next_out: Option<EdgeIdx<N, E>>,
+    prev_in: Option<EdgeIdx<N, E>>,
+    next_in: Option<EdgeIdx<N, E>>,
+}
+
+///
+/// A handle to a node of an ArenaGraph.
+///
+pub type NodeIdx<N, E> = ArenaIdx<GraphNode<N, E>>;
+
+///
+/// A handle to an edge of an ArenaGraph.
+///
+pub type EdgeIdx<N, E> = ArenaIdx<GraphEdge<N, E>>;
+
+///
+/// Selects the outgoing or the incoming edges of a node.
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Direction{
+    Outgoing,
+    Incoming,
+}
+
+///
+/// A directed graph whose nodes and edges live in two Arenas.
+///
+/// The edges of a node are chained through the edges themselves,
+/// so adding an edge never allocates per node and removing one is O(1).
+/// Handles stay valid until their node or edge is removed.
+/// Removing a node removes all of its edges.
+///
+/// # Example
+///
+///```rust
+/// use gen_arena::*;
+///
+/// let mut graph = ArenaGraph::new();
+///
+/// let a = graph.add_node("a");
+/// let b = graph.add_node("b");
+/// let c = graph.add_node("c");
+/// let ab = graph.add_edge(a, b, 1).unwrap();
+/// graph.add_edge(a, c, 2).unwrap();
+/// graph.add_edge(b, c, 3).unwrap();
+///
+/// let mut neighbors: Vec<_> = graph.neighbors(a).map(|n| graph[n]).collect();
+/// neighbors.sort();
+/// assert_eq!(neighbors, ["b", "c"]);
+/// assert_eq!(graph[ab], 1);
+///
+/// assert_eq!(graph.remove_node(b), Some("b"));
+/// assert_eq!(graph.edge(ab), None);
+/// assert_eq!(graph.edge_count(), 1);
+///```
+///
+#[derive(Debug, Clone)]
+pub struct ArenaGraph<N, E>{
+    nodes: Arena<GraphNode<N, E>>,
+    edges: Arena<GraphEdge<N, E>>,
+}
+
+impl<N, E> ArenaGraph<N, E>{
+    ///
+    /// Creates an empty ArenaGraph.
+    ///
+    pub fn new() -> Self{
+        Self{
+            nodes: Arena::new(),
+            edges: Arena::new(),
+        }
+    }
+
+    #[inline]
+    fn node_links_mut(&mut self, index: NodeIdx<N, E>) -> &mut GraphNode<N, E>{
+        self.nodes.get_mut(index).expect("Nodes of edges are always valid.")
+    }
+
+    #[inline]
+    fn edge_links_mut(&mut self, index: EdgeIdx<N, E>) -> &mut GraphEdge<N, E>{
+        self.edges.get_mut(index).expect("Linked edges are always valid.")
+    }
+
+    ///
+    /// Adds a node without edges.
+    ///
+    #[must_use]
+    pub fn add_node(&mut self, val: N) -> NodeIdx<N, E>{
+        self.nodes.insert(GraphNode{
+            val,
+            first_out: None,
+            first_in: None,
+        })
+    }
+
+    ///
+    /// Adds an edge from one node to another, which may be the same node.
+    /// Returns val as Err if either node is not valid.
+    ///
+    pub fn add_edge(&mut self, from: NodeIdx<N, E>, to: NodeIdx<N, E>, val: E) -> Result<EdgeIdx<N, E>, E>{
+        let (Some(from_node), Some(to_node)) = (self.nodes.get(from), self.nodes.get(to)) else{
+            return Err(val);
+        };
+        let (next_out, next_in) = (from_node.first_out, to_node.first_in);
+        let index = self.edges.insert(GraphEdge{
+            val,
+            from,
+            to,
+            prev_out: None,
+            next_out,
+            prev_in: None,
+            next_in,
+        });
+        if let Some(next) = next_out{
+            self.edge_links_mut(next).prev_out = Some(index);
+        }
+        if let Some(next) = next_in{
+            self.edge_links_mut(next).prev_in = Some(index);
+        }
+        self.node_links_mut(from).first_out = Some(index);
+        self.node_links_mut(to).first_in = Some(index);
+        Ok(index)
+    }
+
+    ///
+    /// Removes the edge and returns its value if the index is still valid.
+    ///
+    pub fn remove_edge(&mut self, index: EdgeIdx<N, E>) -> Option<E>{
+        let edge = self.edges.remove(index)?;
+        match edge.prev_out{
+            Some(prev) => self.edge_links_mut(prev).next_out = edge.next_out,
+            None => self.node_links_mut(edge.from).first_out = edge.next_out,
+        }
+        if let Some(next) = edge.next_out{
+            self.edge_links_mut(next).prev_out = edge.prev_out;
+        }
+        match edge.prev_in{
+            Some(prev) => self.edge_links_mut(prev).next_in = edge.next_in,
+            None => self.node_links_mut(edge.to).first_in = edge.next_in,
+        }
+        if let Some(next) = edge.next_in{
+            self.edge_links_mut(next).prev_in = edge.prev_in;
+        }
+        Some(edge.val)
+    }
+
+    ///
+    /// Removes the node together with all of its outgoing and incoming edges.
+    /// Returns its value if the index is still valid.
+    ///
+    pub fn remove_node(&mut self, index: NodeIdx<N, E>) -> Option<N>{
+        let mut out = self.nodes.get(index)?.first_out;
+        while let Some(edge) = out{
+            out = self.edges[edge].next_out;
+            self.remove_edge(edge);
+        }
+        // Read the incoming edges only now, since self loops were removed as outgoing edges.
+        let mut inc = self.nodes[index].first_in;
+        while let Some(edge) = inc{
+            inc = self.edges[edge].next_in;
+            self.remove_edge(edge);
+        }
+        self.nodes.remove(index).map(|node| node.val)
+    }
+
+    ///
+    /// Returns an optional reference to the value of the node.
+    ///
+    #[inline]
+    pub fn node(&self, index: NodeIdx<N, E>) -> Option<&N>{
+        self.nodes.get(index).map(|node| &node.val)
+    }
+
+    ///
+    /// Returns a mutable optional reference to the value of the node.
+    ///
+    #[inline]
+    pub fn node_mut(&mut self, index: NodeIdx<N, E>) -> Option<&mut N>{
+        self.nodes.get_mut(index).map(|node| &mut node.val)
+    }
+
+    ///
+    /// Returns an optional reference to the value of the edge.
+    ///
+    #[inline]
+    pub fn edge(&self, index: EdgeIdx<N, E>) -> Option<&E>{
+        self.edges.get(index).map(|edge| &edge.val)
+    }
+
+    ///
+    /// Returns a mutable optional reference to the value of the edge.
+    ///
+    #[inline]
+    pub fn edge_mut(&mut self, index: EdgeIdx<N, E>) -> Option<&mut E>{
+        self.edges.get_mut(index).map(|edge| &mut edge.val)
+    }
+
+    ///
+    /// Returns the source and target node of the edge.
+    ///
+    #[inline]
+    pub fn endpoints(&self, index: EdgeIdx<N, E>) -> Option<(NodeIdx<N, E>, NodeIdx<N, E>)>{
+        self.edges.get(index).map(|edge| (edge.from, edge.to))
+    }
+
+    ///
+    /// Returns an iterator over the outgoing or incoming edges of the node, newest first.
+    /// It is empty if the index is not valid.
+    ///
+    pub fn edges_directed(&self, index: NodeIdx<N, E>, dir: Direction) -> GraphEdges<'_, N, E>{
+        let next = self.nodes.get(index).and_then(|node|{
+            match dir{
+                Direction::Outgoing => node.first_out,
+                Direction::Incoming => node.first_in,
+            }
+        });
+        GraphEdges{
+            graph: self,
+            next,
+            dir,
+        }
+    }
+
+    ///
+    /// Returns an iterator over the outgoing edges of the node, newest first.
+    ///
+    #[inline]
+    pub fn edges(&self, index: NodeIdx<N, E>) -> GraphEdges<'_, N, E>{
+        self.edges_directed(index, Direction::Outgoing)
+    }
+
+    ///
+    /// Returns an iterator over the nodes connected to the node by an edge in the direction.
+    /// Nodes connected by several edges are returned once per edge.
+    ///
+    pub fn neighbors_directed(&self, index: NodeIdx<N, E>, dir: Direction) -> impl Iterator<Item = NodeIdx<N, E>> + '_{
+        self.edges_directed(index, dir).map(move |edge|{
+            let edge = &self.edges[edge];
+            match dir{
+                Direction::Outgoing => edge.to,
+                Direction::Incoming => edge.from,
+            }
+        })
+    }
+
+    ///
+    /// Returns an iterator over the targets of the outgoing edges of the node.
+    ///
+    #[inline]
+    pub fn neighbors(&self, index: NodeIdx<N, E>) -> impl Iterator<Item = NodeIdx<N, E>> + '_{
+        self.neighbors_directed(index, Direction::Outgoing)
+    }
+
+    ///
+    /// Returns an iterator over all nodes with their values.
+    ///
+    pub fn nodes(&self) -> impl Iterator<Item = (NodeIdx<N, E>, &N)>{
+        self.nodes.iter().map(|(index, node)| (index, &node.val))
+    }
+
+    ///
+    /// Returns an iterator over all edges with their values.
+    ///
+    pub fn all_edges(&self) -> impl Iterator<Item = (EdgeIdx<N, E>, &E)>{
+        self.edges.iter().map(|(index, edge)| (index, &edge.val))
+    }
+
+    #[inline]
+    pub fn node_count(&self) -> usize{
+        self.nodes.num()
+    }
+
+    #[inline]
+    pub fn edge_count(&self) -> usize{
+        self.edges.num()
+    }
+
+    #[inline]
+    pub fn clear(&mut self){
+        self.nodes.clear();
+        self.edges.clear();
+    }
+}
+
+impl<N, E> Default for ArenaGraph<N, E>{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<N, E> Index<NodeIdx<N, E>> for ArenaGraph<N, E>{
+    type Output = N;
+
+    fn index(&self, index: NodeIdx<N, E>) -> &Self::Output {
+        self.node(index).expect("There is no element at this index with that generation.")
+    }
+}
+
+impl<N, E> IndexMut<NodeIdx<N, E>> for ArenaGraph<N, E>{
+    fn index_mut(&mut self, index: NodeIdx<N, E>) -> &mut Self::Output {
+        self.node_mut(index).expect("There is no element at this index with that generation.")
+    }
+}
+
+impl<N, E> Index<EdgeIdx<N, E>> for ArenaGraph<N, E>{
+    type Output = E;
+
+    fn index(&self, index: EdgeIdx<N, E>) -> &Self::Output {
+        self.edge(index).expect("There is no element at this index with that generation.")
+    }
+}
+
+impl<N, E> IndexMut<EdgeIdx<N, E>> for ArenaGraph<N, E>{
+    fn index_mut(&mut self, index: EdgeIdx<N, E>) -> &mut Self::Output {
+        self.edge_mut(index).expect("There is no element at this index with that generation.")
+    }
+}
+
+pub struct GraphEdges<'i, N: 'i, E: 'i>{
+    graph: &'i ArenaGraph<N, E>,
+    next: Option<EdgeIdx<N, E>>,
+    dir: Direction,
+}
+
+impl<'i, N, E> Iterator for GraphEdges<'i, N, E>{
+    type Item = EdgeIdx<N, E>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let index = self.next?;
+        let edge = &self.graph.edges[index];
+        self.next = match self.dir{
+            Direction::Outgoing => edge.next_out,
+            Direction::Incoming => edge.next_in,
+        };
+        Some(index)
+    }
+}
+
+#[cfg(test)]
+mod test{
+    use super::*;
+    use std::collections::HashSet;
+
+    struct Rng(u64);
+
+    impl Rng{
+        fn next(&mut self) -> usize{
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            self.0 as usize
+        }
+    }
+
+    type Idx = NodeIdx<usize, usize>;
+
+    type Model = [(Idx, Idx, EdgeIdx<usize, usize>)];
+
+    fn key(edge: EdgeIdx<usize, usize>) -> (usize, usize){
+        (edge.index(), edge.gen())
+    }
+
+    ///
+    /// Compares the adjacency of every node with a list of (from, to, edge) triples.
+    ///
+    fn assert_adjacency(graph: &ArenaGraph<usize, usize>, model: &Model){
+        assert_eq!(graph.edge_count(), model.len());
+        for (node, _) in graph.nodes(){
+            let out: HashSet<_> = graph.edges(node).map(key).collect();
+            let expected: HashSet<_> = model.iter().filter(|(from, _, _)| *from == node).map(|(_, _, e)| key(*e)).collect();
+            assert_eq!(out, expected);
+            let inc: HashSet<_> = graph.edges_directed(node, Direction::Incoming).map(key).collect();
+            let expected: HashSet<_> = model.iter().filter(|(_, to, _)| *to == node).map(|(_, _, e)| key(*e)).collect();
+            assert_eq!(inc, expected);
+        }
+        for (from, to, edge) in model{
+            assert_eq!(graph.endpoints(*edge), Some((*from, *to)));
+        }
+    }
+
+    #[test]
+    fn test_remove_node_fixes_adjacency(){
+        let mut graph = ArenaGraph::new();
+        let nodes: Vec<_> = (0..4).map(|i| graph.add_node(i)).collect();
+        let mut model = Vec::new();
+        for (from, to) in [(0, 1), (1, 2), (2, 0), (1, 1), (3, 1), (0, 1)]{
+            let edge = graph.add_edge(nodes[from], nodes[to], from * 10 + to).unwrap();
+            model.push((nodes[from], nodes[to], edge));
+        }
+        assert_adjacency(&graph, &model);
+
+        // Node 1 has outgoing, incoming, parallel and self loop edges.
+        assert_eq!(graph.remove_node(nodes[1]), Some(1));
+        model.retain(|(from, to, _)| *from != nodes[1] && *to != nodes[1]);
+        assert_adjacency(&graph, &model);
+        assert_eq!(graph.neighbors(nodes[0]).count(), 0);
+        assert_eq!(graph.neighbors_directed(nodes[0], Direction::Incoming).collect::<Vec<_>>(), [nodes[2]]);
+        assert_eq!(graph.add_edge(nodes[1], nodes[0], 0), Err(0));
+        assert_eq!(graph.remove_node(nodes[1]), None);
+    }
+
+    #[test]
+    fn test_churn(){
+        let mut rng = Rng(0x9e3779b97f4a7c15);
+        let mut graph = ArenaGraph::new();
+        let mut nodes: Vec<Idx> = Vec::new();
+        let mut model = Vec::new();
+
+        for i in 0..2000{
+            match rng.next() % 6{
+                0 | 1 => nodes.push(graph.add_node(i)),
+                2 | 3 if !nodes.is_empty() => {
+                    let (from, to) = (nodes[rng.next() % nodes.len()], nodes[rng.next() % nodes.len()]);
+                    let edge = graph.add_edge(from, to, i).unwrap();
+                    model.push((from, to, edge));
+                }
+                4 if !model.is_empty() => {
+                    let (_, _, edge) = model.swap_remove(rng.next() % model.len());
+                    assert!(graph.remove_edge(edge).is_some());
+                    assert!(graph.remove_edge(edge).is_none());
+                }
+                5 if !nodes.is_empty() => {
+                    let node = nodes.swap_remove(rng.next() % nodes.len());
+                    assert!(graph.remove_node(node).is_some());
+                    model.retain(|(from, to, _)| *from != node && *to != node);
+                }
+                _ => {}
+            }
+            if i % 100 == 0{
+                assert_adjacency(&graph, &model);
+            }
+        }
+        assert_adjacency(&graph, &model);
+        assert_eq!(graph.node_count(), nodes.len());
+    }
+}
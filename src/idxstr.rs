@@ -0,0 +1,204 @@
+use std::{fmt, num::ParseIntError, str::FromStr};
+
+use crate::*;
+
+///
+/// The reason a key could not be parsed from its textual form "index@generation".
+///
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseIdxError{
+    /// There is no '@' between index and generation.
+    MissingSeparator,
+    /// The index is empty, not a number, out of range or followed by junk.
+    InvalidIndex(ParseIntError),
+    /// The generation is empty, not a number, out of range or followed by junk.
+    InvalidGeneration(ParseIntError),
+}
+
+impl fmt::Display for ParseIdxError{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self{
+            Self::MissingSeparator => write!(f, "expected a key of the form index@generation"),
+            Self::InvalidIndex(err) => write!(f, "invalid index: {}", err),
+            Self::InvalidGeneration(err) => write!(f, "invalid generation: {}", err),
+        }
+    }
+}
+
+// ParseIntError has no defmt::Format, only its kind is logged.
+#[cfg(feature = "defmt")]
+impl defmt::Format for ParseIdxError{
+    fn format(&self, f: defmt::Formatter<'_>) {
+        match self{
+            Self::MissingSeparator => defmt::write!(f, "MissingSeparator"),
+            Self::InvalidIndex(err) => defmt::write!(f, "InvalidIndex({})", defmt::Debug2Format(err.kind())),
+            Self::InvalidGeneration(err) => defmt::write!(f, "InvalidGeneration({})", defmt::Debug2Format(err.kind())),
+        }
+    }
+}
+
+impl std::error::Error for ParseIdxError{
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self{
+            Self::MissingSeparator => None,
+            Self::InvalidIndex(err) | Self::InvalidGeneration(err) => Some(err),
+        }
+    }
+}
+
+///
+/// The reason Arena::get_str failed.
+///
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum KeyParseOrGetError{
+    /// The string is not a key.
+    Parse(ParseIdxError),
+    /// The key is stale or was never issued by the arena.
+    NotFound,
+}
+
+impl fmt::Display for KeyParseOrGetError{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self{
+            Self::Parse(err) => err.fmt(f),
+            Self::NotFound => write!(f, "there is no element at this index with that generation"),
+        }
+    }
+}
+
+impl std::error::Error for KeyParseOrGetError{
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self{
+            Self::Parse(err) => Some(err),
+            Self::NotFound => None,
+        }
+    }
+}
+
+impl From<ParseIdxError> for KeyParseOrGetError{
+    fn from(err: ParseIdxError) -> Self {
+        Self::Parse(err)
+    }
+}
+
+///
+/// Formats the key as "index@generation", which FromStr parses back.
+///
+/// ```rust
+/// use gen_arena::*;
+///
+/// let mut arena = Arena::new();
+/// let _ = arena.insert("a");
+/// let i1 = arena.insert("b");
+///
+/// assert_eq!(i1.to_string(), "1@0");
+/// assert_eq!("1@0".parse(), Ok(i1));
+/// ```
+///
+impl<T: ?Sized, G: GenCounter + fmt::Display, Tag> fmt::Display for ArenaIdx<T, G, Tag>{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}@{}", self.index(), self.gen())
+    }
+}
+
+impl<T: ?Sized, G: GenCounter + FromStr<Err = ParseIntError>, Tag> FromStr for ArenaIdx<T, G, Tag>{
+    type Err = ParseIdxError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (index, generation) = s.split_once('@').ok_or(ParseIdxError::MissingSeparator)?;
+        let index = index.parse().map_err(ParseIdxError::InvalidIndex)?;
+        let generation = generation.parse().map_err(ParseIdxError::InvalidGeneration)?;
+        Ok(ArenaIdx::from_parts(index, generation))
+    }
+}
+
+impl<T, G: GenCounter + FromStr<Err = ParseIntError>, Tag> Arena<T, G, Tag>{
+    ///
+    /// Parses a key of the form "index@generation" and returns the element it refers to.
+    ///
+    /// ```rust
+    /// use gen_arena::*;
+    ///
+    /// let mut arena = Arena::new();
+    /// let i0 = arena.insert("a");
+    ///
+    /// assert_eq!(arena.get_str("0@0"), Ok(&"a"));
+    /// assert_eq!(arena.get_str("0@1"), Err(KeyParseOrGetError::NotFound));
+    /// assert!(matches!(arena.get_str("0"), Err(KeyParseOrGetError::Parse(_))));
+    /// ```
+    ///
+    pub fn get_str(&self, s: &str) -> Result<&T, KeyParseOrGetError>{
+        let index: ArenaIdx<T, G, Tag> = s.parse()?;
+        self.get(index).ok_or(KeyParseOrGetError::NotFound)
+    }
+}
+
+#[cfg(test)]
+mod test{
+    use super::*;
+    use std::num::IntErrorKind;
+
+    fn kind(err: &ParseIdxError) -> Option<&IntErrorKind>{
+        match err{
+            ParseIdxError::MissingSeparator => None,
+            ParseIdxError::InvalidIndex(err) | ParseIdxError::InvalidGeneration(err) => Some(err.kind()),
+        }
+    }
+
+    #[test]
+    fn test_round_trip(){
+        let mut arena = Arena::new();
+        let mut keys: Vec<_> = (0..20).map(|i| arena.insert(i)).collect();
+        for key in keys.iter().step_by(3){
+            arena.remove(*key);
+        }
+        // Stale keys round trip as well, but are not found.
+        keys.extend((0..5).map(|i| arena.insert(i)));
+        for key in keys{
+            assert_eq!(key.to_string().parse(), Ok(key));
+            assert_eq!(arena.get_str(&key.to_string()), arena.get(key).ok_or(KeyParseOrGetError::NotFound));
+        }
+
+        let max = ArenaIdx::<u8>::from_raw_parts(usize::MAX, usize::MAX);
+        assert_eq!(max.to_string().parse(), Ok(max));
+
+        let mut narrow = Arena::<&str, u16>::default();
+        let i0 = narrow.insert("a");
+        assert_eq!(i0.to_string(), "0@0");
+        assert_eq!(narrow.get_str("0@0"), Ok(&"a"));
+    }
+
+    #[test]
+    fn test_malformed(){
+        let parse = |s: &str| s.parse::<ArenaIdx<u8>>().unwrap_err();
+
+        assert_eq!(parse(""), ParseIdxError::MissingSeparator);
+        assert_eq!(parse("17"), ParseIdxError::MissingSeparator);
+        assert_eq!(parse("17:3"), ParseIdxError::MissingSeparator);
+
+        assert!(matches!(parse("@3"), ParseIdxError::InvalidIndex(_)));
+        assert_eq!(kind(&parse("@3")), Some(&IntErrorKind::Empty));
+        assert_eq!(kind(&parse("17@")), Some(&IntErrorKind::Empty));
+        assert_eq!(kind(&parse("x@3")), Some(&IntErrorKind::InvalidDigit));
+        assert_eq!(kind(&parse("-1@3")), Some(&IntErrorKind::InvalidDigit));
+
+        // Trailing junk, including a second separator or whitespace.
+        assert!(matches!(parse("17@3x"), ParseIdxError::InvalidGeneration(_)));
+        assert_eq!(kind(&parse("17@3@4")), Some(&IntErrorKind::InvalidDigit));
+        assert_eq!(kind(&parse("17@3 ")), Some(&IntErrorKind::InvalidDigit));
+        assert_eq!(kind(&parse(" 17@3")), Some(&IntErrorKind::InvalidDigit));
+
+        // Overflow of the index and of a narrow generation.
+        assert!(matches!(parse("99999999999999999999999@0"), ParseIdxError::InvalidIndex(_)));
+        assert_eq!(kind(&parse("99999999999999999999999@0")), Some(&IntErrorKind::PosOverflow));
+        let err = "0@256".parse::<ArenaIdx<u8, u8>>().unwrap_err();
+        assert!(matches!(err, ParseIdxError::InvalidGeneration(_)));
+        assert_eq!(kind(&err), Some(&IntErrorKind::PosOverflow));
+
+        let arena = Arena::<u8>::new();
+        assert_eq!(arena.get_str("0@0"), Err(KeyParseOrGetError::NotFound));
+        assert_eq!(arena.get_str("0@"), Err(KeyParseOrGetError::Parse(parse("0@"))));
+        assert_eq!(KeyParseOrGetError::from(parse("17")).to_string(), "expected a key of the form index@generation");
+    }
+}
@@ -0,0 +1,74 @@
+use std::fmt;
+
+///
+/// The reason an insertion failed, see InsertError.
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum InsertErrorKind{
+    /// Every slot of the arena holds an element or is retired.
+    Full,
+    /// The list of freed slots links to a slot that is not free. The arena dropped the link
+    /// and retried, which failed as well.
+    CorruptFreeList{
+        slot: usize,
+    },
+}
+
+///
+/// The error of try_insert, holding the value that could not be inserted.
+///
+#[derive(Clone, PartialEq, Eq)]
+pub struct InsertError<T>{
+    pub kind: InsertErrorKind,
+    value: T,
+}
+
+impl<T> InsertError<T>{
+    #[inline]
+    pub(crate) fn new(value: T, kind: InsertErrorKind) -> Self{
+        Self{
+            kind,
+            value,
+        }
+    }
+
+    ///
+    /// Returns the value that could not be inserted.
+    ///
+    #[inline]
+    pub fn into_value(self) -> T{
+        self.value
+    }
+
+    #[inline]
+    pub fn value(&self) -> &T{
+        &self.value
+    }
+}
+
+impl<T> fmt::Debug for InsertError<T>{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("InsertError")
+            .field("kind", &self.kind)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<T> fmt::Display for InsertError<T>{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.kind{
+            InsertErrorKind::Full => write!(f, "the arena is full"),
+            InsertErrorKind::CorruptFreeList{slot} => write!(f, "the list of freed slots links to slot {}, which is not free", slot),
+        }
+    }
+}
+
+impl<T> std::error::Error for InsertError<T>{}
+
+#[cfg(feature = "defmt")]
+impl<T> defmt::Format for InsertError<T>{
+    fn format(&self, f: defmt::Formatter<'_>) {
+        defmt::write!(f, "InsertError {{ kind: {} }}", self.kind)
+    }
+}
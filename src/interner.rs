@@ -0,0 +1,245 @@
+use std::{borrow::Borrow, collections::{hash_map::RandomState, HashMap}, hash::{BuildHasher, Hash}, ops::Index};
+
+use crate::*;
+
+///
+/// An Interner storing each distinct value once in an Arena.
+///
+/// Equal values are mapped to the same key, so keys can be compared instead of values.
+/// Values can not be removed, which keeps every key valid for the lifetime of the Interner.
+///
+/// The hash index only stores keys, so values are not required to be Clone and
+/// lookups through borrowed forms (e.g. &str for String) do not allocate.
+///
+/// # Example
+///
+///```rust
+/// use gen_arena::*;
+///
+/// let mut interner = Interner::<String>::new();
+///
+/// let i0 = interner.get_or_intern("hello".to_string());
+/// let i1 = interner.intern_ref("hello");
+/// let i2 = interner.intern_ref("world");
+///
+/// assert_eq!(i0, i1);
+/// assert_ne!(i0, i2);
+/// assert_eq!(interner.resolve(i2).map(|s| s.as_str()), Some("world"));
+/// assert_eq!(interner.get("world"), Some(i2));
+/// assert_eq!(interner.get("missing"), None);
+///```
+///
+pub struct Interner<T: Hash + Eq, S = RandomState>{
+    arena: Arena<T>,
+    map: HashMap<u64, Vec<ArenaIdx<T>>>,
+    hasher: S,
+}
+
+impl<T: Hash + Eq> Interner<T>{
+    ///
+    /// Creates an empty Interner.
+    ///
+    pub fn new() -> Self{
+        Self::with_hasher(RandomState::new())
+    }
+}
+
+impl<T: Hash + Eq> Default for Interner<T>{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Hash + Eq, S: BuildHasher> Interner<T, S>{
+    ///
+    /// Creates an empty Interner hashing values with the hasher.
+    ///
+    pub fn with_hasher(hasher: S) -> Self{
+        Self{
+            arena: Arena::new(),
+            map: HashMap::new(),
+            hasher,
+        }
+    }
+
+    ///
+    /// Returns the key of the value equal to val, if it was interned.
+    ///
+    pub fn get<Q: ?Sized + Hash + Eq>(&self, val: &Q) -> Option<ArenaIdx<T>>
+    where T: Borrow<Q>
+    {
+        self.find(self.hasher.hash_one(val), val)
+    }
+
+    fn find<Q: ?Sized + Eq>(&self, hash: u64, val: &Q) -> Option<ArenaIdx<T>>
+    where T: Borrow<Q>
+    {
+        self.map.get(&hash)?.iter().copied().find(|index|{
+            self.arena[*index].borrow() == val
+        })
+    }
+
+    ///
+    /// Returns the key of the value equal to val.
+    /// Inserts the value constructed by f if there is none.
+    ///
+    pub fn get_or_intern_with<Q: ?Sized + Hash + Eq>(&mut self, val: &Q, f: impl FnOnce() -> T) -> ArenaIdx<T>
+    where T: Borrow<Q>
+    {
+        let hash = self.hasher.hash_one(val);
+        if let Some(index) = self.find(hash, val){
+            return index;
+        }
+        let owned = f();
+        debug_assert!(owned.borrow() == val, "The constructed value has to be equal to the looked up one.");
+        let index = self.arena.insert(owned);
+        self.map.entry(hash).or_default().push(index);
+        index
+    }
+
+    ///
+    /// Returns the key of the value equal to val, inserting val if there is none.
+    ///
+    pub fn get_or_intern(&mut self, val: T) -> ArenaIdx<T>{
+        let hash = self.hasher.hash_one(&val);
+        if let Some(index) = self.find(hash, &val){
+            return index;
+        }
+        let index = self.arena.insert(val);
+        self.map.entry(hash).or_default().push(index);
+        index
+    }
+
+    ///
+    /// Returns the key of the value equal to val.
+    /// Only allocates an owned copy of val if it was not interned before.
+    ///
+    pub fn intern_ref<Q: ?Sized + Hash + Eq + ToOwned<Owned = T>>(&mut self, val: &Q) -> ArenaIdx<T>
+    where T: Borrow<Q>
+    {
+        self.get_or_intern_with(val, || val.to_owned())
+    }
+
+    ///
+    /// Returns the value of the key.
+    ///
+    #[inline]
+    pub fn resolve(&self, index: ArenaIdx<T>) -> Option<&T>{
+        self.arena.get(index)
+    }
+
+    ///
+    /// Returns true if the value equal to val was interned.
+    ///
+    #[inline]
+    pub fn contains<Q: ?Sized + Hash + Eq>(&self, val: &Q) -> bool
+    where T: Borrow<Q>
+    {
+        self.get(val).is_some()
+    }
+
+    ///
+    /// Returns an iterator over the interned values with their keys.
+    ///
+    #[inline]
+    pub fn iter(&self) -> Iter<'_, T>{
+        self.arena.iter()
+    }
+
+    ///
+    /// Returns the number of distinct values.
+    ///
+    #[inline]
+    pub fn len(&self) -> usize{
+        self.arena.num()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool{
+        self.len() == 0
+    }
+}
+
+impl<T: Hash + Eq + std::fmt::Debug, S> std::fmt::Debug for Interner<T, S>{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Interner")
+            .field("arena", &self.arena)
+            .finish()
+    }
+}
+
+impl<T: Hash + Eq, S: BuildHasher> Index<ArenaIdx<T>> for Interner<T, S>{
+    type Output = T;
+
+    fn index(&self, index: ArenaIdx<T>) -> &Self::Output {
+        self.resolve(index).expect("There is no element at this index with that generation.")
+    }
+}
+
+impl<T: Hash + Eq> FromIterator<T> for Interner<T>{
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut interner = Self::new();
+        for val in iter{
+            interner.get_or_intern(val);
+        }
+        interner
+    }
+}
+
+#[cfg(test)]
+mod test{
+    use super::*;
+    use std::hash::Hasher;
+
+    #[test]
+    fn test_intern(){
+        let mut interner = Interner::new();
+
+        let words = ["a", "b", "a", "c", "b", "a"];
+        let keys: Vec<_> = words.iter().map(|w| interner.get_or_intern(w.to_string())).collect();
+
+        assert_eq!(keys[0], keys[2]);
+        assert_eq!(keys[0], keys[5]);
+        assert_eq!(keys[1], keys[4]);
+        assert_ne!(keys[0], keys[1]);
+        assert_ne!(keys[0], keys[3]);
+        assert_ne!(keys[1], keys[3]);
+        assert_eq!(interner.len(), 3);
+
+        for (word, key) in words.iter().zip(keys.iter()){
+            assert_eq!(interner[*key], *word);
+            assert_eq!(interner.intern_ref(*word), *key);
+        }
+        assert_eq!(interner.len(), 3);
+    }
+
+    // Sends every value into the same bucket so equality has to resolve collisions.
+    #[derive(Default)]
+    struct Collide;
+
+    impl Hasher for Collide{
+        fn finish(&self) -> u64{
+            0
+        }
+        fn write(&mut self, _: &[u8]){}
+    }
+
+    impl BuildHasher for Collide{
+        type Hasher = Collide;
+        fn build_hasher(&self) -> Collide{
+            Collide
+        }
+    }
+
+    #[test]
+    fn test_hash_collisions(){
+        let mut interner = Interner::with_hasher(Collide);
+        let keys: Vec<_> = (0..100).map(|i| interner.get_or_intern(i)).collect();
+        for (i, key) in keys.iter().enumerate(){
+            assert_eq!(interner.get_or_intern(i as i32), *key);
+            assert_eq!(interner.resolve(*key), Some(&(i as i32)));
+        }
+        assert_eq!(interner.len(), 100);
+        assert!(!interner.contains(&100));
+    }
+}
@@ -0,0 +1,204 @@
+use std::cell::RefCell;
+
+use critical_section::{CriticalSection, Mutex};
+
+use crate::*;
+
+///
+/// An SArena shared between interrupt handlers and the main loop.
+///
+/// Every access runs in a critical section, so the arena is Sync and can live in a static.
+/// Nothing panics: insert returns the value if the arena is full, and lookups return None
+/// for stale keys. Values cannot be borrowed out of the critical section, so get clones them
+/// and with_mut runs a closure on them. An access from inside that closure fails like a full
+/// or stale access, insert returning the value and the others None.
+///
+/// Requires the critical-section feature and an implementation of critical-section for the target,
+/// such as the one of the std feature on hosts.
+///
+/// # Example
+///
+///```rust
+/// use gen_arena::*;
+///
+/// static EVENTS: IsrArena<u32, 16> = IsrArena::new();
+///
+/// // In the interrupt handler.
+/// let _ = EVENTS.insert(7);
+///
+/// // In the main loop.
+/// while let Some((_, event)) = EVENTS.pop(){
+///     assert_eq!(event, 7);
+/// }
+///```
+///
+pub struct IsrArena<T, const N: usize, G = usize>{
+    arena: Mutex<RefCell<SArena<T, N, G>>>,
+}
+
+impl<T, const N: usize, G: GenCounter> IsrArena<T, N, G>{
+    ///
+    /// Creates a new empty IsrArena.
+    ///
+    pub const fn new() -> Self{
+        Self{
+            arena: Mutex::new(RefCell::new(SArena::new())),
+        }
+    }
+
+    // Runs f on the arena in a critical section, or returns None if it is already borrowed by with_mut.
+    #[inline]
+    fn with<R>(&self, f: impl FnOnce(&mut SArena<T, N, G>) -> R) -> Option<R>{
+        critical_section::with(|cs: CriticalSection|{
+            let mut arena = self.arena.borrow(cs).try_borrow_mut().ok()?;
+            Some(f(&mut arena))
+        })
+    }
+
+    ///
+    /// Inserts a new element into the arena.
+    /// Returns the value if the arena is full or called from inside with_mut.
+    ///
+    pub fn insert(&self, val: T) -> Result<ArenaIdx<T, G>, T>{
+        let mut val = Some(val);
+        self.with(|arena| arena.try_insert(val.take().expect("The value is only taken once.")).map_err(InsertError::into_value))
+            .unwrap_or_else(|| Err(val.take().expect("The value was not taken if the arena is borrowed.")))
+    }
+
+    ///
+    /// Removes the element and returns its value if the index is still valid.
+    ///
+    pub fn remove(&self, index: ArenaIdx<T, G>) -> Option<T>{
+        self.with(|arena| arena.remove(index)).flatten()
+    }
+
+    ///
+    /// Removes the element with the lowest index, so the main loop can drain elements
+    /// inserted by interrupt handlers without knowing their keys.
+    ///
+    pub fn pop(&self) -> Option<(ArenaIdx<T, G>, T)>{
+        self.with(|arena|{
+            let index = arena.keys().next()?;
+            arena.remove(index).map(|val| (index, val))
+        }).flatten()
+    }
+
+    ///
+    /// Returns a clone of the value.
+    ///
+    pub fn get(&self, index: ArenaIdx<T, G>) -> Option<T>
+    where
+        T: Clone,
+    {
+        self.with(|arena| arena.get(index).cloned()).flatten()
+    }
+
+    ///
+    /// Runs f on the value in the critical section and returns its result.
+    /// f should be short, as interrupts are disabled while it runs.
+    ///
+    pub fn with_mut<R>(&self, index: ArenaIdx<T, G>, f: impl FnOnce(&mut T) -> R) -> Option<R>{
+        self.with(|arena| arena.get_mut(index).map(f)).flatten()
+    }
+
+    #[inline]
+    pub fn contains(&self, index: ArenaIdx<T, G>) -> bool{
+        self.with(|arena| arena.contains(index)).unwrap_or(false)
+    }
+
+    ///
+    /// Returns the number of elements, or None if called from inside with_mut.
+    ///
+    #[inline]
+    pub fn len(&self) -> Option<usize>{
+        self.with(|arena| arena.len())
+    }
+
+    ///
+    /// Returns true if the arena is empty, or None if called from inside with_mut.
+    ///
+    #[inline]
+    pub fn is_empty(&self) -> Option<bool>{
+        self.with(|arena| arena.is_empty())
+    }
+
+    #[inline]
+    pub fn capacity(&self) -> usize{
+        N
+    }
+
+    ///
+    /// Returns the arena, which is not shared anymore.
+    ///
+    pub fn into_inner(self) -> SArena<T, N, G>{
+        self.arena.into_inner().into_inner()
+    }
+}
+
+impl<T, const N: usize, G: GenCounter> Default for IsrArena<T, N, G>{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod test{
+    use super::*;
+
+    #[test]
+    fn test_concurrent(){
+        static EVENTS: IsrArena<(usize, usize), 8> = IsrArena::new();
+
+        // Producers stand in for interrupt handlers, retrying while the arena is full.
+        let mut received = Vec::new();
+        std::thread::scope(|s|{
+            for producer in 0..4{
+                s.spawn(move ||{
+                    for i in 0..250{
+                        let mut event = (producer, i);
+                        while let Err(rejected) = EVENTS.insert(event){
+                            event = rejected;
+                            std::thread::yield_now();
+                        }
+                    }
+                });
+            }
+            while received.len() < 1000{
+                match EVENTS.pop(){
+                    Some((_, event)) => received.push(event),
+                    None => std::thread::yield_now(),
+                }
+            }
+        });
+        assert_eq!(EVENTS.len(), Some(0));
+
+        // Every event arrived exactly once.
+        received.sort();
+        assert_eq!(received, (0..4).flat_map(|p| (0..250).map(move |i| (p, i))).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_no_panics(){
+        let arena = IsrArena::<String, 2>::new();
+        let a = arena.insert(String::from("a")).unwrap();
+        let b = arena.insert(String::from("b")).unwrap();
+        assert_eq!(arena.insert(String::from("c")), Err(String::from("c")));
+
+        assert_eq!(arena.get(a).as_deref(), Some("a"));
+        assert_eq!(arena.with_mut(b, |val| val.push('!')), Some(()));
+        assert_eq!(arena.remove(a).as_deref(), Some("a"));
+        assert_eq!(arena.remove(a), None);
+        assert_eq!(arena.get(a), None);
+        assert!(!arena.contains(a));
+
+        // Accessing the arena from inside with_mut fails instead of panicking.
+        let nested = arena.with_mut(b, |_|{
+            (arena.insert(String::from("d")), arena.get(b), arena.len(), arena.pop())
+        });
+        assert_eq!(nested, Some((Err(String::from("d")), None, None, None)));
+
+        assert_eq!(arena.pop(), Some((b, String::from("b!"))));
+        assert_eq!(arena.is_empty(), Some(true));
+        assert!(arena.into_inner().is_empty());
+    }
+}
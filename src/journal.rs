@@ -0,0 +1,409 @@
+use std::collections::VecDeque;
+
+use crate::*;
+
+// A change to one slot. Applying an entry to the arena returns the entry reverting it.
+#[derive(Debug, Clone)]
+enum Entry<T>{
+    // Frees the slot, leaving the generation at gen. Pops the cell if pop is set.
+    Take{
+        slot: usize,
+        gen: usize,
+        pop: bool,
+    },
+    // Allocates the slot with val at generation gen. Pushes the cell if push is set.
+    Put{
+        slot: usize,
+        gen: usize,
+        val: T,
+        push: bool,
+    },
+    // Replaces the value in the slot.
+    Replace{
+        slot: usize,
+        val: T,
+    },
+}
+
+impl<T> Entry<T>{
+    fn apply(self, arena: &mut Arena<T>) -> Self{
+        match self{
+            Entry::Take{slot, gen, pop} => {
+                let idx = arena.idx_at(slot).expect("Journaled slots are always allocated.");
+                let val = arena.take_at(slot, gen, pop);
+                Entry::Put{slot, gen: idx.gen(), val, push: pop}
+            }
+            Entry::Put{slot, gen, val, push} => {
+                // The generation a later Take restores, which is the one of the freed cell.
+                let freed_gen = if push {gen} else{arena.gen(slot)};
+                arena.put_at(slot, val, gen, push);
+                Entry::Take{slot, gen: freed_gen, pop: push}
+            }
+            Entry::Replace{slot, val} => {
+                let old = arena.get_any_mut(slot).expect("Journaled slots are always allocated.");
+                Entry::Replace{slot, val: std::mem::replace(old, val)}
+            }
+        }
+    }
+}
+
+///
+/// An Arena recording the inverse of every mutation, for undo and redo.
+///
+/// Mutations are grouped into transactions with begin and commit; mutations outside of
+/// a transaction form one of their own. undo reverts the last transaction and redo reapplies it,
+/// both restoring the exact slots, generations and free list, so keys held elsewhere keep referring
+/// to the same elements. A consequence is that after undoing an insertion, the next insertion may
+/// reuse the key of the undone one.
+/// Values are only changed through update and get_mut, which record the previous value.
+///
+/// # Example
+///
+///```rust
+/// use gen_arena::*;
+///
+/// let mut doc = Journal::new();
+///
+/// let title = doc.insert(String::from("Title"));
+///
+/// doc.begin();
+/// let body = doc.insert(String::from("Body"));
+/// doc.update(title, |t| t.push('!'));
+/// doc.commit();
+///
+/// doc.remove(title);
+///
+/// doc.undo();
+/// assert_eq!(doc[title], "Title!");
+/// doc.undo();
+/// assert_eq!(doc[title], "Title");
+/// assert_eq!(doc.get(body), None);
+///
+/// doc.redo();
+/// assert_eq!(doc[body], "Body");
+///```
+///
+#[derive(Debug, Clone)]
+pub struct Journal<T>{
+    arena: Arena<T>,
+    undo: VecDeque<Vec<Entry<T>>>,
+    redo: Vec<Vec<Entry<T>>>,
+    open: Option<Vec<Entry<T>>>,
+    limit: usize,
+}
+
+impl<T> Journal<T>{
+    ///
+    /// Creates an empty Journal with unlimited history.
+    ///
+    pub fn new() -> Self{
+        Self::from(Arena::new())
+    }
+
+    ///
+    /// Limits the number of transactions that can be undone, dropping the oldest ones.
+    ///
+    pub fn set_history_limit(&mut self, limit: usize){
+        self.limit = limit;
+        self.enforce_limit();
+    }
+
+    fn enforce_limit(&mut self){
+        while self.undo.len() > self.limit{
+            self.undo.pop_front();
+        }
+    }
+
+    ///
+    /// Opens a transaction. Panics if one is already open.
+    ///
+    pub fn begin(&mut self){
+        assert!(self.open.is_none(), "A transaction is already open.");
+        self.open = Some(Vec::new());
+    }
+
+    ///
+    /// Closes the open transaction, making it undoable as a whole. Panics if none is open.
+    ///
+    pub fn commit(&mut self){
+        let entries = self.open.take().expect("There is no open transaction.");
+        self.push_group(entries);
+    }
+
+    fn push_group(&mut self, entries: Vec<Entry<T>>){
+        if !entries.is_empty(){
+            self.undo.push_back(entries);
+            self.enforce_limit();
+        }
+    }
+
+    fn record(&mut self, entry: Entry<T>){
+        self.redo.clear();
+        match self.open.as_mut(){
+            Some(open) => open.push(entry),
+            None => self.push_group(vec![entry]),
+        }
+    }
+
+    ///
+    /// Inserts a new element.
+    ///
+    #[must_use]
+    pub fn insert(&mut self, val: T) -> ArenaIdx<T>{
+        let push = self.arena.vacant_index() == self.arena.slots();
+        let index = self.arena.insert(val);
+        self.record(Entry::Take{slot: index.index(), gen: index.gen(), pop: push});
+        index
+    }
+
+    ///
+    /// Removes the element and returns its value if the index is still valid.
+    ///
+    pub fn remove(&mut self, index: ArenaIdx<T>) -> Option<T>
+    where T: Clone
+    {
+        let val = self.arena.remove(index)?;
+        self.record(Entry::Put{slot: index.index(), gen: index.gen(), val: val.clone(), push: false});
+        Some(val)
+    }
+
+    ///
+    /// Changes the value with f, recording the previous value.
+    /// Returns false if the index is not valid.
+    ///
+    pub fn update(&mut self, index: ArenaIdx<T>, f: impl FnOnce(&mut T)) -> bool
+    where T: Clone
+    {
+        match self.get_mut(index){
+            Some(val) => {
+                f(val);
+                true
+            }
+            None => false,
+        }
+    }
+
+    ///
+    /// Returns a mutable optional reference to the value at the index, recording the previous value.
+    ///
+    pub fn get_mut(&mut self, index: ArenaIdx<T>) -> Option<&mut T>
+    where T: Clone
+    {
+        let old = self.arena.get(index)?.clone();
+        self.record(Entry::Replace{slot: index.index(), val: old});
+        self.arena.get_mut(index)
+    }
+
+    fn replay(arena: &mut Arena<T>, group: Vec<Entry<T>>) -> Vec<Entry<T>>{
+        // Reverting in reverse order restores the free list exactly.
+        group.into_iter().rev().map(|entry| entry.apply(arena)).collect()
+    }
+
+    ///
+    /// Reverts the last transaction, committing the open one first.
+    /// Returns false if there is nothing to undo.
+    ///
+    pub fn undo(&mut self) -> bool{
+        if let Some(open) = self.open.take(){
+            self.push_group(open);
+        }
+        match self.undo.pop_back(){
+            Some(group) => {
+                let inverse = Self::replay(&mut self.arena, group);
+                self.redo.push(inverse);
+                true
+            }
+            None => false,
+        }
+    }
+
+    ///
+    /// Reapplies the last undone transaction.
+    /// Returns false if there is nothing to redo.
+    ///
+    pub fn redo(&mut self) -> bool{
+        match self.redo.pop(){
+            Some(group) => {
+                let inverse = Self::replay(&mut self.arena, group);
+                self.undo.push_back(inverse);
+                self.enforce_limit();
+                true
+            }
+            None => false,
+        }
+    }
+
+    #[inline]
+    pub fn can_undo(&self) -> bool{
+        !self.undo.is_empty() || self.open.as_ref().is_some_and(|open| !open.is_empty())
+    }
+
+    #[inline]
+    pub fn can_redo(&self) -> bool{
+        !self.redo.is_empty()
+    }
+
+    ///
+    /// Drops the undo and redo history, keeping the current state.
+    ///
+    pub fn clear_history(&mut self){
+        self.undo.clear();
+        self.redo.clear();
+        if let Some(open) = self.open.as_mut(){
+            open.clear();
+        }
+    }
+
+    ///
+    /// Returns the underlying Arena.
+    ///
+    #[inline]
+    pub fn arena(&self) -> &Arena<T>{
+        &self.arena
+    }
+
+    ///
+    /// Returns the underlying Arena, dropping the history.
+    ///
+    #[inline]
+    pub fn into_arena(self) -> Arena<T>{
+        self.arena
+    }
+
+    #[inline]
+    pub fn get(&self, index: ArenaIdx<T>) -> Option<&T>{
+        self.arena.get(index)
+    }
+
+    #[inline]
+    pub fn contains(&self, index: ArenaIdx<T>) -> bool{
+        self.get(index).is_some()
+    }
+
+    ///
+    /// Returns an iterator over the elements with their keys.
+    ///
+    #[inline]
+    pub fn iter(&self) -> Iter<'_, T>{
+        self.arena.iter()
+    }
+
+    #[inline]
+    pub fn num(&self) -> usize{
+        self.arena.num()
+    }
+}
+
+impl<T> Default for Journal<T>{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> From<Arena<T>> for Journal<T>{
+    fn from(arena: Arena<T>) -> Self {
+        Self{
+            arena,
+            undo: VecDeque::new(),
+            redo: Vec::new(),
+            open: None,
+            limit: usize::MAX,
+        }
+    }
+}
+
+impl<T> std::ops::Index<ArenaIdx<T>> for Journal<T>{
+    type Output = T;
+
+    fn index(&self, index: ArenaIdx<T>) -> &Self::Output {
+        self.get(index).expect("There is no element at this index with that generation.")
+    }
+}
+
+#[cfg(test)]
+mod test{
+    use super::*;
+
+    // The Debug output of an Arena shows every cell with its generation and the free list.
+    // The slots with their generations, the free list and the values, but not the metrics of the arena.
+    fn state<T: std::fmt::Debug>(journal: &Journal<T>) -> String{
+        format!("{}{:?}", journal.arena().debug_dump(), journal.arena().values().collect::<Vec<_>>())
+    }
+
+    #[test]
+    fn test_undo_all(){
+        let mut arena = Arena::new();
+        let pre: Vec<_> = (0..4).map(|i| arena.insert(format!("pre{}", i))).collect();
+        arena.remove(pre[1]);
+        let mut journal = Journal::from(arena);
+
+        let mut states = vec![state(&journal)];
+        let a = journal.insert(String::from("a"));
+        states.push(state(&journal));
+
+        journal.begin();
+        let b = journal.insert(String::from("b"));
+        journal.update(pre[0], |v| v.push('!'));
+        journal.remove(pre[2]);
+        let c = journal.insert(String::from("c"));
+        journal.commit();
+        states.push(state(&journal));
+        assert_eq!(c.index(), pre[2].index());
+
+        *journal.get_mut(a).unwrap() += "a";
+        states.push(state(&journal));
+
+        journal.begin();
+        journal.remove(b);
+        journal.remove(c);
+        journal.remove(pre[3]);
+        let d = journal.insert(String::from("d"));
+        journal.update(d, |v| v.push('d'));
+        let e = journal.insert(String::from("e"));
+        journal.commit();
+        states.push(state(&journal));
+
+        journal.begin();
+        journal.commit();
+        let final_state = states.last().unwrap().clone();
+
+        // Undo everything, checking every intermediate state.
+        for expected in states.iter().rev().skip(1){
+            assert!(journal.undo());
+            assert_eq!(&state(&journal), expected);
+        }
+        assert!(!journal.undo());
+        assert!(journal.contains(pre[2]) && journal.contains(pre[3]));
+        assert!(!journal.contains(a));
+
+        // Redo everything, keys stay valid.
+        while journal.redo(){}
+        assert_eq!(state(&journal), final_state);
+        assert_eq!(journal[a], "aa");
+        assert_eq!(journal[d], "dd");
+        assert_eq!(journal[e], "e");
+        assert_eq!(journal.get(b), None);
+
+        // A new mutation drops the redo history.
+        journal.undo();
+        let _ = journal.insert(String::from("f"));
+        assert!(!journal.can_redo());
+    }
+
+    #[test]
+    fn test_history_limit(){
+        let mut journal = Journal::new();
+        let keys: Vec<_> = (0..10).map(|i| journal.insert(i)).collect();
+        journal.set_history_limit(3);
+        for key in keys.iter(){
+            journal.update(*key, |v| *v += 100);
+        }
+        let mut undone = 0;
+        while journal.undo(){
+            undone += 1;
+        }
+        assert_eq!(undone, 3);
+        let values: Vec<_> = journal.iter().map(|(_, v)| *v).collect();
+        assert_eq!(values, [100, 101, 102, 103, 104, 105, 106, 7, 8, 9]);
+    }
+}
@@ -0,0 +1,253 @@
+use std::marker::PhantomData;
+
+use crate::*;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+struct Target{
+    // The generation of the old key, stale old keys are not remapped.
+    old_gen: usize,
+    index: usize,
+    generation: usize,
+}
+
+///
+/// A mapping from old keys to new keys, as produced by operations that move elements
+/// to other slots or into other arenas.
+///
+/// It stores one entry per old slot, so an old key is only remapped if its generation matches.
+/// With the serde feature remaps can be persisted next to the data they belong to.
+///
+/// # Example
+///
+///```rust
+/// use gen_arena::*;
+///
+/// let mut old = Arena::new();
+/// let a = old.insert("a");
+/// let b = old.insert("b");
+///
+/// let mut new = Arena::new();
+/// let remap: KeyRemap<&str> = old.iter().map(|(key, val)| (key, new.insert(*val))).collect();
+///
+/// assert_eq!(new[remap.remap(b).unwrap()], "b");
+///
+/// let mut keys = [a, b, ArenaIdx::dangling()];
+/// assert_eq!(remap.apply_to(&mut keys), 2);
+/// assert_eq!(new[keys[0]], "a");
+///```
+///
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(bound = ""))]
+pub struct KeyRemap<T>{
+    slots: Vec<Option<Target>>,
+    len: usize,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    _ty: PhantomData<fn() -> T>,
+}
+
+impl<T> KeyRemap<T>{
+    ///
+    /// Creates an empty KeyRemap.
+    ///
+    pub fn new() -> Self{
+        Self{
+            slots: Vec::new(),
+            len: 0,
+            _ty: PhantomData,
+        }
+    }
+
+    ///
+    /// Creates a KeyRemap mapping every key of the arena to itself.
+    ///
+    pub fn identity(arena: &Arena<T>) -> Self{
+        arena.keys().map(|key| (key, key)).collect()
+    }
+
+    ///
+    /// Maps old to new. Returns the previous target of the slot of old, if there was one.
+    ///
+    pub fn insert(&mut self, old: ArenaIdx<T>, new: ArenaIdx<T>) -> Option<ArenaIdx<T>>{
+        if old.index() >= self.slots.len(){
+            self.slots.resize(old.index() + 1, None);
+        }
+        let prev = self.slots[old.index()].replace(Target{
+            old_gen: old.gen(),
+            index: new.index(),
+            generation: new.gen(),
+        });
+        if prev.is_none(){
+            self.len += 1;
+        }
+        prev.map(|prev| ArenaIdx::from_parts(prev.index, prev.generation))
+    }
+
+    ///
+    /// Returns the new key of old, or None if old was not remapped or is stale.
+    ///
+    #[inline]
+    pub fn remap(&self, old: ArenaIdx<T>) -> Option<ArenaIdx<T>>{
+        match self.slots.get(old.index())?{
+            Some(target) if target.old_gen == old.gen() => Some(ArenaIdx::from_parts(target.index, target.generation)),
+            _ => None,
+        }
+    }
+
+    ///
+    /// Returns an iterator over the pairs of old and new keys, ordered by the old slot.
+    ///
+    pub fn iter(&self) -> impl Iterator<Item = (ArenaIdx<T>, ArenaIdx<T>)> + '_{
+        self.slots.iter().enumerate().filter_map(|(i, target)|{
+            let target = target.as_ref()?;
+            Some((ArenaIdx::from_parts(i, target.old_gen), ArenaIdx::from_parts(target.index, target.generation)))
+        })
+    }
+
+    ///
+    /// Returns the remap applying self and then next.
+    /// Keys whose new key is not remapped by next are dropped.
+    ///
+    /// ```rust
+    /// use gen_arena::*;
+    ///
+    /// let mut arena = Arena::new();
+    /// let a = arena.insert(0);
+    /// let b = arena.insert(1);
+    /// let c = arena.insert(2);
+    ///
+    /// let first: KeyRemap<i32> = [(a, b), (b, c)].into_iter().collect();
+    /// let second: KeyRemap<i32> = [(b, a)].into_iter().collect();
+    ///
+    /// let both = first.then(&second);
+    /// assert_eq!(both.remap(a), Some(a));
+    /// assert_eq!(both.remap(b), None);
+    /// ```
+    ///
+    pub fn then(&self, next: &KeyRemap<T>) -> KeyRemap<T>{
+        self.iter().filter_map(|(old, new)| Some((old, next.remap(new)?))).collect()
+    }
+
+    ///
+    /// Replaces every remapped key in the slice with its new key and leaves the others untouched.
+    /// Returns the number of replaced keys.
+    ///
+    pub fn apply_to(&self, keys: &mut [ArenaIdx<T>]) -> usize{
+        let mut n = 0;
+        for key in keys.iter_mut(){
+            if let Some(new) = self.remap(*key){
+                *key = new;
+                n += 1;
+            }
+        }
+        n
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize{
+        self.len
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool{
+        self.len == 0
+    }
+}
+
+impl<T> Default for KeyRemap<T>{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> FromIterator<(ArenaIdx<T>, ArenaIdx<T>)> for KeyRemap<T>{
+    fn from_iter<I: IntoIterator<Item = (ArenaIdx<T>, ArenaIdx<T>)>>(iter: I) -> Self {
+        let mut remap = Self::new();
+        remap.extend(iter);
+        remap
+    }
+}
+
+impl<T> Extend<(ArenaIdx<T>, ArenaIdx<T>)> for KeyRemap<T>{
+    fn extend<I: IntoIterator<Item = (ArenaIdx<T>, ArenaIdx<T>)>>(&mut self, iter: I) {
+        for (old, new) in iter{
+            self.insert(old, new);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test{
+    use super::*;
+
+    #[test]
+    fn test_remap(){
+        let mut old = Arena::new();
+        let keys: Vec<_> = (0..10).map(|i| old.insert(i)).collect();
+        old.remove(keys[3]);
+        let reused = old.insert(30);
+        assert_eq!(reused.index(), keys[3].index());
+
+        // Move the odd elements into a fresh arena, in reverse.
+        let mut new = Arena::new();
+        let mut remap = KeyRemap::new();
+        for (key, val) in old.iter().collect::<Vec<_>>().into_iter().rev(){
+            if val % 2 == 1{
+                assert_eq!(remap.insert(key, new.insert(*val)), None);
+            }
+        }
+        assert_eq!(remap.len(), 4);
+        for (old_key, new_key) in remap.iter(){
+            assert_eq!(old[old_key], new[new_key]);
+        }
+        assert_eq!(remap.iter().map(|(k, _)| k.index()).collect::<Vec<_>>(), [1, 5, 7, 9]);
+
+        // Stale keys and keys of unmapped slots are left alone.
+        assert_eq!(remap.remap(keys[0]), None);
+        let mut held = vec![keys[1], keys[3], reused, keys[9], keys[8]];
+        let before = held.clone();
+        assert_eq!(remap.apply_to(&mut held), 2);
+        assert_eq!(new[held[0]], 1);
+        assert_eq!(new[held[3]], 9);
+        assert_eq!(held[1..3], before[1..3]);
+        assert_eq!(held[4], before[4]);
+
+        // Remapping the same slot again replaces the target.
+        let mut again = remap.clone();
+        assert_eq!(again.insert(keys[1], keys[0]), remap.remap(keys[1]));
+        assert_eq!(again.len(), remap.len());
+        assert_eq!(again.remap(keys[1]), Some(keys[0]));
+    }
+
+    #[test]
+    fn test_compose(){
+        let mut arena = Arena::new();
+        let keys: Vec<_> = (0..6).map(|i| arena.insert(i)).collect();
+        let identity = KeyRemap::identity(&arena);
+        assert_eq!(identity.len(), 6);
+
+        let shift: KeyRemap<_> = (0..5).map(|i| (keys[i], keys[i + 1])).collect();
+        assert_eq!(shift.then(&identity), shift);
+        assert_eq!(identity.then(&shift), shift);
+
+        let twice = shift.then(&shift);
+        assert_eq!(twice.len(), 4);
+        assert_eq!(twice.remap(keys[0]), Some(keys[2]));
+        assert_eq!(twice.remap(keys[4]), None);
+        assert!(KeyRemap::new().then(&shift).is_empty());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde(){
+        let mut arena = Arena::new();
+        let keys: Vec<_> = (0..4).map(|i| arena.insert(i)).collect();
+        arena.remove(keys[1]);
+        let remap: KeyRemap<i32> = [(keys[0], keys[3]), (keys[2], arena.insert(9))].into_iter().collect();
+
+        let json = serde_json::to_string(&remap).unwrap();
+        let loaded: KeyRemap<i32> = serde_json::from_str(&json).unwrap();
+        assert_eq!(loaded, remap);
+        assert_eq!(loaded.remap(keys[2]), remap.remap(keys[2]));
+    }
+}
@@ -0,0 +1,96 @@
+use std::{borrow::Cow, collections::HashMap};
+
+use crate::*;
+
+impl<T, G: GenCounter, Tag> Arena<T, G, Tag>{
+    ///
+    /// Attaches a label to the element for debugging, shown by debug_dump, Debug and the panic
+    /// of Index with a stale key to its slot. The label is dropped when the element is removed.
+    /// Returns false and does nothing if the index is not valid.
+    ///
+    /// The labels are only allocated once the first one is set.
+    ///
+    /// ```rust
+    /// use gen_arena::*;
+    ///
+    /// let mut arena = Arena::new();
+    /// let flash = arena.insert(0.5);
+    ///
+    /// assert!(arena.set_label(flash, "player_weapon_muzzle_flash"));
+    /// assert_eq!(arena.label(flash), Some("player_weapon_muzzle_flash"));
+    ///
+    /// arena.remove(flash);
+    /// assert!(!arena.set_label(flash, "stale"));
+    /// assert_eq!(arena.label(flash), None);
+    /// ```
+    ///
+    pub fn set_label(&mut self, index: ArenaIdx<T, G, Tag>, label: impl Into<Cow<'static, str>>) -> bool{
+        if self.get(index).is_none(){
+            return false;
+        }
+        self.labels.insert(index.index(), (index.gen(), label.into()));
+        true
+    }
+
+    ///
+    /// Returns the label of the element, see set_label.
+    ///
+    #[inline]
+    pub fn label(&self, index: ArenaIdx<T, G, Tag>) -> Option<&str>{
+        match self.labels.get(&index.index()){
+            Some((generation, label)) if *generation == index.gen() && self.get(index).is_some() => Some(label),
+            _ => None,
+        }
+    }
+
+    // Returns the label of the element currently in the slot.
+    pub(crate) fn slot_label(&self, slot: usize) -> Option<&str>{
+        self.label(self.idx_at(slot)?)
+    }
+}
+
+///
+/// The labels of the elements of an Arena by slot, with the generation they were set for.
+///
+pub(crate) type Labels<G> = HashMap<usize, (G, Cow<'static, str>)>;
+
+#[cfg(test)]
+mod test{
+    use super::*;
+
+    #[test]
+    fn test_labels(){
+        let mut arena = Arena::new();
+        let a = arena.insert("a");
+        let b = arena.insert("b");
+        assert!(arena.set_label(a, "first"));
+        assert!(arena.set_label(b, String::from("second")));
+        assert!(arena.set_label(b, "renamed"));
+        assert_eq!((arena.label(a), arena.label(b)), (Some("first"), Some("renamed")));
+        assert_eq!(arena.debug_dump(), "free list empty\n#0 gen=0 LIVE \"first\"\n#1 gen=0 LIVE \"renamed\"\n");
+
+        // Labels do not survive the reuse of their slot.
+        arena.remove(a);
+        let c = arena.insert("c");
+        assert_eq!(c.index(), a.index());
+        assert_eq!((arena.label(a), arena.label(c)), (None, None));
+        assert!(!arena.labels.contains_key(&a.index()));
+
+        arena.clear();
+        let d = [arena.insert("d"), arena.insert("d")];
+        assert!(d.iter().any(|d| d.index() == b.index()));
+        assert_eq!(d.map(|d| arena.label(d)), [None, None]);
+        assert!(arena.labels.is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "the slot holds \"replacement\"")]
+    fn test_index_panic(){
+        let mut arena = Arena::new();
+        let stale = arena.insert(0);
+        arena.remove(stale);
+        let key = arena.insert(1);
+        arena.set_label(key, "replacement");
+        let _ = arena[stale];
+    }
+}
@@ -0,0 +1,272 @@
+use std::{backtrace::{Backtrace, BacktraceStatus}, borrow::Cow, collections::HashMap, fmt, sync::Arc};
+
+use crate::*;
+
+enum LeakSite{
+    Backtrace(Backtrace),
+    Label(Cow<'static, str>),
+}
+
+impl fmt::Display for LeakSite{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self{
+            Self::Backtrace(backtrace) if backtrace.status() == BacktraceStatus::Captured => write!(f, "\n{}", backtrace),
+            Self::Backtrace(_) => write!(f, "an unknown site, set RUST_LIB_BACKTRACE=1 to capture it"),
+            Self::Label(label) => write!(f, "{}", label),
+        }
+    }
+}
+
+///
+/// The insertion sites of the elements of an Arena, recorded with the leak-detect feature.
+///
+#[derive(Default, Clone)]
+pub(crate) struct LeakTracker{
+    // Clones of the arena share the sites of the elements they cloned.
+    sites: HashMap<usize, Arc<LeakSite>>,
+    handler: Option<fn(&LeakReport)>,
+    // Shared by the clones, counts the live copies of the arena.
+    copies: Arc<()>,
+}
+
+impl LeakTracker{
+    #[inline]
+    pub(crate) fn record(&mut self, index: usize){
+        self.sites.insert(index, Arc::new(LeakSite::Backtrace(Backtrace::capture())));
+    }
+
+    #[inline]
+    pub(crate) fn forget(&mut self, index: usize){
+        self.sites.remove(&index);
+    }
+
+    #[inline]
+    pub(crate) fn reset(&mut self){
+        self.sites.clear();
+    }
+
+    fn report(&self, live: impl Iterator<Item = usize>) -> LeakReport{
+        LeakReport{
+            leaked: live.map(|slot| LeakedEntry{
+                slot,
+                site: self.sites.get(&slot).map(|site| site.to_string()),
+            }).collect(),
+        }
+    }
+
+    // Reports the elements left when the last copy of the arena is dropped.
+    // Dropping a copy while another one lives on, like a CowArena snapshot or an old
+    // RcuArena version, reports nothing.
+    pub(crate) fn report_dropped(&self, live: impl Iterator<Item = usize>){
+        // Leaks are expected while unwinding, and a panicking handler would abort.
+        if std::thread::panicking() || Arc::strong_count(&self.copies) > 1{
+            return;
+        }
+        let report = self.report(live);
+        if report.leaked.is_empty(){
+            return;
+        }
+        match self.handler{
+            Some(handler) => handler(&report),
+            None => eprintln!("{}", report),
+        }
+    }
+}
+
+impl fmt::Debug for LeakTracker{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LeakTracker").field("tracked", &self.sites.len()).finish()
+    }
+}
+
+///
+/// An element that was still in an Arena, see LeakReport.
+///
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LeakedEntry{
+    pub slot: usize,
+    /// The label or backtrace of the insertion, None if the element was not inserted by
+    /// the arena, for example by from_sparse_vec.
+    pub site: Option<String>,
+}
+
+///
+/// The elements left in an Arena, reported when a non-empty Arena is dropped with the leak-detect feature.
+/// Clones of an arena count as copies of it, only the last copy to be dropped reports.
+///
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LeakReport{
+    pub leaked: Vec<LeakedEntry>,
+}
+
+impl fmt::Display for LeakReport{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} elements of an Arena were not removed:", self.leaked.len())?;
+        for entry in self.leaked.iter(){
+            match &entry.site{
+                Some(site) => write!(f, "\nslot {} inserted at {}", entry.slot, site)?,
+                None => write!(f, "\nslot {} inserted at an unknown site", entry.slot)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<T, G: GenCounter, Tag> Arena<T, G, Tag>{
+    ///
+    /// Inserts a new element and records the label as its insertion site instead of a backtrace.
+    ///
+    /// ```rust
+    /// use gen_arena::*;
+    ///
+    /// let mut arena = Arena::new();
+    /// let _ = arena.insert_labeled("particle", "spawn_particles");
+    ///
+    /// let report = arena.leak_report();
+    /// assert_eq!(report.leaked[0].site.as_deref(), Some("spawn_particles"));
+    /// ```
+    ///
+    #[must_use]
+    pub fn insert_labeled(&mut self, val: T, label: impl Into<Cow<'static, str>>) -> ArenaIdx<T, G, Tag>{
+        let index = match self.raw_mut().try_insert(val){
+            Ok((index, generation)) => ArenaIdx::from_parts(index, generation),
+            Err(_val) => panic!("Insertion not successfull."),
+        };
+        self.leaks.sites.insert(index.index(), Arc::new(LeakSite::Label(label.into())));
+        #[cfg(feature = "metrics")]
+        self.metrics.inserted(1, self.num(), self.slots());
+        index
+    }
+
+    ///
+    /// Returns the elements currently in the arena together with their insertion sites.
+    ///
+    pub fn leak_report(&self) -> LeakReport{
+        self.leaks.report(self.iter().map(|(index, _)| index.index()))
+    }
+
+    ///
+    /// Replaces printing the LeakReport to stderr when the arena is dropped with the handler,
+    /// which may panic to fail a test. It is not called while the thread is panicking.
+    ///
+    pub fn set_leak_handler(&mut self, handler: fn(&LeakReport)){
+        self.leaks.handler = Some(handler);
+    }
+}
+
+#[cfg(test)]
+mod test{
+    use std::cell::RefCell;
+
+    use super::*;
+
+    thread_local!{
+        static REPORT: RefCell<Option<LeakReport>> = const{RefCell::new(None)};
+    }
+
+    fn store(report: &LeakReport){
+        REPORT.with(|stored| *stored.borrow_mut() = Some(report.clone()));
+    }
+
+    fn take() -> Option<LeakReport>{
+        REPORT.with(|stored| stored.borrow_mut().take())
+    }
+
+    #[test]
+    fn test_leak_reported(){
+        let mut arena = Arena::new();
+        arena.set_leak_handler(store);
+        let a = arena.insert(0);
+        let _ = arena.insert_labeled(1, "leaked on purpose");
+        let c = arena.insert(2);
+        arena.remove(a);
+        arena.remove(c);
+        drop(arena);
+
+        let report = take().unwrap();
+        assert_eq!(report.leaked, [LeakedEntry{slot: 1, site: Some(String::from("leaked on purpose"))}]);
+        assert_eq!(report.to_string(), "1 elements of an Arena were not removed:\nslot 1 inserted at leaked on purpose");
+    }
+
+    #[test]
+    fn test_no_leak(){
+        let mut arena = Arena::new();
+        arena.set_leak_handler(store);
+        let keys: Vec<_> = (0..4).map(|i| arena.insert(i)).collect();
+        arena.remove(keys[0]);
+        assert_eq!(arena.leak_report().leaked.len(), 3);
+
+        // Clearing resets the tracking, reused slots get new sites.
+        arena.clear();
+        assert!(arena.leak_report().leaked.is_empty());
+        let key = arena.insert_labeled(4, "reused");
+        assert_eq!(arena.leak_report().leaked, [LeakedEntry{slot: key.index(), site: Some(String::from("reused"))}]);
+        arena.remove(key);
+        drop(arena);
+        assert_eq!(take(), None);
+
+        // Elements inserted without a site are still reported.
+        let mut arena: Arena<i32> = Arena::from_sparse_vec(vec![Some(0), None, Some(1)]);
+        arena.set_leak_handler(store);
+        drop(arena);
+        assert_eq!(take().unwrap().leaked, [LeakedEntry{slot: 0, site: None}, LeakedEntry{slot: 2, site: None}]);
+    }
+
+    #[test]
+    fn test_clone_not_reported(){
+        let mut arena = Arena::new();
+        arena.set_leak_handler(store);
+        let a = arena.insert(0);
+        let b = arena.insert(1);
+
+        // The elements live on in the original.
+        drop(arena.clone());
+        assert_eq!(take(), None);
+
+        // The last copy reports what it holds.
+        let mut copy = arena.clone();
+        copy.remove(a);
+        drop(arena);
+        assert_eq!(take(), None);
+        drop(copy);
+        assert_eq!(take().unwrap().leaked.iter().map(|entry| entry.slot).collect::<Vec<_>>(), [b.index()]);
+    }
+
+    #[cfg(feature = "arc-swap")]
+    #[test]
+    fn test_rcu_update_not_reported(){
+        let mut arena = Arena::new();
+        arena.set_leak_handler(store);
+        let rcu = RcuArena::from_arena(arena);
+        let a = rcu.insert(0);
+        let _ = rcu.insert(1);
+        assert_eq!(take(), None);
+        rcu.remove(a);
+        assert_eq!(take(), None);
+        drop(rcu);
+        assert_eq!(take().unwrap().leaked.len(), 1);
+    }
+
+    #[test]
+    fn test_slice_leak_reported(){
+        let mut arena = Arena::new();
+        arena.set_leak_handler(store);
+        let _ = arena.insert(0);
+        let range = arena.insert_slice([1, 2, 3]);
+        arena.remove(range.key(1));
+        drop(arena);
+
+        let leaked = take().unwrap().leaked;
+        assert_eq!(leaked.iter().map(|entry| entry.slot).collect::<Vec<_>>(), [0, 1, 3]);
+        assert!(leaked.iter().all(|entry| entry.site.is_some()));
+
+        // Removing the whole slice forgets its elements.
+        let mut arena = Arena::new();
+        arena.set_leak_handler(store);
+        let range = arena.insert_slice([0, 1]);
+        assert_eq!(arena.leak_report().leaked.len(), 2);
+        arena.remove_slice(range);
+        drop(arena);
+        assert_eq!(take(), None);
+    }
+}
@@ -1,9 +1,153 @@
 
+pub mod anyarena;
 pub mod arena;
+pub mod arena32;
+pub mod arenacommands;
+pub mod arenadiff;
+pub mod arenalist;
+pub mod arenarc;
+pub mod arenascope;
+pub mod arenaset;
+mod arenatag;
+pub mod atomicarena;
+pub mod boxedarena;
+pub mod bufarena;
+pub mod bump;
+pub mod cachealigned;
+pub mod chunkarena;
+pub mod compat;
+pub mod concurrentarena;
+pub mod cowarena;
+mod debugdump;
+pub mod densearena;
+pub mod doublebuffered;
+pub mod dynarena;
+pub mod dynfixedarena;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+pub mod gc;
+pub mod generation;
+pub mod graph;
+pub mod idxstr;
+pub mod inserterror;
+pub mod interner;
+#[cfg(feature = "critical-section")]
+pub mod israrena;
+pub mod journal;
+pub mod keyremap;
+#[cfg(feature = "labels")]
+mod labels;
+#[cfg(feature = "leak-detect")]
+pub mod leakdetect;
+pub mod lockarena;
+pub mod lruarena;
+#[cfg(feature = "memory-usage")]
+pub mod memoryusage;
+#[cfg(feature = "metrics")]
+pub mod metrics;
+#[cfg(feature = "oplog")]
+pub mod oplog;
+pub mod orderedarena;
+mod orderlist;
+pub mod persistentarena;
+#[cfg(feature = "petgraph")]
+pub mod petgraphview;
+pub mod pool;
+pub mod rangeidx;
+#[cfg(feature = "arc-swap")]
+pub mod rcuarena;
+mod raw;
+pub mod recycler;
+pub mod registry;
+#[cfg(feature = "removal-watch")]
+pub mod removalwatch;
+pub mod reservation;
 pub mod sarena;
+pub mod slab;
+pub mod smallarena;
+pub mod soa;
+pub mod sparsemap;
+pub mod sringarena;
+pub mod strarena;
+pub mod taggedidx;
+pub mod timedarena;
+pub mod treearena;
+pub mod weakidx;
 
+pub use anyarena::*;
 pub use arena::*;
+pub use arena32::*;
+pub use arenacommands::*;
+pub use arenadiff::*;
+pub use arenalist::*;
+pub use arenarc::*;
+pub use arenascope::*;
+pub use arenaset::*;
+pub use atomicarena::*;
+pub use boxedarena::*;
+pub use bufarena::*;
+pub use bump::*;
+pub use cachealigned::*;
+pub use chunkarena::*;
+pub use concurrentarena::*;
+pub use cowarena::*;
+pub use densearena::*;
+pub use doublebuffered::*;
+pub use dynarena::*;
+pub use dynfixedarena::*;
+pub use gc::*;
+pub use generation::*;
+pub use graph::*;
+pub use idxstr::*;
+pub use inserterror::*;
+pub use interner::*;
+#[cfg(feature = "critical-section")]
+pub use israrena::*;
+pub use journal::*;
+pub use keyremap::*;
+#[cfg(feature = "leak-detect")]
+pub use leakdetect::*;
+pub use lockarena::*;
+pub use lruarena::*;
+#[cfg(feature = "memory-usage")]
+pub use memoryusage::*;
+#[cfg(feature = "metrics")]
+pub use metrics::*;
+#[cfg(feature = "oplog")]
+pub use oplog::*;
+pub use orderedarena::*;
+pub use persistentarena::*;
+#[cfg(feature = "petgraph")]
+pub use petgraphview::*;
+pub use pool::*;
+pub use rangeidx::*;
+#[cfg(feature = "arc-swap")]
+pub use rcuarena::*;
+pub use recycler::*;
+pub use registry::*;
+#[cfg(feature = "removal-watch")]
+pub use removalwatch::*;
+pub use reservation::*;
 pub use sarena::*;
+pub use slab::*;
+pub use smallarena::*;
+pub use soa::*;
+pub use sparsemap::*;
+pub use sringarena::*;
+pub use strarena::*;
+pub use taggedidx::*;
+pub use timedarena::*;
+pub use treearena::*;
+pub use weakidx::*;
+
+// The cells are private, but shared by most arenas.
+pub(crate) use raw::RawCell;
+
+#[cfg(feature = "derive")]
+pub use gen_arena_derive::ArenaSoA;
+
+// Lets code generated by the derive macros refer to ::gen_arena inside this crate.
+extern crate self as gen_arena;
 
 #[cfg(test)]
 mod tests {
@@ -0,0 +1,226 @@
+#[cfg(feature = "parking_lot")]
+use parking_lot::{RwLock, RwLockReadGuard, RwLockWriteGuard};
+#[cfg(not(feature = "parking_lot"))]
+use std::sync::{RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+use crate::*;
+
+///
+/// Guard returned by LockArena::read.
+/// Comes from parking_lot if the parking_lot feature is enabled and from std otherwise.
+///
+pub type LockReadGuard<'a, T> = RwLockReadGuard<'a, T>;
+
+///
+/// Guard returned by LockArena::write.
+/// Comes from parking_lot if the parking_lot feature is enabled and from std otherwise.
+///
+pub type LockWriteGuard<'a, T> = RwLockWriteGuard<'a, T>;
+
+///
+/// A Generational Arena with one RwLock per element.
+///
+/// Threads sharing a &LockArena can read and write different elements concurrently
+/// and only contend if they access the same element.
+/// Insertion and removal take &mut self, so the table of slots itself needs no locking.
+///
+/// # Example
+///
+///```rust
+/// use gen_arena::*;
+///
+/// let mut arena = LockArena::new();
+/// let i0 = arena.insert(0);
+/// let i1 = arena.insert(0);
+///
+/// std::thread::scope(|s|{
+///     s.spawn(|| *arena.write(i0).unwrap() += 1);
+///     s.spawn(|| *arena.write(i1).unwrap() += 2);
+/// });
+///
+/// assert_eq!(*arena.read(i0).unwrap(), 1);
+/// assert_eq!(*arena.read(i1).unwrap(), 2);
+///
+/// assert_eq!(arena.remove(i0), Some(1));
+/// assert!(arena.read(i0).is_none());
+///```
+///
+#[derive(Debug)]
+pub struct LockArena<T>{
+    arena: Arena<RwLock<T>>,
+}
+
+#[cfg(feature = "parking_lot")]
+#[inline]
+fn into_inner<T>(lock: RwLock<T>) -> T{
+    lock.into_inner()
+}
+
+#[cfg(not(feature = "parking_lot"))]
+#[inline]
+fn into_inner<T>(lock: RwLock<T>) -> T{
+    lock.into_inner().unwrap_or_else(|err| err.into_inner())
+}
+
+impl<T> LockArena<T>{
+    ///
+    /// Creates an empty LockArena.
+    ///
+    pub fn new() -> Self{
+        Self{
+            arena: Arena::new(),
+        }
+    }
+
+    #[inline]
+    fn cast(index: ArenaIdx<T>) -> ArenaIdx<RwLock<T>>{
+        ArenaIdx::from_parts(index.index(), index.gen())
+    }
+
+    ///
+    /// Tries to insert the value.
+    /// Returns val as Err if failed.
+    ///
+    pub fn try_insert(&mut self, val: T) -> Result<ArenaIdx<T>, T>{
+        self.arena.try_insert(RwLock::new(val))
+            .map(|index| ArenaIdx::from_parts(index.index(), index.gen()))
+            .map_err(|err| into_inner(err.into_value()))
+    }
+
+    ///
+    /// Inserts a new element into the arena.
+    ///
+    #[must_use]
+    pub fn insert(&mut self, val: T) -> ArenaIdx<T>{
+        self.try_insert(val).ok().expect("Insertion not successfull.")
+    }
+
+    ///
+    /// Removes the element and returns its value if the index is still valid.
+    ///
+    pub fn remove(&mut self, index: ArenaIdx<T>) -> Option<T>{
+        self.arena.remove(Self::cast(index)).map(into_inner)
+    }
+
+    ///
+    /// Locks the element for reading, blocking while it is written by another thread.
+    /// Returns None if the index is not valid.
+    ///
+    /// Panics if a thread panicked while writing the element, unless the parking_lot feature is enabled.
+    ///
+    pub fn read(&self, index: ArenaIdx<T>) -> Option<LockReadGuard<'_, T>>{
+        let lock = self.arena.get(Self::cast(index))?;
+        #[cfg(feature = "parking_lot")]
+        return Some(lock.read());
+        #[cfg(not(feature = "parking_lot"))]
+        return Some(lock.read().expect("A thread panicked while writing this element."));
+    }
+
+    ///
+    /// Locks the element for writing, blocking while it is read or written by another thread.
+    /// Returns None if the index is not valid.
+    ///
+    /// Panics if a thread panicked while writing the element, unless the parking_lot feature is enabled.
+    ///
+    pub fn write(&self, index: ArenaIdx<T>) -> Option<LockWriteGuard<'_, T>>{
+        let lock = self.arena.get(Self::cast(index))?;
+        #[cfg(feature = "parking_lot")]
+        return Some(lock.write());
+        #[cfg(not(feature = "parking_lot"))]
+        return Some(lock.write().expect("A thread panicked while writing this element."));
+    }
+
+    ///
+    /// Returns a mutable optional reference to the value at the index.
+    /// No locking is necessary since the arena is borrowed mutably.
+    ///
+    #[cfg(feature = "parking_lot")]
+    pub fn get_mut(&mut self, index: ArenaIdx<T>) -> Option<&mut T>{
+        Some(self.arena.get_mut(Self::cast(index))?.get_mut())
+    }
+
+    ///
+    /// Returns a mutable optional reference to the value at the index.
+    /// No locking is necessary since the arena is borrowed mutably.
+    ///
+    #[cfg(not(feature = "parking_lot"))]
+    pub fn get_mut(&mut self, index: ArenaIdx<T>) -> Option<&mut T>{
+        Some(self.arena.get_mut(Self::cast(index))?.get_mut().unwrap_or_else(|err| err.into_inner()))
+    }
+
+    ///
+    /// Returns true if the index refers to an element.
+    ///
+    #[inline]
+    pub fn contains(&self, index: ArenaIdx<T>) -> bool{
+        self.arena.get(Self::cast(index)).is_some()
+    }
+
+    ///
+    /// Returns the keys of all elements.
+    ///
+    pub fn keys(&self) -> impl Iterator<Item = ArenaIdx<T>> + '_{
+        self.arena.keys().map(|index| ArenaIdx::from_parts(index.index(), index.gen()))
+    }
+
+    #[inline]
+    pub fn clear(&mut self){
+        self.arena.clear()
+    }
+
+    #[inline]
+    pub fn num(&self) -> usize{
+        self.arena.num()
+    }
+}
+
+impl<T> Default for LockArena<T>{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod test{
+    use super::*;
+    use std::sync::Barrier;
+
+    #[test]
+    fn test_concurrent_readers_and_writers(){
+        const THREADS: usize = 8;
+        const ROUNDS: usize = 1000;
+
+        let mut arena = LockArena::new();
+        // One counter per thread plus one shared by all of them.
+        let own: Vec<_> = (0..THREADS).map(|_| arena.insert(0usize)).collect();
+        let shared = arena.insert(0usize);
+        let stale = arena.insert(0usize);
+        assert_eq!(arena.remove(stale), Some(0));
+
+        let barrier = Barrier::new(THREADS);
+        std::thread::scope(|s|{
+            for (t, key) in own.iter().enumerate(){
+                let (arena, barrier, own) = (&arena, &barrier, &own);
+                s.spawn(move ||{
+                    barrier.wait();
+                    for round in 0..ROUNDS{
+                        *arena.write(*key).unwrap() += 1;
+                        *arena.write(shared).unwrap() += 1;
+                        // Readers of other threads' counters only ever see complete writes.
+                        let other = own[(t + round) % THREADS];
+                        assert!(*arena.read(other).unwrap() <= ROUNDS);
+                        assert!(*arena.read(shared).unwrap() <= THREADS * ROUNDS);
+                        assert!(arena.read(stale).is_none());
+                    }
+                });
+            }
+        });
+
+        for key in own.iter(){
+            assert_eq!(*arena.read(*key).unwrap(), ROUNDS);
+        }
+        assert_eq!(*arena.get_mut(shared).unwrap(), THREADS * ROUNDS);
+        assert_eq!(arena.num(), THREADS + 1);
+        assert_eq!(arena.keys().count(), THREADS + 1);
+    }
+}
@@ -0,0 +1,307 @@
+use crate::*;
+
+#[derive(Debug)]
+struct LruEntry<T>{
+    val: T,
+    // Slot indices of the more and less recently used entries.
+    prev: Option<usize>,
+    next: Option<usize>,
+}
+
+type EvictFn<T> = Box<dyn FnMut(ArenaIdx<T>, T)>;
+
+///
+/// A Generational Arena holding at most a fixed number of elements.
+///
+/// Inserting into a full LruArena evicts the least recently used element, which is handed to
+/// the on_evict callback if one is set. Insertion, get, get_mut and touch mark an element
+/// as used. The recency order is an intrusive list through the slots, so accesses do not allocate.
+/// Keys of evicted elements are stale like keys of removed ones.
+///
+/// # Example
+///
+///```rust
+/// use gen_arena::*;
+///
+/// let mut cache = LruArena::with_capacity(2);
+///
+/// let a = cache.insert("a");
+/// let b = cache.insert("b");
+/// cache.touch(a);
+///
+/// // b is the least recently used element.
+/// let c = cache.insert("c");
+/// assert_eq!(cache.get(b), None);
+/// assert_eq!(cache.get(a), Some(&"a"));
+/// assert_eq!(cache.get(c), Some(&"c"));
+///```
+///
+pub struct LruArena<T>{
+    entries: Arena<LruEntry<T>>,
+    head: Option<usize>,
+    tail: Option<usize>,
+    capacity: usize,
+    on_evict: Option<EvictFn<T>>,
+}
+
+impl<T> LruArena<T>{
+    ///
+    /// Creates an empty LruArena holding at most capacity elements.
+    /// Panics if capacity is zero.
+    ///
+    pub fn with_capacity(capacity: usize) -> Self{
+        assert!(capacity > 0, "An LruArena needs a capacity of at least one.");
+        Self{
+            entries: Arena::with_capacity(capacity),
+            head: None,
+            tail: None,
+            capacity,
+            on_evict: None,
+        }
+    }
+
+    ///
+    /// Sets the function called with the key and value of every evicted element.
+    /// It is not called for elements removed with remove or pop_lru.
+    ///
+    pub fn set_on_evict(&mut self, f: impl FnMut(ArenaIdx<T>, T) + 'static){
+        self.on_evict = Some(Box::new(f));
+    }
+
+    #[inline]
+    fn cast(index: ArenaIdx<T>) -> ArenaIdx<LruEntry<T>>{
+        ArenaIdx::from_parts(index.index(), index.gen())
+    }
+
+    #[inline]
+    fn uncast(index: ArenaIdx<LruEntry<T>>) -> ArenaIdx<T>{
+        ArenaIdx::from_parts(index.index(), index.gen())
+    }
+
+    #[inline]
+    fn entry_mut(&mut self, slot: usize) -> &mut LruEntry<T>{
+        self.entries.get_any_mut(slot).expect("Linked entries are always valid.")
+    }
+
+    fn unlink(&mut self, slot: usize){
+        let entry = self.entry_mut(slot);
+        let (prev, next) = (entry.prev.take(), entry.next.take());
+        match prev{
+            Some(prev) => self.entry_mut(prev).next = next,
+            None => self.head = next,
+        }
+        match next{
+            Some(next) => self.entry_mut(next).prev = prev,
+            None => self.tail = prev,
+        }
+    }
+
+    fn push_front(&mut self, slot: usize){
+        let head = self.head;
+        self.entry_mut(slot).next = head;
+        match head{
+            Some(head) => self.entry_mut(head).prev = Some(slot),
+            None => self.tail = Some(slot),
+        }
+        self.head = Some(slot);
+    }
+
+    ///
+    /// Inserts a new element as the most recently used one.
+    /// Evicts the least recently used element first if the arena is full.
+    ///
+    #[must_use]
+    pub fn insert(&mut self, val: T) -> ArenaIdx<T>{
+        if self.len() == self.capacity{
+            if let Some((index, val)) = self.pop_lru(){
+                if let Some(on_evict) = self.on_evict.as_mut(){
+                    on_evict(index, val);
+                }
+            }
+        }
+        let index = self.entries.insert(LruEntry{
+            val,
+            prev: None,
+            next: None,
+        });
+        self.push_front(index.index());
+        Self::uncast(index)
+    }
+
+    ///
+    /// Removes the element and returns its value if the index is still valid.
+    ///
+    pub fn remove(&mut self, index: ArenaIdx<T>) -> Option<T>{
+        self.entries.get(Self::cast(index))?;
+        self.unlink(index.index());
+        self.entries.remove(Self::cast(index)).map(|entry| entry.val)
+    }
+
+    ///
+    /// Removes the least recently used element without calling on_evict.
+    ///
+    pub fn pop_lru(&mut self) -> Option<(ArenaIdx<T>, T)>{
+        let index = self.lru()?;
+        self.remove(index).map(|val| (index, val))
+    }
+
+    ///
+    /// Returns the key of the least recently used element.
+    ///
+    pub fn lru(&self) -> Option<ArenaIdx<T>>{
+        self.entries.idx_at(self.tail?).map(Self::uncast)
+    }
+
+    ///
+    /// Marks the element as the most recently used one.
+    /// Returns false if the index is not valid.
+    ///
+    pub fn touch(&mut self, index: ArenaIdx<T>) -> bool{
+        if !self.contains(index){
+            return false;
+        }
+        if self.head != Some(index.index()){
+            self.unlink(index.index());
+            self.push_front(index.index());
+        }
+        true
+    }
+
+    ///
+    /// Returns an optional reference to the value and marks it as the most recently used one.
+    ///
+    pub fn get(&mut self, index: ArenaIdx<T>) -> Option<&T>{
+        self.touch(index);
+        self.peek(index)
+    }
+
+    ///
+    /// Returns a mutable optional reference to the value and marks it as the most recently used one.
+    ///
+    pub fn get_mut(&mut self, index: ArenaIdx<T>) -> Option<&mut T>{
+        self.touch(index);
+        self.entries.get_mut(Self::cast(index)).map(|entry| &mut entry.val)
+    }
+
+    ///
+    /// Returns an optional reference to the value without changing the recency order.
+    ///
+    #[inline]
+    pub fn peek(&self, index: ArenaIdx<T>) -> Option<&T>{
+        self.entries.get(Self::cast(index)).map(|entry| &entry.val)
+    }
+
+    #[inline]
+    pub fn contains(&self, index: ArenaIdx<T>) -> bool{
+        self.peek(index).is_some()
+    }
+
+    ///
+    /// Returns an iterator over the elements from the most to the least recently used one.
+    ///
+    pub fn iter(&self) -> impl Iterator<Item = (ArenaIdx<T>, &T)>{
+        let mut next = self.head;
+        std::iter::from_fn(move ||{
+            let slot = next?;
+            let index = self.entries.idx_at(slot).expect("Linked entries are always valid.");
+            let entry = &self.entries[index];
+            next = entry.next;
+            Some((Self::uncast(index), &entry.val))
+        })
+    }
+
+    #[inline]
+    pub fn clear(&mut self){
+        self.entries.clear();
+        self.head = None;
+        self.tail = None;
+    }
+
+    #[inline]
+    pub fn capacity(&self) -> usize{
+        self.capacity
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize{
+        self.entries.num()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool{
+        self.len() == 0
+    }
+}
+
+impl<T: std::fmt::Debug> std::fmt::Debug for LruArena<T>{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_map().entries(self.iter().map(|(i, val)| (i.index(), val))).finish()
+    }
+}
+
+#[cfg(test)]
+mod test{
+    use super::*;
+    use std::{cell::RefCell, rc::Rc};
+
+    #[test]
+    fn test_eviction_order(){
+        let evicted = Rc::new(RefCell::new(Vec::new()));
+        let mut cache = LruArena::with_capacity(3);
+        {
+            let evicted = evicted.clone();
+            cache.set_on_evict(move |index, val| evicted.borrow_mut().push((index, val)));
+        }
+
+        let a = cache.insert('a');
+        let b = cache.insert('b');
+        let c = cache.insert('c');
+        let order = |cache: &LruArena<char>| cache.iter().map(|(_, v)| *v).collect::<String>();
+        assert_eq!(order(&cache), "cba");
+
+        // Reads, writes and touches all count as uses, peeks do not.
+        assert_eq!(cache.get(a), Some(&'a'));
+        *cache.get_mut(b).unwrap() = 'B';
+        assert_eq!(cache.peek(c), Some(&'c'));
+        assert_eq!(order(&cache), "Bac");
+        assert_eq!(cache.lru(), Some(c));
+
+        let d = cache.insert('d');
+        assert_eq!(*evicted.borrow(), [(c, 'c')]);
+        assert_eq!(cache.get(c), None);
+        assert!(!cache.touch(c));
+
+        assert!(cache.touch(a));
+        let e = cache.insert('e');
+        assert_eq!(*evicted.borrow(), [(c, 'c'), (b, 'B')]);
+        assert_eq!(order(&cache), "ead");
+
+        // Removal does not count as eviction and frees a place.
+        assert_eq!(cache.remove(d), Some('d'));
+        let f = cache.insert('f');
+        assert_eq!(evicted.borrow().len(), 2);
+        assert_eq!(order(&cache), "fea");
+
+        // The evicted slots are reused with new generations.
+        let g = cache.insert('g');
+        assert_eq!(evicted.borrow().last(), Some(&(a, 'a')));
+        assert_eq!(cache.get(a), None);
+        assert_eq!(cache.get(g), Some(&'g'));
+        assert!([b.index(), c.index(), d.index()].contains(&f.index()));
+        assert_eq!(cache.len(), 3);
+        assert_eq!(cache.pop_lru(), Some((e, 'e')));
+    }
+
+    #[test]
+    fn test_capacity_one(){
+        let mut cache = LruArena::with_capacity(1);
+        let mut last = cache.insert(0);
+        for i in 1..10{
+            let key = cache.insert(i);
+            assert_eq!(cache.get(last), None);
+            assert_eq!(cache.get(key), Some(&i));
+            last = key;
+        }
+        assert_eq!(cache.len(), 1);
+    }
+}
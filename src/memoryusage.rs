@@ -0,0 +1,219 @@
+use std::mem::size_of;
+
+use crate::*;
+
+///
+/// Reports the heap memory a value owns, for Arena::deep_size.
+///
+/// Only memory owned through the value is counted, its own size and borrowed data are not.
+/// Collections count their capacity rather than their length.
+///
+/// # Example
+///
+///```rust
+/// use gen_arena::*;
+///
+/// struct Mesh{
+///     name: String,
+///     vertices: Vec<[f32; 3]>,
+/// }
+///
+/// impl MemoryUsage for Mesh{
+///     fn heap_bytes(&self) -> usize{
+///         self.name.heap_bytes() + self.vertices.heap_bytes()
+///     }
+/// }
+///```
+///
+pub trait MemoryUsage{
+    fn heap_bytes(&self) -> usize;
+}
+
+impl<T: ?Sized> MemoryUsage for &T{
+    #[inline]
+    fn heap_bytes(&self) -> usize{
+        0
+    }
+}
+
+impl<T: MemoryUsage> MemoryUsage for Option<T>{
+    #[inline]
+    fn heap_bytes(&self) -> usize{
+        self.as_ref().map_or(0, T::heap_bytes)
+    }
+}
+
+impl<T: MemoryUsage> MemoryUsage for [T]{
+    fn heap_bytes(&self) -> usize{
+        self.iter().map(T::heap_bytes).sum()
+    }
+}
+
+impl<T: MemoryUsage, const N: usize> MemoryUsage for [T; N]{
+    #[inline]
+    fn heap_bytes(&self) -> usize{
+        self.as_slice().heap_bytes()
+    }
+}
+
+impl<T: MemoryUsage> MemoryUsage for Vec<T>{
+    #[inline]
+    fn heap_bytes(&self) -> usize{
+        self.capacity() * size_of::<T>() + self.as_slice().heap_bytes()
+    }
+}
+
+impl<T: MemoryUsage + ?Sized> MemoryUsage for Box<T>{
+    #[inline]
+    fn heap_bytes(&self) -> usize{
+        std::mem::size_of_val(&**self) + (**self).heap_bytes()
+    }
+}
+
+impl MemoryUsage for String{
+    #[inline]
+    fn heap_bytes(&self) -> usize{
+        self.capacity()
+    }
+}
+
+impl<T: ?Sized, G, Tag> MemoryUsage for ArenaIdx<T, G, Tag>{
+    #[inline]
+    fn heap_bytes(&self) -> usize{
+        0
+    }
+}
+
+impl<T: MemoryUsage, G: GenCounter, Tag> MemoryUsage for Arena<T, G, Tag>{
+    #[inline]
+    fn heap_bytes(&self) -> usize{
+        self.deep_size().total()
+    }
+}
+
+macro_rules! impl_memory_usage_leaf{
+    ($($ty:ty),*) => {
+        $(
+            impl MemoryUsage for $ty{
+                #[inline]
+                fn heap_bytes(&self) -> usize{
+                    0
+                }
+            }
+        )*
+    };
+}
+
+impl_memory_usage_leaf!((), bool, char, u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize, f32, f64, str);
+
+macro_rules! impl_memory_usage_tuple{
+    ($($name:ident),*) => {
+        impl<$($name: MemoryUsage),*> MemoryUsage for ($($name,)*){
+            #[allow(non_snake_case)]
+            fn heap_bytes(&self) -> usize{
+                let ($($name,)*) = self;
+                0 $(+ $name.heap_bytes())*
+            }
+        }
+    };
+}
+
+impl_memory_usage_tuple!(A);
+impl_memory_usage_tuple!(A, B);
+impl_memory_usage_tuple!(A, B, C);
+impl_memory_usage_tuple!(A, B, C, D);
+
+///
+/// The memory of an Arena, returned by Arena::deep_size.
+///
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DeepSize{
+    /// The size of the live elements themselves.
+    pub inline: usize,
+    /// The heap memory owned by the live elements, see MemoryUsage.
+    pub heap: usize,
+    /// The rest of the slot buffer: generations, freed slots and unused capacity.
+    pub slots_overhead: usize,
+}
+
+impl DeepSize{
+    #[inline]
+    pub fn total(&self) -> usize{
+        self.inline + self.heap + self.slots_overhead
+    }
+}
+
+impl<T: MemoryUsage, G: GenCounter, Tag> Arena<T, G, Tag>{
+    ///
+    /// Sums the memory of the arena, including the heap memory owned by its elements.
+    ///
+    /// ```rust
+    /// use gen_arena::*;
+    ///
+    /// let mut arena = Arena::with_capacity(4);
+    /// let _ = arena.insert(String::with_capacity(100));
+    ///
+    /// let size = arena.deep_size();
+    /// assert_eq!(size.inline, std::mem::size_of::<String>());
+    /// assert_eq!(size.heap, 100);
+    /// ```
+    ///
+    pub fn deep_size(&self) -> DeepSize{
+        let inline = self.num() * size_of::<T>();
+        DeepSize{
+            inline,
+            heap: self.values().map(T::heap_bytes).sum(),
+            slots_overhead: self.capacity() * size_of::<RawCell<T, G>>() - inline,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test{
+    use super::*;
+
+    struct Mesh{
+        name: String,
+        vertices: Vec<[f32; 3]>,
+        material: Option<Box<(u32, String)>>,
+    }
+
+    impl MemoryUsage for Mesh{
+        fn heap_bytes(&self) -> usize{
+            self.name.heap_bytes() + self.vertices.heap_bytes() + self.material.heap_bytes()
+        }
+    }
+
+    fn mesh(name: &str, vertices: usize, material: Option<&str>) -> Mesh{
+        let mut name_buf = String::with_capacity(16);
+        name_buf.push_str(name);
+        Mesh{
+            name: name_buf,
+            vertices: Vec::with_capacity(vertices),
+            material: material.map(|material| Box::new((0, String::from(material)))),
+        }
+    }
+
+    #[test]
+    fn test_deep_size(){
+        let mut arena = Arena::with_capacity(4);
+        let a = arena.insert(mesh("a", 10, None));
+        let _ = arena.insert(mesh("b", 2, Some("steel")));
+        let c = arena.insert(mesh("c", 0, None));
+        arena.remove(c);
+
+        let box_size = size_of::<(u32, String)>();
+        let size = arena.deep_size();
+        assert_eq!(size.inline, 2 * size_of::<Mesh>());
+        assert_eq!(size.heap, 16 + 10 * 12 + 16 + 2 * 12 + box_size + 5);
+        assert_eq!(size.slots_overhead, 4 * size_of::<RawCell<Mesh, usize>>() - 2 * size_of::<Mesh>());
+
+        // Nested arenas count the whole slot buffer of the inner arena.
+        arena.remove(a);
+        let mut outer = Arena::with_capacity(1);
+        let _ = outer.insert(arena);
+        let inner = outer.values().next().unwrap().deep_size().total();
+        assert_eq!(outer.deep_size().heap, inner);
+        assert_eq!(inner, 4 * size_of::<RawCell<Mesh, usize>>() + 16 + 2 * 12 + box_size + 5);
+    }
+}
@@ -0,0 +1,100 @@
+use crate::*;
+
+///
+/// Counters of an Arena for capacity planning, maintained with the metrics feature.
+///
+/// Bulk operations count every element they insert or remove, clear removes all elements.
+/// Undo and redo of a Journal restore earlier states and are not counted.
+///
+/// # Example
+///
+///```rust
+/// use gen_arena::*;
+///
+/// let mut arena = Arena::new();
+/// let keys: Vec<_> = (0..4).map(|i| arena.insert(i)).collect();
+/// arena.remove(keys[0]);
+/// let _ = arena.insert(4);
+///
+/// let metrics = arena.metrics();
+/// assert_eq!((metrics.inserts, metrics.removes), (5, 1));
+/// assert_eq!((metrics.peak_live, metrics.peak_slots), (4, 4));
+///```
+///
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ArenaMetrics{
+    /// The number of inserted elements.
+    pub inserts: u64,
+    /// The number of removed elements.
+    pub removes: u64,
+    /// The highest number of elements at once.
+    pub peak_live: usize,
+    /// The highest number of slots, including freed ones.
+    pub peak_slots: usize,
+}
+
+impl ArenaMetrics{
+    #[inline]
+    pub(crate) fn inserted(&mut self, count: usize, live: usize, slots: usize){
+        self.inserts += count as u64;
+        self.peak_live = self.peak_live.max(live);
+        self.peak_slots = self.peak_slots.max(slots);
+    }
+
+    #[inline]
+    pub(crate) fn removed(&mut self, count: usize){
+        self.removes += count as u64;
+    }
+}
+
+impl<T, G: GenCounter, Tag> Arena<T, G, Tag>{
+    ///
+    /// Returns the counters since the arena was created or reset_metrics was called.
+    ///
+    #[inline]
+    pub fn metrics(&self) -> ArenaMetrics{
+        self.metrics
+    }
+
+    ///
+    /// Sets the counters to zero and the peaks to the current number of elements and slots.
+    ///
+    pub fn reset_metrics(&mut self){
+        self.metrics = ArenaMetrics{
+            peak_live: self.num(),
+            peak_slots: self.slots(),
+            ..Default::default()
+        };
+    }
+}
+
+#[cfg(test)]
+mod test{
+    use super::*;
+
+    #[test]
+    fn test_workload(){
+        let mut arena = Arena::new();
+        let keys: Vec<_> = (0..10).map(|i| arena.insert(i)).collect();
+        for key in keys.iter().step_by(2){
+            arena.remove(*key);
+        }
+        // Stale removals are not counted.
+        assert_eq!(arena.remove(keys[0]), None);
+        for i in 0..3{
+            let _ = arena.try_insert(i);
+        }
+        assert_eq!(arena.metrics(), ArenaMetrics{inserts: 13, removes: 5, peak_live: 10, peak_slots: 10});
+
+        // Bulk operations count every element.
+        let range = arena.insert_slice([0, 1, 2, 3]);
+        assert_eq!(arena.metrics(), ArenaMetrics{inserts: 17, removes: 5, peak_live: 12, peak_slots: 14});
+        arena.remove_slice(range);
+        arena.clear();
+        assert_eq!(arena.metrics(), ArenaMetrics{inserts: 17, removes: 17, peak_live: 12, peak_slots: 14});
+
+        let _ = arena.insert(0);
+        arena.reset_metrics();
+        assert_eq!(arena.metrics(), ArenaMetrics{inserts: 0, removes: 0, peak_live: 1, peak_slots: 14});
+    }
+}
@@ -0,0 +1,399 @@
+use std::fmt;
+
+use crate::*;
+
+///
+/// A structural mutation of an Arena, as recorded by a LoggedArena.
+///
+/// Slots and generations are the ones of the recording arena, so a replica applying the ops with
+/// Arena::apply_ops ends up with the same keys. With the serde feature ops can be serialized.
+///
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ArenaOp<T>{
+    Insert{
+        slot: usize,
+        gen: usize,
+        val: T,
+    },
+    Remove{
+        slot: usize,
+        gen: usize,
+    },
+    Set{
+        slot: usize,
+        gen: usize,
+        val: T,
+    },
+    Clear,
+}
+
+///
+/// The reason applying an ArenaOp failed because the replica diverged from the recording arena.
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum ApplyError{
+    /// An insertion targets a slot that holds an element.
+    SlotOccupied{
+        slot: usize,
+    },
+    /// A removal or update targets a slot that holds no element.
+    SlotVacant{
+        slot: usize,
+    },
+    /// An insertion targets a slot other than the one the replica would use next.
+    SlotMismatch{
+        expected: usize,
+        found: usize,
+    },
+    /// The element in the slot, or the one inserted into it, has another generation.
+    GenerationMismatch{
+        slot: usize,
+        expected: usize,
+        found: usize,
+    },
+}
+
+impl fmt::Display for ApplyError{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self{
+            Self::SlotOccupied{slot} => write!(f, "slot {} is already occupied", slot),
+            Self::SlotVacant{slot} => write!(f, "slot {} is vacant", slot),
+            Self::SlotMismatch{expected, found} => write!(f, "expected to insert into slot {} but the next vacant slot is {}", expected, found),
+            Self::GenerationMismatch{slot, expected, found} => write!(f, "expected generation {} in slot {} but found {}", expected, slot, found),
+        }
+    }
+}
+
+impl std::error::Error for ApplyError{}
+
+impl<T> Arena<T>{
+    ///
+    /// Replays ops recorded by a LoggedArena.
+    ///
+    /// The arena has to be in the state the recording arena was in when the ops were recorded,
+    /// for example a replica that received all earlier ops. Stops at the first op that does not
+    /// match the state of the arena and returns why, the ops before it stay applied.
+    ///
+    pub fn apply_ops(&mut self, ops: impl IntoIterator<Item = ArenaOp<T>>) -> Result<(), ApplyError>{
+        for op in ops{
+            match op{
+                ArenaOp::Insert{slot, gen, val} => {
+                    if self.idx_at(slot).is_some(){
+                        return Err(ApplyError::SlotOccupied{slot});
+                    }
+                    let vacant = self.vacant_index();
+                    if vacant != slot{
+                        return Err(ApplyError::SlotMismatch{expected: slot, found: vacant});
+                    }
+                    // A new slot starts with the first generation, a freed one keeps its generation.
+                    let found = self.generation_at(slot).unwrap_or(0);
+                    if found != gen{
+                        return Err(ApplyError::GenerationMismatch{slot, expected: gen, found});
+                    }
+                    let _ = self.insert(val);
+                }
+                ArenaOp::Remove{slot, gen} => {
+                    let index = self.checked_idx(slot, gen)?;
+                    self.remove(index);
+                }
+                ArenaOp::Set{slot, gen, val} => {
+                    let index = self.checked_idx(slot, gen)?;
+                    self[index] = val;
+                }
+                ArenaOp::Clear => self.clear(),
+            }
+        }
+        Ok(())
+    }
+
+    fn checked_idx(&self, slot: usize, gen: usize) -> Result<ArenaIdx<T>, ApplyError>{
+        let index = self.idx_at(slot).ok_or(ApplyError::SlotVacant{slot})?;
+        if index.gen() != gen{
+            return Err(ApplyError::GenerationMismatch{slot, expected: gen, found: index.gen()});
+        }
+        Ok(index)
+    }
+}
+
+///
+/// An Arena recording every structural mutation as an ArenaOp.
+///
+/// Values are only changed through set, so every change is recorded. The ops are collected with
+/// take_ops and replayed on a replica with Arena::apply_ops.
+///
+/// # Example
+///
+///```rust
+/// use gen_arena::*;
+///
+/// let mut server = LoggedArena::new();
+/// let mut client = Arena::new();
+///
+/// let a = server.insert("a");
+/// let b = server.insert("b");
+/// server.remove(a);
+/// server.set(b, "B").unwrap();
+///
+/// client.apply_ops(server.take_ops()).unwrap();
+///
+/// assert_eq!(client.get(a), None);
+/// assert_eq!(client[b], "B");
+/// assert!(server.take_ops().is_empty());
+///```
+///
+#[derive(Debug, Clone)]
+pub struct LoggedArena<T>{
+    arena: Arena<T>,
+    ops: Vec<ArenaOp<T>>,
+}
+
+impl<T: Clone> LoggedArena<T>{
+    ///
+    /// Creates an empty LoggedArena.
+    ///
+    pub fn new() -> Self{
+        Self{
+            arena: Arena::new(),
+            ops: Vec::new(),
+        }
+    }
+
+    ///
+    /// Inserts a new element and records it.
+    ///
+    #[must_use]
+    pub fn insert(&mut self, val: T) -> ArenaIdx<T>{
+        let index = self.arena.insert(val.clone());
+        self.ops.push(ArenaOp::Insert{
+            slot: index.index(),
+            gen: index.gen(),
+            val,
+        });
+        index
+    }
+
+    ///
+    /// Removes the element and returns its value if the index is still valid.
+    /// Only successful removals are recorded.
+    ///
+    pub fn remove(&mut self, index: ArenaIdx<T>) -> Option<T>{
+        let val = self.arena.remove(index)?;
+        self.ops.push(ArenaOp::Remove{
+            slot: index.index(),
+            gen: index.gen(),
+        });
+        Some(val)
+    }
+
+    ///
+    /// Replaces the value at the index and records it.
+    /// Returns the old value, or val as Err if the index is not valid.
+    ///
+    pub fn set(&mut self, index: ArenaIdx<T>, val: T) -> Result<T, T>{
+        match self.arena.get_mut(index){
+            Some(old) => {
+                let old = std::mem::replace(old, val.clone());
+                self.ops.push(ArenaOp::Set{
+                    slot: index.index(),
+                    gen: index.gen(),
+                    val,
+                });
+                Ok(old)
+            }
+            None => Err(val),
+        }
+    }
+
+    ///
+    /// Removes all elements and records it.
+    ///
+    pub fn clear(&mut self){
+        self.arena.clear();
+        self.ops.push(ArenaOp::Clear);
+    }
+
+    ///
+    /// Returns the ops recorded since the last call.
+    ///
+    pub fn take_ops(&mut self) -> Vec<ArenaOp<T>>{
+        std::mem::take(&mut self.ops)
+    }
+}
+
+impl<T: Clone> Default for LoggedArena<T>{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> LoggedArena<T>{
+    ///
+    /// Returns the recording arena.
+    ///
+    #[inline]
+    pub fn arena(&self) -> &Arena<T>{
+        &self.arena
+    }
+
+    #[inline]
+    pub fn get(&self, index: ArenaIdx<T>) -> Option<&T>{
+        self.arena.get(index)
+    }
+
+    #[inline]
+    pub fn contains(&self, index: ArenaIdx<T>) -> bool{
+        self.get(index).is_some()
+    }
+
+    ///
+    /// Returns an iterator over the elements with their keys.
+    ///
+    #[inline]
+    pub fn iter(&self) -> Iter<'_, T>{
+        self.arena.iter()
+    }
+
+    #[inline]
+    pub fn num(&self) -> usize{
+        self.arena.num()
+    }
+}
+
+impl<T> std::ops::Index<ArenaIdx<T>> for LoggedArena<T>{
+    type Output = T;
+
+    fn index(&self, index: ArenaIdx<T>) -> &Self::Output {
+        self.get(index).expect("There is no element at this index with that generation.")
+    }
+}
+
+#[cfg(test)]
+mod test{
+    use super::*;
+
+    struct Rng(u64);
+
+    impl Rng{
+        fn next(&mut self) -> u64{
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            self.0
+        }
+    }
+
+    fn snapshot<T: Clone>(arena: &Arena<T>) -> Vec<(usize, usize, T)>{
+        arena.iter().map(|(i, val)| (i.index(), i.gen(), val.clone())).collect()
+    }
+
+    #[test]
+    fn test_replication(){
+        let mut rng = Rng(0x2545f4914f6cdd1d);
+        let mut server = LoggedArena::new();
+        let mut client = Arena::new();
+        let mut keys = Vec::new();
+        let mut stale = Vec::new();
+
+        for round in 0..500{
+            for _ in 0..rng.next() % 8{
+                match rng.next() % 10{
+                    0..=3 => keys.push(server.insert(rng.next() as u32)),
+                    4..=6 if !keys.is_empty() => {
+                        let key = keys.swap_remove(rng.next() as usize % keys.len());
+                        assert!(server.remove(key).is_some());
+                        stale.push(key);
+                    }
+                    7 | 8 if !keys.is_empty() => {
+                        let key = keys[rng.next() as usize % keys.len()];
+                        assert!(server.set(key, rng.next() as u32).is_ok());
+                    }
+                    9 if round % 100 == 99 => {
+                        server.clear();
+                        stale.append(&mut keys);
+                    }
+                    _ if !stale.is_empty() => {
+                        // Mutations through stale keys fail and are not recorded.
+                        let key = stale[rng.next() as usize % stale.len()];
+                        let ops = server.take_ops();
+                        assert_eq!(server.remove(key), None);
+                        assert_eq!(server.set(key, 0), Err(0));
+                        assert!(server.take_ops().is_empty());
+                        client.apply_ops(ops).unwrap();
+                    }
+                    _ => {}
+                }
+            }
+            client.apply_ops(server.take_ops()).unwrap();
+            assert_eq!(snapshot(&client), snapshot(server.arena()));
+            assert_eq!(client.vacant_index(), server.arena().vacant_index());
+        }
+        assert!(server.num() > 0);
+    }
+
+    #[test]
+    fn test_divergence(){
+        let mut server = LoggedArena::new();
+        let a = server.insert(0);
+        let b = server.insert(1);
+        let ops = server.take_ops();
+
+        let mut client = Arena::new();
+        client.apply_ops(ops.clone()).unwrap();
+        assert_eq!(client.apply_ops(ops.clone()), Err(ApplyError::SlotOccupied{slot: 0}));
+
+        server.remove(a);
+        let remove_a = server.take_ops();
+        client.apply_ops(remove_a.clone()).unwrap();
+        assert_eq!(client.apply_ops(remove_a), Err(ApplyError::SlotVacant{slot: 0}));
+
+        // The client reused the slot behind the server's back.
+        let local = client.insert(10);
+        server.set(b, 11).unwrap();
+        let c = server.insert(12);
+        let ops = server.take_ops();
+        assert_eq!(c.index(), local.index());
+        assert_eq!(client.apply_ops(ops), Err(ApplyError::SlotOccupied{slot: 0}));
+        // The op before the failing one stays applied.
+        assert_eq!(client[b], 11);
+
+        let mut client = Arena::new();
+        let _ = client.insert(0);
+        assert_eq!(client.apply_ops([ArenaOp::Set{slot: 0, gen: 1, val: 0}]), Err(ApplyError::GenerationMismatch{slot: 0, expected: 1, found: 0}));
+        assert_eq!(client.apply_ops([ArenaOp::Insert{slot: 2, gen: 0, val: 0}]), Err(ApplyError::SlotMismatch{expected: 2, found: 1}));
+        assert_eq!(client.apply_ops([ArenaOp::Insert{slot: 1, gen: 3, val: 0}]), Err(ApplyError::GenerationMismatch{slot: 1, expected: 3, found: 0}));
+        assert_eq!(client.num(), 1);
+
+        // A rejected insertion leaves the arena as it was.
+        let mut client = Arena::new();
+        let before = client.debug_dump();
+        assert_eq!(client.apply_ops([ArenaOp::Insert{slot: 0, gen: 5, val: 1}]), Err(ApplyError::GenerationMismatch{slot: 0, expected: 5, found: 0}));
+        assert_eq!(client.debug_dump(), before);
+
+        let a = client.insert(0);
+        client.remove(a);
+        let before = client.debug_dump();
+        assert_eq!(client.apply_ops([ArenaOp::Insert{slot: 0, gen: 0, val: 1}]), Err(ApplyError::GenerationMismatch{slot: 0, expected: 0, found: 1}));
+        assert_eq!(client.debug_dump(), before);
+        assert_eq!(client.insert(2), ArenaIdx::from_raw_parts(0, 1));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde(){
+        let mut server = LoggedArena::new();
+        let a = server.insert(String::from("a"));
+        server.set(a, String::from("b")).unwrap();
+        server.clear();
+        let ops = server.take_ops();
+
+        let json = serde_json::to_string(&ops).unwrap();
+        let received: Vec<ArenaOp<String>> = serde_json::from_str(&json).unwrap();
+        assert_eq!(received, ops);
+
+        let mut client = Arena::new();
+        client.apply_ops(received).unwrap();
+        assert_eq!(snapshot(&client), snapshot(server.arena()));
+    }
+}
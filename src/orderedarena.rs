@@ -0,0 +1,197 @@
+use std::ops::{Index, IndexMut};
+
+use crate::*;
+use crate::orderlist::*;
+
+///
+/// A Generational Arena that remembers the order in which its elements were inserted.
+///
+/// The insertion order is an intrusive list through the slots, so pop removes the most recently
+/// inserted element in O(1) and the arena can serve as a stack whose entries have stable keys.
+/// Removing an element unlinks it, so pop and last_inserted skip removed elements and return
+/// the most recently inserted element that is still in the arena.
+///
+/// # Example
+///
+///```rust
+/// use gen_arena::*;
+///
+/// let mut undo = OrderedArena::new();
+/// let typed = undo.insert("type");
+/// let moved = undo.insert("move");
+/// let deleted = undo.insert("delete");
+///
+/// // The move was undone through its key.
+/// undo.remove(moved);
+///
+/// assert_eq!(undo.pop(), Some((deleted, "delete")));
+/// assert_eq!(undo.last_inserted(), Some(typed));
+///```
+///
+#[derive(Debug, Clone)]
+pub struct OrderedArena<T, G = usize>{
+    entries: Arena<OrderEntry<T>, G>,
+    order: OrderList,
+}
+
+impl<T> OrderedArena<T>{
+    ///
+    /// Creates an empty OrderedArena.
+    ///
+    pub fn new() -> Self{
+        Self::default()
+    }
+}
+
+impl<T, G: GenCounter> OrderedArena<T, G>{
+    ///
+    /// Tries to insert a new element as the most recent one.
+    /// Returns val as Err if failed.
+    ///
+    pub fn try_insert(&mut self, val: T) -> Result<ArenaIdx<T, G>, T>{
+        let index = self.entries.try_insert(OrderEntry::new(val)).map_err(|err| err.into_value().val)?;
+        self.order.push_newest(&mut self.entries, index.index());
+        Ok(uncast(index))
+    }
+
+    ///
+    /// Inserts a new element as the most recent one.
+    ///
+    #[must_use]
+    pub fn insert(&mut self, val: T) -> ArenaIdx<T, G>{
+        match self.try_insert(val){
+            Ok(index) => index,
+            Err(_val) => panic!("Insertion not successfull."),
+        }
+    }
+
+    ///
+    /// Removes the element and returns its value if the index is still valid.
+    ///
+    pub fn remove(&mut self, index: ArenaIdx<T, G>) -> Option<T>{
+        self.entries.get(cast(index))?;
+        self.order.unlink(&mut self.entries, index.index());
+        self.entries.remove(cast(index)).map(|entry| entry.val)
+    }
+
+    ///
+    /// Removes the most recently inserted element that is still in the arena.
+    ///
+    pub fn pop(&mut self) -> Option<(ArenaIdx<T, G>, T)>{
+        let index = self.last_inserted()?;
+        self.remove(index).map(|val| (index, val))
+    }
+
+    ///
+    /// Returns the key of the most recently inserted element that is still in the arena.
+    ///
+    pub fn last_inserted(&self) -> Option<ArenaIdx<T, G>>{
+        self.entries.idx_at(self.order.newest()?).map(uncast)
+    }
+
+    ///
+    /// Returns the key of the least recently inserted element that is still in the arena.
+    ///
+    pub fn first_inserted(&self) -> Option<ArenaIdx<T, G>>{
+        self.entries.idx_at(self.order.oldest()?).map(uncast)
+    }
+
+    #[inline]
+    pub fn get(&self, index: ArenaIdx<T, G>) -> Option<&T>{
+        self.entries.get(cast(index)).map(|entry| &entry.val)
+    }
+
+    #[inline]
+    pub fn get_mut(&mut self, index: ArenaIdx<T, G>) -> Option<&mut T>{
+        self.entries.get_mut(cast(index)).map(|entry| &mut entry.val)
+    }
+
+    #[inline]
+    pub fn contains(&self, index: ArenaIdx<T, G>) -> bool{
+        self.get(index).is_some()
+    }
+
+    ///
+    /// Returns an iterator over the elements from the least to the most recently inserted one.
+    ///
+    pub fn iter(&self) -> impl Iterator<Item = (ArenaIdx<T, G>, &T)>{
+        self.order.slots(&self.entries).map(move |slot|{
+            let index = self.entries.idx_at(slot).expect("Linked entries are always valid.");
+            (uncast(index), &self.entries[index].val)
+        })
+    }
+
+    #[inline]
+    pub fn clear(&mut self){
+        self.entries.clear();
+        self.order.clear();
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize{
+        self.entries.num()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool{
+        self.len() == 0
+    }
+}
+
+impl<T, G: GenCounter> Default for OrderedArena<T, G>{
+    fn default() -> Self {
+        Self{
+            entries: Arena::default(),
+            order: OrderList::new(),
+        }
+    }
+}
+
+impl<T, G: GenCounter> Index<ArenaIdx<T, G>> for OrderedArena<T, G>{
+    type Output = T;
+
+    fn index(&self, index: ArenaIdx<T, G>) -> &Self::Output {
+        self.get(index).expect("There is no element at this index with that generation.")
+    }
+}
+
+impl<T, G: GenCounter> IndexMut<ArenaIdx<T, G>> for OrderedArena<T, G>{
+    fn index_mut(&mut self, index: ArenaIdx<T, G>) -> &mut Self::Output {
+        self.get_mut(index).expect("There is no element at this index with that generation.")
+    }
+}
+
+#[cfg(test)]
+mod test{
+    use super::*;
+
+    #[test]
+    fn test_pop_skips_removed(){
+        let mut stack = OrderedArena::new();
+        let keys: Vec<_> = (0..5).map(|i| stack.insert(i)).collect();
+
+        // The newest element and one in the middle are already gone.
+        stack.remove(keys[4]);
+        stack.remove(keys[2]);
+        assert_eq!(stack.last_inserted(), Some(keys[3]));
+        assert_eq!(stack.pop(), Some((keys[3], 3)));
+        assert_eq!(stack.pop(), Some((keys[1], 1)));
+
+        // Reused slots are ordered by their new insertion, not by their slot.
+        let a = stack.insert(5);
+        let b = stack.insert(6);
+        assert_eq!(a.index(), keys[1].index());
+        assert_eq!(stack.iter().map(|(_, val)| *val).collect::<Vec<_>>(), [0, 5, 6]);
+        assert_eq!(stack.first_inserted(), Some(keys[0]));
+        stack.remove(b);
+        assert_eq!(stack.pop(), Some((a, 5)));
+        assert_eq!(stack.pop(), Some((keys[0], 0)));
+        assert_eq!(stack.pop(), None);
+        assert_eq!(stack.last_inserted(), None);
+        assert!(stack.is_empty());
+
+        let _ = stack.insert(7);
+        stack.clear();
+        assert_eq!((stack.pop(), stack.first_inserted()), (None, None));
+    }
+}
@@ -0,0 +1,139 @@
+use crate::*;
+
+// An element together with its neighbours in the insertion order of an OrderList.
+#[derive(Debug, Clone)]
+pub(crate) struct OrderEntry<T>{
+    pub(crate) val: T,
+    // Slot indices of the entries inserted before and after this one.
+    older: Option<usize>,
+    newer: Option<usize>,
+}
+
+impl<T> OrderEntry<T>{
+    #[inline]
+    pub(crate) fn new(val: T) -> Self{
+        Self{
+            val,
+            older: None,
+            newer: None,
+        }
+    }
+}
+
+#[inline]
+pub(crate) fn cast<T, G: GenCounter>(index: ArenaIdx<T, G>) -> ArenaIdx<OrderEntry<T>, G>{
+    ArenaIdx::from_parts(index.index(), index.gen())
+}
+
+#[inline]
+pub(crate) fn uncast<T, G: GenCounter>(index: ArenaIdx<OrderEntry<T>, G>) -> ArenaIdx<T, G>{
+    ArenaIdx::from_parts(index.index(), index.gen())
+}
+
+// The arenas holding the entries an OrderList links.
+pub(crate) trait OrderSlots<T>{
+    fn entry(&self, slot: usize) -> &OrderEntry<T>;
+    fn entry_mut(&mut self, slot: usize) -> &mut OrderEntry<T>;
+}
+
+impl<T, G: GenCounter> OrderSlots<T> for Arena<OrderEntry<T>, G>{
+    #[inline]
+    fn entry(&self, slot: usize) -> &OrderEntry<T>{
+        self.get_any(slot).expect("Linked entries are always valid.")
+    }
+
+    #[inline]
+    fn entry_mut(&mut self, slot: usize) -> &mut OrderEntry<T>{
+        self.get_any_mut(slot).expect("Linked entries are always valid.")
+    }
+}
+
+impl<T, const N: usize, G: GenCounter> OrderSlots<T> for SArena<OrderEntry<T>, N, G>{
+    #[inline]
+    fn entry(&self, slot: usize) -> &OrderEntry<T>{
+        self.get_any(slot).expect("Linked entries are always valid.")
+    }
+
+    #[inline]
+    fn entry_mut(&mut self, slot: usize) -> &mut OrderEntry<T>{
+        self.get_any_mut(slot).expect("Linked entries are always valid.")
+    }
+}
+
+///
+/// The insertion order of the elements of an arena, as an intrusive list through their slots.
+///
+/// Shared by OrderedArena and SRingArena. The list only stores the slots of the newest and
+/// oldest entries, the links between them live in the OrderEntry of every slot.
+///
+#[derive(Debug, Clone)]
+pub(crate) struct OrderList{
+    newest: Option<usize>,
+    oldest: Option<usize>,
+}
+
+impl OrderList{
+    pub(crate) const fn new() -> Self{
+        Self{
+            newest: None,
+            oldest: None,
+        }
+    }
+
+    #[inline]
+    pub(crate) fn newest(&self) -> Option<usize>{
+        self.newest
+    }
+
+    #[inline]
+    pub(crate) fn oldest(&self) -> Option<usize>{
+        self.oldest
+    }
+
+    ///
+    /// Links the entry in slot as the newest one.
+    ///
+    pub(crate) fn push_newest<T>(&mut self, slots: &mut impl OrderSlots<T>, slot: usize){
+        let newest = self.newest;
+        slots.entry_mut(slot).older = newest;
+        match newest{
+            Some(newest) => slots.entry_mut(newest).newer = Some(slot),
+            None => self.oldest = Some(slot),
+        }
+        self.newest = Some(slot);
+    }
+
+    ///
+    /// Unlinks the entry in slot, its neighbours become neighbours of each other.
+    ///
+    pub(crate) fn unlink<T>(&mut self, slots: &mut impl OrderSlots<T>, slot: usize){
+        let entry = slots.entry_mut(slot);
+        let (older, newer) = (entry.older.take(), entry.newer.take());
+        match older{
+            Some(older) => slots.entry_mut(older).newer = newer,
+            None => self.oldest = newer,
+        }
+        match newer{
+            Some(newer) => slots.entry_mut(newer).older = older,
+            None => self.newest = older,
+        }
+    }
+
+    ///
+    /// Returns the linked slots from the oldest to the newest entry.
+    ///
+    pub(crate) fn slots<'a, T: 'a, S: OrderSlots<T>>(&self, slots: &'a S) -> impl Iterator<Item = usize> + 'a{
+        let mut next = self.oldest;
+        std::iter::from_fn(move ||{
+            let slot = next?;
+            next = slots.entry(slot).newer;
+            Some(slot)
+        })
+    }
+
+    #[inline]
+    pub(crate) fn clear(&mut self){
+        self.newest = None;
+        self.oldest = None;
+    }
+}
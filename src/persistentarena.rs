@@ -0,0 +1,406 @@
+use std::sync::Arc;
+
+use crate::*;
+
+const BITS: u32 = 5;
+const WIDTH: usize = 1 << BITS;
+const MASK: usize = WIDTH - 1;
+
+type PCell<T> = RawCell<Arc<T>>;
+
+///
+/// A node of the trie of a PersistentArena.
+/// Nodes are shared between versions and copied on write.
+///
+enum Node<T>{
+    Branch(Arc<Vec<Node<T>>>),
+    Leaf(Arc<Vec<PCell<T>>>),
+}
+
+// Implemented by hand so that T does not need to implement Clone.
+impl<T> Clone for Node<T>{
+    fn clone(&self) -> Self {
+        match self{
+            Node::Branch(children) => Node::Branch(children.clone()),
+            Node::Leaf(cells) => Node::Leaf(cells.clone()),
+        }
+    }
+}
+
+impl<T> Node<T>{
+    fn cell(&self, level: u32, index: usize) -> &PCell<T>{
+        match self{
+            Node::Branch(children) => children[(index >> (level * BITS)) & MASK].cell(level - 1, index),
+            Node::Leaf(cells) => &cells[index & MASK],
+        }
+    }
+
+    ///
+    /// Returns the cell at the index, copying every shared node on the way.
+    ///
+    fn cell_mut(&mut self, level: u32, index: usize) -> &mut PCell<T>{
+        match self{
+            Node::Branch(children) => Arc::make_mut(children)[(index >> (level * BITS)) & MASK].cell_mut(level - 1, index),
+            Node::Leaf(cells) => &mut Arc::make_mut(cells)[index & MASK],
+        }
+    }
+
+    ///
+    /// Appends a cell at the index, creating missing nodes and copying shared ones on the way.
+    ///
+    fn push(&mut self, level: u32, index: usize, cell: PCell<T>){
+        match self{
+            Node::Branch(children) => {
+                let children = Arc::make_mut(children);
+                let child = (index >> (level * BITS)) & MASK;
+                if child == children.len(){
+                    children.push(if level == 1{
+                        Node::Leaf(Arc::new(Vec::with_capacity(WIDTH)))
+                    }
+                    else{
+                        Node::Branch(Arc::new(Vec::with_capacity(WIDTH)))
+                    });
+                }
+                children[child].push(level - 1, index, cell);
+            }
+            Node::Leaf(cells) => Arc::make_mut(cells).push(cell),
+        }
+    }
+}
+
+///
+/// A persistent Generational Arena.
+///
+/// Modifying operations leave the arena untouched and return a new version instead.
+/// Versions share all storage except for the path from the root of the internal trie
+/// to the modified cell, so keeping many versions only costs the differences between them.
+/// Cloning is O(1).
+///
+/// Keys stay valid across versions and resolve to the value of the version they are used with.
+///
+/// # Example
+///
+///```rust
+/// use gen_arena::*;
+///
+/// let v0 = PersistentArena::new();
+/// let (v1, i0) = v0.insert("a");
+/// let v2 = v1.set(i0, "b").unwrap();
+/// let v3 = v2.remove(i0).unwrap();
+///
+/// assert_eq!(v0.get(i0), None);
+/// assert_eq!(v1.get(i0), Some(&"a"));
+/// assert_eq!(v2.get(i0), Some(&"b"));
+/// assert_eq!(v3.get(i0), None);
+///```
+///
+pub struct PersistentArena<T>{
+    root: Node<T>,
+    depth: u32,
+    cells: usize,
+    freed: Option<usize>,
+    num: usize,
+}
+
+impl<T> Clone for PersistentArena<T>{
+    #[inline]
+    fn clone(&self) -> Self {
+        Self{
+            root: self.root.clone(),
+            depth: self.depth,
+            cells: self.cells,
+            freed: self.freed,
+            num: self.num,
+        }
+    }
+}
+
+impl<T> PersistentArena<T>{
+    ///
+    /// Creates an empty PersistentArena.
+    ///
+    pub fn new() -> Self{
+        Self{
+            root: Node::Leaf(Arc::new(Vec::new())),
+            depth: 0,
+            cells: 0,
+            freed: None,
+            num: 0,
+        }
+    }
+
+    #[inline]
+    fn cell(&self, index: usize) -> Option<&PCell<T>>{
+        if index < self.cells{
+            Some(self.root.cell(self.depth, index))
+        }
+        else{
+            None
+        }
+    }
+
+    ///
+    /// Returns a new version with the value inserted and its key.
+    ///
+    #[must_use]
+    pub fn insert(&self, val: T) -> (Self, ArenaIdx<T>){
+        let mut next = self.clone();
+        let index = next.insert_in_place(Arc::new(val));
+        (next, index)
+    }
+
+    fn insert_in_place(&mut self, val: Arc<T>) -> ArenaIdx<T>{
+        self.num += 1;
+        match self.freed{
+            Some(i) => {
+                let cell = self.root.cell_mut(self.depth, i);
+                let (next, generation) = match cell{
+                    RawCell::Freed{next, generation} => (*next, *generation),
+                    RawCell::Allocated{..} => unreachable!("The list of freed cells only contains Freed cells."),
+                };
+                *cell = RawCell::Allocated{val, generation};
+                self.freed = next;
+                ArenaIdx::from_parts(i, generation)
+            }
+            None => {
+                if self.cells == WIDTH << (self.depth * BITS){
+                    let root = std::mem::replace(&mut self.root, Node::Branch(Arc::new(Vec::with_capacity(WIDTH))));
+                    if let Node::Branch(children) = &mut self.root{
+                        Arc::make_mut(children).push(root);
+                    }
+                    self.depth += 1;
+                }
+                let i = self.cells;
+                self.root.push(self.depth, i, RawCell::Allocated{val, generation: 0});
+                self.cells += 1;
+                ArenaIdx::from_parts(i, 0)
+            }
+        }
+    }
+
+    ///
+    /// Returns a new version without the element, or None if the index is not valid.
+    ///
+    #[must_use]
+    pub fn remove(&self, index: ArenaIdx<T>) -> Option<Self>{
+        self.get(index)?;
+        let mut next = self.clone();
+        *next.root.cell_mut(next.depth, index.index()) = RawCell::Freed{
+            next: next.freed,
+            generation: index.gen() + 1,
+        };
+        next.freed = Some(index.index());
+        next.num -= 1;
+        Some(next)
+    }
+
+    ///
+    /// Returns a new version with the value at the index replaced, or None if the index is not valid.
+    ///
+    #[must_use]
+    pub fn set(&self, index: ArenaIdx<T>, val: T) -> Option<Self>{
+        self.get(index)?;
+        let mut next = self.clone();
+        *next.root.cell_mut(next.depth, index.index()) = RawCell::Allocated{
+            val: Arc::new(val),
+            generation: index.gen(),
+        };
+        Some(next)
+    }
+
+    ///
+    /// Returns an optional reference to the value at the index in this version.
+    ///
+    pub fn get(&self, index: ArenaIdx<T>) -> Option<&T>{
+        match self.cell(index.index())?{
+            RawCell::Allocated{val, generation} if *generation == index.gen() => Some(val),
+            _ => None,
+        }
+    }
+
+    ///
+    /// Returns an iterator over the elements of this version with their keys.
+    ///
+    #[inline]
+    pub fn iter(&self) -> PersistentIter<'_, T>{
+        PersistentIter{
+            arena: self,
+            index: 0,
+        }
+    }
+
+    ///
+    /// Returns true if both versions share the same storage.
+    ///
+    #[inline]
+    pub fn ptr_eq(&self, other: &Self) -> bool{
+        match (&self.root, &other.root){
+            (Node::Branch(a), Node::Branch(b)) => Arc::ptr_eq(a, b),
+            (Node::Leaf(a), Node::Leaf(b)) => Arc::ptr_eq(a, b),
+            _ => false,
+        }
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize{
+        self.num
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool{
+        self.num == 0
+    }
+}
+
+impl<T> Default for PersistentArena<T>{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> FromIterator<T> for PersistentArena<T>{
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut arena = Self::new();
+        for val in iter{
+            arena.insert_in_place(Arc::new(val));
+        }
+        arena
+    }
+}
+
+impl<T: std::fmt::Debug> std::fmt::Debug for PersistentArena<T>{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_map().entries(self.iter().map(|(i, val)| (i.index(), val))).finish()
+    }
+}
+
+pub struct PersistentIter<'i, T: 'i>{
+    arena: &'i PersistentArena<T>,
+    index: usize,
+}
+
+impl<'i, T> Iterator for PersistentIter<'i, T>{
+    type Item = (ArenaIdx<T>, &'i T);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some(cell) = self.arena.cell(self.index){
+            let i = self.index;
+            self.index += 1;
+            if let RawCell::Allocated{val, generation} = cell{
+                return Some((ArenaIdx::from_parts(i, *generation), &**val));
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod test{
+    use super::*;
+    use proptest::prelude::*;
+
+    #[derive(Debug, Clone)]
+    enum Op{
+        Insert(u32),
+        Remove(usize),
+        Set(usize, u32),
+        Checkout(usize),
+    }
+
+    fn op() -> impl Strategy<Value = Op>{
+        prop_oneof![
+            3 => any::<u32>().prop_map(Op::Insert),
+            1 => any::<usize>().prop_map(Op::Remove),
+            1 => (any::<usize>(), any::<u32>()).prop_map(|(i, v)| Op::Set(i, v)),
+            1 => any::<usize>().prop_map(Op::Checkout),
+        ]
+    }
+
+    type Model = im::HashMap<(usize, usize), u32>;
+
+    fn assert_matches(arena: &PersistentArena<u32>, model: &Model){
+        assert_eq!(arena.len(), model.len());
+        let mut count = 0;
+        for (key, val) in arena.iter(){
+            assert_eq!(model.get(&(key.index(), key.gen())), Some(val));
+            count += 1;
+        }
+        assert_eq!(count, model.len());
+    }
+
+    proptest!{
+        #[test]
+        fn test_versions_match_model(ops in proptest::collection::vec(op(), 1..400)){
+            let mut versions = vec![(PersistentArena::new(), Model::new())];
+            let mut keys: Vec<ArenaIdx<u32>> = Vec::new();
+            let mut current = 0;
+
+            for op in ops{
+                let (arena, model) = versions[current].clone();
+                let next = match op{
+                    Op::Insert(val) => {
+                        let (arena, key) = arena.insert(val);
+                        keys.push(key);
+                        Some((arena, model.update((key.index(), key.gen()), val)))
+                    }
+                    Op::Remove(i) if !keys.is_empty() => {
+                        let key = keys[i % keys.len()];
+                        let removed = arena.remove(key);
+                        prop_assert_eq!(removed.is_some(), model.contains_key(&(key.index(), key.gen())));
+                        removed.map(|arena| (arena, model.without(&(key.index(), key.gen()))))
+                    }
+                    Op::Set(i, val) if !keys.is_empty() => {
+                        let key = keys[i % keys.len()];
+                        let set = arena.set(key, val);
+                        prop_assert_eq!(set.is_some(), model.contains_key(&(key.index(), key.gen())));
+                        set.map(|arena| (arena, model.update((key.index(), key.gen()), val)))
+                    }
+                    Op::Checkout(i) => {
+                        current = i % versions.len();
+                        None
+                    }
+                    _ => None,
+                };
+                if let Some(next) = next{
+                    versions.push(next);
+                    current = versions.len() - 1;
+                }
+            }
+
+            // Every version still resolves all keys to its own values.
+            for (arena, model) in versions.iter(){
+                assert_matches(arena, model);
+                for key in keys.iter(){
+                    prop_assert_eq!(arena.get(*key), model.get(&(key.index(), key.gen())));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_structural_sharing(){
+        let v0: PersistentArena<_> = (0..10_000).collect();
+        let key = v0.iter().nth(5000).unwrap().0;
+
+        let v1 = v0.set(key, 0).unwrap();
+        let copy = v0.clone();
+        assert!(copy.ptr_eq(&v0));
+        assert!(!v1.ptr_eq(&v0));
+
+        // Only the path to the modified cell was copied.
+        let (Node::Branch(a), Node::Branch(b)) = (&v0.root, &v1.root) else{
+            panic!("10000 cells need more than one leaf.");
+        };
+        let shared = a.iter().zip(b.iter()).filter(|(a, b)|{
+            match (a, b){
+                (Node::Branch(a), Node::Branch(b)) => Arc::ptr_eq(a, b),
+                (Node::Leaf(a), Node::Leaf(b)) => Arc::ptr_eq(a, b),
+                _ => false,
+            }
+        }).count();
+        assert_eq!(shared, a.len() - 1);
+
+        assert_eq!(v0.get(key), Some(&5000));
+        assert_eq!(v1.get(key), Some(&0));
+    }
+}
@@ -0,0 +1,290 @@
+use std::{cell::OnceCell, slice};
+
+use petgraph::{visit::{Data, GraphBase, GraphProp, IntoNeighbors, IntoNeighborsDirected, IntoNodeIdentifiers, NodeCount, NodeIndexable, VisitMap, Visitable}, Directed};
+
+use crate::*;
+
+///
+/// A directed graph over the elements of an Arena for petgraph's algorithms, created by Arena::as_graph_nodes.
+///
+/// The nodes are the elements of the arena and the edges are read from the elements by the
+/// closure, which returns the keys each element links to. Keys of removed elements are skipped.
+/// Nothing is copied, except that the incoming edges are collected the first time an
+/// algorithm walks the graph backwards, as toposort and kosaraju_scc do.
+///
+/// Keys carry a generation, so they are mapped to slots through NodeIndexable and cannot be
+/// petgraph's IndexType, which is created from a plain integer.
+///
+/// # Example
+///
+///```rust
+/// use gen_arena::*;
+///
+/// struct Task{
+///     name: &'static str,
+///     after: Vec<ArenaIdx<Task>>,
+/// }
+///
+/// let mut tasks = Arena::new();
+/// let fetch = tasks.insert(Task{name: "fetch", after: vec![]});
+/// let build = tasks.insert(Task{name: "build", after: vec![]});
+/// let test = tasks.insert(Task{name: "test", after: vec![]});
+/// tasks[fetch].after.push(build);
+/// tasks[build].after.push(test);
+///
+/// let graph = tasks.as_graph_nodes(|task| task.after.iter().copied());
+/// let order = petgraph::algo::toposort(&graph, None).unwrap();
+/// assert_eq!(order.iter().map(|&task| tasks[task].name).collect::<Vec<_>>(), ["fetch", "build", "test"]);
+///```
+///
+pub struct ArenaGraphView<'a, T, G, F>{
+    arena: &'a Arena<T, G>,
+    neighbors: F,
+    // The sources of the edges into each slot.
+    incoming: OnceCell<Vec<Vec<ArenaIdx<T, G>>>>,
+}
+
+impl<T, G: GenCounter> Arena<T, G>{
+    ///
+    /// Returns a graph over the elements of the arena with an edge from every element to the
+    /// keys neighbors returns for it, see ArenaGraphView.
+    ///
+    pub fn as_graph_nodes<'a, F, I>(&'a self, neighbors: F) -> ArenaGraphView<'a, T, G, F>
+    where
+        F: Fn(&'a T) -> I,
+        I: IntoIterator<Item = ArenaIdx<T, G>>,
+    {
+        ArenaGraphView{
+            arena: self,
+            neighbors,
+            incoming: OnceCell::new(),
+        }
+    }
+}
+
+impl<'a, T, G: GenCounter, F, I> ArenaGraphView<'a, T, G, F>
+where
+    F: Fn(&'a T) -> I,
+    I: IntoIterator<Item = ArenaIdx<T, G>>,
+{
+    ///
+    /// Returns the arena the graph is over.
+    ///
+    #[inline]
+    pub fn arena(&self) -> &'a Arena<T, G>{
+        self.arena
+    }
+
+    fn outgoing(&self, index: ArenaIdx<T, G>) -> GraphViewNeighbors<'_, T, G, I::IntoIter>{
+        let targets = self.arena.get(index).map(|val| (self.neighbors)(val).into_iter());
+        GraphViewNeighbors{
+            arena: self.arena,
+            inner: Inner::Outgoing(targets),
+        }
+    }
+
+    fn incoming(&self, index: ArenaIdx<T, G>) -> GraphViewNeighbors<'_, T, G, I::IntoIter>{
+        let incoming = self.incoming.get_or_init(||{
+            let mut incoming = vec![Vec::new(); self.arena.slots()];
+            for (source, val) in self.arena.iter(){
+                for target in (self.neighbors)(val){
+                    if self.arena.get(target).is_some(){
+                        incoming[target.index()].push(source);
+                    }
+                }
+            }
+            incoming
+        });
+        let sources = match self.arena.get(index){
+            Some(_) => incoming[index.index()].iter(),
+            None => [].iter(),
+        };
+        GraphViewNeighbors{
+            arena: self.arena,
+            inner: Inner::Incoming(sources),
+        }
+    }
+}
+
+enum Inner<'v, T, G, I>{
+    Outgoing(Option<I>),
+    Incoming(slice::Iter<'v, ArenaIdx<T, G>>),
+}
+
+///
+/// The neighbors of a node of an ArenaGraphView.
+///
+pub struct GraphViewNeighbors<'v, T, G, I>{
+    arena: &'v Arena<T, G>,
+    inner: Inner<'v, T, G, I>,
+}
+
+impl<'v, T, G: GenCounter, I: Iterator<Item = ArenaIdx<T, G>>> Iterator for GraphViewNeighbors<'v, T, G, I>{
+    type Item = ArenaIdx<T, G>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match &mut self.inner{
+            Inner::Outgoing(targets) => {
+                let arena = self.arena;
+                targets.as_mut()?.find(|&target| arena.get(target).is_some())
+            }
+            Inner::Incoming(sources) => sources.next().copied(),
+        }
+    }
+}
+
+///
+/// The visited nodes of a traversal of an ArenaGraphView, by slot.
+///
+#[derive(Debug, Clone)]
+pub struct GraphViewVisitMap{
+    visited: Vec<bool>,
+}
+
+impl<T, G: GenCounter> VisitMap<ArenaIdx<T, G>> for GraphViewVisitMap{
+    fn visit(&mut self, a: ArenaIdx<T, G>) -> bool {
+        !std::mem::replace(&mut self.visited[a.index()], true)
+    }
+
+    fn is_visited(&self, a: &ArenaIdx<T, G>) -> bool {
+        self.visited[a.index()]
+    }
+
+    fn unvisit(&mut self, a: ArenaIdx<T, G>) -> bool {
+        std::mem::replace(&mut self.visited[a.index()], false)
+    }
+}
+
+impl<'a, T, G: GenCounter, F> GraphBase for ArenaGraphView<'a, T, G, F>{
+    type EdgeId = (ArenaIdx<T, G>, ArenaIdx<T, G>);
+    type NodeId = ArenaIdx<T, G>;
+}
+
+impl<'a, T, G: GenCounter, F> Data for ArenaGraphView<'a, T, G, F>{
+    type NodeWeight = T;
+    type EdgeWeight = ();
+}
+
+impl<'a, T, G: GenCounter, F> GraphProp for ArenaGraphView<'a, T, G, F>{
+    type EdgeType = Directed;
+}
+
+impl<'a, T, G: GenCounter, F> NodeCount for ArenaGraphView<'a, T, G, F>{
+    fn node_count(&self) -> usize {
+        self.arena.num()
+    }
+}
+
+impl<'a, T, G: GenCounter, F> NodeIndexable for ArenaGraphView<'a, T, G, F>{
+    fn node_bound(&self) -> usize {
+        self.arena.slots()
+    }
+
+    fn to_index(&self, a: Self::NodeId) -> usize {
+        a.index()
+    }
+
+    fn from_index(&self, i: usize) -> Self::NodeId {
+        self.arena.idx_at(i).expect("There is no element at this index.")
+    }
+}
+
+impl<'a, T, G: GenCounter, F> Visitable for ArenaGraphView<'a, T, G, F>{
+    type Map = GraphViewVisitMap;
+
+    fn visit_map(&self) -> Self::Map {
+        GraphViewVisitMap{
+            visited: vec![false; self.arena.slots()],
+        }
+    }
+
+    fn reset_map(&self, map: &mut Self::Map) {
+        map.visited.clear();
+        map.visited.resize(self.arena.slots(), false);
+    }
+}
+
+impl<'a, T, G: GenCounter, F> IntoNodeIdentifiers for &ArenaGraphView<'a, T, G, F>{
+    type NodeIdentifiers = Keys<'a, T, G>;
+
+    fn node_identifiers(self) -> Self::NodeIdentifiers {
+        self.arena.keys()
+    }
+}
+
+impl<'v, 'a, T, G: GenCounter, F, I> IntoNeighbors for &'v ArenaGraphView<'a, T, G, F>
+where
+    F: Fn(&'a T) -> I,
+    I: IntoIterator<Item = ArenaIdx<T, G>>,
+{
+    type Neighbors = GraphViewNeighbors<'v, T, G, I::IntoIter>;
+
+    fn neighbors(self, a: Self::NodeId) -> Self::Neighbors {
+        self.outgoing(a)
+    }
+}
+
+impl<'v, 'a, T, G: GenCounter, F, I> IntoNeighborsDirected for &'v ArenaGraphView<'a, T, G, F>
+where
+    F: Fn(&'a T) -> I,
+    I: IntoIterator<Item = ArenaIdx<T, G>>,
+{
+    type NeighborsDirected = GraphViewNeighbors<'v, T, G, I::IntoIter>;
+
+    fn neighbors_directed(self, n: Self::NodeId, d: petgraph::Direction) -> Self::NeighborsDirected {
+        match d{
+            petgraph::Direction::Outgoing => self.outgoing(n),
+            petgraph::Direction::Incoming => self.incoming(n),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test{
+    use super::*;
+    use petgraph::algo::{kosaraju_scc, tarjan_scc, toposort};
+
+    struct Module{
+        name: &'static str,
+        imports: Vec<ArenaIdx<Module>>,
+    }
+
+    fn names(arena: &Arena<Module>, keys: &[ArenaIdx<Module>]) -> Vec<&'static str>{
+        let mut names: Vec<_> = keys.iter().map(|&key| arena[key].name).collect();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn test_toposort(){
+        let mut modules = Arena::new();
+        let keys: Vec<_> = ["main", "net", "fs", "io", "stale"].iter()
+            .map(|&name| modules.insert(Module{name, imports: Vec::new()}))
+            .collect();
+        let (main, net, fs, io, stale) = (keys[0], keys[1], keys[2], keys[3], keys[4]);
+        modules[main].imports = vec![net, fs];
+        modules[net].imports = vec![io, stale];
+        modules[fs].imports = vec![io];
+        // Edges to removed elements are ignored.
+        modules.remove(stale);
+
+        let graph = modules.as_graph_nodes(|module| module.imports.iter().copied());
+        let order = toposort(&graph, None).unwrap();
+        assert_eq!(order.len(), 4);
+        let position = |key| order.iter().position(|&k| k == key).unwrap();
+        for (module, imported) in [(main, net), (main, fs), (net, io), (fs, io)]{
+            assert!(position(module) < position(imported));
+        }
+        assert_eq!(names(&modules, &graph.neighbors_directed(io, petgraph::Direction::Incoming).collect::<Vec<_>>()), ["fs", "net"]);
+        assert_eq!(graph.neighbors(stale).count(), 0);
+
+        // A cycle through io and main.
+        modules[io].imports.push(main);
+        let graph = modules.as_graph_nodes(|module| module.imports.iter().copied());
+        assert!(toposort(&graph, None).is_err());
+        for components in [tarjan_scc(&graph), kosaraju_scc(&graph)]{
+            assert_eq!(components.len(), 1);
+            assert_eq!(names(&modules, &components[0]), ["fs", "io", "main", "net"]);
+        }
+    }
+}
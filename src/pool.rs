@@ -0,0 +1,266 @@
+use std::ops::{Index, IndexMut};
+
+use crate::*;
+
+///
+/// Objects that can be returned to a freshly constructed state without being dropped.
+///
+pub trait Reset{
+    fn reset(&mut self);
+}
+
+impl<T> Reset for Vec<T>{
+    #[inline]
+    fn reset(&mut self){
+        self.clear()
+    }
+}
+
+impl Reset for String{
+    #[inline]
+    fn reset(&mut self){
+        self.clear()
+    }
+}
+
+///
+/// A pool of reusable objects built on an Arena.
+///
+/// Live objects are stored in an Arena and addressed by ArenaIdx.
+/// Released objects are kept idle instead of being dropped and are handed out again by acquire
+/// after Reset::reset was called on them. New objects are only constructed by the factory
+/// if no idle object is available.
+///
+/// # Example
+///
+///```rust
+/// use gen_arena::*;
+///
+/// let mut pool = Pool::new(|| Vec::<u8>::with_capacity(1024));
+///
+/// let (i0, buf) = pool.acquire();
+/// buf.extend_from_slice(b"hello");
+/// assert_eq!(pool.live_count(), 1);
+///
+/// assert!(pool.release(i0));
+/// assert_eq!(pool.idle_count(), 1);
+///
+/// // The buffer is reused and was reset.
+/// let (_, buf) = pool.acquire();
+/// assert!(buf.is_empty());
+/// assert!(buf.capacity() >= 1024);
+/// assert_eq!(pool.idle_count(), 0);
+///```
+///
+pub struct Pool<T: Reset>{
+    live: Arena<T>,
+    idle: Vec<T>,
+    factory: Box<dyn FnMut() -> T>,
+}
+
+impl<T: Reset> Pool<T>{
+    ///
+    /// Creates an empty Pool which constructs new objects with the factory.
+    ///
+    pub fn new(factory: impl FnMut() -> T + 'static) -> Self{
+        Self{
+            live: Arena::new(),
+            idle: Vec::new(),
+            factory: Box::new(factory),
+        }
+    }
+
+    ///
+    /// Constructs n idle objects up front.
+    ///
+    pub fn prefill(&mut self, n: usize){
+        self.idle.reserve(n);
+        for _ in 0..n{
+            self.idle.push((self.factory)());
+        }
+    }
+
+    ///
+    /// Returns a reset idle object, or a new one from the factory if there is none.
+    ///
+    pub fn acquire(&mut self) -> (ArenaIdx<T>, &mut T){
+        let val = match self.idle.pop(){
+            Some(mut val) => {
+                val.reset();
+                val
+            }
+            None => (self.factory)(),
+        };
+        let index = self.live.insert(val);
+        (index, self.live.get_mut(index).expect("The object was just inserted."))
+    }
+
+    ///
+    /// Returns the object to the pool without dropping it.
+    /// Returns false if the index is not valid.
+    ///
+    pub fn release(&mut self, index: ArenaIdx<T>) -> bool{
+        match self.live.remove(index){
+            Some(val) => {
+                self.idle.push(val);
+                true
+            }
+            None => false,
+        }
+    }
+
+    ///
+    /// Drops idle objects until at most n are left.
+    ///
+    pub fn shrink_idle(&mut self, n: usize){
+        self.idle.truncate(n);
+        self.idle.shrink_to_fit();
+    }
+
+    ///
+    /// Returns an optional reference to the live object at the index.
+    ///
+    #[inline]
+    pub fn get(&self, index: ArenaIdx<T>) -> Option<&T>{
+        self.live.get(index)
+    }
+
+    ///
+    /// Returns a mutable optional reference to the live object at the index.
+    ///
+    #[inline]
+    pub fn get_mut(&mut self, index: ArenaIdx<T>) -> Option<&mut T>{
+        self.live.get_mut(index)
+    }
+
+    ///
+    /// Returns an iterator over the live objects with their indices.
+    ///
+    #[inline]
+    pub fn iter(&self) -> Iter<'_, T>{
+        self.live.iter()
+    }
+
+    ///
+    /// Returns a mutable iterator over the live objects with their indices.
+    ///
+    #[inline]
+    pub fn iter_mut(&mut self) -> IterMut<'_, T>{
+        self.live.iter_mut()
+    }
+
+    ///
+    /// Returns the number of acquired objects.
+    ///
+    #[inline]
+    pub fn live_count(&self) -> usize{
+        self.live.num()
+    }
+
+    ///
+    /// Returns the number of released objects waiting to be reused.
+    ///
+    #[inline]
+    pub fn idle_count(&self) -> usize{
+        self.idle.len()
+    }
+}
+
+impl<T: Reset + std::fmt::Debug> std::fmt::Debug for Pool<T>{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Pool")
+            .field("live", &self.live)
+            .field("idle", &self.idle)
+            .finish()
+    }
+}
+
+impl<T: Reset> Index<ArenaIdx<T>> for Pool<T>{
+    type Output = T;
+
+    fn index(&self, index: ArenaIdx<T>) -> &Self::Output {
+        self.get(index).expect("There is no element at this index with that generation.")
+    }
+}
+
+impl<T: Reset> IndexMut<ArenaIdx<T>> for Pool<T>{
+    fn index_mut(&mut self, index: ArenaIdx<T>) -> &mut Self::Output {
+        self.get_mut(index).expect("There is no element at this index with that generation.")
+    }
+}
+
+#[cfg(test)]
+mod test{
+    use super::*;
+    use std::{cell::Cell, rc::Rc};
+
+    struct Decoder{
+        id: usize,
+        frames: usize,
+        resets: usize,
+    }
+
+    impl Reset for Decoder{
+        fn reset(&mut self){
+            self.frames = 0;
+            self.resets += 1;
+        }
+    }
+
+    #[test]
+    fn test_objects_are_reused(){
+        let constructed = Rc::new(Cell::new(0));
+        let mut pool = {
+            let constructed = constructed.clone();
+            Pool::new(move ||{
+                constructed.set(constructed.get() + 1);
+                Decoder{id: constructed.get(), frames: 0, resets: 0}
+            })
+        };
+
+        let keys: Vec<_> = (0..4).map(|_|{
+            let (key, decoder) = pool.acquire();
+            decoder.frames = 10;
+            key
+        }).collect();
+        assert_eq!(constructed.get(), 4);
+        assert_eq!(pool.live_count(), 4);
+
+        for key in keys.iter(){
+            assert!(pool.release(*key));
+            assert!(!pool.release(*key));
+        }
+        assert_eq!(pool.live_count(), 0);
+        assert_eq!(pool.idle_count(), 4);
+
+        // Acquiring again reuses the idle objects and constructs nothing.
+        let mut ids = Vec::new();
+        for _ in 0..4{
+            let (_, decoder) = pool.acquire();
+            assert_eq!(decoder.frames, 0);
+            assert_eq!(decoder.resets, 1);
+            ids.push(decoder.id);
+        }
+        // Released keys are stale even though their slots are reused.
+        assert!(keys.iter().all(|key| pool.get(*key).is_none()));
+        ids.sort();
+        assert_eq!(ids, vec![1, 2, 3, 4]);
+        assert_eq!(constructed.get(), 4);
+
+        let (_, decoder) = pool.acquire();
+        assert_eq!(decoder.resets, 0);
+        assert_eq!(constructed.get(), 5);
+
+        // Shrinking drops surplus idle objects.
+        let live: Vec<_> = pool.iter().map(|(key, _)| key).collect();
+        for key in live{
+            pool.release(key);
+        }
+        assert_eq!(pool.idle_count(), 5);
+        pool.shrink_idle(2);
+        assert_eq!(pool.idle_count(), 2);
+        pool.prefill(1);
+        assert_eq!(pool.idle_count(), 3);
+        assert_eq!(constructed.get(), 6);
+    }
+}
@@ -0,0 +1,348 @@
+use std::{marker::PhantomData, ops::{Index, IndexMut}};
+
+use crate::*;
+
+///
+/// A key to a run of elements inserted with Arena::insert_slice.
+///
+/// The elements occupy consecutive slots and share one generation, so every element also has
+/// its own key, see key. The range is only valid as a whole: once any of its elements is removed,
+/// get_slice, get_slice_mut and remove_slice return None, while the keys of the remaining
+/// elements keep working and are the way to remove them.
+///
+/// # Example
+///
+///```rust
+/// use gen_arena::*;
+///
+/// let mut arena = Arena::new();
+/// let range = arena.insert_slice([1, 2, 3]);
+///
+/// assert_eq!(arena.get_slice(range).unwrap().iter().sum::<i32>(), 6);
+/// assert_eq!(arena[range.key(1)], 2);
+///
+/// arena.remove(range.key(0));
+/// assert!(arena.get_slice(range).is_none());
+/// assert_eq!(arena[range.key(2)], 3);
+///```
+///
+pub struct RangeIdx<T, G = usize, Tag = ()>{
+    start: usize,
+    len: usize,
+    generation: G,
+    _ty: PhantomData<T>,
+    _tag: PhantomData<fn() -> Tag>,
+}
+
+impl<T, G: GenCounter, Tag> RangeIdx<T, G, Tag>{
+    ///
+    /// Returns the slot of the first element.
+    ///
+    #[inline]
+    pub fn start(&self) -> usize{
+        self.start
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize{
+        self.len
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool{
+        self.len == 0
+    }
+
+    #[inline]
+    pub fn gen(&self) -> G{
+        self.generation
+    }
+
+    ///
+    /// Returns the key of the i-th element of the range.
+    /// Panics if i is not smaller than the length.
+    ///
+    #[inline]
+    pub fn key(&self, i: usize) -> ArenaIdx<T, G, Tag>{
+        assert!(i < self.len, "The index {} is out of the range of length {}.", i, self.len);
+        ArenaIdx::from_parts(self.start + i, self.generation)
+    }
+
+    ///
+    /// Returns an iterator over the keys of the elements of the range.
+    ///
+    pub fn keys(&self) -> impl Iterator<Item = ArenaIdx<T, G, Tag>>{
+        let generation = self.generation;
+        (self.start..self.start + self.len).map(move |i| ArenaIdx::from_parts(i, generation))
+    }
+}
+
+impl<T, G: GenCounter, Tag> Clone for RangeIdx<T, G, Tag>{
+    #[inline]
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T, G: GenCounter, Tag> Copy for RangeIdx<T, G, Tag>{}
+
+impl<T, G: GenCounter, Tag> PartialEq for RangeIdx<T, G, Tag>{
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        self.start == other.start && self.len == other.len && self.generation == other.generation
+    }
+}
+
+impl<T, G: GenCounter, Tag> Eq for RangeIdx<T, G, Tag>{}
+
+impl<T, G: GenCounter, Tag> std::fmt::Debug for RangeIdx<T, G, Tag>{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RangeIdx")
+            .field("start", &self.start)
+            .field("len", &self.len)
+            .field("generation", &self.generation)
+            .finish()
+    }
+}
+
+#[inline]
+fn val<T, G>(cell: &RawCell<T, G>) -> &T{
+    match cell{
+        RawCell::Allocated{val, ..} => val,
+        RawCell::Freed{..} => unreachable!("Slices only hold allocated cells."),
+    }
+}
+
+#[inline]
+fn val_mut<T, G>(cell: &mut RawCell<T, G>) -> &mut T{
+    match cell{
+        RawCell::Allocated{val, ..} => val,
+        RawCell::Freed{..} => unreachable!("Slices only hold allocated cells."),
+    }
+}
+
+///
+/// The elements of a RangeIdx, returned by Arena::get_slice.
+///
+/// The elements are stored in consecutive cells next to their generations,
+/// so this is a view indexed like a slice rather than a &[T].
+///
+pub struct ArenaSlice<'a, T, G = usize>{
+    cells: &'a [RawCell<T, G>],
+}
+
+impl<'a, T, G> ArenaSlice<'a, T, G>{
+    #[inline]
+    pub fn len(&self) -> usize{
+        self.cells.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool{
+        self.cells.is_empty()
+    }
+
+    #[inline]
+    pub fn get(&self, i: usize) -> Option<&'a T>{
+        self.cells.get(i).map(val)
+    }
+
+    #[inline]
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = &'a T> + ExactSizeIterator{
+        self.cells.iter().map(val)
+    }
+}
+
+impl<'a, T, G> Index<usize> for ArenaSlice<'a, T, G>{
+    type Output = T;
+
+    fn index(&self, i: usize) -> &Self::Output {
+        val(&self.cells[i])
+    }
+}
+
+///
+/// The mutable elements of a RangeIdx, returned by Arena::get_slice_mut.
+///
+pub struct ArenaSliceMut<'a, T, G = usize>{
+    cells: &'a mut [RawCell<T, G>],
+}
+
+impl<'a, T, G> ArenaSliceMut<'a, T, G>{
+    #[inline]
+    pub fn len(&self) -> usize{
+        self.cells.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool{
+        self.cells.is_empty()
+    }
+
+    #[inline]
+    pub fn get(&self, i: usize) -> Option<&T>{
+        self.cells.get(i).map(val)
+    }
+
+    #[inline]
+    pub fn get_mut(&mut self, i: usize) -> Option<&mut T>{
+        self.cells.get_mut(i).map(val_mut)
+    }
+
+    #[inline]
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = &T> + ExactSizeIterator{
+        self.cells.iter().map(val)
+    }
+
+    #[inline]
+    pub fn iter_mut(&mut self) -> impl DoubleEndedIterator<Item = &mut T> + ExactSizeIterator{
+        self.cells.iter_mut().map(val_mut)
+    }
+}
+
+impl<'a, T, G> Index<usize> for ArenaSliceMut<'a, T, G>{
+    type Output = T;
+
+    fn index(&self, i: usize) -> &Self::Output {
+        val(&self.cells[i])
+    }
+}
+
+impl<'a, T, G> IndexMut<usize> for ArenaSliceMut<'a, T, G>{
+    fn index_mut(&mut self, i: usize) -> &mut Self::Output {
+        val_mut(&mut self.cells[i])
+    }
+}
+
+impl<T, G: GenCounter, Tag> Arena<T, G, Tag>{
+    ///
+    /// Inserts the values into consecutive slots and returns a key to all of them.
+    ///
+    /// Uses the first run of freed slots that is long enough, otherwise the values are appended.
+    /// The slots are given the highest generation among them, so the elements share one generation.
+    ///
+    #[must_use]
+    pub fn insert_slice(&mut self, values: impl IntoIterator<Item = T>) -> RangeIdx<T, G, Tag>{
+        let values: Vec<T> = values.into_iter().collect();
+        let len = values.len();
+        let (start, generation) = self.raw_mut().insert_run(values);
+        #[cfg(feature = "leak-detect")]
+        for index in start..start + len{
+            self.leaks.record(index);
+        }
+        #[cfg(feature = "metrics")]
+        self.metrics.inserted(len, self.num(), self.slots());
+        RangeIdx{
+            start,
+            len,
+            generation,
+            _ty: PhantomData,
+            _tag: PhantomData,
+        }
+    }
+
+    ///
+    /// Returns the elements of the range, or None if any of them was removed.
+    ///
+    pub fn get_slice(&self, range: RangeIdx<T, G, Tag>) -> Option<ArenaSlice<'_, T, G>>{
+        let cells = self.raw().run(range.start, range.len, range.generation)?;
+        Some(ArenaSlice{cells})
+    }
+
+    ///
+    /// Returns the mutable elements of the range, or None if any of them was removed.
+    ///
+    pub fn get_slice_mut(&mut self, range: RangeIdx<T, G, Tag>) -> Option<ArenaSliceMut<'_, T, G>>{
+        let cells = self.raw_mut().run_mut(range.start, range.len, range.generation)?;
+        Some(ArenaSliceMut{cells})
+    }
+
+    ///
+    /// Removes all elements of the range and returns them in order.
+    /// Returns None and removes nothing if any of them was already removed.
+    ///
+    pub fn remove_slice(&mut self, range: RangeIdx<T, G, Tag>) -> Option<Vec<T>>{
+        self.get_slice(range)?;
+        Some(range.keys().map(|key| self.remove(key).expect("The range was just checked.")).collect())
+    }
+}
+
+#[cfg(test)]
+mod test{
+    use super::*;
+
+    #[test]
+    fn test_ranges(){
+        let mut arena = Arena::new();
+        let singles: Vec<_> = (0..6).map(|i| arena.insert(i)).collect();
+
+        // No free run yet, the range is appended.
+        let a = arena.insert_slice(10..13);
+        assert_eq!((a.start(), a.len(), a.gen()), (6, 3, 0));
+        assert_eq!(arena.num(), 9);
+        assert_eq!(arena.get_slice(a).unwrap().iter().copied().collect::<Vec<_>>(), [10, 11, 12]);
+        for (i, val) in arena.get_slice_mut(a).unwrap().iter_mut().enumerate(){
+            *val += i as i32;
+        }
+        assert_eq!(arena.get(a.key(2)), Some(&14));
+
+        // A run of two freed slots with different generations is reused with the higher one.
+        arena.remove(singles[1]);
+        let reused = arena.insert(100);
+        arena.remove(reused);
+        arena.remove(singles[2]);
+        arena.remove(singles[4]);
+        let b = arena.insert_slice([20, 21]);
+        assert_eq!((b.start(), b.gen()), (1, 2));
+        assert_eq!(arena.get(reused), None);
+        assert_eq!(arena.get(singles[2]), None);
+        assert_eq!(b.keys().map(|key| arena[key]).collect::<Vec<_>>(), [20, 21]);
+
+        // The run was unlinked from the free list, slot 4 is still free.
+        let c = arena.insert(30);
+        assert_eq!(c.index(), 4);
+        let d = arena.insert(31);
+        assert_eq!(d.index(), 9);
+
+        // Removing one element invalidates the range, the other keys still work.
+        assert_eq!(arena.remove(a.key(1)), Some(12));
+        assert!(arena.get_slice(a).is_none());
+        assert!(arena.get_slice_mut(a).is_none());
+        assert_eq!(arena.remove_slice(a), None);
+        assert_eq!(arena[a.key(0)], 10);
+        assert_eq!(arena.remove(a.key(2)), Some(14));
+
+        assert_eq!(arena.remove_slice(b), Some(vec![20, 21]));
+        assert_eq!(arena.remove_slice(b), None);
+        assert_eq!(arena.get(b.key(0)), None);
+
+        // A longer range does not fit into the freed slots.
+        let e = arena.insert_slice(0..4);
+        assert_eq!(e.start(), 10);
+        let f = arena.insert_slice(Vec::new());
+        assert!(f.is_empty());
+        assert_eq!(arena.get_slice(f).map(|s| s.len()), Some(0));
+        assert_eq!(arena.remove_slice(f), Some(vec![]));
+    }
+
+    #[test]
+    fn test_tagged_range(){
+        arena_tag!{
+            struct Meshes;
+        }
+
+        let mut arena: Arena<u32, usize, Meshes> = Arena::default();
+        let range = arena.insert_slice([1, 2]);
+        let key: ArenaIdx<u32, usize, Meshes> = range.key(1);
+        assert_eq!(arena[key], 2);
+        assert_eq!(arena.remove_slice(range), Some(vec![1, 2]));
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_key_out_of_range(){
+        let mut arena = Arena::new();
+        let range = arena.insert_slice([0, 1]);
+        let _ = range.key(2);
+    }
+}
@@ -0,0 +1,475 @@
+use crate::*;
+
+///
+/// Generation bookkeeping of a RawArena.
+/// Implemented by every GenCounter for generational arenas and by () for arenas without generation checks.
+///
+pub(crate) trait Generation: Copy + Eq + Ord{
+    const FIRST: Self;
+
+    fn next(self) -> Self;
+
+    // Retired cells are freed but never put on the free list.
+    fn retired(self) -> bool;
+}
+
+impl<G: GenCounter> Generation for G{
+    const FIRST: Self = G::FIRST;
+
+    #[inline]
+    fn next(self) -> Self{
+        GenCounter::next(self)
+    }
+
+    #[inline]
+    fn retired(self) -> bool{
+        self == G::RETIRED
+    }
+}
+
+impl Generation for (){
+    const FIRST: Self = ();
+
+    #[inline]
+    fn next(self) -> Self{}
+
+    #[inline]
+    fn retired(self) -> bool{
+        false
+    }
+}
+
+///
+/// Cell of a RawArena, either holding an element or linking to the next freed cell.
+/// A freed cell linking to itself is reserved, it is not on the free list until it is filled or released.
+///
+#[derive(Debug, Clone)]
+pub(crate) enum RawCell<T, G = usize>{
+    Allocated{val: T, generation: G},
+    Freed{next: Option<usize>, generation: G},
+}
+
+///
+/// Storage shared by Arena and Slab.
+/// Keys are passed as a slot index together with the generation they were created with.
+///
+#[derive(Debug, Clone)]
+pub(crate) struct RawArena<T, G>{
+    pub(crate) cells: Vec<RawCell<T, G>>,
+    pub(crate) freed: Option<usize>,
+    pub(crate) num: usize,
+}
+
+impl<T, G: Generation> RawArena<T, G>{
+    pub(crate) fn new() -> Self{
+        Self{
+            cells: Vec::new(),
+            freed: None,
+            num: 0,
+        }
+    }
+
+    pub(crate) fn with_capacity(cap: usize) -> Self{
+        Self{
+            cells: Vec::with_capacity(cap),
+            freed: None,
+            num: 0,
+        }
+    }
+
+    // Builds the arena from cells whose links are ignored,
+    // the Freed cells are linked in ascending order like after clear.
+    pub(crate) fn from_cells(cells: Vec<RawCell<T, G>>) -> Self{
+        let mut raw = Self{
+            cells,
+            freed: None,
+            num: 0,
+        };
+        raw.relink();
+        raw
+    }
+
+    // Links the Freed cells in ascending order, skipping retired and reserved ones, and recounts the elements.
+    pub(crate) fn relink(&mut self){
+        let mut freed = None;
+        let mut num = 0;
+        for (i, cell) in self.cells.iter_mut().enumerate().rev(){
+            match cell{
+                RawCell::Allocated{..} => num += 1,
+                RawCell::Freed{next, ..} if *next == Some(i) => (),
+                RawCell::Freed{next, generation} => {
+                    if generation.retired(){
+                        *next = None;
+                    }
+                    else{
+                        *next = freed;
+                        freed = Some(i);
+                    }
+                }
+            }
+        }
+        self.freed = freed;
+        self.num = num;
+    }
+
+    pub(crate) fn clear(&mut self){
+        let mut freed = None;
+        for (i, cell) in self.cells.iter_mut().enumerate().rev(){
+            let generation = match cell{
+                RawCell::Allocated{generation, ..} => generation.next(),
+                // Keys of reservations become stale like keys of elements.
+                RawCell::Freed{next, generation} if *next == Some(i) => generation.next(),
+                RawCell::Freed{generation, ..} => *generation,
+            };
+            if generation.retired(){
+                *cell = RawCell::Freed{generation, next: None};
+            }
+            else{
+                *cell = RawCell::Freed{generation, next: freed};
+                freed = Some(i);
+            }
+        }
+        self.freed = freed;
+        self.num = 0;
+    }
+
+    // Takes the head of the free list, or returns the slot it links to if that is not a freed cell.
+    fn pop_freed(&mut self) -> Result<Option<(usize, G)>, usize>{
+        let Some(i) = self.freed else{
+            return Ok(None);
+        };
+        match self.cells.get(i){
+            Some(&RawCell::Freed{next, generation}) => {
+                self.freed = next;
+                Ok(Some((i, generation)))
+            }
+            _ => Err(i),
+        }
+    }
+
+    // Like pop_freed, but rebuilds a corrupt list from the cells once, which drops the bogus link.
+    fn take_freed(&mut self) -> Result<Option<(usize, G)>, usize>{
+        self.pop_freed().or_else(|_|{
+            self.relink();
+            self.pop_freed()
+        })
+    }
+
+    pub(crate) fn try_insert(&mut self, val: T) -> Result<(usize, G), InsertError<T>>{
+        let freed = match self.take_freed(){
+            Ok(freed) => freed,
+            Err(slot) => return Err(InsertError::new(val, InsertErrorKind::CorruptFreeList{slot})),
+        };
+        match freed{
+            Some((i, generation)) => {
+                self.cells[i] = RawCell::Allocated{
+                    val,
+                    generation,
+                };
+                self.num += 1;
+                Ok((i, generation))
+            }
+            None => {
+                self.cells.push(RawCell::Allocated{
+                    generation: G::FIRST,
+                    val,
+                });
+                self.num += 1;
+                Ok((self.cells.len() - 1, G::FIRST))
+            }
+        }
+    }
+
+    ///
+    /// Takes a cell off the free list, or pushes a new one, and reserves it without allocating it.
+    /// Returns the cell and the generation the element will get.
+    ///
+    pub(crate) fn reserve(&mut self) -> (usize, G){
+        let (i, generation) = match self.take_freed(){
+            Ok(Some(freed)) => freed,
+            _ => {
+                self.cells.push(RawCell::Freed{next: None, generation: G::FIRST});
+                (self.cells.len() - 1, G::FIRST)
+            }
+        };
+        self.cells[i] = RawCell::Freed{next: Some(i), generation};
+        (i, generation)
+    }
+
+    #[inline]
+    pub(crate) fn is_reserved(&self, index: usize, gen: G) -> bool{
+        matches!(self.cells.get(index), Some(&RawCell::Freed{next, generation}) if next == Some(index) && generation == gen)
+    }
+
+    ///
+    /// Allocates the reserved cell, or returns the value if it is not reserved with the generation.
+    ///
+    pub(crate) fn fill(&mut self, index: usize, gen: G, val: T) -> Result<(), T>{
+        if !self.is_reserved(index, gen){
+            return Err(val);
+        }
+        self.cells[index] = RawCell::Allocated{val, generation: gen};
+        self.num += 1;
+        Ok(())
+    }
+
+    ///
+    /// Puts the reserved cell back on the free list with the next generation.
+    /// Returns false if it is not reserved with the generation.
+    ///
+    pub(crate) fn release(&mut self, index: usize, gen: G) -> bool{
+        if !self.is_reserved(index, gen){
+            return false;
+        }
+        let generation = gen.next();
+        let retired = generation.retired();
+        self.cells[index] = RawCell::Freed{
+            next: if retired {None} else{self.freed},
+            generation,
+        };
+        if !retired{
+            self.freed = Some(index);
+        }
+        true
+    }
+
+    pub(crate) fn remove(&mut self, index: usize, gen: G) -> Option<T>{
+        match self.cells.get(index){
+            Some(RawCell::Allocated{generation, ..}) if *generation == gen => {
+                let generation = gen.next();
+                let retired = generation.retired();
+                let cell = std::mem::replace(&mut self.cells[index], RawCell::Freed{
+                    next: if retired {None} else{self.freed},
+                    generation,
+                });
+                self.num -= 1;
+                if !retired{
+                    self.freed = Some(index);
+                }
+                match cell{
+                    RawCell::Allocated{val, ..} => Some(val),
+                    RawCell::Freed{..} => unreachable!(),
+                }
+            }
+            _ => None,
+        }
+    }
+
+    // Removes the cells matching the predicate from the free list.
+    fn unlink(&mut self, pred: impl Fn(usize) -> bool){
+        let mut prev: Option<usize> = None;
+        let mut cur = self.freed;
+        while let Some(i) = cur{
+            let next = match self.cells[i]{
+                RawCell::Freed{next, ..} => next,
+                RawCell::Allocated{..} => unreachable!("The free list only holds freed cells."),
+            };
+            if pred(i){
+                match prev{
+                    Some(prev) => if let RawCell::Freed{next: link, ..} = &mut self.cells[prev]{
+                        *link = next;
+                    },
+                    None => self.freed = next,
+                }
+            }
+            else{
+                prev = Some(i);
+            }
+            cur = next;
+        }
+    }
+
+    ///
+    /// Allocates the values in consecutive cells, all with the same generation.
+    /// Uses the first run of freed cells that is long enough, or pushes new cells.
+    /// Returns the first cell and the generation.
+    ///
+    pub(crate) fn insert_run(&mut self, vals: Vec<T>) -> (usize, G){
+        let len = vals.len();
+        let mut run = 0;
+        let mut start = None;
+        if len > 0{
+            for (i, cell) in self.cells.iter().enumerate(){
+                match cell{
+                    RawCell::Freed{next, generation} if !generation.retired() && *next != Some(i) => run += 1,
+                    _ => run = 0,
+                }
+                if run == len{
+                    start = Some(i + 1 - len);
+                    break;
+                }
+            }
+        }
+        self.num += len;
+        match start{
+            Some(start) => {
+                let range = start..start + len;
+                // Raising the generation of a freed cell only invalidates more stale keys.
+                let generation = range.clone().map(|i| self.gen(i)).max().expect("The run is not empty.");
+                self.unlink(|i| range.contains(&i));
+                for (i, val) in range.zip(vals){
+                    self.cells[i] = RawCell::Allocated{val, generation};
+                }
+                (start, generation)
+            }
+            None => {
+                let start = self.cells.len();
+                self.cells.extend(vals.into_iter().map(|val| RawCell::Allocated{val, generation: G::FIRST}));
+                (start, G::FIRST)
+            }
+        }
+    }
+
+    ///
+    /// Returns the cells of a run if all of them are allocated with the generation.
+    ///
+    pub(crate) fn run(&self, start: usize, len: usize, gen: G) -> Option<&[RawCell<T, G>]>{
+        let cells = self.cells.get(start..start.checked_add(len)?)?;
+        cells.iter().all(|cell| matches!(cell, RawCell::Allocated{generation, ..} if *generation == gen)).then_some(cells)
+    }
+
+    pub(crate) fn run_mut(&mut self, start: usize, len: usize, gen: G) -> Option<&mut [RawCell<T, G>]>{
+        self.run(start, len, gen)?;
+        Some(&mut self.cells[start..start + len])
+    }
+
+    ///
+    /// Advances the generation of an allocated cell without touching its value.
+    /// Returns the new generation, or None if the key is stale or the generation is exhausted.
+    ///
+    pub(crate) fn bump(&mut self, index: usize, gen: G) -> Option<G>{
+        match self.cells.get_mut(index){
+            Some(RawCell::Allocated{generation, ..}) if *generation == gen && !gen.next().retired() => {
+                *generation = gen.next();
+                Some(*generation)
+            }
+            _ => None,
+        }
+    }
+
+    ///
+    /// Frees the allocated cell at index like remove, but with the given generation.
+    /// If pop is set the cell has to be the last one and is popped instead.
+    ///
+    pub(crate) fn take_at(&mut self, index: usize, generation: G, pop: bool) -> T{
+        let cell = if pop{
+            debug_assert_eq!(index, self.cells.len() - 1);
+            self.cells.pop().expect("The cell exists.")
+        }
+        else{
+            let cell = std::mem::replace(&mut self.cells[index], RawCell::Freed{
+                next: self.freed,
+                generation,
+            });
+            self.freed = Some(index);
+            cell
+        };
+        self.num -= 1;
+        match cell{
+            RawCell::Allocated{val, ..} => val,
+            RawCell::Freed{..} => panic!("Only allocated cells can be taken."),
+        }
+    }
+
+    ///
+    /// Allocates the cell at index with the given generation. The cell has to be the head of the
+    /// free list, or if push is set, the cell after the last one.
+    /// Inverse of take_at.
+    ///
+    pub(crate) fn put_at(&mut self, index: usize, val: T, generation: G, push: bool){
+        if push{
+            assert_eq!(index, self.cells.len(), "Only the next cell can be pushed.");
+            self.cells.push(RawCell::Allocated{val, generation});
+        }
+        else{
+            assert_eq!(self.freed, Some(index), "Only the head of the free list can be put.");
+            if let RawCell::Freed{next, ..} = self.cells[index]{
+                self.freed = next;
+            }
+            self.cells[index] = RawCell::Allocated{val, generation};
+        }
+        self.num += 1;
+    }
+
+    #[inline]
+    pub(crate) fn gen(&self, index: usize) -> G{
+        match self.cells[index]{
+            RawCell::Freed{generation, ..} => generation,
+            RawCell::Allocated{generation, ..} => generation,
+        }
+    }
+
+    #[inline]
+    pub(crate) fn get(&self, index: usize, gen: G) -> Option<&T>{
+        match self.cells.get(index){
+            Some(RawCell::Allocated{val, generation}) if *generation == gen => Some(val),
+            _ => None,
+        }
+    }
+
+    #[inline]
+    pub(crate) fn get_mut(&mut self, index: usize, gen: G) -> Option<&mut T>{
+        match self.cells.get_mut(index){
+            Some(RawCell::Allocated{val, generation}) if *generation == gen => Some(val),
+            _ => None,
+        }
+    }
+
+    #[inline]
+    pub(crate) fn get_any(&self, index: usize) -> Option<&T>{
+        match self.cells.get(index){
+            Some(RawCell::Allocated{val, ..}) => Some(val),
+            _ => None,
+        }
+    }
+
+    #[inline]
+    pub(crate) fn get_any_mut(&mut self, index: usize) -> Option<&mut T>{
+        match self.cells.get_mut(index){
+            Some(RawCell::Allocated{val, ..}) => Some(val),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn get2_mut(&mut self, a: (usize, G), b: (usize, G)) -> (Option<&mut T>, Option<&mut T>){
+        if a.0 == b.0{
+            // Only a generation matching the allocated one refers to a value,
+            // so the same slot is an error only if both indices are live.
+            if self.get(a.0, a.1).is_none(){
+                return (None, self.get_mut(b.0, b.1));
+            }
+            if a.1 == b.1{
+                panic!("Cannot take 2 mutable references to a value at the same index.")
+            }
+            return (self.get_mut(a.0, a.1), None);
+        }
+
+        if a.0 >= self.cells.len(){
+            return (None, self.get_mut(b.0, b.1));
+        }
+        if b.0 >= self.cells.len(){
+            return (self.get_mut(a.0, a.1), None);
+        }
+
+        let (cell0, cell1) = {
+            let split = self.cells.split_at_mut(a.0.max(b.0));
+            if a.0 < b.0{
+                (&mut split.0[a.0], &mut split.1[0])
+            }
+            else{
+                (&mut split.1[0], &mut split.0[b.0])
+            }
+        };
+
+        let cell0 = match cell0{
+            RawCell::Allocated{val, generation} if *generation == a.1 => Some(val),
+            _ => None
+        };
+        let cell1 = match cell1{
+            RawCell::Allocated{val, generation} if *generation == b.1 => Some(val),
+            _ => None
+        };
+
+        (cell0, cell1)
+    }
+}
@@ -0,0 +1,258 @@
+use std::sync::{Arc, Mutex};
+
+use arc_swap::ArcSwap;
+
+use crate::*;
+
+///
+/// A published version of an RcuArena, see RcuArena::load.
+/// It never changes, so it can be read from any number of threads without synchronization.
+///
+#[derive(Debug, Clone)]
+pub struct FrozenSnapshot<T>{
+    arena: Arena<T>,
+}
+
+impl<T> FrozenSnapshot<T>{
+    #[inline]
+    pub fn get(&self, index: ArenaIdx<T>) -> Option<&T>{
+        self.arena.get(index)
+    }
+
+    #[inline]
+    pub fn contains(&self, index: ArenaIdx<T>) -> bool{
+        self.arena.get(index).is_some()
+    }
+
+    #[inline]
+    pub fn iter(&self) -> Iter<'_, T>{
+        self.arena.iter()
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize{
+        self.arena.num()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool{
+        self.len() == 0
+    }
+
+    ///
+    /// Returns the arena of this version.
+    ///
+    #[inline]
+    pub fn arena(&self) -> &Arena<T>{
+        &self.arena
+    }
+}
+
+///
+/// An Arena for read-mostly data shared between threads, updated by read-copy-update.
+///
+/// Readers load the current version as a FrozenSnapshot without locking and without ever
+/// waiting for a writer. Writers apply a batch of changes to a copy of the current arena and
+/// publish it atomically, so readers see either all or none of a batch. Writers are serialized,
+/// and every batch copies the arena, which suits rare updates like configuration reloads.
+///
+/// Copies keep the slots, generations and free list, so keys stay valid across versions
+/// until their element is removed, and the keys a batch hands out are the ones it publishes.
+/// A version is dropped once no reader holds it anymore.
+///
+/// Requires the arc-swap feature.
+///
+/// # Example
+///
+///```rust
+/// use gen_arena::*;
+///
+/// let routes = RcuArena::new();
+/// let home = routes.insert("/home");
+///
+/// let before = routes.load();
+/// let about = routes.update(|arena|{
+///     arena.remove(home);
+///     arena.insert("/about")
+/// });
+///
+/// // Old versions stay readable and unchanged.
+/// assert_eq!(before.get(home), Some(&"/home"));
+/// assert_eq!(before.get(about), None);
+///
+/// let now = routes.load();
+/// assert_eq!(now.get(home), None);
+/// assert_eq!(routes.with(about, |route| route.len()), Some(6));
+///```
+///
+pub struct RcuArena<T>{
+    current: ArcSwap<FrozenSnapshot<T>>,
+    writer: Mutex<()>,
+}
+
+impl<T> RcuArena<T>{
+    ///
+    /// Creates an empty RcuArena.
+    ///
+    pub fn new() -> Self{
+        Self::from_arena(Arena::new())
+    }
+
+    ///
+    /// Publishes the arena as the first version, keeping its keys.
+    ///
+    pub fn from_arena(arena: Arena<T>) -> Self{
+        Self{
+            current: ArcSwap::from_pointee(FrozenSnapshot{arena}),
+            writer: Mutex::new(()),
+        }
+    }
+
+    ///
+    /// Returns the current version.
+    /// Lookups in it are plain generation checked gets, and it stays the same while it is held.
+    ///
+    #[inline]
+    pub fn load(&self) -> Arc<FrozenSnapshot<T>>{
+        self.current.load_full()
+    }
+
+    ///
+    /// Calls f with the element in the current version and returns its result,
+    /// or None if the index is not valid. Cheaper than load for a single lookup.
+    ///
+    #[inline]
+    pub fn with<R>(&self, index: ArenaIdx<T>, f: impl FnOnce(&T) -> R) -> Option<R>{
+        self.current.load().get(index).map(f)
+    }
+
+    ///
+    /// Applies f to a copy of the current arena and publishes the result as the new version.
+    /// Other writers wait until it is published, readers keep reading the previous version.
+    /// Nothing is published if f panics.
+    ///
+    pub fn update<R>(&self, f: impl FnOnce(&mut Arena<T>) -> R) -> R
+    where
+        T: Clone,
+    {
+        // The lock only serializes writers, so a writer that panicked left nothing behind.
+        let _writer = self.writer.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        let mut arena = self.current.load().arena.clone();
+        let r = f(&mut arena);
+        self.current.store(Arc::new(FrozenSnapshot{arena}));
+        r
+    }
+
+    ///
+    /// Inserts a new element and publishes it as a batch of its own, see update.
+    ///
+    #[must_use]
+    pub fn insert(&self, val: T) -> ArenaIdx<T>
+    where
+        T: Clone,
+    {
+        self.update(|arena| arena.insert(val))
+    }
+
+    ///
+    /// Removes the element and publishes the removal as a batch of its own, see update.
+    /// Returns the value if the index is still valid.
+    ///
+    pub fn remove(&self, index: ArenaIdx<T>) -> Option<T>
+    where
+        T: Clone,
+    {
+        self.update(|arena| arena.remove(index))
+    }
+
+    ///
+    /// Returns the current arena, which is not shared anymore.
+    ///
+    pub fn into_arena(self) -> Arena<T>
+    where
+        T: Clone,
+    {
+        let current = self.current.into_inner();
+        Arc::try_unwrap(current).map(|snapshot| snapshot.arena).unwrap_or_else(|current| current.arena.clone())
+    }
+}
+
+impl<T> Default for RcuArena<T>{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: std::fmt::Debug> std::fmt::Debug for RcuArena<T>{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_map().entries(self.load().iter().map(|(i, val)| (i.index(), val))).finish()
+    }
+}
+
+#[cfg(test)]
+mod test{
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    use super::*;
+
+    #[test]
+    fn test_concurrent_readers(){
+        // Every version holds pairs of elements (i, -i) inserted and removed together.
+        let arena = RcuArena::new();
+        let mut pairs: Vec<_> = (0..8i64).map(|i| arena.update(|arena| (arena.insert(i), arena.insert(-i)))).collect();
+        let done = AtomicBool::new(false);
+
+        std::thread::scope(|s|{
+            for _ in 0..4{
+                s.spawn(||{
+                    let mut checked = 0;
+                    while !done.load(Ordering::Relaxed) || checked == 0{
+                        let snapshot = arena.load();
+                        let sum = snapshot.iter().map(|(_, val)| *val).sum::<i64>();
+                        assert_eq!(sum, 0, "A reader saw half of a batch.");
+                        assert_eq!(snapshot.len() % 2, 0);
+                        for (key, val) in snapshot.iter(){
+                            assert_eq!(snapshot.get(key), Some(val));
+                        }
+                        checked += 1;
+                    }
+                });
+            }
+            for round in 0..200i64{
+                let (a, b) = pairs.remove(0);
+                let removed = arena.update(|arena|{
+                    let removed = (arena.remove(a), arena.remove(b));
+                    pairs.push((arena.insert(round), arena.insert(-round)));
+                    removed
+                });
+                assert!(matches!(removed, (Some(x), Some(y)) if x == -y));
+                // Keys of removed elements stay stale in later versions.
+                assert_eq!(arena.with(a, |val| *val), None);
+            }
+            done.store(true, Ordering::Relaxed);
+        });
+
+        let snapshot = arena.load();
+        assert_eq!(snapshot.len(), 16);
+        for &(a, b) in pairs.iter(){
+            assert_eq!(snapshot.get(a).map(|val| -val), snapshot.get(b).copied());
+        }
+        let arena = arena.into_arena();
+        assert_eq!(arena.num(), 16);
+    }
+
+    #[test]
+    fn test_panicking_writer(){
+        let arena = RcuArena::new();
+        let key = arena.insert(String::from("kept"));
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(||{
+            arena.update(|arena|{
+                arena.remove(key);
+                panic!("Aborted update.");
+            })
+        }));
+        assert!(result.is_err());
+        assert_eq!(arena.with(key, |val| val.clone()).as_deref(), Some("kept"));
+        assert_eq!(arena.remove(key).as_deref(), Some("kept"));
+    }
+}
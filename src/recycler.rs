@@ -0,0 +1,181 @@
+use std::{marker::PhantomData, sync::{Arc, Mutex, OnceLock}};
+
+use crate::*;
+
+type Queue = Mutex<Vec<(usize, usize)>>;
+
+///
+/// The queue shared between an Arena and its Recyclers.
+/// It is created on the first call to Arena::recycler.
+///
+#[derive(Default)]
+pub(crate) struct RecycleQueue(OnceLock<Arc<Queue>>);
+
+// A cloned Arena must not drain the keys marked for the original one.
+impl Clone for RecycleQueue{
+    fn clone(&self) -> Self {
+        Self::default()
+    }
+}
+
+impl std::fmt::Debug for RecycleQueue{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let pending = self.0.get().map(|queue| lock(queue).len()).unwrap_or(0);
+        f.debug_struct("RecycleQueue").field("pending", &pending).finish()
+    }
+}
+
+#[inline]
+fn lock(queue: &Queue) -> std::sync::MutexGuard<'_, Vec<(usize, usize)>>{
+    // The queue only holds plain keys, so it is valid even if a thread panicked while pushing.
+    queue.lock().unwrap_or_else(|err| err.into_inner())
+}
+
+///
+/// A handle to mark elements of an Arena as free from any thread.
+///
+/// The marked elements are removed by the next call to Arena::drain_recycler.
+/// Recyclers do not borrow the Arena and can be cloned and sent to other threads,
+/// even if T itself can not.
+///
+/// # Example
+///
+///```rust
+/// use gen_arena::*;
+///
+/// let mut arena = Arena::new();
+/// let keys: Vec<_> = (0..4).map(|i| arena.insert(i)).collect();
+///
+/// let recycler = arena.recycler();
+/// std::thread::spawn(move ||{
+///     recycler.mark_free(keys[1]);
+///     recycler.mark_free(keys[2]);
+/// }).join().unwrap();
+///
+/// assert_eq!(arena.drain_recycler(), 2);
+/// assert_eq!(arena.values().copied().collect::<Vec<_>>(), [0, 3]);
+///```
+///
+pub struct Recycler<T>{
+    queue: Arc<Queue>,
+    _ty: PhantomData<fn() -> T>,
+}
+
+impl<T> Recycler<T>{
+    ///
+    /// Marks the element as free.
+    /// Keys which are not valid anymore when the recycler is drained are ignored.
+    ///
+    pub fn mark_free(&self, index: ArenaIdx<T>){
+        lock(&self.queue).push((index.index(), index.gen()));
+    }
+
+    ///
+    /// Returns the number of keys waiting to be drained.
+    ///
+    pub fn pending(&self) -> usize{
+        lock(&self.queue).len()
+    }
+}
+
+impl<T> Clone for Recycler<T>{
+    fn clone(&self) -> Self {
+        Self{
+            queue: self.queue.clone(),
+            _ty: PhantomData,
+        }
+    }
+}
+
+impl<T> std::fmt::Debug for Recycler<T>{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Recycler").field("pending", &self.pending()).finish()
+    }
+}
+
+impl<T> Arena<T>{
+    ///
+    /// Returns a Recycler handle for this arena.
+    /// All handles of an arena share the same queue.
+    ///
+    pub fn recycler(&self) -> Recycler<T>{
+        Recycler{
+            queue: self.recycler.0.get_or_init(Default::default).clone(),
+            _ty: PhantomData,
+        }
+    }
+
+    ///
+    /// Removes all elements marked by the Recyclers of this arena.
+    /// Returns the number of removed elements. Stale keys and keys marked twice are skipped.
+    ///
+    pub fn drain_recycler(&mut self) -> usize{
+        let Some(queue) = self.recycler.0.get() else{
+            return 0;
+        };
+        let pending = std::mem::take(&mut *lock(queue));
+        pending.into_iter()
+            .filter(|(index, gen)| self.remove(ArenaIdx::from_parts(*index, *gen)).is_some())
+            .count()
+    }
+}
+
+#[cfg(test)]
+mod test{
+    use super::*;
+    use std::collections::HashSet;
+
+    #[test]
+    fn test_producers_and_consumer(){
+        const PRODUCERS: usize = 4;
+        const ROUNDS: usize = 50;
+        const PER_ROUND: usize = 20;
+
+        let mut arena = Arena::new();
+        let mut removed = 0;
+
+        for round in 0..ROUNDS{
+            let keys: Vec<_> = (0..PRODUCERS * PER_ROUND).map(|i| arena.insert((round, i))).collect();
+            let stale = keys[0];
+
+            std::thread::scope(|s|{
+                for (p, chunk) in keys.chunks(PER_ROUND).enumerate(){
+                    let recycler = arena.recycler();
+                    s.spawn(move ||{
+                        for key in chunk{
+                            recycler.mark_free(*key);
+                        }
+                        // Mark some keys twice, including keys of other producers.
+                        recycler.mark_free(chunk[p % chunk.len()]);
+                        recycler.mark_free(stale);
+                    });
+                }
+                // The consumer drains concurrently with the producers.
+                removed += arena.drain_recycler();
+            });
+            removed += arena.drain_recycler();
+
+            assert_eq!(arena.recycler().pending(), 0);
+            assert_eq!(arena.num(), 0);
+            assert!(keys.iter().all(|key| arena.get(*key).is_none()));
+        }
+        assert_eq!(removed, ROUNDS * PRODUCERS * PER_ROUND);
+
+        // Slots are still reused exactly once each.
+        let keys: HashSet<_> = (0..PRODUCERS * PER_ROUND).map(|_| arena.insert((0, 0)).index()).collect();
+        assert_eq!(keys.len(), PRODUCERS * PER_ROUND);
+        assert!(keys.iter().all(|index| *index < PRODUCERS * PER_ROUND));
+    }
+
+    #[test]
+    fn test_clone_has_own_queue(){
+        let mut arena = Arena::new();
+        let key = arena.insert(0);
+        arena.recycler().mark_free(key);
+
+        let mut clone = arena.clone();
+        assert_eq!(clone.drain_recycler(), 0);
+        assert_eq!(arena.drain_recycler(), 1);
+        assert_eq!(clone.get(key), Some(&0));
+    }
+}
@@ -0,0 +1,287 @@
+use std::ops::{Index, IndexMut};
+
+use crate::*;
+
+///
+/// Identifies an Arena in an ArenaRegistry.
+/// Ids of destroyed arenas stay invalid, even if their slot is reused by a new arena.
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ArenaId{
+    index: usize,
+    generation: usize,
+}
+
+impl ArenaId{
+    #[inline]
+    pub fn index(&self) -> usize{
+        self.index
+    }
+
+    #[inline]
+    pub fn gen(&self) -> usize{
+        self.generation
+    }
+}
+
+///
+/// An index referring to an element of an Arena in an ArenaRegistry.
+///
+#[derive(Debug)]
+pub struct GlobalIdx<T>{
+    arena: ArenaId,
+    idx: ArenaIdx<T>,
+}
+
+impl<T> GlobalIdx<T>{
+    pub fn new(arena: ArenaId, idx: ArenaIdx<T>) -> Self{
+        Self{
+            arena,
+            idx,
+        }
+    }
+
+    ///
+    /// Returns the id of the arena the element is in.
+    ///
+    #[inline]
+    pub fn arena(&self) -> ArenaId{
+        self.arena
+    }
+
+    ///
+    /// Returns the index of the element in its arena.
+    ///
+    #[inline]
+    pub fn idx(&self) -> ArenaIdx<T>{
+        self.idx
+    }
+}
+
+impl<T> Clone for GlobalIdx<T>{
+    #[inline]
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for GlobalIdx<T>{}
+
+impl<T> PartialEq for GlobalIdx<T>{
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        self.arena == other.arena && self.idx == other.idx
+    }
+}
+
+impl<T> Eq for GlobalIdx<T>{}
+
+///
+/// A collection of Arenas which can be created and destroyed at runtime.
+///
+/// Arenas are addressed by ArenaId and their elements by GlobalIdx, which combines the ArenaId
+/// with the index of the element. Both carry a generation, so destroying an arena invalidates
+/// all GlobalIdx into it, even after a new arena reuses its slot.
+///
+/// # Example
+///
+///```rust
+/// use gen_arena::*;
+///
+/// let mut registry = ArenaRegistry::new();
+/// let level0 = registry.create_arena();
+/// let level1 = registry.create_arena();
+///
+/// let a = registry.insert(level0, "a").unwrap();
+/// let b = registry.insert(level1, "b").unwrap();
+///
+/// assert_eq!(registry.get(a), Some(&"a"));
+/// assert_eq!(registry.get(b), Some(&"b"));
+///
+/// registry.destroy_arena(level0);
+/// assert_eq!(registry.get(a), None);
+/// assert_eq!(registry.insert(level0, "c"), None);
+/// assert_eq!(registry.get(b), Some(&"b"));
+///```
+///
+#[derive(Debug)]
+pub struct ArenaRegistry<T>{
+    arenas: Arena<Arena<T>>,
+}
+
+impl<T> ArenaRegistry<T>{
+    ///
+    /// Creates an empty ArenaRegistry.
+    ///
+    pub fn new() -> Self{
+        Self{
+            arenas: Arena::new(),
+        }
+    }
+
+    #[inline]
+    fn cast(id: ArenaId) -> ArenaIdx<Arena<T>>{
+        ArenaIdx::from_parts(id.index, id.generation)
+    }
+
+    ///
+    /// Creates a new empty arena and returns its id.
+    ///
+    pub fn create_arena(&mut self) -> ArenaId{
+        let idx = self.arenas.insert(Arena::new());
+        ArenaId{
+            index: idx.index(),
+            generation: idx.gen(),
+        }
+    }
+
+    ///
+    /// Removes the arena and returns it if the id is still valid.
+    /// All GlobalIdx into the arena become invalid.
+    ///
+    pub fn destroy_arena(&mut self, id: ArenaId) -> Option<Arena<T>>{
+        self.arenas.remove(Self::cast(id))
+    }
+
+    ///
+    /// Returns an optional reference to the arena with the id.
+    ///
+    #[inline]
+    pub fn arena(&self, id: ArenaId) -> Option<&Arena<T>>{
+        self.arenas.get(Self::cast(id))
+    }
+
+    ///
+    /// Returns a mutable optional reference to the arena with the id.
+    ///
+    #[inline]
+    pub fn arena_mut(&mut self, id: ArenaId) -> Option<&mut Arena<T>>{
+        self.arenas.get_mut(Self::cast(id))
+    }
+
+    ///
+    /// Inserts the value into the arena with the id.
+    /// Returns None if the id is not valid.
+    ///
+    pub fn insert(&mut self, id: ArenaId, val: T) -> Option<GlobalIdx<T>>{
+        let idx = self.arena_mut(id)?.insert(val);
+        Some(GlobalIdx::new(id, idx))
+    }
+
+    ///
+    /// Removes the element and returns its value if both the arena and the element are still valid.
+    ///
+    pub fn remove(&mut self, index: GlobalIdx<T>) -> Option<T>{
+        self.arena_mut(index.arena)?.remove(index.idx)
+    }
+
+    ///
+    /// Returns an optional reference to the element,
+    /// checking the generation of both the arena and the element.
+    ///
+    pub fn get(&self, index: GlobalIdx<T>) -> Option<&T>{
+        self.arena(index.arena)?.get(index.idx)
+    }
+
+    ///
+    /// Returns a mutable optional reference to the element,
+    /// checking the generation of both the arena and the element.
+    ///
+    pub fn get_mut(&mut self, index: GlobalIdx<T>) -> Option<&mut T>{
+        self.arena_mut(index.arena)?.get_mut(index.idx)
+    }
+
+    ///
+    /// Returns an iterator over the ids of all arenas with the arenas.
+    ///
+    pub fn arenas(&self) -> impl Iterator<Item = (ArenaId, &Arena<T>)>{
+        self.arenas.iter().map(|(idx, arena)|{
+            (ArenaId{index: idx.index(), generation: idx.gen()}, arena)
+        })
+    }
+
+    ///
+    /// Returns the number of arenas.
+    ///
+    #[inline]
+    pub fn num_arenas(&self) -> usize{
+        self.arenas.num()
+    }
+
+    ///
+    /// Returns the number of elements in all arenas.
+    ///
+    pub fn num(&self) -> usize{
+        self.arenas.values().map(|arena| arena.num()).sum()
+    }
+}
+
+impl<T> Default for ArenaRegistry<T>{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Index<GlobalIdx<T>> for ArenaRegistry<T>{
+    type Output = T;
+
+    fn index(&self, index: GlobalIdx<T>) -> &Self::Output {
+        self.get(index).expect("There is no element at this index with that generation.")
+    }
+}
+
+impl<T> IndexMut<GlobalIdx<T>> for ArenaRegistry<T>{
+    fn index_mut(&mut self, index: GlobalIdx<T>) -> &mut Self::Output {
+        self.get_mut(index).expect("There is no element at this index with that generation.")
+    }
+}
+
+#[cfg(test)]
+mod test{
+    use super::*;
+
+    #[test]
+    fn test_destroy_and_recreate(){
+        let mut registry = ArenaRegistry::new();
+        let a0 = registry.create_arena();
+        let a1 = registry.create_arena();
+
+        let e0 = registry.insert(a0, 0).unwrap();
+        let e1 = registry.insert(a1, 1).unwrap();
+        // Same element slot in different arenas.
+        assert_eq!(e0.idx().index(), e1.idx().index());
+        registry[e1] += 10;
+        assert_eq!(registry.num(), 2);
+
+        let destroyed = registry.destroy_arena(a0).unwrap();
+        assert_eq!(destroyed.get(e0.idx()), Some(&0));
+        assert!(registry.destroy_arena(a0).is_none());
+        assert!(registry.arena(a0).is_none());
+        assert_eq!(registry.get(e0), None);
+        assert_eq!(registry.remove(e0), None);
+
+        // The new arena reuses the slot of the destroyed one.
+        let a2 = registry.create_arena();
+        assert_eq!(a2.index(), a0.index());
+        assert_ne!(a2, a0);
+        let e2 = registry.insert(a2, 2).unwrap();
+
+        // Old handles fail the arena check even though the element check alone would pass.
+        assert_eq!(e2.idx(), e0.idx());
+        assert_eq!(registry.get(e0), None);
+        assert_eq!(registry.get_mut(e0), None);
+        assert_eq!(registry.insert(a0, 3), None);
+        assert_eq!(registry.get(e2), Some(&2));
+        assert_eq!(registry.get(e1), Some(&11));
+
+        // Element generations are checked within a live arena.
+        assert_eq!(registry.remove(e2), Some(2));
+        let e3 = registry.insert(a2, 3).unwrap();
+        assert_eq!(registry.get(e2), None);
+        assert_eq!(registry[e3], 3);
+
+        let ids: Vec<_> = registry.arenas().map(|(id, _)| id).collect();
+        assert_eq!(ids, vec![a2, a1]);
+        assert_eq!(registry.num_arenas(), 2);
+    }
+}
@@ -0,0 +1,274 @@
+use std::{collections::HashMap, fmt};
+
+use crate::*;
+
+///
+/// Identifies a callback registered with Arena::on_remove, to cancel it with Arena::cancel.
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SubscriptionId{
+    slot: usize,
+    id: u64,
+}
+
+struct Watcher<T, G, Tag>{
+    id: u64,
+    index: ArenaIdx<T, G, Tag>,
+    callback: Box<dyn FnOnce(ArenaIdx<T, G, Tag>) + Send>,
+}
+
+impl<T, G, Tag> Watcher<T, G, Tag>{
+    #[inline]
+    fn fire(self){
+        (self.callback)(self.index)
+    }
+}
+
+// SAFETY: The callback is only moved or called through &mut, so sharing a reference to a
+// Watcher between threads never touches it.
+unsafe impl<T: Sync, G: Sync, Tag> Sync for Watcher<T, G, Tag>{}
+
+///
+/// The callbacks of an Arena by slot, maintained with the removal-watch feature.
+///
+/// Clones of the arena do not share the callbacks, and callbacks that are left when the arena
+/// is dropped are dropped without being called. Arenas are cloned and dropped as copies, by
+/// CowArena and RcuArena for example, so only removals fire.
+///
+pub(crate) struct RemovalWatchers<T, G, Tag>{
+    slots: HashMap<usize, Vec<Watcher<T, G, Tag>>>,
+    next_id: u64,
+}
+
+impl<T, G, Tag> RemovalWatchers<T, G, Tag>{
+    pub(crate) fn new() -> Self{
+        Self{
+            slots: HashMap::new(),
+            next_id: 0,
+        }
+    }
+
+    // Fires the callbacks of the element that was in the slot.
+    #[inline]
+    pub(crate) fn fire(&mut self, slot: usize){
+        if self.slots.is_empty(){
+            return;
+        }
+        for watcher in self.slots.remove(&slot).into_iter().flatten(){
+            watcher.fire();
+        }
+    }
+
+    pub(crate) fn fire_all(&mut self){
+        for watcher in std::mem::take(&mut self.slots).into_values().flatten(){
+            watcher.fire();
+        }
+    }
+}
+
+impl<T, G, Tag> Clone for RemovalWatchers<T, G, Tag>{
+    fn clone(&self) -> Self {
+        Self{
+            slots: HashMap::new(),
+            next_id: self.next_id,
+        }
+    }
+}
+
+impl<T, G, Tag> fmt::Debug for RemovalWatchers<T, G, Tag>{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RemovalWatchers").field("watched", &self.slots.len()).finish()
+    }
+}
+
+impl<T, G: GenCounter, Tag> Arena<T, G, Tag>{
+    ///
+    /// Registers a callback that is called once with the key when its element leaves the arena,
+    /// by remove, clear, apply_diff, bump_generation or undoing its insertion in a Journal.
+    /// If the key is already stale the callback is called immediately.
+    /// The callback belongs to this arena: clones do not have it, and dropping the arena drops it
+    /// without calling it. Arenas that copy on write, like CowArena and RcuArena, keep it only until
+    /// their next copy.
+    ///
+    /// Callbacks run while the arena is borrowed, so they cannot access it. They are Send to keep
+    /// the arena Send and Sync.
+    ///
+    /// ```rust
+    /// use std::sync::mpsc;
+    /// use gen_arena::*;
+    ///
+    /// let mut arena = Arena::new();
+    /// let target = arena.insert("camera target");
+    ///
+    /// let (tx, rx) = mpsc::channel();
+    /// let _ = arena.on_remove(target, move |key| tx.send(key).unwrap());
+    ///
+    /// arena.remove(target);
+    /// assert_eq!(rx.try_recv(), Ok(target));
+    /// ```
+    ///
+    pub fn on_remove(&mut self, index: ArenaIdx<T, G, Tag>, callback: impl FnOnce(ArenaIdx<T, G, Tag>) + Send + 'static) -> SubscriptionId{
+        let id = SubscriptionId{
+            slot: index.index(),
+            id: self.watchers.next_id,
+        };
+        self.watchers.next_id += 1;
+        if self.get(index).is_none(){
+            callback(index);
+            return id;
+        }
+        self.watchers.slots.entry(index.index()).or_default().push(Watcher{
+            id: id.id,
+            index,
+            callback: Box::new(callback),
+        });
+        id
+    }
+
+    ///
+    /// Drops the callback without calling it.
+    /// Returns false if it already fired or was cancelled.
+    ///
+    pub fn cancel(&mut self, subscription: SubscriptionId) -> bool{
+        let Some(watchers) = self.watchers.slots.get_mut(&subscription.slot) else{
+            return false;
+        };
+        let Some(i) = watchers.iter().position(|watcher| watcher.id == subscription.id) else{
+            return false;
+        };
+        watchers.remove(i);
+        if watchers.is_empty(){
+            self.watchers.slots.remove(&subscription.slot);
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod test{
+    use std::sync::{Arc, Mutex};
+
+    use super::*;
+
+    // The tags of the callbacks that fired, in order.
+    #[derive(Default)]
+    struct Fired(Arc<Mutex<Vec<usize>>>);
+
+    impl Fired{
+        fn record(&self, tag: usize) -> impl FnOnce(ArenaIdx<u32>) + Send + 'static{
+            let fired = self.0.clone();
+            move |_| fired.lock().unwrap().push(tag)
+        }
+
+        fn tags(&self) -> Vec<usize>{
+            self.0.lock().unwrap().clone()
+        }
+    }
+
+    #[test]
+    fn test_fire_on_remove(){
+        let fired = Fired::default();
+        let mut arena = Arena::new();
+        let a = arena.insert(0);
+        let b = arena.insert(1);
+        let _ = arena.on_remove(a, fired.record(0));
+        let _ = arena.on_remove(a, fired.record(1));
+        let _ = arena.on_remove(b, fired.record(2));
+
+        assert_eq!(arena.remove(a), Some(0));
+        assert_eq!(fired.tags(), [0, 1]);
+
+        // The new element in the slot has no callbacks.
+        let c = arena.insert(2);
+        assert_eq!(c.index(), a.index());
+        arena.remove(c);
+        assert_eq!(fired.tags(), [0, 1]);
+
+        // The key becomes stale without removing the element.
+        arena.bump_generation(b);
+        assert_eq!(fired.tags(), [0, 1, 2]);
+    }
+
+    #[test]
+    fn test_fire_on_clear(){
+        let fired = Fired::default();
+        let mut arena = Arena::new();
+        let keys: Vec<_> = (0..3).map(|i| arena.insert(i)).collect();
+        for (i, &key) in keys.iter().enumerate(){
+            let _ = arena.on_remove(key, fired.record(i));
+        }
+        arena.clear();
+        let mut tags = fired.tags();
+        tags.sort();
+        assert_eq!(tags, [0, 1, 2]);
+        arena.clear();
+        assert_eq!(fired.tags().len(), 3);
+
+        // Clones do not share the callbacks, and dropping an arena does not call them.
+        let key = arena.insert(3);
+        let _ = arena.on_remove(key, fired.record(3));
+        drop(arena.clone());
+        assert_eq!(fired.tags().len(), 3);
+        drop(arena);
+        assert_eq!(fired.tags().len(), 3);
+    }
+
+    #[test]
+    fn test_copy_on_write(){
+        let fired = Fired::default();
+
+        // Writing to a shared CowArena copies it and drops the old copy with the snapshot.
+        let mut cow = CowArena::new();
+        let a = cow.insert(0);
+        let _ = cow.make_mut().on_remove(a, fired.record(0));
+        let snapshot = cow.clone();
+        let _ = cow.insert(1);
+        drop(snapshot);
+        assert_eq!(cow.make_mut().get(a), Some(&0));
+        assert!(fired.tags().is_empty());
+
+        // Every update of an RcuArena copies the arena and drops the previous version.
+        #[cfg(feature = "arc-swap")]
+        {
+            let rcu = RcuArena::new();
+            let b = rcu.insert(2);
+            rcu.update(|arena| arena.on_remove(b, fired.record(1)));
+            let _ = rcu.insert(3);
+            let _ = rcu.insert(4);
+            assert_eq!(rcu.load().get(b), Some(&2));
+            assert!(fired.tags().is_empty());
+        }
+    }
+
+    #[test]
+    fn test_cancel(){
+        let fired = Fired::default();
+        let mut arena = Arena::new();
+        let a = arena.insert(0);
+        let first = arena.on_remove(a, fired.record(0));
+        let second = arena.on_remove(a, fired.record(1));
+
+        assert!(arena.cancel(first));
+        assert!(!arena.cancel(first));
+        arena.remove(a);
+        assert_eq!(fired.tags(), [1]);
+        assert!(!arena.cancel(second));
+    }
+
+    #[test]
+    fn test_stale_at_registration(){
+        let fired = Fired::default();
+        let mut arena = Arena::new();
+        let a = arena.insert(0);
+        arena.remove(a);
+
+        let subscription = arena.on_remove(a, fired.record(0));
+        assert_eq!(fired.tags(), [0]);
+        assert!(!arena.cancel(subscription));
+
+        // The slot is reused, the stale key does not watch the new element.
+        let b = arena.insert(1);
+        arena.remove(b);
+        assert_eq!(fired.tags(), [0]);
+    }
+}
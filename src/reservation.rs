@@ -0,0 +1,160 @@
+use std::fmt;
+
+use crate::*;
+
+///
+/// The reason Arena::fill failed.
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum FillError{
+    /// The reservation was already filled.
+    Filled,
+    /// The key is not a reservation, or it was released or invalidated by clear.
+    NotReserved,
+}
+
+impl fmt::Display for FillError{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self{
+            Self::Filled => write!(f, "the reservation was already filled"),
+            Self::NotReserved => write!(f, "the key is not a reserved slot"),
+        }
+    }
+}
+
+impl std::error::Error for FillError{}
+
+impl<T, G: GenCounter, Tag> Arena<T, G, Tag>{
+    ///
+    /// Reserves n slots and returns their keys, so values referring to each other can be
+    /// built before they are inserted with fill.
+    ///
+    /// Reserved slots hold no element, get returns None for their keys and they are neither
+    /// iterated nor counted by num. Freed slots are reserved first, like insert would use them.
+    /// Slots that are never filled stay reserved until they are released or the arena is cleared.
+    ///
+    /// ```rust
+    /// use gen_arena::*;
+    ///
+    /// struct Node{
+    ///     next: ArenaIdx<Node>,
+    /// }
+    ///
+    /// let mut arena = Arena::new();
+    /// let keys = arena.reserve_keys(3);
+    /// for (i, &key) in keys.iter().enumerate(){
+    ///     arena.fill(key, Node{next: keys[(i + 1) % 3]}).unwrap();
+    /// }
+    ///
+    /// let next = arena[keys[2]].next;
+    /// assert_eq!(next, keys[0]);
+    /// ```
+    ///
+    pub fn reserve_keys(&mut self, n: usize) -> Vec<ArenaIdx<T, G, Tag>>{
+        (0..n).map(|_|{
+            let (index, generation) = self.raw_mut().reserve();
+            ArenaIdx::from_parts(index, generation)
+        }).collect()
+    }
+
+    ///
+    /// Inserts the value into a slot reserved by reserve_keys, so the key becomes valid.
+    /// Drops the value and returns why if the key is not an unfilled reservation.
+    ///
+    pub fn fill(&mut self, index: ArenaIdx<T, G, Tag>, val: T) -> Result<(), FillError>{
+        if self.raw_mut().fill(index.index(), index.gen(), val).is_err(){
+            return Err(match self.get(index){
+                Some(_) => FillError::Filled,
+                None => FillError::NotReserved,
+            });
+        }
+        #[cfg(feature = "leak-detect")]
+        self.leaks.record(index.index());
+        #[cfg(feature = "metrics")]
+        self.metrics.inserted(1, self.num(), self.slots());
+        Ok(())
+    }
+
+    ///
+    /// Returns an unfilled reservation to the free list, which invalidates its key.
+    /// Returns false if the key is not an unfilled reservation.
+    ///
+    pub fn release(&mut self, index: ArenaIdx<T, G, Tag>) -> bool{
+        self.raw_mut().release(index.index(), index.gen())
+    }
+
+    ///
+    /// Returns true if the key is a reservation that is not filled yet.
+    ///
+    #[inline]
+    pub fn is_reserved(&self, index: ArenaIdx<T, G, Tag>) -> bool{
+        self.raw().is_reserved(index.index(), index.gen())
+    }
+}
+
+#[cfg(test)]
+mod test{
+    use super::*;
+
+    #[test]
+    fn test_fill_out_of_order(){
+        let mut arena = Arena::new();
+        let a = arena.insert(0);
+        let b = arena.insert(1);
+        arena.remove(a);
+
+        // The freed slot is reserved first.
+        let keys = arena.reserve_keys(3);
+        assert_eq!(keys.iter().map(|key| key.index()).collect::<Vec<_>>(), [0, 2, 3]);
+        assert_eq!(keys[0].gen(), 1);
+        assert!(keys.iter().all(|&key| arena.is_reserved(key) && arena.get(key).is_none()));
+        assert_eq!(arena.num(), 1);
+        assert_eq!(arena.iter().count(), 1);
+
+        // Inserting does not take reserved slots.
+        let c = arena.insert(4);
+        assert_eq!(c.index(), 4);
+
+        arena.fill(keys[2], 30).unwrap();
+        arena.fill(keys[0], 10).unwrap();
+        assert_eq!(arena.num(), 4);
+        assert_eq!(arena.values().copied().collect::<Vec<_>>(), [10, 1, 30, 4]);
+        arena.fill(keys[1], 20).unwrap();
+        assert_eq!(arena.values().copied().collect::<Vec<_>>(), [10, 1, 20, 30, 4]);
+
+        assert_eq!(arena.fill(keys[1], 21), Err(FillError::Filled));
+        assert_eq!(arena.fill(a, 0), Err(FillError::NotReserved));
+        assert_eq!(arena.fill(b, 0).unwrap_err().to_string(), "the reservation was already filled");
+        assert!(!arena.release(keys[1]));
+        assert_eq!(arena[keys[1]], 20);
+    }
+
+    #[test]
+    fn test_release(){
+        let mut arena = Arena::<u32>::new();
+        let keys = arena.reserve_keys(4);
+        assert_eq!(arena.num(), 0);
+
+        // Released slots are reused with a new generation, their keys are stale.
+        assert!(arena.release(keys[1]));
+        assert!(!arena.release(keys[1]));
+        let a = arena.insert(1);
+        assert_eq!((a.index(), a.gen()), (1, 1));
+        assert_eq!(arena.fill(keys[1], 0), Err(FillError::NotReserved));
+
+        // Reserved slots survive rebuilding the free list.
+        arena.canonicalize_free_list();
+        let b = arena.insert(2);
+        assert_eq!(b.index(), 4);
+        arena.fill(keys[3], 3).unwrap();
+
+        // Clearing releases the remaining reservations.
+        arena.clear();
+        assert!(!arena.is_reserved(keys[0]));
+        assert_eq!(arena.fill(keys[2], 0), Err(FillError::NotReserved));
+        assert_eq!(arena.free_indices().collect::<Vec<_>>(), [0, 1, 2, 3, 4]);
+        let keys = arena.reserve_keys(2);
+        assert_eq!(keys.iter().map(|key| (key.index(), key.gen())).collect::<Vec<_>>(), [(0, 1), (1, 2)]);
+    }
+}
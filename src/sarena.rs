@@ -1,116 +1,505 @@
-
-use std::marker::PhantomData;
+use std::{cell::{Cell, UnsafeCell}, iter::FusedIterator, marker::PhantomData, mem::{ManuallyDrop, MaybeUninit}};
 
 use crate::*;
+use crate::raw::RawArena;
+
+///
+/// The former key type of SArena, which now uses ArenaIdx like all other arenas.
+///
+#[deprecated(note = "SArena uses ArenaIdx now.")]
+pub type SArenaIdx<T> = ArenaIdx<T>;
 
+// A cell of an SArena. Unlike in RawCell the free list link is a u32,
+// so with a narrow generation Freed cells are not larger than Allocated ones.
+#[derive(Clone)]
+enum SCell<T, G>{
+    Allocated{val: T, generation: G},
+    Freed{next: Option<u32>, generation: G},
+}
+
+// The cells moved out of an SArena, only the initialized ones are yielded.
+type RawCells<T, const N: usize, G> = std::iter::Take<std::array::IntoIter<UnsafeCell<MaybeUninit<SCell<T, G>>>, N>>;
+
+///
+/// A Generational Arena with a fixed capacity of N elements, stored inline.
 ///
-/// An index referring to an index and epoch in an Arena.
+/// Insertion only needs a shared reference, because it only writes to freed cells and existing
+/// references only point into allocated ones. Cells, free list and counter are therefore
+/// interior mutable, which makes the arena usable through &self on a single thread only.
 ///
-#[derive(Debug, PartialEq, Eq)]
-pub struct SArenaIdx<T>{
-    index: usize,
-    generation: usize,
-    _ty: PhantomData<T>,
+/// SArena is Send if T is, but never Sync: two threads inserting through a shared reference
+/// could both take the head of the free list. AtomicArena and ConcurrentArena support
+/// inserting from several threads.
+///
+/// Cells are only initialized when they are first used, so creating an arena is O(1)
+/// and does not touch the memory of the cells.
+///
+/// G is the generation type of the cells and keys, see GenCounter. A narrow generation
+/// makes the cells smaller, while keys keep their usize index. The saturating integers retire
+/// a slot once its generation is exhausted, with u8 after 255 reuses, which reduces the capacity
+/// by one. The Wrapping integers reuse the slot forever, at the risk of a key that survived
+/// 2^bits - 1 reuses referring to the new element.
+///
+///```rust
+/// use gen_arena::*;
+/// use std::num::Wrapping;
+///
+/// let arena = SArena::<u32, 32, Wrapping<u8>>::new();
+///
+/// let i1 = arena.insert(1);
+/// assert_eq!(i1.gen(), Wrapping(0));
+/// assert!(std::mem::size_of_val(&arena) < std::mem::size_of::<SArena<u32, 32>>());
+///```
+///
+pub struct SArena<T, const N: usize, G = usize>{
+    cells: [UnsafeCell<MaybeUninit<SCell<T, G>>>; N],
+    // The number of initialized cells. The cells after them were never used
+    // and are taken once the list of Freed cells is empty.
+    init: Cell<usize>,
+    freed: Cell<Option<usize>>,
+    // The length of the list of Freed cells plus the cells that are not initialized.
+    free: Cell<usize>,
+    num: Cell<usize>,
+    // Keeps the arena from being Sync even if the fields above change.
+    _not_sync: PhantomData<Cell<()>>,
 }
 
-impl<T> SArenaIdx<T>{
-    pub fn new(index: usize, generation: usize) -> Self{
+impl<T, const N: usize, G: GenCounter> SArena<T, N, G>{
+    ///
+    /// Creates a new empty SArena.
+    /// The arena is built on the stack, use new_boxed for large arenas on the heap.
+    ///
+    /// It is a const fn, so an arena can live in a static. SArena is not Sync,
+    /// so the static has to wrap it, for example in a Mutex:
+    ///
+    ///```rust
+    /// use gen_arena::*;
+    /// use std::sync::Mutex;
+    ///
+    /// static REGISTRY: Mutex<SArena<&str, 64>> = Mutex::new(SArena::new());
+    ///
+    /// let i1 = REGISTRY.lock().unwrap().insert("device");
+    /// assert_eq!(REGISTRY.lock().unwrap().get(i1), Some(&"device"));
+    ///```
+    ///
+    pub const fn new() -> Self{
+        const{
+            assert!(N <= u32::MAX as usize, "An SArena has at most u32::MAX cells.");
+        }
         Self{
-            index,
-            generation,
-            _ty: PhantomData,
+            cells: [const{UnsafeCell::new(MaybeUninit::uninit())}; N],
+            init: Cell::new(0),
+            freed: Cell::new(None),
+            free: Cell::new(N),
+            num: Cell::new(0),
+            _not_sync: PhantomData,
         }
     }
 
-    #[inline]
-    pub fn index(&self) -> usize{
-        self.index
+    ///
+    /// Creates a new empty SArena on the heap.
+    ///
+    /// The arena is written into the allocation directly. Box::new(SArena::new()) builds
+    /// the arena on the stack first and moves it, which overflows the stack for large N,
+    /// especially in debug builds.
+    ///
+    ///```rust
+    /// use gen_arena::*;
+    ///
+    /// let arena = SArena::<[u8; 64], 4096>::new_boxed();
+    ///
+    /// let i1 = arena.insert([1; 64]);
+    /// assert_eq!(arena.get(i1), Some(&[1; 64]));
+    ///```
+    ///
+    pub fn new_boxed() -> Box<Self>{
+        const{
+            assert!(N <= u32::MAX as usize, "An SArena has at most u32::MAX cells.");
+        }
+        let mut boxed = Box::<Self>::new_uninit();
+        let ptr = boxed.as_mut_ptr();
+        // SAFETY: The pointer is valid for writes and aligned, and every field but the cells is written once.
+        // The cells are MaybeUninit and stay uninitialized, see init. All fields are initialized afterwards.
+        unsafe{
+            std::ptr::addr_of_mut!((*ptr).init).write(Cell::new(0));
+            std::ptr::addr_of_mut!((*ptr).freed).write(Cell::new(None));
+            std::ptr::addr_of_mut!((*ptr).free).write(Cell::new(N));
+            std::ptr::addr_of_mut!((*ptr).num).write(Cell::new(0));
+            std::ptr::addr_of_mut!((*ptr)._not_sync).write(PhantomData);
+            boxed.assume_init()
+        }
+    }
+
+    ///
+    /// Creates an SArena holding the values in order, the key of values[i] has index i and generation 0.
+    /// Fails to compile if M is larger than N.
+    ///
+    ///```rust
+    /// use gen_arena::*;
+    ///
+    /// let arena = SArena::<_, 4>::from_array(["a", "b"]);
+    ///
+    /// assert_eq!(arena.get(ArenaIdx::from_raw_parts(1, 0)), Some(&"b"));
+    /// assert_eq!(arena.remaining(), 2);
+    ///```
+    ///
+    ///```compile_fail
+    /// use gen_arena::*;
+    ///
+    /// let arena = SArena::<_, 1>::from_array(["a", "b"]);
+    ///```
+    ///
+    pub fn from_array<const M: usize>(values: [T; M]) -> Self{
+        const{
+            assert!(M <= N, "The array does not fit into the SArena.");
+        }
+        let arena = Self::new();
+        for val in values{
+            let _ = arena.insert(val);
+        }
+        arena
+    }
+
+    ///
+    /// Creates an SArena holding the values in order, like from_array.
+    /// Returns an error if the iterator yields more than N values, the values are dropped in that case.
+    ///
+    ///```rust
+    /// use gen_arena::*;
+    ///
+    /// let arena = SArena::<_, 4>::try_from_iter(0..3).unwrap();
+    /// assert_eq!(arena.keys().map(|key| key.index()).collect::<Vec<_>>(), [0, 1, 2]);
+    ///
+    /// assert!(SArena::<_, 4>::try_from_iter(0..5).is_err());
+    ///```
+    ///
+    pub fn try_from_iter(values: impl IntoIterator<Item = T>) -> Result<Self, OverflowError>{
+        let arena = Self::new();
+        for val in values{
+            if arena.try_insert(val).is_err(){
+                return Err(OverflowError{capacity: N});
+            }
+        }
+        Ok(arena)
     }
 
     #[inline]
-    pub fn gen(&self) -> usize{
-        self.generation
+    fn cell(&self, index: usize) -> Option<&SCell<T, G>>{
+        // SAFETY: Cells are only written through &self by try_insert and insert_with, and only if they are freed.
+        // References returned from &self methods only point into allocated cells and the ones used
+        // internally do not outlive the method. No user code runs while a cell is written,
+        // and the arena is not Sync, so no other thread can write concurrently.
+        // Cells before init are initialized.
+        if index < self.init.get(){
+            Some(unsafe{(*self.cells[index].get()).assume_init_ref()})
+        }
+        else{
+            None
+        }
     }
-}
 
-impl<T> Clone for SArenaIdx<T>{
+    // Returns the initialized cells.
     #[inline]
-    fn clone(&self) -> Self {
-        *self
+    fn cells_mut(&mut self) -> &mut [SCell<T, G>]{
+        let init = *self.init.get_mut();
+        // SAFETY: UnsafeCell<X> and MaybeUninit<X> have the same layout as X, the cells before init
+        // are initialized and &mut self guarantees exclusive access.
+        unsafe{&mut *(&mut self.cells[..init] as *mut [UnsafeCell<MaybeUninit<SCell<T, G>>>] as *mut [SCell<T, G>])}
     }
-}
 
-impl<T> Copy for SArenaIdx<T>{}
+    // Initializes the next cell, which has to exist.
+    fn push_cell(&mut self, cell: SCell<T, G>){
+        let init = *self.init.get_mut();
+        self.cells[init].get_mut().write(cell);
+        *self.init.get_mut() = init + 1;
+    }
 
-pub struct SArena<T, const N: usize>{
-    cells: [ArenaCell<T>; N],
-    freed: Option<usize>,
-    num: usize,
-}
+    // Takes the head of the list of Freed cells, or initializes the first unused cell if the list is empty.
+    // The cell stays Freed but is off the list, the caller allocates it or puts it back.
+    //
+    // If the head is not a freed cell the list is dropped and an unused cell is tried instead.
+    // The cells can only be relinked through &mut self, as insert_with may hold one off the list,
+    // so the dropped cells are unused until canonicalize_free_list.
+    fn take_slot(&self) -> Result<(usize, G), InsertErrorKind>{
+        if let Some(i) = self.freed.get(){
+            let Some(&SCell::Freed{next, generation}) = self.cell(i) else{
+                self.freed.set(None);
+                self.free.set(N - self.init.get());
+                return self.init_slot().ok_or(InsertErrorKind::CorruptFreeList{slot: i});
+            };
+            self.freed.set(next.map(|next| next as usize));
+            self.free.set(self.free.get() - 1);
+            return Ok((i, generation));
+        }
+        self.init_slot().ok_or(InsertErrorKind::Full)
+    }
+
+    // Initializes the first unused cell as Freed and off the list.
+    fn init_slot(&self) -> Option<(usize, G)>{
+        let i = self.init.get();
+        if i == N{
+            return None;
+        }
+        // SAFETY: The cell is not initialized, so no reference into it exists, see cell.
+        unsafe{
+            (*self.cells[i].get()).write(SCell::Freed{next: None, generation: G::FIRST});
+        }
+        self.init.set(i + 1);
+        self.free.set(self.free.get() - 1);
+        Some((i, G::FIRST))
+    }
+
+    // Frees the cell at index with the generation after the given one,
+    // which is put on the list of Freed cells unless it is retired.
+    fn free(&mut self, index: usize, generation: G) -> SCell<T, G>{
+        let generation = generation.next();
+        let retired = generation == G::RETIRED;
+        let next = if retired {None} else {self.freed.get().map(|next| next as u32)};
+        let cell = std::mem::replace(&mut self.cells_mut()[index], SCell::Freed{next, generation});
+        if !retired{
+            self.freed.set(Some(index));
+            *self.free.get_mut() += 1;
+        }
+        *self.num.get_mut() -= 1;
+        cell
+    }
 
-impl<T, const N: usize> SArena<T, N>{
     ///
-    /// Creates a new empty SArena.
+    /// Drops all elements, increases their generations and resets the list of Freed cells,
+    /// so the arena can be filled to its capacity again.
     ///
-    ///```rust
+    /// ```rust
     /// use gen_arena::*;
     ///
-    /// let arena = SArena::<i32, 100>::new();
-    ///```
+    /// let mut arena = SArena::<_, 2>::new();
     ///
-    pub fn new() -> Self{
-        let mut i = 0;
-        let cells: [ArenaCell<T>; N] = [(); N].map(|()|{
-            let ret = {
-                if i < N -1{
-                    ArenaCell::Freed{next: Some(i +1), generation: 0}
-                }
-                else{
-                    ArenaCell::Freed{next: None, generation: 0}
+    /// let i1 = arena.insert(1);
+    /// let i2 = arena.insert(2);
+    ///
+    /// arena.clear();
+    ///
+    /// assert_eq!(arena.get(i1), None);
+    /// assert_eq!(arena.get(i2), None);
+    /// assert_eq!(arena.len(), 0);
+    /// ```
+    ///
+    pub fn clear(&mut self){
+        for cell in self.cells_mut(){
+            if let SCell::Allocated{generation, ..} = cell{
+                *cell = SCell::Freed{next: None, generation: generation.next()};
+            }
+        }
+        self.relink();
+    }
+
+    // Rebuilds the list of Freed cells and the counters from the cells, ignoring their links.
+    fn relink(&mut self){
+        // Linking from the back keeps the list in ascending order and skips retired cells.
+        let mut freed = None;
+        let mut free = 0;
+        let mut num = 0;
+        for (i, cell) in self.cells_mut().iter_mut().enumerate().rev(){
+            match cell{
+                SCell::Allocated{..} => num += 1,
+                SCell::Freed{next, generation} => {
+                    if *generation == G::RETIRED{
+                        *next = None;
+                    }
+                    else{
+                        *next = freed;
+                        freed = Some(i as u32);
+                        free += 1;
+                    }
                 }
+            }
+        }
+        self.freed.set(freed.map(|freed| freed as usize));
+        self.free.set(free + N - *self.init.get_mut());
+        self.num.set(num);
+    }
+
+    ///
+    /// Moves the elements of the Arena into an SArena at the same slots and with the same generations,
+    /// so keys of the arena stay valid. Freed slots keep their generations as well.
+    ///
+    /// Fails if the highest occupied slot is not smaller than N. The error returns the arena.
+    ///
+    /// ```rust
+    /// use gen_arena::*;
+    ///
+    /// let mut arena = Arena::new();
+    /// let i0 = arena.insert("a");
+    /// let i1 = arena.insert("b");
+    /// arena.remove(i0);
+    ///
+    /// let sarena = SArena::<_, 2>::try_from_arena(arena).unwrap();
+    /// assert_eq!(sarena.get(i1), Some(&"b"));
+    /// assert_eq!(sarena.get(i0), None);
+    ///
+    /// let err = SArena::<_, 1>::try_from_arena(Arena::from(&sarena)).unwrap_err();
+    /// assert_eq!(err.required, 2);
+    /// assert_eq!(err.into_arena()[i1], "b");
+    /// ```
+    ///
+    pub fn try_from_arena(mut arena: Arena<T, G>) -> Result<Self, CapacityError<T, G>>{
+        let required = arena.raw().cells.iter()
+            .rposition(|cell| matches!(cell, RawCell::Allocated{..}))
+            .map_or(0, |i| i + 1);
+        if required > N{
+            return Err(CapacityError{
+                capacity: N,
+                required,
+                arena: Box::new(arena),
+            });
+        }
+
+        let mut sarena = Self::new();
+        for cell in std::mem::take(&mut arena.raw_mut().cells).into_iter().take(N){
+            sarena.push_cell(match cell{
+                RawCell::Allocated{val, generation} => SCell::Allocated{val, generation},
+                RawCell::Freed{generation, ..} => SCell::Freed{next: None, generation},
+            });
+        }
+        sarena.relink();
+        Ok(sarena)
+    }
+
+    ///
+    /// Removes all elements for which f returns false, in one pass and without allocating.
+    ///
+    /// ```rust
+    /// use gen_arena::*;
+    ///
+    /// let mut arena = SArena::<_, 4>::new();
+    ///
+    /// let i1 = arena.insert(1);
+    /// let i2 = arena.insert(2);
+    ///
+    /// arena.retain(|_, val| *val % 2 == 0);
+    ///
+    /// assert_eq!(arena.get(i1), None);
+    /// assert_eq!(arena.get(i2), Some(&2));
+    /// ```
+    ///
+    pub fn retain(&mut self, mut f: impl FnMut(ArenaIdx<T, G>, &mut T) -> bool){
+        for i in 0..*self.init.get_mut(){
+            let SCell::Allocated{val, generation} = &mut self.cells_mut()[i] else{
+                continue;
             };
-            i += 1;
-            ret
-        });
-        
-        Self{
-            cells,
-            freed: Some(0),
-            num: 0,
+            let generation = *generation;
+            if f(ArenaIdx::from_parts(i, generation), val){
+                continue;
+            }
+            // Dropped after the arena is consistent again, in case drop panics.
+            drop(self.free(i, generation));
         }
     }
 
     ///
     /// Tries to insert a value into the Arena.
-    /// Unlike Arena::try_insert this does not need a mut ref 
-    /// because the array stays in the same place all the time.
+    /// Unlike Arena::try_insert this does not need a mut ref
+    /// because it only writes to a freed cell, which no reference points into.
     ///
-    #[must_use]
-    pub fn try_insert(&self, val: T) -> Result<SArenaIdx<T>, T>{
-
-        // SAFETY: 
-        // - Insertion abborts if cell is iccupied hence only freed cells are affected.
-        // - The memory location of cells does not change on insertion unlike Vec.
+    /// Returns val with the reason as Err if the arena is full, which is_full tells beforehand.
+    /// remaining is the number of insertions that will still succeed.
+    ///
+    /// If the list of freed slots is corrupt, the link to the slot that is not free is dropped
+    /// together with the rest of the list and the insertion is retried with an unused slot.
+    /// The dropped slots are only used again after canonicalize_free_list.
+    ///
+    /// ```rust
+    /// use gen_arena::*;
+    ///
+    /// let arena = SArena::<_, 2>::new();
+    ///
+    /// assert!(arena.try_insert(1).is_ok());
+    /// assert_eq!(arena.remaining(), 1);
+    /// assert!(arena.try_insert(2).is_ok());
+    ///
+    /// assert!(arena.is_full());
+    /// let err = arena.try_insert(3).unwrap_err();
+    /// assert_eq!(err.kind, InsertErrorKind::Full);
+    /// assert_eq!(err.into_value(), 3);
+    /// ```
+    ///
+    pub fn try_insert(&self, val: T) -> Result<ArenaIdx<T, G>, InsertError<T>>{
+        let (i, generation) = match self.take_slot(){
+            Ok(slot) => slot,
+            Err(kind) => return Err(InsertError::new(val, kind)),
+        };
+        // SAFETY: The cell is freed, so no reference into it exists, see cell.
+        // Overwriting a freed cell drops no T, so no user code runs while it is written.
         unsafe{
-            let selfp = (self as *const Self) as *mut Self;
-            match self.freed{
-                Some(i) => {
-                    if let ArenaCell::Freed{next, generation} = self.cells[i]{
-                        (*selfp).freed = next;
-                        (*selfp).cells[i] = ArenaCell::Allocated{
-                            val,
-                            generation,
-                        };
-                        (*selfp).num += 1;
-                        Ok(SArenaIdx::new(i, generation))
-                    }
-                    else{
-                        Err(val)
-                    }
+            (*self.cells[i].get()).write(SCell::Allocated{
+                val,
+                generation,
+            });
+        }
+        self.num.set(self.num.get() + 1);
+        Ok(ArenaIdx::from_parts(i, generation))
+    }
+
+    ///
+    /// Inserts the value returned by f, which is given the key of the value.
+    /// Returns Err without calling f if the arena is full.
+    ///
+    /// Takes &self like try_insert. The slot is taken off the list of Freed cells while f runs,
+    /// so f may insert into the arena as well. If f panics the slot is freed again.
+    ///
+    /// ```rust
+    /// use gen_arena::*;
+    ///
+    /// struct Node{
+    ///     this: ArenaIdx<Node>,
+    /// }
+    ///
+    /// let arena = SArena::<_, 1>::new();
+    ///
+    /// let i1 = arena.insert_with(|this| Node{this}).unwrap();
+    /// assert_eq!(arena.get(i1).unwrap().this, i1);
+    ///
+    /// assert!(arena.insert_with(|this| Node{this}).is_err());
+    /// ```
+    ///
+    #[allow(clippy::result_unit_err)]
+    pub fn insert_with(&self, f: impl FnOnce(ArenaIdx<T, G>) -> T) -> Result<ArenaIdx<T, G>, ()>{
+        // Puts the slot back onto the list of Freed cells if f panics.
+        struct Relink<'a, T, const N: usize, G: GenCounter>{
+            arena: &'a SArena<T, N, G>,
+            index: usize,
+            generation: G,
+        }
+        impl<T, const N: usize, G: GenCounter> Drop for Relink<'_, T, N, G>{
+            fn drop(&mut self){
+                // SAFETY: The cell is freed, see try_insert.
+                unsafe{
+                    (*self.arena.cells[self.index].get()).write(SCell::Freed{
+                        next: self.arena.freed.get().map(|next| next as u32),
+                        generation: self.generation,
+                    });
                 }
-                None => Err(val)
+                self.arena.freed.set(Some(self.index));
+                self.arena.free.set(self.arena.free.get() + 1);
             }
         }
+
+        let Ok((i, generation)) = self.take_slot() else{
+            return Err(());
+        };
+        let relink = Relink{arena: self, index: i, generation};
+        let index = ArenaIdx::from_parts(i, generation);
+        let val = f(index);
+        std::mem::forget(relink);
+        // SAFETY: The cell is freed and was not on the list of Freed cells while f ran,
+        // so nothing was inserted into it, see try_insert.
+        unsafe{
+            (*self.cells[i].get()).write(SCell::Allocated{
+                val,
+                generation,
+            });
+        }
+        self.num.set(self.num.get() + 1);
+        Ok(index)
     }
 
     ///
@@ -119,7 +508,7 @@ impl<T, const N: usize> SArena<T, N>{
     ///
     /// # Example:
     ///
-    /// ```rust 
+    /// ```rust
     /// use gen_arena::*;
     ///
     /// let arena = SArena::<_, 100>::new();
@@ -133,7 +522,7 @@ impl<T, const N: usize> SArena<T, N>{
     /// ```
     ///
     #[must_use]
-    pub fn insert(&self, val: T) -> SArenaIdx<T>{
+    pub fn insert(&self, val: T) -> ArenaIdx<T, G>{
         match self.try_insert(val){
             Ok(index) => index,
             Err(_val) => panic!("Insertion not successfull."),
@@ -142,25 +531,44 @@ impl<T, const N: usize> SArena<T, N>{
 
     ///
     /// Removes the cell from the arena and increaces its generation.
+    /// Returns the value if the index is still valid, stale indices leave the cell untouched.
     ///
-    pub fn remove(&mut self, index: SArenaIdx<T>){
-        if let ArenaCell::Allocated{val: _, generation} = &self.cells[index.index]{
-            self.cells[index.index] = ArenaCell::Freed{
-                next: self.freed,
-                generation: generation + 1,
-            };
-            self.num -= 1;
-            self.freed = Some(index.index);
+    /// ```rust
+    /// use gen_arena::*;
+    ///
+    /// let mut arena = SArena::<_, 100>::new();
+    ///
+    /// let i1 = arena.insert(1);
+    ///
+    /// assert_eq!(arena.remove(i1), Some(1));
+    /// assert_eq!(arena.remove(i1), None);
+    ///
+    /// let i2 = arena.insert(2);
+    ///
+    /// // The stale index does not remove the new value.
+    /// assert_eq!(arena.remove(i1), None);
+    /// assert_eq!(*arena.get(i2).unwrap(), 2);
+    /// ```
+    ///
+    pub fn remove(&mut self, index: ArenaIdx<T, G>) -> Option<T>{
+        if !self.contains(index){
+            return None;
+        }
+        match self.free(index.index(), index.gen()){
+            SCell::Allocated{val, ..} => Some(val),
+            SCell::Freed{..} => unreachable!(),
         }
     }
 
     ///
     /// Gets the Generation for a given index.
+    /// Returns None if the index is not smaller than N.
     ///
-    pub fn gen(&self, index: usize) -> usize{
-        match self.cells[index]{
-            ArenaCell::Freed{generation, ..} => generation,
-            ArenaCell::Allocated{generation, ..} => generation,
+    pub fn gen(&self, index: usize) -> Option<G>{
+        match self.cell(index){
+            Some(SCell::Freed{generation, ..} | SCell::Allocated{generation, ..}) => Some(*generation),
+            None if index < N => Some(G::FIRST),
+            None => None,
         }
     }
 
@@ -182,9 +590,9 @@ impl<T, const N: usize> SArena<T, N>{
     ///
     /// ```
     ///
-    pub fn get(&self, index: SArenaIdx<T>) -> Option<&T>{
-        if let ArenaCell::Allocated{val, generation} = &self.cells[index.index]{
-            if *generation == index.generation{
+    pub fn get(&self, index: ArenaIdx<T, G>) -> Option<&T>{
+        if let Some(SCell::Allocated{val, generation}) = self.cell(index.index()){
+            if *generation == index.gen(){
                 Some(val)
             }
             else{
@@ -196,6 +604,82 @@ impl<T, const N: usize> SArena<T, N>{
         }
     }
 
+    ///
+    /// Returns a reference to the value at the index without checking the index and generation.
+    ///
+    /// # Safety
+    ///
+    /// The index has to refer to an element of the arena, that is contains(index) has to be true.
+    /// This is checked with a debug assertion.
+    ///
+    /// ```rust
+    /// use gen_arena::*;
+    ///
+    /// let arena = SArena::<_, 4>::new();
+    /// let i1 = arena.insert(1);
+    ///
+    /// // SAFETY: i1 was just inserted and nothing was removed.
+    /// assert_eq!(unsafe{arena.get_unchecked(i1)}, &1);
+    /// ```
+    ///
+    #[inline]
+    pub unsafe fn get_unchecked(&self, index: ArenaIdx<T, G>) -> &T{
+        debug_assert!(self.contains(index), "get_unchecked with an index that is out of range or stale.");
+        // SAFETY: The caller guarantees that the index is in range and its cell allocated,
+        // so it is initialized. References to allocated cells are valid, see cell.
+        match unsafe{(*self.cells.get_unchecked(index.index()).get()).assume_init_ref()}{
+            SCell::Allocated{val, ..} => val,
+            SCell::Freed{..} => unsafe{std::hint::unreachable_unchecked()},
+        }
+    }
+
+    ///
+    /// Returns a mutable reference to the value at the index without checking the index and generation.
+    ///
+    /// # Safety
+    ///
+    /// The index has to refer to an element of the arena, see get_unchecked.
+    ///
+    #[inline]
+    pub unsafe fn get_unchecked_mut(&mut self, index: ArenaIdx<T, G>) -> &mut T{
+        debug_assert!(self.contains(index), "get_unchecked_mut with an index that is out of range or stale.");
+        // SAFETY: The caller guarantees that the index is in range and its cell allocated.
+        match unsafe{self.cells_mut().get_unchecked_mut(index.index())}{
+            SCell::Allocated{val, ..} => val,
+            SCell::Freed{..} => unsafe{std::hint::unreachable_unchecked()},
+        }
+    }
+
+    ///
+    /// Returns true if the key refers to an element of the arena.
+    /// Out of range and stale keys return false.
+    ///
+    /// ```rust
+    /// use gen_arena::*;
+    ///
+    /// let mut arena = SArena::<_, 4>::new();
+    ///
+    /// let i1 = arena.insert(1);
+    /// assert!(arena.contains(i1));
+    ///
+    /// arena.remove(i1);
+    /// assert!(!arena.contains(i1));
+    /// assert!(!arena.contains(ArenaIdx::from_raw_parts(4, 0)));
+    /// ```
+    ///
+    #[inline]
+    pub fn contains(&self, index: ArenaIdx<T, G>) -> bool{
+        matches!(self.cell(index.index()), Some(SCell::Allocated{generation, ..}) if *generation == index.gen())
+    }
+
+    ///
+    /// Returns true if the slot at index holds an element of any generation.
+    ///
+    #[inline]
+    pub fn contains_slot(&self, index: usize) -> bool{
+        matches!(self.cell(index), Some(SCell::Allocated{..}))
+    }
+
     ///
     /// Returns an optional reference to a cell with any generation.
     ///
@@ -218,7 +702,7 @@ impl<T, const N: usize> SArena<T, N>{
     ///
     ///
     pub fn get_any(&self, index: usize) -> Option<&T>{
-        if let ArenaCell::Allocated{val, generation: _} = &self.cells[index]{
+        if let Some(SCell::Allocated{val, generation: _}) = self.cell(index){
             Some(val)
         }
         else{
@@ -244,7 +728,7 @@ impl<T, const N: usize> SArena<T, N>{
     ///
     /// ```
     ///
-    pub fn getn<const M: usize>(&self, indices: [SArenaIdx<T>; M]) -> [Option<&T>; M]{
+    pub fn getn<const M: usize>(&self, indices: [ArenaIdx<T, G>; M]) -> [Option<&T>; M]{
         let mut ret = [None; M];
 
         for (i, index) in indices.iter().enumerate(){
@@ -275,9 +759,9 @@ impl<T, const N: usize> SArena<T, N>{
     ///
     /// ```
     ///
-    pub fn get_mut(&mut self, index: SArenaIdx<T>) -> Option<&mut T>{
-        if let ArenaCell::Allocated{val, generation} = &mut self.cells[index.index]{
-            if *generation == index.generation{
+    pub fn get_mut(&mut self, index: ArenaIdx<T, G>) -> Option<&mut T>{
+        if let Some(SCell::Allocated{val, generation}) = self.cells_mut().get_mut(index.index()){
+            if *generation == index.gen(){
                 Some(val)
             }
             else{
@@ -290,7 +774,7 @@ impl<T, const N: usize> SArena<T, N>{
     }
 
     pub fn get_any_mut(&mut self, index: usize) -> Option<&mut T>{
-        if let ArenaCell::Allocated{val, generation: _} = &mut self.cells[index]{
+        if let Some(SCell::Allocated{val, generation: _}) = self.cells_mut().get_mut(index){
             Some(val)
         }
         else{
@@ -300,7 +784,7 @@ impl<T, const N: usize> SArena<T, N>{
 
     ///
     /// Returns mutable optional references to two distinct values.
-    /// Indices have to be different.
+    /// Panics if both indices refer to the same element, stale indices to the same slot yield None.
     ///
     ///```rust
     /// use gen_arena::*;
@@ -320,40 +804,39 @@ impl<T, const N: usize> SArena<T, N>{
     ///
     ///```
     ///
-    pub fn get2_mut(&mut self, indices: (SArenaIdx<T>, SArenaIdx<T>)) -> (Option<&mut T>, Option<&mut T>){
-        if indices.0.index == indices.1.index{
-            if indices.0.generation == indices.1.generation{
-                panic!("Cannot take 2 mutable references to a value at the same index.")
-            }
-
-            if indices.0.generation > indices.1.generation{
-                return (self.get_mut(indices.0), None);
-            }
-            else{
+    pub fn get2_mut(&mut self, indices: (ArenaIdx<T, G>, ArenaIdx<T, G>)) -> (Option<&mut T>, Option<&mut T>){
+        if indices.0.index() == indices.1.index(){
+            // Only a generation matching the allocated one refers to a value,
+            // so the same slot is an error only if both indices are live.
+            if !self.contains(indices.0){
                 return (None, self.get_mut(indices.1));
             }
+            if indices.0.gen() == indices.1.gen(){
+                panic!("Cannot take 2 mutable references to a value at the same index.")
+            }
+            return (self.get_mut(indices.0), None);
         }
 
-        if indices.0.index >= self.cells.len(){
+        if indices.0.index() >= *self.init.get_mut(){
             return (None, self.get_mut(indices.1));
         }
-        if indices.1.index >= self.cells.len(){
+        if indices.1.index() >= *self.init.get_mut(){
             return (self.get_mut(indices.0), None);
         }
 
         let (cell0, cell1) = {
-            let split = self.cells.split_at_mut(indices.0.index.max(indices.1.index));
-            if indices.0.index < indices.1.index{
-                (&mut split.0[indices.0.index], &mut split.1[0])
+            let split = self.cells_mut().split_at_mut(indices.0.index().max(indices.1.index()));
+            if indices.0.index() < indices.1.index(){
+                (&mut split.0[indices.0.index()], &mut split.1[0])
             }
             else{
-                (&mut split.1[0], &mut split.0[indices.1.index])
+                (&mut split.1[0], &mut split.0[indices.1.index()])
             }
         };
 
         let cell0 = match cell0{
-            ArenaCell::Allocated{val, generation} => {
-                if indices.0.generation == *generation{
+            SCell::Allocated{val, generation} => {
+                if indices.0.gen() == *generation{
                     Some(val)
                 }
                 else{
@@ -363,8 +846,8 @@ impl<T, const N: usize> SArena<T, N>{
             _ => None
         };
         let cell1 = match cell1{
-            ArenaCell::Allocated{val, generation} => {
-                if indices.1.generation == *generation{
+            SCell::Allocated{val, generation} => {
+                if indices.1.gen() == *generation{
                     Some(val)
                 }
                 else{
@@ -378,28 +861,90 @@ impl<T, const N: usize> SArena<T, N>{
     }
 
     ///
-    /// Returns iterator over all Allocated cells.
+    /// Returns mutable optional references to M distinct values.
+    /// Stale and out of range indices yield None, like in get2_mut, even if they are passed twice.
+    /// Panics if the same live index is passed twice.
     ///
-    /// ```rust
+    ///```rust
     /// use gen_arena::*;
+    ///
     /// let mut arena = SArena::<_, 100>::new();
     ///
     /// let i1 = arena.insert(1);
-    /// let i2 = arena.insert(1);
+    /// let i2 = arena.insert(2);
+    /// let i3 = arena.insert(3);
     ///
-    /// for val in arena.iter(){
-    ///     assert_eq!(*val, 1);
-    /// }
+    /// let [c3, c1, c2] = arena.getn_mut([i3, i1, i2]);
     ///
-    /// ```
+    /// *c1.unwrap() += 10;
+    /// *c2.unwrap() += 10;
+    /// *c3.unwrap() += 10;
     ///
-    #[inline]
-    pub fn iter(&self) -> Values<T>{
-        Values{
-            iter: self.enumerate()
-        }
-    }
-
+    /// assert_eq!(arena.iter().collect::<Vec<_>>(), [&11, &12, &13]);
+    ///
+    ///```
+    ///
+    pub fn getn_mut<const M: usize>(&mut self, indices: [ArenaIdx<T, G>; M]) -> [Option<&mut T>; M]{
+        for (i, index) in indices.iter().enumerate(){
+            if indices[..i].contains(index) && self.contains(*index){
+                panic!("Cannot take 2 mutable references to a value at the same index.")
+            }
+        }
+
+        // Visit the indices in ascending order, so each matching cell can be split off
+        // the front of the remaining cells.
+        let mut order = [0; M];
+        for (i, j) in order.iter_mut().enumerate(){
+            *j = i;
+        }
+        order.sort_unstable_by_key(|&j| indices[j].index());
+
+        let mut ret = [(); M].map(|()| None);
+        let mut rest = self.cells_mut();
+        let mut start = 0;
+        for j in order{
+            let index = indices[j];
+            // A smaller index or another generation of an index that was already handed out.
+            if index.index() < start{
+                continue;
+            }
+            match rest.get(index.index() - start){
+                Some(SCell::Allocated{generation, ..}) if *generation == index.gen() => {}
+                _ => continue,
+            }
+            let (head, tail) = std::mem::take(&mut rest).split_at_mut(index.index() - start + 1);
+            rest = tail;
+            start = index.index() + 1;
+            if let Some(SCell::Allocated{val, ..}) = head.last_mut(){
+                ret[j] = Some(val);
+            }
+        }
+        ret
+    }
+
+    ///
+    /// Returns iterator over all Allocated cells.
+    ///
+    /// ```rust
+    /// use gen_arena::*;
+    /// let mut arena = SArena::<_, 100>::new();
+    ///
+    /// let i1 = arena.insert(1);
+    /// let i2 = arena.insert(1);
+    ///
+    /// for val in arena.iter(){
+    ///     assert_eq!(*val, 1);
+    /// }
+    ///
+    /// ```
+    ///
+    #[inline]
+    pub fn iter(&self) -> SValues<'_, T, N, G>{
+        SValues{
+            iter: self.enumerate()
+        }
+    }
+
     ///
     /// Returns mutable iterator over all Allocated cells.
     ///
@@ -420,8 +965,8 @@ impl<T, const N: usize> SArena<T, N>{
     /// ```
     ///
     #[inline]
-    pub fn iter_mut(&mut self) -> ValuesMut<T>{
-        ValuesMut{
+    pub fn iter_mut(&mut self) -> SValuesMut<'_, T, G>{
+        SValuesMut{
             iter: self.enumerate_mut()
         }
     }
@@ -429,8 +974,7 @@ impl<T, const N: usize> SArena<T, N>{
     ///
     /// Returns an iterator over the Allocated cells with index.
     ///
-    /// TODO: either add new iterator type for SArena or use ArenaIdx for SArena.
-    /// ```rust, ignore
+    /// ```rust
     /// use gen_arena::*;
     /// let mut arena = SArena::<_, 100>::new();
     ///
@@ -449,9 +993,11 @@ impl<T, const N: usize> SArena<T, N>{
     /// ```
     ///
     #[inline]
-    pub fn enumerate(&self) -> Iter<T>{
-        Iter{
-            iter: self.cells.iter().enumerate(),
+    pub fn enumerate(&self) -> SIter<'_, T, N, G>{
+        SIter{
+            arena: self,
+            iter: 0..N,
+            remaining: self.len(),
         }
     }
 
@@ -464,7 +1010,7 @@ impl<T, const N: usize> SArena<T, N>{
     ///
     /// let i1 = arena.insert(1);
     /// let i2 = arena.insert(2);
-    /// 
+    ///
     /// for (index, val) in arena.enumerate_mut(){
     ///     *val = index.index();
     /// }
@@ -475,9 +1021,76 @@ impl<T, const N: usize> SArena<T, N>{
     /// ```
     ///
     #[inline]
-    pub fn enumerate_mut(&mut self) -> IterMut<T>{
-        IterMut{
-            iter: self.cells.iter_mut().enumerate(),
+    pub fn enumerate_mut(&mut self) -> SIterMut<'_, T, G>{
+        let remaining = self.len();
+        SIterMut{
+            iter: self.cells_mut().iter_mut().enumerate(),
+            remaining,
+        }
+    }
+
+    ///
+    /// Returns iterator over all Allocated cells, the same as iter.
+    ///
+    /// ```rust
+    /// use gen_arena::*;
+    /// let arena = SArena::<_, 100>::new();
+    ///
+    /// let i1 = arena.insert(1);
+    /// let i2 = arena.insert(1);
+    ///
+    /// for val in arena.values(){
+    ///     assert_eq!(*val, 1);
+    /// }
+    ///
+    /// ```
+    ///
+    #[inline]
+    pub fn values(&self) -> SValues<'_, T, N, G>{
+        self.iter()
+    }
+
+    ///
+    /// Returns mutable iterator over all Allocated cells, the same as iter_mut.
+    ///
+    /// ```rust
+    /// use gen_arena::*;
+    /// let mut arena = SArena::<_, 100>::new();
+    ///
+    /// let i1 = arena.insert(1);
+    /// let i2 = arena.insert(2);
+    ///
+    /// for val in arena.values_mut(){
+    ///     *val = 0;
+    /// }
+    ///
+    /// assert_eq!(*arena.get(i1).unwrap(), 0);
+    /// assert_eq!(*arena.get(i2).unwrap(), 0);
+    ///
+    /// ```
+    ///
+    #[inline]
+    pub fn values_mut(&mut self) -> SValuesMut<'_, T, G>{
+        self.iter_mut()
+    }
+
+    ///
+    /// Iterator over all keys in the SArena.
+    ///
+    /// ```rust
+    /// use gen_arena::*;
+    /// let arena = SArena::<_, 100>::new();
+    ///
+    /// let i1 = arena.insert(1);
+    /// let i2 = arena.insert(2);
+    ///
+    /// assert_eq!(arena.keys().collect::<Vec<_>>(), [i1, i2]);
+    /// ```
+    ///
+    #[inline]
+    pub fn keys(&self) -> SKeys<'_, T, N, G>{
+        SKeys{
+            iter: self.enumerate(),
         }
     }
 
@@ -486,8 +1099,1910 @@ impl<T, const N: usize> SArena<T, N>{
         N
     }
 
+    ///
+    /// Returns the number of elements in the arena.
+    ///
+    #[inline]
+    pub fn len(&self) -> usize{
+        self.num.get()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool{
+        self.len() == 0
+    }
+
+    ///
+    /// Returns true if no more elements can be inserted, which is when remaining is 0.
+    ///
+    #[inline]
+    pub fn is_full(&self) -> bool{
+        self.remaining() == 0
+    }
+
+    ///
+    /// Returns the number of elements that can still be inserted.
+    /// Slots retired by a saturating generation are not counted, so this is the same as free_count.
+    ///
+    #[inline]
+    pub fn remaining(&self) -> usize{
+        self.free_count()
+    }
+
+    ///
+    /// Returns the number of freed slots, which excludes the slots retired by a saturating generation.
+    ///
+    #[inline]
+    pub fn free_count(&self) -> usize{
+        self.free.get()
+    }
+
+    ///
+    /// Returns an iterator over the indices of the freed slots in the order they are reused,
+    /// the first one is taken by the next insert. Slots that were never used come last.
+    ///
+    /// ```rust
+    /// use gen_arena::*;
+    ///
+    /// let mut arena = SArena::<_, 4>::new();
+    ///
+    /// let i0 = arena.insert(0);
+    /// let i1 = arena.insert(1);
+    /// arena.remove(i0);
+    ///
+    /// assert_eq!(arena.free_indices().collect::<Vec<_>>(), [0, 2, 3]);
+    /// assert_eq!(arena.free_count(), 3);
+    /// ```
+    ///
+    pub fn free_indices(&self) -> impl Iterator<Item = usize> + '_{
+        std::iter::successors(self.freed.get(), move |&i|{
+            match self.cell(i){
+                Some(SCell::Freed{next, ..}) => next.map(|next| next as usize),
+                _ => None,
+            }
+        }).chain(self.init.get()..N)
+    }
+
+    ///
+    /// Walks the list of Freed cells and checks it against the cells and the counters.
+    ///
+    /// Every freed cell that is not retired has to be reachable from the list exactly once,
+    /// and len and free_count have to match the cells. Slots that were never used are free
+    /// but not on the list. Only unsafe code writing to the cells
+    /// can break this, so it is meant for tests and watchdogs. It does not allocate.
+    ///
+    /// ```rust
+    /// use gen_arena::*;
+    ///
+    /// let mut arena = SArena::<_, 4>::new();
+    /// let i0 = arena.insert(0);
+    /// let _ = arena.insert(1);
+    /// arena.remove(i0);
+    ///
+    /// assert_eq!(arena.validate(), Ok(()));
+    /// ```
+    ///
+    pub fn validate(&self) -> Result<(), SArenaCorruption>{
+        // Walks without marking the cells, so validate does not allocate.
+        // Each cell can be linked once, a walk over more than N links has to go around a cycle.
+        let mut linked = 0;
+        let mut i = self.freed.get();
+        while let Some(index) = i{
+            match self.cell(index){
+                None => return Err(SArenaCorruption::LinkOutOfRange{index}),
+                Some(SCell::Allocated{..}) => return Err(SArenaCorruption::AllocatedOnFreeList{index}),
+                Some(SCell::Freed{generation, ..}) if *generation == G::RETIRED => {
+                    return Err(SArenaCorruption::RetiredOnFreeList{index});
+                }
+                Some(SCell::Freed{next, ..}) => {
+                    linked += 1;
+                    if linked > N{
+                        return Err(SArenaCorruption::Cycle{index});
+                    }
+                    i = next.map(|next| next as usize);
+                }
+            }
+        }
+
+        let stats = self.stats();
+        if stats.live != self.len(){
+            return Err(SArenaCorruption::LenMismatch{counted: stats.live, len: self.len()});
+        }
+        // The list holds distinct freed cells, so it misses one if it is shorter than their number.
+        let is_freed = |i: usize| matches!(self.cell(i), Some(SCell::Freed{generation, ..}) if *generation != G::RETIRED);
+        if linked < (0..N).filter(|&i| is_freed(i)).count(){
+            // Only searched on failure, as it walks the list again for every cell.
+            if let Some(index) = (0..N).find(|&i| is_freed(i) && !self.free_indices().take(linked).any(|j| j == i)){
+                return Err(SArenaCorruption::Unreachable{index});
+            }
+        }
+        if stats.free != self.free_count(){
+            return Err(SArenaCorruption::FreeCountMismatch{counted: stats.free, free_count: self.free_count()});
+        }
+        Ok(())
+    }
+
+    ///
+    /// Panics if validate fails in debug builds, does nothing in release builds.
+    ///
+    pub fn debug_assert_valid(&self){
+        if cfg!(debug_assertions){
+            if let Err(err) = self.validate(){
+                panic!("The SArena is corrupted: {}", err);
+            }
+        }
+    }
+
+    ///
+    /// Counts the live, free and retired slots and the longest run of consecutive free slots.
+    /// Looks at every cell, unlike len and free_count.
+    ///
+    /// ```rust
+    /// use gen_arena::*;
+    ///
+    /// let mut arena = SArena::<_, 8>::new();
+    /// let keys: Vec<_> = (0..6).map(|i| arena.insert(i)).collect();
+    /// arena.remove(keys[1]);
+    ///
+    /// let stats = arena.stats();
+    /// assert_eq!((stats.live, stats.free, stats.retired), (5, 3, 0));
+    /// assert_eq!(stats.longest_free_run, 2);
+    /// ```
+    ///
+    pub fn stats(&self) -> SArenaStats{
+        let mut stats = SArenaStats::default();
+        let mut run = 0;
+        for i in 0..N{
+            match self.cell(i){
+                Some(SCell::Allocated{..}) => stats.live += 1,
+                Some(SCell::Freed{generation, ..}) if *generation == G::RETIRED => stats.retired += 1,
+                _ => {
+                    stats.free += 1;
+                    run += 1;
+                    stats.longest_free_run = stats.longest_free_run.max(run);
+                    continue;
+                }
+            }
+            run = 0;
+        }
+        stats
+    }
+
+    ///
+    /// Links the freed slots in ascending order, see Arena::canonicalize_free_list.
+    /// Deserialized SArenas are canonical already.
+    ///
+    pub fn canonicalize_free_list(&mut self){
+        self.relink()
+    }
+
+    #[deprecated(note = "Use SArena::len like for other collections.")]
     #[inline]
     pub fn num(&self) -> usize{
-        self.num
+        self.len()
+    }
+}
+
+///
+/// The error of SArena::try_from_iter, more values were given than the arena can hold.
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct OverflowError{
+    pub capacity: usize,
+}
+
+impl std::fmt::Display for OverflowError{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "more than {} values for an SArena of that capacity", self.capacity)
+    }
+}
+
+impl std::error::Error for OverflowError{}
+
+///
+/// An inconsistency between the cells of an SArena, its list of Freed cells and its counters,
+/// as found by SArena::validate.
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum SArenaCorruption{
+    /// The list links to a slot that was never used or is not smaller than N.
+    LinkOutOfRange{index: usize},
+    /// The list links to an allocated cell, which the next insert would overwrite.
+    AllocatedOnFreeList{index: usize},
+    /// The list links to a cell whose generation is exhausted.
+    RetiredOnFreeList{index: usize},
+    /// The list reaches the cell a second time.
+    Cycle{index: usize},
+    /// The freed cell is not on the list, so it is never reused.
+    Unreachable{index: usize},
+    /// The number of allocated cells differs from len.
+    LenMismatch{counted: usize, len: usize},
+    /// The number of free cells differs from free_count.
+    FreeCountMismatch{counted: usize, free_count: usize},
+}
+
+impl std::fmt::Display for SArenaCorruption{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self{
+            Self::LinkOutOfRange{index} => write!(f, "the free list links to {}, which is out of range", index),
+            Self::AllocatedOnFreeList{index} => write!(f, "the allocated cell {} is on the free list", index),
+            Self::RetiredOnFreeList{index} => write!(f, "the retired cell {} is on the free list", index),
+            Self::Cycle{index} => write!(f, "the free list reaches the cell {} twice", index),
+            Self::Unreachable{index} => write!(f, "the freed cell {} is not on the free list", index),
+            Self::LenMismatch{counted, len} => write!(f, "{} cells are allocated, but len is {}", counted, len),
+            Self::FreeCountMismatch{counted, free_count} => write!(f, "{} cells are free, but free_count is {}", counted, free_count),
+        }
+    }
+}
+
+impl std::error::Error for SArenaCorruption{}
+
+///
+/// Occupancy of an SArena, returned by SArena::stats.
+///
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct SArenaStats{
+    pub live: usize,
+    pub free: usize,
+    /// Slots whose saturating generation is exhausted, which are never reused.
+    pub retired: usize,
+    /// The largest number of consecutive free slots.
+    pub longest_free_run: usize,
+}
+
+///
+/// The error of SArena::try_from_arena, the arena has elements at slots the SArena does not have.
+/// Holds the arena, so its elements are not lost. The arena is boxed to keep the error small.
+///
+pub struct CapacityError<T, G = usize>{
+    pub capacity: usize,
+    /// The highest occupied slot of the arena plus one.
+    pub required: usize,
+    arena: Box<Arena<T, G>>,
+}
+
+impl<T, G> CapacityError<T, G>{
+    ///
+    /// Returns the arena that could not be converted.
+    ///
+    pub fn into_arena(self) -> Arena<T, G>{
+        *self.arena
+    }
+}
+
+impl<T, G> std::fmt::Debug for CapacityError<T, G>{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CapacityError")
+            .field("capacity", &self.capacity)
+            .field("required", &self.required)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<T, G> std::fmt::Display for CapacityError<T, G>{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "an arena occupying {} slots does not fit into an SArena of capacity {}", self.required, self.capacity)
+    }
+}
+
+impl<T, G> std::error::Error for CapacityError<T, G>{}
+
+#[cfg(feature = "defmt")]
+impl<T, G> defmt::Format for CapacityError<T, G>{
+    fn format(&self, f: defmt::Formatter<'_>) {
+        defmt::write!(f, "CapacityError {{ capacity: {=usize}, required: {=usize} }}", self.capacity, self.required)
+    }
+}
+
+///
+/// Creates an empty SArena, so structs holding arenas can derive Default.
+///
+///```rust
+/// use gen_arena::*;
+///
+/// #[derive(Default)]
+/// struct Scene{
+///     meshes: Arena<&'static str>,
+///     lights: SArena<[f32; 3], 8>,
+/// }
+///
+/// let mut scene = Scene::default();
+/// let m0 = scene.meshes.insert("cube");
+/// let l0 = scene.lights.insert([1.0, 1.0, 1.0]);
+///
+/// assert_eq!(scene.meshes[m0], "cube");
+/// assert_eq!(scene.lights.get(l0), Some(&[1.0, 1.0, 1.0]));
+///```
+///
+impl<T, const N: usize, G: GenCounter> Default for SArena<T, N, G>{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+///
+/// Clones the elements with their generations and the list of Freed cells,
+/// so keys of the arena are valid for the clone as well.
+///
+impl<T: Clone, const N: usize, G: GenCounter> Clone for SArena<T, N, G>{
+    fn clone(&self) -> Self {
+        Self{
+            cells: std::array::from_fn(|i| UnsafeCell::new(match self.cell(i){
+                Some(cell) => MaybeUninit::new(cell.clone()),
+                None => MaybeUninit::uninit(),
+            })),
+            init: self.init.clone(),
+            freed: self.freed.clone(),
+            free: self.free.clone(),
+            num: self.num.clone(),
+            _not_sync: PhantomData,
+        }
+    }
+}
+
+impl<T: std::fmt::Debug, const N: usize, G: GenCounter> std::fmt::Debug for SArena<T, N, G>{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_map().entries(self.enumerate().map(|(i, val)| (i.index(), val))).finish()
+    }
+}
+
+///
+/// Arenas are equal if they hold equal elements with the same keys.
+/// Freed cells and the order in which they are reused are not compared.
+///
+impl<T: PartialEq, const N: usize, G: GenCounter> PartialEq for SArena<T, N, G>{
+    fn eq(&self, other: &Self) -> bool {
+        self.enumerate().eq(other.enumerate())
+    }
+}
+
+impl<T: Eq, const N: usize, G: GenCounter> Eq for SArena<T, N, G>{}
+
+///
+/// Creates a full SArena from an array of exactly N values, see SArena::from_array.
+///
+impl<T, const N: usize, G: GenCounter> From<[T; N]> for SArena<T, N, G>{
+    fn from(values: [T; N]) -> Self {
+        Self::from_array(values)
+    }
+}
+
+impl<T, const N: usize, G: GenCounter> TryFrom<Arena<T, G>> for SArena<T, N, G>{
+    type Error = CapacityError<T, G>;
+
+    fn try_from(arena: Arena<T, G>) -> Result<Self, Self::Error> {
+        Self::try_from_arena(arena)
+    }
+}
+
+///
+/// Moves the elements into an Arena, keeping their slots and generations
+/// so keys of the SArena stay valid, see SArena::try_from_arena.
+///
+impl<T, const N: usize, G: GenCounter> From<SArena<T, N, G>> for Arena<T, G>{
+    fn from(sarena: SArena<T, N, G>) -> Self {
+        let cells = sarena.into_cells().map(|cell| match cell{
+            SCell::Allocated{val, generation} => RawCell::Allocated{val, generation},
+            SCell::Freed{generation, ..} => RawCell::Freed{next: None, generation},
+        });
+        let mut arena = Arena::default();
+        *arena.raw_mut() = RawArena::from_cells(cells.collect());
+        arena
+    }
+}
+
+///
+/// Clones the elements into an Arena, like From<SArena>.
+///
+impl<T: Clone, const N: usize, G: GenCounter> From<&SArena<T, N, G>> for Arena<T, G>{
+    fn from(sarena: &SArena<T, N, G>) -> Self {
+        let cells = (0..sarena.init.get()).map(|i| match sarena.cell(i).expect("i is smaller than init."){
+            SCell::Allocated{val, generation} => RawCell::Allocated{val: val.clone(), generation: *generation},
+            SCell::Freed{generation, ..} => RawCell::Freed{next: None, generation: *generation},
+        });
+        let mut arena = Arena::default();
+        *arena.raw_mut() = RawArena::from_cells(cells.collect());
+        arena
+    }
+}
+
+impl<T, const N: usize, G> SArena<T, N, G>{
+    // Moves the cells out of the arena without dropping it.
+    // Only the initialized cells are yielded, which the caller has to drop.
+    fn into_raw_cells(self) -> RawCells<T, N, G>{
+        let this = ManuallyDrop::new(self);
+        // SAFETY: The arena is not dropped, so the cells are only owned by the result.
+        let cells = unsafe{std::ptr::read(&this.cells)};
+        cells.into_iter().take(this.init.get())
+    }
+
+    // Moves the initialized cells out of the arena. Cells that are not reached are leaked.
+    fn into_cells(self) -> impl Iterator<Item = SCell<T, G>>{
+        // SAFETY: Only the initialized cells are yielded, see into_raw_cells.
+        self.into_raw_cells().map(|cell| unsafe{cell.into_inner().assume_init()})
+    }
+}
+
+impl<T, const N: usize, G> Drop for SArena<T, N, G>{
+    fn drop(&mut self){
+        let init = *self.init.get_mut();
+        for cell in self.cells[..init].iter_mut(){
+            // SAFETY: The cells before init are initialized and are not used afterwards.
+            unsafe{
+                cell.get_mut().assume_init_drop();
+            }
+        }
+    }
+}
+
+///
+/// Serialized as a struct of the elements with their index and generation
+/// and the generations of freed slots that were already used, so keys stay valid after deserializing.
+///
+#[cfg(feature = "serde")]
+impl<T: serde::Serialize, const N: usize, G: GenCounter + serde::Serialize> serde::Serialize for SArena<T, N, G>{
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+
+        use serde::ser::SerializeSeq;
+
+        // Sequences are written with their length, which formats like postcard require.
+        struct Values<'a, T, const N: usize, G>(&'a SArena<T, N, G>);
+        impl<T: serde::Serialize, const N: usize, G: GenCounter + serde::Serialize> serde::Serialize for Values<'_, T, N, G>{
+            fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                let mut seq = serializer.serialize_seq(Some(self.0.len()))?;
+                for (key, val) in self.0.enumerate(){
+                    seq.serialize_element(&(key.index(), key.gen(), val))?;
+                }
+                seq.end()
+            }
+        }
+
+        struct Freed<'a, T, const N: usize, G>(&'a SArena<T, N, G>);
+        impl<T, const N: usize, G: GenCounter + serde::Serialize> Freed<'_, T, N, G>{
+            fn iter(&self) -> impl Iterator<Item = (usize, G)> + '_{
+                (0..N).filter_map(|i| match self.0.cell(i){
+                    Some(SCell::Freed{generation, ..}) if *generation != G::FIRST => Some((i, *generation)),
+                    _ => None,
+                })
+            }
+        }
+        impl<T, const N: usize, G: GenCounter + serde::Serialize> serde::Serialize for Freed<'_, T, N, G>{
+            fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                let mut seq = serializer.serialize_seq(Some(self.iter().count()))?;
+                for freed in self.iter(){
+                    seq.serialize_element(&freed)?;
+                }
+                seq.end()
+            }
+        }
+
+        let mut state = serializer.serialize_struct("SArena", 2)?;
+        state.serialize_field("values", &Values(self))?;
+        state.serialize_field("freed", &Freed(self))?;
+        state.end()
+    }
+}
+
+///
+/// Rejects indices that are not smaller than N and slots that are given twice.
+/// Slots that are not given are freed with the first generation.
+///
+#[cfg(feature = "serde")]
+impl<'de, T: serde::Deserialize<'de>, const N: usize, G: GenCounter + serde::Deserialize<'de>> serde::Deserialize<'de> for SArena<T, N, G>{
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        use serde::de::Error;
+
+        #[derive(serde::Deserialize)]
+        #[serde(rename = "SArena")]
+        struct Data<T, G>{
+            values: Vec<(usize, G, T)>,
+            freed: Vec<(usize, G)>,
+        }
+
+        let data = Data::<T, G>::deserialize(deserializer)?;
+        let mut arena = Self::new();
+        let mut assigned = vec![false; N];
+        let mut assign = |index: usize|{
+            match assigned.get_mut(index){
+                None => Err(D::Error::custom(format_args!("the index {} is out of range for an SArena of capacity {}", index, N))),
+                Some(true) => Err(D::Error::custom(format_args!("the slot {} is given twice", index))),
+                Some(assigned) => {
+                    *assigned = true;
+                    Ok(())
+                }
+            }
+        };
+        for (index, _, _) in data.values.iter(){
+            assign(*index)?;
+        }
+        for (index, _) in data.freed.iter(){
+            assign(*index)?;
+        }
+        // Only the slots up to the last given one are initialized.
+        let init = data.values.iter().map(|(index, _, _)| index + 1)
+            .chain(data.freed.iter().map(|(index, _)| index + 1))
+            .max()
+            .unwrap_or(0);
+        for _ in 0..init{
+            arena.push_cell(SCell::Freed{next: None, generation: G::FIRST});
+        }
+        for (index, generation, val) in data.values{
+            arena.cells_mut()[index] = SCell::Allocated{val, generation};
+        }
+        for (index, generation) in data.freed{
+            arena.cells_mut()[index] = SCell::Freed{next: None, generation};
+        }
+        arena.relink();
+        Ok(arena)
+    }
+}
+
+///
+/// Moves the elements out of the arena together with their keys.
+///
+///```rust
+/// use gen_arena::*;
+///
+/// let arena = SArena::<_, 4>::new();
+/// let i1 = arena.insert(String::from("a"));
+///
+/// for (key, val) in arena{
+///     assert_eq!(key, i1);
+///     assert_eq!(val, "a");
+/// }
+///```
+///
+impl<T, const N: usize, G: GenCounter> IntoIterator for SArena<T, N, G>{
+    type Item = (ArenaIdx<T, G>, T);
+    type IntoIter = SIntoIter<T, N, G>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        SIntoIter{
+            remaining: self.len(),
+            iter: self.into_raw_cells().enumerate(),
+        }
+    }
+}
+
+impl<'i, T, const N: usize, G: GenCounter> IntoIterator for &'i SArena<T, N, G>{
+    type Item = &'i T;
+    type IntoIter = SValues<'i, T, N, G>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'i, T, const N: usize, G: GenCounter> IntoIterator for &'i mut SArena<T, N, G>{
+    type Item = &'i mut T;
+    type IntoIter = SValuesMut<'i, T, G>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
+}
+
+///
+/// Owning iterator over the elements of an SArena with their keys.
+/// Elements that are not reached are dropped with the iterator.
+///
+pub struct SIntoIter<T, const N: usize, G = usize>{
+    iter: std::iter::Enumerate<RawCells<T, N, G>>,
+    remaining: usize,
+}
+
+impl<T, const N: usize, G> Drop for SIntoIter<T, N, G>{
+    fn drop(&mut self){
+        for (_, cell) in self.iter.by_ref(){
+            // SAFETY: The iterator only yields initialized cells, see into_raw_cells.
+            unsafe{
+                cell.into_inner().assume_init_drop();
+            }
+        }
+    }
+}
+
+impl<T, const N: usize, G: GenCounter> Iterator for SIntoIter<T, N, G>{
+    type Item = (ArenaIdx<T, G>, T);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        for (i, cell) in self.iter.by_ref(){
+            // SAFETY: The iterator only yields initialized cells, see into_raw_cells.
+            if let SCell::Allocated{val, generation} = unsafe{cell.into_inner().assume_init()}{
+                self.remaining -= 1;
+                return Some((ArenaIdx::from_parts(i, generation), val));
+            }
+        }
+        None
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<T, const N: usize, G: GenCounter> DoubleEndedIterator for SIntoIter<T, N, G>{
+    fn next_back(&mut self) -> Option<Self::Item> {
+        while let Some((i, cell)) = self.iter.next_back(){
+            // SAFETY: The iterator only yields initialized cells, see into_raw_cells.
+            if let SCell::Allocated{val, generation} = unsafe{cell.into_inner().assume_init()}{
+                self.remaining -= 1;
+                return Some((ArenaIdx::from_parts(i, generation), val));
+            }
+        }
+        None
+    }
+}
+
+impl<T, const N: usize, G: GenCounter> ExactSizeIterator for SIntoIter<T, N, G>{}
+
+impl<T, const N: usize, G: GenCounter> FusedIterator for SIntoIter<T, N, G>{}
+
+///
+/// Iterator over the elements of an SArena with their keys.
+/// It looks at one cell at a time, so elements may be inserted while iterating.
+///
+/// Because of that it is not an ExactSizeIterator: elements inserted into the part that is
+/// not yet iterated are reached as well, so the size hint is only exact if nothing is inserted.
+///
+pub struct SIter<'i, T: 'i, const N: usize, G = usize>{
+    arena: &'i SArena<T, N, G>,
+    iter: std::ops::Range<usize>,
+    // The number of elements not yet reached that existed when iteration started.
+    remaining: usize,
+}
+
+impl<'i, T, const N: usize, G: GenCounter> Iterator for SIter<'i, T, N, G>{
+    type Item = (ArenaIdx<T, G>, &'i T);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        for i in self.iter.by_ref(){
+            if let Some(SCell::Allocated{val, generation}) = self.arena.cell(i){
+                self.remaining = self.remaining.saturating_sub(1);
+                return Some((ArenaIdx::from_parts(i, *generation), val));
+            }
+        }
+        None
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.iter.len()))
+    }
+}
+
+impl<'i, T, const N: usize, G: GenCounter> DoubleEndedIterator for SIter<'i, T, N, G>{
+    fn next_back(&mut self) -> Option<Self::Item> {
+        while let Some(i) = self.iter.next_back(){
+            if let Some(SCell::Allocated{val, generation}) = self.arena.cell(i){
+                self.remaining = self.remaining.saturating_sub(1);
+                return Some((ArenaIdx::from_parts(i, *generation), val));
+            }
+        }
+        None
+    }
+}
+
+impl<'i, T, const N: usize, G: GenCounter> FusedIterator for SIter<'i, T, N, G>{}
+
+pub struct SValues<'i, T: 'i, const N: usize, G = usize>{
+    iter: SIter<'i, T, N, G>,
+}
+
+impl<'i, T, const N: usize, G: GenCounter> Iterator for SValues<'i, T, N, G>{
+    type Item = &'i T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.iter.next().map(|(_, val)| val)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+impl<'i, T, const N: usize, G: GenCounter> DoubleEndedIterator for SValues<'i, T, N, G>{
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.iter.next_back().map(|(_, val)| val)
+    }
+}
+
+impl<'i, T, const N: usize, G: GenCounter> FusedIterator for SValues<'i, T, N, G>{}
+
+pub struct SKeys<'i, T: 'i, const N: usize, G = usize>{
+    iter: SIter<'i, T, N, G>,
+}
+
+impl<'i, T, const N: usize, G: GenCounter> Iterator for SKeys<'i, T, N, G>{
+    type Item = ArenaIdx<T, G>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.iter.next().map(|(key, _)| key)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+impl<'i, T, const N: usize, G: GenCounter> DoubleEndedIterator for SKeys<'i, T, N, G>{
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.iter.next_back().map(|(key, _)| key)
+    }
+}
+
+impl<'i, T, const N: usize, G: GenCounter> FusedIterator for SKeys<'i, T, N, G>{}
+
+pub struct SIterMut<'i, T: 'i, G = usize>{
+    iter: std::iter::Enumerate<std::slice::IterMut<'i, SCell<T, G>>>,
+    remaining: usize,
+}
+
+impl<'i, T, G: GenCounter> Iterator for SIterMut<'i, T, G>{
+    type Item = (ArenaIdx<T, G>, &'i mut T);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        for (i, cell) in self.iter.by_ref(){
+            if let SCell::Allocated{val, generation} = cell{
+                self.remaining -= 1;
+                return Some((ArenaIdx::from_parts(i, *generation), val));
+            }
+        }
+        None
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<'i, T, G: GenCounter> DoubleEndedIterator for SIterMut<'i, T, G>{
+    fn next_back(&mut self) -> Option<Self::Item> {
+        while let Some((i, cell)) = self.iter.next_back(){
+            if let SCell::Allocated{val, generation} = cell{
+                self.remaining -= 1;
+                return Some((ArenaIdx::from_parts(i, *generation), val));
+            }
+        }
+        None
+    }
+}
+
+impl<'i, T, G: GenCounter> ExactSizeIterator for SIterMut<'i, T, G>{}
+
+impl<'i, T, G: GenCounter> FusedIterator for SIterMut<'i, T, G>{}
+
+pub struct SValuesMut<'i, T: 'i, G = usize>{
+    iter: SIterMut<'i, T, G>,
+}
+
+impl<'i, T, G: GenCounter> Iterator for SValuesMut<'i, T, G>{
+    type Item = &'i mut T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.iter.next().map(|(_, val)| val)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+impl<'i, T, G: GenCounter> DoubleEndedIterator for SValuesMut<'i, T, G>{
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.iter.next_back().map(|(_, val)| val)
+    }
+}
+
+impl<'i, T, G: GenCounter> ExactSizeIterator for SValuesMut<'i, T, G>{}
+
+impl<'i, T, G: GenCounter> FusedIterator for SValuesMut<'i, T, G>{}
+
+// These tests exercise inserting through &self while references into the arena are alive,
+// as well as reading and dropping the lazily initialized cells, and are meant to be run under Miri as well:
+// PROPTEST_CASES=2 cargo +nightly miri test sarena
+#[cfg(test)]
+mod test{
+    use super::*;
+    use proptest::prelude::*;
+
+    #[test]
+    fn test_insert_while_borrowed(){
+        let arena = SArena::<String, 8>::new();
+        let a = arena.insert(String::from("a"));
+        let a_ref = arena.get(a).unwrap();
+        let b = arena.insert(String::from("b"));
+        let b_ref = arena.get(b).unwrap();
+        let c = arena.insert(String::from("c"));
+        assert_eq!((a_ref.as_str(), b_ref.as_str()), ("a", "b"));
+        assert_eq!(arena.get(c).map(String::as_str), Some("c"));
+
+        // Inserting while iterating, the new element is reached by the iteration.
+        let mut seen = Vec::new();
+        for (i, (_, val)) in arena.enumerate().enumerate(){
+            if i == 0{
+                let _ = arena.insert(String::from("d"));
+            }
+            seen.push(val.as_str());
+        }
+        assert_eq!(seen, ["a", "b", "c", "d"]);
+        assert_eq!(a_ref, "a");
+        assert_eq!(arena.len(), 4);
+    }
+
+    #[test]
+    fn test_interleaved(){
+        let mut arena = SArena::<Box<usize>, 4>::new();
+        let mut keys = Vec::new();
+        for round in 0..5{
+            while let Ok(key) = arena.try_insert(Box::new(round)){
+                keys.push(key);
+            }
+            assert_eq!(arena.len(), 4);
+            assert!(arena.try_insert(Box::new(0)).is_err());
+
+            let first = keys[keys.len() - 4];
+            **arena.get_mut(first).unwrap() = round + 10;
+            for key in keys.drain(keys.len() - 2..){
+                arena.remove(key);
+            }
+            let held = arena.get(first).unwrap();
+            let key = arena.insert(Box::new(100));
+            assert_eq!(**held, round + 10);
+            assert_eq!(**arena.get(key).unwrap(), 100);
+            keys.push(key);
+            arena.iter_mut().for_each(|val| **val += 1);
+            assert_eq!(arena.iter().count(), 3);
+        }
+    }
+
+    #[test]
+    fn test_zero_capacity(){
+        let mut arena = SArena::<i32, 0>::new();
+        assert_eq!(arena.capacity(), 0);
+        assert_eq!(arena.try_insert(1).map_err(InsertError::into_value), Err(1));
+        assert_eq!(arena.try_insert(2).map_err(InsertError::into_value), Err(2));
+        assert_eq!(arena.len(), 0);
+        assert_eq!(arena.iter().count(), 0);
+        assert_eq!(arena.enumerate().count(), 0);
+        assert_eq!(arena.iter_mut().count(), 0);
+        assert_eq!(arena.enumerate_mut().count(), 0);
+    }
+
+    #[test]
+    fn test_single_capacity(){
+        let mut arena = SArena::<i32, 1>::new();
+        let i0 = arena.insert(1);
+        assert_eq!(i0.index(), 0);
+        assert_eq!(arena.try_insert(2).map_err(InsertError::into_value), Err(2));
+        assert_eq!(arena.enumerate().collect::<Vec<_>>(), [(i0, &1)]);
+
+        arena.remove(i0);
+        assert_eq!(arena.len(), 0);
+        assert_eq!(arena.iter().count(), 0);
+
+        let i1 = arena.insert(3);
+        assert_eq!((i1.index(), i1.gen()), (0, 1));
+        assert_eq!(arena.get(i0), None);
+        assert_eq!(arena.try_insert(4).map_err(InsertError::into_value), Err(4));
+        arena.iter_mut().for_each(|val| *val += 1);
+        assert_eq!(arena.iter().collect::<Vec<_>>(), [&4]);
+    }
+
+    #[test]
+    fn test_foreign_index(){
+        let mut arena = SArena::<i32, 4>::new();
+        let i0 = arena.insert(0);
+        let larger = SArena::<i32, 8>::new();
+        let foreign: Vec<_> = (0..6).map(|i| larger.insert(i)).collect();
+
+        for index in [ArenaIdx::from_raw_parts(4, 0), foreign[5], ArenaIdx::from_raw_parts(1000, 0), ArenaIdx::dangling()]{
+            assert_eq!(arena.get(index), None);
+            assert_eq!(arena.get_mut(index), None);
+            assert_eq!(arena.get_any(index.index()), None);
+            assert_eq!(arena.get_any_mut(index.index()), None);
+            assert_eq!(arena.gen(index.index()), None);
+            assert_eq!(arena.getn([i0, index]), [Some(&0), None]);
+            assert_eq!(arena.get2_mut((i0, index)), (Some(&mut 0), None));
+        }
+        assert_eq!(arena.gen(3), Some(0));
+    }
+
+    #[test]
+    fn test_remove_stale(){
+        let mut arena = SArena::<String, 2>::new();
+        let i0 = arena.insert(String::from("a"));
+        let i1 = arena.insert(String::from("b"));
+
+        assert_eq!(arena.remove(i1).as_deref(), Some("b"));
+        assert_eq!(arena.remove(i1), None);
+        assert_eq!(arena.len(), 1);
+
+        // The stale index neither removes nor returns the new value.
+        let i2 = arena.insert(String::from("c"));
+        assert_eq!(i2.index(), i1.index());
+        assert_eq!(arena.remove(i1), None);
+        assert_eq!(arena.get(i2).map(String::as_str), Some("c"));
+        assert_eq!(arena.len(), 2);
+        assert_eq!(arena.gen(i2.index()), Some(1));
+        assert!(arena.try_insert(String::new()).is_err());
+
+        assert_eq!(arena.remove(ArenaIdx::dangling()), None);
+        assert_eq!(arena.remove(i0).as_deref(), Some("a"));
+        assert_eq!(arena.remove(i2).as_deref(), Some("c"));
+        assert_eq!(arena.len(), 0);
+    }
+
+    #[test]
+    fn test_clear(){
+        use std::rc::Rc;
+
+        let drops = Rc::new(Cell::new(0));
+        struct Counted(Rc<Cell<usize>>);
+        impl Drop for Counted{
+            fn drop(&mut self){
+                self.0.set(self.0.get() + 1);
+            }
+        }
+
+        let mut arena = SArena::<Counted, 4>::new();
+        let keys: Vec<_> = (0..4).map(|_| arena.insert(Counted(drops.clone()))).collect();
+        drop(arena.remove(keys[1]));
+        assert_eq!(drops.get(), 1);
+
+        arena.clear();
+        assert_eq!(drops.get(), 4);
+        assert_eq!(arena.len(), 0);
+        assert_eq!(arena.iter().count(), 0);
+        assert!(keys.iter().all(|key| arena.get(*key).is_none()));
+
+        // The arena refills to exactly N elements with new generations.
+        let refilled: Vec<_> = (0..4).map(|_| arena.insert(Counted(drops.clone()))).collect();
+        assert!(arena.try_insert(Counted(drops.clone())).is_err());
+        assert_eq!(drops.get(), 5);
+        assert_eq!(refilled.iter().map(|key| key.index()).collect::<Vec<_>>(), [0, 1, 2, 3]);
+        assert!(refilled.iter().zip(&keys).all(|(new, old)| new.gen() == old.gen() + 1));
+        assert!(keys.iter().all(|key| arena.get(*key).is_none()));
+
+        drop(arena);
+        assert_eq!(drops.get(), 9);
+
+        let mut empty = SArena::<Counted, 0>::new();
+        empty.clear();
+        assert!(empty.try_insert(Counted(drops.clone())).is_err());
+    }
+
+    #[test]
+    fn test_static(){
+        use std::sync::Mutex;
+
+        static REGISTRY: Mutex<SArena<String, 4>> = Mutex::new(SArena::new());
+        thread_local!{
+            static LOCAL: SArena<u32, 2> = const{SArena::new()};
+        }
+
+        let keys: Vec<_> = (0..4).map(|i| REGISTRY.lock().unwrap().insert(i.to_string())).collect();
+        let mut registry = REGISTRY.lock().unwrap();
+        assert!(registry.try_insert(String::new()).is_err());
+        assert_eq!(registry.get(keys[2]).map(String::as_str), Some("2"));
+        assert_eq!(registry.remove(keys[0]).as_deref(), Some("0"));
+        drop(registry);
+
+        LOCAL.with(|arena|{
+            let i0 = arena.insert(7);
+            let i1 = arena.insert(8);
+            assert_eq!(arena.get(i0), Some(&7));
+            assert_eq!(arena.try_insert(9).map_err(InsertError::into_value), Err(9));
+            assert_eq!(arena.get(i1), Some(&8));
+        });
+    }
+
+    #[test]
+    fn test_keys_values(){
+        let mut arena = SArena::<i32, 6>::new();
+        let keys: Vec<_> = (0..6).map(|i| arena.insert(i)).collect();
+        arena.remove(keys[1]);
+        arena.remove(keys[4]);
+
+        assert_eq!(arena.keys().collect::<Vec<_>>(), [keys[0], keys[2], keys[3], keys[5]]);
+        assert_eq!(arena.values().copied().collect::<Vec<_>>(), [0, 2, 3, 5]);
+        arena.values_mut().for_each(|val| *val *= 10);
+        assert!(arena.keys().all(|key| arena.get(key) == Some(&(key.index() as i32 * 10))));
+    }
+
+    #[test]
+    fn test_enumerate_keys(){
+        let mut arena = SArena::<String, 8>::new();
+        let keys: Vec<_> = (0..8).map(|i| arena.insert(i.to_string())).collect();
+        arena.remove(keys[2]);
+        arena.remove(keys[5]);
+        let reused = arena.insert(String::from("r"));
+
+        let pairs: Vec<_> = arena.enumerate().map(|(key, val)| (key, val.clone())).collect();
+        assert_eq!(pairs.len(), 7);
+        assert!(pairs.iter().any(|(key, _)| *key == reused));
+        for (key, val) in &pairs{
+            assert_eq!(arena.get(*key), Some(val));
+        }
+
+        let keys_mut: Vec<_> = arena.enumerate_mut().map(|(key, _)| key).collect();
+        assert_eq!(keys_mut, pairs.iter().map(|(key, _)| *key).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_getn_mut(){
+        let mut arena = SArena::<i32, 8>::new();
+        let keys: Vec<_> = (0..8).map(|i| arena.insert(i)).collect();
+        arena.remove(keys[3]);
+        let reused = arena.insert(30);
+
+        // Unordered, stale, reused and forged indices.
+        let forged = ArenaIdx::from_raw_parts(8, 0);
+        let got = arena.getn_mut([keys[6], keys[3], forged, reused, keys[0], ArenaIdx::dangling()]);
+        assert_eq!(got.iter().map(|val| val.as_deref().copied()).collect::<Vec<_>>(),
+            [Some(6), None, None, Some(30), Some(0), None]);
+        for val in got.into_iter().flatten(){
+            *val += 100;
+        }
+        assert_eq!(arena.values().copied().collect::<Vec<_>>(), [100, 1, 2, 130, 4, 5, 106, 7]);
+
+        // The stale key after the reused one at the same index, both orders.
+        let [a, b] = arena.getn_mut([reused, keys[3]]);
+        assert_eq!((a.copied(), b), (Some(130), None));
+        let [a, b] = arena.getn_mut([keys[3], reused]);
+        assert_eq!((a, b.copied()), (None, Some(130)));
+
+        assert_eq!(arena.getn_mut::<0>([]).len(), 0);
+        assert_eq!(SArena::<i32, 0>::new().getn_mut([forged]), [None]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_getn_mut_duplicate(){
+        let mut arena = SArena::<i32, 4>::new();
+        let i0 = arena.insert(0);
+        let i1 = arena.insert(1);
+        let _ = arena.getn_mut([i0, i1, i0]);
+    }
+
+    #[test]
+    fn test_into_iter(){
+        use std::rc::Rc;
+
+        let drops = Rc::new(Cell::new(0));
+        #[derive(Debug)]
+        struct Counted(usize, Rc<Cell<usize>>);
+        impl Drop for Counted{
+            fn drop(&mut self){
+                self.1.set(self.1.get() + 1);
+            }
+        }
+
+        let fill = |drops: &Rc<Cell<usize>>|{
+            let arena = SArena::<Counted, 6>::new();
+            let keys: Vec<_> = (0..5).map(|i| arena.insert(Counted(i, drops.clone()))).collect();
+            (arena, keys)
+        };
+
+        // Consuming every element moves them out without dropping.
+        let (mut arena, keys) = fill(&drops);
+        drop(arena.remove(keys[1]));
+        assert_eq!(drops.get(), 1);
+        let moved: Vec<_> = arena.into_iter().collect();
+        assert_eq!(drops.get(), 1);
+        assert_eq!(moved.iter().map(|(key, val)| (key.index(), val.0)).collect::<Vec<_>>(), [(0, 0), (2, 2), (3, 3), (4, 4)]);
+        assert!(moved.iter().all(|(key, _)| keys.contains(key)));
+        drop(moved);
+        assert_eq!(drops.get(), 5);
+
+        // Abandoning the iterator drops the remaining elements once.
+        drops.set(0);
+        let (arena, _) = fill(&drops);
+        let mut iter = arena.into_iter();
+        let first = iter.next().unwrap();
+        assert_eq!(first.1.0, 0);
+        drop(iter);
+        assert_eq!(drops.get(), 4);
+        drop(first);
+        assert_eq!(drops.get(), 5);
+
+        // By reference.
+        let (mut arena, _) = fill(&drops);
+        for val in &mut arena{
+            val.0 *= 2;
+        }
+        assert_eq!((&arena).into_iter().map(|val| val.0).collect::<Vec<_>>(), [0, 2, 4, 6, 8]);
+    }
+
+    #[test]
+    fn test_retain(){
+        let mut arena = SArena::<usize, 8>::new();
+        let keys: Vec<_> = (0..8).map(|i| arena.insert(i)).collect();
+
+        let mut seen = Vec::new();
+        arena.retain(|key, val|{
+            seen.push(key);
+            *val += 10;
+            *val % 3 != 0
+        });
+        assert_eq!(seen, keys);
+        assert_eq!(arena.len(), 6);
+        assert_eq!(arena.values().copied().collect::<Vec<_>>(), [10, 11, 13, 14, 16, 17]);
+        assert_eq!(arena.get(keys[2]), None);
+
+        // The freed slots are reused with new generations.
+        let refilled: Vec<_> = (0..2).map(|i| arena.insert(i)).collect();
+        assert!(arena.try_insert(0).is_err());
+        assert_eq!(refilled.iter().map(|key| (key.index(), key.gen())).collect::<Vec<_>>(), [(5, 1), (2, 1)]);
+
+        arena.retain(|_, _| false);
+        assert_eq!(arena.len(), 0);
+        assert_eq!(arena.iter().count(), 0);
+        assert!(refilled.iter().all(|key| arena.get(*key).is_none()));
+        for i in 0..8{
+            let _ = arena.insert(i);
+        }
+        assert_eq!(arena.len(), 8);
+
+        let mut empty = SArena::<usize, 4>::new();
+        empty.retain(|_, _| unreachable!());
+        assert_eq!(empty.len(), 0);
+    }
+
+    #[test]
+    fn test_full(){
+        let mut arena = SArena::<usize, 3>::new();
+        assert!(arena.is_empty());
+        assert_eq!(arena.remaining(), 3);
+
+        let mut keys = Vec::new();
+        while !arena.is_full(){
+            keys.push(arena.insert(keys.len()));
+            assert_eq!(arena.len() + arena.remaining(), arena.capacity());
+        }
+        assert_eq!((arena.len(), arena.remaining()), (3, 0));
+        assert!(!arena.is_empty());
+        assert!(arena.try_insert(3).is_err());
+
+        for key in keys.drain(..){
+            arena.remove(key);
+            assert!(!arena.is_full());
+            assert_eq!(arena.len() + arena.remaining(), arena.capacity());
+        }
+        assert!(arena.is_empty());
+        assert_eq!(arena.remaining(), 3);
+
+        let empty = SArena::<usize, 0>::new();
+        assert!(empty.is_empty() && empty.is_full());
+        assert_eq!(empty.remaining(), 0);
+    }
+
+    #[test]
+    fn test_contains(){
+        let mut arena = SArena::<u8, 4>::new();
+        let i0 = arena.insert(0);
+        let i1 = arena.insert(1);
+        arena.remove(i1);
+        let i2 = arena.insert(2);
+
+        assert!(arena.contains(i0) && arena.contains(i2));
+        assert!(!arena.contains(i1));
+        assert!(arena.contains_slot(i1.index()));
+        assert!(!arena.contains_slot(2));
+
+        for index in [4, 5, usize::MAX]{
+            assert!(!arena.contains(ArenaIdx::from_raw_parts(index, 0)));
+            assert!(!arena.contains_slot(index));
+        }
+        assert!(!arena.contains(ArenaIdx::dangling()));
+        assert!(!SArena::<u8, 0>::new().contains_slot(0));
+    }
+
+    #[test]
+    fn test_insert_with(){
+        use std::panic::{catch_unwind, AssertUnwindSafe};
+
+        let arena = SArena::<String, 3>::new();
+        let i0 = arena.insert_with(|key| key.to_string()).unwrap();
+        assert_eq!(arena.get(i0).map(String::as_str), Some("0@0"));
+
+        // Inserting from within f does not take the reserved slot.
+        let mut inner = None;
+        let outer = arena.insert_with(|key|{
+            assert_eq!(arena.get(key), None);
+            inner = Some(arena.insert(String::from("inner")));
+            key.to_string()
+        }).unwrap();
+        let inner = inner.unwrap();
+        assert_eq!((outer.index(), inner.index()), (1, 2));
+        assert_eq!(arena.get(outer).map(String::as_str), Some("1@0"));
+        assert_eq!(arena.get(inner).map(String::as_str), Some("inner"));
+        assert_eq!(arena.insert_with(|_| unreachable!()), Err(()));
+
+        // A panicking f leaves the slot free and the length unchanged.
+        let mut arena = arena;
+        arena.remove(i0);
+        let res = catch_unwind(AssertUnwindSafe(|| arena.insert_with(|_| panic!("f"))));
+        assert!(res.is_err());
+        assert_eq!(arena.len(), 2);
+        assert_eq!(arena.iter().count(), 2);
+        let reused = arena.insert_with(|key| key.to_string()).unwrap();
+        assert_eq!(arena.get(reused).map(String::as_str), Some("0@1"));
+        assert!(arena.is_full());
+    }
+
+    #[test]
+    fn test_from_values(){
+        // Underfull.
+        let arena = SArena::<u8, 4>::from_array([1, 2]);
+        assert_eq!(arena.keys().collect::<Vec<_>>(), [ArenaIdx::from_raw_parts(0, 0), ArenaIdx::from_raw_parts(1, 0)]);
+        assert_eq!(arena.remaining(), 2);
+        let key = arena.insert(3);
+        assert_eq!(key.index(), 2);
+
+        let arena = SArena::<u8, 4>::try_from_iter([1, 2, 3]).unwrap();
+        assert_eq!(arena.values().copied().collect::<Vec<_>>(), [1, 2, 3]);
+        assert_eq!(SArena::<u8, 4>::try_from_iter([]).unwrap().len(), 0);
+
+        // Exact.
+        let arena = SArena::<_, 4>::from([1u8, 2, 3, 4]);
+        assert!(arena.is_full());
+        assert_eq!(arena.get(ArenaIdx::from_raw_parts(3, 0)), Some(&4));
+        let arena = SArena::<u8, 4>::try_from_iter(1..=4).unwrap();
+        assert!(arena.is_full());
+        assert_eq!(SArena::<u8, 0>::from([]).len(), 0);
+
+        // Overflowing, the iterator is not consumed further than needed.
+        let mut iter = 0..10u8;
+        assert_eq!(SArena::<u8, 4>::try_from_iter(iter.by_ref()).err(), Some(OverflowError{capacity: 4}));
+        assert_eq!(iter.next(), Some(5));
+        assert!(SArena::<u8, 0>::try_from_iter([1]).is_err());
+        assert_eq!(OverflowError{capacity: 4}.to_string(), "more than 4 values for an SArena of that capacity");
+    }
+
+    #[test]
+    fn test_clone_eq(){
+        let mut arena = SArena::<String, 4>::new();
+        let keys: Vec<_> = ["a", "b", "c"].map(String::from).into_iter().map(|val| arena.insert(val)).collect();
+        arena.remove(keys[1]);
+
+        // The clone accepts the keys of the original and reuses the same slots.
+        let mut clone = arena.clone();
+        assert_eq!(clone, arena);
+        assert_eq!(clone.get(keys[0]).map(String::as_str), Some("a"));
+        assert_eq!(clone.get(keys[1]), None);
+        let a = arena.insert(String::from("d"));
+        let b = clone.insert(String::from("d"));
+        assert_eq!(a, b);
+        assert_eq!(clone, arena);
+        *clone.get_mut(keys[2]).unwrap() = String::from("x");
+        assert_ne!(clone, arena);
+        assert_eq!(format!("{:?}", arena), r#"{0: "a", 1: "d", 2: "c"}"#);
+
+        // Removal order changes the free list but not equality.
+        let mut first = SArena::<u8, 4>::from_array([0, 1, 2, 3]);
+        let mut second = first.clone();
+        let keys: Vec<_> = first.keys().collect();
+        first.remove(keys[1]);
+        first.remove(keys[3]);
+        second.remove(keys[3]);
+        second.remove(keys[1]);
+        assert_eq!(first, second);
+
+        // Equal values with other generations are not equal.
+        let mut third = SArena::<u8, 4>::from_array([0, 1, 2, 3]);
+        let fourth = third.clone();
+        third.remove(keys[0]);
+        let _ = third.insert(0);
+        assert_eq!(third.values().collect::<Vec<_>>(), fourth.values().collect::<Vec<_>>());
+        assert_ne!(third, fourth);
+    }
+
+    #[test]
+    fn test_generation_width(){
+        use std::mem::size_of;
+        use std::num::Wrapping;
+
+        // The cells of the default arena are as large as ArenaCells, narrow generations halve them on 64 bit targets.
+        assert_eq!(size_of::<SCell<u32, usize>>(), size_of::<RawCell<u32>>());
+        assert_eq!(size_of::<SCell<u32, u8>>(), 12);
+        assert_eq!(size_of::<SCell<u32, u16>>(), 12);
+        // Besides the cells an SArena holds the head of the free list and the number of elements.
+        let header = 5 * size_of::<usize>();
+        assert_eq!(size_of::<SArena<u32, 32>>(), 32 * size_of::<RawCell<u32>>() + header);
+        assert_eq!(size_of::<SArena<u32, 32, u8>>(), 32 * 12 + header);
+        assert_eq!(size_of::<SArena<u32, 32, Wrapping<u8>>>(), size_of::<SArena<u32, 32, u8>>());
+        // Keys keep their usize index.
+        assert_eq!(size_of::<ArenaIdx<u32, u8>>(), size_of::<usize>() * 2);
+
+        // A saturating u8 retires the slot after 255 reuses, the capacity shrinks by one.
+        let mut arena = SArena::<u32, 2, u8>::new();
+        let mut i0 = arena.insert(0);
+        for _ in 0..254{
+            arena.remove(i0);
+            i0 = arena.insert(0);
+            assert_eq!(i0.index(), 0);
+        }
+        assert_eq!(i0.gen(), 254);
+        arena.remove(i0);
+        assert_eq!(arena.gen(0), Some(u8::MAX));
+        assert_eq!((arena.len(), arena.remaining(), arena.free_count()), (0, 1, 1));
+        let i1 = arena.insert(1);
+        assert_eq!(i1.index(), 1);
+        assert!(arena.is_full());
+        assert_eq!(arena.remaining(), 0);
+        assert_eq!(arena.get(i0), None);
+        // Clearing keeps the slot retired.
+        arena.clear();
+        assert_eq!(arena.insert(2).index(), 1);
+        assert!(arena.try_insert(3).is_err());
+
+        // Wrapping<u8> wraps back to 0 and keeps the slot.
+        let mut arena = SArena::<u32, 1, Wrapping<u8>>::new();
+        let first = arena.insert(0);
+        let mut key = first;
+        for _ in 1..=u8::MAX{
+            arena.remove(key);
+            key = arena.insert(0);
+        }
+        assert_eq!(key, first);
+        arena.retain(|_, _| false);
+        assert_eq!(arena.insert(0).gen(), Wrapping(1));
+    }
+
+    #[test]
+    fn test_get_unchecked(){
+        let mut arena = SArena::<String, 4>::new();
+        let keys: Vec<_> = (0..4).map(|i| arena.insert(i.to_string())).collect();
+        for key in keys.iter(){
+            // SAFETY: All keys are valid.
+            assert_eq!(unsafe{arena.get_unchecked(*key)}, arena.get(*key).unwrap());
+        }
+        // SAFETY: keys[2] is valid.
+        unsafe{arena.get_unchecked_mut(keys[2])}.push('!');
+        assert_eq!(arena.get(keys[2]).map(String::as_str), Some("2!"));
+    }
+
+    #[cfg(debug_assertions)]
+    #[test]
+    #[should_panic]
+    fn test_get_unchecked_stale(){
+        let mut arena = SArena::<u32, 4>::new();
+        let i0 = arena.insert(0);
+        arena.remove(i0);
+        let _ = arena.insert(1);
+        // The debug assertion catches the stale key before it is used.
+        let _ = unsafe{arena.get_unchecked(i0)};
+    }
+
+    #[test]
+    fn test_get2_mut_same_slot(){
+        let mut arena = SArena::<u32, 4>::new();
+        let stale = arena.insert(0);
+        arena.remove(stale);
+        let live = arena.insert(1);
+        assert_eq!(stale.index(), live.index());
+
+        assert_eq!(arena.get2_mut((stale, stale)), (None, None));
+        assert_eq!(arena.get2_mut((live, stale)), (Some(&mut 1), None));
+        assert_eq!(arena.get2_mut((stale, live)), (None, Some(&mut 1)));
+
+        // Two stale copies of a freed slot.
+        arena.remove(live);
+        assert_eq!(arena.get2_mut((live, live)), (None, None));
+        assert_eq!(arena.get2_mut((stale, live)), (None, None));
+
+        let foreign = ArenaIdx::from_raw_parts(4, 0);
+        assert_eq!(arena.get2_mut((foreign, foreign)), (None, None));
+    }
+
+    #[test]
+    fn test_getn_mut_stale_duplicate(){
+        let mut arena = SArena::<u32, 4>::new();
+        let stale = arena.insert(0);
+        arena.remove(stale);
+        let live = arena.insert(1);
+
+        assert_eq!(arena.getn_mut([stale, stale]), [None, None]);
+        assert_eq!(arena.getn_mut([stale, live, stale]), [None, Some(&mut 1), None]);
+
+        arena.remove(live);
+        assert_eq!(arena.getn_mut([live, stale, live]), [None, None, None]);
+    }
+
+    #[test]
+    fn test_get2_mut_getn_mut_agree(){
+        let mut arena = SArena::<u32, 4>::new();
+        let stale = arena.insert(0);
+        arena.remove(stale);
+        let live = arena.insert(1);
+        let other = arena.insert(2);
+        let freed = arena.insert(3);
+        arena.remove(freed);
+        let foreign = ArenaIdx::from_raw_parts(4, 0);
+
+        let keys = [stale, live, other, freed, foreign];
+        for a in keys{
+            for b in keys{
+                if a == b && arena.contains(a){
+                    continue;
+                }
+                let pair = arena.get2_mut((a, b));
+                let pair = (pair.0.copied(), pair.1.copied());
+                let [x, y] = arena.getn_mut([a, b]);
+                assert_eq!(pair, (x.copied(), y.copied()));
+            }
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_get2_mut_same_live(){
+        let mut arena = SArena::<u32, 4>::new();
+        let i0 = arena.insert(0);
+        let _ = arena.get2_mut((i0, i0));
+    }
+
+    #[test]
+    fn test_free_count(){
+        let mut arena = SArena::<u32, 8>::new();
+        assert_eq!(arena.free_count(), 8);
+        assert_eq!(arena.free_indices().collect::<Vec<_>>(), (0..8).collect::<Vec<_>>());
+        arena.debug_assert_valid();
+
+        for cycle in 0..3{
+            let keys: Vec<_> = (0..8).map(|i| arena.insert(i)).collect();
+            assert_eq!(arena.free_count(), 0);
+            assert_eq!(arena.free_indices().count(), 0);
+            arena.debug_assert_valid();
+
+            // Drain every other element, then the rest, or everything at once with clear.
+            for key in keys.iter().step_by(2){
+                arena.remove(*key);
+            }
+            assert_eq!(arena.free_count(), 4);
+            // The last removed slot is reused first.
+            let removed: Vec<_> = keys.iter().step_by(2).rev().map(|key| key.index()).collect();
+            assert_eq!(arena.free_indices().collect::<Vec<_>>(), removed);
+            arena.debug_assert_valid();
+            if cycle == 1{
+                arena.clear();
+            }
+            else{
+                arena.retain(|_, _| false);
+            }
+            assert_eq!(arena.free_count(), 8);
+            assert_eq!(arena.free_indices().count(), 8);
+            arena.debug_assert_valid();
+        }
+
+        // A panicking insert_with puts the slot back.
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(||{
+            let _ = arena.insert_with(|_| panic!());
+        }));
+        assert!(result.is_err());
+        assert_eq!(arena.free_count(), 8);
+        arena.debug_assert_valid();
+
+        // Retired slots are neither free nor allocated.
+        let mut narrow = SArena::<u32, 2, u8>::new();
+        for _ in 0..255{
+            let i0 = narrow.insert(0);
+            narrow.remove(i0);
+        }
+        assert_eq!(narrow.free_count(), 1);
+        assert_eq!(narrow.remaining(), 1);
+        assert_eq!(narrow.free_indices().collect::<Vec<_>>(), [1]);
+        narrow.debug_assert_valid();
+        narrow.clear();
+        assert_eq!(narrow.free_count(), 1);
+        narrow.debug_assert_valid();
+    }
+
+    #[cfg(debug_assertions)]
+    #[test]
+    #[should_panic]
+    fn test_debug_assert_valid_cycle(){
+        let mut arena = SArena::<u32, 4>::from_array([0, 1, 2, 3]);
+        let keys: Vec<_> = arena.keys().collect();
+        arena.remove(keys[3]);
+        arena.remove(keys[2]);
+        // Corrupt the list 2 -> 3 by linking the last cell back to the first.
+        arena.cells_mut()[3] = SCell::Freed{next: Some(2), generation: 1};
+        arena.debug_assert_valid();
+    }
+
+    #[test]
+    fn test_arena_conversion(){
+        // A fragmented arena with reused and trailing freed slots.
+        let mut arena = Arena::new();
+        let mut keys: Vec<_> = (0..8).map(|i| arena.insert(i.to_string())).collect();
+        for i in [1, 3, 7, 6]{
+            arena.remove(keys[i]);
+        }
+        keys.push(arena.insert(String::from("reused")));
+        let stale = keys[6];
+        assert_eq!(keys[8].index(), 6);
+        arena.remove(keys[8]);
+
+        let sarena = SArena::<_, 8>::try_from_arena(arena.clone()).unwrap();
+        sarena.debug_assert_valid();
+        assert_eq!(sarena.len(), arena.num());
+        for key in keys.iter(){
+            assert_eq!(sarena.get(*key), arena.get(*key));
+        }
+        assert_eq!(sarena.gen(6), Some(arena.raw().gen(6)));
+        assert_eq!(sarena.free_indices().collect::<Vec<_>>(), [1, 3, 6, 7]);
+
+        // Slot 6 was reused, so inserting there again does not revive stale keys.
+        let _ = sarena.insert(String::from("new"));
+        let _ = sarena.insert(String::from("new"));
+        let i6 = sarena.insert(String::from("new"));
+        assert_eq!(i6.index(), 6);
+        assert_eq!(sarena.get(stale), None);
+        assert_eq!(sarena.get(keys[8]), None);
+
+        // Round trip back, cloning and consuming.
+        let cloned = Arena::from(&sarena);
+        let moved = Arena::from(sarena.clone());
+        for arena in [&cloned, &moved]{
+            assert_eq!(arena.num(), sarena.len());
+            assert_eq!(arena.iter().map(|(key, val)| (key, val.clone())).collect::<Vec<_>>(),
+                sarena.enumerate().map(|(key, val)| (key, val.clone())).collect::<Vec<_>>());
+        }
+        let mut moved = moved;
+        let key = moved.insert(String::from("appended"));
+        assert_eq!(key.index(), 7);
+        assert_eq!(SArena::<_, 8>::try_from(moved).unwrap().get(key).map(String::as_str), Some("appended"));
+
+        // A trailing occupied slot has to fit, freed slots after it are dropped.
+        let mut arena = Arena::new();
+        let keys: Vec<_> = (0..6).map(|i| arena.insert(i)).collect();
+        arena.remove(keys[4]);
+        arena.remove(keys[5]);
+        let sarena = SArena::<_, 4>::try_from_arena(arena).unwrap();
+        assert_eq!(sarena.values().copied().collect::<Vec<_>>(), [0, 1, 2, 3]);
+        let err = SArena::<_, 3>::try_from_arena(Arena::from(sarena)).unwrap_err();
+        assert_eq!((err.capacity, err.required), (3, 4));
+        assert_eq!(err.into_arena().num(), 4);
+        assert!(SArena::<u8, 0>::try_from_arena(Arena::new()).is_ok());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde(){
+        #[derive(Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+        struct Session{
+            user: String,
+            expires: u64,
+        }
+
+        let mut arena = SArena::<Session, 64>::new();
+        let mut keys: Vec<_> = (0..10).map(|i| arena.insert(Session{user: format!("user{}", i), expires: i})).collect();
+        for key in keys.drain(..5).step_by(2){
+            arena.remove(key);
+        }
+        let stale = arena.insert(Session{user: String::from("temp"), expires: 0});
+        arena.remove(stale);
+        keys.push(arena.insert(Session{user: String::from("reused"), expires: 100}));
+
+        let bytes = postcard::to_allocvec(&arena).unwrap();
+        let loaded: SArena<Session, 64> = postcard::from_bytes(&bytes).unwrap();
+        loaded.debug_assert_valid();
+        assert_eq!(loaded, arena);
+        for key in keys.iter(){
+            assert_eq!(loaded.get(*key), arena.get(*key));
+        }
+        assert_eq!(loaded.get(stale), None);
+        assert_eq!(loaded.free_count(), arena.free_count());
+        assert_eq!((0..64).map(|i| loaded.gen(i)).collect::<Vec<_>>(), (0..64).map(|i| arena.gen(i)).collect::<Vec<_>>());
+
+        // The same through a self describing format, with a narrow generation.
+        let narrow = SArena::<u8, 4, u8>::from_array([1, 2]);
+        let json = serde_json::to_string(&narrow).unwrap();
+        assert_eq!(json, r#"{"values":[[0,0,1],[1,0,2]],"freed":[]}"#);
+        assert_eq!(serde_json::from_str::<SArena<u8, 4, u8>>(&json).unwrap(), narrow);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_invalid(){
+        let load = |json: &str| serde_json::from_str::<SArena<u8, 4>>(json).map_err(|err| err.to_string());
+
+        assert!(load(r#"{"values":[[3,0,1]],"freed":[[2,5]]}"#).is_ok());
+        assert!(load(r#"{"values":[[4,0,1]],"freed":[]}"#).unwrap_err().contains("out of range"));
+        assert!(load(r#"{"values":[],"freed":[[7,1]]}"#).unwrap_err().contains("out of range"));
+        assert!(load(r#"{"values":[[1,0,1],[1,0,2]],"freed":[]}"#).unwrap_err().contains("twice"));
+        assert!(load(r#"{"values":[[1,0,1]],"freed":[[1,1]]}"#).unwrap_err().contains("twice"));
+
+        // Freed slots keep their generation and the free chain is rebuilt in ascending order.
+        let arena = load(r#"{"values":[[1,3,1]],"freed":[[2,5]]}"#).unwrap();
+        assert_eq!(arena.gen(2), Some(5));
+        assert_eq!(arena.free_indices().collect::<Vec<_>>(), [0, 2, 3]);
+        assert_eq!(arena.get(ArenaIdx::from_raw_parts(1, 3)), Some(&1));
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn test_new_boxed(){
+        // About 5 MB, more than the stack of a test thread.
+        let mut arena = SArena::<[u64; 8], 65536>::new_boxed();
+        assert!(std::mem::size_of_val(&*arena) > 4 << 20);
+        assert_eq!((arena.len(), arena.free_count()), (0, 65536));
+        arena.debug_assert_valid();
+
+        let keys: Vec<_> = (0..65536u64).map(|i| arena.insert([i; 8])).collect();
+        assert!(arena.is_full());
+        assert_eq!(keys[65535].index(), 65535);
+        assert_eq!(arena.remove(keys[1000]), Some([1000; 8]));
+        assert_eq!(arena.insert([0; 8]).index(), 1000);
+
+        let empty = SArena::<String, 0>::new_boxed();
+        assert!(empty.is_full());
+    }
+
+    #[test]
+    fn test_double_ended(){
+        let mut arena = SArena::<u32, 16>::new();
+        let keys: Vec<_> = (0..16).map(|i| arena.insert(i)).collect();
+        for i in [0, 3, 4, 9, 15]{
+            arena.remove(keys[i]);
+        }
+        let mut reference: Vec<_> = arena.enumerate().map(|(key, val)| (key, *val)).collect();
+        assert_eq!(reference.len(), 11);
+
+        // Alternate between front and back and compare against the reference list.
+        let mut iter = arena.enumerate();
+        let mut front = true;
+        while !reference.is_empty(){
+            assert_eq!(iter.size_hint().0, reference.len());
+            let expected = if front {reference.remove(0)} else {reference.pop().unwrap()};
+            let item = if front {iter.next()} else {iter.next_back()};
+            assert_eq!(item.map(|(key, val)| (key, *val)), Some(expected));
+            front = !front;
+        }
+        assert_eq!(iter.next(), None);
+        assert_eq!(iter.next_back(), None);
+
+        assert_eq!(arena.keys().rev().map(|key| key.index()).collect::<Vec<_>>(), [14, 13, 12, 11, 10, 8, 7, 6, 5, 2, 1]);
+        assert_eq!(arena.values().next_back(), Some(&14));
+
+        // Mutable and owning iterators are exact.
+        let mut iter = arena.iter_mut();
+        assert_eq!(iter.len(), 11);
+        *iter.next_back().unwrap() += 100;
+        *iter.next().unwrap() += 100;
+        assert_eq!(iter.len(), 9);
+        let mut iter = arena.enumerate_mut().rev();
+        assert_eq!(iter.next().map(|(key, val)| (key.index(), *val)), Some((14, 114)));
+        assert_eq!(iter.len(), 10);
+
+        let mut iter = arena.clone().into_iter();
+        assert_eq!(iter.len(), 11);
+        assert_eq!(iter.next_back().map(|(_, val)| val), Some(114));
+        assert_eq!(iter.next().map(|(_, val)| val), Some(101));
+        assert_eq!(iter.len(), 9);
+        assert_eq!(iter.collect::<Vec<_>>().capacity(), 9);
+
+        // Inserting behind the front keeps the lower bound, the element is reached as well.
+        let arena = SArena::<u32, 4>::from_array([0, 1]);
+        let mut iter = arena.values();
+        assert_eq!(iter.size_hint(), (2, Some(4)));
+        assert_eq!(iter.next(), Some(&0));
+        let _ = arena.insert(2);
+        assert_eq!(iter.size_hint(), (1, Some(3)));
+        assert_eq!(iter.collect::<Vec<_>>(), [&1, &2]);
+    }
+
+    #[test]
+    fn test_validate(){
+        let initialized = ||{
+            let mut arena = SArena::<u32, 4>::from_array([0, 1, 2, 3]);
+            let keys: Vec<_> = arena.keys().collect();
+            arena.remove(keys[3]);
+            arena.remove(keys[2]);
+            arena
+        };
+        let corrupted = |f: fn(&mut [SCell<u32, usize>])|{
+            let mut arena = initialized();
+            f(arena.cells_mut());
+            arena.validate()
+        };
+        // The list is 2 -> 3.
+        assert_eq!(corrupted(|_| ()), Ok(()));
+        assert_eq!(corrupted(|cells| cells[2] = SCell::Freed{next: Some(9), generation: 0}), Err(SArenaCorruption::LinkOutOfRange{index: 9}));
+        assert_eq!(corrupted(|cells| cells[2] = SCell::Freed{next: Some(0), generation: 0}), Err(SArenaCorruption::AllocatedOnFreeList{index: 0}));
+        assert_eq!(corrupted(|cells| cells[3] = SCell::Freed{next: None, generation: usize::MAX}), Err(SArenaCorruption::RetiredOnFreeList{index: 3}));
+        assert_eq!(corrupted(|cells| cells[3] = SCell::Freed{next: Some(2), generation: 0}), Err(SArenaCorruption::Cycle{index: 2}));
+        assert_eq!(corrupted(|cells| cells[3] = SCell::Freed{next: Some(3), generation: 0}), Err(SArenaCorruption::Cycle{index: 3}));
+        assert_eq!(corrupted(|cells| cells[2] = SCell::Freed{next: None, generation: 0}), Err(SArenaCorruption::Unreachable{index: 3}));
+        assert_eq!(corrupted(|cells| cells[1] = SCell::Freed{next: None, generation: 1}), Err(SArenaCorruption::LenMismatch{counted: 1, len: 2}));
+
+        // Linking to a slot that was never used.
+        let arena = SArena::<u32, 4>::from_array([0, 1]);
+        arena.freed.set(Some(3));
+        assert_eq!(arena.validate(), Err(SArenaCorruption::LinkOutOfRange{index: 3}));
+
+        let mut arena = initialized();
+        *arena.free.get_mut() = 3;
+        assert_eq!(arena.validate(), Err(SArenaCorruption::FreeCountMismatch{counted: 2, free_count: 3}));
+        assert_eq!(arena.validate().unwrap_err().to_string(), "2 cells are free, but free_count is 3");
+    }
+
+    #[test]
+    fn test_stats(){
+        let mut arena = SArena::<u32, 8, u8>::new();
+        assert_eq!(arena.stats(), SArenaStats{live: 0, free: 8, retired: 0, longest_free_run: 8});
+
+        let keys: Vec<_> = (0..8).map(|i| arena.insert(i)).collect();
+        assert_eq!(arena.stats(), SArenaStats{live: 8, free: 0, retired: 0, longest_free_run: 0});
+
+        for i in [6, 1, 3, 2]{
+            arena.remove(keys[i]);
+        }
+        assert_eq!(arena.stats(), SArenaStats{live: 4, free: 4, retired: 0, longest_free_run: 3});
+
+        // Retire slot 2, which splits the run.
+        for _ in 1..255{
+            let key = arena.insert(0);
+            assert_eq!(key.index(), 2);
+            arena.remove(key);
+        }
+        assert_eq!(arena.gen(2), Some(u8::MAX));
+        assert_eq!(arena.stats(), SArenaStats{live: 4, free: 3, retired: 1, longest_free_run: 1});
+        assert_eq!(arena.stats().free, arena.free_count());
+        assert_eq!(arena.validate(), Ok(()));
+    }
+
+    #[derive(Debug, Clone)]
+    enum Op{
+        Insert(u8),
+        Remove(usize),
+        Retain(u8),
+        Clear,
+    }
+
+    fn op() -> impl Strategy<Value = Op>{
+        prop_oneof![
+            4 => any::<u8>().prop_map(Op::Insert),
+            3 => any::<usize>().prop_map(Op::Remove),
+            1 => any::<u8>().prop_map(Op::Retain),
+            1 => Just(Op::Clear),
+        ]
+    }
+
+    proptest!{
+        #[test]
+        fn test_random_ops_stay_valid(ops in proptest::collection::vec(op(), 1..300)){
+            // Narrow generations retire slots along the way.
+            let mut arena = SArena::<u8, 8, u8>::new();
+            let mut keys = Vec::new();
+            for op in ops{
+                match op{
+                    Op::Insert(val) => keys.extend(arena.try_insert(val).ok()),
+                    Op::Remove(i) if !keys.is_empty() => {
+                        let key = keys.swap_remove(i % keys.len());
+                        prop_assert!(arena.remove(key).is_some());
+                    }
+                    Op::Remove(_) => (),
+                    Op::Retain(threshold) => {
+                        arena.retain(|_, val| *val < threshold);
+                        keys.retain(|key| arena.contains(*key));
+                    }
+                    Op::Clear => {
+                        arena.clear();
+                        keys.clear();
+                    }
+                }
+                prop_assert_eq!(arena.validate(), Ok(()));
+                let stats = arena.stats();
+                prop_assert_eq!(stats.live, keys.len());
+                prop_assert_eq!(stats.live + stats.free + stats.retired, 8);
+                prop_assert_eq!(arena.is_full(), stats.free == 0);
+            }
+        }
+    }
+
+    #[test]
+    fn test_lazy_init(){
+        let mut arena = SArena::<u32, 4>::new();
+        assert_eq!(arena.init.get(), 0);
+        assert_eq!((arena.free_count(), arena.gen(3), arena.gen(4)), (4, Some(0), None));
+        assert_eq!(arena.free_indices().collect::<Vec<_>>(), [0, 1, 2, 3]);
+
+        // The free list is used before new slots.
+        let a = arena.insert(0);
+        let b = arena.insert(1);
+        assert_eq!(arena.init.get(), 2);
+        arena.remove(a);
+        assert_eq!(arena.free_indices().collect::<Vec<_>>(), [0, 2, 3]);
+        let c = arena.insert(2);
+        assert_eq!((c.index(), c.gen(), arena.init.get()), (0, 1, 2));
+        assert_eq!(arena.validate(), Ok(()));
+        assert_eq!(arena.get(ArenaIdx::from_raw_parts(3, 0)), None);
+
+        let d = arena.insert(3);
+        let e = arena.insert(4);
+        assert!(arena.is_full());
+        assert_eq!(arena.try_insert(5).map_err(InsertError::into_value), Err(5));
+        assert_eq!(arena.values().copied().collect::<Vec<_>>(), [2, 1, 3, 4]);
+        assert_eq!((b.index(), d.index(), e.index()), (1, 2, 3));
+
+        // Clearing keeps the cells initialized.
+        arena.clear();
+        assert_eq!((arena.init.get(), arena.free_count()), (4, 4));
+        assert_eq!(arena.validate(), Ok(()));
+    }
+
+    #[test]
+    fn test_lazy_drop(){
+        use std::rc::Rc;
+
+        let rc = Rc::new(());
+        {
+            let arena = SArena::<_, 8>::new();
+            for _ in 0..3{
+                let _ = arena.insert(rc.clone());
+            }
+            let clone = arena.clone();
+            assert_eq!(Rc::strong_count(&rc), 7);
+            drop(clone);
+            assert_eq!(Rc::strong_count(&rc), 4);
+        }
+        assert_eq!(Rc::strong_count(&rc), 1);
+
+        // Dropping an iterator early drops the rest, including the uninitialized slots.
+        let arena = SArena::<_, 8>::new();
+        for _ in 0..3{
+            let _ = arena.insert(rc.clone());
+        }
+        let mut iter = arena.into_iter();
+        assert!(iter.next().is_some());
+        assert_eq!(Rc::strong_count(&rc), 3);
+        drop(iter);
+        assert_eq!(Rc::strong_count(&rc), 1);
+
+        let arena = SArena::<_, 8>::new();
+        let _ = arena.insert(rc.clone());
+        let arena = Arena::from(arena);
+        assert_eq!((arena.num(), Rc::strong_count(&rc)), (1, 2));
+        drop(arena);
+        assert_eq!(Rc::strong_count(&rc), 1);
+    }
+
+    #[test]
+    fn test_corrupt_free_list(){
+        // The list links to an allocated cell while an unused cell is left.
+        let arena = SArena::<u32, 4>::from_array([0, 1]);
+        arena.freed.set(Some(0));
+        let key = arena.try_insert(2).unwrap();
+        assert_eq!(key.index(), 2);
+        assert_eq!(arena.get(key), Some(&2));
+        assert_eq!(arena.validate(), Ok(()));
+
+        // Without an unused cell the retry fails, the dropped cells are linked again by canonicalize_free_list.
+        let mut arena = SArena::<u32, 4>::from_array([0, 1, 2, 3]);
+        let keys: Vec<_> = arena.keys().collect();
+        arena.remove(keys[3]);
+        arena.remove(keys[2]);
+        arena.cells_mut()[2] = SCell::Freed{next: Some(1), generation: 1};
+        assert_eq!(arena.try_insert(4).map(|_| ()), Ok(()));
+        let err = arena.try_insert(5).unwrap_err();
+        assert_eq!(err.kind, InsertErrorKind::CorruptFreeList{slot: 1});
+        assert_eq!(err.to_string(), "the list of freed slots links to slot 1, which is not free");
+        assert_eq!(err.into_value(), 5);
+        assert_eq!(arena.validate(), Err(SArenaCorruption::Unreachable{index: 3}));
+        arena.canonicalize_free_list();
+        assert_eq!(arena.try_insert(5).map(|key| key.index()), Ok(3));
+        assert_eq!(arena.try_insert(6).unwrap_err().kind, InsertErrorKind::Full);
     }
 }
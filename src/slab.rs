@@ -0,0 +1,305 @@
+use std::ops::{Index, IndexMut};
+
+use crate::*;
+use crate::raw::RawArena;
+
+///
+/// An Arena without generations.
+/// Keys are plain slot indices, so a key of a removed element refers to whatever is inserted into its slot next.
+///
+/// Slab shares its implementation with Arena, only the generation bookkeeping is left out.
+///
+/// # When to use Slab instead of Arena
+///
+/// Use a Slab if keys never outlive the elements they refer to,
+/// for example in short lived arenas that are rebuilt every frame,
+/// or if the owner of a key is always notified when its element is removed.
+/// Lookups then skip the generation check and cells are a little smaller.
+///
+/// Use an Arena whenever keys can be held on to after their element was removed.
+/// A stale Arena key reliably returns None, a stale Slab key silently aliases a new element.
+///
+/// # Example
+///
+///```rust
+/// use gen_arena::*;
+///
+/// let mut slab = Slab::new();
+///
+/// let i0 = slab.insert(0);
+/// let i1 = slab.insert(1);
+///
+/// assert_eq!(slab[i0], 0);
+/// assert_eq!(slab.remove(i1), Some(1));
+/// assert_eq!(slab.get(i1), None);
+///
+/// // The slot is reused and the old key refers to the new element.
+/// let i2 = slab.insert(2);
+/// assert_eq!(i1, i2);
+/// assert_eq!(slab[i1], 2);
+///```
+///
+#[derive(Debug, Clone)]
+pub struct Slab<T>{
+    raw: RawArena<T, ()>,
+}
+
+impl<T> Slab<T>{
+    ///
+    /// Creates an empty Slab.
+    ///
+    pub fn new() -> Self{
+        Self{
+            raw: RawArena::new(),
+        }
+    }
+
+    ///
+    /// Creates an empty Slab with capacity.
+    ///
+    pub fn with_capacity(cap: usize) -> Self{
+        Self{
+            raw: RawArena::with_capacity(cap),
+        }
+    }
+
+    ///
+    /// Clears the slab and resets the list of Freed cells.
+    ///
+    pub fn clear(&mut self){
+        self.raw.clear()
+    }
+
+    ///
+    /// Tries to insert into the slab.
+    /// Returns val as Err if failed.
+    ///
+    pub fn try_insert(&mut self, val: T) -> Result<usize, T>{
+        self.raw.try_insert(val).map(|(index, _)| index).map_err(InsertError::into_value)
+    }
+
+    ///
+    /// Inserts a new element into the slab and returns its index.
+    ///
+    #[must_use]
+    pub fn insert(&mut self, val: T) -> usize{
+        match self.try_insert(val){
+            Ok(index) => index,
+            Err(_val) => panic!("Insertion not successfull."),
+        }
+    }
+
+    ///
+    /// Removes the element at the index.
+    /// Returns the value if the slot was occupied.
+    ///
+    pub fn remove(&mut self, index: usize) -> Option<T>{
+        self.raw.remove(index, ())
+    }
+
+    ///
+    /// Returns true if the slot at the index is occupied.
+    ///
+    #[inline]
+    pub fn contains(&self, index: usize) -> bool{
+        self.raw.get(index, ()).is_some()
+    }
+
+    ///
+    /// Returns an optional reference to the value at the index.
+    ///
+    #[inline]
+    pub fn get(&self, index: usize) -> Option<&T>{
+        self.raw.get(index, ())
+    }
+
+    ///
+    /// Get N optional references to N indices in the slab.
+    ///
+    pub fn getn<const N: usize>(&self, indices: [usize; N]) -> [Option<&T>; N]{
+        indices.map(|index| self.get(index))
+    }
+
+    ///
+    /// Returns a mutable optional reference to the value at the index.
+    ///
+    #[inline]
+    pub fn get_mut(&mut self, index: usize) -> Option<&mut T>{
+        self.raw.get_mut(index, ())
+    }
+
+    ///
+    /// Returns mutable optional references to two distinct values.
+    /// Panics if the indices are equal.
+    ///
+    ///```rust
+    /// use gen_arena::*;
+    ///
+    /// let mut slab = Slab::new();
+    ///
+    /// let i1 = slab.insert(1);
+    /// let i2 = slab.insert(2);
+    ///
+    /// let (c1, c2) = slab.get2_mut((i1, i2));
+    /// std::mem::swap(c1.unwrap(), c2.unwrap());
+    ///
+    /// assert_eq!(slab[i1], 2);
+    /// assert_eq!(slab[i2], 1);
+    ///```
+    ///
+    pub fn get2_mut(&mut self, indices: (usize, usize)) -> (Option<&mut T>, Option<&mut T>){
+        self.raw.get2_mut((indices.0, ()), (indices.1, ()))
+    }
+
+    ///
+    /// Returns iterator over all values.
+    ///
+    #[inline]
+    pub fn values(&self) -> impl Iterator<Item = &T> + '_{
+        self.iter().map(|(_, val)| val)
+    }
+
+    ///
+    /// Returns mutable iterator over all values.
+    ///
+    #[inline]
+    pub fn values_mut(&mut self) -> impl Iterator<Item = &mut T> + '_{
+        self.iter_mut().map(|(_, val)| val)
+    }
+
+    ///
+    /// Iterator over all occupied indices.
+    ///
+    #[inline]
+    pub fn keys(&self) -> impl Iterator<Item = usize> + '_{
+        self.iter().map(|(i, _)| i)
+    }
+
+    ///
+    /// Returns an iterator over the values with their indices.
+    ///
+    #[inline]
+    pub fn iter(&self) -> SlabIter<'_, T>{
+        SlabIter{
+            iter: self.raw.cells.iter().enumerate(),
+        }
+    }
+
+    ///
+    /// Returns a mutable iterator over the values with their indices.
+    ///
+    #[inline]
+    pub fn iter_mut(&mut self) -> SlabIterMut<'_, T>{
+        SlabIterMut{
+            iter: self.raw.cells.iter_mut().enumerate(),
+        }
+    }
+
+    #[inline]
+    pub fn reserve(&mut self, additional: usize){
+        self.raw.cells.reserve(additional)
+    }
+
+    #[inline]
+    pub fn capacity(&self) -> usize{
+        self.raw.cells.capacity()
+    }
+
+    #[inline]
+    pub fn num(&self) -> usize{
+        self.raw.num
+    }
+}
+
+impl<T> Default for Slab<T>{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Index<usize> for Slab<T>{
+    type Output = T;
+
+    fn index(&self, index: usize) -> &Self::Output {
+        self.get(index).expect("There is no element at this index.")
+    }
+}
+
+impl<T> IndexMut<usize> for Slab<T>{
+    fn index_mut(&mut self, index: usize) -> &mut Self::Output {
+        self.get_mut(index).expect("There is no element at this index.")
+    }
+}
+
+pub struct SlabIter<'i, T: 'i>{
+    iter: std::iter::Enumerate<std::slice::Iter<'i, RawCell<T, ()>>>,
+}
+
+impl<'i, T> Iterator for SlabIter<'i, T>{
+    type Item = (usize, &'i T);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        for (i, cell) in self.iter.by_ref(){
+            if let RawCell::Allocated{val, ..} = cell{
+                return Some((i, val));
+            }
+        }
+        None
+    }
+}
+
+pub struct SlabIterMut<'i, T: 'i>{
+    iter: std::iter::Enumerate<std::slice::IterMut<'i, RawCell<T, ()>>>,
+}
+
+impl<'i, T> Iterator for SlabIterMut<'i, T>{
+    type Item = (usize, &'i mut T);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        for (i, cell) in self.iter.by_ref(){
+            if let RawCell::Allocated{val, ..} = cell{
+                return Some((i, val));
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod test{
+    use super::*;
+
+    #[test]
+    fn test_matches_arena(){
+        let mut arena = Arena::new();
+        let mut slab = Slab::new();
+
+        for i in 0..20{
+            let key = arena.insert(i);
+            assert_eq!(slab.insert(i), key.index());
+        }
+        for i in (0..20).step_by(3){
+            assert_eq!(arena.remove(ArenaIdx::from_raw_parts(i, 0)), slab.remove(i));
+        }
+        assert_eq!(slab.remove(0), None);
+        for i in 20..30{
+            let key = arena.insert(i);
+            assert_eq!(slab.insert(i), key.index());
+        }
+
+        let a: Vec<_> = arena.iter().map(|(k, v)| (k.index(), *v)).collect();
+        let s: Vec<_> = slab.iter().map(|(k, v)| (k, *v)).collect();
+        assert_eq!(a, s);
+        assert_eq!(arena.num(), slab.num());
+
+        slab.values_mut().for_each(|val| *val += 1);
+        assert_eq!(slab.values().sum::<i32>(), arena.values().sum::<i32>() + slab.num() as i32);
+
+        slab.clear();
+        assert_eq!(slab.num(), 0);
+        assert_eq!(slab.get(0), None);
+        assert_eq!(slab.insert(0), 0);
+        assert!(slab.contains(0));
+        assert!(!slab.contains(100));
+    }
+}
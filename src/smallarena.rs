@@ -0,0 +1,496 @@
+use std::{marker::PhantomData, ops::{Index, IndexMut}};
+
+use crate::*;
+
+#[derive(Debug, Clone)]
+enum SmallCells<T, const N: usize>{
+    Inline([RawCell<T>; N]),
+    Heap(Vec<RawCell<T>>),
+}
+
+///
+/// A Generational Arena that stores the first N cells inline and spills to the heap beyond that.
+///
+/// The inline cells are moved into a Vec at the same positions when the arena spills,
+/// so keys created before the spill stay valid.
+///
+/// # Example
+///
+///```rust
+/// use gen_arena::*;
+///
+/// let mut arena = SmallArena::<i32, 2>::new();
+///
+/// let i0 = arena.insert(0);
+/// let i1 = arena.insert(1);
+/// assert!(arena.is_inline());
+///
+/// let i2 = arena.insert(2);
+/// assert!(!arena.is_inline());
+///
+/// assert_eq!(*arena.get(i0).unwrap(), 0);
+/// assert_eq!(*arena.get(i1).unwrap(), 1);
+/// assert_eq!(*arena.get(i2).unwrap(), 2);
+///```
+///
+#[derive(Debug, Clone)]
+pub struct SmallArena<T, const N: usize>{
+    cells: SmallCells<T, N>,
+    freed: Option<usize>,
+    num: usize,
+}
+
+impl<T, const N: usize> SmallArena<T, N>{
+    ///
+    /// Creates an empty SmallArena.
+    /// No heap memory is allocated until more than N elements are inserted.
+    ///
+    pub fn new() -> Self{
+        Self{
+            cells: SmallCells::Inline(std::array::from_fn(|i| RawCell::Freed{
+                next: if i + 1 < N {Some(i + 1)} else{None},
+                generation: 0,
+            })),
+            freed: if N > 0 {Some(0)} else{None},
+            num: 0,
+        }
+    }
+
+    #[inline]
+    fn cells(&self) -> &[RawCell<T>]{
+        match &self.cells{
+            SmallCells::Inline(cells) => cells,
+            SmallCells::Heap(cells) => cells,
+        }
+    }
+
+    #[inline]
+    fn cells_mut(&mut self) -> &mut [RawCell<T>]{
+        match &mut self.cells{
+            SmallCells::Inline(cells) => cells,
+            SmallCells::Heap(cells) => cells,
+        }
+    }
+
+    ///
+    /// Moves the inline cells to the heap, keeping their positions.
+    ///
+    fn spill(&mut self, cap: usize){
+        if !self.is_inline(){
+            return;
+        }
+        if let SmallCells::Inline(cells) = std::mem::replace(&mut self.cells, SmallCells::Heap(Vec::new())){
+            let mut vec = Vec::with_capacity(cap.max(N));
+            vec.extend(cells);
+            self.cells = SmallCells::Heap(vec);
+        }
+    }
+
+    ///
+    /// Returns true if the cells are still stored inline.
+    ///
+    #[inline]
+    pub fn is_inline(&self) -> bool{
+        matches!(self.cells, SmallCells::Inline(_))
+    }
+
+    ///
+    /// Clears the arena and resets the list of Freed cells.
+    ///
+    pub fn clear(&mut self){
+        let cells = self.cells_mut();
+        let len = cells.len();
+        for (i, cell) in cells.iter_mut().enumerate(){
+            let generation = match cell{
+                RawCell::Allocated{generation, ..} => *generation + 1,
+                RawCell::Freed{generation, ..} => *generation,
+            };
+            *cell = RawCell::Freed{
+                generation,
+                next: if i < len-1 {Some(i+1)} else{None},
+            };
+        }
+        self.freed = if len > 0 {Some(0)} else{None};
+        self.num = 0;
+    }
+
+    ///
+    /// Tries to insert into the arena.
+    /// Returns val as Err if failed.
+    ///
+    pub fn try_insert(&mut self, val: T) -> Result<ArenaIdx<T>, T>{
+        match self.freed{
+            Some(i) => {
+                let cell = &mut self.cells_mut()[i];
+                if let RawCell::Freed{next, generation} = *cell{
+                    *cell = RawCell::Allocated{
+                        val,
+                        generation,
+                    };
+                    self.freed = next;
+                    self.num += 1;
+                    Ok(ArenaIdx::from_parts(i, generation))
+                }
+                else{
+                    Err(val)
+                }
+            }
+            None => {
+                self.spill(2 * N);
+                let SmallCells::Heap(cells) = &mut self.cells else{
+                    return Err(val);
+                };
+                cells.push(RawCell::Allocated{
+                    generation: 0,
+                    val,
+                });
+                self.num += 1;
+                Ok(ArenaIdx::from_parts(cells.len() - 1, 0))
+            }
+        }
+    }
+
+    ///
+    /// Inserts a new element into the arena.
+    ///
+    #[must_use]
+    pub fn insert(&mut self, val: T) -> ArenaIdx<T>{
+        match self.try_insert(val){
+            Ok(index) => index,
+            Err(_val) => panic!("Insertion not successfull."),
+        }
+    }
+
+    ///
+    /// Removes the cell from the arena and increaces its generation.
+    /// Returns the value if the index is still valid.
+    ///
+    pub fn remove(&mut self, index: ArenaIdx<T>) -> Option<T>{
+        let next = self.freed;
+        let cell = self.cells_mut().get_mut(index.index())?;
+        match cell{
+            RawCell::Allocated{generation, ..} if *generation == index.gen() => {
+                let cell = std::mem::replace(cell, RawCell::Freed{
+                    next,
+                    generation: index.gen() + 1,
+                });
+                self.num -= 1;
+                self.freed = Some(index.index());
+                match cell{
+                    RawCell::Allocated{val, ..} => Some(val),
+                    RawCell::Freed{..} => unreachable!(),
+                }
+            }
+            _ => None,
+        }
+    }
+
+    ///
+    /// Gets the Generation for a given index.
+    /// Panics if the index is out of bounds.
+    ///
+    pub fn gen(&self, index: usize) -> usize{
+        match self.cells()[index]{
+            RawCell::Freed{generation, ..} => generation,
+            RawCell::Allocated{generation, ..} => generation,
+        }
+    }
+
+    ///
+    /// Returns an optional reference to the value at the index.
+    ///
+    pub fn get(&self, index: ArenaIdx<T>) -> Option<&T>{
+        match self.cells().get(index.index()){
+            Some(RawCell::Allocated{val, generation}) if *generation == index.gen() => Some(val),
+            _ => None,
+        }
+    }
+
+    ///
+    /// Returns an optional reference to a cell with any generation.
+    ///
+    pub fn get_any(&self, index: usize) -> Option<&T>{
+        match self.cells().get(index){
+            Some(RawCell::Allocated{val, ..}) => Some(val),
+            _ => None,
+        }
+    }
+
+    ///
+    /// Get N optional references to N indices in the arena.
+    ///
+    pub fn getn<const M: usize>(&self, indices: [ArenaIdx<T>; M]) -> [Option<&T>; M]{
+        indices.map(|index| self.get(index))
+    }
+
+    ///
+    /// Returns a mutable optional reference to the value at the index.
+    ///
+    pub fn get_mut(&mut self, index: ArenaIdx<T>) -> Option<&mut T>{
+        match self.cells_mut().get_mut(index.index()){
+            Some(RawCell::Allocated{val, generation}) if *generation == index.gen() => Some(val),
+            _ => None,
+        }
+    }
+
+    ///
+    /// Returns an optional mutable reference to the value of a cell at a index with any generation.
+    ///
+    pub fn get_any_mut(&mut self, index: usize) -> Option<&mut T>{
+        match self.cells_mut().get_mut(index){
+            Some(RawCell::Allocated{val, ..}) => Some(val),
+            _ => None,
+        }
+    }
+
+    ///
+    /// Returns mutable optional references to two distinct values.
+    /// Panics if both indices refer to the same element, stale indices to the same slot yield None.
+    ///
+    ///```rust
+    /// use gen_arena::*;
+    ///
+    /// let mut arena = SmallArena::<i32, 4>::new();
+    ///
+    /// let i1 = arena.insert(1);
+    /// let i2 = arena.insert(2);
+    ///
+    /// let (c1, c2) = arena.get2_mut((i1, i2));
+    ///
+    /// *c1.unwrap() = 3;
+    /// *c2.unwrap() = 4;
+    ///
+    /// assert_eq!(*arena.get(i1).unwrap(), 3);
+    /// assert_eq!(*arena.get(i2).unwrap(), 4);
+    ///```
+    ///
+    pub fn get2_mut(&mut self, indices: (ArenaIdx<T>, ArenaIdx<T>)) -> (Option<&mut T>, Option<&mut T>){
+        if indices.0.index() == indices.1.index(){
+            // Only a generation matching the allocated one refers to a value,
+            // so the same slot is an error only if both indices are live.
+            if self.get(indices.0).is_none(){
+                return (None, self.get_mut(indices.1));
+            }
+            if indices.0.gen() == indices.1.gen(){
+                panic!("Cannot take 2 mutable references to a value at the same index.")
+            }
+            return (self.get_mut(indices.0), None);
+        }
+
+        let len = self.cells().len();
+        if indices.0.index() >= len{
+            return (None, self.get_mut(indices.1));
+        }
+        if indices.1.index() >= len{
+            return (self.get_mut(indices.0), None);
+        }
+
+        let (cell0, cell1) = {
+            let split = self.cells_mut().split_at_mut(indices.0.index().max(indices.1.index()));
+            if indices.0.index() < indices.1.index(){
+                (&mut split.0[indices.0.index()], &mut split.1[0])
+            }
+            else{
+                (&mut split.1[0], &mut split.0[indices.1.index()])
+            }
+        };
+
+        (value_mut(cell0, indices.0), value_mut(cell1, indices.1))
+    }
+
+    ///
+    /// Returns iterator over all Allocated cells.
+    ///
+    #[inline]
+    pub fn values(&self) -> Values<'_, T>{
+        Values{
+            iter: self.iter()
+        }
+    }
+
+    ///
+    /// Returns mutable iterator over all Allocated cells.
+    ///
+    #[inline]
+    pub fn values_mut(&mut self) -> ValuesMut<'_, T>{
+        ValuesMut{
+            iter: self.iter_mut()
+        }
+    }
+
+    ///
+    /// Iterator over all keys in the arena.
+    ///
+    #[inline]
+    pub fn keys(&self) -> Keys<'_, T>{
+        Keys{
+            iter: self.iter(),
+        }
+    }
+
+    ///
+    /// Returns an iterator over the Allocated cells with index.
+    ///
+    #[inline]
+    pub fn iter(&self) -> Iter<'_, T>{
+        Iter{
+            iter: self.cells().iter().enumerate(),
+            _tag: PhantomData,
+        }
+    }
+
+    ///
+    /// Returns an mutable iterator over the Allocated cells with indices.
+    ///
+    #[inline]
+    pub fn iter_mut(&mut self) -> IterMut<'_, T>{
+        IterMut{
+            iter: self.cells_mut().iter_mut().enumerate(),
+            _tag: PhantomData,
+        }
+    }
+
+    ///
+    /// Reserves capacity for additional elements.
+    /// Spills to the heap if they do not fit inline.
+    ///
+    pub fn reserve(&mut self, additional: usize){
+        match &mut self.cells{
+            SmallCells::Inline(_) => {
+                if self.num + additional > N{
+                    self.spill(self.num + additional);
+                }
+            }
+            SmallCells::Heap(cells) => cells.reserve(additional),
+        }
+    }
+
+    #[inline]
+    pub fn capacity(&self) -> usize{
+        match &self.cells{
+            SmallCells::Inline(_) => N,
+            SmallCells::Heap(cells) => cells.capacity(),
+        }
+    }
+
+    #[inline]
+    pub fn num(&self) -> usize{
+        self.num
+    }
+}
+
+impl<T, const N: usize> Default for SmallArena<T, N>{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[inline]
+fn value_mut<T>(cell: &mut RawCell<T>, index: ArenaIdx<T>) -> Option<&mut T>{
+    match cell{
+        RawCell::Allocated{val, generation} if *generation == index.gen() => Some(val),
+        _ => None,
+    }
+}
+
+impl<T, const N: usize> Index<ArenaIdx<T>> for SmallArena<T, N>{
+    type Output = T;
+
+    fn index(&self, index: ArenaIdx<T>) -> &Self::Output {
+        self.get(index).expect("There is no element at this index with that generation.")
+    }
+}
+
+impl<T, const N: usize> IndexMut<ArenaIdx<T>> for SmallArena<T, N>{
+    fn index_mut(&mut self, index: ArenaIdx<T>) -> &mut Self::Output {
+        self.get_mut(index).expect("There is no element at this index with that generation.")
+    }
+}
+
+#[cfg(test)]
+mod test{
+    use super::*;
+
+    #[test]
+    fn test_spill_keeps_keys(){
+        let mut arena = SmallArena::<String, 4>::new();
+
+        let inline: Vec<_> = (0..4).map(|i| arena.insert(i.to_string())).collect();
+        assert!(arena.is_inline());
+        assert_eq!(arena.capacity(), 4);
+
+        // Reuse a slot while still inline.
+        assert_eq!(arena.remove(inline[1]).as_deref(), Some("1"));
+        let reused = arena.insert("reused".to_string());
+        assert_eq!(reused, ArenaIdx::from_raw_parts(1, 1));
+        assert!(arena.is_inline());
+
+        let heap: Vec<_> = (4..10).map(|i| arena.insert(i.to_string())).collect();
+        assert!(!arena.is_inline());
+        assert_eq!(heap[0], ArenaIdx::from_raw_parts(4, 0));
+
+        // Keys minted while inline still resolve after the spill.
+        assert_eq!(arena.get(inline[0]).map(|s| s.as_str()), Some("0"));
+        assert_eq!(arena.get(inline[1]), None);
+        assert_eq!(arena[reused], "reused");
+        assert_eq!(arena[inline[3]], "3");
+        for (i, key) in heap.iter().enumerate(){
+            assert_eq!(arena[*key], (i + 4).to_string());
+        }
+        assert_eq!(arena.num(), 10);
+
+        let (a, b) = arena.get2_mut((inline[0], heap[5]));
+        std::mem::swap(a.unwrap(), b.unwrap());
+        assert_eq!(arena[inline[0]], "9");
+        assert_eq!(arena[heap[5]], "0");
+        assert_eq!(arena.get2_mut((inline[1], reused)).1.map(|s| s.as_str()), Some("reused"));
+
+        let keys: Vec<_> = arena.keys().map(|key| key.index()).collect();
+        assert_eq!(keys, (0..10).collect::<Vec<_>>());
+
+        arena.clear();
+        assert_eq!(arena.num(), 0);
+        assert_eq!(arena.get(reused), None);
+        assert_eq!(arena.insert("a".to_string()), ArenaIdx::from_raw_parts(0, 1));
+    }
+
+    #[test]
+    fn test_zero_inline(){
+        let mut arena = SmallArena::<i32, 0>::new();
+        assert!(arena.is_inline());
+        let i0 = arena.insert(0);
+        assert!(!arena.is_inline());
+        assert_eq!(arena[i0], 0);
+        assert_eq!(arena.get(ArenaIdx::from_raw_parts(5, 0)), None);
+    }
+
+    #[test]
+    fn test_get2_mut_same_slot(){
+        let mut arena = SmallArena::<i32, 4>::new();
+        let stale = arena.insert(0);
+        arena.remove(stale);
+        let live = arena.insert(1);
+        assert_eq!(stale.index(), live.index());
+
+        assert_eq!(arena.get2_mut((stale, stale)), (None, None));
+        assert_eq!(arena.get2_mut((live, stale)), (Some(&mut 1), None));
+        assert_eq!(arena.get2_mut((stale, live)), (None, Some(&mut 1)));
+
+        // A stale key of a later generation does not hide the live one.
+        let ahead = ArenaIdx::from_raw_parts(live.index(), live.gen() + 5);
+        assert_eq!(arena.get2_mut((live, ahead)), (Some(&mut 1), None));
+        assert_eq!(arena.get2_mut((ahead, live)), (None, Some(&mut 1)));
+
+        // Two stale copies of a freed slot.
+        arena.remove(live);
+        assert_eq!(arena.get2_mut((live, live)), (None, None));
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_get2_mut_same_live(){
+        let mut arena = SmallArena::<i32, 4>::new();
+        let i0 = arena.insert(0);
+        let _ = arena.get2_mut((i0, i0));
+    }
+}
@@ -0,0 +1,189 @@
+use crate::*;
+
+///
+/// The slot table of a struct of arrays generated by `#[derive(ArenaSoA)]`.
+///
+/// Maps generational keys to positions in the dense field Vecs, which are kept packed by
+/// swapping the last element into the position of a removed one, like in a DenseArena.
+/// The derive pushes to and swap removes from every field Vec at the positions it returns.
+///
+/// # Example
+///
+///```rust
+/// use gen_arena::*;
+///
+/// let mut index = SoaIndex::<(u32, f32)>::new();
+/// let mut a = Vec::new();
+/// let mut b = Vec::new();
+///
+/// let k0 = index.insert();
+/// a.push(0u32);
+/// b.push(0.0f32);
+/// let k1 = index.insert();
+/// a.push(1);
+/// b.push(1.0);
+///
+/// let dense = index.remove(k0).unwrap();
+/// a.swap_remove(dense);
+/// b.swap_remove(dense);
+///
+/// assert_eq!(index.dense(k0), None);
+/// assert_eq!(a[index.dense(k1).unwrap()], 1);
+/// assert_eq!(index.keys(), [k1]);
+///```
+///
+#[derive(Debug, Clone)]
+pub struct SoaIndex<T>{
+    slots: Arena<usize>,
+    keys: Vec<ArenaIdx<T>>,
+}
+
+impl<T> SoaIndex<T>{
+    ///
+    /// Creates an empty SoaIndex.
+    ///
+    pub fn new() -> Self{
+        Self{
+            slots: Arena::new(),
+            keys: Vec::new(),
+        }
+    }
+
+    #[inline]
+    fn cast(index: ArenaIdx<T>) -> ArenaIdx<usize>{
+        ArenaIdx::from_parts(index.index(), index.gen())
+    }
+
+    ///
+    /// Allocates a key for an element at the end of the field Vecs.
+    ///
+    pub fn insert(&mut self) -> ArenaIdx<T>{
+        let slot = self.slots.insert(self.keys.len());
+        let index = ArenaIdx::from_parts(slot.index(), slot.gen());
+        self.keys.push(index);
+        index
+    }
+
+    ///
+    /// Frees the key and returns the position of its element, which has to be swap removed from
+    /// every field Vec.
+    ///
+    pub fn remove(&mut self, index: ArenaIdx<T>) -> Option<usize>{
+        let dense = self.slots.remove(Self::cast(index))?;
+        self.keys.swap_remove(dense);
+        if let Some(moved) = self.keys.get(dense){
+            self.slots[Self::cast(*moved)] = dense;
+        }
+        Some(dense)
+    }
+
+    ///
+    /// Returns the position of the element of the key in the field Vecs.
+    ///
+    #[inline]
+    pub fn dense(&self, index: ArenaIdx<T>) -> Option<usize>{
+        self.slots.get(Self::cast(index)).copied()
+    }
+
+    ///
+    /// Returns the keys in the order of the field Vecs.
+    ///
+    #[inline]
+    pub fn keys(&self) -> &[ArenaIdx<T>]{
+        &self.keys
+    }
+
+    #[inline]
+    pub fn clear(&mut self){
+        self.slots.clear();
+        self.keys.clear();
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize{
+        self.keys.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool{
+        self.keys.is_empty()
+    }
+}
+
+impl<T> Default for SoaIndex<T>{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(all(test, feature = "derive"))]
+mod test{
+    use crate::*;
+
+    #[derive(Debug, Clone, PartialEq, ArenaSoA)]
+    struct Particle{
+        pos: [f32; 3],
+        vel: [f32; 3],
+        life: u32,
+    }
+
+    #[derive(Debug, PartialEq, ArenaSoA)]
+    pub struct Tagged<T: Clone>{
+        tag: T,
+        weight: f64,
+    }
+
+    fn particle(i: u32) -> Particle{
+        Particle{
+            pos: [i as f32; 3],
+            vel: [1.; 3],
+            life: i,
+        }
+    }
+
+    #[test]
+    fn test_particles(){
+        let mut arenas = ParticleArenas::new();
+        let keys: Vec<_> = (0..10).map(|i| arenas.insert(particle(i))).collect();
+        assert_eq!(arenas.len(), 10);
+
+        assert_eq!(arenas.remove(keys[2]), Some(particle(2)));
+        assert_eq!(arenas.remove(keys[2]), None);
+        assert_eq!(arenas.life(keys[2]), None);
+        // The last element moved into the freed position.
+        assert_eq!(arenas.life_slice()[2], 9);
+        assert_eq!(arenas.keys()[2], keys[9]);
+
+        *arenas.life_mut(keys[9]).unwrap() += 100;
+        assert_eq!(arenas.life(keys[9]), Some(&109));
+
+        let ParticleSlicesMut{pos, vel, ..} = arenas.slices_mut();
+        for (p, v) in pos.iter_mut().zip(vel.iter()){
+            p[0] += v[0];
+        }
+        assert_eq!(arenas.pos(keys[4]), Some(&[5., 4., 4.]));
+
+        let slices = arenas.slices();
+        assert_eq!(slices.keys.len(), slices.life.len());
+        let total: u32 = arenas.life_slice().iter().sum();
+        assert_eq!(total, (0..10).sum::<u32>() - 2 + 100);
+
+        let k = arenas.insert(particle(50));
+        assert_eq!(k.index(), keys[2].index());
+        assert_ne!(k, keys[2]);
+        assert!(arenas.contains(k));
+        arenas.clear();
+        assert!(arenas.is_empty());
+        assert!(!arenas.contains(k));
+    }
+
+    #[test]
+    fn test_generic(){
+        let mut arenas = TaggedArenas::new();
+        let a = arenas.insert(Tagged{tag: "a", weight: 1.});
+        let b = arenas.insert(Tagged{tag: "b", weight: 2.});
+        assert_eq!(arenas.tag(b), Some(&"b"));
+        assert_eq!(arenas.remove(a), Some(Tagged{tag: "a", weight: 1.}));
+        assert_eq!(arenas.weight_slice(), [2.]);
+    }
+}
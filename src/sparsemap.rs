@@ -0,0 +1,321 @@
+use std::{collections::HashMap, marker::PhantomData, ops::{Index, IndexMut}};
+
+use crate::*;
+
+///
+/// A map attaching additional values to elements of an Arena.
+///
+/// Values are stored in a HashMap keyed by slot index together with the generation of the key they were
+/// inserted with, so memory scales with the number of attached values rather than the size of the arena.
+/// Values attached to a previous occupant of a slot are never returned for a newer key.
+///
+/// Iteration order is arbitrary. The map takes the Tag of the arena, so it only accepts its keys.
+///
+/// # Example
+///
+///```rust
+/// use gen_arena::*;
+///
+/// let mut arena = Arena::new();
+/// let mut labels = SparseSecondaryMap::new();
+///
+/// let i0 = arena.insert(0);
+/// let i1 = arena.insert(1);
+///
+/// labels.insert(i1, "one");
+///
+/// assert_eq!(labels.get(i0), None);
+/// assert_eq!(labels.get(i1), Some(&"one"));
+///
+/// arena.remove(i1);
+/// let i2 = arena.insert(2);
+///
+/// // i2 reuses the slot of i1 but the label belongs to the old generation.
+/// assert_eq!(i2.index(), i1.index());
+/// assert_eq!(labels.get(i2), None);
+///```
+///
+#[derive(Debug, Clone)]
+pub struct SparseSecondaryMap<T, U, Tag = ()>{
+    values: HashMap<usize, (usize, U)>,
+    _ty: PhantomData<(T, fn() -> Tag)>,
+}
+
+impl<T, U, Tag> SparseSecondaryMap<T, U, Tag>{
+    ///
+    /// Creates an empty SparseSecondaryMap.
+    ///
+    pub fn new() -> Self{
+        Self{
+            values: HashMap::new(),
+            _ty: PhantomData,
+        }
+    }
+
+    ///
+    /// Creates an empty SparseSecondaryMap with capacity.
+    ///
+    pub fn with_capacity(cap: usize) -> Self{
+        Self{
+            values: HashMap::with_capacity(cap),
+            _ty: PhantomData,
+        }
+    }
+
+    ///
+    /// Attaches a value to the key and returns the value previously attached to the same key.
+    ///
+    /// A value attached to an older generation of the slot is replaced (and not returned).
+    /// If the slot already holds a value for a newer generation, the key is stale,
+    /// nothing is inserted and val is dropped.
+    ///
+    /// ```rust
+    /// use gen_arena::*;
+    ///
+    /// let mut map = SparseSecondaryMap::<i32, _>::new();
+    ///
+    /// assert_eq!(map.insert(ArenaIdx::from_raw_parts(0, 1), "a"), None);
+    /// assert_eq!(map.insert(ArenaIdx::from_raw_parts(0, 1), "b"), Some("a"));
+    ///
+    /// // Stale key.
+    /// assert_eq!(map.insert(ArenaIdx::from_raw_parts(0, 0), "c"), None);
+    /// assert_eq!(map.get(ArenaIdx::from_raw_parts(0, 1)), Some(&"b"));
+    ///
+    /// // Newer key replaces the value.
+    /// assert_eq!(map.insert(ArenaIdx::from_raw_parts(0, 2), "d"), None);
+    /// assert_eq!(map.get(ArenaIdx::from_raw_parts(0, 1)), None);
+    /// ```
+    ///
+    pub fn insert(&mut self, index: ArenaIdx<T, usize, Tag>, val: U) -> Option<U>{
+        match self.values.get_mut(&index.index()){
+            Some((generation, old)) => {
+                if *generation == index.gen(){
+                    Some(std::mem::replace(old, val))
+                }
+                else{
+                    if *generation < index.gen(){
+                        *generation = index.gen();
+                        *old = val;
+                    }
+                    None
+                }
+            }
+            None => {
+                self.values.insert(index.index(), (index.gen(), val));
+                None
+            }
+        }
+    }
+
+    ///
+    /// Removes the value attached to the key.
+    ///
+    pub fn remove(&mut self, index: ArenaIdx<T, usize, Tag>) -> Option<U>{
+        match self.values.get(&index.index()){
+            Some((generation, _)) if *generation == index.gen() => {
+                self.values.remove(&index.index()).map(|(_, val)| val)
+            }
+            _ => None,
+        }
+    }
+
+    ///
+    /// Returns true if a value is attached to the key.
+    ///
+    #[inline]
+    pub fn contains_key(&self, index: ArenaIdx<T, usize, Tag>) -> bool{
+        self.get(index).is_some()
+    }
+
+    ///
+    /// Returns an optional reference to the value attached to the key.
+    ///
+    pub fn get(&self, index: ArenaIdx<T, usize, Tag>) -> Option<&U>{
+        match self.values.get(&index.index()){
+            Some((generation, val)) if *generation == index.gen() => Some(val),
+            _ => None,
+        }
+    }
+
+    ///
+    /// Returns an optional mutable reference to the value attached to the key.
+    ///
+    pub fn get_mut(&mut self, index: ArenaIdx<T, usize, Tag>) -> Option<&mut U>{
+        match self.values.get_mut(&index.index()){
+            Some((generation, val)) if *generation == index.gen() => Some(val),
+            _ => None,
+        }
+    }
+
+    ///
+    /// Retains only the values for which the predicate returns true.
+    ///
+    pub fn retain(&mut self, mut f: impl FnMut(ArenaIdx<T, usize, Tag>, &mut U) -> bool){
+        self.values.retain(|index, (generation, val)| f(ArenaIdx::from_parts(*index, *generation), val));
+    }
+
+    ///
+    /// Removes all values.
+    ///
+    #[inline]
+    pub fn clear(&mut self){
+        self.values.clear()
+    }
+
+    #[inline]
+    pub fn num(&self) -> usize{
+        self.values.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool{
+        self.values.is_empty()
+    }
+
+    ///
+    /// Returns an iterator over the keys and values in arbitrary order.
+    ///
+    #[inline]
+    pub fn iter(&self) -> SparseIter<'_, T, U, Tag>{
+        SparseIter{
+            iter: self.values.iter(),
+            _ty: PhantomData,
+        }
+    }
+
+    ///
+    /// Returns a mutable iterator over the keys and values in arbitrary order.
+    ///
+    #[inline]
+    pub fn iter_mut(&mut self) -> SparseIterMut<'_, T, U, Tag>{
+        SparseIterMut{
+            iter: self.values.iter_mut(),
+            _ty: PhantomData,
+        }
+    }
+
+    ///
+    /// Returns an iterator over the keys in arbitrary order.
+    ///
+    #[inline]
+    pub fn keys(&self) -> impl Iterator<Item = ArenaIdx<T, usize, Tag>> + '_{
+        self.iter().map(|(i, _)| i)
+    }
+
+    ///
+    /// Returns an iterator over the values in arbitrary order.
+    ///
+    #[inline]
+    pub fn values(&self) -> impl Iterator<Item = &U> + '_{
+        self.values.values().map(|(_, val)| val)
+    }
+
+    ///
+    /// Returns a mutable iterator over the values in arbitrary order.
+    ///
+    #[inline]
+    pub fn values_mut(&mut self) -> impl Iterator<Item = &mut U> + '_{
+        self.values.values_mut().map(|(_, val)| val)
+    }
+}
+
+impl<T, U, Tag> Default for SparseSecondaryMap<T, U, Tag>{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T, U, Tag> Index<ArenaIdx<T, usize, Tag>> for SparseSecondaryMap<T, U, Tag>{
+    type Output = U;
+
+    fn index(&self, index: ArenaIdx<T, usize, Tag>) -> &Self::Output {
+        self.get(index).expect("There is no value attached to this index with that generation.")
+    }
+}
+
+impl<T, U, Tag> IndexMut<ArenaIdx<T, usize, Tag>> for SparseSecondaryMap<T, U, Tag>{
+    fn index_mut(&mut self, index: ArenaIdx<T, usize, Tag>) -> &mut Self::Output {
+        self.get_mut(index).expect("There is no value attached to this index with that generation.")
+    }
+}
+
+pub struct SparseIter<'i, T, U: 'i, Tag = ()>{
+    iter: std::collections::hash_map::Iter<'i, usize, (usize, U)>,
+    _ty: PhantomData<(T, fn() -> Tag)>,
+}
+
+impl<'i, T, U, Tag> Iterator for SparseIter<'i, T, U, Tag>{
+    type Item = (ArenaIdx<T, usize, Tag>, &'i U);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.iter.next().map(|(index, (generation, val))| (ArenaIdx::from_parts(*index, *generation), val))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+pub struct SparseIterMut<'i, T, U: 'i, Tag = ()>{
+    iter: std::collections::hash_map::IterMut<'i, usize, (usize, U)>,
+    _ty: PhantomData<(T, fn() -> Tag)>,
+}
+
+impl<'i, T, U, Tag> Iterator for SparseIterMut<'i, T, U, Tag>{
+    type Item = (ArenaIdx<T, usize, Tag>, &'i mut U);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.iter.next().map(|(index, (generation, val))| (ArenaIdx::from_parts(*index, *generation), val))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+#[cfg(test)]
+mod test{
+    use super::*;
+
+    #[test]
+    fn test_stale_generation_filtering(){
+        let mut arena = Arena::new();
+        let mut map = SparseSecondaryMap::new();
+
+        let keys: Vec<_> = (0..10).map(|i| arena.insert(i)).collect();
+        map.insert(keys[3], "three");
+        map.insert(keys[7], "seven");
+
+        arena.remove(keys[3]);
+        let reused = arena.insert(30);
+        assert_eq!(reused.index(), keys[3].index());
+
+        // The old value is still attached to the old key, but not to the new one.
+        assert_eq!(map.get(keys[3]), Some(&"three"));
+        assert_eq!(map.get(reused), None);
+        assert!(!map.contains_key(reused));
+        assert_eq!(map.get_mut(reused), None);
+        assert_eq!(map.remove(reused), None);
+        assert_eq!(map.num(), 2);
+
+        // Attaching to the new key replaces the stale value.
+        assert_eq!(map.insert(reused, "thirty"), None);
+        assert_eq!(map.get(keys[3]), None);
+        assert_eq!(map.remove(keys[3]), None);
+        assert_eq!(map[reused], "thirty");
+
+        // A stale key can no longer attach a value.
+        assert_eq!(map.insert(keys[3], "stale"), None);
+        assert_eq!(map[reused], "thirty");
+
+        let mut pairs: Vec<_> = map.iter().map(|(k, v)| (k.index(), *v)).collect();
+        pairs.sort();
+        assert_eq!(pairs, vec![(3, "thirty"), (7, "seven")]);
+
+        map.retain(|k, _| arena.get(k).is_some());
+        assert_eq!(map.num(), 2);
+        arena.remove(keys[7]);
+        map.retain(|k, _| arena.get(k).is_some());
+        assert_eq!(map.keys().collect::<Vec<_>>(), vec![reused]);
+    }
+}
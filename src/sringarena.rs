@@ -0,0 +1,246 @@
+use crate::*;
+use crate::orderlist::*;
+
+///
+/// An SArena that can overwrite its oldest element when full, like a bounded ring buffer.
+///
+/// try_insert and insert fail when the arena is full, while insert_overwrite evicts the oldest
+/// element and returns it. The insertion order is an intrusive list through the slots, so
+/// eviction is O(1) and elements can still be removed in any order. Keys of evicted elements
+/// are stale like keys of removed ones.
+///
+/// # Example
+///
+///```rust
+/// use gen_arena::*;
+///
+/// let mut log = SRingArena::<_, 2>::new();
+///
+/// let a = log.insert("boot");
+/// let _ = log.insert("link up");
+///
+/// let (c, evicted) = log.insert_overwrite("link down");
+/// assert_eq!(evicted, Some("boot"));
+/// assert_eq!(log.get(a), None);
+/// assert_eq!(log.iter().map(|(_, msg)| *msg).collect::<Vec<_>>(), ["link up", "link down"]);
+/// assert_eq!(log.get(c), Some(&"link down"));
+///```
+///
+pub struct SRingArena<T, const N: usize, G = usize>{
+    entries: SArena<OrderEntry<T>, N, G>,
+    order: OrderList,
+}
+
+impl<T, const N: usize, G: GenCounter> SRingArena<T, N, G>{
+    ///
+    /// Creates a new empty SRingArena.
+    ///
+    pub const fn new() -> Self{
+        Self{
+            entries: SArena::new(),
+            order: OrderList::new(),
+        }
+    }
+
+    ///
+    /// Inserts a new element as the newest one.
+    /// Returns the value if the arena is full.
+    ///
+    pub fn try_insert(&mut self, val: T) -> Result<ArenaIdx<T, G>, T>{
+        let index = self.entries.try_insert(OrderEntry::new(val)).map_err(|err| err.into_value().val)?;
+        self.order.push_newest(&mut self.entries, index.index());
+        Ok(uncast(index))
+    }
+
+    ///
+    /// Inserts a new element as the newest one.
+    /// Panics if the arena is full.
+    ///
+    #[must_use]
+    pub fn insert(&mut self, val: T) -> ArenaIdx<T, G>{
+        self.try_insert(val).ok().expect("Insertion not successfull.")
+    }
+
+    ///
+    /// Inserts a new element as the newest one, evicting the oldest element if the arena is full.
+    /// Returns the key of the new element and the evicted value.
+    ///
+    /// With a saturating generation the slot of an evicted element may retire, in which case
+    /// further elements are evicted and dropped until a slot is free. Panics if no slot is left.
+    ///
+    pub fn insert_overwrite(&mut self, val: T) -> (ArenaIdx<T, G>, Option<T>){
+        let mut evicted = None;
+        while self.is_full(){
+            let (_, val) = self.pop_oldest().expect("Insertion not successfull.");
+            evicted.get_or_insert(val);
+        }
+        (self.insert(val), evicted)
+    }
+
+    ///
+    /// Removes the element and returns its value if the index is still valid.
+    ///
+    pub fn remove(&mut self, index: ArenaIdx<T, G>) -> Option<T>{
+        if !self.contains(index){
+            return None;
+        }
+        self.order.unlink(&mut self.entries, index.index());
+        self.entries.remove(cast(index)).map(|entry| entry.val)
+    }
+
+    ///
+    /// Removes the oldest element.
+    ///
+    pub fn pop_oldest(&mut self) -> Option<(ArenaIdx<T, G>, T)>{
+        let index = self.oldest()?;
+        self.remove(index).map(|val| (index, val))
+    }
+
+    ///
+    /// Returns the key of the oldest element, which insert_overwrite evicts next.
+    ///
+    pub fn oldest(&self) -> Option<ArenaIdx<T, G>>{
+        let slot = self.order.oldest()?;
+        self.entries.gen(slot).map(|generation| ArenaIdx::from_parts(slot, generation))
+    }
+
+    #[inline]
+    pub fn get(&self, index: ArenaIdx<T, G>) -> Option<&T>{
+        self.entries.get(cast(index)).map(|entry| &entry.val)
+    }
+
+    #[inline]
+    pub fn get_mut(&mut self, index: ArenaIdx<T, G>) -> Option<&mut T>{
+        self.entries.get_mut(cast(index)).map(|entry| &mut entry.val)
+    }
+
+    #[inline]
+    pub fn contains(&self, index: ArenaIdx<T, G>) -> bool{
+        self.entries.contains(cast(index))
+    }
+
+    ///
+    /// Returns an iterator over the elements from the oldest to the newest one.
+    ///
+    pub fn iter(&self) -> impl Iterator<Item = (ArenaIdx<T, G>, &T)>{
+        self.order.slots(&self.entries).map(move |slot|{
+            let generation = self.entries.gen(slot).expect("Linked entries are always valid.");
+            let entry = self.entries.get_any(slot).expect("Linked entries are always valid.");
+            (ArenaIdx::from_parts(slot, generation), &entry.val)
+        })
+    }
+
+    #[inline]
+    pub fn clear(&mut self){
+        self.entries.clear();
+        self.order.clear();
+    }
+
+    #[inline]
+    pub fn capacity(&self) -> usize{
+        N
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize{
+        self.entries.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool{
+        self.len() == 0
+    }
+
+    #[inline]
+    pub fn is_full(&self) -> bool{
+        self.entries.is_full()
+    }
+}
+
+impl<T, const N: usize, G: GenCounter> Default for SRingArena<T, N, G>{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: std::fmt::Debug, const N: usize, G: GenCounter> std::fmt::Debug for SRingArena<T, N, G>{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_map().entries(self.iter().map(|(i, val)| (i.index(), val))).finish()
+    }
+}
+
+#[cfg(test)]
+mod test{
+    use super::*;
+
+    #[test]
+    fn test_fifo_eviction(){
+        let mut ring = SRingArena::<_, 4>::new();
+        let mut keys: Vec<_> = (0..4).map(|i| ring.insert(i)).collect();
+        assert!(ring.is_full());
+        assert_eq!(ring.try_insert(4), Err(4));
+
+        // Keep inserting, every insert evicts the oldest element.
+        for i in 4..12{
+            let oldest = ring.oldest().unwrap();
+            assert_eq!(oldest, keys[i - 4]);
+            let (key, evicted) = ring.insert_overwrite(i);
+            assert_eq!(evicted, Some(i - 4));
+            assert_eq!(key.index(), oldest.index());
+            assert_ne!(key.gen(), oldest.gen());
+            keys.push(key);
+        }
+        for (i, key) in keys.iter().enumerate(){
+            assert_eq!(ring.get(*key), if i < 8 {None} else {Some(&i)});
+        }
+        assert_eq!(ring.iter().map(|(_, val)| *val).collect::<Vec<_>>(), [8, 9, 10, 11]);
+
+        // Removing out of order keeps the order of the rest, the freed slot is used first.
+        assert_eq!(ring.remove(keys[9]), Some(9));
+        assert_eq!(ring.remove(keys[9]), None);
+        let (key, evicted) = ring.insert_overwrite(12);
+        assert_eq!((key.index(), evicted), (keys[9].index(), None));
+        let (_, evicted) = ring.insert_overwrite(13);
+        assert_eq!(evicted, Some(8));
+        assert_eq!(ring.iter().map(|(_, val)| *val).collect::<Vec<_>>(), [10, 11, 12, 13]);
+
+        *ring.get_mut(key).unwrap() += 100;
+        assert_eq!(ring.pop_oldest(), Some((keys[10], 10)));
+        assert_eq!(ring.iter().map(|(_, val)| *val).collect::<Vec<_>>(), [11, 112, 13]);
+
+        ring.clear();
+        assert!(ring.is_empty());
+        assert_eq!(ring.oldest(), None);
+        assert_eq!(ring.insert_overwrite(0).1, None);
+    }
+
+    #[test]
+    fn test_retired_slots(){
+        // With u8 generations slot 0 retires when its element is evicted.
+        let mut ring = SRingArena::<u32, 2, u8>::new();
+        for i in 0..254{
+            let key = ring.insert(i);
+            ring.remove(key);
+        }
+        let a = ring.insert(0);
+        let b = ring.insert(1);
+        assert_eq!((a.index(), a.gen()), (0, 254));
+
+        // The retired slot cannot take the new element, so b is evicted as well.
+        let (c, evicted) = ring.insert_overwrite(2);
+        assert_eq!(evicted, Some(0));
+        assert_eq!(ring.get(b), None);
+        assert_eq!(c.index(), 1);
+        assert_eq!(ring.len(), 1);
+
+        let (d, evicted) = ring.insert_overwrite(3);
+        assert_eq!((d.index(), evicted), (1, Some(2)));
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_zero_capacity(){
+        let mut ring = SRingArena::<u32, 0>::new();
+        let _ = ring.insert_overwrite(0);
+    }
+}
@@ -0,0 +1,237 @@
+use std::ops::Index;
+
+const MIN_CHUNK: usize = 4096;
+
+///
+/// A handle to a string in a StrArena.
+///
+/// Handles are 16 bytes and Copy. They stay valid until the StrArena is cleared.
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct StrIdx{
+    chunk: u32,
+    start: u32,
+    len: u32,
+    epoch: u32,
+}
+
+impl StrIdx{
+    ///
+    /// Returns the length of the string in bytes.
+    ///
+    #[inline]
+    pub fn len(&self) -> usize{
+        self.len as usize
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool{
+        self.len == 0
+    }
+}
+
+///
+/// An append only arena for strings.
+///
+/// Strings are copied into large shared buffers, so allocating a string does not allocate
+/// unless the current buffer is full. Unlike an Interner it does not deduplicate strings
+/// and they can only be looked up by their StrIdx.
+/// Strings can not be removed individually, clear removes all of them at once and
+/// invalidates all handles.
+///
+/// # Example
+///
+///```rust
+/// use gen_arena::*;
+///
+/// let mut strings = StrArena::new();
+///
+/// let foo = strings.alloc("foo");
+/// let bar = strings.alloc("bar");
+/// let foo2 = strings.alloc("foo");
+///
+/// assert_eq!(&strings[foo], "foo");
+/// assert_eq!(strings.get(bar), Some("bar"));
+/// assert_ne!(foo, foo2);
+/// assert_eq!(strings.len_bytes(), 9);
+/// assert_eq!(strings.iter().collect::<Vec<_>>(), ["foo", "bar", "foo"]);
+///
+/// strings.clear();
+/// assert_eq!(strings.get(foo), None);
+///```
+///
+#[derive(Debug, Clone, Default)]
+pub struct StrArena{
+    chunks: Vec<String>,
+    strs: Vec<StrIdx>,
+    len_bytes: usize,
+    epoch: u32,
+}
+
+impl StrArena{
+    ///
+    /// Creates an empty StrArena. No buffer is allocated before the first string.
+    ///
+    pub fn new() -> Self{
+        Self::default()
+    }
+
+    ///
+    /// Creates an empty StrArena with a first buffer of at least cap bytes.
+    ///
+    pub fn with_capacity(cap: usize) -> Self{
+        Self{
+            chunks: vec![String::with_capacity(cap)],
+            ..Self::default()
+        }
+    }
+
+    ///
+    /// Copies the string into the arena and returns its handle.
+    /// Panics if the string or the number of buffers exceeds u32::MAX.
+    ///
+    pub fn alloc(&mut self, s: &str) -> StrIdx{
+        let fits = self.chunks.last().is_some_and(|chunk| chunk.capacity() - chunk.len() >= s.len());
+        if !fits{
+            // Buffers double in size, so there are only logarithmically many of them.
+            let last = self.chunks.last().map_or(0, |chunk| chunk.capacity());
+            let cap = (last * 2).max(MIN_CHUNK).max(s.len());
+            self.chunks.push(String::with_capacity(cap));
+        }
+        let chunk = self.chunks.len() - 1;
+        let buf = &mut self.chunks[chunk];
+        let start = buf.len();
+        // Appending within the capacity never moves the buffer.
+        buf.push_str(s);
+
+        let index = StrIdx{
+            chunk: u32::try_from(chunk).expect("Too many buffers."),
+            start: u32::try_from(start).expect("Buffer too large."),
+            len: u32::try_from(s.len()).expect("String too large."),
+            epoch: self.epoch,
+        };
+        self.strs.push(index);
+        self.len_bytes += s.len();
+        index
+    }
+
+    ///
+    /// Returns the string of the handle, or None if the arena was cleared since it was allocated.
+    ///
+    #[inline]
+    pub fn get(&self, index: StrIdx) -> Option<&str>{
+        if index.epoch != self.epoch{
+            return None;
+        }
+        let start = index.start as usize;
+        self.chunks.get(index.chunk as usize)?.get(start..start + index.len())
+    }
+
+    ///
+    /// Returns an iterator over all strings in allocation order.
+    ///
+    pub fn iter(&self) -> impl Iterator<Item = &str>{
+        self.strs.iter().map(|index| &self[*index])
+    }
+
+    ///
+    /// Returns an iterator over the handles of all strings in allocation order.
+    ///
+    pub fn keys(&self) -> impl Iterator<Item = StrIdx> + '_{
+        self.strs.iter().copied()
+    }
+
+    ///
+    /// Removes all strings and invalidates their handles.
+    /// Keeps the largest buffer for further allocations.
+    ///
+    pub fn clear(&mut self){
+        if let Some(mut last) = self.chunks.pop(){
+            last.clear();
+            self.chunks.clear();
+            self.chunks.push(last);
+        }
+        self.strs.clear();
+        self.len_bytes = 0;
+        self.epoch = self.epoch.wrapping_add(1);
+    }
+
+    ///
+    /// Returns the number of bytes of all stored strings.
+    ///
+    #[inline]
+    pub fn len_bytes(&self) -> usize{
+        self.len_bytes
+    }
+
+    ///
+    /// Returns the number of bytes reserved by the buffers.
+    ///
+    pub fn capacity_bytes(&self) -> usize{
+        self.chunks.iter().map(|chunk| chunk.capacity()).sum()
+    }
+
+    ///
+    /// Returns the number of stored strings.
+    ///
+    #[inline]
+    pub fn num(&self) -> usize{
+        self.strs.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool{
+        self.strs.is_empty()
+    }
+}
+
+impl Index<StrIdx> for StrArena{
+    type Output = str;
+
+    fn index(&self, index: StrIdx) -> &Self::Output {
+        self.get(index).expect("The StrArena was cleared since the string was allocated.")
+    }
+}
+
+#[cfg(test)]
+mod test{
+    use super::*;
+
+    #[test]
+    fn test_alloc_and_clear(){
+        let mut strings = StrArena::new();
+        let words: Vec<String> = (0..2000).map(|i| "x".repeat(i % 37) + &i.to_string()).collect();
+        let keys: Vec<_> = words.iter().map(|w| strings.alloc(w)).collect();
+
+        for (key, word) in keys.iter().zip(words.iter()){
+            assert_eq!(&strings[*key], word);
+            assert_eq!(key.len(), word.len());
+        }
+        assert_eq!(strings.iter().collect::<Vec<_>>(), words);
+        assert_eq!(strings.keys().collect::<Vec<_>>(), keys);
+        assert_eq!(strings.len_bytes(), words.iter().map(|w| w.len()).sum::<usize>());
+        assert!(strings.capacity_bytes() >= strings.len_bytes());
+        // Far fewer buffers than strings.
+        assert!(strings.chunks.len() < 10);
+
+        // Empty strings and strings larger than a buffer.
+        let empty = strings.alloc("");
+        let large = "y".repeat(3 * MIN_CHUNK);
+        let l = strings.alloc(&large);
+        assert_eq!(&strings[empty], "");
+        assert!(empty.is_empty());
+        assert_eq!(&strings[l], large);
+
+        strings.clear();
+        assert_eq!(strings.get(keys[0]), None);
+        assert_eq!(strings.get(l), None);
+        assert_eq!(strings.num(), 0);
+        assert_eq!(strings.chunks.len(), 1);
+
+        // New handles may have the same position as old ones but are distinguished by epoch.
+        let again = strings.alloc("again");
+        assert_eq!(&strings[again], "again");
+        assert_eq!(strings.get(keys[0]), None);
+        assert_eq!(strings.len_bytes(), 5);
+    }
+}
@@ -0,0 +1,200 @@
+use std::{hash::{Hash, Hasher}, marker::PhantomData};
+
+use crate::*;
+
+///
+/// A key and a small user tag packed into a u64, for example to pass handles to shaders.
+///
+/// The layout is stable. From the least significant bit:
+///
+/// | bits                    | content    |
+/// |-------------------------|------------|
+/// | 0 .. 32                 | index      |
+/// | 32 .. 64 - TAG_BITS     | generation |
+/// | 64 - TAG_BITS .. 64     | tag        |
+///
+/// so a decoder reads `index = bits & 0xffffffff`, `generation = (bits >> 32) & ((1 << (32 - TAG_BITS)) - 1)`
+/// and `tag = bits >> (64 - TAG_BITS)`. TAG_BITS defaults to 8 and can be at most 16.
+/// Keys whose index or generation do not fit are rejected when packing.
+///
+/// Arena::get and Arena::get_mut accept a TaggedIdx directly and ignore its tag.
+///
+/// # Example
+///
+///```rust
+/// use gen_arena::*;
+///
+/// let mut arena = Arena::new();
+/// let _ = arena.insert("a");
+/// let i1 = arena.insert("b");
+///
+/// let tagged = TaggedIdx::<_>::new(i1, 5).unwrap();
+/// assert_eq!(tagged.to_bits(), (5 << 56) | 1);
+/// assert_eq!(tagged.tag(), 5);
+/// assert_eq!(tagged.idx(), i1);
+/// assert_eq!(arena.get(tagged), Some(&"b"));
+///
+/// assert_eq!(TaggedIdx::<_>::new(i1, 256), None);
+///```
+///
+pub struct TaggedIdx<T, const TAG_BITS: u32 = 8>{
+    bits: u64,
+    _ty: PhantomData<T>,
+}
+
+impl<T, const TAG_BITS: u32> TaggedIdx<T, TAG_BITS>{
+    const GEN_BITS: u32 = {
+        assert!(TAG_BITS <= 16, "A TaggedIdx has at most 16 tag bits.");
+        32 - TAG_BITS
+    };
+
+    ///
+    /// The largest tag that can be packed.
+    ///
+    pub const MAX_TAG: u32 = ((1u64 << TAG_BITS) - 1) as u32;
+
+    ///
+    /// The largest generation that can be packed.
+    ///
+    pub const MAX_GEN: usize = ((1u64 << Self::GEN_BITS) - 1) as usize;
+
+    ///
+    /// Packs the key with the tag.
+    /// Returns None if the tag is larger than MAX_TAG, the generation larger than MAX_GEN
+    /// or the index does not fit into 32 bits.
+    ///
+    pub fn new(index: ArenaIdx<T>, tag: u32) -> Option<Self>{
+        if index.index() > u32::MAX as usize || index.gen() > Self::MAX_GEN || tag > Self::MAX_TAG{
+            return None;
+        }
+        Some(Self::from_bits(
+            ((tag as u64) << Self::GEN_BITS << 32) | ((index.gen() as u64) << 32) | index.index() as u64
+        ))
+    }
+
+    ///
+    /// Returns the same key with another tag, or None if the tag is larger than MAX_TAG.
+    ///
+    #[inline]
+    pub fn with_tag(self, tag: u32) -> Option<Self>{
+        Self::new(self.idx(), tag)
+    }
+
+    #[inline]
+    pub fn tag(&self) -> u32{
+        (self.bits >> Self::GEN_BITS >> 32) as u32
+    }
+
+    ///
+    /// Returns the key without the tag.
+    ///
+    #[inline]
+    pub fn idx(&self) -> ArenaIdx<T>{
+        let generation = (self.bits >> 32) & Self::MAX_GEN as u64;
+        ArenaIdx::from_parts(self.bits as u32 as usize, generation as usize)
+    }
+
+    #[inline]
+    pub fn to_bits(self) -> u64{
+        self.bits
+    }
+
+    ///
+    /// Unpacks bits previously packed with to_bits.
+    ///
+    #[inline]
+    pub fn from_bits(bits: u64) -> Self{
+        Self{
+            bits,
+            _ty: PhantomData,
+        }
+    }
+}
+
+impl<T, const TAG_BITS: u32> From<TaggedIdx<T, TAG_BITS>> for ArenaIdx<T>{
+    #[inline]
+    fn from(index: TaggedIdx<T, TAG_BITS>) -> Self {
+        index.idx()
+    }
+}
+
+impl<T, const TAG_BITS: u32> Clone for TaggedIdx<T, TAG_BITS>{
+    #[inline]
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T, const TAG_BITS: u32> Copy for TaggedIdx<T, TAG_BITS>{}
+
+impl<T, const TAG_BITS: u32> PartialEq for TaggedIdx<T, TAG_BITS>{
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        self.bits == other.bits
+    }
+}
+
+impl<T, const TAG_BITS: u32> Eq for TaggedIdx<T, TAG_BITS>{}
+
+impl<T, const TAG_BITS: u32> Hash for TaggedIdx<T, TAG_BITS>{
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.bits.hash(state)
+    }
+}
+
+impl<T, const TAG_BITS: u32> std::fmt::Debug for TaggedIdx<T, TAG_BITS>{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let index = self.idx();
+        f.debug_struct("TaggedIdx")
+            .field("index", &index.index())
+            .field("generation", &index.gen())
+            .field("tag", &self.tag())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod test{
+    use super::*;
+
+    #[test]
+    fn test_limits(){
+        let mut arena = Arena::new();
+        let keys: Vec<_> = (0..4).map(|i| arena.insert(i)).collect();
+
+        let tagged = TaggedIdx::<_>::new(keys[3], TaggedIdx::<i32>::MAX_TAG).unwrap();
+        assert_eq!(tagged.tag(), 255);
+        assert_eq!(tagged.idx(), keys[3]);
+        assert_eq!(tagged.to_bits() >> 56, 255);
+        assert_eq!(TaggedIdx::<_>::new(keys[3], 256), None);
+        assert_eq!(tagged.with_tag(0).unwrap().tag(), 0);
+        assert_eq!(tagged.with_tag(1000), None);
+
+        // The largest index and generation pack without touching the tag.
+        let max = ArenaIdx::<i32>::from_raw_parts(u32::MAX as usize, TaggedIdx::<i32>::MAX_GEN);
+        let packed = TaggedIdx::<_>::new(max, 0x81).unwrap();
+        assert_eq!(packed.to_bits(), !(0x7e << 56));
+        assert_eq!(packed.idx(), max);
+        assert_eq!(packed.tag(), 0x81);
+        assert_eq!(TaggedIdx::<_>::new(ArenaIdx::<i32>::from_raw_parts(u32::MAX as usize + 1, 0), 0), None);
+        assert_eq!(TaggedIdx::<_>::new(ArenaIdx::<i32>::from_raw_parts(0, TaggedIdx::<i32>::MAX_GEN + 1), 0), None);
+
+        // Four tag bits leave 28 for the generation.
+        assert_eq!(TaggedIdx::<i32, 4>::MAX_TAG, 15);
+        assert_eq!(TaggedIdx::<i32, 4>::MAX_GEN, (1 << 28) - 1);
+        let narrow = TaggedIdx::<_, 4>::new(keys[1], 15).unwrap();
+        assert_eq!(narrow.to_bits(), (15 << 60) | 1);
+        assert_eq!(TaggedIdx::<_, 4>::new(keys[1], 16), None);
+        assert_eq!(TaggedIdx::<_, 4>::from_bits(narrow.to_bits()), narrow);
+
+        // Lookups ignore the tag and still check the generation.
+        let mut tagged = TaggedIdx::<_>::new(keys[2], 7).unwrap();
+        assert_eq!(arena.get(tagged), Some(&2));
+        *arena.get_mut(tagged).unwrap() += 10;
+        tagged = tagged.with_tag(3).unwrap();
+        assert_eq!(arena.get(tagged), Some(&12));
+        arena.remove(keys[2]);
+        let _ = arena.insert(20);
+        assert_eq!(arena.get(tagged), None);
+    }
+}
@@ -0,0 +1,262 @@
+use std::ops::{Index, IndexMut};
+
+use crate::*;
+
+#[derive(Debug, Clone)]
+struct Stamped<T>{
+    val: T,
+    touched: u64,
+}
+
+///
+/// A Generational Arena recording when each element was last touched.
+///
+/// The current tick is set by the user with set_now, so any clock works, including a frame counter
+/// on targets without std::time. Insertion, touch and every mutable access stamp the element
+/// with the current tick; sweep_older_than removes all elements not touched since a cutoff.
+///
+/// # Example
+///
+///```rust
+/// use gen_arena::*;
+///
+/// let mut sessions = TimedArena::new();
+///
+/// sessions.set_now(0);
+/// let alice = sessions.insert("alice");
+/// let bob = sessions.insert("bob");
+///
+/// sessions.set_now(10);
+/// sessions.touch(alice);
+///
+/// let mut expired = Vec::new();
+/// sessions.sweep_older_than(5, |_, name| expired.push(name));
+///
+/// assert_eq!(expired, ["bob"]);
+/// assert_eq!(sessions.get(alice), Some(&"alice"));
+/// assert_eq!(sessions.get(bob), None);
+///```
+///
+#[derive(Debug, Clone)]
+pub struct TimedArena<T>{
+    arena: Arena<Stamped<T>>,
+    now: u64,
+}
+
+impl<T> TimedArena<T>{
+    ///
+    /// Creates an empty TimedArena at tick 0.
+    ///
+    pub fn new() -> Self{
+        Self{
+            arena: Arena::new(),
+            now: 0,
+        }
+    }
+
+    #[inline]
+    fn cast(index: ArenaIdx<T>) -> ArenaIdx<Stamped<T>>{
+        ArenaIdx::from_parts(index.index(), index.gen())
+    }
+
+    #[inline]
+    fn uncast(index: ArenaIdx<Stamped<T>>) -> ArenaIdx<T>{
+        ArenaIdx::from_parts(index.index(), index.gen())
+    }
+
+    ///
+    /// Sets the current tick. Ticks have to increase monotonically.
+    ///
+    #[inline]
+    pub fn set_now(&mut self, now: u64){
+        debug_assert!(now >= self.now, "Ticks have to increase monotonically.");
+        self.now = now;
+    }
+
+    ///
+    /// Returns the current tick.
+    ///
+    #[inline]
+    pub fn now(&self) -> u64{
+        self.now
+    }
+
+    ///
+    /// Inserts a new element stamped with the current tick.
+    ///
+    #[must_use]
+    pub fn insert(&mut self, val: T) -> ArenaIdx<T>{
+        Self::uncast(self.arena.insert(Stamped{
+            val,
+            touched: self.now,
+        }))
+    }
+
+    ///
+    /// Removes the element and returns its value if the index is still valid.
+    ///
+    pub fn remove(&mut self, index: ArenaIdx<T>) -> Option<T>{
+        self.arena.remove(Self::cast(index)).map(|stamped| stamped.val)
+    }
+
+    ///
+    /// Stamps the element with the current tick without accessing its value.
+    /// Returns false if the index is not valid.
+    ///
+    pub fn touch(&mut self, index: ArenaIdx<T>) -> bool{
+        match self.arena.get_mut(Self::cast(index)){
+            Some(stamped) => {
+                stamped.touched = self.now;
+                true
+            }
+            None => false,
+        }
+    }
+
+    ///
+    /// Returns the tick the element was last touched at.
+    ///
+    #[inline]
+    pub fn last_touched(&self, index: ArenaIdx<T>) -> Option<u64>{
+        self.arena.get(Self::cast(index)).map(|stamped| stamped.touched)
+    }
+
+    ///
+    /// Returns an optional reference to the value at the index without stamping it.
+    ///
+    #[inline]
+    pub fn get(&self, index: ArenaIdx<T>) -> Option<&T>{
+        self.arena.get(Self::cast(index)).map(|stamped| &stamped.val)
+    }
+
+    ///
+    /// Returns a mutable optional reference to the value at the index and stamps it with the current tick.
+    ///
+    pub fn get_mut(&mut self, index: ArenaIdx<T>) -> Option<&mut T>{
+        let now = self.now;
+        self.arena.get_mut(Self::cast(index)).map(|stamped|{
+            stamped.touched = now;
+            &mut stamped.val
+        })
+    }
+
+    ///
+    /// Removes every element last touched before the cutoff and passes it to f.
+    ///
+    pub fn sweep_older_than(&mut self, cutoff: u64, mut f: impl FnMut(ArenaIdx<T>, T)){
+        let expired: Vec<_> = self.arena.iter()
+            .filter(|(_, stamped)| stamped.touched < cutoff)
+            .map(|(index, _)| index)
+            .collect();
+        for index in expired{
+            if let Some(stamped) = self.arena.remove(index){
+                f(Self::uncast(index), stamped.val);
+            }
+        }
+    }
+
+    ///
+    /// Returns an iterator over the elements with their keys, without stamping them.
+    ///
+    pub fn iter(&self) -> impl Iterator<Item = (ArenaIdx<T>, &T)>{
+        self.arena.iter().map(|(index, stamped)| (Self::uncast(index), &stamped.val))
+    }
+
+    ///
+    /// Returns a mutable iterator over the elements with their keys.
+    /// Every element yielded is stamped with the current tick.
+    ///
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (ArenaIdx<T>, &mut T)>{
+        let now = self.now;
+        self.arena.iter_mut().map(move |(index, stamped)|{
+            stamped.touched = now;
+            (Self::uncast(index), &mut stamped.val)
+        })
+    }
+
+    #[inline]
+    pub fn contains(&self, index: ArenaIdx<T>) -> bool{
+        self.get(index).is_some()
+    }
+
+    #[inline]
+    pub fn clear(&mut self){
+        self.arena.clear()
+    }
+
+    #[inline]
+    pub fn num(&self) -> usize{
+        self.arena.num()
+    }
+}
+
+impl<T> Default for TimedArena<T>{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Index<ArenaIdx<T>> for TimedArena<T>{
+    type Output = T;
+
+    fn index(&self, index: ArenaIdx<T>) -> &Self::Output {
+        self.get(index).expect("There is no element at this index with that generation.")
+    }
+}
+
+impl<T> IndexMut<ArenaIdx<T>> for TimedArena<T>{
+    fn index_mut(&mut self, index: ArenaIdx<T>) -> &mut Self::Output {
+        self.get_mut(index).expect("There is no element at this index with that generation.")
+    }
+}
+
+#[cfg(test)]
+mod test{
+    use super::*;
+
+    #[test]
+    fn test_sweep(){
+        let mut arena = TimedArena::new();
+        // Element i is inserted at tick i.
+        let keys: Vec<_> = (0..10u64).map(|i|{
+            arena.set_now(i);
+            arena.insert(i)
+        }).collect();
+
+        arena.set_now(20);
+        // Reads do not count as touches, writes and touches do.
+        assert_eq!(arena.get(keys[0]), Some(&0));
+        *arena.get_mut(keys[1]).unwrap() += 100;
+        arena[keys[2]] += 100;
+        assert!(arena.touch(keys[3]));
+        assert_eq!(arena.last_touched(keys[3]), Some(20));
+        assert_eq!(arena.last_touched(keys[4]), Some(4));
+
+        let mut swept = Vec::new();
+        arena.sweep_older_than(7, |index, val| swept.push((index, val)));
+        swept.sort_by_key(|(_, val)| *val);
+        assert_eq!(swept, [(keys[0], 0), (keys[4], 4), (keys[5], 5), (keys[6], 6)]);
+
+        // Exactly the elements touched at or after the cutoff are left.
+        let mut left: Vec<_> = arena.iter().map(|(_, val)| *val).collect();
+        left.sort();
+        assert_eq!(left, [3, 7, 8, 9, 101, 102]);
+        assert!(!arena.touch(keys[0]));
+        assert_eq!(arena.get(keys[4]), None);
+
+        // Mutable iteration stamps every element, so nothing is older than the current tick.
+        arena.set_now(30);
+        for (_, val) in arena.iter_mut(){
+            *val %= 100;
+        }
+        let mut count = 0;
+        arena.sweep_older_than(30, |_, _| count += 1);
+        assert_eq!(count, 0);
+
+        arena.set_now(40);
+        arena.touch(keys[8]);
+        arena.sweep_older_than(40, |_, _| count += 1);
+        assert_eq!(count, 5);
+        assert_eq!(arena.iter().map(|(index, _)| index).collect::<Vec<_>>(), [keys[8]]);
+    }
+}
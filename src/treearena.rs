@@ -0,0 +1,594 @@
+use std::ops::{Index, IndexMut};
+
+use crate::*;
+
+///
+/// A node of a TreeArena.
+///
+#[derive(Debug, Clone)]
+pub struct TreeNode<T>{
+    val: T,
+    parent: Option<TreeIdx<T>>,
+    first_child: Option<TreeIdx<T>>,
+    last_child: Option<TreeIdx<T>>,
+    prev: Option<TreeIdx<T>>,
+    next: Option<TreeIdx<T>>,
+}
+
+///
+/// A handle to a node of a TreeArena.
+///
+pub type TreeIdx<T> = ArenaIdx<TreeNode<T>>;
+
+///
+/// A forest of ordered trees whose nodes live in an Arena.
+///
+/// The parent, child and sibling links are kept next to the values and are only changed
+/// by the methods of the TreeArena, so they always describe a consistent set of trees.
+/// Nodes without a parent are roots.
+///
+/// Removing a single node with remove promotes its children into its place among its siblings.
+/// To remove a node together with all of its descendants use remove_subtree.
+///
+/// # Example
+///
+///```rust
+/// use gen_arena::*;
+///
+/// let mut tree = TreeArena::new();
+///
+/// let window = tree.insert_root("window");
+/// let panel = tree.insert_child(window, "panel").unwrap();
+/// let button = tree.insert_child(panel, "button").unwrap();
+/// let label = tree.insert_child(window, "label").unwrap();
+///
+/// let children: Vec<_> = tree.children(window).map(|i| tree[i]).collect();
+/// assert_eq!(children, ["panel", "label"]);
+///
+/// let descendants: Vec<_> = tree.descendants(window).map(|i| tree[i]).collect();
+/// assert_eq!(descendants, ["panel", "button", "label"]);
+///
+/// assert!(tree.reparent(button, label));
+/// assert_eq!(tree.ancestors(button).collect::<Vec<_>>(), [label, window]);
+///
+/// assert_eq!(tree.remove_subtree(window), 4);
+/// assert!(tree.is_empty());
+///```
+///
+#[derive(Debug, Clone)]
+pub struct TreeArena<T>{
+    nodes: Arena<TreeNode<T>>,
+}
+
+impl<T> TreeArena<T>{
+    ///
+    /// Creates an empty TreeArena.
+    ///
+    pub fn new() -> Self{
+        Self{
+            nodes: Arena::new(),
+        }
+    }
+
+    #[inline]
+    fn node(&self, index: TreeIdx<T>) -> &TreeNode<T>{
+        self.nodes.get(index).expect("Linked nodes are always valid.")
+    }
+
+    #[inline]
+    fn node_mut(&mut self, index: TreeIdx<T>) -> &mut TreeNode<T>{
+        self.nodes.get_mut(index).expect("Linked nodes are always valid.")
+    }
+
+    ///
+    /// Removes the node from the children of its parent, making it a root.
+    ///
+    fn unlink(&mut self, index: TreeIdx<T>){
+        let TreeNode{parent, prev, next, ..} = *self.node(index);
+        if let Some(parent) = parent{
+            match prev{
+                Some(prev) => self.node_mut(prev).next = next,
+                None => self.node_mut(parent).first_child = next,
+            }
+            match next{
+                Some(next) => self.node_mut(next).prev = prev,
+                None => self.node_mut(parent).last_child = prev,
+            }
+        }
+        let node = self.node_mut(index);
+        node.parent = None;
+        node.prev = None;
+        node.next = None;
+    }
+
+    ///
+    /// Appends the root index to the children of parent.
+    ///
+    fn link_last(&mut self, parent: TreeIdx<T>, index: TreeIdx<T>){
+        let prev = self.node(parent).last_child;
+        match prev{
+            Some(prev) => self.node_mut(prev).next = Some(index),
+            None => self.node_mut(parent).first_child = Some(index),
+        }
+        self.node_mut(parent).last_child = Some(index);
+        let node = self.node_mut(index);
+        node.parent = Some(parent);
+        node.prev = prev;
+    }
+
+    ///
+    /// Inserts a new node without a parent.
+    ///
+    #[must_use]
+    pub fn insert_root(&mut self, val: T) -> TreeIdx<T>{
+        self.nodes.insert(TreeNode{
+            val,
+            parent: None,
+            first_child: None,
+            last_child: None,
+            prev: None,
+            next: None,
+        })
+    }
+
+    ///
+    /// Inserts a new node as the last child of parent.
+    /// Returns val as Err if parent is not valid.
+    ///
+    pub fn insert_child(&mut self, parent: TreeIdx<T>, val: T) -> Result<TreeIdx<T>, T>{
+        if !self.contains(parent){
+            return Err(val);
+        }
+        let index = self.insert_root(val);
+        self.link_last(parent, index);
+        Ok(index)
+    }
+
+    ///
+    /// Detaches the node from its parent, making it the root of its own tree.
+    /// Returns false if the index is not valid.
+    ///
+    pub fn detach(&mut self, index: TreeIdx<T>) -> bool{
+        if !self.contains(index){
+            return false;
+        }
+        self.unlink(index);
+        true
+    }
+
+    ///
+    /// Moves the node and its descendants to the end of the children of new_parent.
+    /// Returns false, leaving the tree unchanged, if either index is not valid
+    /// or new_parent is the node itself or one of its descendants.
+    ///
+    pub fn reparent(&mut self, index: TreeIdx<T>, new_parent: TreeIdx<T>) -> bool{
+        if !self.contains(index) || !self.contains(new_parent){
+            return false;
+        }
+        if new_parent == index || self.ancestors(new_parent).any(|ancestor| ancestor == index){
+            return false;
+        }
+        self.unlink(index);
+        self.link_last(new_parent, index);
+        true
+    }
+
+    ///
+    /// Removes the node and returns its value if the index is still valid.
+    /// The children of the node take its place among its siblings,
+    /// or become roots if the node was a root.
+    ///
+    pub fn remove(&mut self, index: TreeIdx<T>) -> Option<T>{
+        let TreeNode{parent, first_child, last_child, prev, next, ..} = *self.nodes.get(index)?;
+
+        let mut child = first_child;
+        while let Some(c) = child{
+            let node = self.node_mut(c);
+            child = node.next;
+            node.parent = parent;
+            if parent.is_none(){
+                node.prev = None;
+                node.next = None;
+            }
+        }
+
+        if let Some(parent) = parent{
+            // Splice the children between the siblings of the node.
+            let (first, last) = match (first_child, last_child){
+                (Some(first), Some(last)) => {
+                    self.node_mut(first).prev = prev;
+                    self.node_mut(last).next = next;
+                    (Some(first), Some(last))
+                }
+                _ => (next, prev),
+            };
+            match prev{
+                Some(prev) => self.node_mut(prev).next = first,
+                None => self.node_mut(parent).first_child = first,
+            }
+            match next{
+                Some(next) => self.node_mut(next).prev = last,
+                None => self.node_mut(parent).last_child = last,
+            }
+        }
+
+        self.nodes.remove(index).map(|node| node.val)
+    }
+
+    ///
+    /// Removes the node and all of its descendants.
+    /// Returns the number of removed nodes, which is 0 if the index is not valid.
+    ///
+    pub fn remove_subtree(&mut self, index: TreeIdx<T>) -> usize{
+        if !self.contains(index){
+            return 0;
+        }
+        self.unlink(index);
+        let descendants: Vec<_> = self.descendants(index).collect();
+        for descendant in descendants.iter(){
+            self.nodes.remove(*descendant);
+        }
+        self.nodes.remove(index);
+        descendants.len() + 1
+    }
+
+    ///
+    /// Returns true if the index refers to a node.
+    ///
+    #[inline]
+    pub fn contains(&self, index: TreeIdx<T>) -> bool{
+        self.nodes.get(index).is_some()
+    }
+
+    ///
+    /// Returns an optional reference to the value of the node.
+    ///
+    #[inline]
+    pub fn get(&self, index: TreeIdx<T>) -> Option<&T>{
+        self.nodes.get(index).map(|node| &node.val)
+    }
+
+    ///
+    /// Returns a mutable optional reference to the value of the node.
+    ///
+    #[inline]
+    pub fn get_mut(&mut self, index: TreeIdx<T>) -> Option<&mut T>{
+        self.nodes.get_mut(index).map(|node| &mut node.val)
+    }
+
+    ///
+    /// Returns the parent of the node.
+    ///
+    #[inline]
+    pub fn parent(&self, index: TreeIdx<T>) -> Option<TreeIdx<T>>{
+        self.nodes.get(index)?.parent
+    }
+
+    ///
+    /// Returns the first child of the node.
+    ///
+    #[inline]
+    pub fn first_child(&self, index: TreeIdx<T>) -> Option<TreeIdx<T>>{
+        self.nodes.get(index)?.first_child
+    }
+
+    ///
+    /// Returns the last child of the node.
+    ///
+    #[inline]
+    pub fn last_child(&self, index: TreeIdx<T>) -> Option<TreeIdx<T>>{
+        self.nodes.get(index)?.last_child
+    }
+
+    ///
+    /// Returns the next sibling of the node.
+    ///
+    #[inline]
+    pub fn next_sibling(&self, index: TreeIdx<T>) -> Option<TreeIdx<T>>{
+        self.nodes.get(index)?.next
+    }
+
+    ///
+    /// Returns the previous sibling of the node.
+    ///
+    #[inline]
+    pub fn prev_sibling(&self, index: TreeIdx<T>) -> Option<TreeIdx<T>>{
+        self.nodes.get(index)?.prev
+    }
+
+    ///
+    /// Returns an iterator over the children of the node in order.
+    /// It is empty if the index is not valid.
+    ///
+    pub fn children(&self, index: TreeIdx<T>) -> TreeChildren<'_, T>{
+        TreeChildren{
+            tree: self,
+            next: self.first_child(index),
+        }
+    }
+
+    ///
+    /// Returns an iterator over the parent, grandparent and so on up to the root of the node.
+    ///
+    pub fn ancestors(&self, index: TreeIdx<T>) -> TreeAncestors<'_, T>{
+        TreeAncestors{
+            tree: self,
+            next: self.parent(index),
+        }
+    }
+
+    ///
+    /// Returns an iterator over all descendants of the node in depth first pre-order,
+    /// not including the node itself.
+    ///
+    pub fn descendants(&self, index: TreeIdx<T>) -> TreeDescendants<'_, T>{
+        TreeDescendants{
+            tree: self,
+            root: index,
+            next: self.first_child(index),
+        }
+    }
+
+    ///
+    /// Returns an iterator over all nodes without a parent.
+    ///
+    pub fn roots(&self) -> impl Iterator<Item = TreeIdx<T>> + '_{
+        self.nodes.iter().filter(|(_, node)| node.parent.is_none()).map(|(index, _)| index)
+    }
+
+    ///
+    /// Returns an iterator over all nodes with their values in arena order.
+    ///
+    pub fn iter(&self) -> impl Iterator<Item = (TreeIdx<T>, &T)>{
+        self.nodes.iter().map(|(index, node)| (index, &node.val))
+    }
+
+    #[inline]
+    pub fn clear(&mut self){
+        self.nodes.clear();
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize{
+        self.nodes.num()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool{
+        self.len() == 0
+    }
+}
+
+impl<T> Default for TreeArena<T>{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Index<TreeIdx<T>> for TreeArena<T>{
+    type Output = T;
+
+    fn index(&self, index: TreeIdx<T>) -> &Self::Output {
+        self.get(index).expect("There is no element at this index with that generation.")
+    }
+}
+
+impl<T> IndexMut<TreeIdx<T>> for TreeArena<T>{
+    fn index_mut(&mut self, index: TreeIdx<T>) -> &mut Self::Output {
+        self.get_mut(index).expect("There is no element at this index with that generation.")
+    }
+}
+
+pub struct TreeChildren<'i, T: 'i>{
+    tree: &'i TreeArena<T>,
+    next: Option<TreeIdx<T>>,
+}
+
+impl<'i, T> Iterator for TreeChildren<'i, T>{
+    type Item = TreeIdx<T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let index = self.next?;
+        self.next = self.tree.node(index).next;
+        Some(index)
+    }
+}
+
+pub struct TreeAncestors<'i, T: 'i>{
+    tree: &'i TreeArena<T>,
+    next: Option<TreeIdx<T>>,
+}
+
+impl<'i, T> Iterator for TreeAncestors<'i, T>{
+    type Item = TreeIdx<T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let index = self.next?;
+        self.next = self.tree.node(index).parent;
+        Some(index)
+    }
+}
+
+pub struct TreeDescendants<'i, T: 'i>{
+    tree: &'i TreeArena<T>,
+    root: TreeIdx<T>,
+    next: Option<TreeIdx<T>>,
+}
+
+impl<'i, T> Iterator for TreeDescendants<'i, T>{
+    type Item = TreeIdx<T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let index = self.next?;
+        let node = self.tree.node(index);
+        self.next = match node.first_child{
+            Some(child) => Some(child),
+            None => {
+                // Climb until a node has a next sibling, without leaving the subtree of root.
+                let mut current = index;
+                loop{
+                    if current == self.root{
+                        break None;
+                    }
+                    let node = self.tree.node(current);
+                    if let Some(next) = node.next{
+                        break Some(next);
+                    }
+                    match node.parent{
+                        Some(parent) => current = parent,
+                        None => break None,
+                    }
+                }
+            }
+        };
+        Some(index)
+    }
+}
+
+#[cfg(test)]
+mod test{
+    use super::*;
+    use proptest::prelude::*;
+
+    ///
+    /// Asserts that all links of the tree are consistent.
+    ///
+    fn assert_invariants<T: std::fmt::Debug>(tree: &TreeArena<T>){
+        let mut reachable = 0;
+        for root in tree.roots(){
+            let node = tree.node(root);
+            assert!(node.prev.is_none() && node.next.is_none());
+            reachable += 1 + tree.descendants(root).count();
+        }
+        // Every node is in exactly one tree, so there are no cycles.
+        assert_eq!(reachable, tree.len());
+
+        for (index, node) in tree.nodes.iter(){
+            let children: Vec<_> = tree.children(index).collect();
+            assert_eq!(node.first_child, children.first().copied());
+            assert_eq!(node.last_child, children.last().copied());
+            for (i, child) in children.iter().enumerate(){
+                let child = tree.node(*child);
+                assert_eq!(child.parent, Some(index));
+                assert_eq!(child.prev, i.checked_sub(1).map(|i| children[i]));
+                assert_eq!(child.next, children.get(i + 1).copied());
+            }
+            if let Some(parent) = node.parent{
+                assert_eq!(tree.children(parent).filter(|c| *c == index).count(), 1);
+                assert!(tree.ancestors(index).count() < tree.len());
+            }
+        }
+    }
+
+    #[derive(Debug, Clone)]
+    enum Op{
+        InsertRoot,
+        InsertChild(usize),
+        Detach(usize),
+        Reparent(usize, usize),
+        Remove(usize),
+        RemoveSubtree(usize),
+    }
+
+    fn op() -> impl Strategy<Value = Op>{
+        prop_oneof![
+            1 => Just(Op::InsertRoot),
+            4 => any::<usize>().prop_map(Op::InsertChild),
+            1 => any::<usize>().prop_map(Op::Detach),
+            3 => (any::<usize>(), any::<usize>()).prop_map(|(a, b)| Op::Reparent(a, b)),
+            1 => any::<usize>().prop_map(Op::Remove),
+            1 => any::<usize>().prop_map(Op::RemoveSubtree),
+        ]
+    }
+
+    proptest!{
+        #[test]
+        fn test_random_edits_keep_invariants(ops in proptest::collection::vec(op(), 1..200)){
+            let mut tree = TreeArena::new();
+            // Keys ever handed out, including removed ones.
+            let mut keys = vec![tree.insert_root(0)];
+
+            for (i, op) in ops.into_iter().enumerate(){
+                let key = |k: usize| keys[k % keys.len()];
+                match op{
+                    Op::InsertRoot => keys.push(tree.insert_root(i)),
+                    Op::InsertChild(p) => {
+                        let parent = key(p);
+                        match tree.insert_child(parent, i){
+                            Ok(child) => {
+                                prop_assert_eq!(tree.parent(child), Some(parent));
+                                keys.push(child);
+                            }
+                            Err(val) => {
+                                prop_assert!(!tree.contains(parent));
+                                prop_assert_eq!(val, i);
+                            }
+                        }
+                    }
+                    Op::Detach(k) => {
+                        let index = key(k);
+                        prop_assert_eq!(tree.detach(index), tree.contains(index));
+                        prop_assert_eq!(tree.parent(index), None);
+                    }
+                    Op::Reparent(a, b) => {
+                        let (index, parent) = (key(a), key(b));
+                        let cyclic = index == parent || tree.ancestors(parent).any(|x| x == index);
+                        let valid = tree.contains(index) && tree.contains(parent) && !cyclic;
+                        prop_assert_eq!(tree.reparent(index, parent), valid);
+                        if valid{
+                            prop_assert_eq!(tree.last_child(parent), Some(index));
+                        }
+                    }
+                    Op::Remove(k) => {
+                        let index = key(k);
+                        let parent = tree.parent(index);
+                        let children: Vec<_> = tree.children(index).collect();
+                        let len = tree.len();
+                        if tree.remove(index).is_some(){
+                            prop_assert_eq!(tree.len(), len - 1);
+                            for child in children{
+                                prop_assert_eq!(tree.parent(child), parent);
+                            }
+                        }
+                    }
+                    Op::RemoveSubtree(k) => {
+                        let index = key(k);
+                        let expected = if tree.contains(index){
+                            tree.descendants(index).count() + 1
+                        }
+                        else{
+                            0
+                        };
+                        let len = tree.len();
+                        prop_assert_eq!(tree.remove_subtree(index), expected);
+                        prop_assert_eq!(tree.len(), len - expected);
+                    }
+                }
+                assert_invariants(&tree);
+            }
+        }
+    }
+
+    #[test]
+    fn test_remove_promotes_children(){
+        let mut tree = TreeArena::new();
+        let root = tree.insert_root('r');
+        let a = tree.insert_child(root, 'a').unwrap();
+        let b = tree.insert_child(root, 'b').unwrap();
+        let c = tree.insert_child(root, 'c').unwrap();
+        let b0 = tree.insert_child(b, '0').unwrap();
+        let b1 = tree.insert_child(b, '1').unwrap();
+
+        assert_eq!(tree.remove(b), Some('b'));
+        let children: String = tree.children(root).map(|i| tree[i]).collect();
+        assert_eq!(children, "a01c");
+        assert_eq!(tree.parent(b0), Some(root));
+        assert_eq!(tree.next_sibling(a), Some(b0));
+        assert_eq!(tree.prev_sibling(c), Some(b1));
+
+        // Children of a removed root become roots.
+        assert_eq!(tree.remove(root), Some('r'));
+        assert_eq!(tree.roots().count(), 4);
+        assert_invariants(&tree);
+    }
+}
@@ -0,0 +1,176 @@
+use std::{cmp::Ordering, hash::{Hash, Hasher}};
+
+use crate::*;
+
+///
+/// A best effort key to an element of an Arena.
+///
+/// A WeakIdx carries the same information as an ArenaIdx but has to be upgraded before it can be used.
+/// Upgrading validates that the slot is still occupied by the same generation,
+/// which makes it visible in the types which keys are known to be live and which might be stale.
+///
+/// # Example
+///
+///```rust
+/// use gen_arena::*;
+///
+/// let mut arena = Arena::new();
+///
+/// let i0 = arena.insert(0);
+/// let weak = WeakIdx::new(i0);
+///
+/// assert_eq!(weak.upgrade(&arena), Some(i0));
+///
+/// arena.remove(i0);
+/// let i1 = arena.insert(1);
+///
+/// assert_eq!(i1.index(), i0.index());
+/// assert_eq!(weak.upgrade(&arena), None);
+///```
+///
+pub struct WeakIdx<T>{
+    index: ArenaIdx<T>,
+}
+
+impl<T> WeakIdx<T>{
+    #[inline]
+    pub fn new(index: ArenaIdx<T>) -> Self{
+        Self{
+            index,
+        }
+    }
+
+    ///
+    /// Returns the key if the slot is still occupied by the same generation.
+    ///
+    #[inline]
+    pub fn upgrade(&self, arena: &Arena<T>) -> Option<ArenaIdx<T>>{
+        arena.get(self.index).map(|_| self.index)
+    }
+
+    #[inline]
+    pub fn index(&self) -> usize{
+        self.index.index()
+    }
+
+    #[inline]
+    pub fn gen(&self) -> usize{
+        self.index.gen()
+    }
+}
+
+impl<T> From<ArenaIdx<T>> for WeakIdx<T>{
+    #[inline]
+    fn from(index: ArenaIdx<T>) -> Self {
+        Self::new(index)
+    }
+}
+
+// Implemented by hand so that T does not need to implement the traits.
+impl<T> Clone for WeakIdx<T>{
+    #[inline]
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for WeakIdx<T>{}
+
+impl<T> std::fmt::Debug for WeakIdx<T>{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("WeakIdx")
+            .field("index", &self.index())
+            .field("generation", &self.gen())
+            .finish()
+    }
+}
+
+impl<T> PartialEq for WeakIdx<T>{
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        self.index() == other.index() && self.gen() == other.gen()
+    }
+}
+
+impl<T> Eq for WeakIdx<T>{}
+
+impl<T> PartialOrd for WeakIdx<T>{
+    #[inline]
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+///
+/// Orders by index first and generation second.
+///
+impl<T> Ord for WeakIdx<T>{
+    #[inline]
+    fn cmp(&self, other: &Self) -> Ordering {
+        (self.index(), self.gen()).cmp(&(other.index(), other.gen()))
+    }
+}
+
+impl<T> Hash for WeakIdx<T>{
+    #[inline]
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.index().hash(state);
+        self.gen().hash(state);
+    }
+}
+
+///
+/// Serialized as a tuple of index and generation.
+///
+#[cfg(feature = "serde")]
+impl<T> serde::Serialize for WeakIdx<T>{
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        (self.index(), self.gen()).serialize(serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de, T> serde::Deserialize<'de> for WeakIdx<T>{
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let (index, generation) = <(usize, usize)>::deserialize(deserializer)?;
+        Ok(Self::new(ArenaIdx::from_raw_parts(index, generation)))
+    }
+}
+
+#[cfg(test)]
+mod test{
+    use super::*;
+    use std::collections::{BTreeSet, HashMap};
+
+    struct NotHashable;
+
+    #[test]
+    fn test_maps(){
+        let mut arena = Arena::new();
+        let keys: Vec<_> = (0..4).map(|_| arena.insert(NotHashable)).collect();
+
+        let mut names = HashMap::new();
+        for (i, key) in keys.iter().enumerate(){
+            names.insert(WeakIdx::from(*key), i);
+        }
+        assert_eq!(names[&WeakIdx::new(keys[2])], 2);
+
+        arena.remove(keys[1]);
+        let reused = arena.insert(NotHashable);
+        let live: BTreeSet<_> = names.keys().filter_map(|weak| weak.upgrade(&arena)).map(|key| key.index()).collect();
+        assert_eq!(live, BTreeSet::from([0, 2, 3]));
+        assert!(!names.contains_key(&WeakIdx::new(reused)));
+
+        assert!(WeakIdx::new(keys[0]) < WeakIdx::new(keys[1]));
+        assert!(WeakIdx::new(keys[1]) < WeakIdx::new(reused));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde(){
+        let weak = WeakIdx::<NotHashable>::new(ArenaIdx::from_raw_parts(3, 7));
+        let json = serde_json::to_string(&weak).unwrap();
+        assert_eq!(json, "[3,7]");
+        assert_eq!(serde_json::from_str::<WeakIdx<NotHashable>>(&json).unwrap(), weak);
+    }
+}
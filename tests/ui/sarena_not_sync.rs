@@ -0,0 +1,10 @@
+use gen_arena::*;
+
+// Inserting takes &self, so two threads sharing an SArena could race on the free list.
+fn main(){
+    let arena = SArena::<u32, 4>::new();
+    std::thread::scope(|s|{
+        s.spawn(|| arena.insert(1));
+        s.spawn(|| arena.insert(2));
+    });
+}
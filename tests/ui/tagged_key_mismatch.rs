@@ -0,0 +1,15 @@
+use gen_arena::*;
+
+arena_tag!{
+    struct GpuTag;
+    struct CpuTag;
+}
+
+// Keys of an arena tagged for the GPU cannot be used with one tagged for the CPU.
+fn main(){
+    let mut gpu: Arena<&str, usize, GpuTag> = Arena::default();
+    let mut cpu: Arena<&str, usize, CpuTag> = Arena::default();
+    let mesh = gpu.insert("cube");
+    let _ = cpu.get(mesh);
+    cpu.remove(mesh);
+}
@@ -0,0 +1,14 @@
+use gen_arena::*;
+
+arena_tag!{
+    struct GpuTag;
+}
+
+// A map filled with keys of a tagged arena does not accept keys of an untagged one.
+fn main(){
+    let mut gpu: Arena<u32, usize, GpuTag> = Arena::default();
+    let mut plain = Arena::new();
+    let mut names = SparseSecondaryMap::new();
+    names.insert(gpu.insert(0), "gpu");
+    names.insert(plain.insert(1), "plain");
+}